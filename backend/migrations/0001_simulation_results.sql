@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS simulation_results (
+    id TEXT PRIMARY KEY NOT NULL,
+    simulation_id TEXT NOT NULL,
+    parameters TEXT NOT NULL,
+    data TEXT NOT NULL,
+    computed_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_simulation_results_simulation_id
+    ON simulation_results (simulation_id);
@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS simulation_progress (
+    user_id TEXT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    simulation_id TEXT NOT NULL,
+    completed INTEGER NOT NULL DEFAULT 0,
+    score REAL,
+    time_spent_minutes INTEGER NOT NULL DEFAULT 0,
+    last_parameters TEXT,
+    started_at TEXT NOT NULL,
+    completed_at TEXT,
+    updated_at TEXT NOT NULL,
+    PRIMARY KEY (user_id, simulation_id)
+);
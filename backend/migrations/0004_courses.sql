@@ -0,0 +1,31 @@
+CREATE TABLE IF NOT EXISTS courses (
+    id TEXT PRIMARY KEY NOT NULL,
+    owner_id TEXT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    title TEXT NOT NULL,
+    description TEXT NOT NULL,
+    published INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_courses_owner_id ON courses (owner_id);
+
+CREATE TABLE IF NOT EXISTS course_modules (
+    id TEXT PRIMARY KEY NOT NULL,
+    course_id TEXT NOT NULL REFERENCES courses (id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    title TEXT NOT NULL,
+    description TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_course_modules_course_id ON course_modules (course_id, position);
+
+CREATE TABLE IF NOT EXISTS lessons (
+    id TEXT PRIMARY KEY NOT NULL,
+    module_id TEXT NOT NULL REFERENCES course_modules (id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    title TEXT NOT NULL,
+    items TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_lessons_module_id ON lessons (module_id, position);
@@ -0,0 +1,29 @@
+CREATE TABLE IF NOT EXISTS assignments (
+    id TEXT PRIMARY KEY NOT NULL,
+    class_id TEXT NOT NULL REFERENCES classes (id) ON DELETE CASCADE,
+    simulation_id TEXT NOT NULL,
+    title TEXT NOT NULL,
+    instructions TEXT NOT NULL,
+    explorations TEXT NOT NULL,
+    questions TEXT NOT NULL,
+    due_at TEXT,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_assignments_class_id ON assignments (class_id);
+
+CREATE TABLE IF NOT EXISTS assignment_submissions (
+    id TEXT PRIMARY KEY NOT NULL,
+    assignment_id TEXT NOT NULL REFERENCES assignments (id) ON DELETE CASCADE,
+    student_id TEXT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    result_ids TEXT NOT NULL,
+    answers TEXT NOT NULL,
+    explorations_completed TEXT NOT NULL,
+    submitted_at TEXT NOT NULL,
+    late INTEGER NOT NULL,
+    grade REAL,
+    feedback TEXT,
+    graded_at TEXT,
+    UNIQUE (assignment_id, student_id)
+);
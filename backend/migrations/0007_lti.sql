@@ -0,0 +1,24 @@
+CREATE TABLE IF NOT EXISTS lti_identities (
+    issuer TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    user_id TEXT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    created_at TEXT NOT NULL,
+    PRIMARY KEY (issuer, subject)
+);
+
+CREATE TABLE IF NOT EXISTS lti_contexts (
+    issuer TEXT NOT NULL,
+    context_id TEXT NOT NULL,
+    class_id TEXT NOT NULL REFERENCES classes (id) ON DELETE CASCADE,
+    PRIMARY KEY (issuer, context_id)
+);
+
+CREATE TABLE IF NOT EXISTS lti_grade_links (
+    user_id TEXT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    simulation_id TEXT NOT NULL,
+    issuer TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    lineitem TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    PRIMARY KEY (user_id, simulation_id, lineitem)
+);
@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS simulation_translations (
+    simulation_id TEXT NOT NULL,
+    locale TEXT NOT NULL,
+    name TEXT,
+    description TEXT,
+    theory TEXT,
+    parameter_labels TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    PRIMARY KEY (simulation_id, locale)
+);
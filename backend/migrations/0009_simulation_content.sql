@@ -0,0 +1,17 @@
+-- Simulation content edited through the admin API. A draft can be previewed
+-- until it is published; published content is served instead of the
+-- content file.
+CREATE TABLE IF NOT EXISTS simulation_content (
+    simulation_id TEXT NOT NULL,
+    state TEXT NOT NULL CHECK (state IN ('draft', 'published')),
+    content TEXT NOT NULL,
+    updated_by TEXT NOT NULL REFERENCES users (id),
+    updated_at TEXT NOT NULL,
+    PRIMARY KEY (simulation_id, state)
+);
+
+CREATE TABLE IF NOT EXISTS retired_simulations (
+    simulation_id TEXT PRIMARY KEY NOT NULL,
+    retired_by TEXT NOT NULL REFERENCES users (id),
+    retired_at TEXT NOT NULL
+);
@@ -0,0 +1,3 @@
+-- Version of the computation that produced each result, so it can be
+-- replayed with the same code path. Earlier results came from version 1.
+ALTER TABLE simulation_results ADD COLUMN simulation_version INTEGER NOT NULL DEFAULT 1;
@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS result_pins (
+    result_id TEXT PRIMARY KEY NOT NULL REFERENCES simulation_results (id) ON DELETE CASCADE,
+    cid TEXT NOT NULL,
+    sha256 TEXT NOT NULL,
+    size INTEGER NOT NULL,
+    pinned_by TEXT NOT NULL,
+    pinned_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS result_pins_cid ON result_pins (cid);
@@ -0,0 +1,8 @@
+ALTER TABLE simulation_results ADD COLUMN attestation TEXT;
+
+CREATE TABLE IF NOT EXISTS attestation_keys (
+    key_id TEXT PRIMARY KEY NOT NULL,
+    algorithm TEXT NOT NULL,
+    public_key TEXT NOT NULL,
+    first_used_at TEXT NOT NULL
+);
@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS pending_jobs (
+    id TEXT PRIMARY KEY NOT NULL,
+    simulation_id TEXT NOT NULL,
+    parameters TEXT NOT NULL,
+    created_at TEXT NOT NULL
+);
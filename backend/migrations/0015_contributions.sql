@@ -0,0 +1,36 @@
+-- Scripted simulations submitted for the community library. A contribution
+-- waits in `pending` until a reviewer approves or rejects it; approved
+-- contributions are published into the library by an administrator.
+CREATE TABLE IF NOT EXISTS contributions (
+    id TEXT PRIMARY KEY NOT NULL,
+    author_id TEXT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    status TEXT NOT NULL CHECK (status IN ('pending', 'approved', 'rejected', 'published')),
+    name TEXT NOT NULL,
+    summary TEXT NOT NULL,
+    description TEXT NOT NULL,
+    difficulty TEXT NOT NULL,
+    topics TEXT NOT NULL,
+    theory TEXT NOT NULL,
+    parameters TEXT NOT NULL,
+    script TEXT NOT NULL,
+    domain TEXT NOT NULL,
+    output TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    published_at TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_contributions_author_id ON contributions (author_id);
+CREATE INDEX IF NOT EXISTS idx_contributions_status ON contributions (status);
+
+-- Every review decision, kept so the author sees why a revision was needed
+CREATE TABLE IF NOT EXISTS contribution_reviews (
+    id TEXT PRIMARY KEY NOT NULL,
+    contribution_id TEXT NOT NULL REFERENCES contributions (id) ON DELETE CASCADE,
+    reviewer_id TEXT NOT NULL REFERENCES users (id),
+    decision TEXT NOT NULL CHECK (decision IN ('approved', 'rejected')),
+    comment TEXT NOT NULL,
+    created_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_contribution_reviews_contribution_id ON contribution_reviews (contribution_id);
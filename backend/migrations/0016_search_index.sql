@@ -0,0 +1,12 @@
+-- Full-text index of the catalog, rebuilt from the simulation content
+-- whenever it changes. `kind` is `simulation` or `glossary`; glossary rows
+-- are the key concepts a theory section defines.
+CREATE VIRTUAL TABLE IF NOT EXISTS search_index USING fts5(
+    kind UNINDEXED,
+    simulation_id UNINDEXED,
+    title,
+    topics,
+    description,
+    body,
+    tokenize = 'porter unicode61 remove_diacritics 2'
+);
@@ -0,0 +1,11 @@
+-- Embeddings of catalog text returned by the configured embeddings API,
+-- keyed by model and the SHA-256 of the text, so a restart or a catalog
+-- change only sends the API text it has not embedded before. `vector`
+-- holds little-endian f32 values.
+CREATE TABLE IF NOT EXISTS embeddings (
+    model TEXT NOT NULL,
+    text_sha256 TEXT NOT NULL,
+    vector BLOB NOT NULL,
+    created_at TEXT NOT NULL,
+    PRIMARY KEY (model, text_sha256)
+);
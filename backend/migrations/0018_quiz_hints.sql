@@ -0,0 +1,11 @@
+-- Hints a signed-in learner has revealed on a quiz question; each one
+-- lowers the credit for a correct answer to it
+CREATE TABLE IF NOT EXISTS quiz_hints (
+    user_id TEXT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    simulation_id TEXT NOT NULL,
+    question_id TEXT NOT NULL,
+    revealed INTEGER NOT NULL,
+    first_revealed_at TEXT NOT NULL,
+    last_revealed_at TEXT NOT NULL,
+    PRIMARY KEY (user_id, simulation_id, question_id)
+);
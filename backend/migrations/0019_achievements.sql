@@ -0,0 +1,28 @@
+-- Experience points a learner earned on a day: one row per kind of
+-- activity and simulation, keeping the best award of the day
+CREATE TABLE IF NOT EXISTS activity_days (
+    user_id TEXT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    day TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    simulation_id TEXT NOT NULL,
+    xp INTEGER NOT NULL,
+    PRIMARY KEY (user_id, day, kind, simulation_id)
+);
+
+-- Simulations on which a learner has met a badge's condition, counted
+-- towards badges that need several
+CREATE TABLE IF NOT EXISTS badge_progress (
+    user_id TEXT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    badge_id TEXT NOT NULL,
+    simulation_id TEXT NOT NULL,
+    PRIMARY KEY (user_id, badge_id, simulation_id)
+);
+
+-- Badges earned, with the points they were worth at the time
+CREATE TABLE IF NOT EXISTS user_badges (
+    user_id TEXT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    badge_id TEXT NOT NULL,
+    xp INTEGER NOT NULL,
+    earned_at TEXT NOT NULL,
+    PRIMARY KEY (user_id, badge_id)
+);
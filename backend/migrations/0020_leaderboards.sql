@@ -0,0 +1,17 @@
+-- Leaderboard settings a teacher chose for a class; classes without a row
+-- have no leaderboard
+CREATE TABLE IF NOT EXISTS class_leaderboards (
+    class_id TEXT PRIMARY KEY NOT NULL REFERENCES classes (id) ON DELETE CASCADE,
+    enabled INTEGER NOT NULL,
+    anonymize INTEGER NOT NULL,
+    rank_by TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+-- Students who chose to appear on their class's leaderboard
+CREATE TABLE IF NOT EXISTS leaderboard_entries (
+    class_id TEXT NOT NULL REFERENCES classes (id) ON DELETE CASCADE,
+    user_id TEXT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    opted_in_at TEXT NOT NULL,
+    PRIMARY KEY (class_id, user_id)
+);
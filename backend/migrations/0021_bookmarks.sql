@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS bookmarks (
+    id TEXT PRIMARY KEY NOT NULL,
+    user_id TEXT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    simulation_id TEXT NOT NULL,
+    result_id TEXT REFERENCES simulation_results (id) ON DELETE CASCADE,
+    note TEXT NOT NULL DEFAULT '',
+    created_at TEXT NOT NULL
+);
+
+-- One bookmark per simulation, and per result, for each user
+CREATE UNIQUE INDEX IF NOT EXISTS idx_bookmarks_target
+    ON bookmarks (user_id, simulation_id, COALESCE(result_id, ''));
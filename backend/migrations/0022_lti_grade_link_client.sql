@@ -0,0 +1,3 @@
+-- The registration a grade link's launch came through, so scores go back
+-- with the same client ID when a platform has several registrations
+ALTER TABLE lti_grade_links ADD COLUMN client_id TEXT;
@@ -0,0 +1,2 @@
+-- Hardware each result was computed on. Earlier results came from the CPU.
+ALTER TABLE simulation_results ADD COLUMN compute_backend TEXT NOT NULL DEFAULT 'cpu';
@@ -0,0 +1,4 @@
+-- When a score was posted to each gradebook column. Only the first quiz
+-- attempt after a launch is posted, since a quiz shows which answers were
+-- right and can be retaken.
+ALTER TABLE lti_grade_links ADD COLUMN scored_at TEXT;
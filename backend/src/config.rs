@@ -0,0 +1,638 @@
+// Server configuration
+//
+// Settings are read once at startup from a TOML file, `config.toml` in the
+// working directory or the file named by `CONFIG_FILE`, and any of them can
+// be overridden by an environment variable. The variables keep the names
+// the server has always read, so deployments configured through the
+// environment alone carry on unchanged. The merged settings are checked as
+// a whole and the server refuses to start, listing every problem, rather
+// than running with a setting it could not use.
+//
+// Simulation numerics are not configured here. Parameters such as the
+// screen distance or the number of points are part of each simulation's
+// content, and the resolutions fixed in code are part of its versioned
+// computation, which cached and replayed results depend on. Thread limits
+// are, since parallel loops give the same numbers on any number of threads.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::services::lti::Platform;
+use crate::services::{attestation, cache, citation, lti, toml, tutor};
+use crate::simulations;
+
+/// Read when `CONFIG_FILE` is not set, if it exists
+const DEFAULT_FILE: &str = "config.toml";
+
+/// How an environment variable's text becomes a setting
+#[derive(Clone, Copy)]
+enum Kind {
+    Text,
+    Integer,
+    /// `true`/`false`, `1`/`0` or `yes`/`no`
+    Flag,
+    /// Comma-separated
+    List,
+    Json,
+}
+
+/// Environment variables that override settings, with the section and key
+/// they set
+const OVERRIDES: [(&str, &str, &str, Kind); 37] = [
+    ("HOST", "server", "host", Kind::Text),
+    ("PORT", "server", "port", Kind::Integer),
+    ("CORS_ORIGINS", "server", "cors_origins", Kind::List),
+    ("PUBLIC_API_URL", "server", "public_url", Kind::Text),
+    ("FRONTEND_URL", "server", "frontend_url", Kind::Text),
+    ("DATABASE_URL", "database", "url", Kind::Text),
+    ("CONTENT_DIR", "content", "dir", Kind::Text),
+    ("CONTENT_WATCH", "content", "watch", Kind::Flag),
+    ("JWT_SECRET", "auth", "jwt_secret", Kind::Text),
+    ("ADMIN_EMAILS", "auth", "admin_emails", Kind::List),
+    ("REDIS_URL", "cache", "redis_url", Kind::Text),
+    ("RATE_LIMIT_READ_PER_MINUTE", "rate_limit", "read_per_minute", Kind::Integer),
+    ("RATE_LIMIT_READ_BURST", "rate_limit", "read_burst", Kind::Integer),
+    ("RATE_LIMIT_COMPUTE_PER_MINUTE", "rate_limit", "compute_per_minute", Kind::Integer),
+    ("RATE_LIMIT_COMPUTE_BURST", "rate_limit", "compute_burst", Kind::Integer),
+    ("RATE_LIMIT_TRUST_FORWARDED", "rate_limit", "trust_forwarded", Kind::Flag),
+    ("JOB_DRAIN_TIMEOUT_SECS", "jobs", "drain_timeout_secs", Kind::Integer),
+    ("COMPUTE_THREADS", "compute", "threads", Kind::Integer),
+    ("SIMULATION_THREADS", "compute", "simulation_threads", Kind::Json),
+    ("COMPUTE_BACKEND", "compute", "backend", Kind::Text),
+    ("PLUGINS_DIR", "plugins", "dir", Kind::Text),
+    ("PLUGIN_FUEL", "plugins", "fuel", Kind::Integer),
+    ("PLUGIN_MAX_MEMORY_MB", "plugins", "max_memory_mb", Kind::Integer),
+    ("LTI_PLATFORMS", "lti", "platforms", Kind::Json),
+    ("LTI_PRIVATE_KEY", "lti", "private_key", Kind::Text),
+    ("IPFS_API_URL", "ipfs", "api_url", Kind::Text),
+    ("IPFS_API_AUTHORIZATION", "ipfs", "api_authorization", Kind::Text),
+    ("IPFS_GATEWAY_URL", "ipfs", "gateway_url", Kind::Text),
+    ("ATTESTATION_KEY", "attestation", "key", Kind::Text),
+    ("CITATION_DOI", "citation", "doi", Kind::Text),
+    ("EMBEDDINGS_API_URL", "embeddings", "api_url", Kind::Text),
+    ("EMBEDDINGS_API_KEY", "embeddings", "api_key", Kind::Text),
+    ("EMBEDDINGS_MODEL", "embeddings", "model", Kind::Text),
+    ("TUTOR_PROVIDER", "tutor", "provider", Kind::Text),
+    ("TUTOR_API_URL", "tutor", "api_url", Kind::Text),
+    ("TUTOR_API_KEY", "tutor", "api_key", Kind::Text),
+    ("TUTOR_MODEL", "tutor", "model", Kind::Text),
+];
+
+/// Everything the server reads at startup
+#[derive(Default)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    pub content: ContentConfig,
+    pub auth: AuthConfig,
+    pub cache: CacheConfig,
+    pub rate_limit: RateLimitConfig,
+    pub jobs: JobsConfig,
+    pub compute: ComputeConfig,
+    pub plugins: PluginsConfig,
+    pub lti: LtiConfig,
+    pub ipfs: IpfsConfig,
+    pub attestation: AttestationConfig,
+    pub citation: CitationConfig,
+    pub embeddings: EmbeddingsConfig,
+    pub tutor: TutorConfig,
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    /// Address to listen on
+    pub host: IpAddr,
+    pub port: u16,
+    /// Origins browsers may call the API from; `["*"]` allows any
+    pub cors_origins: Vec<String>,
+    /// Public address of this API, e.g. where LTI platforms send launches
+    pub public_url: String,
+    /// Address of the web app
+    pub frontend_url: String,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            host: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            port: 3001,
+            cors_origins: vec!["*".to_string()],
+            public_url: "http://localhost:3001".to_string(),
+            frontend_url: "http://localhost:5173".to_string(),
+        }
+    }
+}
+
+impl ServerConfig {
+    pub fn address(&self) -> SocketAddr {
+        SocketAddr::new(self.host, self.port)
+    }
+
+    /// Whether any origin may call the API
+    pub fn any_origin(&self) -> bool {
+        self.cors_origins.iter().any(|origin| origin == "*")
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    /// SQLite connection URL
+    pub url: String,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            url: "sqlite://physics_tutorial.db".to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ContentConfig {
+    /// Directory holding `simulations/`, the content files, and the
+    /// optional `datasets/`, `badges/` and `golden/`
+    pub dir: PathBuf,
+    /// Reload the catalog when its files change
+    pub watch: bool,
+}
+
+impl Default for ContentConfig {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from("content"),
+            watch: false,
+        }
+    }
+}
+
+impl ContentConfig {
+    /// Directory holding the simulation content files
+    pub fn simulations_dir(&self) -> PathBuf {
+        self.dir.join("simulations")
+    }
+
+    /// Directory holding the experimental datasets, if there are any
+    pub fn datasets_dir(&self) -> PathBuf {
+        self.dir.join("datasets")
+    }
+
+    pub fn badges_dir(&self) -> PathBuf {
+        self.dir.join("badges")
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    /// Secret signing access tokens; a random one is used when unset
+    pub jwt_secret: Option<String>,
+    /// Emails of the accounts with administrator rights
+    pub admin_emails: Vec<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct CacheConfig {
+    /// `redis://host[:port]` to share results between instances
+    pub redis_url: Option<String>,
+}
+
+/// Request budgets; `0` a minute turns a limit off. Bursts default to the
+/// usual burst or the rate a minute, whichever is smaller.
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitConfig {
+    pub read_per_minute: u32,
+    pub read_burst: Option<u32>,
+    pub compute_per_minute: u32,
+    pub compute_burst: Option<u32>,
+    /// Take client addresses from `X-Forwarded-For`, behind a reverse proxy
+    pub trust_forwarded: bool,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            read_per_minute: 300,
+            read_burst: None,
+            compute_per_minute: 30,
+            compute_burst: None,
+            trust_forwarded: false,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct JobsConfig {
+    /// How long running jobs may take to finish at shutdown before they
+    /// are saved to run again after the restart
+    pub drain_timeout_secs: u64,
+}
+
+impl Default for JobsConfig {
+    fn default() -> Self {
+        Self { drain_timeout_secs: 30 }
+    }
+}
+
+impl JobsConfig {
+    pub fn drain_timeout(&self) -> Duration {
+        Duration::from_secs(self.drain_timeout_secs)
+    }
+}
+
+/// Threads a simulation run may use for its parallel loops, such as the
+/// rows of a grid, and the hardware it computes on. Runs of different
+/// requests and jobs still go on at the same time, each on its own blocking
+/// thread.
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ComputeConfig {
+    /// Limit for every simulation; every core when unset
+    pub threads: Option<usize>,
+    /// Limits of particular simulations, by ID, over `threads`
+    pub simulation_threads: BTreeMap<String, usize>,
+    /// `cpu`, or `gpu` for wgpu compute shaders where a simulation has
+    /// them, falling back to the CPU when there is no adapter
+    pub backend: String,
+}
+
+impl Default for ComputeConfig {
+    fn default() -> Self {
+        Self {
+            threads: None,
+            simulation_threads: BTreeMap::new(),
+            backend: "cpu".to_string(),
+        }
+    }
+}
+
+/// Simulations compiled to WebAssembly, loaded at startup next to the
+/// built-in ones
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PluginsConfig {
+    /// Directory of `<id>.wasm` modules; no plugins when unset
+    pub dir: Option<PathBuf>,
+    /// Instructions one run of a plugin may execute
+    pub fuel: u64,
+    /// Memory one run of a plugin may use (MiB)
+    pub max_memory_mb: u32,
+}
+
+impl Default for PluginsConfig {
+    fn default() -> Self {
+        Self {
+            dir: None,
+            fuel: 5_000_000_000,
+            max_memory_mb: 256,
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct LtiConfig {
+    /// Platforms we accept launches from; none disables LTI
+    pub platforms: Vec<Platform>,
+    /// PKCS#8 PEM RSA key we sign with; a random one is used when unset
+    pub private_key: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IpfsConfig {
+    /// RPC address of a Kubo node or pinning service; unset disables pinning
+    pub api_url: Option<String>,
+    /// `Authorization` header the node requires
+    pub api_authorization: Option<String>,
+    /// Public gateway that shared links point to
+    pub gateway_url: String,
+}
+
+impl Default for IpfsConfig {
+    fn default() -> Self {
+        Self {
+            api_url: None,
+            api_authorization: None,
+            gateway_url: "https://ipfs.io".to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct AttestationConfig {
+    /// Base64 Ed25519 seed of 32 bytes; a random one is used when unset
+    pub key: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct CitationConfig {
+    /// DOI minted for this release
+    pub doi: Option<String>,
+}
+
+/// Where embeddings for semantic search come from; the built-in model when
+/// no API is set
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct EmbeddingsConfig {
+    /// Base URL of an OpenAI-compatible API, such as
+    /// `https://api.openai.com/v1` or a self-hosted Ollama, vLLM or
+    /// text-embeddings-inference server; `/embeddings` is appended
+    pub api_url: Option<String>,
+    /// Sent as a bearer token, for APIs that require one
+    pub api_key: Option<String>,
+    /// Model the API embeds with; required with `api_url`
+    pub model: Option<String>,
+}
+
+/// Language model the AI tutor asks; no tutor when no API is set
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TutorConfig {
+    /// `openai` for OpenAI-compatible chat completions (OpenAI, vLLM,
+    /// llama.cpp, LM Studio and others) or `ollama` for Ollama's own API
+    pub provider: String,
+    /// Base URL of the API, e.g. `https://api.openai.com/v1` or
+    /// `http://localhost:11434` for Ollama
+    pub api_url: Option<String>,
+    /// Sent as a bearer token, for APIs that require one
+    pub api_key: Option<String>,
+    /// Model to chat with; required with `api_url`
+    pub model: Option<String>,
+}
+
+impl Default for TutorConfig {
+    fn default() -> Self {
+        Self {
+            provider: "openai".to_string(),
+            api_url: None,
+            api_key: None,
+            model: None,
+        }
+    }
+}
+
+/// Every problem found in the configuration
+#[derive(Debug)]
+pub struct ConfigError {
+    pub problems: Vec<String>,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for problem in &self.problems {
+            writeln!(f, "  - {problem}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Config {
+    /// Read the configuration file, apply environment overrides and check
+    /// the result
+    pub fn load() -> Result<Self, ConfigError> {
+        let mut problems = Vec::new();
+        let mut root = match std::env::var("CONFIG_FILE").ok().filter(|path| !path.trim().is_empty()) {
+            Some(path) => read_file(Path::new(&path), &mut problems),
+            None if Path::new(DEFAULT_FILE).exists() => read_file(Path::new(DEFAULT_FILE), &mut problems),
+            None => Map::new(),
+        };
+        apply_overrides(&mut root, |name| std::env::var(name).ok(), &mut problems);
+
+        let config = Self {
+            server: section(&mut root, "server", &mut problems),
+            database: section(&mut root, "database", &mut problems),
+            content: section(&mut root, "content", &mut problems),
+            auth: section(&mut root, "auth", &mut problems),
+            cache: section(&mut root, "cache", &mut problems),
+            rate_limit: section(&mut root, "rate_limit", &mut problems),
+            jobs: section(&mut root, "jobs", &mut problems),
+            compute: section(&mut root, "compute", &mut problems),
+            plugins: section(&mut root, "plugins", &mut problems),
+            lti: section(&mut root, "lti", &mut problems),
+            ipfs: section(&mut root, "ipfs", &mut problems),
+            attestation: section(&mut root, "attestation", &mut problems),
+            citation: section(&mut root, "citation", &mut problems),
+            embeddings: section(&mut root, "embeddings", &mut problems),
+            tutor: section(&mut root, "tutor", &mut problems),
+        };
+        for name in root.keys() {
+            problems.push(format!("unknown section [{name}]"));
+        }
+        config.validate(&mut problems);
+
+        if problems.is_empty() {
+            Ok(config)
+        } else {
+            Err(ConfigError { problems })
+        }
+    }
+
+    fn validate(&self, problems: &mut Vec<String>) {
+        let mut problem = |message: String| problems.push(message);
+
+        let server = &self.server;
+        if server.any_origin() && server.cors_origins.len() > 1 {
+            problem("server.cors_origins lists \"*\" with other origins; use one or the other".to_string());
+        }
+        for origin in server.cors_origins.iter().filter(|origin| *origin != "*") {
+            if !is_http_url(origin) || origin.trim_start_matches("http://").trim_start_matches("https://").contains('/')
+            {
+                problem(format!("server.cors_origins: {origin:?} is not an origin such as https://example.org"));
+            }
+        }
+        for (key, url) in [("server.public_url", &server.public_url), ("server.frontend_url", &server.frontend_url)] {
+            if !is_http_url(url) {
+                problem(format!("{key}: {url:?} is not an http(s) URL"));
+            }
+        }
+
+        if !self.database.url.starts_with("sqlite:") {
+            problem(format!("database.url: {:?} is not a sqlite: URL", self.database.url));
+        }
+
+        let simulations = self.content.simulations_dir();
+        if !simulations.is_dir() {
+            problem(format!("content.dir: {} is not a directory", simulations.display()));
+        }
+
+        if self.auth.jwt_secret.as_ref().is_some_and(|secret| secret.is_empty()) {
+            problem("auth.jwt_secret is empty".to_string());
+        }
+
+        if let Some(url) = &self.cache.redis_url {
+            if !cache::is_redis_url(url) {
+                problem(format!("cache.redis_url: {url:?} is not redis://host[:port]"));
+            }
+        }
+
+        let limits = &self.rate_limit;
+        for (key, burst) in [("read_burst", limits.read_burst), ("compute_burst", limits.compute_burst)] {
+            if burst == Some(0) {
+                problem(format!("rate_limit.{key} must be at least 1"));
+            }
+        }
+
+        if self.compute.threads == Some(0) {
+            problem("compute.threads must be at least 1".to_string());
+        }
+        for (id, _) in self.compute.simulation_threads.iter().filter(|(_, &threads)| threads == 0) {
+            problem(format!("compute.simulation_threads: {id} must have at least 1 thread"));
+        }
+        if !simulations::BACKENDS.contains(&self.compute.backend.as_str()) {
+            problem(format!("compute.backend must be one of: {}", simulations::BACKENDS.join(", ")));
+        }
+
+        let plugins = &self.plugins;
+        if let Some(dir) = plugins.dir.as_ref().filter(|dir| !dir.is_dir()) {
+            problem(format!("plugins.dir: {} is not a directory", dir.display()));
+        }
+        if plugins.fuel == 0 {
+            problem("plugins.fuel must be at least 1".to_string());
+        }
+        if !(1..=4096).contains(&plugins.max_memory_mb) {
+            problem("plugins.max_memory_mb must be between 1 and 4096".to_string());
+        }
+
+        for platform in &self.lti.platforms {
+            for (key, url) in [
+                ("auth_login_url", &platform.auth_login_url),
+                ("auth_token_url", &platform.auth_token_url),
+                ("jwks_url", &platform.jwks_url),
+            ] {
+                if !is_http_url(url) {
+                    problem(format!("lti.platforms: {key} of {} is not an http(s) URL", platform.issuer));
+                }
+            }
+        }
+        if let Some(pem) = &self.lti.private_key {
+            if let Err(err) = lti::decode_private_key(pem) {
+                problem(format!("lti.private_key is not a PKCS#8 PEM RSA key ({err})"));
+            }
+        }
+
+        let ipfs = &self.ipfs;
+        for (key, url) in [("ipfs.api_url", ipfs.api_url.as_ref()), ("ipfs.gateway_url", Some(&ipfs.gateway_url))] {
+            if let Some(url) = url.filter(|url| !is_http_url(url)) {
+                problem(format!("{key}: {url:?} is not an http(s) URL"));
+            }
+        }
+
+        if let Some(key) = &self.attestation.key {
+            if attestation::decode_seed(key).is_none() {
+                problem("attestation.key is not 32 base64-encoded bytes".to_string());
+            }
+        }
+
+        if let Some(doi) = &self.citation.doi {
+            if !citation::is_doi(doi) {
+                problem(format!("citation.doi: {doi:?} is not a DOI of the form 10.prefix/suffix"));
+            }
+        }
+
+        let embeddings = &self.embeddings;
+        if let Some(url) = &embeddings.api_url {
+            if !is_http_url(url) {
+                problem(format!("embeddings.api_url: {url:?} is not an http(s) URL"));
+            }
+            if embeddings.model.as_ref().is_none_or(|model| model.trim().is_empty()) {
+                problem("embeddings.model must be set with embeddings.api_url".to_string());
+            }
+        }
+
+        let tutor_config = &self.tutor;
+        if !tutor::PROVIDERS.contains(&tutor_config.provider.as_str()) {
+            problem(format!("tutor.provider must be one of: {}", tutor::PROVIDERS.join(", ")));
+        }
+        if let Some(url) = &tutor_config.api_url {
+            if !is_http_url(url) {
+                problem(format!("tutor.api_url: {url:?} is not an http(s) URL"));
+            }
+            if tutor_config.model.as_ref().is_none_or(|model| model.trim().is_empty()) {
+                problem("tutor.model must be set with tutor.api_url".to_string());
+            }
+        }
+    }
+}
+
+fn read_file(path: &Path, problems: &mut Vec<String>) -> Map<String, Value> {
+    let parsed = std::fs::read_to_string(path)
+        .map_err(|err| err.to_string())
+        .and_then(|text| toml::parse(&text).map_err(|err| err.to_string()));
+    match parsed {
+        Ok(Value::Object(root)) => root,
+        Ok(_) => Map::new(),
+        Err(err) => {
+            problems.push(format!("{}: {err}", path.display()));
+            Map::new()
+        }
+    }
+}
+
+/// Put each set environment variable over the file's setting. Empty
+/// variables count as unset.
+fn apply_overrides(root: &mut Map<String, Value>, var: impl Fn(&str) -> Option<String>, problems: &mut Vec<String>) {
+    for (name, section, key, kind) in OVERRIDES {
+        let Some(text) = var(name).filter(|text| !text.trim().is_empty()) else {
+            continue;
+        };
+        let value = match kind {
+            Kind::Text => Ok(Value::from(text)),
+            Kind::Integer => text.trim().parse::<u64>().map(Value::from).map_err(|_| "a whole number"),
+            Kind::Flag => match text.trim() {
+                "1" | "true" | "yes" => Ok(Value::Bool(true)),
+                "0" | "false" | "no" => Ok(Value::Bool(false)),
+                _ => Err("true or false"),
+            },
+            Kind::List => Ok(text
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(Value::from)
+                .collect()),
+            Kind::Json => serde_json::from_str(&text).map_err(|_| "JSON"),
+        };
+        match value {
+            Ok(value) => {
+                let table = root.entry(section).or_insert_with(|| Value::Object(Map::new()));
+                if let Value::Object(table) = table {
+                    table.insert(key.to_string(), value);
+                }
+            }
+            Err(expected) => problems.push(format!("{name} must be {expected}")),
+        }
+    }
+}
+
+/// Take a section out of the document, using the defaults when it is
+/// missing or unusable
+fn section<T: DeserializeOwned + Default>(root: &mut Map<String, Value>, name: &str, problems: &mut Vec<String>) -> T {
+    let Some(value) = root.remove(name) else {
+        return T::default();
+    };
+    serde_json::from_value(value).unwrap_or_else(|err| {
+        problems.push(format!("[{name}]: {err}"));
+        T::default()
+    })
+}
+
+fn is_http_url(url: &str) -> bool {
+    let rest = url.strip_prefix("https://").or_else(|| url.strip_prefix("http://"));
+    rest.is_some_and(|rest| !rest.is_empty() && !rest.contains(char::is_whitespace))
+}
@@ -1,16 +1,33 @@
+// The OpenAPI schema table is one large json! literal
+#![recursion_limit = "512"]
+
 use axum::{
-    routing::{get, post},
+    middleware,
+    routing::{delete, get, post, put},
     Json, Router,
 };
 use serde::Serialize;
 use std::net::SocketAddr;
-use tower_http::cors::{Any, CorsLayer};
+use std::future::IntoFuture;
+use std::time::Duration;
+use axum::http::HeaderValue;
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
 use tower_http::trace::TraceLayer;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
+mod config;
+mod db;
 mod routes;
 mod models;
+mod numerics;
+mod physics;
 mod services;
+mod simulations;
+mod state;
+mod websocket;
+
+use simulations::SimulationRegistry;
+use state::AppState;
 
 #[tokio::main]
 async fn main() {
@@ -23,37 +40,314 @@ async fn main() {
         .with(tracing_subscriber::fmt::layer())
         .init();
 
-    // Load environment variables
+    // Load environment variables, then the configuration they override
     dotenvy::dotenv().ok();
+    let config = config::Config::load().unwrap_or_else(|err| panic!("invalid configuration:\n{err}"));
+
+    // Connect to the database and run migrations
+    let db = db::connect(&config.database.url).await.expect("failed to initialize database");
+
+    // Register built-in simulations and plugins and load their text and parameters
+    let mut registry = SimulationRegistry::with_builtins();
+    if let Some(dir) = &config.plugins.dir {
+        match simulations::plugin::load(&mut registry, dir, &config.plugins) {
+            Ok(count) => tracing::info!("Loaded {} simulation plugins from {}", count, dir.display()),
+            Err(err) => panic!("failed to load the simulation plugins from {}: {}", dir.display(), err),
+        }
+    }
+    let content_dir = config.content.simulations_dir();
+    match registry.load_catalog(&content_dir) {
+        Ok(count) => tracing::info!("Loaded {} simulations from {}", count, content_dir.display()),
+        Err(err) => panic!("failed to load the simulation catalog from {}: {}", content_dir.display(), err),
+    }
+    registry.limit_threads(&config.compute).unwrap_or_else(|err| panic!("invalid configuration: {err}"));
+    let backend = simulations::Backend::select(&config.compute.backend);
+    tracing::info!("Simulations compute on the {}", backend);
+    let datasets_dir = config.content.datasets_dir();
+    let datasets = match services::datasets::Datasets::load(&datasets_dir, &registry) {
+        Ok(datasets) => datasets,
+        Err(err) => panic!("failed to load the datasets from {}: {}", datasets_dir.display(), err),
+    };
+    let badges_dir = config.content.badges_dir();
+    let achievements = match services::achievements::Achievements::load(&badges_dir, &registry) {
+        Ok(achievements) => achievements,
+        Err(err) => panic!("failed to load the badges from {}: {}", badges_dir.display(), err),
+    };
+    let state = AppState::new(config, registry, datasets, achievements, db);
+    routes::content::apply_stored_content(&state)
+        .await
+        .expect("failed to load published simulation content");
+    routes::attestation::register_key(&state)
+        .await
+        .expect("failed to record the attestation key");
+    routes::jobs::resume_pending_jobs(&state)
+        .await
+        .expect("failed to resume jobs saved at the last shutdown");
+    if state.config.content.watch {
+        simulations::catalog::spawn_watcher(state.simulations.clone(), Duration::from_secs(2));
+    }
+
+    let addr = state.config.server.address();
+    let origins = allowed_origins(&state.config.server);
 
     // Build our application with routes
     let app = Router::new()
         // Health check
         .route("/health", get(health_check))
+        .route("/healthz", get(routes::health::healthz))
+        .route("/readyz", get(routes::health::readyz))
+        .route("/version", get(routes::health::version))
+        .route("/metrics", get(routes::metrics::metrics))
+        // API documentation
+        .route("/api/openapi.json", get(routes::openapi::openapi_json))
+        .route("/api/docs", get(routes::openapi::swagger_ui))
         // API routes
         .route("/api/v1/simulations", get(routes::simulations::list_simulations))
+        .route("/api/v1/search", get(routes::search::search))
+        .route("/api/v1/search/semantic", get(routes::search::semantic_search))
         .route("/api/v1/simulations/:id", get(routes::simulations::get_simulation))
+        .route("/api/v1/simulations/:id/theory", get(routes::simulations::get_theory))
+        .route("/api/v1/simulations/:id/related", get(routes::search::related_simulations))
+        .route("/api/v1/simulations/results/:id", get(routes::simulations::get_result))
+        .route("/api/v1/simulations/results/:id/export", get(routes::simulations::export_result))
+        .route("/api/v1/simulations/results/:id/animation", get(routes::simulations::animate_result))
+        .route("/api/v1/simulations/results/:id/bundle", get(routes::simulations::bundle_result))
+        .route("/api/v1/simulations/results/:id/pin", post(routes::simulations::pin_result))
+        .route("/api/v1/simulations/results/by-cid/:cid", get(routes::simulations::bundle_by_cid))
+        .route("/api/v1/simulations/results/:id/replay", post(routes::simulations::replay_result))
+        .route("/api/v1/attestation/keys", get(routes::attestation::list_keys))
+        .route("/api/v1/attestation/verify", post(routes::attestation::verify_result))
+        .route("/api/v1/simulations/doppler/audio", get(routes::audio::doppler_audio))
         .route("/api/v1/simulations/:id/run", post(routes::simulations::run_simulation))
+        .route("/api/v1/simulations/:id/sweep", post(routes::simulations::sweep_simulation))
+        .route("/api/v1/simulations/:id/compare", post(routes::simulations::compare_simulation))
+        .route("/api/v1/simulations/:id/selftest", get(routes::simulations::selftest_simulation))
+        .route("/api/v1/datasets", get(routes::datasets::list_datasets))
+        .route("/api/v1/datasets/:id", get(routes::datasets::get_dataset))
+        .route("/api/v1/analysis/fit", post(routes::analysis::fit_curve))
+        .route("/api/v1/analysis/stats", post(routes::analysis::compute_statistics))
+        .route("/api/v1/analysis/fft", post(routes::analysis::fourier_transform))
+        .route("/api/v1/simulations/:id/jobs", post(routes::jobs::submit_job))
+        .route("/api/v1/jobs/:id", get(routes::jobs::get_job).delete(routes::jobs::cancel_job))
+        .route("/api/v1/jobs/:id/events", get(routes::jobs::job_events))
+        .route("/api/v1/simulations/:id/translations", get(routes::translations::list_translations))
+        .route("/api/v1/simulations/:id/citation", get(routes::citation::get_citation))
+        .route("/api/v1/simulations/:id/quiz", get(routes::quiz::get_quiz))
+        .route("/api/v1/simulations/:id/quiz", post(routes::quiz::submit_quiz))
+        .route("/api/v1/simulations/:id/quiz/hints", get(routes::quiz::list_hints))
+        .route("/api/v1/simulations/:id/quiz/questions/:question_id/hint", post(routes::quiz::reveal_hint))
+        .route("/api/v1/ws/simulations/:id", get(routes::stream::stream_simulation))
+        // Reference data
+        .route("/api/v1/materials", get(routes::materials::list_materials))
+        .route("/api/v1/constants", get(routes::constants::list_constants))
+        .route("/api/v1/units", get(routes::constants::list_units))
+        .route("/api/v1/topics", get(routes::topics::list_topics))
+        .route("/api/v1/topics/:id/simulations", get(routes::topics::topic_simulations))
+        .route("/api/v1/curriculum/graph", get(routes::curriculum::curriculum_graph))
+        .route("/api/v1/curriculum/order", get(routes::curriculum::curriculum_order))
+        // Custom simulations
+        .route(
+            "/api/v1/custom-simulations",
+            get(routes::custom_simulations::list_custom_simulations)
+                .post(routes::custom_simulations::create_custom_simulation),
+        )
+        .route(
+            "/api/v1/custom-simulations/:id",
+            get(routes::custom_simulations::get_custom_simulation)
+                .put(routes::custom_simulations::update_custom_simulation)
+                .delete(routes::custom_simulations::delete_custom_simulation),
+        )
+        .route("/api/v1/custom-simulations/:id/run", post(routes::custom_simulations::run_custom_simulation))
+        // Community library
+        .route(
+            "/api/v1/contributions",
+            get(routes::contributions::list_contributions).post(routes::contributions::submit_contribution),
+        )
+        .route(
+            "/api/v1/contributions/:id",
+            get(routes::contributions::get_contribution)
+                .put(routes::contributions::revise_contribution)
+                .delete(routes::contributions::withdraw_contribution),
+        )
+        .route("/api/v1/library", get(routes::contributions::list_library))
+        .route("/api/v1/library/:id", get(routes::contributions::get_library_entry))
+        .route("/api/v1/library/:id/theory", get(routes::contributions::get_library_theory))
+        .route("/api/v1/library/:id/run", post(routes::contributions::run_library_entry))
+        // Courses
+        .route(
+            "/api/v1/courses",
+            get(routes::courses::list_courses).post(routes::courses::create_course),
+        )
+        .route(
+            "/api/v1/courses/:id",
+            get(routes::courses::get_course)
+                .put(routes::courses::update_course)
+                .delete(routes::courses::delete_course),
+        )
+        .route("/api/v1/courses/:id/modules", post(routes::courses::create_module))
+        .route(
+            "/api/v1/modules/:id",
+            put(routes::courses::update_module).delete(routes::courses::delete_module),
+        )
+        .route("/api/v1/modules/:id/lessons", post(routes::courses::create_lesson))
+        .route(
+            "/api/v1/lessons/:id",
+            get(routes::courses::get_lesson)
+                .put(routes::courses::update_lesson)
+                .delete(routes::courses::delete_lesson),
+        )
+        .route("/api/v1/instructor/courses", get(routes::courses::my_courses))
+        // Classes
+        .route(
+            "/api/v1/classes",
+            get(routes::classes::list_classes).post(routes::classes::create_class),
+        )
+        .route("/api/v1/classes/join", post(routes::classes::join_class))
+        .route(
+            "/api/v1/classes/:id",
+            get(routes::classes::get_class).delete(routes::classes::delete_class),
+        )
+        .route("/api/v1/classes/:id/join-code", post(routes::classes::regenerate_join_code))
+        .route("/api/v1/classes/:id/students/:user_id", delete(routes::classes::remove_student))
+        .route("/api/v1/classes/:id/progress", get(routes::classes::class_progress))
+        .route("/api/v1/classes/:id/gradebook.csv", get(routes::classes::gradebook_csv))
+        .route(
+            "/api/v1/classes/:id/leaderboard",
+            get(routes::leaderboards::get_leaderboard).put(routes::leaderboards::update_leaderboard),
+        )
+        .route(
+            "/api/v1/classes/:id/leaderboard/me",
+            put(routes::leaderboards::join_leaderboard).delete(routes::leaderboards::leave_leaderboard),
+        )
+        .route(
+            "/api/v1/classes/:id/assignments",
+            get(routes::assignments::list_assignments).post(routes::assignments::create_assignment),
+        )
+        .route(
+            "/api/v1/assignments/:id",
+            get(routes::assignments::get_assignment)
+                .put(routes::assignments::update_assignment)
+                .delete(routes::assignments::delete_assignment),
+        )
+        .route(
+            "/api/v1/assignments/:id/submission",
+            get(routes::assignments::get_submission).post(routes::assignments::submit_assignment),
+        )
+        .route("/api/v1/assignments/:id/submissions", get(routes::assignments::list_submissions))
+        .route("/api/v1/submissions/:id/grade", put(routes::assignments::grade_submission))
+        // LMS integration
+        .route("/api/v1/lti/login", get(routes::lti::login).post(routes::lti::login_form))
+        .route("/api/v1/lti/launch", post(routes::lti::launch))
+        .route("/api/v1/lti/deep-link", post(routes::lti::deep_link))
+        .route("/api/v1/lti/jwks", get(routes::lti::jwks))
+        // Administration
+        .route("/api/v1/admin/catalog/reload", post(routes::catalog::reload_catalog))
+        .route("/api/v1/admin/simulations", get(routes::content::list_content))
+        .route("/api/v1/admin/simulations/:id", get(routes::content::get_content))
+        .route(
+            "/api/v1/admin/simulations/:id/draft",
+            put(routes::content::put_draft).delete(routes::content::delete_draft),
+        )
+        .route("/api/v1/admin/simulations/:id/draft/preview", get(routes::content::preview_draft))
+        .route("/api/v1/admin/simulations/:id/publish", post(routes::content::publish_draft))
+        .route("/api/v1/admin/simulations/:id/published", delete(routes::content::revert_content))
+        .route("/api/v1/admin/simulations/:id/retire", post(routes::content::retire_simulation))
+        .route("/api/v1/admin/simulations/:id/restore", post(routes::content::restore_simulation))
+        .route(
+            "/api/v1/admin/simulations/:id/translations/:locale",
+            put(routes::translations::put_translation).delete(routes::translations::delete_translation),
+        )
+        .route("/api/v1/admin/contributions", get(routes::contributions::review_queue))
+        .route("/api/v1/admin/contributions/:id", get(routes::contributions::get_contribution_for_review))
+        .route("/api/v1/admin/contributions/:id/review", post(routes::contributions::review_contribution))
+        .route(
+            "/api/v1/admin/contributions/:id/publish",
+            post(routes::contributions::publish_contribution).delete(routes::contributions::unpublish_contribution),
+        )
+        // Accounts
+        .route("/api/v1/auth/register", post(routes::auth::register))
+        .route("/api/v1/auth/login", post(routes::auth::login))
+        .route("/api/v1/auth/refresh", post(routes::auth::refresh))
+        .route("/api/v1/auth/me", get(routes::auth::me))
         // AI assistant
         .route("/api/v1/ai/ask", post(routes::ai::ask_question))
+        .route("/api/v1/tutor/chat", post(routes::tutor::chat))
         // User progress
         .route("/api/v1/progress", get(routes::progress::get_progress))
-        .route("/api/v1/progress", post(routes::progress::save_progress))
+        .route("/api/v1/progress/:simulation_id", post(routes::progress::save_progress))
+        .route("/api/v1/recommendations", get(routes::recommendations::recommendations))
+        .route("/api/v1/users/me/achievements", get(routes::achievements::my_achievements))
+        .route(
+            "/api/v1/users/me/bookmarks",
+            get(routes::bookmarks::list_bookmarks).post(routes::bookmarks::create_bookmark),
+        )
+        .route("/api/v1/users/me/bookmarks/:id", delete(routes::bookmarks::delete_bookmark))
         // Middleware
-        .layer(TraceLayer::new_for_http())
+        .layer(middleware::from_fn_with_state(state.clone(), routes::rate_limit::limit_requests))
+        .layer(middleware::from_fn_with_state(state.clone(), routes::metrics::track_requests))
+        .layer(TraceLayer::new_for_http().make_span_with(routes::request_id::request_span))
+        .layer(middleware::from_fn(routes::request_id::assign_request_id))
         .layer(
             CorsLayer::new()
-                .allow_origin(Any)
+                .allow_origin(origins)
                 .allow_methods(Any)
                 .allow_headers(Any),
-        );
+        )
+        .with_state(state.clone());
 
     // Run server
-    let addr = SocketAddr::from(([0, 0, 0, 0], 3001));
     tracing::info!("🚀 Physics Tutorial API listening on {}", addr);
     
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+    // Client addresses are needed for rate limiting
+    let server = axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(async {
+            stopped.await.ok();
+        });
+    let server = tokio::spawn(server.into_future());
+
+    // Keep serving while jobs drain, so clients can follow them and fetch
+    // their results, then give open requests a moment to complete
+    shutdown_signal().await;
+    tracing::info!("Shutting down: finishing running jobs");
+    routes::jobs::drain_jobs(&state).await;
+    stop.send(()).ok();
+    if tokio::time::timeout(REQUEST_GRACE, server).await.is_err() {
+        tracing::warn!("Closing connections still open after {:?}", REQUEST_GRACE);
+    }
+}
+
+/// How long requests in progress may take to complete once jobs are drained
+const REQUEST_GRACE: Duration = Duration::from_secs(5);
+
+/// Ctrl-C, or SIGTERM from the container runtime
+async fn shutdown_signal() {
+    let interrupt = async {
+        tokio::signal::ctrl_c().await.expect("failed to listen for Ctrl-C");
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to listen for SIGTERM")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    tokio::select! {
+        _ = interrupt => {}
+        _ = terminate => {}
+    }
+}
+
+/// Origins from the configuration, already checked to be valid
+fn allowed_origins(server: &config::ServerConfig) -> AllowOrigin {
+    if server.any_origin() {
+        return AllowOrigin::any();
+    }
+    let origins = server.cors_origins.iter().filter_map(|origin| HeaderValue::from_str(origin).ok());
+    AllowOrigin::list(origins)
 }
 
 /// Health check endpoint
@@ -0,0 +1,126 @@
+// Achievement records
+// Experience points by day, progress towards badges and the badges earned
+
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::{Row, SqlitePool};
+
+/// A badge a learner has earned
+pub struct EarnedBadge {
+    pub badge_id: String,
+    pub xp: u32,
+    pub earned_at: DateTime<Utc>,
+}
+
+/// Everything stored about one learner's achievements
+pub struct Standing {
+    /// Points from activity, without those of badges
+    pub activity_xp: u32,
+    /// Days with any activity, oldest first
+    pub active_days: Vec<NaiveDate>,
+    pub badges: Vec<EarnedBadge>,
+    /// Simulations counted towards each badge, by badge ID
+    pub progress: Vec<(String, u32)>,
+}
+
+/// Award points for an activity on a day; repeats of the same kind of
+/// activity on the same simulation that day only raise it to the best award
+pub async fn record_activity(
+    pool: &SqlitePool,
+    user_id: &str,
+    day: NaiveDate,
+    kind: &str,
+    simulation_id: &str,
+    xp: u32,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO activity_days (user_id, day, kind, simulation_id, xp) VALUES (?, ?, ?, ?, ?)
+         ON CONFLICT (user_id, day, kind, simulation_id) DO UPDATE SET xp = MAX(xp, excluded.xp)",
+    )
+    .bind(user_id)
+    .bind(day)
+    .bind(kind)
+    .bind(simulation_id)
+    .bind(xp)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Count a simulation towards a badge and return how many now count
+pub async fn count_towards(
+    pool: &SqlitePool,
+    user_id: &str,
+    badge_id: &str,
+    simulation_id: &str,
+) -> Result<u32, sqlx::Error> {
+    sqlx::query("INSERT OR IGNORE INTO badge_progress (user_id, badge_id, simulation_id) VALUES (?, ?, ?)")
+        .bind(user_id)
+        .bind(badge_id)
+        .bind(simulation_id)
+        .execute(pool)
+        .await?;
+    sqlx::query("SELECT COUNT(*) AS count FROM badge_progress WHERE user_id = ? AND badge_id = ?")
+        .bind(user_id)
+        .bind(badge_id)
+        .fetch_one(pool)
+        .await?
+        .try_get("count")
+}
+
+/// Record a badge as earned; false if the learner already had it
+pub async fn award(pool: &SqlitePool, user_id: &str, badge_id: &str, xp: u32) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("INSERT OR IGNORE INTO user_badges (user_id, badge_id, xp, earned_at) VALUES (?, ?, ?, ?)")
+        .bind(user_id)
+        .bind(badge_id)
+        .bind(xp)
+        .bind(Utc::now())
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() == 1)
+}
+
+pub async fn standing(pool: &SqlitePool, user_id: &str) -> Result<Standing, sqlx::Error> {
+    let days = sqlx::query(
+        "SELECT day, SUM(xp) AS xp FROM activity_days WHERE user_id = ? GROUP BY day ORDER BY day",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+    let mut activity_xp = 0;
+    let mut active_days = Vec::with_capacity(days.len());
+    for row in &days {
+        activity_xp += row.try_get::<u32, _>("xp")?;
+        active_days.push(row.try_get("day")?);
+    }
+
+    let badges = sqlx::query("SELECT badge_id, xp, earned_at FROM user_badges WHERE user_id = ? ORDER BY earned_at")
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?
+        .iter()
+        .map(|row| {
+            Ok(EarnedBadge {
+                badge_id: row.try_get("badge_id")?,
+                xp: row.try_get("xp")?,
+                earned_at: row.try_get("earned_at")?,
+            })
+        })
+        .collect::<Result<_, sqlx::Error>>()?;
+
+    let progress = sqlx::query(
+        "SELECT badge_id, COUNT(*) AS count FROM badge_progress WHERE user_id = ? GROUP BY badge_id",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?
+    .iter()
+    .map(|row| Ok((row.try_get("badge_id")?, row.try_get("count")?)))
+    .collect::<Result<_, sqlx::Error>>()?;
+
+    Ok(Standing {
+        activity_xp,
+        active_days,
+        badges,
+        progress,
+    })
+}
@@ -0,0 +1,276 @@
+// Assignment models
+// Teachers set assignments on a simulation for a class; each student hands
+// in one submission, which the teacher grades
+
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::{Row, SqlitePool};
+
+#[derive(Serialize)]
+pub struct Assignment {
+    pub id: String,
+    pub class_id: String,
+    pub simulation_id: String,
+    pub title: String,
+    pub instructions: String,
+    /// Parameter settings the student must run and hand in
+    pub explorations: Vec<Exploration>,
+    pub questions: Vec<AssignmentQuestion>,
+    /// Submissions after this are accepted but marked late
+    pub due_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A required run: any stored result whose parameters include these values
+/// satisfies it
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Exploration {
+    pub id: String,
+    pub description: String,
+    pub parameters: Map<String, Value>,
+}
+
+/// A free-response question, graded by the teacher
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AssignmentQuestion {
+    pub id: String,
+    pub prompt: String,
+}
+
+/// A student's hand-in. Students may resubmit until it is graded.
+#[derive(Serialize)]
+pub struct Submission {
+    pub id: String,
+    pub assignment_id: String,
+    pub student_id: String,
+    /// Stored simulation results handed in
+    pub result_ids: Vec<String>,
+    /// Answers keyed by question ID
+    pub answers: Map<String, Value>,
+    /// Explorations satisfied by the handed-in results
+    pub explorations_completed: Vec<String>,
+    pub submitted_at: DateTime<Utc>,
+    pub late: bool,
+    /// Percentage awarded by the teacher
+    pub grade: Option<f64>,
+    pub feedback: Option<String>,
+    pub graded_at: Option<DateTime<Utc>>,
+}
+
+impl Exploration {
+    /// Whether a run with `parameters` covers this exploration. Numbers
+    /// match when equal to within rounding.
+    pub fn is_satisfied_by(&self, parameters: &Value) -> bool {
+        self.parameters.iter().all(|(name, expected)| {
+            match (parameters.get(name), expected.as_f64()) {
+                (Some(actual), Some(expected)) => actual
+                    .as_f64()
+                    .is_some_and(|actual| (actual - expected).abs() <= 1e-9 * expected.abs().max(1.0)),
+                (Some(actual), None) => actual == expected,
+                (None, _) => false,
+            }
+        })
+    }
+}
+
+impl Assignment {
+    pub async fn insert(&self, pool: &SqlitePool) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO assignments (id, class_id, simulation_id, title, instructions, explorations,
+                 questions, due_at, created_at, updated_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&self.id)
+        .bind(&self.class_id)
+        .bind(&self.simulation_id)
+        .bind(&self.title)
+        .bind(&self.instructions)
+        .bind(serde_json::to_string(&self.explorations).unwrap_or_default())
+        .bind(serde_json::to_string(&self.questions).unwrap_or_default())
+        .bind(self.due_at)
+        .bind(self.created_at)
+        .bind(self.updated_at)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn update(&self, pool: &SqlitePool) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "UPDATE assignments SET simulation_id = ?, title = ?, instructions = ?, explorations = ?,
+                 questions = ?, due_at = ?, updated_at = ?
+             WHERE id = ?",
+        )
+        .bind(&self.simulation_id)
+        .bind(&self.title)
+        .bind(&self.instructions)
+        .bind(serde_json::to_string(&self.explorations).unwrap_or_default())
+        .bind(serde_json::to_string(&self.questions).unwrap_or_default())
+        .bind(self.due_at)
+        .bind(self.updated_at)
+        .bind(&self.id)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Delete an assignment with its submissions
+    pub async fn delete(pool: &SqlitePool, id: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM assignments WHERE id = ?").bind(id).execute(pool).await?;
+        Ok(())
+    }
+
+    pub async fn find(pool: &SqlitePool, id: &str) -> Result<Option<Self>, sqlx::Error> {
+        let row = sqlx::query(
+            "SELECT id, class_id, simulation_id, title, instructions, explorations, questions, due_at,
+                    created_at, updated_at
+             FROM assignments WHERE id = ?",
+        )
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+        row.map(|row| Self::from_row(&row)).transpose()
+    }
+
+    /// Assignments of a class, soonest due first and undated ones last
+    pub async fn for_class(pool: &SqlitePool, class_id: &str) -> Result<Vec<Self>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT id, class_id, simulation_id, title, instructions, explorations, questions, due_at,
+                    created_at, updated_at
+             FROM assignments WHERE class_id = ?
+             ORDER BY due_at IS NULL, due_at, created_at",
+        )
+        .bind(class_id)
+        .fetch_all(pool)
+        .await?;
+        rows.iter().map(Self::from_row).collect()
+    }
+
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            id: row.try_get("id")?,
+            class_id: row.try_get("class_id")?,
+            simulation_id: row.try_get("simulation_id")?,
+            title: row.try_get("title")?,
+            instructions: row.try_get("instructions")?,
+            explorations: json_column(row, "explorations")?,
+            questions: json_column(row, "questions")?,
+            due_at: row.try_get("due_at")?,
+            created_at: row.try_get("created_at")?,
+            updated_at: row.try_get("updated_at")?,
+        })
+    }
+}
+
+impl Submission {
+    /// Save a first submission or replace the student's earlier one
+    pub async fn upsert(&self, pool: &SqlitePool) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO assignment_submissions (id, assignment_id, student_id, result_ids, answers,
+                 explorations_completed, submitted_at, late)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT (assignment_id, student_id) DO UPDATE SET
+                 result_ids = excluded.result_ids,
+                 answers = excluded.answers,
+                 explorations_completed = excluded.explorations_completed,
+                 submitted_at = excluded.submitted_at,
+                 late = excluded.late",
+        )
+        .bind(&self.id)
+        .bind(&self.assignment_id)
+        .bind(&self.student_id)
+        .bind(serde_json::to_string(&self.result_ids).unwrap_or_default())
+        .bind(Value::Object(self.answers.clone()).to_string())
+        .bind(serde_json::to_string(&self.explorations_completed).unwrap_or_default())
+        .bind(self.submitted_at)
+        .bind(self.late)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Record the teacher's grade and feedback
+    pub async fn grade(&self, pool: &SqlitePool) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE assignment_submissions SET grade = ?, feedback = ?, graded_at = ? WHERE id = ?")
+            .bind(self.grade)
+            .bind(&self.feedback)
+            .bind(self.graded_at)
+            .bind(&self.id)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn find(pool: &SqlitePool, id: &str) -> Result<Option<Self>, sqlx::Error> {
+        let row = sqlx::query(&format!("{SUBMISSION_COLUMNS} WHERE id = ?"))
+            .bind(id)
+            .fetch_optional(pool)
+            .await?;
+        row.map(|row| Self::from_row(&row)).transpose()
+    }
+
+    pub async fn find_for_student(
+        pool: &SqlitePool,
+        assignment_id: &str,
+        student_id: &str,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        let row = sqlx::query(&format!("{SUBMISSION_COLUMNS} WHERE assignment_id = ? AND student_id = ?"))
+            .bind(assignment_id)
+            .bind(student_id)
+            .fetch_optional(pool)
+            .await?;
+        row.map(|row| Self::from_row(&row)).transpose()
+    }
+
+    /// Every submission to an assignment, oldest first
+    pub async fn for_assignment(pool: &SqlitePool, assignment_id: &str) -> Result<Vec<Self>, sqlx::Error> {
+        let rows = sqlx::query(&format!("{SUBMISSION_COLUMNS} WHERE assignment_id = ? ORDER BY submitted_at"))
+            .bind(assignment_id)
+            .fetch_all(pool)
+            .await?;
+        rows.iter().map(Self::from_row).collect()
+    }
+
+    /// Every submission to any assignment of a class
+    pub async fn for_class(pool: &SqlitePool, class_id: &str) -> Result<Vec<Self>, sqlx::Error> {
+        let rows = sqlx::query(&format!(
+            "{SUBMISSION_COLUMNS} WHERE assignment_id IN (SELECT id FROM assignments WHERE class_id = ?)"
+        ))
+        .bind(class_id)
+        .fetch_all(pool)
+        .await?;
+        rows.iter().map(Self::from_row).collect()
+    }
+
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            id: row.try_get("id")?,
+            assignment_id: row.try_get("assignment_id")?,
+            student_id: row.try_get("student_id")?,
+            result_ids: json_column(row, "result_ids")?,
+            answers: json_column(row, "answers")?,
+            explorations_completed: json_column(row, "explorations_completed")?,
+            submitted_at: row.try_get("submitted_at")?,
+            late: row.try_get("late")?,
+            grade: row.try_get("grade")?,
+            feedback: row.try_get("feedback")?,
+            graded_at: row.try_get("graded_at")?,
+        })
+    }
+}
+
+const SUBMISSION_COLUMNS: &str = "SELECT id, assignment_id, student_id, result_ids, answers, explorations_completed,
+        submitted_at, late, grade, feedback, graded_at
+ FROM assignment_submissions";
+
+/// Decode a column holding JSON text
+fn json_column<T: DeserializeOwned>(row: &sqlx::sqlite::SqliteRow, name: &str) -> Result<T, sqlx::Error> {
+    let text: String = row.try_get(name)?;
+    serde_json::from_str(&text).map_err(|e| sqlx::Error::ColumnDecode {
+        index: name.to_string(),
+        source: Box::new(e),
+    })
+}
@@ -0,0 +1,56 @@
+// Public keys results have been signed with
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
+
+#[derive(Serialize, Clone)]
+pub struct AttestationKey {
+    pub key_id: String,
+    pub algorithm: String,
+    /// Raw public key, base64 encoded
+    pub public_key: String,
+    pub first_used_at: DateTime<Utc>,
+}
+
+impl AttestationKey {
+    /// Record the key unless it is already known
+    pub async fn register(&self, pool: &SqlitePool) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO attestation_keys (key_id, algorithm, public_key, first_used_at) VALUES (?, ?, ?, ?)
+             ON CONFLICT (key_id) DO NOTHING",
+        )
+        .bind(&self.key_id)
+        .bind(&self.algorithm)
+        .bind(&self.public_key)
+        .bind(self.first_used_at)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn find(pool: &SqlitePool, key_id: &str) -> Result<Option<Self>, sqlx::Error> {
+        let row = sqlx::query(&format!("{COLUMNS} WHERE key_id = ?"))
+            .bind(key_id)
+            .fetch_optional(pool)
+            .await?;
+        row.as_ref().map(Self::from_row).transpose()
+    }
+
+    /// Every key, oldest first
+    pub async fn all(pool: &SqlitePool) -> Result<Vec<Self>, sqlx::Error> {
+        let rows = sqlx::query(&format!("{COLUMNS} ORDER BY first_used_at")).fetch_all(pool).await?;
+        rows.iter().map(Self::from_row).collect()
+    }
+
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            key_id: row.try_get("key_id")?,
+            algorithm: row.try_get("algorithm")?,
+            public_key: row.try_get("public_key")?,
+            first_used_at: row.try_get("first_used_at")?,
+        })
+    }
+}
+
+const COLUMNS: &str = "SELECT key_id, algorithm, public_key, first_used_at FROM attestation_keys";
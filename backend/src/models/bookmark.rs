@@ -0,0 +1,111 @@
+// Bookmark models
+// A learner's saved simulations and simulation results, each with a note
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Row, SqlitePool};
+
+const COLUMNS: &str = "SELECT id, user_id, simulation_id, result_id, note, created_at FROM bookmarks";
+
+/// A simulation, or one stored result of it, a learner wants to come back to
+#[derive(Serialize)]
+pub struct Bookmark {
+    pub id: String,
+    #[serde(skip)]
+    pub user_id: String,
+    pub simulation_id: String,
+    /// The stored result bookmarked; absent for a bookmark of the simulation
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result_id: Option<String>,
+    pub note: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Bookmark {
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            id: row.try_get("id")?,
+            user_id: row.try_get("user_id")?,
+            simulation_id: row.try_get("simulation_id")?,
+            result_id: row.try_get("result_id")?,
+            note: row.try_get("note")?,
+            created_at: row.try_get("created_at")?,
+        })
+    }
+
+    pub async fn insert(&self, pool: &SqlitePool) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO bookmarks (id, user_id, simulation_id, result_id, note, created_at)
+             VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&self.id)
+        .bind(&self.user_id)
+        .bind(&self.simulation_id)
+        .bind(&self.result_id)
+        .bind(&self.note)
+        .bind(self.created_at)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn find(pool: &SqlitePool, id: &str) -> Result<Option<Self>, sqlx::Error> {
+        sqlx::query(&format!("{COLUMNS} WHERE id = ?"))
+            .bind(id)
+            .fetch_optional(pool)
+            .await?
+            .map(|row| Self::from_row(&row))
+            .transpose()
+    }
+
+    /// The user's bookmark of a simulation, or of one of its results
+    pub async fn find_target(
+        pool: &SqlitePool,
+        user_id: &str,
+        simulation_id: &str,
+        result_id: Option<&str>,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        sqlx::query(&format!("{COLUMNS} WHERE user_id = ? AND simulation_id = ? AND result_id IS ?"))
+            .bind(user_id)
+            .bind(simulation_id)
+            .bind(result_id)
+            .fetch_optional(pool)
+            .await?
+            .map(|row| Self::from_row(&row))
+            .transpose()
+    }
+
+    /// A user's bookmarks, newest first, optionally only those of one
+    /// simulation
+    pub async fn of_user(
+        pool: &SqlitePool,
+        user_id: &str,
+        simulation_id: Option<&str>,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query(&format!(
+            "{COLUMNS} WHERE user_id = ? AND (? IS NULL OR simulation_id = ?) ORDER BY created_at DESC"
+        ))
+        .bind(user_id)
+        .bind(simulation_id)
+        .bind(simulation_id)
+        .fetch_all(pool)
+        .await?
+        .iter()
+        .map(Self::from_row)
+        .collect()
+    }
+
+    pub async fn count_of_user(pool: &SqlitePool, user_id: &str) -> Result<i64, sqlx::Error> {
+        sqlx::query("SELECT COUNT(*) AS count FROM bookmarks WHERE user_id = ?")
+            .bind(user_id)
+            .fetch_one(pool)
+            .await?
+            .try_get("count")
+    }
+
+    pub async fn delete(pool: &SqlitePool, id: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM bookmarks WHERE id = ?").bind(id).execute(pool).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,139 @@
+// Classroom models
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
+
+/// A class run by a teacher. Students join it with its join code.
+#[derive(Serialize)]
+pub struct Classroom {
+    pub id: String,
+    pub teacher_id: String,
+    pub name: String,
+    /// Only shown to the teacher
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub join_code: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A student enrolled in a class
+#[derive(Serialize)]
+pub struct ClassMember {
+    pub user_id: String,
+    pub display_name: String,
+    pub email: String,
+    pub joined_at: DateTime<Utc>,
+}
+
+impl Classroom {
+    pub async fn insert(&self, pool: &SqlitePool) -> Result<(), sqlx::Error> {
+        sqlx::query("INSERT INTO classes (id, teacher_id, name, join_code, created_at) VALUES (?, ?, ?, ?, ?)")
+            .bind(&self.id)
+            .bind(&self.teacher_id)
+            .bind(&self.name)
+            .bind(&self.join_code)
+            .bind(self.created_at)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Replace the join code; the old one stops working
+    pub async fn set_join_code(pool: &SqlitePool, id: &str, join_code: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE classes SET join_code = ? WHERE id = ?")
+            .bind(join_code)
+            .bind(id)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Delete a class and its enrolments. Students keep their progress.
+    pub async fn delete(pool: &SqlitePool, id: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM classes WHERE id = ?").bind(id).execute(pool).await?;
+        Ok(())
+    }
+
+    pub async fn find(pool: &SqlitePool, id: &str) -> Result<Option<Self>, sqlx::Error> {
+        let row = sqlx::query("SELECT id, teacher_id, name, join_code, created_at FROM classes WHERE id = ?")
+            .bind(id)
+            .fetch_optional(pool)
+            .await?;
+        row.map(|row| Self::from_row(&row)).transpose()
+    }
+
+    pub async fn find_by_join_code(pool: &SqlitePool, join_code: &str) -> Result<Option<Self>, sqlx::Error> {
+        let row = sqlx::query("SELECT id, teacher_id, name, join_code, created_at FROM classes WHERE join_code = ?")
+            .bind(join_code)
+            .fetch_optional(pool)
+            .await?;
+        row.map(|row| Self::from_row(&row)).transpose()
+    }
+
+    /// Classes a user teaches, then classes they have joined, newest first
+    pub async fn for_user(pool: &SqlitePool, user_id: &str) -> Result<Vec<Self>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT id, teacher_id, name, join_code, created_at FROM classes
+             WHERE teacher_id = ?1
+                OR id IN (SELECT class_id FROM class_members WHERE user_id = ?1)
+             ORDER BY teacher_id = ?1 DESC, created_at DESC",
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
+        rows.iter().map(Self::from_row).collect()
+    }
+
+    /// Enrol a student; joining twice is harmless
+    pub async fn add_member(pool: &SqlitePool, id: &str, user_id: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("INSERT OR IGNORE INTO class_members (class_id, user_id, joined_at) VALUES (?, ?, ?)")
+            .bind(id)
+            .bind(user_id)
+            .bind(Utc::now())
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Remove a student and report whether they were enrolled
+    pub async fn remove_member(pool: &SqlitePool, id: &str, user_id: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM class_members WHERE class_id = ? AND user_id = ?")
+            .bind(id)
+            .bind(user_id)
+            .execute(pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Enrolled students, in the order they joined
+    pub async fn members(pool: &SqlitePool, id: &str) -> Result<Vec<ClassMember>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT u.id, u.display_name, u.email, m.joined_at
+             FROM class_members m JOIN users u ON u.id = m.user_id
+             WHERE m.class_id = ? ORDER BY m.joined_at",
+        )
+        .bind(id)
+        .fetch_all(pool)
+        .await?;
+        rows.iter()
+            .map(|row| {
+                Ok(ClassMember {
+                    user_id: row.try_get("id")?,
+                    display_name: row.try_get("display_name")?,
+                    email: row.try_get("email")?,
+                    joined_at: row.try_get("joined_at")?,
+                })
+            })
+            .collect()
+    }
+
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            id: row.try_get("id")?,
+            teacher_id: row.try_get("teacher_id")?,
+            name: row.try_get("name")?,
+            join_code: row.try_get("join_code")?,
+            created_at: row.try_get("created_at")?,
+        })
+    }
+}
@@ -0,0 +1,144 @@
+// Simulation content models
+// Drafts and published versions of simulation content edited through the
+// admin API, and the simulations withdrawn from the catalog
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
+
+use crate::simulations::catalog::SimulationContent;
+
+pub const DRAFT: &str = "draft";
+pub const PUBLISHED: &str = "published";
+
+/// One stored version of a simulation's content
+#[derive(Serialize)]
+pub struct ContentRevision {
+    #[serde(skip)]
+    pub simulation_id: String,
+    /// `draft` or `published`
+    #[serde(skip)]
+    pub state: &'static str,
+    pub content: SimulationContent,
+    /// ID of the administrator who saved it
+    pub updated_by: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ContentRevision {
+    /// Save the revision, replacing the simulation's earlier one in the same
+    /// state
+    pub async fn upsert(&self, pool: &SqlitePool) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO simulation_content (simulation_id, state, content, updated_by, updated_at)
+             VALUES (?, ?, ?, ?, ?)
+             ON CONFLICT (simulation_id, state) DO UPDATE SET
+                 content = excluded.content,
+                 updated_by = excluded.updated_by,
+                 updated_at = excluded.updated_at",
+        )
+        .bind(&self.simulation_id)
+        .bind(self.state)
+        .bind(serde_json::to_string(&self.content).unwrap_or_default())
+        .bind(&self.updated_by)
+        .bind(self.updated_at)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn find(pool: &SqlitePool, simulation_id: &str, state: &'static str) -> Result<Option<Self>, sqlx::Error> {
+        let row = sqlx::query(&format!("{COLUMNS} WHERE simulation_id = ? AND state = ?"))
+            .bind(simulation_id)
+            .bind(state)
+            .fetch_optional(pool)
+            .await?;
+        row.map(|row| Self::from_row(&row, state)).transpose()
+    }
+
+    /// Every revision in one state
+    pub async fn all(pool: &SqlitePool, state: &'static str) -> Result<Vec<Self>, sqlx::Error> {
+        let rows = sqlx::query(&format!("{COLUMNS} WHERE state = ? ORDER BY simulation_id"))
+            .bind(state)
+            .fetch_all(pool)
+            .await?;
+        rows.iter().map(|row| Self::from_row(row, state)).collect()
+    }
+
+    /// Delete a revision and report whether it existed
+    pub async fn delete(pool: &SqlitePool, simulation_id: &str, state: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM simulation_content WHERE simulation_id = ? AND state = ?")
+            .bind(simulation_id)
+            .bind(state)
+            .execute(pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Make the draft the published version, in one transaction
+    pub async fn publish(pool: &SqlitePool, simulation_id: &str, published_by: &str) -> Result<bool, sqlx::Error> {
+        let mut tx = pool.begin().await?;
+        sqlx::query("DELETE FROM simulation_content WHERE simulation_id = ? AND state = ?")
+            .bind(simulation_id)
+            .bind(PUBLISHED)
+            .execute(&mut *tx)
+            .await?;
+        let result = sqlx::query(
+            "UPDATE simulation_content SET state = ?, updated_by = ?, updated_at = ?
+             WHERE simulation_id = ? AND state = ?",
+        )
+        .bind(PUBLISHED)
+        .bind(published_by)
+        .bind(Utc::now())
+        .bind(simulation_id)
+        .bind(DRAFT)
+        .execute(&mut *tx)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    fn from_row(row: &sqlx::sqlite::SqliteRow, state: &'static str) -> Result<Self, sqlx::Error> {
+        let content: String = row.try_get("content")?;
+        Ok(Self {
+            simulation_id: row.try_get("simulation_id")?,
+            state,
+            content: serde_json::from_str(&content).map_err(|e| sqlx::Error::ColumnDecode {
+                index: "content".to_string(),
+                source: Box::new(e),
+            })?,
+            updated_by: row.try_get("updated_by")?,
+            updated_at: row.try_get("updated_at")?,
+        })
+    }
+}
+
+const COLUMNS: &str = "SELECT simulation_id, content, updated_by, updated_at FROM simulation_content";
+
+/// IDs of every retired simulation
+pub async fn retired(pool: &SqlitePool) -> Result<Vec<String>, sqlx::Error> {
+    let rows = sqlx::query("SELECT simulation_id FROM retired_simulations").fetch_all(pool).await?;
+    rows.iter().map(|row| row.try_get("simulation_id")).collect()
+}
+
+pub async fn retire(pool: &SqlitePool, simulation_id: &str, retired_by: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT OR IGNORE INTO retired_simulations (simulation_id, retired_by, retired_at) VALUES (?, ?, ?)")
+        .bind(simulation_id)
+        .bind(retired_by)
+        .bind(Utc::now())
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Put a simulation back in the catalog and report whether it was retired
+pub async fn restore(pool: &SqlitePool, simulation_id: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM retired_simulations WHERE simulation_id = ?")
+        .bind(simulation_id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
@@ -0,0 +1,229 @@
+// Contribution models
+// A contribution is a scripted simulation, with the metadata and theory a
+// catalog entry carries, submitted for review. Reviewers approve or reject
+// it with a comment; administrators publish approved contributions into the
+// community library, where anyone can read and run them.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
+
+use crate::models::custom_simulation::{json_column, Domain, Output};
+use crate::simulations::catalog::ParameterDefinition;
+
+pub const PENDING: &str = "pending";
+pub const APPROVED: &str = "approved";
+pub const REJECTED: &str = "rejected";
+pub const PUBLISHED: &str = "published";
+pub const STATUSES: [&str; 4] = [PENDING, APPROVED, REJECTED, PUBLISHED];
+
+/// A simulation submitted to the community library
+#[derive(Serialize)]
+pub struct Contribution {
+    pub id: String,
+    pub author_id: String,
+    /// `pending`, `approved`, `rejected` or `published`
+    pub status: String,
+    pub name: String,
+    /// One sentence for listings
+    pub summary: String,
+    pub description: String,
+    /// `beginner`, `intermediate` or `advanced`
+    pub difficulty: String,
+    /// Free-form topic labels, as simulations carry
+    pub topics: Vec<String>,
+    /// Markdown with LaTeX math, as in the content files
+    pub theory: String,
+    /// Sliders the script reads by name
+    pub parameters: Vec<ParameterDefinition>,
+    /// Statements computing f; see `services::script`
+    pub script: String,
+    pub domain: Domain,
+    pub output: Output,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub published_at: Option<DateTime<Utc>>,
+    /// Review decisions, oldest first; left out of the public library
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reviews: Option<Vec<Review>>,
+}
+
+/// A reviewer's decision on one revision of a contribution
+#[derive(Serialize)]
+pub struct Review {
+    pub id: String,
+    pub reviewer_id: String,
+    /// `approved` or `rejected`
+    pub decision: String,
+    pub comment: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Contribution {
+    pub async fn insert(&self, pool: &SqlitePool) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO contributions (id, author_id, status, name, summary, description, difficulty, topics,
+                 theory, parameters, script, domain, output, created_at, updated_at, published_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&self.id)
+        .bind(&self.author_id)
+        .bind(&self.status)
+        .bind(&self.name)
+        .bind(&self.summary)
+        .bind(&self.description)
+        .bind(&self.difficulty)
+        .bind(serde_json::to_string(&self.topics).unwrap_or_default())
+        .bind(&self.theory)
+        .bind(serde_json::to_string(&self.parameters).unwrap_or_default())
+        .bind(&self.script)
+        .bind(serde_json::to_string(&self.domain).unwrap_or_default())
+        .bind(serde_json::to_string(&self.output).unwrap_or_default())
+        .bind(self.created_at)
+        .bind(self.updated_at)
+        .bind(self.published_at)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Save a changed definition or status
+    pub async fn update(&self, pool: &SqlitePool) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "UPDATE contributions SET status = ?, name = ?, summary = ?, description = ?, difficulty = ?,
+                 topics = ?, theory = ?, parameters = ?, script = ?, domain = ?, output = ?, updated_at = ?,
+                 published_at = ?
+             WHERE id = ?",
+        )
+        .bind(&self.status)
+        .bind(&self.name)
+        .bind(&self.summary)
+        .bind(&self.description)
+        .bind(&self.difficulty)
+        .bind(serde_json::to_string(&self.topics).unwrap_or_default())
+        .bind(&self.theory)
+        .bind(serde_json::to_string(&self.parameters).unwrap_or_default())
+        .bind(&self.script)
+        .bind(serde_json::to_string(&self.domain).unwrap_or_default())
+        .bind(serde_json::to_string(&self.output).unwrap_or_default())
+        .bind(self.updated_at)
+        .bind(self.published_at)
+        .bind(&self.id)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn delete(pool: &SqlitePool, id: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM contributions WHERE id = ?").bind(id).execute(pool).await?;
+        Ok(())
+    }
+
+    /// A contribution with its reviews
+    pub async fn find(pool: &SqlitePool, id: &str) -> Result<Option<Self>, sqlx::Error> {
+        let row = sqlx::query(&format!("{COLUMNS} WHERE id = ?")).bind(id).fetch_optional(pool).await?;
+        let Some(mut contribution) = row.as_ref().map(Self::from_row).transpose()? else {
+            return Ok(None);
+        };
+        contribution.reviews = Some(Review::for_contribution(pool, id).await?);
+        Ok(Some(contribution))
+    }
+
+    /// A user's contributions with their reviews, most recently updated first
+    pub async fn by_author(pool: &SqlitePool, author_id: &str) -> Result<Vec<Self>, sqlx::Error> {
+        let rows = sqlx::query(&format!("{COLUMNS} WHERE author_id = ? ORDER BY updated_at DESC"))
+            .bind(author_id)
+            .fetch_all(pool)
+            .await?;
+        let mut contributions = rows.iter().map(Self::from_row).collect::<Result<Vec<_>, _>>()?;
+        for contribution in &mut contributions {
+            contribution.reviews = Some(Review::for_contribution(pool, &contribution.id).await?);
+        }
+        Ok(contributions)
+    }
+
+    /// Contributions in one status without their reviews, the longest
+    /// waiting first
+    pub async fn with_status(pool: &SqlitePool, status: &str) -> Result<Vec<Self>, sqlx::Error> {
+        let rows = sqlx::query(&format!("{COLUMNS} WHERE status = ? ORDER BY updated_at"))
+            .bind(status)
+            .fetch_all(pool)
+            .await?;
+        rows.iter().map(Self::from_row).collect()
+    }
+
+    /// How many of a user's contributions are in one status
+    pub async fn count_by_author(pool: &SqlitePool, author_id: &str, status: &str) -> Result<i64, sqlx::Error> {
+        sqlx::query("SELECT COUNT(*) FROM contributions WHERE author_id = ? AND status = ?")
+            .bind(author_id)
+            .bind(status)
+            .fetch_one(pool)
+            .await?
+            .try_get(0)
+    }
+
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            id: row.try_get("id")?,
+            author_id: row.try_get("author_id")?,
+            status: row.try_get("status")?,
+            name: row.try_get("name")?,
+            summary: row.try_get("summary")?,
+            description: row.try_get("description")?,
+            difficulty: row.try_get("difficulty")?,
+            topics: json_column(row, "topics")?,
+            theory: row.try_get("theory")?,
+            parameters: json_column(row, "parameters")?,
+            script: row.try_get("script")?,
+            domain: json_column(row, "domain")?,
+            output: json_column(row, "output")?,
+            created_at: row.try_get("created_at")?,
+            updated_at: row.try_get("updated_at")?,
+            published_at: row.try_get("published_at")?,
+            reviews: None,
+        })
+    }
+}
+
+const COLUMNS: &str = "SELECT id, author_id, status, name, summary, description, difficulty, topics, theory,
+     parameters, script, domain, output, created_at, updated_at, published_at FROM contributions";
+
+impl Review {
+    /// Record a decision on a contribution
+    pub async fn insert(&self, pool: &SqlitePool, contribution_id: &str) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO contribution_reviews (id, contribution_id, reviewer_id, decision, comment, created_at)
+             VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&self.id)
+        .bind(contribution_id)
+        .bind(&self.reviewer_id)
+        .bind(&self.decision)
+        .bind(&self.comment)
+        .bind(self.created_at)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn for_contribution(pool: &SqlitePool, contribution_id: &str) -> Result<Vec<Self>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT id, reviewer_id, decision, comment, created_at FROM contribution_reviews
+             WHERE contribution_id = ? ORDER BY created_at",
+        )
+        .bind(contribution_id)
+        .fetch_all(pool)
+        .await?;
+        rows.iter()
+            .map(|row| {
+                Ok(Self {
+                    id: row.try_get("id")?,
+                    reviewer_id: row.try_get("reviewer_id")?,
+                    decision: row.try_get("decision")?,
+                    comment: row.try_get("comment")?,
+                    created_at: row.try_get("created_at")?,
+                })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,396 @@
+// Course models
+// A course is an ordered list of modules, a module an ordered list of
+// lessons, and a lesson an ordered list of items drawn from the simulations
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::{Row, SqliteConnection, SqlitePool};
+
+/// A course written by an instructor. Only its owner sees it until it is
+/// published.
+#[derive(Serialize)]
+pub struct Course {
+    pub id: String,
+    pub owner_id: String,
+    pub title: String,
+    pub description: String,
+    pub published: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Serialize)]
+pub struct CourseModule {
+    pub id: String,
+    pub course_id: String,
+    /// Place within the course, from 0
+    pub position: u32,
+    pub title: String,
+    pub description: String,
+}
+
+#[derive(Serialize)]
+pub struct Lesson {
+    pub id: String,
+    pub module_id: String,
+    /// Place within the module, from 0
+    pub position: u32,
+    pub title: String,
+    pub items: Vec<LessonItem>,
+}
+
+/// One step of a lesson
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LessonItem {
+    /// Instructor-written text
+    Text { body: String },
+    /// Run a simulation, optionally starting from the given parameters
+    Simulation {
+        simulation_id: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        parameters: Option<Map<String, Value>>,
+    },
+    /// The theory section of a simulation
+    Theory { simulation_id: String },
+    /// The quiz of a simulation
+    Quiz { simulation_id: String },
+}
+
+impl LessonItem {
+    /// Simulation the item refers to, if any
+    pub fn simulation_id(&self) -> Option<&str> {
+        match self {
+            Self::Text { .. } => None,
+            Self::Simulation { simulation_id, .. } | Self::Theory { simulation_id } | Self::Quiz { simulation_id } => {
+                Some(simulation_id)
+            }
+        }
+    }
+}
+
+impl Course {
+    pub async fn insert(&self, pool: &SqlitePool) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO courses (id, owner_id, title, description, published, created_at, updated_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&self.id)
+        .bind(&self.owner_id)
+        .bind(&self.title)
+        .bind(&self.description)
+        .bind(self.published)
+        .bind(self.created_at)
+        .bind(self.updated_at)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Save a changed title, description or publication state
+    pub async fn update(&self, pool: &SqlitePool) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE courses SET title = ?, description = ?, published = ?, updated_at = ? WHERE id = ?")
+            .bind(&self.title)
+            .bind(&self.description)
+            .bind(self.published)
+            .bind(self.updated_at)
+            .bind(&self.id)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Record that the course's modules or lessons changed
+    pub async fn touch(pool: &SqlitePool, id: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE courses SET updated_at = ? WHERE id = ?")
+            .bind(Utc::now())
+            .bind(id)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Delete a course with all its modules and lessons
+    pub async fn delete(pool: &SqlitePool, id: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM courses WHERE id = ?").bind(id).execute(pool).await?;
+        Ok(())
+    }
+
+    pub async fn find(pool: &SqlitePool, id: &str) -> Result<Option<Self>, sqlx::Error> {
+        let row = sqlx::query(
+            "SELECT id, owner_id, title, description, published, created_at, updated_at
+             FROM courses WHERE id = ?",
+        )
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+        row.map(|row| Self::from_row(&row)).transpose()
+    }
+
+    /// Published courses, most recently updated first
+    pub async fn published(pool: &SqlitePool) -> Result<Vec<Self>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT id, owner_id, title, description, published, created_at, updated_at
+             FROM courses WHERE published = 1 ORDER BY updated_at DESC",
+        )
+        .fetch_all(pool)
+        .await?;
+        rows.iter().map(Self::from_row).collect()
+    }
+
+    /// Every course of one instructor, drafts included, most recently
+    /// updated first
+    pub async fn owned_by(pool: &SqlitePool, owner_id: &str) -> Result<Vec<Self>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT id, owner_id, title, description, published, created_at, updated_at
+             FROM courses WHERE owner_id = ? ORDER BY updated_at DESC",
+        )
+        .bind(owner_id)
+        .fetch_all(pool)
+        .await?;
+        rows.iter().map(Self::from_row).collect()
+    }
+
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            id: row.try_get("id")?,
+            owner_id: row.try_get("owner_id")?,
+            title: row.try_get("title")?,
+            description: row.try_get("description")?,
+            published: row.try_get("published")?,
+            created_at: row.try_get("created_at")?,
+            updated_at: row.try_get("updated_at")?,
+        })
+    }
+}
+
+impl CourseModule {
+    /// Insert at `position`, or at the end, moving later modules down.
+    /// `self.position` is updated to where the module landed.
+    pub async fn insert(&mut self, pool: &SqlitePool, position: Option<u32>) -> Result<(), sqlx::Error> {
+        let mut tx = pool.begin().await?;
+        self.position = MODULES.open(&mut tx, &self.course_id, position).await?;
+        sqlx::query(
+            "INSERT INTO course_modules (id, course_id, position, title, description) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(&self.id)
+        .bind(&self.course_id)
+        .bind(self.position)
+        .bind(&self.title)
+        .bind(&self.description)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await
+    }
+
+    /// Save a changed title or description, and move the module when
+    /// `position` is given
+    pub async fn update(&mut self, pool: &SqlitePool, position: Option<u32>) -> Result<(), sqlx::Error> {
+        let mut tx = pool.begin().await?;
+        if let Some(position) = position {
+            self.position = MODULES.move_to(&mut tx, &self.course_id, &self.id, self.position, position).await?;
+        }
+        sqlx::query("UPDATE course_modules SET title = ?, description = ? WHERE id = ?")
+            .bind(&self.title)
+            .bind(&self.description)
+            .bind(&self.id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await
+    }
+
+    /// Delete the module with its lessons and close the gap it leaves
+    pub async fn delete(&self, pool: &SqlitePool) -> Result<(), sqlx::Error> {
+        let mut tx = pool.begin().await?;
+        sqlx::query("DELETE FROM course_modules WHERE id = ?").bind(&self.id).execute(&mut *tx).await?;
+        MODULES.close(&mut tx, &self.course_id, self.position).await?;
+        tx.commit().await
+    }
+
+    pub async fn find(pool: &SqlitePool, id: &str) -> Result<Option<Self>, sqlx::Error> {
+        let row = sqlx::query("SELECT id, course_id, position, title, description FROM course_modules WHERE id = ?")
+            .bind(id)
+            .fetch_optional(pool)
+            .await?;
+        row.map(|row| Self::from_row(&row)).transpose()
+    }
+
+    /// Modules of a course in order
+    pub async fn for_course(pool: &SqlitePool, course_id: &str) -> Result<Vec<Self>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT id, course_id, position, title, description FROM course_modules
+             WHERE course_id = ? ORDER BY position",
+        )
+        .bind(course_id)
+        .fetch_all(pool)
+        .await?;
+        rows.iter().map(Self::from_row).collect()
+    }
+
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            id: row.try_get("id")?,
+            course_id: row.try_get("course_id")?,
+            position: row.try_get("position")?,
+            title: row.try_get("title")?,
+            description: row.try_get("description")?,
+        })
+    }
+}
+
+impl Lesson {
+    /// Insert at `position`, or at the end, moving later lessons down.
+    /// `self.position` is updated to where the lesson landed.
+    pub async fn insert(&mut self, pool: &SqlitePool, position: Option<u32>) -> Result<(), sqlx::Error> {
+        let mut tx = pool.begin().await?;
+        self.position = LESSONS.open(&mut tx, &self.module_id, position).await?;
+        sqlx::query("INSERT INTO lessons (id, module_id, position, title, items) VALUES (?, ?, ?, ?, ?)")
+            .bind(&self.id)
+            .bind(&self.module_id)
+            .bind(self.position)
+            .bind(&self.title)
+            .bind(serde_json::to_string(&self.items).unwrap_or_default())
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await
+    }
+
+    /// Save a changed title or items, and move the lesson when `position`
+    /// is given
+    pub async fn update(&mut self, pool: &SqlitePool, position: Option<u32>) -> Result<(), sqlx::Error> {
+        let mut tx = pool.begin().await?;
+        if let Some(position) = position {
+            self.position = LESSONS.move_to(&mut tx, &self.module_id, &self.id, self.position, position).await?;
+        }
+        sqlx::query("UPDATE lessons SET title = ?, items = ? WHERE id = ?")
+            .bind(&self.title)
+            .bind(serde_json::to_string(&self.items).unwrap_or_default())
+            .bind(&self.id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await
+    }
+
+    /// Delete the lesson and close the gap it leaves
+    pub async fn delete(&self, pool: &SqlitePool) -> Result<(), sqlx::Error> {
+        let mut tx = pool.begin().await?;
+        sqlx::query("DELETE FROM lessons WHERE id = ?").bind(&self.id).execute(&mut *tx).await?;
+        LESSONS.close(&mut tx, &self.module_id, self.position).await?;
+        tx.commit().await
+    }
+
+    pub async fn find(pool: &SqlitePool, id: &str) -> Result<Option<Self>, sqlx::Error> {
+        let row = sqlx::query("SELECT id, module_id, position, title, items FROM lessons WHERE id = ?")
+            .bind(id)
+            .fetch_optional(pool)
+            .await?;
+        row.map(|row| Self::from_row(&row)).transpose()
+    }
+
+    /// Lessons of every module of a course, ordered by module then position
+    pub async fn for_course(pool: &SqlitePool, course_id: &str) -> Result<Vec<Self>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT l.id, l.module_id, l.position, l.title, l.items
+             FROM lessons l JOIN course_modules m ON m.id = l.module_id
+             WHERE m.course_id = ? ORDER BY m.position, l.position",
+        )
+        .bind(course_id)
+        .fetch_all(pool)
+        .await?;
+        rows.iter().map(Self::from_row).collect()
+    }
+
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Self, sqlx::Error> {
+        let items: String = row.try_get("items")?;
+        Ok(Self {
+            id: row.try_get("id")?,
+            module_id: row.try_get("module_id")?,
+            position: row.try_get("position")?,
+            title: row.try_get("title")?,
+            items: serde_json::from_str(&items).map_err(|e| sqlx::Error::ColumnDecode {
+                index: "items".to_string(),
+                source: Box::new(e),
+            })?,
+        })
+    }
+}
+
+/// Rows ordered within a parent (modules in a course, lessons in a module),
+/// kept at positions 0, 1, 2, ... without gaps
+struct Sequence {
+    table: &'static str,
+    parent: &'static str,
+}
+
+const MODULES: Sequence = Sequence {
+    table: "course_modules",
+    parent: "course_id",
+};
+
+const LESSONS: Sequence = Sequence {
+    table: "lessons",
+    parent: "module_id",
+};
+
+impl Sequence {
+    /// Make room at `position`, or at the end when it is `None` or past the
+    /// end, and return the position made free
+    async fn open(&self, tx: &mut SqliteConnection, parent_id: &str, position: Option<u32>) -> Result<u32, sqlx::Error> {
+        let count: i64 = sqlx::query_scalar(&format!(
+            "SELECT COUNT(*) FROM {} WHERE {} = ? AND position >= 0",
+            self.table, self.parent
+        ))
+        .bind(parent_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        let count = count as u32;
+        let position = position.map_or(count, |p| p.min(count));
+        sqlx::query(&format!(
+            "UPDATE {} SET position = position + 1 WHERE {} = ? AND position >= ?",
+            self.table, self.parent
+        ))
+        .bind(parent_id)
+        .bind(position)
+        .execute(&mut *tx)
+        .await?;
+        Ok(position)
+    }
+
+    /// Move everything after `position` up by one
+    async fn close(&self, tx: &mut SqliteConnection, parent_id: &str, position: u32) -> Result<(), sqlx::Error> {
+        sqlx::query(&format!(
+            "UPDATE {} SET position = position - 1 WHERE {} = ? AND position > ?",
+            self.table, self.parent
+        ))
+        .bind(parent_id)
+        .bind(position)
+        .execute(&mut *tx)
+        .await?;
+        Ok(())
+    }
+
+    /// Move row `id` from `from` to `to` and return where it landed
+    async fn move_to(
+        &self,
+        tx: &mut SqliteConnection,
+        parent_id: &str,
+        id: &str,
+        from: u32,
+        to: u32,
+    ) -> Result<u32, sqlx::Error> {
+        // Park the row outside the sequence while the others shift
+        sqlx::query(&format!("UPDATE {} SET position = -1 WHERE id = ?", self.table))
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        self.close(tx, parent_id, from).await?;
+        let position = self.open(tx, parent_id, Some(to)).await?;
+        sqlx::query(&format!("UPDATE {} SET position = ? WHERE id = ?", self.table))
+            .bind(position)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        Ok(position)
+    }
+}
@@ -0,0 +1,147 @@
+// Custom simulation models
+// A custom simulation is a script f(parameters, x) a user wrote, sampled
+// along x with sliders for its parameters. Only its author sees and runs it.
+
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+
+use crate::simulations::catalog::ParameterDefinition;
+
+/// A user's scripted simulation
+#[derive(Serialize)]
+pub struct CustomSimulation {
+    pub id: String,
+    pub owner_id: String,
+    pub name: String,
+    pub description: String,
+    /// Sliders the script reads by name
+    pub parameters: Vec<ParameterDefinition>,
+    /// Statements computing f; see `services::script`
+    pub script: String,
+    /// Range of x the script is sampled over
+    pub domain: Domain,
+    /// What f is
+    pub output: Output,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Evenly spaced values of x from `min` to `max`
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Domain {
+    pub label: String,
+    /// Symbol from `/api/v1/units`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unit: Option<String>,
+    pub min: f64,
+    pub max: f64,
+    pub points: usize,
+}
+
+/// Label and unit of the script's value
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Output {
+    pub label: String,
+    /// Symbol from `/api/v1/units`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unit: Option<String>,
+}
+
+impl CustomSimulation {
+    pub async fn insert(&self, pool: &SqlitePool) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO custom_simulations (id, owner_id, name, description, parameters, script, domain, output,
+                 created_at, updated_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&self.id)
+        .bind(&self.owner_id)
+        .bind(&self.name)
+        .bind(&self.description)
+        .bind(serde_json::to_string(&self.parameters).unwrap_or_default())
+        .bind(&self.script)
+        .bind(serde_json::to_string(&self.domain).unwrap_or_default())
+        .bind(serde_json::to_string(&self.output).unwrap_or_default())
+        .bind(self.created_at)
+        .bind(self.updated_at)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Save a changed definition
+    pub async fn update(&self, pool: &SqlitePool) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "UPDATE custom_simulations SET name = ?, description = ?, parameters = ?, script = ?, domain = ?,
+                 output = ?, updated_at = ?
+             WHERE id = ?",
+        )
+        .bind(&self.name)
+        .bind(&self.description)
+        .bind(serde_json::to_string(&self.parameters).unwrap_or_default())
+        .bind(&self.script)
+        .bind(serde_json::to_string(&self.domain).unwrap_or_default())
+        .bind(serde_json::to_string(&self.output).unwrap_or_default())
+        .bind(self.updated_at)
+        .bind(&self.id)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn delete(pool: &SqlitePool, id: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM custom_simulations WHERE id = ?").bind(id).execute(pool).await?;
+        Ok(())
+    }
+
+    pub async fn find(pool: &SqlitePool, id: &str) -> Result<Option<Self>, sqlx::Error> {
+        let row = sqlx::query(&format!("{COLUMNS} WHERE id = ?")).bind(id).fetch_optional(pool).await?;
+        row.as_ref().map(Self::from_row).transpose()
+    }
+
+    /// A user's custom simulations, most recently updated first
+    pub async fn owned_by(pool: &SqlitePool, owner_id: &str) -> Result<Vec<Self>, sqlx::Error> {
+        let rows = sqlx::query(&format!("{COLUMNS} WHERE owner_id = ? ORDER BY updated_at DESC"))
+            .bind(owner_id)
+            .fetch_all(pool)
+            .await?;
+        rows.iter().map(Self::from_row).collect()
+    }
+
+    /// How many custom simulations a user has
+    pub async fn count_owned_by(pool: &SqlitePool, owner_id: &str) -> Result<i64, sqlx::Error> {
+        sqlx::query("SELECT COUNT(*) FROM custom_simulations WHERE owner_id = ?")
+            .bind(owner_id)
+            .fetch_one(pool)
+            .await?
+            .try_get(0)
+    }
+
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            id: row.try_get("id")?,
+            owner_id: row.try_get("owner_id")?,
+            name: row.try_get("name")?,
+            description: row.try_get("description")?,
+            parameters: json_column(row, "parameters")?,
+            script: row.try_get("script")?,
+            domain: json_column(row, "domain")?,
+            output: json_column(row, "output")?,
+            created_at: row.try_get("created_at")?,
+            updated_at: row.try_get("updated_at")?,
+        })
+    }
+}
+
+const COLUMNS: &str = "SELECT id, owner_id, name, description, parameters, script, domain, output, created_at,
+     updated_at FROM custom_simulations";
+
+pub(crate) fn json_column<T: DeserializeOwned>(row: &sqlx::sqlite::SqliteRow, column: &str) -> Result<T, sqlx::Error> {
+    let text: String = row.try_get(column)?;
+    serde_json::from_str(&text).map_err(|e| sqlx::Error::ColumnDecode {
+        index: column.to_string(),
+        source: Box::new(e),
+    })
+}
@@ -0,0 +1,37 @@
+// Stored embeddings
+// Vectors the embeddings API returned for catalog text, so it is only asked
+// to embed text once per model
+
+use chrono::Utc;
+use sqlx::{Row, SqlitePool};
+
+/// Vector stored for a text under `model`
+pub async fn find(pool: &SqlitePool, model: &str, text_sha256: &str) -> Result<Option<Vec<f32>>, sqlx::Error> {
+    let row = sqlx::query("SELECT vector FROM embeddings WHERE model = ? AND text_sha256 = ?")
+        .bind(model)
+        .bind(text_sha256)
+        .fetch_optional(pool)
+        .await?;
+    let Some(row) = row else {
+        return Ok(None);
+    };
+    let bytes: Vec<u8> = row.try_get("vector")?;
+    Ok(Some(
+        bytes.chunks_exact(4).map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])).collect(),
+    ))
+}
+
+pub async fn insert(pool: &SqlitePool, model: &str, text_sha256: &str, vector: &[f32]) -> Result<(), sqlx::Error> {
+    let bytes: Vec<u8> = vector.iter().flat_map(|value| value.to_le_bytes()).collect();
+    sqlx::query(
+        "INSERT OR REPLACE INTO embeddings (model, text_sha256, vector, created_at)
+         VALUES (?, ?, ?, ?)",
+    )
+    .bind(model)
+    .bind(text_sha256)
+    .bind(bytes)
+    .bind(Utc::now())
+    .execute(pool)
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,48 @@
+// Background jobs saved at shutdown to run after the restart
+
+use serde_json::{Map, Value};
+use sqlx::{Row, SqlitePool};
+
+/// A job that had not finished when the server stopped
+pub struct PendingJob {
+    pub id: String,
+    pub simulation_id: String,
+    /// Validated parameter values, defaults included
+    pub parameters: Map<String, Value>,
+    pub created_at: String,
+}
+
+impl PendingJob {
+    pub async fn insert(&self, pool: &SqlitePool) -> Result<(), sqlx::Error> {
+        sqlx::query("INSERT OR REPLACE INTO pending_jobs (id, simulation_id, parameters, created_at) VALUES (?, ?, ?, ?)")
+            .bind(&self.id)
+            .bind(&self.simulation_id)
+            .bind(Value::Object(self.parameters.clone()).to_string())
+            .bind(&self.created_at)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Every saved job, oldest first, removing them from the table
+    pub async fn take_all(pool: &SqlitePool) -> Result<Vec<Self>, sqlx::Error> {
+        let mut tx = pool.begin().await?;
+        let rows = sqlx::query("SELECT id, simulation_id, parameters, created_at FROM pending_jobs ORDER BY created_at")
+            .fetch_all(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM pending_jobs").execute(&mut *tx).await?;
+        tx.commit().await?;
+
+        rows.iter()
+            .map(|row| {
+                let parameters: String = row.try_get("parameters")?;
+                Ok(Self {
+                    id: row.try_get("id")?,
+                    simulation_id: row.try_get("simulation_id")?,
+                    parameters: serde_json::from_str(&parameters).unwrap_or_default(),
+                    created_at: row.try_get("created_at")?,
+                })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,127 @@
+// Class leaderboard models
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
+
+/// What students can be ranked by
+pub const RANK_BY: [&str; 2] = ["xp", "completions"];
+
+/// A teacher's choices for their class's leaderboard
+#[derive(Serialize)]
+pub struct LeaderboardSettings {
+    #[serde(skip)]
+    pub class_id: String,
+    /// Whether students can see the leaderboard
+    pub enabled: bool,
+    /// Show students to each other under pseudonyms
+    pub anonymize: bool,
+    /// One of `RANK_BY`
+    pub rank_by: String,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// An opted-in student with the totals they are ranked by
+pub struct Participant {
+    pub user_id: String,
+    pub display_name: String,
+    pub xp: u32,
+    pub completions: u32,
+}
+
+impl LeaderboardSettings {
+    /// Settings of a class whose teacher has not set any: off, and
+    /// anonymized once turned on
+    pub fn off(class_id: &str) -> Self {
+        Self {
+            class_id: class_id.to_string(),
+            enabled: false,
+            anonymize: true,
+            rank_by: RANK_BY[0].to_string(),
+            updated_at: None,
+        }
+    }
+
+    pub async fn find(pool: &SqlitePool, class_id: &str) -> Result<Option<Self>, sqlx::Error> {
+        let row = sqlx::query(
+            "SELECT class_id, enabled, anonymize, rank_by, updated_at FROM class_leaderboards WHERE class_id = ?",
+        )
+        .bind(class_id)
+        .fetch_optional(pool)
+        .await?;
+        row.map(|row| {
+            Ok(Self {
+                class_id: row.try_get("class_id")?,
+                enabled: row.try_get("enabled")?,
+                anonymize: row.try_get("anonymize")?,
+                rank_by: row.try_get("rank_by")?,
+                updated_at: row.try_get("updated_at")?,
+            })
+        })
+        .transpose()
+    }
+
+    pub async fn save(&self, pool: &SqlitePool) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT OR REPLACE INTO class_leaderboards (class_id, enabled, anonymize, rank_by, updated_at)
+             VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(&self.class_id)
+        .bind(self.enabled)
+        .bind(self.anonymize)
+        .bind(&self.rank_by)
+        .bind(self.updated_at)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+}
+
+/// Put a student on their class's leaderboard; opting in twice is harmless
+pub async fn opt_in(pool: &SqlitePool, class_id: &str, user_id: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT OR IGNORE INTO leaderboard_entries (class_id, user_id, opted_in_at) VALUES (?, ?, ?)")
+        .bind(class_id)
+        .bind(user_id)
+        .bind(Utc::now())
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Take a student off the leaderboard and report whether they were on it
+pub async fn opt_out(pool: &SqlitePool, class_id: &str, user_id: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM leaderboard_entries WHERE class_id = ? AND user_id = ?")
+        .bind(class_id)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Enrolled students who opted in, with their XP from activity and badges
+/// and their completed simulations
+pub async fn participants(pool: &SqlitePool, class_id: &str) -> Result<Vec<Participant>, sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT u.id, u.display_name,
+                COALESCE((SELECT SUM(a.xp) FROM activity_days a WHERE a.user_id = u.id), 0)
+                    + COALESCE((SELECT SUM(b.xp) FROM user_badges b WHERE b.user_id = u.id), 0) AS xp,
+                (SELECT COUNT(*) FROM simulation_progress p WHERE p.user_id = u.id AND p.completed) AS completions
+         FROM leaderboard_entries e
+         JOIN class_members m ON m.class_id = e.class_id AND m.user_id = e.user_id
+         JOIN users u ON u.id = e.user_id
+         WHERE e.class_id = ?",
+    )
+    .bind(class_id)
+    .fetch_all(pool)
+    .await?;
+    rows.iter()
+        .map(|row| {
+            Ok(Participant {
+                user_id: row.try_get("id")?,
+                display_name: row.try_get("display_name")?,
+                xp: row.try_get("xp")?,
+                completions: row.try_get("completions")?,
+            })
+        })
+        .collect()
+}
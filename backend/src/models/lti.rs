@@ -0,0 +1,132 @@
+// LTI models
+// Map LMS users and courses onto our accounts and classes, and remember the
+// gradebook columns launches told us to report scores to
+
+use chrono::Utc;
+use sqlx::{Row, SqlitePool};
+
+/// A gradebook column on an LMS that receives a learner's quiz scores for
+/// one simulation
+pub struct GradeLink {
+    pub user_id: String,
+    pub simulation_id: String,
+    pub issuer: String,
+    /// Our client ID in the registration the launch came through; absent on
+    /// links saved before it was recorded
+    pub client_id: Option<String>,
+    /// The learner's ID on the platform
+    pub subject: String,
+    /// AGS line item URL
+    pub lineitem: String,
+}
+
+/// The account linked to a platform user, if any
+pub async fn find_identity(pool: &SqlitePool, issuer: &str, subject: &str) -> Result<Option<String>, sqlx::Error> {
+    let row = sqlx::query("SELECT user_id FROM lti_identities WHERE issuer = ? AND subject = ?")
+        .bind(issuer)
+        .bind(subject)
+        .fetch_optional(pool)
+        .await?;
+    row.map(|row| row.try_get("user_id")).transpose()
+}
+
+pub async fn link_identity(pool: &SqlitePool, issuer: &str, subject: &str, user_id: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT OR IGNORE INTO lti_identities (issuer, subject, user_id, created_at) VALUES (?, ?, ?, ?)")
+        .bind(issuer)
+        .bind(subject)
+        .bind(user_id)
+        .bind(Utc::now())
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// The class standing in for a platform course, if any
+pub async fn find_context(pool: &SqlitePool, issuer: &str, context_id: &str) -> Result<Option<String>, sqlx::Error> {
+    let row = sqlx::query("SELECT class_id FROM lti_contexts WHERE issuer = ? AND context_id = ?")
+        .bind(issuer)
+        .bind(context_id)
+        .fetch_optional(pool)
+        .await?;
+    row.map(|row| row.try_get("class_id")).transpose()
+}
+
+pub async fn link_context(pool: &SqlitePool, issuer: &str, context_id: &str, class_id: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT OR REPLACE INTO lti_contexts (issuer, context_id, class_id) VALUES (?, ?, ?)")
+        .bind(issuer)
+        .bind(context_id)
+        .bind(class_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+impl GradeLink {
+    /// Save the link, or refresh it when the learner launches again
+    pub async fn upsert(&self, pool: &SqlitePool) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO lti_grade_links (user_id, simulation_id, issuer, client_id, subject, lineitem, updated_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT (user_id, simulation_id, lineitem) DO UPDATE SET
+                 issuer = excluded.issuer,
+                 client_id = excluded.client_id,
+                 subject = excluded.subject,
+                 updated_at = excluded.updated_at",
+        )
+        .bind(&self.user_id)
+        .bind(&self.simulation_id)
+        .bind(&self.issuer)
+        .bind(&self.client_id)
+        .bind(&self.subject)
+        .bind(&self.lineitem)
+        .bind(Utc::now())
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Claim the gradebook columns for a learner's simulation that have not
+    /// been sent a score, so of two attempts submitted at once only one is
+    /// posted
+    pub async fn claim_unscored(
+        pool: &SqlitePool,
+        user_id: &str,
+        simulation_id: &str,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        let rows = sqlx::query(
+            "UPDATE lti_grade_links SET scored_at = ?
+             WHERE user_id = ? AND simulation_id = ? AND scored_at IS NULL
+             RETURNING user_id, simulation_id, issuer, client_id, subject, lineitem",
+        )
+        .bind(Utc::now())
+        .bind(user_id)
+        .bind(simulation_id)
+        .fetch_all(pool)
+        .await?;
+        rows.iter()
+            .map(|row| {
+                Ok(Self {
+                    user_id: row.try_get("user_id")?,
+                    simulation_id: row.try_get("simulation_id")?,
+                    issuer: row.try_get("issuer")?,
+                    client_id: row.try_get("client_id")?,
+                    subject: row.try_get("subject")?,
+                    lineitem: row.try_get("lineitem")?,
+                })
+            })
+            .collect()
+    }
+    /// Give up a claimed column after posting its score failed, so the next
+    /// attempt is posted instead
+    pub async fn release(&self, pool: &SqlitePool) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "UPDATE lti_grade_links SET scored_at = NULL WHERE user_id = ? AND simulation_id = ? AND lineitem = ?",
+        )
+        .bind(&self.user_id)
+        .bind(&self.simulation_id)
+        .bind(&self.lineitem)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+}
@@ -4,3 +4,21 @@
 pub mod simulation;
 pub mod user;
 pub mod progress;
+pub mod quiz;
+pub mod topic;
+pub mod course;
+pub mod classroom;
+pub mod assignment;
+pub mod lti;
+pub mod translation;
+pub mod content;
+pub mod pin;
+pub mod attestation;
+pub mod job;
+pub mod custom_simulation;
+pub mod contribution;
+pub mod search;
+pub mod embedding;
+pub mod achievement;
+pub mod leaderboard;
+pub mod bookmark;
@@ -0,0 +1,67 @@
+// IPFS pins of result bundles
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
+
+/// A result's reproducibility bundle as pinned to IPFS
+#[derive(Serialize, Clone)]
+pub struct ResultPin {
+    pub result_id: String,
+    /// Content identifier of the bundle
+    pub cid: String,
+    /// SHA-256 of the bundle, checked when it is fetched back
+    pub sha256: String,
+    /// Bundle size (bytes)
+    pub size: i64,
+    #[serde(skip)]
+    pub pinned_by: String,
+    pub pinned_at: DateTime<Utc>,
+}
+
+impl ResultPin {
+    pub async fn insert(&self, pool: &SqlitePool) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO result_pins (result_id, cid, sha256, size, pinned_by, pinned_at)
+             VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&self.result_id)
+        .bind(&self.cid)
+        .bind(&self.sha256)
+        .bind(self.size)
+        .bind(&self.pinned_by)
+        .bind(self.pinned_at)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn for_result(pool: &SqlitePool, result_id: &str) -> Result<Option<Self>, sqlx::Error> {
+        let row = sqlx::query(&format!("{COLUMNS} WHERE result_id = ?"))
+            .bind(result_id)
+            .fetch_optional(pool)
+            .await?;
+        row.as_ref().map(Self::from_row).transpose()
+    }
+
+    pub async fn for_cid(pool: &SqlitePool, cid: &str) -> Result<Option<Self>, sqlx::Error> {
+        let row = sqlx::query(&format!("{COLUMNS} WHERE cid = ? ORDER BY pinned_at LIMIT 1"))
+            .bind(cid)
+            .fetch_optional(pool)
+            .await?;
+        row.as_ref().map(Self::from_row).transpose()
+    }
+
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            result_id: row.try_get("result_id")?,
+            cid: row.try_get("cid")?,
+            sha256: row.try_get("sha256")?,
+            size: row.try_get("size")?,
+            pinned_by: row.try_get("pinned_by")?,
+            pinned_at: row.try_get("pinned_at")?,
+        })
+    }
+}
+
+const COLUMNS: &str = "SELECT result_id, cid, sha256, size, pinned_by, pinned_at FROM result_pins";
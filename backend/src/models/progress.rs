@@ -1 +1,243 @@
 // Progress models
+
+use chrono::{DateTime, Utc};
+use sqlx::{Row, SqlitePool};
+
+/// One learner's record for one simulation
+pub struct SimulationProgress {
+    pub user_id: String,
+    pub simulation_id: String,
+    pub completed: bool,
+    /// Best score reported so far
+    pub score: Option<f64>,
+    /// Accumulated across all reports
+    pub time_spent_minutes: u32,
+    pub last_parameters: Option<serde_json::Value>,
+    pub started_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl SimulationProgress {
+    /// Merge a progress report into the stored record. Completion is sticky,
+    /// the best score is kept and time spent accumulates.
+    pub async fn record(&self, pool: &SqlitePool) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO simulation_progress (user_id, simulation_id, completed, score,
+                 time_spent_minutes, last_parameters, started_at, completed_at, updated_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT (user_id, simulation_id) DO UPDATE SET
+                 completed = MAX(completed, excluded.completed),
+                 score = CASE
+                     WHEN excluded.score IS NULL THEN score
+                     WHEN score IS NULL THEN excluded.score
+                     ELSE MAX(score, excluded.score)
+                 END,
+                 time_spent_minutes = time_spent_minutes + excluded.time_spent_minutes,
+                 last_parameters = COALESCE(excluded.last_parameters, last_parameters),
+                 completed_at = COALESCE(completed_at, excluded.completed_at),
+                 updated_at = excluded.updated_at",
+        )
+        .bind(&self.user_id)
+        .bind(&self.simulation_id)
+        .bind(self.completed)
+        .bind(self.score)
+        .bind(self.time_spent_minutes)
+        .bind(self.last_parameters.as_ref().map(|p| p.to_string()))
+        .bind(self.started_at)
+        .bind(self.completed_at)
+        .bind(self.updated_at)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// All records for a user, most recently updated first
+    pub async fn for_user(pool: &SqlitePool, user_id: &str) -> Result<Vec<Self>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT user_id, simulation_id, completed, score, time_spent_minutes,
+                    last_parameters, started_at, completed_at, updated_at
+             FROM simulation_progress WHERE user_id = ?
+             ORDER BY updated_at DESC",
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
+
+        rows.iter().map(Self::from_row).collect()
+    }
+
+    /// Records of every student enrolled in a class
+    pub async fn for_class(pool: &SqlitePool, class_id: &str) -> Result<Vec<Self>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT p.user_id, p.simulation_id, p.completed, p.score, p.time_spent_minutes,
+                    p.last_parameters, p.started_at, p.completed_at, p.updated_at
+             FROM simulation_progress p JOIN class_members m ON m.user_id = p.user_id
+             WHERE m.class_id = ?
+             ORDER BY p.simulation_id",
+        )
+        .bind(class_id)
+        .fetch_all(pool)
+        .await?;
+        rows.iter().map(Self::from_row).collect()
+    }
+
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Self, sqlx::Error> {
+        let last_parameters: Option<String> = row.try_get("last_parameters")?;
+        Ok(Self {
+            user_id: row.try_get("user_id")?,
+            simulation_id: row.try_get("simulation_id")?,
+            completed: row.try_get("completed")?,
+            score: row.try_get("score")?,
+            time_spent_minutes: row.try_get("time_spent_minutes")?,
+            last_parameters: last_parameters
+                .map(|text| serde_json::from_str(&text))
+                .transpose()
+                .map_err(|e| sqlx::Error::ColumnDecode {
+                    index: "last_parameters".to_string(),
+                    source: Box::new(e),
+                })?,
+            started_at: row.try_get("started_at")?,
+            completed_at: row.try_get("completed_at")?,
+            updated_at: row.try_get("updated_at")?,
+        })
+    }
+}
+
+/// One graded quiz submission by a signed-in learner
+pub struct QuizAttempt {
+    pub id: String,
+    pub user_id: String,
+    pub simulation_id: String,
+    /// Percentage of questions answered correctly
+    pub score: f64,
+    pub correct: u32,
+    pub total: u32,
+    pub submitted_at: DateTime<Utc>,
+}
+
+/// A learner's quiz attempts on one simulation, summarized
+pub struct QuizSummary {
+    pub user_id: String,
+    pub simulation_id: String,
+    pub best_score: f64,
+    pub attempts: u32,
+    pub last_submitted_at: DateTime<Utc>,
+}
+
+impl QuizAttempt {
+    pub async fn insert(&self, pool: &SqlitePool) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO quiz_attempts (id, user_id, simulation_id, score, correct, total, submitted_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&self.id)
+        .bind(&self.user_id)
+        .bind(&self.simulation_id)
+        .bind(self.score)
+        .bind(self.correct)
+        .bind(self.total)
+        .bind(self.submitted_at)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Best score and attempt count per simulation for one learner
+    pub async fn summaries_for_user(pool: &SqlitePool, user_id: &str) -> Result<Vec<QuizSummary>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT user_id, simulation_id, MAX(score) AS best_score, COUNT(*) AS attempts,
+                    MAX(submitted_at) AS last_submitted_at
+             FROM quiz_attempts WHERE user_id = ?
+             GROUP BY simulation_id",
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
+        rows.iter().map(QuizSummary::from_row).collect()
+    }
+
+    /// Best score and attempt count per student and simulation in a class
+    pub async fn summaries_for_class(pool: &SqlitePool, class_id: &str) -> Result<Vec<QuizSummary>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT a.user_id, a.simulation_id, MAX(a.score) AS best_score, COUNT(*) AS attempts,
+                    MAX(a.submitted_at) AS last_submitted_at
+             FROM quiz_attempts a JOIN class_members m ON m.user_id = a.user_id
+             WHERE m.class_id = ?
+             GROUP BY a.user_id, a.simulation_id",
+        )
+        .bind(class_id)
+        .fetch_all(pool)
+        .await?;
+        rows.iter().map(QuizSummary::from_row).collect()
+    }
+}
+
+impl QuizSummary {
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            user_id: row.try_get("user_id")?,
+            simulation_id: row.try_get("simulation_id")?,
+            best_score: row.try_get("best_score")?,
+            attempts: row.try_get("attempts")?,
+            last_submitted_at: row.try_get("last_submitted_at")?,
+        })
+    }
+}
+
+/// How far up a quiz question's hint ladder a learner has gone
+pub struct HintUsage {
+    pub question_id: String,
+    pub revealed: u32,
+}
+
+impl HintUsage {
+    /// Reveal the next of a question's `available` hints and return how
+    /// many the learner has now seen; at the top of the ladder it stays
+    /// there
+    pub async fn reveal(
+        pool: &SqlitePool,
+        user_id: &str,
+        simulation_id: &str,
+        question_id: &str,
+        available: u32,
+    ) -> Result<u32, sqlx::Error> {
+        let now = Utc::now();
+        sqlx::query(
+            "INSERT INTO quiz_hints (user_id, simulation_id, question_id, revealed, first_revealed_at,
+                 last_revealed_at)
+             VALUES (?, ?, ?, 1, ?, ?)
+             ON CONFLICT (user_id, simulation_id, question_id)
+             DO UPDATE SET revealed = MIN(revealed + 1, ?), last_revealed_at = excluded.last_revealed_at
+             RETURNING revealed",
+        )
+        .bind(user_id)
+        .bind(simulation_id)
+        .bind(question_id)
+        .bind(now)
+        .bind(now)
+        .bind(available)
+        .fetch_one(pool)
+        .await?
+        .try_get("revealed")
+    }
+
+    /// Hints a learner has revealed on each question of a simulation's quiz
+    pub async fn for_quiz(pool: &SqlitePool, user_id: &str, simulation_id: &str) -> Result<Vec<Self>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT question_id, revealed FROM quiz_hints WHERE user_id = ? AND simulation_id = ? ORDER BY question_id",
+        )
+        .bind(user_id)
+        .bind(simulation_id)
+        .fetch_all(pool)
+        .await?;
+        rows.iter()
+            .map(|row| {
+                Ok(Self {
+                    question_id: row.try_get("question_id")?,
+                    revealed: row.try_get("revealed")?,
+                })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,260 @@
+// Quiz models
+// Answer keys are skipped when serializing so a quiz can be sent to clients as is
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::{Map, Value};
+
+/// Credit a correct answer loses for each hint revealed on its question
+pub const HINT_PENALTY: f64 = 0.25;
+
+/// A short quiz tied to a simulation's theory section
+#[derive(Serialize)]
+pub struct Quiz {
+    pub simulation_id: String,
+    pub title: String,
+    pub questions: Vec<Question>,
+}
+
+#[derive(Serialize)]
+pub struct Question {
+    pub id: String,
+    pub prompt: String,
+    #[serde(flatten)]
+    pub answer: Answer,
+    /// Shown to the learner after a correct answer
+    #[serde(skip_serializing)]
+    pub explanation: String,
+    /// Two or three nudges, each giving away more than the last, revealed
+    /// one at a time on request. Only their number is sent with the quiz.
+    #[serde(rename = "hint_count", serialize_with = "count")]
+    pub hints: Vec<String>,
+}
+
+/// Share of a question's point a correct answer earns after `hints_used`
+/// hints
+pub fn credit(hints_used: u32) -> f64 {
+    (1.0 - HINT_PENALTY * f64::from(hints_used)).max(0.0)
+}
+
+fn count<S: Serializer>(hints: &[String], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u64(hints.len() as u64)
+}
+
+/// How a question is answered, with its hidden answer key
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Answer {
+    /// Answered with the index of one of `choices`
+    MultipleChoice {
+        choices: Vec<String>,
+        #[serde(skip_serializing)]
+        correct: usize,
+    },
+    /// Answered with a number, accepted within a relative tolerance
+    Numeric {
+        unit: String,
+        #[serde(skip_serializing)]
+        value: f64,
+        /// Allowed relative error, e.g. 0.02 for ±2%
+        #[serde(skip_serializing)]
+        tolerance: f64,
+    },
+}
+
+impl Question {
+    pub fn multiple_choice(id: &str, prompt: &str, choices: &[&str], correct: usize, explanation: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            prompt: prompt.to_string(),
+            answer: Answer::MultipleChoice {
+                choices: choices.iter().map(|c| c.to_string()).collect(),
+                correct,
+            },
+            explanation: explanation.to_string(),
+            hints: Vec::new(),
+        }
+    }
+
+    pub fn numeric(id: &str, prompt: &str, unit: &str, value: f64, tolerance: f64, explanation: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            prompt: prompt.to_string(),
+            answer: Answer::Numeric {
+                unit: unit.to_string(),
+                value,
+                tolerance,
+            },
+            explanation: explanation.to_string(),
+            hints: Vec::new(),
+        }
+    }
+
+    /// The question with a hint ladder, least revealing first
+    pub fn with_hints(mut self, hints: &[&str]) -> Self {
+        self.hints = hints.iter().map(|h| h.to_string()).collect();
+        self
+    }
+
+    /// Grade one submitted answer, given how many hints the learner
+    /// revealed. Answers of the wrong type are incorrect. The answer and its
+    /// explanation are given only for a correct answer, so that a wrong
+    /// attempt does not hand out the key for the next one.
+    fn grade(&self, submitted: Option<&Value>, hints_used: u32) -> QuestionResult {
+        let (correct, expected) = match &self.answer {
+            Answer::MultipleChoice { correct, .. } => (
+                submitted.and_then(Value::as_u64) == Some(*correct as u64),
+                Value::from(*correct),
+            ),
+            Answer::Numeric { value, tolerance, .. } => (
+                submitted
+                    .and_then(Value::as_f64)
+                    .is_some_and(|answer| (answer - value).abs() <= tolerance * value.abs()),
+                Value::from(*value),
+            ),
+        };
+        QuestionResult {
+            question_id: self.id.clone(),
+            correct,
+            hints_used,
+            credit: if correct { credit(hints_used) } else { 0.0 },
+            submitted: submitted.cloned(),
+            expected: correct.then_some(expected),
+            explanation: correct.then(|| self.explanation.clone()),
+        }
+    }
+}
+
+/// Answers keyed by question ID: a choice index or a number
+#[derive(Deserialize)]
+pub struct Submission {
+    pub answers: Map<String, Value>,
+}
+
+#[derive(Serialize)]
+pub struct QuizResult {
+    pub simulation_id: String,
+    /// Percentage of the credit earned: each correct answer is worth one,
+    /// less `HINT_PENALTY` for each hint revealed on its question
+    pub score: f64,
+    pub correct: usize,
+    pub total: usize,
+    /// Hints revealed across the quiz
+    pub hints_used: u32,
+    pub results: Vec<QuestionResult>,
+}
+
+#[derive(Serialize)]
+pub struct QuestionResult {
+    pub question_id: String,
+    pub correct: bool,
+    /// Hints revealed on this question
+    pub hints_used: u32,
+    /// Share of the question's point earned, from 0 to 1
+    pub credit: f64,
+    pub submitted: Option<Value>,
+    /// The answer key; only for a correct answer
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected: Option<Value>,
+    /// Only for a correct answer
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<String>,
+}
+
+impl Quiz {
+    /// Grade a submission, given the hints revealed by question ID.
+    /// Unanswered questions count as incorrect; answers to questions that
+    /// are not in the quiz are reported by ID as an error.
+    pub fn grade(&self, submission: &Submission, hints: &HashMap<String, u32>) -> Result<QuizResult, Vec<String>> {
+        let unknown: Vec<String> = submission
+            .answers
+            .keys()
+            .filter(|id| !self.questions.iter().any(|q| &q.id == *id))
+            .cloned()
+            .collect();
+        if !unknown.is_empty() {
+            return Err(unknown);
+        }
+
+        let results: Vec<QuestionResult> = self
+            .questions
+            .iter()
+            .map(|q| q.grade(submission.answers.get(&q.id), hints.get(&q.id).copied().unwrap_or(0)))
+            .collect();
+        let correct = results.iter().filter(|r| r.correct).count();
+        let total = results.len();
+        let credit: f64 = results.iter().map(|r| r.credit).sum();
+        Ok(QuizResult {
+            simulation_id: self.simulation_id.clone(),
+            score: if total == 0 { 0.0 } else { 100.0 * credit / total as f64 },
+            correct,
+            total,
+            hints_used: results.iter().map(|r| r.hints_used).sum(),
+            results,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn quiz() -> Quiz {
+        Quiz {
+            simulation_id: "test".to_string(),
+            title: "Test".to_string(),
+            questions: vec![
+                Question::multiple_choice("choice", "Which?", &["a", "b"], 1, "Because b."),
+                Question::numeric("number", "How much?", "m", 2.0, 0.05, "Two metres."),
+            ],
+        }
+    }
+
+    fn submit(answers: Value) -> QuizResult {
+        let submission = Submission {
+            answers: answers.as_object().cloned().unwrap_or_default(),
+        };
+        quiz().grade(&submission, &HashMap::new()).unwrap_or_else(|_| panic!("unknown question"))
+    }
+
+    #[test]
+    fn wrong_and_missing_answers_do_not_reveal_the_key() {
+        let result = submit(json!({ "choice": 0 }));
+        assert_eq!(result.score, 0.0);
+        for question in &result.results {
+            assert!(question.expected.is_none() && question.explanation.is_none());
+        }
+        let serialized = serde_json::to_value(&result).unwrap_or_default();
+        assert!(serialized["results"][0].get("expected").is_none());
+    }
+
+    #[test]
+    fn correct_answers_come_back_with_the_key() {
+        let result = submit(json!({ "choice": 1, "number": 2.04 }));
+        assert_eq!(result.score, 100.0);
+        assert_eq!(result.results[0].expected, Some(json!(1)));
+        assert_eq!(result.results[1].explanation.as_deref(), Some("Two metres."));
+    }
+
+    #[test]
+    fn hints_reduce_credit() {
+        let submission = Submission {
+            answers: json!({ "choice": 1, "number": 2.0 }).as_object().cloned().unwrap_or_default(),
+        };
+        let hints = HashMap::from([("choice".to_string(), 2)]);
+        let result = quiz().grade(&submission, &hints).unwrap_or_else(|_| panic!("unknown question"));
+        assert_eq!(result.score, 75.0);
+        assert_eq!(result.hints_used, 2);
+    }
+
+    #[test]
+    fn unknown_questions_are_reported() {
+        let submission = Submission {
+            answers: json!({ "nope": 1 }).as_object().cloned().unwrap_or_default(),
+        };
+        assert_eq!(quiz().grade(&submission, &HashMap::new()).err(), Some(vec!["nope".to_string()]));
+    }
+}
@@ -0,0 +1,114 @@
+// Search index models
+// Rows of the full-text index over the catalog; `services::search` builds
+// them from the simulation content and turns queries into FTS5 expressions
+
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
+
+/// One indexed entry: a simulation or a glossary term
+pub struct SearchDocument {
+    /// `simulation` or `glossary`
+    pub kind: &'static str,
+    pub simulation_id: String,
+    /// Simulation name or glossary term
+    pub title: String,
+    pub topics: String,
+    /// Summary and description, or the term's definition
+    pub description: String,
+    /// Theory text without markup
+    pub body: String,
+}
+
+/// A ranked match
+#[derive(Serialize)]
+pub struct SearchHit {
+    /// `simulation` or `glossary`
+    pub kind: String,
+    /// The simulation, or the one whose theory defines the term
+    pub simulation_id: String,
+    pub title: String,
+    /// Best matching passage, or a glossary term's definition, as HTML with the matched words in `<mark>`;
+    /// the index returns them between `\u{2}` and `\u{3}` for the service
+    /// to escape and highlight
+    pub snippet: String,
+    /// BM25 relevance; higher is better
+    pub score: f64,
+}
+
+/// Weights of the title, topics, description and body columns in the BM25
+/// rank (the two unindexed columns come first)
+const RANK: &str = "bm25(search_index, 0.0, 0.0, 10.0, 5.0, 3.0, 1.0)";
+
+/// Replace the whole index in one transaction, so searches never see it
+/// half built
+pub async fn replace_all(pool: &SqlitePool, documents: &[SearchDocument]) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM search_index").execute(&mut *tx).await?;
+    for document in documents {
+        sqlx::query(
+            "INSERT INTO search_index (kind, simulation_id, title, topics, description, body)
+             VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(document.kind)
+        .bind(&document.simulation_id)
+        .bind(&document.title)
+        .bind(&document.topics)
+        .bind(&document.description)
+        .bind(&document.body)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await
+}
+
+/// Entries matching an FTS5 expression, optionally of one kind
+pub async fn count(pool: &SqlitePool, expression: &str, kind: Option<&str>) -> Result<i64, sqlx::Error> {
+    sqlx::query("SELECT COUNT(*) FROM search_index WHERE search_index MATCH ? AND (? IS NULL OR kind = ?)")
+        .bind(expression)
+        .bind(kind)
+        .bind(kind)
+        .fetch_one(pool)
+        .await?
+        .try_get(0)
+}
+
+/// One page of matches, most relevant first
+pub async fn search(
+    pool: &SqlitePool,
+    expression: &str,
+    kind: Option<&str>,
+    limit: usize,
+    offset: usize,
+) -> Result<Vec<SearchHit>, sqlx::Error> {
+    let rows = sqlx::query(&format!(
+        "SELECT kind, simulation_id, title,
+             CASE kind
+                 WHEN 'glossary' THEN snippet(search_index, 4, char(2), char(3), '…', 16)
+                 ELSE snippet(search_index, -1, char(2), char(3), '…', 16)
+             END AS snippet,
+             {RANK} AS rank
+         FROM search_index
+         WHERE search_index MATCH ? AND (? IS NULL OR kind = ?)
+         ORDER BY rank, simulation_id, title
+         LIMIT ? OFFSET ?"
+    ))
+    .bind(expression)
+    .bind(kind)
+    .bind(kind)
+    .bind(limit as i64)
+    .bind(offset as i64)
+    .fetch_all(pool)
+    .await?;
+    rows.iter()
+        .map(|row| {
+            let rank: f64 = row.try_get("rank")?;
+            Ok(SearchHit {
+                kind: row.try_get("kind")?,
+                simulation_id: row.try_get("simulation_id")?,
+                title: row.try_get("title")?,
+                snippet: row.try_get("snippet")?,
+                score: -rank,
+            })
+        })
+        .collect()
+}
@@ -1 +1,143 @@
 // Simulation models
+
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+
+use crate::physics::units::Dimension;
+use crate::services::attestation::Attestation;
+use crate::simulations::params::recorded_seed;
+use crate::simulations::Backend;
+
+/// Difficulty levels, easiest first
+pub const DIFFICULTIES: [&str; 3] = ["beginner", "intermediate", "advanced"];
+
+#[derive(Serialize)]
+pub struct SimulationInfo {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub difficulty: String,
+    pub estimated_time_minutes: u32,
+    pub topics: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct SimulationDetails {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub parameters: Vec<SimulationParameter>,
+    pub theory: String,
+}
+
+#[derive(Serialize)]
+pub struct SimulationParameter {
+    pub name: String,
+    pub label: String,
+    pub param_type: String,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    /// A number for sliders, a boolean for toggles, one of `options` for
+    /// selects, an array of numbers for curves, a formula's text
+    pub default: serde_json::Value,
+    pub step: Option<f64>,
+    /// Allowed values of a "select" parameter
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<Vec<String>>,
+    /// Unit a slider's value, range and step are in. Runs also accept values
+    /// in other units of the same dimension. A curve's points and a
+    /// formula's values are always in its unit.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unit: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dimension: Option<Dimension>,
+    /// Variables a "formula" parameter may use
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variables: Option<Vec<String>>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SimulationResult {
+    pub id: String,
+    pub simulation_id: String,
+    /// `Simulation::version` of the computation that produced the data
+    #[serde(default = "first_version")]
+    pub simulation_version: u32,
+    pub parameters: serde_json::Value,
+    pub data: serde_json::Value,
+    pub computed_at: String,
+    /// Seed of the random numbers of a stochastic simulation's run, also
+    /// among its parameters; passing it again reproduces the run exactly
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    /// This server's signature of the result; absent on results stored
+    /// before results were signed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attestation: Option<Attestation>,
+    /// Hardware the data was computed on
+    #[serde(default)]
+    pub compute_backend: Backend,
+}
+
+impl SimulationResult {
+    /// Store the result so it can be fetched again by ID
+    pub async fn insert(&self, pool: &SqlitePool) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO simulation_results (id, simulation_id, simulation_version, parameters, data, computed_at,
+                 attestation, compute_backend)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&self.id)
+        .bind(&self.simulation_id)
+        .bind(self.simulation_version)
+        .bind(self.parameters.to_string())
+        .bind(self.data.to_string())
+        .bind(&self.computed_at)
+        .bind(self.attestation.as_ref().and_then(|a| serde_json::to_string(a).ok()))
+        .bind(self.compute_backend.as_str())
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn find(pool: &SqlitePool, id: &str) -> Result<Option<Self>, sqlx::Error> {
+        let row = sqlx::query(
+            "SELECT id, simulation_id, simulation_version, parameters, data, computed_at, attestation, compute_backend
+             FROM simulation_results WHERE id = ?",
+        )
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+
+        row.map(|row| {
+            let parameters = parse_json_column(&row, "parameters")?;
+            Ok(Self {
+                id: row.try_get("id")?,
+                simulation_id: row.try_get("simulation_id")?,
+                simulation_version: row.try_get("simulation_version")?,
+                seed: recorded_seed(&parameters),
+                parameters,
+                data: parse_json_column(&row, "data")?,
+                computed_at: row.try_get("computed_at")?,
+                attestation: row
+                    .try_get::<Option<String>, _>("attestation")?
+                    .and_then(|json| serde_json::from_str(&json).ok()),
+                compute_backend: Backend::parse(&row.try_get::<String, _>("compute_backend")?).unwrap_or_default(),
+            })
+        })
+        .transpose()
+    }
+}
+
+/// Version of results stored before versions were recorded
+fn first_version() -> u32 {
+    1
+}
+
+fn parse_json_column(row: &sqlx::sqlite::SqliteRow, column: &str) -> Result<serde_json::Value, sqlx::Error> {
+    let text: String = row.try_get(column)?;
+    serde_json::from_str(&text).map_err(|e| sqlx::Error::ColumnDecode {
+        index: column.to_string(),
+        source: Box::new(e),
+    })
+}
@@ -0,0 +1,261 @@
+// Topic taxonomy
+// Simulations tag themselves with free-form topic labels; this curated tree
+// groups those labels into browsable concepts (mechanics → oscillations → pendulum)
+
+use serde::Serialize;
+
+/// A node of the topic tree
+#[derive(Serialize)]
+pub struct Topic {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Enclosing topic, `None` for the branches of physics at the root
+    pub parent: Option<&'static str>,
+    /// Simulation topic labels that belong directly to this topic. Each label
+    /// is listed under one topic only.
+    #[serde(skip)]
+    pub labels: &'static [&'static str],
+}
+
+/// The curated hierarchy, each topic listed after its parent
+pub const TOPICS: &[Topic] = &[
+    Topic {
+        id: "mechanics",
+        name: "Mechanics",
+        description: "Motion of bodies under forces",
+        parent: None,
+        labels: &[],
+    },
+    Topic {
+        id: "kinematics",
+        name: "Kinematics and Dynamics",
+        description: "Describing motion and relating it to forces",
+        parent: Some("mechanics"),
+        labels: &["kinematics", "Newton's laws", "air resistance"],
+    },
+    Topic {
+        id: "oscillations",
+        name: "Oscillations",
+        description: "Systems that move back and forth about equilibrium",
+        parent: Some("mechanics"),
+        labels: &["resonance"],
+    },
+    Topic {
+        id: "pendulum",
+        name: "Pendulums",
+        description: "From the simple pendulum to chaotic coupled ones",
+        parent: Some("oscillations"),
+        labels: &["chaos", "nonlinear dynamics", "Lagrangian mechanics"],
+    },
+    Topic {
+        id: "gravitation",
+        name: "Gravitation",
+        description: "Gravity and the orbits it produces",
+        parent: Some("mechanics"),
+        labels: &["gravity", "Kepler's laws", "orbital mechanics"],
+    },
+    Topic {
+        id: "waves",
+        name: "Waves and Optics",
+        description: "Disturbances that carry energy, and the behaviour of light",
+        parent: None,
+        labels: &[],
+    },
+    Topic {
+        id: "wave-motion",
+        name: "Wave Motion",
+        description: "Travelling and standing waves on strings and in media",
+        parent: Some("waves"),
+        labels: &["waves", "normal modes"],
+    },
+    Topic {
+        id: "sound",
+        name: "Sound",
+        description: "Pressure waves in air and how we hear them",
+        parent: Some("waves"),
+        labels: &["sound waves", "Doppler effect", "frequency"],
+    },
+    Topic {
+        id: "optics",
+        name: "Optics",
+        description: "Refraction, lenses, interference and polarization of light",
+        parent: Some("waves"),
+        labels: &["Snell's law", "lenses", "image formation", "polarization", "Malus's law", "light", "interference"],
+    },
+    Topic {
+        id: "thermodynamics",
+        name: "Thermodynamics and Statistical Physics",
+        description: "Heat, temperature and the behaviour of many particles",
+        parent: None,
+        labels: &[],
+    },
+    Topic {
+        id: "kinetic-theory",
+        name: "Kinetic Theory",
+        description: "Gas properties from the motion of molecules",
+        parent: Some("thermodynamics"),
+        labels: &["kinetic theory", "Maxwell–Boltzmann distribution", "ideal gas law"],
+    },
+    Topic {
+        id: "heat-transfer",
+        name: "Heat Transfer",
+        description: "Conduction of heat and thermal radiation",
+        parent: Some("thermodynamics"),
+        labels: &[
+            "heat equation",
+            "thermal conduction",
+            "boundary conditions",
+            "thermal radiation",
+            "Planck's law",
+            "ultraviolet catastrophe",
+        ],
+    },
+    Topic {
+        id: "statistical-mechanics",
+        name: "Statistical Mechanics",
+        description: "Phases and fluctuations from microscopic models",
+        parent: Some("thermodynamics"),
+        labels: &["statistical mechanics", "phase transitions", "Monte Carlo"],
+    },
+    Topic {
+        id: "electromagnetism",
+        name: "Electromagnetism",
+        description: "Electric and magnetic fields, charges and circuits",
+        parent: None,
+        labels: &[],
+    },
+    Topic {
+        id: "circuits",
+        name: "Circuits",
+        description: "Current, voltage and the response of circuit elements",
+        parent: Some("electromagnetism"),
+        labels: &["circuits", "transients"],
+    },
+    Topic {
+        id: "charged-particles",
+        name: "Charged Particles in Fields",
+        description: "How electric and magnetic fields steer charges",
+        parent: Some("electromagnetism"),
+        labels: &["electromagnetism", "Lorentz force", "cyclotron motion"],
+    },
+    Topic {
+        id: "relativity",
+        name: "Special Relativity",
+        description: "Space and time at speeds close to that of light",
+        parent: None,
+        labels: &["time dilation", "length contraction", "spacetime diagrams", "relativistic kinematics"],
+    },
+    Topic {
+        id: "quantum",
+        name: "Quantum Mechanics",
+        description: "The physics of the very small",
+        parent: None,
+        labels: &[],
+    },
+    Topic {
+        id: "wave-particle-duality",
+        name: "Wave-Particle Duality",
+        description: "Light as particles, matter as waves",
+        parent: Some("quantum"),
+        labels: &[
+            "wave-particle duality",
+            "photons",
+            "work function",
+            "Einstein's equation",
+            "photon momentum",
+            "cross-sections",
+        ],
+    },
+    Topic {
+        id: "schrodinger-equation",
+        name: "Schrödinger Equation",
+        description: "How quantum states evolve and spread",
+        parent: Some("quantum"),
+        labels: &[
+            "Schrödinger equation",
+            "time-dependent Schrödinger equation",
+            "wave packets",
+            "tunneling",
+            "potential barriers",
+            "probability",
+        ],
+    },
+    Topic {
+        id: "bound-states",
+        name: "Bound States",
+        description: "Quantized energies of confined particles",
+        parent: Some("schrodinger-equation"),
+        labels: &["energy quantization", "bound states", "energy eigenstates", "zero-point energy", "coherent states"],
+    },
+    Topic {
+        id: "atomic-physics",
+        name: "Atomic Physics",
+        description: "Electron orbitals and the spectra of atoms",
+        parent: Some("quantum"),
+        labels: &["orbitals", "energy levels", "spectral lines"],
+    },
+    Topic {
+        id: "quantum-measurement",
+        name: "Measurement and Entanglement",
+        description: "Spin, measurement and correlations no classical theory explains",
+        parent: Some("quantum"),
+        labels: &[
+            "quantum measurement",
+            "spin",
+            "non-commuting observables",
+            "entanglement",
+            "Bell's theorem",
+            "quantum nonlocality",
+        ],
+    },
+    Topic {
+        id: "solid-state",
+        name: "Solid-State Physics",
+        description: "Electrons in crystals and the origin of bands",
+        parent: Some("quantum"),
+        labels: &["band theory", "Bloch's theorem", "solid-state physics"],
+    },
+    Topic {
+        id: "nuclear",
+        name: "Nuclear Physics",
+        description: "Unstable nuclei and radioactive decay",
+        parent: None,
+        labels: &["half-life", "decay chains", "statistical uncertainty"],
+    },
+];
+
+pub fn find_topic(id: &str) -> Option<&'static Topic> {
+    TOPICS.iter().find(|topic| topic.id == id)
+}
+
+impl Topic {
+    /// Direct subtopics
+    pub fn children(&self) -> impl Iterator<Item = &'static Topic> + '_ {
+        TOPICS.iter().filter(move |topic| topic.parent == Some(self.id))
+    }
+
+    /// IDs from the root down to this topic, inclusive
+    pub fn path(&self) -> Vec<&'static str> {
+        let mut path = vec![self.id];
+        let mut parent = self.parent;
+        while let Some(topic) = parent.and_then(find_topic) {
+            path.insert(0, topic.id);
+            parent = topic.parent;
+        }
+        path
+    }
+
+    /// Whether `other` is this topic or lies beneath it
+    pub fn contains(&self, other: &Topic) -> bool {
+        other.path().contains(&self.id)
+    }
+}
+
+/// Most specific topics a simulation's labels belong to
+pub fn topics_for_labels(labels: &[String]) -> Vec<&'static Topic> {
+    TOPICS
+        .iter()
+        .filter(|topic| topic.labels.iter().any(|label| labels.iter().any(|l| l == label)))
+        .collect()
+}
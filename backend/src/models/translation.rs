@@ -0,0 +1,104 @@
+// Translation models
+// Simulations are written in English; uploaded translations replace the
+// name, description, theory and parameter labels for one locale
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+
+/// One simulation's text in one locale. Fields left out fall back to the
+/// next locale in the chain.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Translation {
+    pub simulation_id: String,
+    /// Lowercase BCP 47 tag, e.g. `pt-br`
+    pub locale: String,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub theory: Option<String>,
+    /// Labels keyed by parameter name
+    pub parameter_labels: BTreeMap<String, String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Translation {
+    /// Save a translation, replacing any earlier one for the same locale
+    pub async fn upsert(&self, pool: &SqlitePool) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO simulation_translations (simulation_id, locale, name, description, theory,
+                 parameter_labels, updated_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT (simulation_id, locale) DO UPDATE SET
+                 name = excluded.name,
+                 description = excluded.description,
+                 theory = excluded.theory,
+                 parameter_labels = excluded.parameter_labels,
+                 updated_at = excluded.updated_at",
+        )
+        .bind(&self.simulation_id)
+        .bind(&self.locale)
+        .bind(&self.name)
+        .bind(&self.description)
+        .bind(&self.theory)
+        .bind(serde_json::to_string(&self.parameter_labels).unwrap_or_default())
+        .bind(self.updated_at)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Delete a translation and report whether it existed
+    pub async fn delete(pool: &SqlitePool, simulation_id: &str, locale: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM simulation_translations WHERE simulation_id = ? AND locale = ?")
+            .bind(simulation_id)
+            .bind(locale)
+            .execute(pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Every translation of a simulation, by locale
+    pub async fn for_simulation(pool: &SqlitePool, simulation_id: &str) -> Result<Vec<Self>, sqlx::Error> {
+        let rows = sqlx::query(&format!("{COLUMNS} WHERE simulation_id = ? ORDER BY locale"))
+            .bind(simulation_id)
+            .fetch_all(pool)
+            .await?;
+        rows.iter().map(Self::from_row).collect()
+    }
+
+    /// Translations of any simulation into any of `locales`
+    pub async fn for_locales(pool: &SqlitePool, locales: &[String]) -> Result<Vec<Self>, sqlx::Error> {
+        if locales.is_empty() {
+            return Ok(Vec::new());
+        }
+        let placeholders = vec!["?"; locales.len()].join(", ");
+        let sql = format!("{COLUMNS} WHERE locale IN ({placeholders})");
+        let mut query = sqlx::query(&sql);
+        for locale in locales {
+            query = query.bind(locale);
+        }
+        let rows = query.fetch_all(pool).await?;
+        rows.iter().map(Self::from_row).collect()
+    }
+
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Self, sqlx::Error> {
+        let labels: String = row.try_get("parameter_labels")?;
+        Ok(Self {
+            simulation_id: row.try_get("simulation_id")?,
+            locale: row.try_get("locale")?,
+            name: row.try_get("name")?,
+            description: row.try_get("description")?,
+            theory: row.try_get("theory")?,
+            parameter_labels: serde_json::from_str(&labels).map_err(|e| sqlx::Error::ColumnDecode {
+                index: "parameter_labels".to_string(),
+                source: Box::new(e),
+            })?,
+            updated_at: row.try_get("updated_at")?,
+        })
+    }
+}
+
+const COLUMNS: &str = "SELECT simulation_id, locale, name, description, theory, parameter_labels, updated_at
+ FROM simulation_translations";
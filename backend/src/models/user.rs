@@ -1 +1,107 @@
 // User models
+
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
+
+/// A registered account. The password hash never leaves the server.
+#[derive(Serialize, Clone)]
+pub struct User {
+    pub id: String,
+    pub email: String,
+    pub display_name: String,
+    #[serde(skip)]
+    pub password_hash: String,
+    pub created_at: String,
+}
+
+impl User {
+    pub async fn insert(&self, pool: &SqlitePool) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO users (id, email, display_name, password_hash, created_at)
+             VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(&self.id)
+        .bind(&self.email)
+        .bind(&self.display_name)
+        .bind(&self.password_hash)
+        .bind(&self.created_at)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn find_by_email(pool: &SqlitePool, email: &str) -> Result<Option<Self>, sqlx::Error> {
+        let row = sqlx::query(
+            "SELECT id, email, display_name, password_hash, created_at FROM users WHERE email = ?",
+        )
+        .bind(email)
+        .fetch_optional(pool)
+        .await?;
+        row.map(|row| Self::from_row(&row)).transpose()
+    }
+
+    pub async fn find(pool: &SqlitePool, id: &str) -> Result<Option<Self>, sqlx::Error> {
+        let row = sqlx::query(
+            "SELECT id, email, display_name, password_hash, created_at FROM users WHERE id = ?",
+        )
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+        row.map(|row| Self::from_row(&row)).transpose()
+    }
+
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            id: row.try_get("id")?,
+            email: row.try_get("email")?,
+            display_name: row.try_get("display_name")?,
+            password_hash: row.try_get("password_hash")?,
+            created_at: row.try_get("created_at")?,
+        })
+    }
+}
+
+/// A stored refresh token, identified by the SHA-256 hash of its value
+pub struct RefreshToken {
+    pub token_hash: String,
+    pub user_id: String,
+    pub expires_at: String,
+    pub created_at: String,
+}
+
+impl RefreshToken {
+    pub async fn insert(&self, pool: &SqlitePool) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO refresh_tokens (token_hash, user_id, expires_at, created_at)
+             VALUES (?, ?, ?, ?)",
+        )
+        .bind(&self.token_hash)
+        .bind(&self.user_id)
+        .bind(&self.expires_at)
+        .bind(&self.created_at)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Remove a token and return it, so each refresh token can be used once
+    pub async fn take(pool: &SqlitePool, token_hash: &str) -> Result<Option<Self>, sqlx::Error> {
+        let row = sqlx::query(
+            "DELETE FROM refresh_tokens WHERE token_hash = ?
+             RETURNING token_hash, user_id, expires_at, created_at",
+        )
+        .bind(token_hash)
+        .fetch_optional(pool)
+        .await?;
+
+        row.map(|row| {
+            Ok(Self {
+                token_hash: row.try_get("token_hash")?,
+                user_id: row.try_get("user_id")?,
+                expires_at: row.try_get("expires_at")?,
+                created_at: row.try_get("created_at")?,
+            })
+        })
+        .transpose()
+    }
+}
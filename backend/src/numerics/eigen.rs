@@ -0,0 +1,360 @@
+// Lowest eigenpairs of large sparse symmetric matrices
+//
+// Bound states of a 1D potential are the lowest eigenvectors of the
+// Hamiltonian H = −c d²/dx² + V(x) discretized by central differences, a
+// symmetric tridiagonal matrix of one row per grid point. Rather than
+// diagonalize all of it, the Lanczos method builds an orthonormal basis of
+// the Krylov space {q, Aq, A²q, …}, in which A becomes a small tridiagonal
+// matrix whose extreme eigenvalues converge to those of A within a few
+// dozen steps. A = (H − σ)⁻¹ with σ below the spectrum (shift-and-invert,
+// as ARPACK does) turns the lowest, closely spaced levels of H into the
+// largest and best separated eigenvalues of A, each application costing
+// one tridiagonal solve. The basis is fully reorthogonalized, which keeps
+// spurious copies of converged eigenvalues out, and grows until every
+// requested pair has a small residual ‖Hy − λy‖.
+
+use super::linalg::solve_tridiagonal;
+
+/// Relative size of a Lanczos coefficient below which the Krylov space has
+/// stopped growing
+const BREAKDOWN: f64 = 1e-12;
+/// Largest residual ‖Hy − λy‖ accepted, relative to the size of H
+const RESIDUAL_TOLERANCE: f64 = 1e-10;
+/// Sweeps of the QL algorithm allowed for one eigenvalue of the Lanczos
+/// matrix; a handful is typical
+const MAX_QL_SWEEPS: usize = 60;
+
+/// Real symmetric tridiagonal matrix, stored as its diagonal and the n − 1
+/// elements beside it
+pub struct SymmetricTridiagonal {
+    pub diagonal: Vec<f64>,
+    pub off_diagonal: Vec<f64>,
+}
+
+/// Eigenvalue with its unit eigenvector
+pub struct Eigenpair {
+    pub value: f64,
+    pub vector: Vec<f64>,
+}
+
+impl SymmetricTridiagonal {
+    /// Hamiltonian −c ψ'' + V ψ sampled at grid points `dx` apart, with ψ
+    /// held at zero just beyond both ends as between infinite walls; `c` is
+    /// ħ²/2m in the units of the potential times length squared
+    pub fn hamiltonian(potential: &[f64], dx: f64, c: f64) -> Self {
+        let coupling = c / (dx * dx);
+        Self {
+            diagonal: potential.iter().map(|v| 2.0 * coupling + v).collect(),
+            off_diagonal: vec![-coupling; potential.len().saturating_sub(1)],
+        }
+    }
+
+    /// Number of eigenvalues below `value`, by Sylvester's law of inertia:
+    /// the negative pivots of the LDLᵀ factorization of A − value·I (the
+    /// Sturm sequence count)
+    pub fn count_below(&self, value: f64) -> usize {
+        let mut count = 0;
+        let mut pivot = 1.0;
+        for (i, d) in self.diagonal.iter().enumerate() {
+            let coupling = if i > 0 { self.off_diagonal[i - 1].powi(2) / pivot } else { 0.0 };
+            pivot = d - value - coupling;
+            // A zero pivot is nudged aside, counting `value` itself as not below
+            if pivot == 0.0 {
+                pivot = f64::EPSILON * (d.abs() + value.abs()).max(f64::MIN_POSITIVE);
+            }
+            if pivot < 0.0 {
+                count += 1;
+            }
+        }
+        count
+    }
+
+    fn len(&self) -> usize {
+        self.diagonal.len()
+    }
+
+    fn multiply(&self, x: &[f64]) -> Vec<f64> {
+        (0..self.len())
+            .map(|i| {
+                let below = if i > 0 { self.off_diagonal[i - 1] * x[i - 1] } else { 0.0 };
+                let above = if i + 1 < self.len() { self.off_diagonal[i] * x[i + 1] } else { 0.0 };
+                below + self.diagonal[i] * x[i] + above
+            })
+            .collect()
+    }
+
+    /// Gershgorin discs: every eigenvalue lies within `offsets` of a
+    /// diagonal element, where `offsets` are the row sums of |off-diagonal|
+    fn gershgorin(&self) -> (f64, f64) {
+        (0..self.len()).fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), i| {
+            let below = if i > 0 { self.off_diagonal[i - 1].abs() } else { 0.0 };
+            let above = self.off_diagonal.get(i).map_or(0.0, |e| e.abs());
+            (low.min(self.diagonal[i] - below - above), high.max(self.diagonal[i] + below + above))
+        })
+    }
+
+    /// x with (A − shift·I) x = rhs; for a shift below every Gershgorin disc
+    /// the system is diagonally dominant and needs no pivoting
+    fn solve_shifted(&self, shift: f64, rhs: &[f64]) -> Vec<f64> {
+        let diagonal: Vec<f64> = self.diagonal.iter().map(|d| d - shift).collect();
+        let mut lower = vec![0.0];
+        lower.extend(&self.off_diagonal);
+        let mut upper = self.off_diagonal.clone();
+        upper.push(0.0);
+        solve_tridiagonal(&lower, &diagonal, &upper, rhs)
+    }
+}
+
+/// The `k` lowest eigenvalues of `matrix` in increasing order, with unit
+/// eigenvectors signed so that their first non-negligible entry is positive.
+/// Fewer are returned only when the matrix has fewer than `k` rows.
+/// The eigenvalues of a tridiagonal matrix with non-zero off-diagonal are
+/// all distinct, so one starting vector reaches every eigenvector.
+pub fn lowest(matrix: &SymmetricTridiagonal, k: usize) -> Vec<Eigenpair> {
+    let n = matrix.len();
+    let k = k.min(n);
+    if k == 0 {
+        return Vec::new();
+    }
+    let (low, high) = matrix.gershgorin();
+    let scale = low.abs().max(high.abs()).max(f64::MIN_POSITIVE);
+    let shift = low - 1e-3 * (high - low).max(scale * f64::EPSILON);
+
+    let mut steps = (2 * k + 20).min(n);
+    loop {
+        let (basis, alpha, beta) = lanczos(|x| matrix.solve_shifted(shift, x), start_vector(n), steps);
+        let mut ritz = symmetric_tridiagonal_eigen(&alpha, &beta);
+        // The largest eigenvalues θ of (H − σ)⁻¹ are the lowest λ = σ + 1/θ of H
+        ritz.sort_by(|a, b| b.0.total_cmp(&a.0));
+        let pairs: Vec<Eigenpair> = ritz
+            .into_iter()
+            .take(k)
+            .map(|(theta, coefficients)| {
+                let mut vector = vec![0.0; n];
+                for (q, c) in basis.iter().zip(&coefficients) {
+                    vector.iter_mut().zip(q).for_each(|(v, q)| *v += c * q);
+                }
+                normalize(&mut vector);
+                Eigenpair {
+                    value: shift + 1.0 / theta,
+                    vector,
+                }
+            })
+            .collect();
+
+        let converged = pairs.iter().all(|pair| {
+            let image = matrix.multiply(&pair.vector);
+            let residual = image.iter().zip(&pair.vector).map(|(h, v)| (h - pair.value * v).powi(2)).sum::<f64>();
+            residual.sqrt() <= RESIDUAL_TOLERANCE * scale
+        });
+        // A basis that stopped short of `steps` spans an invariant subspace,
+        // whose pairs are exact
+        if converged || steps == n || basis.len() < steps {
+            let mut pairs = pairs;
+            pairs.sort_by(|a, b| a.value.total_cmp(&b.value));
+            return pairs;
+        }
+        steps = (2 * steps).min(n);
+    }
+}
+
+/// Starting vector with no symmetry, so that it overlaps both even and odd
+/// eigenvectors of symmetric potentials; fixed so results are reproducible
+fn start_vector(n: usize) -> Vec<f64> {
+    let mut q: Vec<f64> = (0..n).map(|i| ((i as f64 + 1.0) * 0.618_033_988_749_895).fract() - 0.5).collect();
+    normalize(&mut q);
+    q
+}
+
+/// Up to `steps` Lanczos steps with the symmetric operator `apply`: the
+/// orthonormal basis and the diagonal and off-diagonal of the tridiagonal
+/// matrix representing the operator in it
+fn lanczos(apply: impl Fn(&[f64]) -> Vec<f64>, start: Vec<f64>, steps: usize) -> (Vec<Vec<f64>>, Vec<f64>, Vec<f64>) {
+    let mut basis = vec![start];
+    let mut alpha = Vec::with_capacity(steps);
+    let mut beta = Vec::with_capacity(steps);
+    loop {
+        let q = basis.last().expect("the basis starts with one vector");
+        let mut w = apply(q);
+        alpha.push(dot(&w, q));
+        // Gram–Schmidt against the whole basis, twice, as once leaves
+        // enough round-off for converged vectors to creep back in
+        for _ in 0..2 {
+            for q in &basis {
+                let overlap = dot(&w, q);
+                w.iter_mut().zip(q).for_each(|(w, q)| *w -= overlap * q);
+            }
+        }
+        let norm = dot(&w, &w).sqrt();
+        if basis.len() == steps || norm <= BREAKDOWN * alpha.iter().fold(0.0, |m: f64, a| m.max(a.abs())) {
+            return (basis, alpha, beta);
+        }
+        beta.push(norm);
+        basis.push(w.into_iter().map(|w| w / norm).collect());
+    }
+}
+
+/// Eigenvalues and eigenvectors of a small symmetric tridiagonal matrix by
+/// the QL algorithm with implicit Wilkinson shifts, as (value, vector)
+/// pairs in no particular order
+fn symmetric_tridiagonal_eigen(diagonal: &[f64], off_diagonal: &[f64]) -> Vec<(f64, Vec<f64>)> {
+    let n = diagonal.len();
+    let mut d = diagonal.to_vec();
+    // e[i] couples rows i and i + 1; the trailing zero simplifies the loops
+    let mut e = off_diagonal.to_vec();
+    e.resize(n, 0.0);
+    // z[row][column], the columns converging to the eigenvectors
+    let mut z: Vec<Vec<f64>> = (0..n).map(|i| (0..n).map(|j| if i == j { 1.0 } else { 0.0 }).collect()).collect();
+
+    for l in 0..n {
+        for _ in 0..MAX_QL_SWEEPS {
+            // Split off a block at the first negligible off-diagonal element
+            let m = (l..n - 1)
+                .find(|&m| e[m].abs() <= f64::EPSILON * (d[m].abs() + d[m + 1].abs()))
+                .unwrap_or(n - 1);
+            if m == l {
+                break;
+            }
+            let mut g = (d[l + 1] - d[l]) / (2.0 * e[l]);
+            let mut r = g.hypot(1.0);
+            g = d[m] - d[l] + e[l] / (g + r.copysign(g));
+            let (mut s, mut c, mut p) = (1.0, 1.0, 0.0);
+            let mut deflated = false;
+            for i in (l..m).rev() {
+                let f = s * e[i];
+                let b = c * e[i];
+                r = f.hypot(g);
+                e[i + 1] = r;
+                if r == 0.0 {
+                    d[i + 1] -= p;
+                    e[m] = 0.0;
+                    deflated = true;
+                    break;
+                }
+                s = f / r;
+                c = g / r;
+                g = d[i + 1] - p;
+                r = (d[i] - g) * s + 2.0 * c * b;
+                p = s * r;
+                d[i + 1] = g + p;
+                g = c * r - b;
+                for row in z.iter_mut() {
+                    let f = row[i + 1];
+                    row[i + 1] = s * row[i] + c * f;
+                    row[i] = c * row[i] - s * f;
+                }
+            }
+            if !deflated {
+                d[l] -= p;
+                e[l] = g;
+                e[m] = 0.0;
+            }
+        }
+    }
+    (0..n).map(|j| (d[j], z.iter().map(|row| row[j]).collect())).collect()
+}
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+/// Scale to unit length, signed so the first entry that is not negligible
+/// is positive
+fn normalize(v: &mut [f64]) {
+    let norm = dot(v, v).sqrt();
+    let largest = v.iter().fold(0.0, |m: f64, x| m.max(x.abs()));
+    let sign = v.iter().find(|x| x.abs() > 1e-6 * largest).map_or(1.0, |x| x.signum());
+    v.iter_mut().for_each(|x| *x *= sign / norm);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    /// The second-difference matrix tridiag(−1, 2, −1) of `n` rows, whose
+    /// eigenvalues are 2 − 2cos(jπ/(n+1)) with eigenvectors sin(ijπ/(n+1))
+    fn second_difference(n: usize) -> SymmetricTridiagonal {
+        SymmetricTridiagonal {
+            diagonal: vec![2.0; n],
+            off_diagonal: vec![-1.0; n - 1],
+        }
+    }
+
+    fn exact(n: usize, j: usize) -> f64 {
+        2.0 - 2.0 * (j as f64 * PI / (n as f64 + 1.0)).cos()
+    }
+
+    #[test]
+    fn second_difference_eigenpairs() {
+        let n = 400;
+        let pairs = lowest(&second_difference(n), 5);
+        assert_eq!(pairs.len(), 5);
+        for (j, pair) in (1..).zip(&pairs) {
+            assert!((pair.value - exact(n, j)).abs() < 1e-12, "λ{j} = {} not {}", pair.value, exact(n, j));
+            let mut expected: Vec<f64> = (1..=n).map(|i| (i as f64 * j as f64 * PI / (n as f64 + 1.0)).sin()).collect();
+            normalize(&mut expected);
+            let error = pair.vector.iter().zip(&expected).map(|(a, b)| (a - b).abs()).fold(0.0, f64::max);
+            assert!(error < 1e-8, "eigenvector {j} is off by {error}");
+        }
+    }
+
+    #[test]
+    fn harmonic_oscillator_levels() {
+        // −½ψ'' + ½x²ψ has levels n + ½, less a discretization error of
+        // order dx² that grows with the level
+        let dx = 0.01;
+        let potential: Vec<f64> = (0..2001).map(|i| 0.5 * (-10.0 + i as f64 * dx).powi(2)).collect();
+        let matrix = SymmetricTridiagonal::hamiltonian(&potential, dx, 0.5);
+        for (n, pair) in lowest(&matrix, 6).iter().enumerate() {
+            assert!((pair.value - (n as f64 + 0.5)).abs() < 1e-3, "E{n} = {}", pair.value);
+        }
+    }
+
+    #[test]
+    fn small_and_degenerate_sizes() {
+        let one = SymmetricTridiagonal {
+            diagonal: vec![3.0],
+            off_diagonal: Vec::new(),
+        };
+        let pairs = lowest(&one, 4);
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(pairs[0].value, 3.0);
+        assert_eq!(pairs[0].vector, [1.0]);
+        assert!(lowest(&one, 0).is_empty());
+
+        // All eigenvalues of a matrix smaller than the Lanczos basis
+        let pairs = lowest(&second_difference(4), 10);
+        let values: Vec<f64> = pairs.iter().map(|pair| pair.value).collect();
+        assert_eq!(values.len(), 4);
+        for (j, value) in (1..).zip(values) {
+            assert!((value - exact(4, j)).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn ql_on_a_small_matrix() {
+        // [[2, 1, 0], [1, 2, 1], [0, 1, 2]] has eigenvalues 2 − √2, 2, 2 + √2
+        let mut pairs = symmetric_tridiagonal_eigen(&[2.0, 2.0, 2.0], &[1.0, 1.0]);
+        pairs.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let expected = [2.0 - 2f64.sqrt(), 2.0, 2.0 + 2f64.sqrt()];
+        for ((value, vector), expected) in pairs.iter().zip(expected) {
+            assert!((value - expected).abs() < 1e-14);
+            let image = [2.0 * vector[0] + vector[1], vector[0] + 2.0 * vector[1] + vector[2], vector[1] + 2.0 * vector[2]];
+            assert!(image.iter().zip(vector).all(|(a, v)| (a - value * v).abs() < 1e-14));
+            assert!((dot(vector, vector) - 1.0).abs() < 1e-14);
+        }
+    }
+
+    #[test]
+    fn counts_eigenvalues_below() {
+        let n = 50;
+        let matrix = second_difference(n);
+        assert_eq!(matrix.count_below(0.0), 0);
+        assert_eq!(matrix.count_below(4.0), n);
+        for j in [1, 7, 25, 49] {
+            let between = 0.5 * (exact(n, j) + exact(n, j + 1));
+            assert_eq!(matrix.count_below(between), j);
+        }
+    }
+}
@@ -0,0 +1,209 @@
+// Discrete Fourier transforms and spectral windows
+//
+// Xₖ = Σ xⱼ e^{−2πijk/n}, unnormalized; the inverse divides by n. Powers of
+// two use the iterative radix-2 Cooley–Tukey algorithm; other lengths are
+// rewritten by Bluestein's chirp z-transform as a convolution of a
+// power-of-two length, so every length takes O(n log n).
+
+use std::f64::consts::PI;
+
+use super::complex::Complex;
+
+/// Discrete Fourier transform of `signal`
+pub fn forward(signal: &[Complex]) -> Vec<Complex> {
+    let mut values = signal.to_vec();
+    if values.len().is_power_of_two() {
+        radix2(&mut values);
+        values
+    } else {
+        bluestein(&values)
+    }
+}
+
+/// Inverse transform, so that `inverse(&forward(x))` gives back x
+pub fn inverse(spectrum: &[Complex]) -> Vec<Complex> {
+    let n = spectrum.len() as f64;
+    let conjugated: Vec<Complex> = spectrum.iter().map(|c| c.conj()).collect();
+    forward(&conjugated).into_iter().map(|c| c.conj() * (1.0 / n)).collect()
+}
+
+/// Transform of a real signal: the n/2 + 1 coefficients from zero up to the
+/// Nyquist frequency; the rest are their complex conjugates
+pub fn real_forward(signal: &[f64]) -> Vec<Complex> {
+    let complex: Vec<Complex> = signal.iter().map(|&x| Complex::real(x)).collect();
+    let mut spectrum = forward(&complex);
+    spectrum.truncate(signal.len() / 2 + 1);
+    spectrum
+}
+
+/// Frequencies of the coefficients `real_forward` returns for `n` samples
+/// taken `sample_rate` per unit time
+pub fn real_frequencies(n: usize, sample_rate: f64) -> Vec<f64> {
+    (0..=n / 2).map(|k| k as f64 * sample_rate / n as f64).collect()
+}
+
+/// In-place radix-2 transform; `values.len()` must be a power of two
+fn radix2(values: &mut [Complex]) {
+    let n = values.len();
+    let bits = n.trailing_zeros();
+    if bits == 0 {
+        return;
+    }
+    for i in 0..n {
+        let j = i.reverse_bits() >> (usize::BITS - bits);
+        if i < j {
+            values.swap(i, j);
+        }
+    }
+    let mut size = 2;
+    while size <= n {
+        let half = size / 2;
+        // Twiddles computed directly rather than by repeated multiplication,
+        // which would accumulate rounding error along each butterfly group
+        let twiddles: Vec<Complex> = (0..half).map(|k| Complex::expi(-2.0 * PI * k as f64 / size as f64)).collect();
+        for group in values.chunks_exact_mut(size) {
+            let (low, high) = group.split_at_mut(half);
+            for ((a, b), &w) in low.iter_mut().zip(high.iter_mut()).zip(&twiddles) {
+                let t = *b * w;
+                *b = *a - t;
+                *a = *a + t;
+            }
+        }
+        size *= 2;
+    }
+}
+
+/// Bluestein's algorithm: with jk = (j² + k² − (k − j)²)/2 the transform is
+/// a chirp times the convolution of the chirped signal with the conjugate
+/// chirp, and the convolution is done by power-of-two transforms
+fn bluestein(signal: &[Complex]) -> Vec<Complex> {
+    let n = signal.len();
+    // e^{−πik²/n}, with k² reduced modulo 2n so the angle stays small and exact
+    let chirp: Vec<Complex> = (0..n)
+        .map(|k| {
+            let square = (k as u128 * k as u128 % (2 * n as u128)) as f64;
+            Complex::expi(-PI * square / n as f64)
+        })
+        .collect();
+    let m = (2 * n - 1).next_power_of_two();
+
+    let mut a = vec![Complex::default(); m];
+    for ((slot, &x), &w) in a.iter_mut().zip(signal).zip(&chirp) {
+        *slot = x * w;
+    }
+    let mut b = vec![Complex::default(); m];
+    b[0] = chirp[0].conj();
+    for k in 1..n {
+        b[k] = chirp[k].conj();
+        b[m - k] = chirp[k].conj();
+    }
+
+    radix2(&mut a);
+    radix2(&mut b);
+    let product: Vec<Complex> = a.iter().zip(&b).map(|(&x, &y)| x * y).collect();
+    inverse(&product).into_iter().zip(&chirp).map(|(c, &w)| c * w).collect()
+}
+
+/// Taper applied to a finite record before transforming it, trading
+/// frequency resolution for less leakage from a peak into distant bins
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Window {
+    Rectangular,
+    Hann,
+    Hamming,
+    Blackman,
+}
+
+impl Window {
+    pub const ALL: [Window; 4] = [Window::Rectangular, Window::Hann, Window::Hamming, Window::Blackman];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Window::Rectangular => "rectangular",
+            Window::Hann => "hann",
+            Window::Hamming => "hamming",
+            Window::Blackman => "blackman",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|window| window.name() == name)
+    }
+
+    /// The n weights, in the periodic form used for spectral analysis
+    pub fn weights(self, n: usize) -> Vec<f64> {
+        (0..n)
+            .map(|j| {
+                let t = 2.0 * PI * j as f64 / n as f64;
+                match self {
+                    Window::Rectangular => 1.0,
+                    Window::Hann => 0.5 - 0.5 * t.cos(),
+                    Window::Hamming => 0.54 - 0.46 * t.cos(),
+                    Window::Blackman => 0.42 - 0.5 * t.cos() + 0.08 * (2.0 * t).cos(),
+                }
+            })
+            .collect()
+    }
+
+    /// Multiply `signal` by the window's weights
+    pub fn apply(self, signal: &[f64]) -> Vec<f64> {
+        signal.iter().zip(self.weights(signal.len())).map(|(x, w)| x * w).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A signal with no symmetry a transform could get away with ignoring
+    fn signal(n: usize) -> Vec<Complex> {
+        (0..n)
+            .map(|j| {
+                let t = j as f64;
+                Complex::new((0.7 * t).sin() + 0.25, 0.5 * (1.9 * t).cos() - 0.1 * t / n as f64)
+            })
+            .collect()
+    }
+
+    /// Xₖ = Σ xⱼ e^{−2πijk/n}, term by term
+    fn direct(signal: &[Complex]) -> Vec<Complex> {
+        let n = signal.len();
+        (0..n)
+            .map(|k| {
+                signal.iter().enumerate().fold(Complex::default(), |sum, (j, &x)| {
+                    sum + x * Complex::expi(-2.0 * PI * ((j * k) % n) as f64 / n as f64)
+                })
+            })
+            .collect()
+    }
+
+    /// Largest |a − b| over the largest |b|
+    fn relative_error(a: &[Complex], b: &[Complex]) -> f64 {
+        assert_eq!(a.len(), b.len());
+        let scale = b.iter().map(|c| c.abs()).fold(f64::MIN_POSITIVE, f64::max);
+        a.iter().zip(b).map(|(&a, &b)| (a - b).abs()).fold(0.0, f64::max) / scale
+    }
+
+    #[test]
+    fn transforms_match_the_direct_sum() {
+        for n in [3, 5, 16, 1000] {
+            let x = signal(n);
+            let expected = direct(&x);
+            let error = relative_error(&forward(&x), &expected);
+            assert!(error < 1e-12, "length {n} is off by {error:e}");
+            if !n.is_power_of_two() {
+                assert_eq!(bluestein(&x), forward(&x));
+            }
+        }
+    }
+
+    #[test]
+    fn inverse_undoes_forward() {
+        for n in [1, 2, 3, 12, 1000, 1024] {
+            let x = signal(n);
+            let error = relative_error(&inverse(&forward(&x)), &x);
+            assert!(error < 1e-13, "length {n} round trip is off by {error:e}");
+        }
+    }
+}
+
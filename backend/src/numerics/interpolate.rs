@@ -0,0 +1,24 @@
+/// Value at `x` of the cubic through the four samples nearest to it, or of
+/// the polynomial through all samples where there are fewer. `xs` must be
+/// increasing. `None` outside [xs₀, xsₙ₋₁] or with fewer than two samples.
+pub fn cubic(xs: &[f64], ys: &[f64], x: f64) -> Option<f64> {
+    let n = xs.len().min(ys.len());
+    if n < 2 || !(xs[0]..=xs[n - 1]).contains(&x) {
+        return None;
+    }
+    // Index of the interval [xsᵢ, xsᵢ₊₁] holding x
+    let i = xs[..n].partition_point(|&xi| xi <= x).clamp(1, n - 1) - 1;
+    let start = i.saturating_sub(1).min(n.saturating_sub(4));
+    let end = (start + 4).min(n);
+    // Lagrange form over the chosen samples
+    let value = (start..end)
+        .map(|j| {
+            let weight: f64 = (start..end)
+                .filter(|&k| k != j)
+                .map(|k| (x - xs[k]) / (xs[j] - xs[k]))
+                .product();
+            weight * ys[j]
+        })
+        .sum();
+    Some(value)
+}
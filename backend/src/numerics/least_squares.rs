@@ -0,0 +1,128 @@
+// Nonlinear least squares by the Levenberg–Marquardt method
+//
+// Minimizes χ² = Σ ((yᵢ − f(p, xᵢ)) / σᵢ)² over the parameters p, blending
+// Gauss–Newton steps with gradient descent: the damping λ grows while steps
+// fail to lower χ² and shrinks while they succeed. Derivatives of the model
+// are taken by central differences, with steps scaled to each parameter's
+// size or, while it is zero, to its initial guess, so any closure will do.
+
+use super::linalg::invert;
+
+const MAX_ITERATIONS: usize = 500;
+/// Relative decrease of χ² below which a step counts as converged
+const TOLERANCE: f64 = 1e-12;
+/// Damping beyond which no step lowers χ², so the fit sits at a minimum
+const MAX_DAMPING: f64 = 1e12;
+
+/// Outcome of a fit
+pub struct Fit {
+    pub params: Vec<f64>,
+    /// (JᵀWJ)⁻¹ at the solution, the parameter covariance when the σᵢ are
+    /// the true uncertainties; `None` if the parameters are degenerate
+    pub covariance: Option<Vec<Vec<f64>>>,
+    pub chi_squared: f64,
+    pub iterations: usize,
+    pub converged: bool,
+}
+
+/// Fit `model(p, x)` to the points `(xs, ys)` with uncertainties `sigmas`,
+/// starting from `initial`
+pub fn levenberg_marquardt<F>(model: F, xs: &[f64], ys: &[f64], sigmas: &[f64], initial: &[f64]) -> Fit
+where
+    F: Fn(&[f64], f64) -> f64,
+{
+    let chi_squared = |params: &[f64]| -> f64 {
+        xs.iter()
+            .zip(ys)
+            .zip(sigmas)
+            .map(|((&x, &y), &sigma)| ((y - model(params, x)) / sigma).powi(2))
+            .sum()
+    };
+
+    let mut params = initial.to_vec();
+    let mut chi2 = chi_squared(&params);
+    let mut damping = 1e-3;
+    let mut iterations = 0;
+    let mut converged = false;
+    'fit: while !converged && iterations < MAX_ITERATIONS && chi2.is_finite() {
+        iterations += 1;
+        let (curvature, gradient) = normal_equations(&model, xs, ys, sigmas, &params, initial);
+        loop {
+            let damped: Vec<Vec<f64>> = curvature
+                .iter()
+                .enumerate()
+                .map(|(i, row)| row.iter().enumerate().map(|(j, &a)| if i == j { a * (1.0 + damping) } else { a }).collect())
+                .collect();
+            let Some(inverse) = invert(&damped) else {
+                break 'fit;
+            };
+            let trial: Vec<f64> = params
+                .iter()
+                .zip(&inverse)
+                .map(|(p, row)| p + row.iter().zip(&gradient).map(|(a, g)| a * g).sum::<f64>())
+                .collect();
+            let trial_chi2 = chi_squared(&trial);
+            if trial_chi2.is_finite() && trial_chi2 <= chi2 {
+                converged = chi2 - trial_chi2 <= TOLERANCE * chi2;
+                params = trial;
+                chi2 = trial_chi2;
+                damping = (damping / 10.0).max(1e-12);
+                break;
+            }
+            damping *= 10.0;
+            if damping > MAX_DAMPING {
+                converged = true;
+                break;
+            }
+        }
+    }
+
+    let (curvature, _) = normal_equations(&model, xs, ys, sigmas, &params, initial);
+    Fit {
+        covariance: invert(&curvature),
+        params,
+        chi_squared: chi2,
+        iterations,
+        converged,
+    }
+}
+
+/// JᵀWJ and JᵀW(y − f) at `params`, W holding the weights 1/σᵢ²
+fn normal_equations<F>(
+    model: &F,
+    xs: &[f64],
+    ys: &[f64],
+    sigmas: &[f64],
+    params: &[f64],
+    initial: &[f64],
+) -> (Vec<Vec<f64>>, Vec<f64>)
+where
+    F: Fn(&[f64], f64) -> f64,
+{
+    let n = params.len();
+    let mut curvature = vec![vec![0.0; n]; n];
+    let mut gradient = vec![0.0; n];
+    let mut shifted = params.to_vec();
+    let mut jacobian = vec![0.0; n];
+    for ((&x, &y), &sigma) in xs.iter().zip(ys).zip(sigmas) {
+        for (k, derivative) in jacobian.iter_mut().enumerate() {
+            let size = params[k].abs().max(initial[k].abs());
+            let h = f64::EPSILON.cbrt() * if size > 0.0 { size } else { 1.0 };
+            shifted[k] = params[k] + h;
+            let above = model(&shifted, x);
+            shifted[k] = params[k] - h;
+            let below = model(&shifted, x);
+            shifted[k] = params[k];
+            *derivative = (above - below) / (2.0 * h);
+        }
+        let weight = 1.0 / (sigma * sigma);
+        let residual = y - model(params, x);
+        for i in 0..n {
+            gradient[i] += weight * jacobian[i] * residual;
+            for j in 0..n {
+                curvature[i][j] += weight * jacobian[i] * jacobian[j];
+            }
+        }
+    }
+    (curvature, gradient)
+}
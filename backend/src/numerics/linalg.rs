@@ -0,0 +1,63 @@
+use std::ops::{Add, Div, Mul, Sub};
+
+/// Solve a tridiagonal system with the Thomas algorithm.
+///
+/// `lower[i]` multiplies x[i-1] and `upper[i]` multiplies x[i+1] in row i, so
+/// `lower[0]` and `upper[n-1]` are ignored. The system must be diagonally
+/// dominant (or otherwise safe without pivoting), which holds for the
+/// implicit schemes used here.
+pub fn solve_tridiagonal<T>(lower: &[T], diag: &[T], upper: &[T], rhs: &[T]) -> Vec<T>
+where
+    T: Copy + Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Div<Output = T>,
+{
+    let n = diag.len();
+    let mut c_prime = Vec::with_capacity(n);
+    let mut d_prime = Vec::with_capacity(n);
+
+    c_prime.push(upper[0] / diag[0]);
+    d_prime.push(rhs[0] / diag[0]);
+    for i in 1..n {
+        let denom = diag[i] - lower[i] * c_prime[i - 1];
+        c_prime.push(upper[i] / denom);
+        d_prime.push((rhs[i] - lower[i] * d_prime[i - 1]) / denom);
+    }
+
+    let mut x = d_prime;
+    for i in (0..n - 1).rev() {
+        x[i] = x[i] - c_prime[i] * x[i + 1];
+    }
+    x
+}
+
+/// Inverse of a small dense square matrix by Gauss–Jordan elimination with
+/// partial pivoting, or `None` if it is singular to working precision.
+pub fn invert(matrix: &[Vec<f64>]) -> Option<Vec<Vec<f64>>> {
+    let n = matrix.len();
+    let scale = matrix.iter().flatten().fold(0.0_f64, |max, &value| max.max(value.abs()));
+    if scale == 0.0 || !scale.is_finite() {
+        return None;
+    }
+    // Each row of the matrix followed by the same row of the identity
+    let mut rows: Vec<Vec<f64>> = matrix
+        .iter()
+        .enumerate()
+        .map(|(i, row)| row.iter().copied().chain((0..n).map(|j| if i == j { 1.0 } else { 0.0 })).collect())
+        .collect();
+    for column in 0..n {
+        let pivot = (column..n).max_by(|&a, &b| rows[a][column].abs().total_cmp(&rows[b][column].abs()))?;
+        if rows[pivot][column].abs() <= scale * f64::EPSILON * n as f64 {
+            return None;
+        }
+        rows.swap(column, pivot);
+        let divisor = rows[column][column];
+        rows[column].iter_mut().for_each(|value| *value /= divisor);
+        let pivot_row = rows[column].clone();
+        for (i, row) in rows.iter_mut().enumerate() {
+            let factor = row[column];
+            if i != column && factor != 0.0 {
+                row.iter_mut().zip(&pivot_row).for_each(|(value, &p)| *value -= factor * p);
+            }
+        }
+    }
+    Some(rows.into_iter().map(|row| row[n..].to_vec()).collect())
+}
@@ -0,0 +1,17 @@
+// Shared numerical building blocks used by the simulations
+
+pub mod complex;
+pub mod diffusion;
+pub mod eigen;
+pub mod fft;
+pub mod interpolate;
+pub mod least_squares;
+pub mod linalg;
+pub mod monte_carlo;
+pub mod ode;
+pub mod parallel;
+pub mod roots;
+pub mod schrodinger;
+#[cfg(feature = "simd")]
+pub mod simd;
+pub mod special;
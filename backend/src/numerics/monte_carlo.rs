@@ -0,0 +1,106 @@
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use super::special::ln_gamma;
+
+/// Means below this are sampled by multiplying uniforms, above it by
+/// transformed rejection
+const POISSON_DIRECT_LIMIT: f64 = 10.0;
+
+/// Seeded source of random samples for the stochastic simulations.
+///
+/// ChaCha8 output is fixed for a given seed across platforms and crate
+/// versions, so a seed stored with a result always reproduces it.
+pub struct MonteCarlo {
+    rng: ChaCha8Rng,
+    seed: u64,
+}
+
+impl MonteCarlo {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            seed,
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Uniform sample in [0, 1)
+    pub fn uniform(&mut self) -> f64 {
+        self.rng.gen()
+    }
+
+    /// Uniform sample in [low, high)
+    pub fn uniform_range(&mut self, low: f64, high: f64) -> f64 {
+        low + (high - low) * self.uniform()
+    }
+
+    /// Waiting time of a Poisson process with the given rate (inverse transform)
+    pub fn exponential(&mut self, rate: f64) -> f64 {
+        // 1 - u lies in (0, 1], keeping the logarithm finite
+        -(1.0 - self.uniform()).ln() / rate
+    }
+
+    /// Standard normal sample (Box–Muller)
+    pub fn gaussian(&mut self) -> f64 {
+        let radius = (-2.0 * (1.0 - self.uniform()).ln()).sqrt();
+        radius * (std::f64::consts::TAU * self.uniform()).cos()
+    }
+
+    /// Poisson-distributed count with the given mean, by Knuth's method for
+    /// small means and Hörmann's PTRS transformed rejection for large ones
+    pub fn poisson(&mut self, mean: f64) -> f64 {
+        if mean <= 0.0 {
+            return 0.0;
+        }
+        if mean < POISSON_DIRECT_LIMIT {
+            let limit = (-mean).exp();
+            let mut product = self.uniform();
+            let mut count = 0.0;
+            while product > limit {
+                product *= self.uniform();
+                count += 1.0;
+            }
+            return count;
+        }
+        let (root, log_mean) = (mean.sqrt(), mean.ln());
+        let b = 0.931 + 2.53 * root;
+        let a = -0.059 + 0.02483 * b;
+        let inverse_alpha = 1.1239 + 1.1328 / (b - 3.4);
+        let v_r = 0.9277 - 3.6224 / (b - 2.0);
+        loop {
+            let u = self.uniform() - 0.5;
+            let v = self.uniform();
+            let us = 0.5 - u.abs();
+            let k = ((2.0 * a / us + b) * u + mean + 0.43).floor();
+            if us >= 0.07 && v <= v_r {
+                return k;
+            }
+            if k < 0.0 || (us < 0.013 && v > us) {
+                continue;
+            }
+            if v.ln() + inverse_alpha.ln() - (a / (us * us) + b).ln() <= -mean + k * log_mean - ln_gamma(k + 1.0) {
+                return k;
+            }
+        }
+    }
+
+    /// Unit vector in a uniformly random direction in the plane
+    pub fn direction_2d(&mut self) -> [f64; 2] {
+        let (sin, cos) = (std::f64::consts::TAU * self.uniform()).sin_cos();
+        [cos, sin]
+    }
+
+    /// Index drawn with probability proportional to its weight, given the
+    /// running totals of the weights
+    pub fn sample_cumulative(&mut self, cumulative: &[f64]) -> usize {
+        let total = cumulative.last().copied().unwrap_or(0.0);
+        let target = self.uniform() * total;
+        cumulative
+            .partition_point(|&c| c <= target)
+            .min(cumulative.len().saturating_sub(1))
+    }
+}
@@ -0,0 +1,32 @@
+/// Advance `y' = f(t, y)` by one classical fourth-order Runge–Kutta step
+pub fn rk4_step<const N: usize, F>(f: F, t: f64, y: &[f64; N], dt: f64) -> [f64; N]
+where
+    F: Fn(f64, &[f64; N]) -> [f64; N],
+{
+    let offset = |y: &[f64; N], k: &[f64; N], h: f64| -> [f64; N] {
+        std::array::from_fn(|i| y[i] + h * k[i])
+    };
+
+    let k1 = f(t, y);
+    let k2 = f(t + dt / 2.0, &offset(y, &k1, dt / 2.0));
+    let k3 = f(t + dt / 2.0, &offset(y, &k2, dt / 2.0));
+    let k4 = f(t + dt, &offset(y, &k3, dt));
+
+    std::array::from_fn(|i| y[i] + dt / 6.0 * (k1[i] + 2.0 * k2[i] + 2.0 * k3[i] + k4[i]))
+}
+
+/// Advance `x'' = a(x)` by one velocity Verlet (leapfrog) step.
+///
+/// The scheme is symplectic, so for conservative forces the energy error
+/// stays bounded over long runs instead of drifting as it does with RK4.
+pub fn verlet_step<const N: usize, F>(accel: F, x: &[f64; N], v: &[f64; N], dt: f64) -> ([f64; N], [f64; N])
+where
+    F: Fn(&[f64; N]) -> [f64; N],
+{
+    let a0 = accel(x);
+    let half: [f64; N] = std::array::from_fn(|i| v[i] + 0.5 * dt * a0[i]);
+    let x1: [f64; N] = std::array::from_fn(|i| x[i] + dt * half[i]);
+    let a1 = accel(&x1);
+    let v1 = std::array::from_fn(|i| half[i] + 0.5 * dt * a1[i]);
+    (x1, v1)
+}
@@ -0,0 +1,66 @@
+use std::cell::Cell;
+use std::num::NonZeroUsize;
+use std::thread;
+
+thread_local! {
+    /// Most threads `par_map` may use when called from this thread
+    static WORKER_LIMIT: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+/// Run `f` with every `par_map` it calls on this thread using at most
+/// `workers` threads, or fewer if an enclosing call already allows fewer
+pub fn with_workers<R>(workers: usize, f: impl FnOnce() -> R) -> R {
+    struct Restore(Option<usize>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            WORKER_LIMIT.set(self.0);
+        }
+    }
+    let enclosing = WORKER_LIMIT.get();
+    let limit = enclosing.map_or(workers, |enclosing| enclosing.min(workers)).max(1);
+    let _restore = Restore(WORKER_LIMIT.replace(Some(limit)));
+    f()
+}
+
+/// Threads `par_map` may use here: every core, or fewer under `with_workers`
+pub fn workers() -> usize {
+    let cores = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+    WORKER_LIMIT.get().map_or(cores, |limit| limit.min(cores))
+}
+
+/// Map `f` over `items` on scoped worker threads, preserving order.
+///
+/// Items are split into one contiguous chunk per worker, up to `workers()`.
+/// A `par_map` inside `f` runs serially on its worker, so nested loops do
+/// not multiply the thread count. Call this from a blocking context (e.g.
+/// `spawn_blocking`), never directly on the async runtime.
+pub fn par_map<T, R, F>(items: Vec<T>, f: F) -> Vec<R>
+where
+    T: Send,
+    R: Send,
+    F: Fn(T) -> R + Sync,
+{
+    let workers = workers();
+    if workers <= 1 || items.len() <= 1 {
+        return items.into_iter().map(f).collect();
+    }
+
+    let chunk_size = items.len().div_ceil(workers);
+    let mut chunks: Vec<Vec<T>> = Vec::with_capacity(workers);
+    let mut items = items.into_iter().peekable();
+    while items.peek().is_some() {
+        chunks.push(items.by_ref().take(chunk_size).collect());
+    }
+
+    let f = &f;
+    thread::scope(|scope| {
+        let handles: Vec<_> = chunks
+            .into_iter()
+            .map(|chunk| scope.spawn(move || with_workers(1, || chunk.into_iter().map(f).collect::<Vec<R>>())))
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap_or_else(|e| std::panic::resume_unwind(e)))
+            .collect()
+    })
+}
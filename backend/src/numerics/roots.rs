@@ -0,0 +1,71 @@
+/// Upper bound on bisection iterations; 200 halvings exhaust any f64 bracket
+const MAX_BISECTIONS: usize = 200;
+
+/// Root of `f` in [lo, hi] by bisection, to within `tolerance`.
+///
+/// `f(lo)` and `f(hi)` must not have the same strict sign; if they do, the
+/// endpoint with the smaller |f| is returned.
+pub fn bisect<F>(f: F, mut lo: f64, mut hi: f64, tolerance: f64) -> f64
+where
+    F: Fn(f64) -> f64,
+{
+    let mut f_lo = f(lo);
+    let f_hi = f(hi);
+    if f_lo == 0.0 {
+        return lo;
+    }
+    if f_hi == 0.0 {
+        return hi;
+    }
+    if f_lo.signum() == f_hi.signum() {
+        return if f_lo.abs() < f_hi.abs() { lo } else { hi };
+    }
+    for _ in 0..MAX_BISECTIONS {
+        if hi - lo <= tolerance {
+            break;
+        }
+        let mid = 0.5 * (lo + hi);
+        let f_mid = f(mid);
+        if f_mid == 0.0 {
+            return mid;
+        }
+        if f_mid.signum() == f_lo.signum() {
+            lo = mid;
+            f_lo = f_mid;
+        } else {
+            hi = mid;
+        }
+    }
+    0.5 * (lo + hi)
+}
+
+/// Every sign change of `f` on [lo, hi], located on a uniform grid of
+/// `samples` intervals and refined by bisection.
+///
+/// Roots closer together than the grid spacing, or where `f` touches zero
+/// without changing sign, can be missed; choose `samples` to resolve the
+/// function's structure.
+pub fn find_roots<F>(f: F, lo: f64, hi: f64, samples: usize, tolerance: f64) -> Vec<f64>
+where
+    F: Fn(f64) -> f64,
+{
+    let h = (hi - lo) / samples as f64;
+    let mut roots = Vec::new();
+    let mut x_prev = lo;
+    let mut f_prev = f(lo);
+    for i in 1..=samples {
+        let x = if i == samples { hi } else { lo + i as f64 * h };
+        let f_x = f(x);
+        if f_prev == 0.0 {
+            roots.push(x_prev);
+        } else if f_x != 0.0 && f_prev.signum() != f_x.signum() {
+            roots.push(bisect(&f, x_prev, x, tolerance));
+        }
+        x_prev = x;
+        f_prev = f_x;
+    }
+    if f_prev == 0.0 {
+        roots.push(x_prev);
+    }
+    roots
+}
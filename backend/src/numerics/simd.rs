@@ -0,0 +1,360 @@
+// Four-lane f64 arithmetic for the hot loops, behind the `simd` feature
+//
+// `std::simd` is not stable, so `F64x4` is a plain array whose lane-wise
+// operations are written without branches, which LLVM compiles to vector
+// instructions. The elementary functions are the Cephes polynomial
+// approximations, evaluated in every lane and blended instead of branching.
+// They agree with the standard library to within a few units in the last
+// place (about 1e-15 relative; sine and cosine lose accuracy beyond
+// |x| ≈ 1e8 and fall back to the standard library there), so results differ
+// from the scalar build only in the last digits, well inside the golden
+// file tolerance, but not bit for bit.
+//
+// This file uses nothing outside `std`, so the benchmark in
+// `benches/kernels.rs` includes it directly.
+
+// The coefficients are quoted as published, digits beyond f64 included
+#![allow(clippy::excessive_precision)]
+
+use std::f64::consts::{FRAC_PI_2, FRAC_PI_4, LOG2_E, PI};
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+pub const LANES: usize = 4;
+
+/// Four f64 values operated on together
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct F64x4(pub [f64; LANES]);
+
+/// Lane-wise outcome of a comparison
+#[derive(Clone, Copy, Debug)]
+pub struct Mask(pub [bool; LANES]);
+
+impl F64x4 {
+    pub fn splat(value: f64) -> Self {
+        Self([value; LANES])
+    }
+
+    /// Apply a scalar function in each lane; used for recurrences such as
+    /// orthogonal polynomials that are already cheap arithmetic
+    pub fn map(self, f: impl Fn(f64) -> f64) -> Self {
+        Self(self.0.map(f))
+    }
+
+    fn zip(self, other: Self, f: impl Fn(f64, f64) -> f64) -> Self {
+        Self(std::array::from_fn(|i| f(self.0[i], other.0[i])))
+    }
+
+    fn compare(self, other: Self, f: impl Fn(f64, f64) -> bool) -> Mask {
+        Mask(std::array::from_fn(|i| f(self.0[i], other.0[i])))
+    }
+
+    pub fn lt(self, other: Self) -> Mask {
+        self.compare(other, |a, b| a < b)
+    }
+
+    pub fn gt(self, other: Self) -> Mask {
+        self.compare(other, |a, b| a > b)
+    }
+
+    pub fn abs(self) -> Self {
+        self.map(f64::abs)
+    }
+
+    pub fn sqrt(self) -> Self {
+        self.map(f64::sqrt)
+    }
+
+    /// x^n by repeated squaring, the same exponent in every lane
+    pub fn powi(self, n: u32) -> Self {
+        let (mut result, mut base, mut n) = (Self::splat(1.0), self, n);
+        while n > 0 {
+            if n & 1 == 1 {
+                result = result * base;
+            }
+            base = base * base;
+            n >>= 1;
+        }
+        result
+    }
+
+    pub fn sin(self) -> Self {
+        // sin(−x) = −sin x: flip the sign bit with the argument's
+        self.reduced_trig(|x| f64::from_bits(sin_octant(x.abs(), 0.0).to_bits() ^ (x.to_bits() & SIGN_BIT)), f64::sin)
+    }
+
+    pub fn cos(self) -> Self {
+        self.reduced_trig(|x| sin_octant(x.abs(), 1.0), f64::cos)
+    }
+
+    /// Sine or cosine through the polynomial, with the standard library in
+    /// lanes too large for the three-part reduction to stay accurate
+    fn reduced_trig(self, lane: impl Fn(f64) -> f64, exact: fn(f64) -> f64) -> Self {
+        let result = self.map(lane);
+        // The rare huge argument goes to libm; checked after the fact so the
+        // common path stays branch-free
+        let largest = self.abs().0.into_iter().fold(0.0, f64::max);
+        if largest > MAX_REDUCIBLE {
+            return self.abs().gt(Self::splat(MAX_REDUCIBLE)).select(self.map(exact), result);
+        }
+        result
+    }
+
+    pub fn exp(self) -> Self {
+        self.map(exp_lane)
+    }
+
+    pub fn atan(self) -> Self {
+        self.map(atan_lane)
+    }
+
+    /// Angle of (x, y) = (`x`, `self`), as `f64::atan2`
+    pub fn atan2(self, x: Self) -> Self {
+        self.zip(x, |y, x| {
+            let angle = atan_lane(y / x);
+            let angle = if x.is_sign_negative() { angle + PI.copysign(y) } else { angle };
+            // 0/0: the signed zero or π of the standard library
+            let origin = if x.is_sign_negative() { PI.copysign(y) } else { 0.0_f64.copysign(y) };
+            if x == 0.0 && y == 0.0 {
+                origin
+            } else {
+                angle
+            }
+        })
+    }
+}
+
+impl Mask {
+    /// `a` in the lanes where the mask holds, `b` elsewhere
+    pub fn select(self, a: F64x4, b: F64x4) -> F64x4 {
+        F64x4(std::array::from_fn(|i| if self.0[i] { a.0[i] } else { b.0[i] }))
+    }
+}
+
+macro_rules! lane_operator {
+    ($trait:ident, $method:ident, $op:tt) => {
+        impl $trait for F64x4 {
+            type Output = Self;
+            fn $method(self, other: Self) -> Self {
+                self.zip(other, |a, b| a $op b)
+            }
+        }
+
+        impl $trait<f64> for F64x4 {
+            type Output = Self;
+            fn $method(self, other: f64) -> Self {
+                self.map(|a| a $op other)
+            }
+        }
+
+        impl $trait<F64x4> for f64 {
+            type Output = F64x4;
+            fn $method(self, other: F64x4) -> F64x4 {
+                other.map(|b| self $op b)
+            }
+        }
+    };
+}
+
+lane_operator!(Add, add, +);
+lane_operator!(Sub, sub, -);
+lane_operator!(Mul, mul, *);
+lane_operator!(Div, div, /);
+
+impl Neg for F64x4 {
+    type Output = Self;
+    fn neg(self) -> Self {
+        self.map(|a| -a)
+    }
+}
+
+/// `kernel` applied to `values` four at a time. The last group is padded
+/// with copies of the last value, so the kernel only sees real inputs.
+pub fn map(values: &[f64], kernel: impl Fn(F64x4) -> F64x4) -> Vec<f64> {
+    let mut result = Vec::with_capacity(values.len().next_multiple_of(LANES));
+    let groups = values.chunks_exact(LANES);
+    let rest = groups.remainder();
+    for group in groups {
+        let lanes = F64x4([group[0], group[1], group[2], group[3]]);
+        result.extend_from_slice(&kernel(lanes).0);
+    }
+    if let Some(&last) = rest.last() {
+        let lanes = F64x4(std::array::from_fn(|i| rest.get(i).copied().unwrap_or(last)));
+        result.extend_from_slice(&kernel(lanes).0[..rest.len()]);
+    }
+    result
+}
+
+/// 1.5 × 2⁵², added and subtracted to round to an integer; valid below 2⁵¹
+const ROUNDING: f64 = 6_755_399_441_055_744.0;
+const SIGN_BIT: u64 = 1 << 63;
+/// Largest |x| whose sine the three-part reduction keeps accurate
+const MAX_REDUCIBLE: f64 = 1e8;
+
+// Integers are kept in f64 throughout: 64-bit integer comparisons have no
+// vector instruction before SSE4.1, and would keep the loops scalar.
+
+/// Nearest integer, ties to even, without a libm call
+#[inline(always)]
+fn round(x: f64) -> f64 {
+    (x + ROUNDING) - ROUNDING
+}
+
+/// 2^k for an integer -1022 ≤ k ≤ 1023: k + 1023 lands in the low mantissa
+/// bits of the rounding constant, and the shift moves it to the exponent
+#[inline(always)]
+fn pow2(k: f64) -> f64 {
+    f64::from_bits((k + (ROUNDING + 1023.0)).to_bits() << 52)
+}
+
+/// Horner evaluation, highest coefficient first
+#[inline(always)]
+fn polynomial(x: f64, coefficients: &[f64]) -> f64 {
+    coefficients.iter().fold(0.0, |acc, &c| acc * x + c)
+}
+
+/// π/4 in three parts for exact reduction
+const DP1: f64 = 7.853_981_256_484_985_351_56e-1;
+const DP2: f64 = 3.774_894_707_930_798_176_68e-8;
+const DP3: f64 = 2.695_151_429_079_059_526_45e-15;
+const SIN: [f64; 6] = [
+    1.589_623_015_765_465_680_60e-10,
+    -2.505_074_776_285_780_728_66e-8,
+    2.755_731_362_138_572_452_13e-6,
+    -1.984_126_982_958_953_859_96e-4,
+    8.333_333_333_322_118_588_78e-3,
+    -1.666_666_666_666_663_072_95e-1,
+];
+const COS: [f64; 6] = [
+    -1.135_853_652_138_768_173_00e-11,
+    2.087_570_084_197_473_167_78e-9,
+    -2.755_731_417_929_673_881_12e-7,
+    2.480_158_728_885_170_453_48e-5,
+    -1.388_888_888_887_305_641_16e-3,
+    4.166_666_666_666_659_292_18e-2,
+];
+
+/// sin(a + quarter·π/2) for 0 ≤ a ≤ `MAX_REDUCIBLE`: a is reduced to z in
+/// [−π/4, π/4] plus k quarter turns, whose count mod 4 picks ±sin z or
+/// ±cos z
+#[inline(always)]
+fn sin_octant(a: f64, quarter: f64) -> f64 {
+    let k = round(a * (2.0 / PI));
+    let q = 2.0 * k;
+    let z = ((a - q * DP1) - q * DP2) - q * DP3;
+    let zz = z * z;
+    let sine = z + z * zz * polynomial(zz, &SIN);
+    let cosine = 1.0 - 0.5 * zz + zz * zz * polynomial(zz, &COS);
+    // (k + quarter) mod 4; the quarters are exact, so rounding x/4 − 3/8
+    // gives the floor of x/4, and likewise x/2 − 1/4 for the halves. Odd
+    // quarters swap in the cosine and the upper two negate; the blend is by
+    // multiplying with exact zeros and ones so the lanes never branch
+    let turns = k + quarter;
+    let turns = turns - 4.0 * round(turns * 0.25 - 0.375);
+    let half = round(turns * 0.5 - 0.25);
+    let odd = turns - 2.0 * half;
+    (sine * (1.0 - odd) + cosine * odd) * (1.0 - 2.0 * half)
+}
+
+/// ln 2 in two parts
+const LN2_HIGH: f64 = 6.931_457_519_531_25e-1;
+const LN2_LOW: f64 = 1.428_606_820_309_417_232_12e-6;
+const EXP_P: [f64; 3] = [1.261_771_930_748_105_908_78e-4, 3.029_944_077_074_419_613_00e-2, 9.999_999_999_999_999_999_10e-1];
+const EXP_Q: [f64; 4] = [
+    3.001_985_051_386_644_550_42e-6,
+    2.524_483_403_496_841_041_92e-3,
+    2.272_655_482_081_550_287_66e-1,
+    2.000_000_000_000_000_000_09,
+];
+
+/// eˣ as 2ⁿ e^r with |r| ≤ ln 2 / 2 and a Padé form for e^r; the power of
+/// two is applied in two halves so results down to the subnormals and up to
+/// overflow come out right
+#[inline(always)]
+fn exp_lane(x: f64) -> f64 {
+    let x = x.clamp(-746.0, 710.0);
+    let n = round(x * LOG2_E);
+    let r = x - n * LN2_HIGH - n * LN2_LOW;
+    let rr = r * r;
+    let p = r * polynomial(rr, &EXP_P);
+    let er = 1.0 + 2.0 * p / (polynomial(rr, &EXP_Q) - p);
+    let half = round(n * 0.5 - 0.25);
+    er * pow2(half) * pow2(n - half)
+}
+
+/// tan(3π/8)
+const TAN_3PI_8: f64 = 2.414_213_562_373_095_048_80;
+/// Part of π/2 below f64 precision
+const MORE_BITS: f64 = 6.123_233_995_736_765_886_130e-17;
+const ATAN_P: [f64; 5] = [
+    -8.750_608_600_031_904_122_785e-1,
+    -1.615_753_718_733_365_076_637e1,
+    -7.500_855_792_314_704_667_340e1,
+    -1.228_866_684_490_136_173_410e2,
+    -6.485_021_904_942_025_371_773e1,
+];
+const ATAN_Q: [f64; 6] = [
+    1.0,
+    2.485_846_490_142_306_297_962e1,
+    1.650_270_098_316_988_542_046e2,
+    4.328_810_604_912_902_668_951e2,
+    4.853_903_996_359_136_964_868e2,
+    1.945_506_571_482_613_964_425e2,
+];
+
+/// arctan x, reduced to |x| ≤ 0.66 through arctan x = π/2 − arctan(1/x)
+/// above tan(3π/8) and π/4 + arctan((x−1)/(x+1)) in between
+#[inline(always)]
+fn atan_lane(x: f64) -> f64 {
+    let a = x.abs();
+    let large = a > TAN_3PI_8;
+    let middle = !large && a > 0.66;
+    let (base, reduced, extra) = if large {
+        (FRAC_PI_2, -1.0 / a, MORE_BITS)
+    } else if middle {
+        (FRAC_PI_4, (a - 1.0) / (a + 1.0), 0.5 * MORE_BITS)
+    } else {
+        (0.0, a, 0.0)
+    };
+    let z = reduced * reduced;
+    let tail = z * polynomial(z, &ATAN_P) / polynomial(z, &ATAN_Q);
+    (base + (reduced * tail + reduced + extra)).copysign(x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `count` points spread over [low, high], off the round numbers
+    fn spread(low: f64, high: f64, count: usize) -> Vec<f64> {
+        (0..count).map(|i| low + (high - low) * (i as f64 + 0.37) / count as f64).collect()
+    }
+
+    /// Largest |simd − std| / max(1, |std|) over the inputs, the deviation
+    /// `benches/kernels.rs` reports
+    fn deviation(inputs: &[f64], scalar: impl Fn(f64) -> f64, lanes: impl Fn(F64x4) -> F64x4) -> f64 {
+        let vectorized = map(inputs, lanes);
+        inputs
+            .iter()
+            .zip(&vectorized)
+            .map(|(&x, simd)| (scalar(x) - simd).abs() / scalar(x).abs().max(1.0))
+            .fold(0.0, f64::max)
+    }
+
+    /// Check a function over [low, high] against the standard library's:
+    /// within one unit in the last place, the 2.2e-16 ARCHITECTURE.md
+    /// quotes, and so well within the 1e-15 promised above
+    fn check(name: &str, low: f64, high: f64, scalar: fn(f64) -> f64, lanes: fn(F64x4) -> F64x4) {
+        let deviation = deviation(&spread(low, high, 40_000), scalar, lanes);
+        assert!(deviation <= f64::EPSILON, "{name} over [{low:e}, {high:e}] deviates by {deviation:e}");
+    }
+
+    #[test]
+    fn functions_match_the_standard_library() {
+        check("sin", -1e3, 1e3, f64::sin, F64x4::sin);
+        check("cos", -1e3, 1e3, f64::cos, F64x4::cos);
+        check("sin", -1e9, 1e9, f64::sin, F64x4::sin);
+        check("cos", -1e9, 1e9, f64::cos, F64x4::cos);
+        check("exp", -700.0, 700.0, f64::exp, F64x4::exp);
+        check("atan", -50.0, 50.0, f64::atan, F64x4::atan);
+    }
+}
@@ -0,0 +1,140 @@
+//! Physical constants (CODATA 2018 and IAU, SI units), SI prefixes and
+//! conversions between particle energy and wavelength
+//!
+//! [`CONSTANTS`] lists the constants with their symbols, units and standard
+//! uncertainties, as served at `/api/v1/constants`. Simulations use the
+//! named values below rather than writing numbers inline; a change to one
+//! that alters a simulation's output needs a new simulation version.
+
+use serde::Serialize;
+
+/// Planck constant h (J·s), exact
+pub const PLANCK: f64 = 6.626_070_15e-34;
+/// Reduced Planck constant ħ = h/2π (J·s), exact (truncated)
+pub const REDUCED_PLANCK: f64 = 1.054_571_817e-34;
+/// Speed of light in vacuum c (m/s), exact
+pub const SPEED_OF_LIGHT: f64 = 299_792_458.0;
+/// Elementary charge, i.e. joules per electronvolt, exact
+pub const ELECTRON_VOLT: f64 = 1.602_176_634e-19;
+/// Electron rest mass (kg)
+pub const ELECTRON_MASS: f64 = 9.109_383_701_5e-31;
+/// Classical electron radius r_e = e²/(4πε₀mc²) (m)
+pub const CLASSICAL_ELECTRON_RADIUS: f64 = 2.817_940_326_2e-15;
+/// Proton rest mass (kg)
+pub const PROTON_MASS: f64 = 1.672_621_923_69e-27;
+/// Neutron rest mass (kg)
+pub const NEUTRON_MASS: f64 = 1.674_927_498_04e-27;
+/// Boltzmann constant k_B (J/K), exact
+pub const BOLTZMANN: f64 = 1.380_649e-23;
+/// Unified atomic mass unit (kg)
+pub const ATOMIC_MASS_UNIT: f64 = 1.660_539_066_60e-27;
+/// Stefan–Boltzmann constant σ (W·m⁻²·K⁻⁴)
+pub const STEFAN_BOLTZMANN: f64 = 5.670_374_419e-8;
+/// Wien displacement constant b (m·K), so that λ_peak = b/T
+pub const WIEN_DISPLACEMENT: f64 = 2.897_771_955e-3;
+/// Heliocentric gravitational constant GM☉ (m³/s², IAU 2015 nominal)
+pub const SOLAR_MASS_PARAMETER: f64 = 1.327_124_4e20;
+/// Astronomical unit (m), exact
+pub const ASTRONOMICAL_UNIT: f64 = 149_597_870_700.0;
+/// Vacuum electric permittivity ε₀ (F/m)
+pub const VACUUM_PERMITTIVITY: f64 = 8.854_187_812_8e-12;
+/// Vacuum magnetic permeability μ₀ (N/A²)
+pub const VACUUM_PERMEABILITY: f64 = 1.256_637_062_12e-6;
+/// Newtonian constant of gravitation G (m³·kg⁻¹·s⁻²)
+pub const GRAVITATIONAL_CONSTANT: f64 = 6.674_30e-11;
+/// Standard acceleration of gravity gₙ (m/s²), exact
+pub const STANDARD_GRAVITY: f64 = 9.806_65;
+/// Avogadro constant N_A (1/mol), exact
+pub const AVOGADRO: f64 = 6.022_140_76e23;
+/// Molar gas constant R (J·mol⁻¹·K⁻¹), exact (truncated)
+pub const GAS_CONSTANT: f64 = 8.314_462_618;
+/// Fine-structure constant α
+pub const FINE_STRUCTURE: f64 = 7.297_352_569_3e-3;
+/// Bohr radius a₀ (m)
+pub const BOHR_RADIUS: f64 = 5.291_772_109_03e-11;
+/// Bohr magneton μ_B (J/T)
+pub const BOHR_MAGNETON: f64 = 9.274_010_078_3e-24;
+/// Rydberg energy R∞hc (eV), the ionization energy of hydrogen with an
+/// infinitely heavy nucleus
+pub const RYDBERG_ENERGY: f64 = 13.605_693_122_994;
+/// Seconds in a day
+pub const DAY: f64 = 86_400.0;
+/// Barn, the unit of nuclear cross sections (m²)
+pub const BARN: f64 = 1e-28;
+
+// Working values in the units the quantum simulations compute in, rounded
+// when those simulations were written. They are part of the simulations'
+// versioned computations, so they are kept as they are rather than
+// recomputed from the constants above.
+
+/// ħ²/2mₑ (eV·nm²): the kinetic energy of an electron is this times k²
+/// with k in 1/nm
+pub const ELECTRON_KINETIC: f64 = 0.038_099_821;
+/// ħ (eV·fs)
+pub const REDUCED_PLANCK_EV_FS: f64 = 0.658_211_957;
+
+// SI prefixes, for converting parameters given in, say, millimetres or
+// nanometres to SI units and back. Converting back multiplies by the
+// reciprocal prefix (`x * GIGA` for metres to nanometres), which gives
+// the same results as the literals the simulations were written with.
+
+pub const NANO: f64 = 1e-9;
+pub const MICRO: f64 = 1e-6;
+pub const MILLI: f64 = 1e-3;
+pub const CENTI: f64 = 1e-2;
+pub const HECTO: f64 = 1e2;
+pub const KILO: f64 = 1e3;
+pub const MEGA: f64 = 1e6;
+pub const GIGA: f64 = 1e9;
+pub const TERA: f64 = 1e12;
+
+/// A physical constant as served by the API
+#[derive(Serialize)]
+pub struct Constant {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub symbol: &'static str,
+    pub value: f64,
+    /// Standard uncertainty in the same unit; 0 for exact values
+    pub uncertainty: f64,
+    /// SI unit, empty for dimensionless constants
+    pub unit: &'static str,
+}
+
+/// Constants the simulations are built on, with CODATA 2018 uncertainties
+pub const CONSTANTS: &[Constant] = &[
+    Constant { id: "speed_of_light", name: "Speed of light in vacuum", symbol: "c", value: SPEED_OF_LIGHT, uncertainty: 0.0, unit: "m s^-1" },
+    Constant { id: "planck", name: "Planck constant", symbol: "h", value: PLANCK, uncertainty: 0.0, unit: "J s" },
+    Constant { id: "reduced_planck", name: "Reduced Planck constant", symbol: "ħ", value: REDUCED_PLANCK, uncertainty: 0.0, unit: "J s" },
+    Constant { id: "elementary_charge", name: "Elementary charge", symbol: "e", value: ELECTRON_VOLT, uncertainty: 0.0, unit: "C" },
+    Constant { id: "boltzmann", name: "Boltzmann constant", symbol: "k_B", value: BOLTZMANN, uncertainty: 0.0, unit: "J K^-1" },
+    Constant { id: "avogadro", name: "Avogadro constant", symbol: "N_A", value: AVOGADRO, uncertainty: 0.0, unit: "mol^-1" },
+    Constant { id: "gas_constant", name: "Molar gas constant", symbol: "R", value: GAS_CONSTANT, uncertainty: 0.0, unit: "J mol^-1 K^-1" },
+    Constant { id: "vacuum_permittivity", name: "Vacuum electric permittivity", symbol: "ε₀", value: VACUUM_PERMITTIVITY, uncertainty: 1.3e-21, unit: "F m^-1" },
+    Constant { id: "vacuum_permeability", name: "Vacuum magnetic permeability", symbol: "μ₀", value: VACUUM_PERMEABILITY, uncertainty: 1.9e-16, unit: "N A^-2" },
+    Constant { id: "gravitational_constant", name: "Newtonian constant of gravitation", symbol: "G", value: GRAVITATIONAL_CONSTANT, uncertainty: 1.5e-15, unit: "m^3 kg^-1 s^-2" },
+    Constant { id: "standard_gravity", name: "Standard acceleration of gravity", symbol: "g_n", value: STANDARD_GRAVITY, uncertainty: 0.0, unit: "m s^-2" },
+    Constant { id: "electron_mass", name: "Electron mass", symbol: "m_e", value: ELECTRON_MASS, uncertainty: 2.8e-40, unit: "kg" },
+    Constant { id: "proton_mass", name: "Proton mass", symbol: "m_p", value: PROTON_MASS, uncertainty: 5.1e-37, unit: "kg" },
+    Constant { id: "neutron_mass", name: "Neutron mass", symbol: "m_n", value: NEUTRON_MASS, uncertainty: 9.5e-37, unit: "kg" },
+    Constant { id: "atomic_mass_unit", name: "Atomic mass constant", symbol: "m_u", value: ATOMIC_MASS_UNIT, uncertainty: 5.0e-37, unit: "kg" },
+    Constant { id: "fine_structure", name: "Fine-structure constant", symbol: "α", value: FINE_STRUCTURE, uncertainty: 1.1e-12, unit: "" },
+    Constant { id: "classical_electron_radius", name: "Classical electron radius", symbol: "r_e", value: CLASSICAL_ELECTRON_RADIUS, uncertainty: 1.3e-24, unit: "m" },
+    Constant { id: "bohr_radius", name: "Bohr radius", symbol: "a₀", value: BOHR_RADIUS, uncertainty: 8.0e-21, unit: "m" },
+    Constant { id: "bohr_magneton", name: "Bohr magneton", symbol: "μ_B", value: BOHR_MAGNETON, uncertainty: 2.8e-33, unit: "J T^-1" },
+    Constant { id: "rydberg_energy", name: "Rydberg energy", symbol: "R∞hc", value: RYDBERG_ENERGY, uncertainty: 2.6e-11, unit: "eV" },
+    Constant { id: "stefan_boltzmann", name: "Stefan–Boltzmann constant", symbol: "σ", value: STEFAN_BOLTZMANN, uncertainty: 0.0, unit: "W m^-2 K^-4" },
+    Constant { id: "wien_displacement", name: "Wien wavelength displacement law constant", symbol: "b", value: WIEN_DISPLACEMENT, uncertainty: 0.0, unit: "m K" },
+    Constant { id: "solar_mass_parameter", name: "Nominal solar mass parameter (IAU 2015)", symbol: "GM☉", value: SOLAR_MASS_PARAMETER, uncertainty: 0.0, unit: "m^3 s^-2" },
+    Constant { id: "astronomical_unit", name: "Astronomical unit", symbol: "au", value: ASTRONOMICAL_UNIT, uncertainty: 0.0, unit: "m" },
+];
+
+/// de Broglie wavelength (m) of a massive particle with the given kinetic
+/// energy (eV): λ = h/p. Uses the relativistic momentum
+/// pc = √(K² + 2Kmc²), which reduces to p = √(2mK) when K ≪ mc².
+pub fn de_broglie_wavelength(mass_kg: f64, kinetic_energy_ev: f64) -> f64 {
+    let kinetic = kinetic_energy_ev * ELECTRON_VOLT;
+    let rest = mass_kg * SPEED_OF_LIGHT * SPEED_OF_LIGHT;
+    let momentum = (kinetic * kinetic + 2.0 * kinetic * rest).sqrt() / SPEED_OF_LIGHT;
+    PLANCK / momentum
+}
@@ -0,0 +1,32 @@
+//! Reference data for materials used by the simulations
+
+use serde::Serialize;
+
+/// A metal and its photoelectric work function
+#[derive(Serialize)]
+pub struct Metal {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub symbol: &'static str,
+    /// Work function (eV) of a clean polycrystalline surface
+    pub work_function: f64,
+}
+
+/// Typical work functions, in order of increasing work function
+pub const METALS: &[Metal] = &[
+    Metal { id: "cesium", name: "Cesium", symbol: "Cs", work_function: 2.14 },
+    Metal { id: "potassium", name: "Potassium", symbol: "K", work_function: 2.29 },
+    Metal { id: "sodium", name: "Sodium", symbol: "Na", work_function: 2.36 },
+    Metal { id: "calcium", name: "Calcium", symbol: "Ca", work_function: 2.87 },
+    Metal { id: "silver", name: "Silver", symbol: "Ag", work_function: 4.26 },
+    Metal { id: "aluminium", name: "Aluminium", symbol: "Al", work_function: 4.28 },
+    Metal { id: "zinc", name: "Zinc", symbol: "Zn", work_function: 4.33 },
+    Metal { id: "iron", name: "Iron", symbol: "Fe", work_function: 4.5 },
+    Metal { id: "copper", name: "Copper", symbol: "Cu", work_function: 4.65 },
+    Metal { id: "gold", name: "Gold", symbol: "Au", work_function: 5.1 },
+    Metal { id: "platinum", name: "Platinum", symbol: "Pt", work_function: 5.65 },
+];
+
+pub fn find_metal(id: &str) -> Option<&'static Metal> {
+    METALS.iter().find(|metal| metal.id == id)
+}
@@ -0,0 +1,6 @@
+// Physical constants and unit conversions shared by simulations
+
+pub mod constants;
+pub mod materials;
+pub mod optics;
+pub mod units;
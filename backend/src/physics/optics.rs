@@ -0,0 +1,179 @@
+//! 2D ray tracing through coaxial refracting surfaces
+//!
+//! The optical axis is the x axis and light travels toward +x. A surface's
+//! radius of curvature is positive when its centre lies to the right of the
+//! vertex; a radius of zero means a flat surface.
+
+use serde::Serialize;
+
+/// A refracting surface between two media
+#[derive(Clone, Copy, Debug)]
+pub struct Surface {
+    /// Vertex position on the axis
+    pub vertex: f64,
+    pub radius: f64,
+    /// Half-height of the clear aperture
+    pub aperture: f64,
+    /// Refractive index to the right of the surface
+    pub index_after: f64,
+}
+
+/// Coaxial surfaces in order along the axis
+pub struct OpticalSystem {
+    /// Refractive index to the left of the first surface
+    pub initial_index: f64,
+    pub surfaces: Vec<Surface>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RayStatus {
+    Transmitted,
+    /// Missed a surface or hit outside its aperture
+    Blocked,
+    TotalInternalReflection,
+}
+
+/// Path of one ray as the points where it meets each surface
+#[derive(Serialize)]
+pub struct TracedRay {
+    pub points: Vec<[f64; 2]>,
+    pub status: RayStatus,
+    /// Unit direction after the last surface reached
+    #[serde(skip)]
+    pub direction: [f64; 2],
+}
+
+impl TracedRay {
+    /// Height where the outgoing ray crosses the plane at `x`
+    pub fn height_at(&self, x: f64) -> Option<f64> {
+        let [x0, y0] = *self.points.last()?;
+        (self.direction[0] > 0.0).then(|| y0 + (x - x0) * self.direction[1] / self.direction[0])
+    }
+
+    /// Where the outgoing ray crosses the optical axis
+    pub fn axis_crossing(&self) -> Option<f64> {
+        let [x0, y0] = *self.points.last()?;
+        (self.direction[1] != 0.0).then(|| x0 - y0 * self.direction[0] / self.direction[1])
+    }
+}
+
+/// Paraxial ray transfer matrix acting on (height, n × angle)
+pub type Matrix = [[f64; 2]; 2];
+
+impl OpticalSystem {
+    /// Exact trace of a ray from `origin` along `direction`, extended to the
+    /// plane x = `end` if it makes it through every surface
+    pub fn trace(&self, origin: [f64; 2], direction: [f64; 2], end: f64) -> TracedRay {
+        let mut position = origin;
+        let mut direction = normalize(direction);
+        let mut index = self.initial_index;
+        let mut points = vec![origin];
+
+        for surface in &self.surfaces {
+            let Some(hit) = intersect(surface, position, direction) else {
+                return TracedRay { points, status: RayStatus::Blocked, direction };
+            };
+            points.push(hit);
+            if hit[1].abs() > surface.aperture {
+                return TracedRay { points, status: RayStatus::Blocked, direction };
+            }
+            let Some(refracted) = refract(direction, normal(surface, hit), index / surface.index_after) else {
+                return TracedRay { points, status: RayStatus::TotalInternalReflection, direction };
+            };
+            position = hit;
+            direction = refracted;
+            index = surface.index_after;
+        }
+
+        let mut ray = TracedRay { points, status: RayStatus::Transmitted, direction };
+        if let Some(y) = ray.height_at(end) {
+            ray.points.push([end, y]);
+        }
+        ray
+    }
+
+    /// Paraxial matrix from the first vertex to the last
+    pub fn system_matrix(&self) -> Matrix {
+        let mut matrix = [[1.0, 0.0], [0.0, 1.0]];
+        let mut index = self.initial_index;
+        let mut vertex = self.surfaces.first().map_or(0.0, |s| s.vertex);
+        for surface in &self.surfaces {
+            let gap = surface.vertex - vertex;
+            matrix = multiply(&[[1.0, gap / index], [0.0, 1.0]], &matrix);
+            let power = if surface.radius == 0.0 { 0.0 } else { (surface.index_after - index) / surface.radius };
+            matrix = multiply(&[[1.0, 0.0], [-power, 1.0]], &matrix);
+            index = surface.index_after;
+            vertex = surface.vertex;
+        }
+        matrix
+    }
+
+    pub fn final_index(&self) -> f64 {
+        self.surfaces.last().map_or(self.initial_index, |s| s.index_after)
+    }
+}
+
+fn intersect(surface: &Surface, origin: [f64; 2], direction: [f64; 2]) -> Option<[f64; 2]> {
+    if surface.radius == 0.0 {
+        if direction[0] <= 0.0 {
+            return None;
+        }
+        let t = (surface.vertex - origin[0]) / direction[0];
+        return Some([surface.vertex, origin[1] + t * direction[1]]);
+    }
+    // |p − c|² = R² with p = origin + t·direction; take the crossing on the
+    // vertex side of the sphere
+    let centre = surface.vertex + surface.radius;
+    let offset = [origin[0] - centre, origin[1]];
+    let b = offset[0] * direction[0] + offset[1] * direction[1];
+    let c = offset[0] * offset[0] + offset[1] * offset[1] - surface.radius * surface.radius;
+    let discriminant = b * b - c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let root = discriminant.sqrt();
+    let t = if surface.radius > 0.0 { -b - root } else { -b + root };
+    (t > 0.0).then(|| [origin[0] + t * direction[0], origin[1] + t * direction[1]])
+}
+
+/// Unit normal at `point`, pointing back toward incoming light (−x side)
+fn normal(surface: &Surface, point: [f64; 2]) -> [f64; 2] {
+    if surface.radius == 0.0 {
+        return [-1.0, 0.0];
+    }
+    let centre = surface.vertex + surface.radius;
+    let outward = normalize([point[0] - centre, point[1]]);
+    if outward[0] <= 0.0 {
+        outward
+    } else {
+        [-outward[0], -outward[1]]
+    }
+}
+
+/// Vector form of Snell's law with `ratio` = n₁/n₂; None on total internal
+/// reflection
+fn refract(direction: [f64; 2], normal: [f64; 2], ratio: f64) -> Option<[f64; 2]> {
+    let cos_i = -(direction[0] * normal[0] + direction[1] * normal[1]);
+    let k = 1.0 - ratio * ratio * (1.0 - cos_i * cos_i);
+    if k < 0.0 {
+        return None;
+    }
+    let scale = ratio * cos_i - k.sqrt();
+    Some(normalize([
+        ratio * direction[0] + scale * normal[0],
+        ratio * direction[1] + scale * normal[1],
+    ]))
+}
+
+fn normalize(v: [f64; 2]) -> [f64; 2] {
+    let length = v[0].hypot(v[1]);
+    [v[0] / length, v[1] / length]
+}
+
+fn multiply(a: &Matrix, b: &Matrix) -> Matrix {
+    [
+        [a[0][0] * b[0][0] + a[0][1] * b[1][0], a[0][0] * b[0][1] + a[0][1] * b[1][1]],
+        [a[1][0] * b[0][0] + a[1][1] * b[1][0], a[1][0] * b[0][1] + a[1][1] * b[1][1]],
+    ]
+}
@@ -0,0 +1,239 @@
+//! Units of simulation parameters and conversions between them
+//!
+//! Content files declare the unit each numeric parameter is computed in.
+//! Run requests may give a value in any other unit of the same dimension,
+//! as `{"value": 0.55, "unit": "μm"}` or `"0.55 μm"`; it is converted to
+//! the declared unit before validation, so ranges, cache keys and stored
+//! results always use the declared unit. [`UNITS`] is served at
+//! `/api/v1/units`.
+
+use std::f64::consts::PI;
+
+use serde::Serialize;
+
+use super::constants::{
+    ASTRONOMICAL_UNIT, DAY, ELECTRON_VOLT, GRAVITATIONAL_CONSTANT, SOLAR_MASS_PARAMETER, SPEED_OF_LIGHT,
+};
+use super::constants::{CENTI, GIGA, KILO, MEGA, MICRO, MILLI, NANO, TERA};
+
+/// Significant digits kept after a conversion, enough for any slider step
+/// while dropping rounding noise such as 550.0000000000001
+const CONVERTED_DIGITS: usize = 12;
+
+/// What a unit measures; only units of the same dimension convert
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Dimension {
+    Length,
+    Area,
+    Time,
+    Frequency,
+    Wavenumber,
+    Energy,
+    Mass,
+    Temperature,
+    Angle,
+    Velocity,
+    Acceleration,
+    Force,
+    Voltage,
+    ElectricField,
+    MagneticField,
+    Resistance,
+    Capacitance,
+    Inductance,
+    Irradiance,
+    Diffusivity,
+    LinearDensity,
+}
+
+impl Dimension {
+    /// Lower-case name for messages, e.g. "electric field"
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Length => "length",
+            Self::Area => "area",
+            Self::Time => "time",
+            Self::Frequency => "frequency",
+            Self::Wavenumber => "wavenumber",
+            Self::Energy => "energy",
+            Self::Mass => "mass",
+            Self::Temperature => "temperature",
+            Self::Angle => "angle",
+            Self::Velocity => "velocity",
+            Self::Acceleration => "acceleration",
+            Self::Force => "force",
+            Self::Voltage => "voltage",
+            Self::ElectricField => "electric field",
+            Self::MagneticField => "magnetic field",
+            Self::Resistance => "resistance",
+            Self::Capacitance => "capacitance",
+            Self::Inductance => "inductance",
+            Self::Irradiance => "irradiance",
+            Self::Diffusivity => "diffusivity",
+            Self::LinearDensity => "linear density",
+        }
+    }
+}
+
+/// A unit as served by the API: a value `x` in it is `x * scale + offset`
+/// in the SI unit of its dimension
+#[derive(Debug, Serialize)]
+pub struct Unit {
+    pub symbol: &'static str,
+    pub name: &'static str,
+    pub dimension: Dimension,
+    pub scale: f64,
+    /// Non-zero only for temperatures on the Celsius and Fahrenheit scales
+    pub offset: f64,
+    /// Other spellings accepted in requests, such as `um` for `μm`
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    pub aliases: &'static [&'static str],
+}
+
+impl Unit {
+    fn to_si(&self, value: f64) -> f64 {
+        value * self.scale + self.offset
+    }
+
+    fn si_to_unit(&self, value: f64) -> f64 {
+        (value - self.offset) / self.scale
+    }
+}
+
+const fn unit(symbol: &'static str, name: &'static str, dimension: Dimension, scale: f64) -> Unit {
+    Unit { symbol, name, dimension, scale, offset: 0.0, aliases: &[] }
+}
+
+const fn aliased(
+    symbol: &'static str,
+    name: &'static str,
+    dimension: Dimension,
+    scale: f64,
+    aliases: &'static [&'static str],
+) -> Unit {
+    Unit { symbol, name, dimension, scale, offset: 0.0, aliases }
+}
+
+/// Julian year (s), as used for light years
+const YEAR: f64 = 365.25 * DAY;
+/// Nominal solar mass (kg)
+const SOLAR_MASS: f64 = SOLAR_MASS_PARAMETER / GRAVITATIONAL_CONSTANT;
+
+/// Every unit parameters may be declared or given in, SI unit first
+/// within each dimension
+pub const UNITS: &[Unit] = &[
+    unit("m", "metre", Dimension::Length, 1.0),
+    unit("km", "kilometre", Dimension::Length, KILO),
+    unit("cm", "centimetre", Dimension::Length, CENTI),
+    unit("mm", "millimetre", Dimension::Length, MILLI),
+    aliased("μm", "micrometre", Dimension::Length, MICRO, &["um", "µm"]),
+    unit("nm", "nanometre", Dimension::Length, NANO),
+    aliased("Å", "ångström", Dimension::Length, 1e-10, &["angstrom"]),
+    aliased("AU", "astronomical unit", Dimension::Length, ASTRONOMICAL_UNIT, &["au"]),
+    aliased("m²", "square metre", Dimension::Area, 1.0, &["m^2", "m2"]),
+    aliased("cm²", "square centimetre", Dimension::Area, CENTI * CENTI, &["cm^2", "cm2"]),
+    aliased("mm²", "square millimetre", Dimension::Area, MILLI * MILLI, &["mm^2", "mm2"]),
+    aliased("nm²", "square nanometre", Dimension::Area, NANO * NANO, &["nm^2", "nm2"]),
+    unit("s", "second", Dimension::Time, 1.0),
+    unit("ms", "millisecond", Dimension::Time, MILLI),
+    aliased("μs", "microsecond", Dimension::Time, MICRO, &["us", "µs"]),
+    unit("ns", "nanosecond", Dimension::Time, NANO),
+    unit("fs", "femtosecond", Dimension::Time, 1e-15),
+    unit("min", "minute", Dimension::Time, 60.0),
+    unit("h", "hour", Dimension::Time, 3_600.0),
+    unit("d", "day", Dimension::Time, DAY),
+    aliased("yr", "year", Dimension::Time, YEAR, &["a", "years"]),
+    unit("Hz", "hertz", Dimension::Frequency, 1.0),
+    unit("kHz", "kilohertz", Dimension::Frequency, KILO),
+    unit("MHz", "megahertz", Dimension::Frequency, MEGA),
+    unit("GHz", "gigahertz", Dimension::Frequency, GIGA),
+    unit("THz", "terahertz", Dimension::Frequency, TERA),
+    aliased("10¹⁴ Hz", "10¹⁴ hertz", Dimension::Frequency, 1e14, &["1e14 Hz"]),
+    aliased("1/s", "per second", Dimension::Frequency, 1.0, &["s^-1"]),
+    aliased("1/m", "per metre", Dimension::Wavenumber, 1.0, &["m^-1"]),
+    aliased("1/cm", "per centimetre", Dimension::Wavenumber, 1.0 / CENTI, &["cm^-1"]),
+    aliased("1/nm", "per nanometre", Dimension::Wavenumber, GIGA, &["nm^-1"]),
+    unit("J", "joule", Dimension::Energy, 1.0),
+    unit("eV", "electronvolt", Dimension::Energy, ELECTRON_VOLT),
+    unit("meV", "millielectronvolt", Dimension::Energy, MILLI * ELECTRON_VOLT),
+    unit("keV", "kiloelectronvolt", Dimension::Energy, KILO * ELECTRON_VOLT),
+    unit("MeV", "megaelectronvolt", Dimension::Energy, MEGA * ELECTRON_VOLT),
+    unit("kg", "kilogram", Dimension::Mass, 1.0),
+    unit("g", "gram", Dimension::Mass, MILLI),
+    aliased("M☉", "solar mass", Dimension::Mass, SOLAR_MASS, &["Msun"]),
+    unit("K", "kelvin", Dimension::Temperature, 1.0),
+    Unit {
+        symbol: "°C",
+        name: "degree Celsius",
+        dimension: Dimension::Temperature,
+        scale: 1.0,
+        offset: 273.15,
+        aliases: &["degC"],
+    },
+    Unit {
+        symbol: "°F",
+        name: "degree Fahrenheit",
+        dimension: Dimension::Temperature,
+        scale: 5.0 / 9.0,
+        offset: 273.15 - 32.0 * 5.0 / 9.0,
+        aliases: &["degF"],
+    },
+    unit("rad", "radian", Dimension::Angle, 1.0),
+    unit("mrad", "milliradian", Dimension::Angle, MILLI),
+    aliased("°", "degree", Dimension::Angle, PI / 180.0, &["deg"]),
+    aliased("m/s", "metre per second", Dimension::Velocity, 1.0, &["m s^-1"]),
+    aliased("km/s", "kilometre per second", Dimension::Velocity, KILO, &["km s^-1"]),
+    aliased("km/h", "kilometre per hour", Dimension::Velocity, KILO / 3_600.0, &["km h^-1"]),
+    unit("c", "speed of light", Dimension::Velocity, SPEED_OF_LIGHT),
+    aliased("m/s²", "metre per second squared", Dimension::Acceleration, 1.0, &["m/s^2", "m s^-2"]),
+    unit("N", "newton", Dimension::Force, 1.0),
+    unit("V", "volt", Dimension::Voltage, 1.0),
+    unit("mV", "millivolt", Dimension::Voltage, MILLI),
+    unit("kV", "kilovolt", Dimension::Voltage, KILO),
+    aliased("V/m", "volt per metre", Dimension::ElectricField, 1.0, &["V m^-1"]),
+    aliased("kV/m", "kilovolt per metre", Dimension::ElectricField, KILO, &["kV m^-1"]),
+    unit("T", "tesla", Dimension::MagneticField, 1.0),
+    unit("mT", "millitesla", Dimension::MagneticField, MILLI),
+    aliased("μT", "microtesla", Dimension::MagneticField, MICRO, &["uT", "µT"]),
+    unit("G", "gauss", Dimension::MagneticField, 1e-4),
+    aliased("Ω", "ohm", Dimension::Resistance, 1.0, &["ohm"]),
+    aliased("kΩ", "kiloohm", Dimension::Resistance, KILO, &["kohm"]),
+    unit("F", "farad", Dimension::Capacitance, 1.0),
+    unit("mF", "millifarad", Dimension::Capacitance, MILLI),
+    aliased("μF", "microfarad", Dimension::Capacitance, MICRO, &["uF", "µF"]),
+    unit("nF", "nanofarad", Dimension::Capacitance, NANO),
+    unit("H", "henry", Dimension::Inductance, 1.0),
+    unit("mH", "millihenry", Dimension::Inductance, MILLI),
+    aliased("μH", "microhenry", Dimension::Inductance, MICRO, &["uH", "µH"]),
+    aliased("W/m²", "watt per square metre", Dimension::Irradiance, 1.0, &["W/m^2", "W m^-2"]),
+    aliased("m²/s", "square metre per second", Dimension::Diffusivity, 1.0, &["m^2/s", "m^2 s^-1"]),
+    aliased("mm²/s", "square millimetre per second", Dimension::Diffusivity, MILLI * MILLI, &["mm^2/s", "mm^2 s^-1"]),
+    aliased("kg/m", "kilogram per metre", Dimension::LinearDensity, 1.0, &["kg m^-1"]),
+    aliased("g/m", "gram per metre", Dimension::LinearDensity, MILLI, &["g m^-1"]),
+];
+
+/// The unit with this symbol or alias
+pub fn find(symbol: &str) -> Option<&'static Unit> {
+    UNITS.iter().find(|unit| unit.symbol == symbol || unit.aliases.contains(&symbol))
+}
+
+/// Convert `value` in unit `from` to the declared unit `to`, or say why it
+/// cannot be
+pub fn convert(value: f64, from: &str, to: &'static Unit) -> Result<f64, String> {
+    let from = find(from).ok_or_else(|| format!("unknown unit `{from}`"))?;
+    if from.dimension != to.dimension {
+        return Err(format!(
+            "`{}` is a unit of {}, expected {} in {}",
+            from.symbol,
+            from.dimension.name(),
+            to.dimension.name(),
+            to.symbol
+        ));
+    }
+    if std::ptr::eq(from, to) {
+        return Ok(value);
+    }
+    let converted = to.si_to_unit(from.to_si(value));
+    Ok(format!("{converted:.prec$e}", prec = CONVERTED_DIGITS - 1).parse().unwrap_or(converted))
+}
@@ -0,0 +1,82 @@
+use axum::{extract::State, Json};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::models::achievement;
+use crate::routes::auth::AuthUser;
+use crate::routes::error::ApiError;
+use crate::services::achievements::{streak, Event, Streak};
+use crate::state::AppState;
+
+/// The signed-in learner's experience points, streaks and every badge,
+/// earned or not, with their progress towards it
+pub async fn my_achievements(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> Result<Json<AchievementSummary>, ApiError> {
+    let standing = achievement::standing(&state.db, &user.id).await?;
+    let badges: Vec<BadgeStatus> = state
+        .achievements
+        .iter()
+        .map(|badge| {
+            let earned = standing.badges.iter().find(|earned| earned.badge_id == badge.id);
+            let progress = standing.progress.iter().find(|(id, _)| *id == badge.id).map_or(0, |(_, count)| *count);
+            BadgeStatus {
+                id: badge.id.clone(),
+                name: badge.name.clone(),
+                description: badge.description.clone(),
+                icon: badge.icon.clone(),
+                xp: badge.xp,
+                earned_at: earned.map(|earned| earned.earned_at),
+                progress: progress.min(badge.count),
+                needed: badge.count,
+            }
+        })
+        .collect();
+    Ok(Json(AchievementSummary {
+        xp: standing.activity_xp + standing.badges.iter().map(|earned| earned.xp).sum::<u32>(),
+        streak: streak(&standing),
+        earned: badges.iter().filter(|badge| badge.earned_at.is_some()).count(),
+        badges,
+    }))
+}
+
+/// Record what a signed-in learner did, off the request path; failures
+/// are logged and never fail the request
+pub(crate) fn emit(state: &AppState, user_id: String, event: Event) {
+    let (achievements, db) = (state.achievements.clone(), state.db.clone());
+    tokio::spawn(async move {
+        match achievements.record(&db, &user_id, &event).await {
+            Ok(earned) => {
+                for badge in earned {
+                    tracing::info!("User {} earned the {} badge", user_id, badge.id);
+                }
+            }
+            Err(err) => tracing::error!("Failed to record achievements for {}: {}", user_id, err),
+        }
+    });
+}
+
+#[derive(Serialize)]
+pub struct AchievementSummary {
+    /// Points from activity and badges
+    pub xp: u32,
+    pub streak: Streak,
+    /// Badges earned so far
+    pub earned: usize,
+    pub badges: Vec<BadgeStatus>,
+}
+
+#[derive(Serialize)]
+pub struct BadgeStatus {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub icon: String,
+    pub xp: u32,
+    /// When the learner earned it; absent while it is locked
+    pub earned_at: Option<DateTime<Utc>>,
+    /// Simulations the condition has been met on, up to `needed`
+    pub progress: u32,
+    pub needed: u32,
+}
@@ -0,0 +1,287 @@
+use std::collections::BTreeMap;
+
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::numerics::fft::Window;
+use crate::routes::error::ApiError;
+use crate::services::expression::{is_constant, Expression};
+use crate::services::fitting::{fit, CurveFit, Model};
+use crate::services::spectrum::{analyze, Spectrum};
+use crate::services::statistics::{default_bins, propagate, Propagation, Quantity, Summary};
+use crate::services::trace;
+use crate::simulations::params::ParameterError;
+
+/// Most points one fit may take
+const MAX_FIT_POINTS: usize = 10_000;
+/// Most values, over all arrays, one statistics request may take
+const MAX_STATS_VALUES: usize = 100_000;
+/// Most histogram bins
+const MAX_BINS: usize = 200;
+/// Most samples one spectrum may take
+const MAX_FFT_SAMPLES: usize = 1 << 18;
+
+#[derive(Deserialize)]
+pub struct FitRequest {
+    pub model: Model,
+    pub x: Vec<f64>,
+    pub y: Vec<f64>,
+    /// One standard deviation of each y, if measured
+    #[serde(default)]
+    pub uncertainty: Option<Vec<f64>>,
+    /// Starting values by parameter name, for fits the automatic guess
+    /// sends to the wrong minimum
+    #[serde(default)]
+    pub initial: BTreeMap<String, f64>,
+}
+
+/// Fit a standard model to x/y data, such as an exported result or a lab
+/// measurement, by least squares.
+///
+/// The response gives each fitted parameter with its standard uncertainty,
+/// the χ² of the fit and the residual of every point.
+pub async fn fit_curve(Json(request): Json<FitRequest>) -> Result<Json<CurveFit>, ApiError> {
+    let errors = problems(&request);
+    if !errors.is_empty() {
+        return Err(ApiError::InvalidParameters(errors));
+    }
+    let result = trace::spawn_blocking(move || {
+        fit(request.model, &request.x, &request.y, request.uncertainty.as_deref(), &request.initial)
+    })
+    .await
+    .map_err(|e| {
+        tracing::error!("Curve fit failed: {}", e);
+        ApiError::Internal
+    })?;
+    Ok(Json(result))
+}
+
+#[derive(Deserialize)]
+pub struct StatsRequest {
+    /// Repeated measurements of each quantity, by name
+    #[serde(default)]
+    pub measurements: BTreeMap<String, Vec<f64>>,
+    /// Quantities known to within an uncertainty, by name
+    #[serde(default)]
+    pub quantities: BTreeMap<String, Quantity>,
+    /// Histogram bins; Sturges' rule for each array by default
+    #[serde(default)]
+    pub bins: Option<usize>,
+    /// Formula over the measurement and quantity names to propagate the
+    /// uncertainties through
+    #[serde(default)]
+    pub propagate: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct StatsResponse {
+    pub measurements: BTreeMap<String, Summary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub propagation: Option<Propagation>,
+}
+
+/// Summarize arrays of lab measurements and propagate their uncertainties
+/// through a formula.
+///
+/// Each array gets its mean, median, standard deviation, standard error and
+/// a histogram. In `propagate`, a measured quantity stands for its mean
+/// with the standard error as its uncertainty.
+pub async fn compute_statistics(Json(request): Json<StatsRequest>) -> Result<Json<StatsResponse>, ApiError> {
+    let mut errors = Vec::new();
+    if request.measurements.is_empty() && request.propagate.is_none() {
+        errors.push(ParameterError::new("measurements", "must hold at least one array of measurements", None));
+    }
+    let total: usize = request.measurements.values().map(Vec::len).sum();
+    if total > MAX_STATS_VALUES {
+        errors.push(ParameterError::new(
+            "measurements",
+            format!("holds {total} values; the limit is {MAX_STATS_VALUES}"),
+            None,
+        ));
+    }
+    for (name, values) in &request.measurements {
+        if values.is_empty() {
+            errors.push(ParameterError::new(&format!("measurements.{name}"), "must not be empty", None));
+        }
+    }
+    for name in request.measurements.keys().chain(request.quantities.keys()) {
+        if !is_name(name) {
+            errors.push(ParameterError::new(
+                name,
+                "must be a name usable in formulas: letters, digits and _, not starting with a digit",
+                None,
+            ));
+        }
+    }
+    for (name, quantity) in &request.quantities {
+        if request.measurements.contains_key(name) {
+            errors.push(ParameterError::new(
+                &format!("quantities.{name}"),
+                "is also the name of a measurement array",
+                None,
+            ));
+        }
+        if quantity.uncertainty < 0.0 {
+            errors.push(ParameterError::new(
+                &format!("quantities.{name}.uncertainty"),
+                "must not be negative",
+                Some(&json!(quantity.uncertainty)),
+            ));
+        }
+    }
+    if let Some(bins) = request.bins {
+        if !(1..=MAX_BINS).contains(&bins) {
+            errors.push(ParameterError::new(
+                "bins",
+                format!("must be between 1 and {MAX_BINS}"),
+                Some(&json!(bins)),
+            ));
+        }
+    }
+    let formula = match &request.propagate {
+        Some(text) => match Expression::parse(text) {
+            Ok(expression) => {
+                for name in expression.variables() {
+                    let known = request.measurements.contains_key(name) || request.quantities.contains_key(name);
+                    if !known && !is_constant(name) {
+                        errors.push(ParameterError::new(
+                            "propagate",
+                            format!("uses `{name}`, which is neither a measurement nor a quantity"),
+                            Some(&json!(text)),
+                        ));
+                    }
+                }
+                Some((text, expression))
+            }
+            Err(message) => {
+                errors.push(ParameterError::new("propagate", message, Some(&json!(text))));
+                None
+            }
+        },
+        None => None,
+    };
+    if !errors.is_empty() {
+        return Err(ApiError::InvalidParameters(errors));
+    }
+
+    let measurements: BTreeMap<String, Summary> = request
+        .measurements
+        .iter()
+        .map(|(name, values)| {
+            let bins = request.bins.unwrap_or_else(|| default_bins(values.len()));
+            (name.clone(), Summary::of(values, bins))
+        })
+        .collect();
+    let propagation = formula.map(|(text, expression)| {
+        let mut quantities = request.quantities.clone();
+        quantities.extend(measurements.iter().map(|(name, summary)| (name.clone(), summary.quantity())));
+        propagate(text, &expression, &quantities)
+    });
+    Ok(Json(StatsResponse {
+        measurements,
+        propagation,
+    }))
+}
+
+#[derive(Deserialize)]
+pub struct FftRequest {
+    /// Equally spaced samples, e.g. a recorded sound or sensor readings
+    pub signal: Vec<f64>,
+    /// Samples per unit time, such as Hz for samples taken each second
+    #[serde(default = "default_sample_rate")]
+    pub sample_rate: f64,
+    #[serde(default = "default_window")]
+    pub window: String,
+}
+
+fn default_sample_rate() -> f64 {
+    1.0
+}
+
+fn default_window() -> String {
+    Window::Hann.name().to_string()
+}
+
+/// Fourier transform of a sampled signal.
+///
+/// The response gives the one-sided amplitude, phase and power spectral
+/// density at each frequency from zero to Nyquist, and the strongest
+/// frequency. Any number of samples is accepted, not only powers of two.
+pub async fn fourier_transform(Json(request): Json<FftRequest>) -> Result<Json<Spectrum>, ApiError> {
+    let mut errors = Vec::new();
+    let n = request.signal.len();
+    if n < 2 {
+        errors.push(ParameterError::new("signal", "must hold at least 2 samples", None));
+    }
+    if n > MAX_FFT_SAMPLES {
+        errors.push(ParameterError::new("signal", format!("holds {n} samples; the limit is {MAX_FFT_SAMPLES}"), None));
+    }
+    if !(request.sample_rate.is_finite() && request.sample_rate > 0.0) {
+        errors.push(ParameterError::new("sample_rate", "must be a positive number", Some(&json!(request.sample_rate))));
+    }
+    let window = Window::from_name(&request.window);
+    if window.is_none() {
+        let names: Vec<&str> = Window::ALL.iter().map(|window| window.name()).collect();
+        errors.push(ParameterError::new(
+            "window",
+            format!("must be one of {}", names.join(", ")),
+            Some(&json!(request.window)),
+        ));
+    }
+    let Some(window) = window.filter(|_| errors.is_empty()) else {
+        return Err(ApiError::InvalidParameters(errors));
+    };
+    let spectrum = trace::spawn_blocking(move || analyze(&request.signal, request.sample_rate, window))
+        .await
+        .map_err(|e| {
+            tracing::error!("Fourier transform failed: {}", e);
+            ApiError::Internal
+        })?;
+    Ok(Json(spectrum))
+}
+
+/// Whether `name` can stand for a quantity in a formula
+fn is_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_') && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+fn problems(request: &FitRequest) -> Vec<ParameterError> {
+    let mut errors = Vec::new();
+    let parameters = request.model.parameters();
+    let n = request.x.len();
+    if request.y.len() != n {
+        errors.push(ParameterError::new("y", format!("must have one value per x ({n})"), None));
+    }
+    if n <= parameters.len() {
+        errors.push(ParameterError::new(
+            "x",
+            format!("a {} fit needs more than {} points", request.model.name(), parameters.len()),
+            None,
+        ));
+    }
+    if n > MAX_FIT_POINTS {
+        errors.push(ParameterError::new("x", format!("holds {n} points; the limit is {MAX_FIT_POINTS}"), None));
+    }
+    if let Some(uncertainty) = &request.uncertainty {
+        if uncertainty.len() != n {
+            errors.push(ParameterError::new("uncertainty", format!("must have one value per x ({n})"), None));
+        }
+        if let Some(i) = uncertainty.iter().position(|&sigma| sigma <= 0.0) {
+            errors.push(ParameterError::new(
+                &format!("uncertainty[{i}]"),
+                "must be a positive number",
+                Some(&json!(uncertainty[i])),
+            ));
+        }
+    }
+    for name in request.initial.keys().filter(|name| !parameters.contains(&name.as_str())) {
+        errors.push(ParameterError::new(
+            &format!("initial.{name}"),
+            format!("is not a parameter of the {} model ({})", request.model.name(), parameters.join(", ")),
+            None,
+        ));
+    }
+    errors
+}
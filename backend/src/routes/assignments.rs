@@ -0,0 +1,409 @@
+use std::collections::HashSet;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use uuid::Uuid;
+
+use crate::models::assignment::{Assignment, AssignmentQuestion, Exploration, Submission};
+use crate::models::classroom::{ClassMember, Classroom};
+use crate::models::simulation::SimulationResult;
+use crate::routes::auth::AuthUser;
+use crate::routes::classes::{taught_class, visible_class};
+use crate::routes::error::ApiError;
+use crate::simulations::params::{ParameterError, Params};
+use crate::state::AppState;
+
+/// Longest assignment title (characters)
+const MAX_TITLE_LENGTH: usize = 200;
+
+/// Set an assignment for a class. Teacher only.
+pub async fn create_assignment(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(class_id): Path<String>,
+    Json(request): Json<AssignmentRequest>,
+) -> Result<(StatusCode, Json<Assignment>), ApiError> {
+    let class = taught_class(&state, &user, &class_id).await?;
+    let (explorations, questions) = check_assignment(&state, &request)?;
+    let now = Utc::now();
+    let assignment = Assignment {
+        id: Uuid::new_v4().to_string(),
+        class_id: class.id,
+        simulation_id: request.simulation_id,
+        title: request.title.trim().to_string(),
+        instructions: request.instructions,
+        explorations,
+        questions,
+        due_at: request.due_at,
+        created_at: now,
+        updated_at: now,
+    };
+    assignment.insert(&state.db).await?;
+    Ok((StatusCode::CREATED, Json(assignment)))
+}
+
+/// Assignments of a class the user teaches or attends, soonest due first
+pub async fn list_assignments(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(class_id): Path<String>,
+) -> Result<Json<Vec<Assignment>>, ApiError> {
+    let class = visible_class(&state, &user, &class_id).await?;
+    Assignment::for_class(&state.db, &class.id).await.map(Json).map_err(ApiError::from)
+}
+
+pub async fn get_assignment(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(id): Path<String>,
+) -> Result<Json<Assignment>, ApiError> {
+    let (assignment, _) = visible_assignment(&state, &user, &id).await?;
+    Ok(Json(assignment))
+}
+
+/// Replace an assignment. Existing submissions are kept; their completed
+/// explorations are re-checked when they are next submitted.
+pub async fn update_assignment(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(id): Path<String>,
+    Json(request): Json<AssignmentRequest>,
+) -> Result<Json<Assignment>, ApiError> {
+    let mut assignment = taught_assignment(&state, &user, &id).await?;
+    let (explorations, questions) = check_assignment(&state, &request)?;
+    assignment.simulation_id = request.simulation_id;
+    assignment.title = request.title.trim().to_string();
+    assignment.instructions = request.instructions;
+    assignment.explorations = explorations;
+    assignment.questions = questions;
+    assignment.due_at = request.due_at;
+    assignment.updated_at = Utc::now();
+    assignment.update(&state.db).await?;
+    Ok(Json(assignment))
+}
+
+/// Delete an assignment with its submissions
+pub async fn delete_assignment(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let assignment = taught_assignment(&state, &user, &id).await?;
+    Assignment::delete(&state.db, &assignment.id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Hand in stored results and answers. Students may resubmit until their
+/// submission is graded; work after the due date is marked late.
+pub async fn submit_assignment(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(id): Path<String>,
+    Json(request): Json<SubmitAssignmentRequest>,
+) -> Result<Json<Submission>, ApiError> {
+    let (assignment, class) = visible_assignment(&state, &user, &id).await?;
+    if class.teacher_id == user.id {
+        return Err(ApiError::Forbidden("only students of the class can submit"));
+    }
+    let previous = Submission::find_for_student(&state.db, &assignment.id, &user.id).await?;
+    if previous.as_ref().is_some_and(|s| s.graded_at.is_some()) {
+        return Err(ApiError::Conflict("the submission has already been graded".to_string()));
+    }
+
+    let mut errors = Vec::new();
+    for (question_id, answer) in &request.answers {
+        let name = format!("answers.{question_id}");
+        if !assignment.questions.iter().any(|q| &q.id == question_id) {
+            errors.push(ParameterError::new(&name, "not a question in this assignment", None));
+        } else if !answer.is_string() {
+            errors.push(ParameterError::new(&name, "must be a string", Some(answer)));
+        }
+    }
+    let mut results = Vec::new();
+    for (i, result_id) in request.result_ids.iter().enumerate() {
+        let name = format!("result_ids[{i}]");
+        match SimulationResult::find(&state.db, result_id).await? {
+            None => errors.push(ParameterError::new(&name, "no such result", Some(&result_id.as_str().into()))),
+            Some(result) if result.simulation_id != assignment.simulation_id => errors.push(ParameterError::new(
+                &name,
+                format!("result is of `{}`, not `{}`", result.simulation_id, assignment.simulation_id),
+                Some(&result_id.as_str().into()),
+            )),
+            Some(result) => results.push(result),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(ApiError::InvalidParameters(errors));
+    }
+
+    let now = Utc::now();
+    let submission = Submission {
+        id: Uuid::new_v4().to_string(),
+        assignment_id: assignment.id.clone(),
+        student_id: user.id.clone(),
+        result_ids: request.result_ids,
+        answers: request.answers,
+        explorations_completed: assignment
+            .explorations
+            .iter()
+            .filter(|e| results.iter().any(|r| e.is_satisfied_by(&r.parameters)))
+            .map(|e| e.id.clone())
+            .collect(),
+        submitted_at: now,
+        late: assignment.due_at.is_some_and(|due| now > due),
+        grade: None,
+        feedback: None,
+        graded_at: None,
+    };
+    submission.upsert(&state.db).await?;
+    own_submission(&state, &assignment.id, &user).await.map(Json)
+}
+
+/// The signed-in student's submission, with grade and feedback once graded
+pub async fn get_submission(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(id): Path<String>,
+) -> Result<Json<Submission>, ApiError> {
+    let (assignment, _) = visible_assignment(&state, &user, &id).await?;
+    own_submission(&state, &assignment.id, &user).await.map(Json)
+}
+
+/// Every student of the class with their submission, if any. Teacher only.
+pub async fn list_submissions(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(id): Path<String>,
+) -> Result<Json<AssignmentSubmissions>, ApiError> {
+    let assignment = taught_assignment(&state, &user, &id).await?;
+    let members = Classroom::members(&state.db, &assignment.class_id).await?;
+    let mut submissions = Submission::for_assignment(&state.db, &assignment.id).await?;
+    let students: Vec<StudentSubmission> = members
+        .into_iter()
+        .map(|student| {
+            let submission = submissions
+                .iter()
+                .position(|s| s.student_id == student.user_id)
+                .map(|i| submissions.swap_remove(i));
+            StudentSubmission { student, submission }
+        })
+        .collect();
+    Ok(Json(AssignmentSubmissions {
+        assignment_id: assignment.id,
+        submitted: students.iter().filter(|s| s.submission.is_some()).count(),
+        graded: students
+            .iter()
+            .filter(|s| s.submission.as_ref().is_some_and(|s| s.graded_at.is_some()))
+            .count(),
+        students,
+    }))
+}
+
+/// Grade a submission, or change its grade. Teacher only.
+pub async fn grade_submission(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(id): Path<String>,
+    Json(request): Json<GradeRequest>,
+) -> Result<Json<Submission>, ApiError> {
+    let mut submission = Submission::find(&state.db, &id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("submission", &id))?;
+    taught_assignment(&state, &user, &submission.assignment_id)
+        .await
+        .map_err(|err| match err {
+            ApiError::NotFound { .. } => ApiError::not_found("submission", &id),
+            err => err,
+        })?;
+    if !(0.0..=100.0).contains(&request.grade) {
+        return Err(ApiError::InvalidParameters(vec![ParameterError::new(
+            "grade",
+            "must be between 0 and 100",
+            Some(&request.grade.into()),
+        )]));
+    }
+    submission.grade = Some(request.grade);
+    submission.feedback = request.feedback;
+    submission.graded_at = Some(Utc::now());
+    submission.grade(&state.db).await?;
+    Ok(Json(submission))
+}
+
+/// An assignment of a class the user teaches or attends, with that class
+async fn visible_assignment(state: &AppState, user: &AuthUser, id: &str) -> Result<(Assignment, Classroom), ApiError> {
+    let assignment = find_assignment(state, id).await?;
+    let class = visible_class(state, user, &assignment.class_id)
+        .await
+        .map_err(|_| ApiError::not_found("assignment", id))?;
+    Ok((assignment, class))
+}
+
+/// An assignment of a class the user teaches
+async fn taught_assignment(state: &AppState, user: &AuthUser, id: &str) -> Result<Assignment, ApiError> {
+    let assignment = find_assignment(state, id).await?;
+    taught_class(state, user, &assignment.class_id)
+        .await
+        .map_err(|err| match err {
+            ApiError::NotFound { .. } => ApiError::not_found("assignment", id),
+            err => err,
+        })?;
+    Ok(assignment)
+}
+
+async fn find_assignment(state: &AppState, id: &str) -> Result<Assignment, ApiError> {
+    Assignment::find(&state.db, id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("assignment", id))
+}
+
+async fn own_submission(state: &AppState, assignment_id: &str, user: &AuthUser) -> Result<Submission, ApiError> {
+    Submission::find_for_student(&state.db, assignment_id, &user.id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("submission", assignment_id))
+}
+
+/// Validate an assignment request, returning its explorations with
+/// normalized parameter values and its questions, each with an ID
+fn check_assignment(
+    state: &AppState,
+    request: &AssignmentRequest,
+) -> Result<(Vec<Exploration>, Vec<AssignmentQuestion>), ApiError> {
+    let mut errors = Vec::new();
+    let title = request.title.trim().chars().count();
+    if title == 0 || title > MAX_TITLE_LENGTH {
+        errors.push(ParameterError::new(
+            "title",
+            format!("must be 1 to {MAX_TITLE_LENGTH} characters"),
+            Some(&request.title.as_str().into()),
+        ));
+    }
+    let Some(simulation) = state.simulations.get(&request.simulation_id) else {
+        errors.push(ParameterError::new(
+            "simulation_id",
+            "unknown simulation",
+            Some(&request.simulation_id.as_str().into()),
+        ));
+        return Err(ApiError::InvalidParameters(errors));
+    };
+
+    let schema = simulation.parameters();
+    let mut explorations = Vec::new();
+    let mut ids = HashSet::new();
+    for (i, exploration) in request.explorations.iter().enumerate() {
+        let id = exploration.id.clone().unwrap_or_else(|| format!("exploration-{}", i + 1));
+        if !ids.insert(id.clone()) {
+            errors.push(ParameterError::new(&format!("explorations[{i}].id"), "is used twice", Some(&id.into())));
+            continue;
+        }
+        if exploration.parameters.is_empty() {
+            errors.push(ParameterError::new(
+                &format!("explorations[{i}].parameters"),
+                "must set at least one parameter",
+                None,
+            ));
+            continue;
+        }
+        match Params::validate(&schema, &exploration.parameters) {
+            Ok(params) => explorations.push(Exploration {
+                id,
+                description: exploration.description.clone(),
+                // Only the values the teacher asked for, as runs store them
+                parameters: exploration
+                    .parameters
+                    .keys()
+                    .filter_map(|name| Some((name.clone(), params.values().get(name)?.clone())))
+                    .collect(),
+            }),
+            Err(fields) => errors.extend(fields.into_iter().map(|e| ParameterError {
+                name: format!("explorations[{i}].parameters.{}", e.name),
+                ..e
+            })),
+        }
+    }
+
+    let mut questions = Vec::new();
+    let mut ids = HashSet::new();
+    for (i, question) in request.questions.iter().enumerate() {
+        let id = question.id.clone().unwrap_or_else(|| format!("q{}", i + 1));
+        if !ids.insert(id.clone()) {
+            errors.push(ParameterError::new(&format!("questions[{i}].id"), "is used twice", Some(&id.into())));
+        } else if question.prompt.trim().is_empty() {
+            errors.push(ParameterError::new(&format!("questions[{i}].prompt"), "must not be empty", None));
+        } else {
+            questions.push(AssignmentQuestion {
+                id,
+                prompt: question.prompt.clone(),
+            });
+        }
+    }
+
+    if errors.is_empty() {
+        Ok((explorations, questions))
+    } else {
+        Err(ApiError::InvalidParameters(errors))
+    }
+}
+
+#[derive(Deserialize)]
+pub struct AssignmentRequest {
+    pub simulation_id: String,
+    pub title: String,
+    #[serde(default)]
+    pub instructions: String,
+    #[serde(default)]
+    pub explorations: Vec<ExplorationRequest>,
+    #[serde(default)]
+    pub questions: Vec<QuestionRequest>,
+    pub due_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize)]
+pub struct ExplorationRequest {
+    /// Defaults to `exploration-<n>`
+    pub id: Option<String>,
+    #[serde(default)]
+    pub description: String,
+    pub parameters: Map<String, Value>,
+}
+
+#[derive(Deserialize)]
+pub struct QuestionRequest {
+    /// Defaults to `q<n>`
+    pub id: Option<String>,
+    pub prompt: String,
+}
+
+#[derive(Deserialize)]
+pub struct SubmitAssignmentRequest {
+    #[serde(default)]
+    pub result_ids: Vec<String>,
+    #[serde(default)]
+    pub answers: Map<String, Value>,
+}
+
+#[derive(Deserialize)]
+pub struct GradeRequest {
+    pub grade: f64,
+    pub feedback: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct AssignmentSubmissions {
+    pub assignment_id: String,
+    pub submitted: usize,
+    pub graded: usize,
+    pub students: Vec<StudentSubmission>,
+}
+
+#[derive(Serialize)]
+pub struct StudentSubmission {
+    #[serde(flatten)]
+    pub student: ClassMember,
+    pub submission: Option<Submission>,
+}
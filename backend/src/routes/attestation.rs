@@ -0,0 +1,97 @@
+use axum::{extract::State, Json};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::models::attestation::AttestationKey;
+use crate::routes::error::ApiError;
+use crate::services::attestation::{check, Attestation, Claim, ALGORITHM};
+use crate::state::AppState;
+
+/// Record the signing key so results it signs stay verifiable after the
+/// key is rotated. Run at startup.
+pub async fn register_key(state: &AppState) -> Result<(), sqlx::Error> {
+    AttestationKey {
+        key_id: state.attester.key_id.clone(),
+        algorithm: ALGORITHM.to_string(),
+        public_key: state.attester.public_key(),
+        first_used_at: Utc::now(),
+    }
+    .register(&state.db)
+    .await
+}
+
+/// Every public key this server has signed results with
+pub async fn list_keys(State(state): State<AppState>) -> Result<Json<AttestationKeys>, ApiError> {
+    let keys = AttestationKey::all(&state.db).await?;
+    Ok(Json(AttestationKeys {
+        current: state.attester.key_id.clone(),
+        backend: state.attester.backend.clone(),
+        keys,
+    }))
+}
+
+/// Check that a result, as returned by the API, was signed by this server
+/// and has not been changed since
+pub async fn verify_result(
+    State(state): State<AppState>,
+    Json(request): Json<VerifyRequest>,
+) -> Result<Json<Verification>, ApiError> {
+    let attestation = request.attestation;
+    let key = AttestationKey::find(&state.db, &attestation.key_id).await?;
+    let outcome = match key {
+        None => Err("the key is not one this server has signed with"),
+        Some(key) => {
+            let public_key = STANDARD.decode(&key.public_key).unwrap_or_default();
+            let claim = Claim {
+                simulation_id: &request.simulation_id,
+                simulation_version: request.simulation_version,
+                parameters: &request.parameters,
+                data: &request.data,
+            };
+            check(&attestation, &claim, &public_key).map_err(|invalid| invalid.reason())
+        }
+    };
+    Ok(Json(Verification {
+        valid: outcome.is_ok(),
+        reason: outcome.err(),
+        key_id: attestation.key_id,
+        backend: attestation.backend,
+    }))
+}
+
+#[derive(Serialize)]
+pub struct AttestationKeys {
+    /// Key new results are signed with
+    pub current: String,
+    /// Server version and commit recorded in new attestations
+    pub backend: String,
+    pub keys: Vec<AttestationKey>,
+}
+
+/// A simulation result with its attestation; other fields are ignored
+#[derive(Deserialize)]
+pub struct VerifyRequest {
+    pub simulation_id: String,
+    #[serde(default = "first_version")]
+    pub simulation_version: u32,
+    pub parameters: Value,
+    pub data: Value,
+    pub attestation: Attestation,
+}
+
+fn first_version() -> u32 {
+    1
+}
+
+#[derive(Serialize)]
+pub struct Verification {
+    pub valid: bool,
+    /// Why the attestation does not hold
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<&'static str>,
+    pub key_id: String,
+    /// Build the attestation names as having computed the result
+    pub backend: String,
+}
@@ -0,0 +1,41 @@
+use axum::{
+    extract::{Query, State},
+    http::header,
+    response::IntoResponse,
+};
+use std::collections::HashMap;
+
+use crate::routes::error::ApiError;
+use crate::routes::stream::query_parameters;
+use crate::services::audio::encode_wav;
+use crate::services::trace;
+use crate::simulations::doppler;
+use crate::simulations::params::Params;
+use crate::state::AppState;
+
+/// Synthesize what the listener hears in the Doppler simulation as a WAV clip.
+///
+/// Query parameters are the simulation's own parameters.
+pub async fn doppler_audio(
+    State(state): State<AppState>,
+    Query(query): Query<HashMap<String, String>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let simulation = state.simulations.get("doppler").ok_or_else(|| ApiError::not_found("simulation", "doppler"))?;
+    let params = Params::validate(&simulation.parameters(), &query_parameters(&query))
+        .map_err(ApiError::InvalidParameters)?;
+
+    let wav = trace::spawn_blocking(move || encode_wav(&doppler::synthesize(&params), doppler::SAMPLE_RATE))
+        .await
+        .map_err(|e| {
+            tracing::error!("Doppler audio synthesis failed: {}", e);
+            ApiError::Internal
+        })?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "audio/wav"),
+            (header::CONTENT_DISPOSITION, "inline; filename=\"doppler.wav\""),
+        ],
+        wav,
+    ))
+}
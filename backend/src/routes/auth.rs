@@ -0,0 +1,287 @@
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, State},
+    http::{header, request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::user::{RefreshToken, User};
+use crate::services::auth::{self, ACCESS_TOKEN_TTL_SECS, REFRESH_TOKEN_TTL_SECS};
+use crate::services::trace;
+use crate::state::AppState;
+
+const MIN_PASSWORD_LENGTH: usize = 8;
+
+/// Create an account and sign it in
+pub async fn register(
+    State(state): State<AppState>,
+    Json(request): Json<RegisterRequest>,
+) -> Result<(StatusCode, Json<TokenResponse>), AuthError> {
+    let email = normalize_email(&request.email);
+    if !is_plausible_email(&email) {
+        return Err(AuthError::BadRequest("email address is not valid"));
+    }
+    if request.password.chars().count() < MIN_PASSWORD_LENGTH {
+        return Err(AuthError::BadRequest("password must be at least 8 characters"));
+    }
+    let display_name = request.display_name.trim();
+    if display_name.is_empty() {
+        return Err(AuthError::BadRequest("display name must not be empty"));
+    }
+
+    if User::find_by_email(&state.db, &email).await?.is_some() {
+        return Err(AuthError::EmailTaken);
+    }
+
+    let password = request.password;
+    let password_hash = trace::spawn_blocking(move || auth::hash_password(&password))
+        .await
+        .map_err(|e| {
+            tracing::error!("Password hashing failed: {}", e);
+            AuthError::Internal
+        })?;
+
+    let user = User {
+        id: Uuid::new_v4().to_string(),
+        email,
+        display_name: display_name.to_string(),
+        password_hash,
+        created_at: Utc::now().to_rfc3339(),
+    };
+    user.insert(&state.db).await.map_err(|e| match e {
+        // Lost a race with a concurrent registration for the same email
+        sqlx::Error::Database(db) if db.is_unique_violation() => AuthError::EmailTaken,
+        e => AuthError::Storage(e),
+    })?;
+
+    let tokens = issue_tokens(&state, user).await?;
+    Ok((StatusCode::CREATED, Json(tokens)))
+}
+
+/// Exchange email and password for a token pair
+pub async fn login(
+    State(state): State<AppState>,
+    Json(request): Json<LoginRequest>,
+) -> Result<Json<TokenResponse>, AuthError> {
+    let user = User::find_by_email(&state.db, &normalize_email(&request.email)).await?;
+
+    // Hash even when the account is unknown so response times don't reveal
+    // which emails are registered
+    let stored = user.as_ref().map(|u| u.password_hash.clone());
+    let password = request.password;
+    let valid = trace::spawn_blocking(move || match stored {
+        Some(stored) => auth::verify_password(&password, &stored),
+        None => {
+            auth::hash_password(&password);
+            false
+        }
+    })
+    .await
+    .map_err(|e| {
+        tracing::error!("Password verification failed: {}", e);
+        AuthError::Internal
+    })?;
+
+    match user {
+        Some(user) if valid => Ok(Json(issue_tokens(&state, user).await?)),
+        _ => Err(AuthError::InvalidCredentials),
+    }
+}
+
+/// Exchange a refresh token for a new token pair. Refresh tokens are single
+/// use: the presented token is revoked and a new one issued.
+pub async fn refresh(
+    State(state): State<AppState>,
+    Json(request): Json<RefreshRequest>,
+) -> Result<Json<TokenResponse>, AuthError> {
+    let token_hash = auth::hash_refresh_token(&request.refresh_token);
+    let stored = RefreshToken::take(&state.db, &token_hash)
+        .await?
+        .ok_or(AuthError::InvalidToken)?;
+
+    let expired = DateTime::parse_from_rfc3339(&stored.expires_at)
+        .map(|expires_at| expires_at <= Utc::now())
+        .unwrap_or(true);
+    if expired {
+        return Err(AuthError::InvalidToken);
+    }
+
+    let user = User::find(&state.db, &stored.user_id)
+        .await?
+        .ok_or(AuthError::InvalidToken)?;
+    Ok(Json(issue_tokens(&state, user).await?))
+}
+
+/// The account behind the presented access token
+pub async fn me(State(state): State<AppState>, auth: AuthUser) -> Result<Json<User>, AuthError> {
+    User::find(&state.db, &auth.id)
+        .await?
+        .map(Json)
+        .ok_or(AuthError::InvalidToken)
+}
+
+pub async fn issue_tokens(state: &AppState, user: User) -> Result<TokenResponse, AuthError> {
+    let access_token = state.keys.issue_access_token(&user.id, &user.email);
+
+    let refresh_token = auth::generate_refresh_token();
+    let now = Utc::now();
+    RefreshToken {
+        token_hash: auth::hash_refresh_token(&refresh_token),
+        user_id: user.id.clone(),
+        expires_at: (now + Duration::seconds(REFRESH_TOKEN_TTL_SECS)).to_rfc3339(),
+        created_at: now.to_rfc3339(),
+    }
+    .insert(&state.db)
+    .await?;
+
+    Ok(TokenResponse {
+        access_token,
+        refresh_token,
+        token_type: "Bearer".to_string(),
+        expires_in: ACCESS_TOKEN_TTL_SECS,
+        user,
+    })
+}
+
+fn normalize_email(email: &str) -> String {
+    email.trim().to_lowercase()
+}
+
+fn is_plausible_email(email: &str) -> bool {
+    match email.split_once('@') {
+        Some((local, domain)) => !local.is_empty() && domain.contains('.') && !email.contains(char::is_whitespace),
+        None => false,
+    }
+}
+
+/// The user identified by a valid `Authorization: Bearer` access token
+pub struct AuthUser {
+    pub id: String,
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for AuthUser {
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let token = parts
+            .headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or(AuthError::MissingToken)?;
+        let claims = state
+            .keys
+            .verify_access_token(token.trim())
+            .ok_or(AuthError::InvalidToken)?;
+        Ok(Self { id: claims.sub })
+    }
+}
+
+/// A signed-in user whose email is listed in `ADMIN_EMAILS`
+pub struct AdminUser {
+    pub id: String,
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for AdminUser {
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let auth = AuthUser::from_request_parts(parts, state).await?;
+        let user = User::find(&state.db, &auth.id).await?.ok_or(AuthError::InvalidToken)?;
+        if !state.admins.contains(&user.email) {
+            return Err(AuthError::Forbidden);
+        }
+        Ok(Self { id: user.id })
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RegisterRequest {
+    pub email: String,
+    pub password: String,
+    pub display_name: String,
+}
+
+#[derive(Deserialize)]
+pub struct LoginRequest {
+    pub email: String,
+    pub password: String,
+}
+
+#[derive(Deserialize)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
+
+#[derive(Serialize)]
+pub struct TokenResponse {
+    pub access_token: String,
+    pub refresh_token: String,
+    pub token_type: String,
+    /// Access token lifetime in seconds
+    pub expires_in: i64,
+    pub user: User,
+}
+
+/// Failure modes of the auth endpoints and the `AuthUser` extractor
+pub enum AuthError {
+    BadRequest(&'static str),
+    EmailTaken,
+    InvalidCredentials,
+    MissingToken,
+    InvalidToken,
+    /// Signed in, but not an administrator
+    Forbidden,
+    Storage(sqlx::Error),
+    Internal,
+}
+
+impl From<sqlx::Error> for AuthError {
+    fn from(err: sqlx::Error) -> Self {
+        Self::Storage(err)
+    }
+}
+
+impl IntoResponse for AuthError {
+    fn into_response(self) -> Response {
+        let (status, error, message) = match self {
+            Self::BadRequest(message) => (StatusCode::BAD_REQUEST, "invalid_request", message),
+            Self::EmailTaken => (StatusCode::CONFLICT, "email_taken", "an account with this email already exists"),
+            Self::InvalidCredentials => (StatusCode::UNAUTHORIZED, "invalid_credentials", "email or password is incorrect"),
+            Self::MissingToken => (StatusCode::UNAUTHORIZED, "missing_token", "a bearer access token is required"),
+            Self::InvalidToken => (StatusCode::UNAUTHORIZED, "invalid_token", "token is invalid or has expired"),
+            Self::Forbidden => (StatusCode::FORBIDDEN, "forbidden", "administrator rights are required"),
+            Self::Storage(e) => {
+                tracing::error!("Auth storage error: {}", e);
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+            Self::Internal => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+        };
+        let mut response = (status, Json(AuthErrorBody {
+            error,
+            message,
+            request_id: trace::request_id(),
+        })).into_response();
+        if status == StatusCode::UNAUTHORIZED {
+            response
+                .headers_mut()
+                .insert(header::WWW_AUTHENTICATE, header::HeaderValue::from_static("Bearer"));
+        }
+        response
+    }
+}
+
+#[derive(Serialize)]
+pub struct AuthErrorBody {
+    pub error: &'static str,
+    pub message: &'static str,
+    /// ID of the failed request, as in the `X-Request-Id` header
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
@@ -0,0 +1,144 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::models::bookmark::Bookmark;
+use crate::models::simulation::SimulationResult;
+use crate::routes::auth::AuthUser;
+use crate::routes::error::ApiError;
+use crate::simulations::params::ParameterError;
+use crate::state::AppState;
+
+/// Longest note (characters)
+const MAX_NOTE_LENGTH: usize = 2_000;
+/// Most bookmarks one user may keep
+const MAX_PER_USER: i64 = 500;
+
+/// The signed-in learner's bookmarks, newest first; `simulation_id` keeps
+/// only those of one simulation and its results
+pub async fn list_bookmarks(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Query(query): Query<BookmarkQuery>,
+) -> Result<Json<Vec<BookmarkView>>, ApiError> {
+    let bookmarks = Bookmark::of_user(&state.db, &user.id, query.simulation_id.as_deref()).await?;
+    Ok(Json(bookmarks.into_iter().map(|bookmark| view(&state, bookmark)).collect()))
+}
+
+/// Bookmark a simulation, or one of its stored results when `result_id` is
+/// given, with an optional note
+pub async fn create_bookmark(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Json(request): Json<BookmarkRequest>,
+) -> Result<(StatusCode, Json<BookmarkView>), ApiError> {
+    let note = request.note.unwrap_or_default().trim().to_string();
+    if note.chars().count() > MAX_NOTE_LENGTH {
+        return Err(ApiError::InvalidParameters(vec![ParameterError::new(
+            "note",
+            format!("must be at most {MAX_NOTE_LENGTH} characters"),
+            Some(&json!(note)),
+        )]));
+    }
+
+    // A result bookmark belongs to the result's simulation
+    let simulation_id = match (&request.result_id, request.simulation_id) {
+        (Some(result_id), simulation_id) => {
+            let result = SimulationResult::find(&state.db, result_id)
+                .await?
+                .ok_or_else(|| ApiError::not_found("result", result_id))?;
+            if simulation_id.as_ref().is_some_and(|id| *id != result.simulation_id) {
+                return Err(ApiError::InvalidParameters(vec![ParameterError::new(
+                    "simulation_id",
+                    format!("result {result_id} is of {}", result.simulation_id),
+                    simulation_id.as_ref().map(|id| json!(id)).as_ref(),
+                )]));
+            }
+            result.simulation_id
+        }
+        (None, Some(simulation_id)) => {
+            state.simulations.get(&simulation_id).ok_or_else(|| ApiError::not_found("simulation", &simulation_id))?;
+            simulation_id
+        }
+        (None, None) => {
+            return Err(ApiError::InvalidParameters(vec![ParameterError::new(
+                "simulation_id",
+                "give a simulation_id, a result_id or both",
+                None,
+            )]))
+        }
+    };
+
+    if let Some(existing) =
+        Bookmark::find_target(&state.db, &user.id, &simulation_id, request.result_id.as_deref()).await?
+    {
+        return Err(ApiError::Conflict(format!("already bookmarked as {}", existing.id)));
+    }
+    if Bookmark::count_of_user(&state.db, &user.id).await? >= MAX_PER_USER {
+        return Err(ApiError::Conflict(format!(
+            "you already have {MAX_PER_USER} bookmarks; remove one to add another"
+        )));
+    }
+    let bookmark = Bookmark {
+        id: Uuid::new_v4().to_string(),
+        user_id: user.id,
+        simulation_id,
+        result_id: request.result_id,
+        note,
+        created_at: Utc::now(),
+    };
+    bookmark.insert(&state.db).await.map_err(|err| match err {
+        sqlx::Error::Database(db) if db.is_unique_violation() => ApiError::Conflict("already bookmarked".to_string()),
+        err => ApiError::from(err),
+    })?;
+    Ok((StatusCode::CREATED, Json(view(&state, bookmark))))
+}
+
+pub async fn delete_bookmark(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    // Another user's bookmark is reported missing, as it is private
+    let bookmark = Bookmark::find(&state.db, &id)
+        .await?
+        .filter(|bookmark| bookmark.user_id == user.id)
+        .ok_or_else(|| ApiError::not_found("bookmark", &id))?;
+    Bookmark::delete(&state.db, &bookmark.id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn view(state: &AppState, bookmark: Bookmark) -> BookmarkView {
+    BookmarkView {
+        simulation_name: state.simulations.get_any(&bookmark.simulation_id).map(|s| s.info().name),
+        bookmark,
+    }
+}
+
+#[derive(Deserialize)]
+pub struct BookmarkRequest {
+    /// Taken from the result when left out
+    pub simulation_id: Option<String>,
+    pub result_id: Option<String>,
+    pub note: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct BookmarkQuery {
+    pub simulation_id: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct BookmarkView {
+    #[serde(flatten)]
+    pub bookmark: Bookmark,
+    /// Absent when the simulation is no longer in the catalog
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub simulation_name: Option<String>,
+}
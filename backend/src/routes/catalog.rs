@@ -0,0 +1,42 @@
+use axum::{extract::State, Json};
+use serde::Serialize;
+
+use crate::routes::auth::AdminUser;
+use crate::routes::error::ApiError;
+use crate::services::trace;
+use crate::simulations::params::ParameterError;
+use crate::state::AppState;
+
+/// Read the simulation content files again. If any file is invalid nothing
+/// changes and each problem is reported with its file name as the field.
+pub async fn reload_catalog(State(state): State<AppState>, admin: AdminUser) -> Result<Json<CatalogReload>, ApiError> {
+    let registry = state.simulations.clone();
+    let reloaded = trace::spawn_blocking(move || registry.reload_catalog())
+        .await
+        .map_err(|err| {
+            tracing::error!("Catalog reload panicked: {}", err);
+            ApiError::Internal
+        })?;
+    match reloaded {
+        Ok(simulations) => {
+            tracing::info!("Simulation catalog reloaded by {}", admin.id);
+            Ok(Json(CatalogReload {
+                simulations,
+                directory: state.simulations.catalog_dir().unwrap_or_default().display().to_string(),
+            }))
+        }
+        Err(err) => Err(ApiError::InvalidParameters(
+            err.problems
+                .iter()
+                .map(|(file, message)| ParameterError::new(file, message.as_str(), None))
+                .collect(),
+        )),
+    }
+}
+
+#[derive(Serialize)]
+pub struct CatalogReload {
+    /// Simulations whose content was loaded
+    pub simulations: usize,
+    pub directory: String,
+}
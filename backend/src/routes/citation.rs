@@ -0,0 +1,58 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::Utc;
+use serde::Deserialize;
+
+use crate::routes::error::ApiError;
+use crate::services::citation::{cite, Cited};
+use crate::simulations::params::ParameterError;
+use crate::state::AppState;
+
+/// How to cite a simulation: the server release and computation version
+/// that produced its results, and the works its physics comes from, as
+/// JSON with both CSL-JSON items and BibTeX, or as either alone
+pub async fn get_citation(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<CitationQuery>,
+) -> Result<Response, ApiError> {
+    let format = query.format.as_deref().unwrap_or("json");
+    if !matches!(format, "json" | "csl" | "bibtex") {
+        return Err(ApiError::InvalidParameters(vec![ParameterError::new(
+            "format",
+            "must be one of: json, csl, bibtex",
+            Some(&format.into()),
+        )]));
+    }
+
+    let simulation = state.simulations.get(&id).ok_or_else(|| ApiError::not_found("simulation", &id))?;
+    let details = simulation.details();
+    let content = state.simulations.catalog().get(&id);
+    let cited = Cited {
+        id: simulation.id(),
+        name: &details.name,
+        version: simulation.version(),
+        references: content.as_ref().map_or(&[][..], |c| &c.references),
+    };
+    let citation = cite(&state.citation, &cited, Utc::now().date_naive());
+
+    Ok(match format {
+        "csl" => (
+            [(header::CONTENT_TYPE, "application/vnd.citationstyles.csl+json")],
+            citation.csl().to_string(),
+        )
+            .into_response(),
+        "bibtex" => ([(header::CONTENT_TYPE, "application/x-bibtex; charset=utf-8")], citation.bibtex).into_response(),
+        _ => Json(citation).into_response(),
+    })
+}
+
+#[derive(Deserialize)]
+pub struct CitationQuery {
+    /// `json` (default), `csl` or `bibtex`
+    pub format: Option<String>,
+}
@@ -0,0 +1,432 @@
+use std::collections::BTreeMap;
+
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::assignment::{Assignment, Submission};
+use crate::models::classroom::{ClassMember, Classroom};
+use crate::models::progress::{QuizAttempt, SimulationProgress};
+use crate::routes::auth::AuthUser;
+use crate::routes::error::ApiError;
+use crate::services::auth::generate_join_code;
+use crate::services::export::csv_field;
+use crate::simulations::params::ParameterError;
+use crate::state::AppState;
+
+/// Longest class name (characters)
+const MAX_NAME_LENGTH: usize = 200;
+/// Attempts at finding an unused join code before giving up
+const JOIN_CODE_ATTEMPTS: usize = 5;
+
+/// Create a class taught by the signed-in user
+pub async fn create_class(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Json(request): Json<CreateClassRequest>,
+) -> Result<(StatusCode, Json<ClassView>), ApiError> {
+    let name = request.name.trim();
+    let length = name.chars().count();
+    if length == 0 || length > MAX_NAME_LENGTH {
+        return Err(ApiError::InvalidParameters(vec![ParameterError::new(
+            "name",
+            format!("must be 1 to {MAX_NAME_LENGTH} characters"),
+            Some(&request.name.as_str().into()),
+        )]));
+    }
+
+    let class = new_class(&state, &user.id, name).await?;
+    Ok((StatusCode::CREATED, Json(view(&state, &user, class).await?)))
+}
+
+/// Save a new class under a fresh join code
+pub async fn new_class(state: &AppState, teacher_id: &str, name: &str) -> Result<Classroom, ApiError> {
+    let mut class = Classroom {
+        id: Uuid::new_v4().to_string(),
+        teacher_id: teacher_id.to_string(),
+        name: name.to_string(),
+        join_code: None,
+        created_at: Utc::now(),
+    };
+    for _ in 0..JOIN_CODE_ATTEMPTS {
+        class.join_code = Some(generate_join_code());
+        match class.insert(&state.db).await {
+            Ok(()) => return Ok(class),
+            // Another class already uses this code
+            Err(sqlx::Error::Database(db)) if db.is_unique_violation() => continue,
+            Err(e) => return Err(ApiError::from(e)),
+        }
+    }
+    tracing::error!("No unused join code found after {} attempts", JOIN_CODE_ATTEMPTS);
+    Err(ApiError::Internal)
+}
+
+/// Classes the signed-in user teaches or has joined
+pub async fn list_classes(State(state): State<AppState>, user: AuthUser) -> Result<Json<Vec<ClassView>>, ApiError> {
+    let classes = Classroom::for_user(&state.db, &user.id).await?;
+    let mut views = Vec::with_capacity(classes.len());
+    for class in classes {
+        views.push(view(&state, &user, class).await?);
+    }
+    Ok(Json(views))
+}
+
+/// A class the signed-in user teaches or has joined. Its teacher also sees
+/// the join code and the students.
+pub async fn get_class(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(id): Path<String>,
+) -> Result<Json<ClassView>, ApiError> {
+    let class = visible_class(&state, &user, &id).await?;
+    view(&state, &user, class).await.map(Json)
+}
+
+/// Delete a class. Its students keep their progress.
+pub async fn delete_class(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let class = taught_class(&state, &user, &id).await?;
+    Classroom::delete(&state.db, &class.id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Replace a class's join code, e.g. after it leaked. Students who already
+/// joined stay enrolled.
+pub async fn regenerate_join_code(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(id): Path<String>,
+) -> Result<Json<ClassView>, ApiError> {
+    let mut class = taught_class(&state, &user, &id).await?;
+    for _ in 0..JOIN_CODE_ATTEMPTS {
+        let code = generate_join_code();
+        match Classroom::set_join_code(&state.db, &class.id, &code).await {
+            Ok(()) => {
+                class.join_code = Some(code);
+                return view(&state, &user, class).await.map(Json);
+            }
+            Err(sqlx::Error::Database(db)) if db.is_unique_violation() => continue,
+            Err(e) => return Err(ApiError::from(e)),
+        }
+    }
+    tracing::error!("No unused join code found after {} attempts", JOIN_CODE_ATTEMPTS);
+    Err(ApiError::Internal)
+}
+
+/// Join a class with the code its teacher handed out
+pub async fn join_class(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Json(request): Json<JoinClassRequest>,
+) -> Result<Json<ClassView>, ApiError> {
+    let code = request.join_code.trim().to_uppercase();
+    let class = Classroom::find_by_join_code(&state.db, &code)
+        .await?
+        .ok_or_else(|| ApiError::not_found("join code", &code))?;
+    if class.teacher_id == user.id {
+        return Err(ApiError::Conflict("teachers cannot join their own class".to_string()));
+    }
+    Classroom::add_member(&state.db, &class.id, &user.id).await?;
+    view(&state, &user, class).await.map(Json)
+}
+
+/// Remove a student from a class. Teachers can remove anyone; students can
+/// only remove themselves.
+pub async fn remove_student(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path((id, student_id)): Path<(String, String)>,
+) -> Result<StatusCode, ApiError> {
+    let class = find_class(&state, &id).await?;
+    if class.teacher_id != user.id && student_id != user.id {
+        if is_member(&state, &class, &user).await? {
+            return Err(ApiError::Forbidden("only the teacher can remove other students"));
+        }
+        return Err(ApiError::not_found("class", &id));
+    }
+    if !Classroom::remove_member(&state.db, &class.id, &student_id).await? {
+        return Err(ApiError::not_found("student", &student_id));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Per-student simulation completions and quiz scores, with class-wide
+/// totals per simulation. Teacher only.
+pub async fn class_progress(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(id): Path<String>,
+) -> Result<Json<ClassProgress>, ApiError> {
+    let class = taught_class(&state, &user, &id).await?;
+    let members = Classroom::members(&state.db, &class.id).await?;
+    let records = SimulationProgress::for_class(&state.db, &class.id).await?;
+    let quizzes = QuizAttempt::summaries_for_class(&state.db, &class.id).await?;
+
+    // Keyed by student, then simulation
+    let mut rows: BTreeMap<(&str, &str), StudentSimulation> = BTreeMap::new();
+    for record in &records {
+        let row = rows
+            .entry((&record.user_id, &record.simulation_id))
+            .or_insert_with(|| StudentSimulation::new(&record.simulation_id));
+        row.completed = record.completed;
+        row.completed_at = record.completed_at.filter(|_| record.completed);
+        row.score = record.score;
+        row.time_spent_minutes = record.time_spent_minutes;
+    }
+    for quiz in &quizzes {
+        let row = rows
+            .entry((&quiz.user_id, &quiz.simulation_id))
+            .or_insert_with(|| StudentSimulation::new(&quiz.simulation_id));
+        row.best_quiz_score = Some(quiz.best_score);
+        row.quiz_attempts = quiz.attempts;
+        row.last_quiz_at = Some(quiz.last_submitted_at);
+    }
+
+    let mut totals: BTreeMap<&str, SimulationTotals> = BTreeMap::new();
+    for ((_, simulation_id), row) in &rows {
+        let total = totals.entry(simulation_id).or_insert_with(|| SimulationTotals {
+            simulation_id: simulation_id.to_string(),
+            started: 0,
+            completed: 0,
+            quiz_takers: 0,
+            average_best_quiz_score: None,
+        });
+        total.started += 1;
+        total.completed += usize::from(row.completed);
+        if let Some(score) = row.best_quiz_score {
+            let sum = total.average_best_quiz_score.unwrap_or(0.0) * total.quiz_takers as f64 + score;
+            total.quiz_takers += 1;
+            total.average_best_quiz_score = Some(sum / total.quiz_takers as f64);
+        }
+    }
+
+    let students = members
+        .into_iter()
+        .map(|member| {
+            let simulations: Vec<StudentSimulation> = rows
+                .range((member.user_id.as_str(), "")..)
+                .take_while(|((user_id, _), _)| *user_id == member.user_id)
+                .map(|(_, row)| row.clone())
+                .collect();
+            let quiz_scores: Vec<f64> = simulations.iter().filter_map(|s| s.best_quiz_score).collect();
+            StudentProgress {
+                completed_simulations: simulations.iter().filter(|s| s.completed).count(),
+                total_time_minutes: simulations.iter().map(|s| s.time_spent_minutes).sum(),
+                average_quiz_score: (!quiz_scores.is_empty())
+                    .then(|| quiz_scores.iter().sum::<f64>() / quiz_scores.len() as f64),
+                student: member,
+                simulations,
+            }
+        })
+        .collect();
+
+    Ok(Json(ClassProgress {
+        class_id: class.id,
+        name: class.name,
+        students,
+        simulations: totals.into_values().collect(),
+    }))
+}
+
+/// Per-student assignment grades and best quiz scores as CSV, one row per
+/// student and one column per assignment, then per quiz taken. Students are
+/// identified by name, user ID and email address so LMS gradebook imports
+/// can match them; scores are percentages and ungraded work is left blank.
+/// Teacher only.
+pub async fn gradebook_csv(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(id): Path<String>,
+) -> Result<Response, ApiError> {
+    let class = taught_class(&state, &user, &id).await?;
+    let members = Classroom::members(&state.db, &class.id).await?;
+    let assignments = Assignment::for_class(&state.db, &class.id).await?;
+    let submissions = Submission::for_class(&state.db, &class.id).await?;
+    let quizzes = QuizAttempt::summaries_for_class(&state.db, &class.id).await?;
+    // Quizzes anyone in the class has taken, in catalog order
+    let quizzed: Vec<_> = state
+        .simulations
+        .iter()
+        .filter(|s| quizzes.iter().any(|q| q.simulation_id == s.id()))
+        .collect();
+
+    let mut header = vec!["Student".to_string(), "ID".to_string(), "Email Address".to_string()];
+    header.extend(assignments.iter().map(|a| a.title.clone()));
+    header.extend(quizzed.iter().map(|s| format!("Quiz: {}", s.info().name)));
+    let mut csv = String::new();
+    push_csv_row(&mut csv, &header);
+    let score = |value: Option<f64>| value.map(|v| format!("{v:.1}")).unwrap_or_default();
+    for member in &members {
+        let mut row = vec![member.display_name.clone(), member.user_id.clone(), member.email.clone()];
+        row.extend(assignments.iter().map(|assignment| {
+            score(
+                submissions
+                    .iter()
+                    .find(|s| s.assignment_id == assignment.id && s.student_id == member.user_id)
+                    .and_then(|s| s.grade),
+            )
+        }));
+        row.extend(quizzed.iter().map(|simulation| {
+            score(
+                quizzes
+                    .iter()
+                    .find(|q| q.simulation_id == simulation.id() && q.user_id == member.user_id)
+                    .map(|q| q.best_score),
+            )
+        }));
+        push_csv_row(&mut csv, &row);
+    }
+
+    let disposition = format!("attachment; filename=\"class-{}-gradebook.csv\"", class.id);
+    Ok((
+        [(header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()), (header::CONTENT_DISPOSITION, disposition)],
+        csv,
+    )
+        .into_response())
+}
+
+fn push_csv_row(csv: &mut String, fields: &[String]) {
+    let fields: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+    csv.push_str(&fields.join(","));
+    csv.push_str("\r\n");
+}
+
+pub async fn find_class(state: &AppState, id: &str) -> Result<Classroom, ApiError> {
+    Classroom::find(&state.db, id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("class", id))
+}
+
+/// A class the user teaches or attends; others don't exist as far as they
+/// can tell
+pub async fn visible_class(state: &AppState, user: &AuthUser, id: &str) -> Result<Classroom, ApiError> {
+    let class = find_class(state, id).await?;
+    if class.teacher_id != user.id && !is_member(state, &class, user).await? {
+        return Err(ApiError::not_found("class", id));
+    }
+    Ok(class)
+}
+
+/// A class the user teaches. Classes they merely attend are forbidden,
+/// others don't exist as far as they can tell.
+pub async fn taught_class(state: &AppState, user: &AuthUser, id: &str) -> Result<Classroom, ApiError> {
+    let class = find_class(state, id).await?;
+    if class.teacher_id != user.id {
+        if is_member(state, &class, user).await? {
+            return Err(ApiError::Forbidden("only the class's teacher can do this"));
+        }
+        return Err(ApiError::not_found("class", id));
+    }
+    Ok(class)
+}
+
+pub async fn is_member(state: &AppState, class: &Classroom, user: &AuthUser) -> Result<bool, ApiError> {
+    let members = Classroom::members(&state.db, &class.id).await?;
+    Ok(members.iter().any(|member| member.user_id == user.id))
+}
+
+/// What the user may see of a class: the join code and roster only if they
+/// teach it
+async fn view(state: &AppState, user: &AuthUser, mut class: Classroom) -> Result<ClassView, ApiError> {
+    let members = Classroom::members(&state.db, &class.id).await?;
+    let teaching = class.teacher_id == user.id;
+    if !teaching {
+        class.join_code = None;
+    }
+    Ok(ClassView {
+        role: if teaching { "teacher" } else { "student" },
+        student_count: members.len(),
+        students: teaching.then_some(members),
+        class,
+    })
+}
+
+#[derive(Deserialize)]
+pub struct CreateClassRequest {
+    pub name: String,
+}
+
+#[derive(Deserialize)]
+pub struct JoinClassRequest {
+    pub join_code: String,
+}
+
+#[derive(Serialize)]
+pub struct ClassView {
+    #[serde(flatten)]
+    pub class: Classroom,
+    /// The signed-in user's part in the class: `teacher` or `student`
+    pub role: &'static str,
+    pub student_count: usize,
+    /// Roster, for the teacher only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub students: Option<Vec<ClassMember>>,
+}
+
+#[derive(Serialize)]
+pub struct ClassProgress {
+    pub class_id: String,
+    pub name: String,
+    pub students: Vec<StudentProgress>,
+    /// Class-wide totals for every simulation any student has worked on
+    pub simulations: Vec<SimulationTotals>,
+}
+
+#[derive(Serialize)]
+pub struct StudentProgress {
+    #[serde(flatten)]
+    pub student: ClassMember,
+    pub completed_simulations: usize,
+    pub total_time_minutes: u32,
+    /// Mean of the best score on each quiz taken
+    pub average_quiz_score: Option<f64>,
+    pub simulations: Vec<StudentSimulation>,
+}
+
+/// One student's work on one simulation
+#[derive(Serialize, Clone)]
+pub struct StudentSimulation {
+    pub simulation_id: String,
+    pub completed: bool,
+    pub completed_at: Option<DateTime<Utc>>,
+    /// Best score reported with the student's progress
+    pub score: Option<f64>,
+    pub time_spent_minutes: u32,
+    pub best_quiz_score: Option<f64>,
+    pub quiz_attempts: u32,
+    pub last_quiz_at: Option<DateTime<Utc>>,
+}
+
+impl StudentSimulation {
+    fn new(simulation_id: &str) -> Self {
+        Self {
+            simulation_id: simulation_id.to_string(),
+            completed: false,
+            completed_at: None,
+            score: None,
+            time_spent_minutes: 0,
+            best_quiz_score: None,
+            quiz_attempts: 0,
+            last_quiz_at: None,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct SimulationTotals {
+    pub simulation_id: String,
+    /// Students with any progress or quiz attempt on the simulation
+    pub started: usize,
+    pub completed: usize,
+    pub quiz_takers: usize,
+    pub average_best_quiz_score: Option<f64>,
+}
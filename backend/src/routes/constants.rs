@@ -0,0 +1,15 @@
+use axum::Json;
+
+use crate::physics::constants::{Constant, CONSTANTS};
+use crate::physics::units::{Unit, UNITS};
+
+/// List the physical constants the simulations use, with their CODATA
+/// uncertainties
+pub async fn list_constants() -> Json<&'static [Constant]> {
+    Json(CONSTANTS)
+}
+
+/// List the units simulation parameters are declared in or may be given in
+pub async fn list_units() -> Json<&'static [Unit]> {
+    Json(UNITS)
+}
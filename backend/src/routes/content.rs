@@ -0,0 +1,253 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::models::content::{self, ContentRevision, DRAFT, PUBLISHED};
+use crate::models::simulation::{SimulationDetails, SimulationInfo};
+use crate::routes::auth::AdminUser;
+use crate::routes::error::ApiError;
+use crate::simulations::catalog::SimulationContent;
+use crate::simulations::params::ParameterError;
+use crate::state::AppState;
+
+/// Serve the content published through the admin API and withdraw retired
+/// simulations. Run at startup, after the content files are loaded.
+pub async fn apply_stored_content(state: &AppState) -> Result<(), sqlx::Error> {
+    let catalog = state.simulations.catalog();
+    for revision in ContentRevision::all(&state.db, PUBLISHED).await? {
+        // Content files ship with the code, so a changed parameter set there
+        // wins over content published for the previous version
+        if let Err(err) = catalog.publish(revision.content) {
+            tracing::warn!("Serving the content file of {}: {}", revision.simulation_id, err);
+        }
+    }
+    for id in content::retired(&state.db).await? {
+        catalog.set_retired(&id, true);
+    }
+    Ok(())
+}
+
+/// Every simulation with where its content comes from and whether a draft
+/// is waiting
+pub async fn list_content(State(state): State<AppState>, _admin: AdminUser) -> Result<Json<Vec<ContentSummary>>, ApiError> {
+    let drafts = ContentRevision::all(&state.db, DRAFT).await?;
+    let catalog = state.simulations.catalog();
+    let summaries = state
+        .simulations
+        .iter_all()
+        .map(|simulation| {
+            let id = simulation.id();
+            ContentSummary {
+                id: id.to_string(),
+                name: simulation.info().name,
+                source: source(&state, id),
+                retired: catalog.is_retired(id),
+                draft_updated_at: drafts.iter().find(|d| d.simulation_id == id).map(|d| d.updated_at),
+            }
+        })
+        .collect();
+    Ok(Json(summaries))
+}
+
+/// The content being served and the draft, if any
+pub async fn get_content(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+    Path(id): Path<String>,
+) -> Result<Json<ContentEntry>, ApiError> {
+    content_entry(&state, &id).await.map(Json)
+}
+
+/// Save a draft, replacing any earlier one. Nothing is served from it until
+/// it is published.
+pub async fn put_draft(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    Path(id): Path<String>,
+    Json(mut content): Json<SimulationContent>,
+) -> Result<Json<ContentRevision>, ApiError> {
+    state.simulations.get_any(&id).ok_or_else(|| ApiError::not_found("simulation", &id))?;
+    if !content.id.is_empty() && content.id != id {
+        return Err(ApiError::InvalidParameters(vec![ParameterError::new(
+            "id",
+            "must match the URL",
+            Some(&content.id.as_str().into()),
+        )]));
+    }
+    content.id = id.clone();
+    let mut errors: Vec<ParameterError> = content
+        .problems()
+        .into_iter()
+        .map(|(field, message)| ParameterError::new(&field, message, None))
+        .collect();
+    if let Some(message) = parameter_mismatch(&state, &content) {
+        errors.push(ParameterError::new("parameters", message, None));
+    }
+    if !errors.is_empty() {
+        return Err(ApiError::InvalidParameters(errors));
+    }
+
+    let draft = ContentRevision {
+        simulation_id: id,
+        state: DRAFT,
+        content,
+        updated_by: admin.id,
+        updated_at: Utc::now(),
+    };
+    draft.upsert(&state.db).await?;
+    Ok(Json(draft))
+}
+
+/// Throw the draft away
+pub async fn delete_draft(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    if ContentRevision::delete(&state.db, &id, DRAFT).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(ApiError::not_found("draft", &id))
+    }
+}
+
+/// The listing entry and details page as they would be served once the
+/// draft is published
+pub async fn preview_draft(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+    Path(id): Path<String>,
+) -> Result<Json<ContentPreview>, ApiError> {
+    let draft = find_draft(&state, &id).await?;
+    Ok(Json(ContentPreview {
+        listing: draft.content.info(),
+        details: draft.content.details(),
+    }))
+}
+
+/// Serve the draft from now on, in place of the content file or earlier
+/// published content
+pub async fn publish_draft(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    Path(id): Path<String>,
+) -> Result<Json<ContentEntry>, ApiError> {
+    let draft = find_draft(&state, &id).await?;
+    // The content file may have changed since the draft was saved
+    if let Some(message) = parameter_mismatch(&state, &draft.content) {
+        return Err(ApiError::Conflict(message));
+    }
+    if !ContentRevision::publish(&state.db, &id, &admin.id).await? {
+        return Err(ApiError::not_found("draft", &id));
+    }
+    state.simulations.catalog().publish(draft.content).map_err(ApiError::Conflict)?;
+    tracing::info!("Content of {} published by {}", id, admin.id);
+    content_entry(&state, &id).await.map(Json)
+}
+
+/// Drop the published content and serve the content file again
+pub async fn revert_content(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    if !ContentRevision::delete(&state.db, &id, PUBLISHED).await? {
+        return Err(ApiError::not_found("published content", &id));
+    }
+    state.simulations.catalog().unpublish(&id);
+    tracing::info!("Content of {} reverted to its file by {}", id, admin.id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Withdraw a simulation: it disappears from listings and its endpoints
+/// answer 404 until it is restored
+pub async fn retire_simulation(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    state.simulations.get_any(&id).ok_or_else(|| ApiError::not_found("simulation", &id))?;
+    content::retire(&state.db, &id, &admin.id).await?;
+    state.simulations.catalog().set_retired(&id, true);
+    tracing::info!("Simulation {} retired by {}", id, admin.id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn restore_simulation(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    if !content::restore(&state.db, &id).await? {
+        return Err(ApiError::not_found("retired simulation", &id));
+    }
+    state.simulations.catalog().set_retired(&id, false);
+    tracing::info!("Simulation {} restored by {}", id, admin.id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn find_draft(state: &AppState, id: &str) -> Result<ContentRevision, ApiError> {
+    ContentRevision::find(&state.db, id, DRAFT)
+        .await?
+        .ok_or_else(|| ApiError::not_found("draft", id))
+}
+
+async fn content_entry(state: &AppState, id: &str) -> Result<ContentEntry, ApiError> {
+    state.simulations.get_any(id).ok_or_else(|| ApiError::not_found("simulation", id))?;
+    let catalog = state.simulations.catalog();
+    let content = catalog.get(id).ok_or_else(|| ApiError::not_found("simulation", id))?;
+    Ok(ContentEntry {
+        id: id.to_string(),
+        source: source(state, id),
+        retired: catalog.is_retired(id),
+        content: content.as_ref().clone(),
+        draft: ContentRevision::find(&state.db, id, DRAFT).await?,
+    })
+}
+
+/// Why `content` cannot replace the content file, if it declares other
+/// parameters than the simulation code reads
+fn parameter_mismatch(state: &AppState, content: &SimulationContent) -> Option<String> {
+    let file = state.simulations.catalog().file(&content.id)?;
+    (!content.same_parameters(&file))
+        .then(|| format!("names, types, options, units and variables must match the simulation code: {}", file.parameter_summary()))
+}
+
+fn source(state: &AppState, id: &str) -> &'static str {
+    if state.simulations.catalog().is_published(id) {
+        PUBLISHED
+    } else {
+        "file"
+    }
+}
+
+#[derive(Serialize)]
+pub struct ContentSummary {
+    pub id: String,
+    pub name: String,
+    /// `file`, or `published` when admin-published content replaces it
+    pub source: &'static str,
+    pub retired: bool,
+    /// When the waiting draft was last saved
+    pub draft_updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize)]
+pub struct ContentEntry {
+    pub id: String,
+    pub source: &'static str,
+    pub retired: bool,
+    /// The content being served
+    pub content: SimulationContent,
+    pub draft: Option<ContentRevision>,
+}
+
+#[derive(Serialize)]
+pub struct ContentPreview {
+    pub listing: SimulationInfo,
+    pub details: SimulationDetails,
+}
@@ -0,0 +1,401 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::models::contribution::{Contribution, Review, APPROVED, PENDING, PUBLISHED, REJECTED, STATUSES};
+use crate::models::simulation::DIFFICULTIES;
+use crate::routes::auth::{AdminUser, AuthUser};
+use crate::routes::custom_simulations::{
+    check_definition, evaluate, CustomRun, CustomRunRequest, CustomSimulationRequest,
+};
+use crate::routes::error::ApiError;
+use crate::routes::simulations::{theory_body, TheoryQuery};
+use crate::simulations::params::ParameterError;
+use crate::state::AppState;
+
+/// Longest listing summary (characters)
+const MAX_SUMMARY_LENGTH: usize = 300;
+/// Longest theory text (characters)
+const MAX_THEORY_LENGTH: usize = 20_000;
+/// Most topic labels on one contribution
+const MAX_TOPICS: usize = 10;
+/// Longest topic label (characters)
+const MAX_TOPIC_LENGTH: usize = 100;
+/// Longest review comment (characters)
+const MAX_COMMENT_LENGTH: usize = 2_000;
+/// Most contributions one user may have waiting for review
+const MAX_PENDING_PER_USER: i64 = 10;
+
+/// The signed-in user's contributions with their reviews, most recently
+/// changed first
+pub async fn list_contributions(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> Result<Json<Vec<Contribution>>, ApiError> {
+    Contribution::by_author(&state.db, &user.id).await.map(Json).map_err(ApiError::from)
+}
+
+/// Submit a scripted simulation for review
+pub async fn submit_contribution(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Json(request): Json<ContributionRequest>,
+) -> Result<(StatusCode, Json<Contribution>), ApiError> {
+    check_contribution(&request)?;
+    check_pending_limit(&state, &user.id).await?;
+    let now = Utc::now();
+    let simulation = request.simulation;
+    let contribution = Contribution {
+        id: Uuid::new_v4().to_string(),
+        author_id: user.id,
+        status: PENDING.to_string(),
+        name: simulation.name.trim().to_string(),
+        summary: request.summary.trim().to_string(),
+        description: simulation.description,
+        difficulty: request.difficulty,
+        topics: request.topics.iter().map(|topic| topic.trim().to_string()).collect(),
+        theory: request.theory,
+        parameters: simulation.parameters,
+        script: simulation.script,
+        domain: simulation.domain,
+        output: simulation.output,
+        created_at: now,
+        updated_at: now,
+        published_at: None,
+        reviews: Some(Vec::new()),
+    };
+    contribution.insert(&state.db).await?;
+    tracing::info!("Contribution {} submitted by {}", contribution.id, contribution.author_id);
+    Ok((StatusCode::CREATED, Json(contribution)))
+}
+
+pub async fn get_contribution(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(id): Path<String>,
+) -> Result<Json<Contribution>, ApiError> {
+    authored(&state, &user, &id).await.map(Json)
+}
+
+/// Replace a contribution's definition, which sends it back to review.
+/// Published contributions are changed by submitting a new one.
+pub async fn revise_contribution(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(id): Path<String>,
+    Json(request): Json<ContributionRequest>,
+) -> Result<Json<Contribution>, ApiError> {
+    check_contribution(&request)?;
+    let mut contribution = authored(&state, &user, &id).await?;
+    if contribution.status == PUBLISHED {
+        return Err(ApiError::Conflict("a published contribution cannot be changed; submit a new one".to_string()));
+    }
+    if contribution.status != PENDING {
+        check_pending_limit(&state, &user.id).await?;
+    }
+    let simulation = request.simulation;
+    contribution.name = simulation.name.trim().to_string();
+    contribution.summary = request.summary.trim().to_string();
+    contribution.description = simulation.description;
+    contribution.difficulty = request.difficulty;
+    contribution.topics = request.topics.iter().map(|topic| topic.trim().to_string()).collect();
+    contribution.theory = request.theory;
+    contribution.parameters = simulation.parameters;
+    contribution.script = simulation.script;
+    contribution.domain = simulation.domain;
+    contribution.output = simulation.output;
+    contribution.status = PENDING.to_string();
+    contribution.updated_at = Utc::now();
+    contribution.update(&state.db).await?;
+    Ok(Json(contribution))
+}
+
+/// Withdraw a contribution that is not in the library
+pub async fn withdraw_contribution(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let contribution = authored(&state, &user, &id).await?;
+    if contribution.status == PUBLISHED {
+        return Err(ApiError::Conflict(
+            "a published contribution stays in the library until an administrator unpublishes it".to_string(),
+        ));
+    }
+    Contribution::delete(&state.db, &contribution.id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Contributions in one status, `pending` by default, the longest waiting
+/// first
+pub async fn review_queue(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+    Query(query): Query<QueueQuery>,
+) -> Result<Json<Vec<Contribution>>, ApiError> {
+    let status = query.status.as_deref().unwrap_or(PENDING);
+    if !STATUSES.contains(&status) {
+        return Err(ApiError::InvalidParameters(vec![ParameterError::new(
+            "status",
+            format!("must be one of: {}", STATUSES.join(", ")),
+            Some(&json!(status)),
+        )]));
+    }
+    Contribution::with_status(&state.db, status).await.map(Json).map_err(ApiError::from)
+}
+
+/// Any contribution with its reviews
+pub async fn get_contribution_for_review(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+    Path(id): Path<String>,
+) -> Result<Json<Contribution>, ApiError> {
+    find(&state, &id).await.map(Json)
+}
+
+/// Approve or reject a pending contribution. A rejection says what to
+/// change; the author revises and the contribution waits for review again.
+pub async fn review_contribution(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    Path(id): Path<String>,
+    Json(request): Json<ReviewRequest>,
+) -> Result<Json<Contribution>, ApiError> {
+    let decision = match request.decision.as_str() {
+        "approve" => APPROVED,
+        "reject" => REJECTED,
+        other => {
+            return Err(ApiError::InvalidParameters(vec![ParameterError::new(
+                "decision",
+                "must be approve or reject",
+                Some(&json!(other)),
+            )]))
+        }
+    };
+    let comment = request.comment.trim().to_string();
+    if comment.chars().count() > MAX_COMMENT_LENGTH {
+        return Err(ApiError::InvalidParameters(vec![ParameterError::new(
+            "comment",
+            format!("must be at most {MAX_COMMENT_LENGTH} characters"),
+            None,
+        )]));
+    }
+    if decision == REJECTED && comment.is_empty() {
+        return Err(ApiError::InvalidParameters(vec![ParameterError::new(
+            "comment",
+            "must say what to change when rejecting",
+            None,
+        )]));
+    }
+
+    let mut contribution = find(&state, &id).await?;
+    if contribution.author_id == admin.id {
+        return Err(ApiError::Forbidden("contributions are reviewed by someone other than their author"));
+    }
+    if contribution.status != PENDING {
+        return Err(ApiError::Conflict(format!(
+            "the contribution is {}; only pending contributions are reviewed",
+            contribution.status
+        )));
+    }
+    let review = Review {
+        id: Uuid::new_v4().to_string(),
+        reviewer_id: admin.id.clone(),
+        decision: decision.to_string(),
+        comment,
+        created_at: Utc::now(),
+    };
+    review.insert(&state.db, &contribution.id).await?;
+    contribution.status = decision.to_string();
+    contribution.updated_at = review.created_at;
+    contribution.update(&state.db).await?;
+    contribution.reviews.get_or_insert_with(Vec::new).push(review);
+    tracing::info!("Contribution {} {} by {}", id, decision, admin.id);
+    Ok(Json(contribution))
+}
+
+/// Put an approved contribution in the community library
+pub async fn publish_contribution(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    Path(id): Path<String>,
+) -> Result<Json<Contribution>, ApiError> {
+    let mut contribution = find(&state, &id).await?;
+    if contribution.status != APPROVED {
+        return Err(ApiError::Conflict(format!(
+            "the contribution is {}; only approved contributions are published",
+            contribution.status
+        )));
+    }
+    let now = Utc::now();
+    contribution.status = PUBLISHED.to_string();
+    contribution.published_at = Some(now);
+    contribution.updated_at = now;
+    contribution.update(&state.db).await?;
+    tracing::info!("Contribution {} published by {}", id, admin.id);
+    Ok(Json(contribution))
+}
+
+/// Take a contribution out of the library; it stays approved and can be
+/// published again
+pub async fn unpublish_contribution(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let mut contribution = find(&state, &id).await?;
+    if contribution.status != PUBLISHED {
+        return Err(ApiError::not_found("published contribution", &id));
+    }
+    contribution.status = APPROVED.to_string();
+    contribution.published_at = None;
+    contribution.updated_at = Utc::now();
+    contribution.update(&state.db).await?;
+    tracing::info!("Contribution {} unpublished by {}", id, admin.id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Published contributions, oldest first
+pub async fn list_library(State(state): State<AppState>) -> Result<Json<Vec<Contribution>>, ApiError> {
+    Contribution::with_status(&state.db, PUBLISHED).await.map(Json).map_err(ApiError::from)
+}
+
+pub async fn get_library_entry(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<Contribution>, ApiError> {
+    published(&state, &id).await.map(Json)
+}
+
+/// A published contribution's theory as written or, with `format=html`,
+/// rendered to a sanitized HTML fragment
+pub async fn get_library_theory(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<TheoryQuery>,
+) -> Result<Response, ApiError> {
+    let contribution = published(&state, &id).await?;
+    let (content_type, body) = theory_body(contribution.theory, query.format.as_deref())?;
+    Ok(([(header::CONTENT_TYPE, content_type)], body).into_response())
+}
+
+/// Evaluate a published contribution's script across its domain, within
+/// `SCRIPT_TIME_LIMIT`
+pub async fn run_library_entry(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(request): Json<CustomRunRequest>,
+) -> Result<Json<CustomRun>, ApiError> {
+    let contribution = published(&state, &id).await?;
+    let Contribution {
+        id, parameters, script, domain, ..
+    } = contribution;
+    evaluate(&id, &parameters, &script, &domain, &request.parameters).await.map(Json)
+}
+
+async fn find(state: &AppState, id: &str) -> Result<Contribution, ApiError> {
+    Contribution::find(&state.db, id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("contribution", id))
+}
+
+/// A contribution of the user's own; anyone else's is reported missing
+async fn authored(state: &AppState, user: &AuthUser, id: &str) -> Result<Contribution, ApiError> {
+    Contribution::find(&state.db, id)
+        .await?
+        .filter(|contribution| contribution.author_id == user.id)
+        .ok_or_else(|| ApiError::not_found("contribution", id))
+}
+
+/// A contribution in the library, without its reviews
+async fn published(state: &AppState, id: &str) -> Result<Contribution, ApiError> {
+    let mut contribution = Contribution::find(&state.db, id)
+        .await?
+        .filter(|contribution| contribution.status == PUBLISHED)
+        .ok_or_else(|| ApiError::not_found("library simulation", id))?;
+    contribution.reviews = None;
+    Ok(contribution)
+}
+
+async fn check_pending_limit(state: &AppState, author_id: &str) -> Result<(), ApiError> {
+    if Contribution::count_by_author(&state.db, author_id, PENDING).await? >= MAX_PENDING_PER_USER {
+        return Err(ApiError::Conflict(format!(
+            "you already have {MAX_PENDING_PER_USER} contributions waiting for review"
+        )));
+    }
+    Ok(())
+}
+
+/// The checks of a custom simulation, and the catalog's rules for the
+/// summary, difficulty, topics and theory
+fn check_contribution(request: &ContributionRequest) -> Result<(), ApiError> {
+    let mut errors = match check_definition(&request.simulation) {
+        Ok(()) => Vec::new(),
+        Err(ApiError::InvalidParameters(errors)) => errors,
+        Err(err) => return Err(err),
+    };
+    for (field, text, limit) in [
+        ("summary", &request.summary, MAX_SUMMARY_LENGTH),
+        ("theory", &request.theory, MAX_THEORY_LENGTH),
+    ] {
+        let length = text.trim().chars().count();
+        if length == 0 || length > limit {
+            errors.push(ParameterError::new(field, format!("must be 1 to {limit} characters"), None));
+        }
+    }
+    if !DIFFICULTIES.contains(&request.difficulty.as_str()) {
+        errors.push(ParameterError::new(
+            "difficulty",
+            format!("must be one of: {}", DIFFICULTIES.join(", ")),
+            Some(&json!(request.difficulty)),
+        ));
+    }
+    if request.topics.len() > MAX_TOPICS {
+        errors.push(ParameterError::new("topics", format!("may hold at most {MAX_TOPICS} labels"), None));
+    }
+    for (i, topic) in request.topics.iter().enumerate() {
+        let length = topic.trim().chars().count();
+        if length == 0 || length > MAX_TOPIC_LENGTH {
+            errors.push(ParameterError::new(
+                &format!("topics[{i}]"),
+                format!("must be 1 to {MAX_TOPIC_LENGTH} characters"),
+                Some(&json!(topic)),
+            ));
+        }
+    }
+    if errors.is_empty() { Ok(()) } else { Err(ApiError::InvalidParameters(errors)) }
+}
+
+#[derive(Deserialize)]
+pub struct ContributionRequest {
+    /// Name, description, sliders, script, domain and output, as for a
+    /// custom simulation
+    #[serde(flatten)]
+    pub simulation: CustomSimulationRequest,
+    pub summary: String,
+    pub difficulty: String,
+    #[serde(default)]
+    pub topics: Vec<String>,
+    pub theory: String,
+}
+
+#[derive(Deserialize)]
+pub struct ReviewRequest {
+    /// `approve` or `reject`
+    pub decision: String,
+    /// Required when rejecting
+    #[serde(default)]
+    pub comment: String,
+}
+
+#[derive(Deserialize)]
+pub struct QueueQuery {
+    pub status: Option<String>,
+}
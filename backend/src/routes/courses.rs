@@ -0,0 +1,327 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::course::{Course, CourseModule, Lesson, LessonItem};
+use crate::routes::auth::AuthUser;
+use crate::routes::error::ApiError;
+use crate::simulations::params::ParameterError;
+use crate::state::AppState;
+
+/// Longest course, module or lesson title (characters)
+const MAX_TITLE_LENGTH: usize = 200;
+
+/// List published courses
+pub async fn list_courses(State(state): State<AppState>) -> Result<Json<Vec<Course>>, ApiError> {
+    Course::published(&state.db).await.map(Json).map_err(ApiError::from)
+}
+
+/// List every course of the signed-in instructor, drafts included
+pub async fn my_courses(State(state): State<AppState>, user: AuthUser) -> Result<Json<Vec<Course>>, ApiError> {
+    Course::owned_by(&state.db, &user.id).await.map(Json).map_err(ApiError::from)
+}
+
+/// Get a course with its modules and lessons in order
+pub async fn get_course(
+    State(state): State<AppState>,
+    user: Option<AuthUser>,
+    Path(id): Path<String>,
+) -> Result<Json<CourseView>, ApiError> {
+    let course = visible_course(&state, user.as_ref(), &id).await?;
+    let modules = CourseModule::for_course(&state.db, &course.id).await?;
+    let mut lessons = Lesson::for_course(&state.db, &course.id).await?;
+    let modules = modules
+        .into_iter()
+        .map(|module| {
+            let (own, rest) = lessons.drain(..).partition(|lesson| lesson.module_id == module.id);
+            lessons = rest;
+            ModuleView { module, lessons: own }
+        })
+        .collect();
+    Ok(Json(CourseView { course, modules }))
+}
+
+/// Create a course owned by the signed-in instructor. It stays a draft
+/// unless `published` is set.
+pub async fn create_course(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Json(request): Json<CourseRequest>,
+) -> Result<(StatusCode, Json<Course>), ApiError> {
+    check_title(&request.title)?;
+    let now = Utc::now();
+    let course = Course {
+        id: Uuid::new_v4().to_string(),
+        owner_id: user.id,
+        title: request.title.trim().to_string(),
+        description: request.description,
+        published: request.published,
+        created_at: now,
+        updated_at: now,
+    };
+    course.insert(&state.db).await?;
+    Ok((StatusCode::CREATED, Json(course)))
+}
+
+/// Replace a course's title, description and publication state
+pub async fn update_course(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(id): Path<String>,
+    Json(request): Json<CourseRequest>,
+) -> Result<Json<Course>, ApiError> {
+    check_title(&request.title)?;
+    let mut course = owned_course(&state, &user, &id).await?;
+    course.title = request.title.trim().to_string();
+    course.description = request.description;
+    course.published = request.published;
+    course.updated_at = Utc::now();
+    course.update(&state.db).await?;
+    Ok(Json(course))
+}
+
+/// Delete a course with all its modules and lessons
+pub async fn delete_course(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let course = owned_course(&state, &user, &id).await?;
+    Course::delete(&state.db, &course.id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Add a module to a course, at `position` or at the end
+pub async fn create_module(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(course_id): Path<String>,
+    Json(request): Json<ModuleRequest>,
+) -> Result<(StatusCode, Json<CourseModule>), ApiError> {
+    check_title(&request.title)?;
+    let course = owned_course(&state, &user, &course_id).await?;
+    let mut module = CourseModule {
+        id: Uuid::new_v4().to_string(),
+        course_id: course.id,
+        position: 0,
+        title: request.title.trim().to_string(),
+        description: request.description,
+    };
+    module.insert(&state.db, request.position).await?;
+    Course::touch(&state.db, &module.course_id).await?;
+    Ok((StatusCode::CREATED, Json(module)))
+}
+
+/// Rename or describe a module, and move it when `position` is given
+pub async fn update_module(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(id): Path<String>,
+    Json(request): Json<ModuleRequest>,
+) -> Result<Json<CourseModule>, ApiError> {
+    check_title(&request.title)?;
+    let mut module = owned_module(&state, &user, &id).await?;
+    module.title = request.title.trim().to_string();
+    module.description = request.description;
+    module.update(&state.db, request.position).await?;
+    Course::touch(&state.db, &module.course_id).await?;
+    Ok(Json(module))
+}
+
+/// Delete a module with its lessons
+pub async fn delete_module(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let module = owned_module(&state, &user, &id).await?;
+    module.delete(&state.db).await?;
+    Course::touch(&state.db, &module.course_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Get one lesson
+pub async fn get_lesson(
+    State(state): State<AppState>,
+    user: Option<AuthUser>,
+    Path(id): Path<String>,
+) -> Result<Json<Lesson>, ApiError> {
+    let lesson = find_lesson(&state, &id).await?;
+    let module = find_module(&state, &lesson.module_id).await?;
+    visible_course(&state, user.as_ref(), &module.course_id)
+        .await
+        // Don't reveal that lessons of a draft course exist
+        .map_err(|_| ApiError::not_found("lesson", &id))?;
+    Ok(Json(lesson))
+}
+
+/// Add a lesson to a module, at `position` or at the end
+pub async fn create_lesson(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(module_id): Path<String>,
+    Json(request): Json<LessonRequest>,
+) -> Result<(StatusCode, Json<Lesson>), ApiError> {
+    check_lesson(&state, &request)?;
+    let module = owned_module(&state, &user, &module_id).await?;
+    let mut lesson = Lesson {
+        id: Uuid::new_v4().to_string(),
+        module_id: module.id,
+        position: 0,
+        title: request.title.trim().to_string(),
+        items: request.items,
+    };
+    lesson.insert(&state.db, request.position).await?;
+    Course::touch(&state.db, &module.course_id).await?;
+    Ok((StatusCode::CREATED, Json(lesson)))
+}
+
+/// Replace a lesson's title and items, and move it when `position` is given
+pub async fn update_lesson(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(id): Path<String>,
+    Json(request): Json<LessonRequest>,
+) -> Result<Json<Lesson>, ApiError> {
+    check_lesson(&state, &request)?;
+    let mut lesson = find_lesson(&state, &id).await?;
+    let module = owned_module(&state, &user, &lesson.module_id).await?;
+    lesson.title = request.title.trim().to_string();
+    lesson.items = request.items;
+    lesson.update(&state.db, request.position).await?;
+    Course::touch(&state.db, &module.course_id).await?;
+    Ok(Json(lesson))
+}
+
+/// Delete a lesson
+pub async fn delete_lesson(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let lesson = find_lesson(&state, &id).await?;
+    let module = owned_module(&state, &user, &lesson.module_id).await?;
+    lesson.delete(&state.db).await?;
+    Course::touch(&state.db, &module.course_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// A course the user may read: published, or their own draft
+async fn visible_course(state: &AppState, user: Option<&AuthUser>, id: &str) -> Result<Course, ApiError> {
+    Course::find(&state.db, id)
+        .await?
+        .filter(|course| course.published || user.is_some_and(|user| user.id == course.owner_id))
+        .ok_or_else(|| ApiError::not_found("course", id))
+}
+
+/// A course the user may change: one they created
+async fn owned_course(state: &AppState, user: &AuthUser, id: &str) -> Result<Course, ApiError> {
+    let course = visible_course(state, Some(user), id).await?;
+    if course.owner_id != user.id {
+        return Err(ApiError::Forbidden("only the course's instructor can change it"));
+    }
+    Ok(course)
+}
+
+async fn find_module(state: &AppState, id: &str) -> Result<CourseModule, ApiError> {
+    CourseModule::find(&state.db, id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("module", id))
+}
+
+/// A module of a course the user may change
+async fn owned_module(state: &AppState, user: &AuthUser, id: &str) -> Result<CourseModule, ApiError> {
+    let module = find_module(state, id).await?;
+    owned_course(state, user, &module.course_id)
+        .await
+        .map_err(|err| match err {
+            ApiError::NotFound { .. } => ApiError::not_found("module", id),
+            err => err,
+        })?;
+    Ok(module)
+}
+
+async fn find_lesson(state: &AppState, id: &str) -> Result<Lesson, ApiError> {
+    Lesson::find(&state.db, id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("lesson", id))
+}
+
+fn check_title(title: &str) -> Result<(), ApiError> {
+    let length = title.trim().chars().count();
+    if length == 0 || length > MAX_TITLE_LENGTH {
+        return Err(ApiError::InvalidParameters(vec![ParameterError::new(
+            "title",
+            format!("must be 1 to {MAX_TITLE_LENGTH} characters"),
+            Some(&title.into()),
+        )]));
+    }
+    Ok(())
+}
+
+/// Every item must refer to an existing simulation, and quiz items to one
+/// that has a quiz
+fn check_lesson(state: &AppState, request: &LessonRequest) -> Result<(), ApiError> {
+    check_title(&request.title)?;
+    let mut errors = Vec::new();
+    for (i, item) in request.items.iter().enumerate() {
+        let Some(simulation_id) = item.simulation_id() else {
+            continue;
+        };
+        let name = format!("items[{i}].simulation_id");
+        match state.simulations.get(simulation_id) {
+            None => errors.push(ParameterError::new(&name, "unknown simulation", Some(&simulation_id.into()))),
+            Some(simulation) if matches!(item, LessonItem::Quiz { .. }) && simulation.quiz().is_none() => {
+                errors.push(ParameterError::new(&name, "simulation has no quiz", Some(&simulation_id.into())))
+            }
+            Some(_) => {}
+        }
+    }
+    if errors.is_empty() { Ok(()) } else { Err(ApiError::InvalidParameters(errors)) }
+}
+
+#[derive(Deserialize)]
+pub struct CourseRequest {
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub published: bool,
+}
+
+#[derive(Deserialize)]
+pub struct ModuleRequest {
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    /// Place within the course, from 0; past the end means last
+    pub position: Option<u32>,
+}
+
+#[derive(Deserialize)]
+pub struct LessonRequest {
+    pub title: String,
+    #[serde(default)]
+    pub items: Vec<LessonItem>,
+    /// Place within the module, from 0; past the end means last
+    pub position: Option<u32>,
+}
+
+#[derive(Serialize)]
+pub struct CourseView {
+    #[serde(flatten)]
+    pub course: Course,
+    pub modules: Vec<ModuleView>,
+}
+
+#[derive(Serialize)]
+pub struct ModuleView {
+    #[serde(flatten)]
+    pub module: CourseModule,
+    pub lessons: Vec<Lesson>,
+}
@@ -0,0 +1,82 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::routes::error::ApiError;
+use crate::services::curriculum::{edges, learning_order, Edge};
+use crate::simulations::Simulation;
+use crate::state::AppState;
+
+/// A simulation in the prerequisite graph
+#[derive(Serialize)]
+pub struct CurriculumNode {
+    pub id: &'static str,
+    pub name: String,
+    pub difficulty: String,
+    pub prerequisites: &'static [&'static str],
+}
+
+impl CurriculumNode {
+    fn new(simulation: &dyn Simulation) -> Self {
+        let info = simulation.info();
+        Self {
+            id: simulation.id(),
+            name: info.name,
+            difficulty: info.difficulty,
+            prerequisites: simulation.prerequisites(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct CurriculumGraph {
+    pub nodes: Vec<CurriculumNode>,
+    /// One edge per prerequisite, from the prerequisite to the simulation
+    /// that needs it
+    pub edges: Vec<Edge>,
+}
+
+#[derive(Deserialize)]
+pub struct OrderQuery {
+    /// Only the path to this simulation
+    pub target: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct LearningOrder {
+    pub target: Option<String>,
+    /// Simulations in the order to study them, prerequisites first
+    pub simulations: Vec<CurriculumNode>,
+}
+
+/// The prerequisite graph between all simulations, for drawing a skill tree
+pub async fn curriculum_graph(State(state): State<AppState>) -> Json<CurriculumGraph> {
+    Json(CurriculumGraph {
+        nodes: state.simulations.iter().map(|s| CurriculumNode::new(s.as_ref())).collect(),
+        edges: edges(&state.simulations),
+    })
+}
+
+/// Recommended order to work through the simulations, optionally only
+/// those leading up to `target`
+pub async fn curriculum_order(
+    State(state): State<AppState>,
+    Query(query): Query<OrderQuery>,
+) -> Result<Json<LearningOrder>, ApiError> {
+    if let Some(target) = &query.target {
+        if state.simulations.get(target).is_none() {
+            return Err(ApiError::not_found("simulation", target));
+        }
+    }
+    let simulations = learning_order(&state.simulations, query.target.as_deref())
+        .into_iter()
+        .filter_map(|id| state.simulations.get(id))
+        .map(|s| CurriculumNode::new(s.as_ref()))
+        .collect();
+    Ok(Json(LearningOrder {
+        target: query.target,
+        simulations,
+    }))
+}
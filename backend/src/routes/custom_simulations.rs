@@ -0,0 +1,298 @@
+use std::collections::{BTreeMap, HashSet};
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use uuid::Uuid;
+
+use crate::models::custom_simulation::{CustomSimulation, Domain, Output};
+use crate::models::simulation::SimulationParameter;
+use crate::physics::units;
+use crate::routes::auth::AuthUser;
+use crate::routes::error::ApiError;
+use crate::services::budget::SCRIPT_TIME_LIMIT;
+use crate::services::script::{Script, VARIABLE};
+use crate::services::trace;
+use crate::simulations::catalog::ParameterDefinition;
+use crate::simulations::params::{ParameterError, Params};
+use crate::simulations::{Progress, SimulationError};
+use crate::state::AppState;
+
+/// Longest name (characters)
+const MAX_NAME_LENGTH: usize = 200;
+/// Longest description (characters)
+const MAX_DESCRIPTION_LENGTH: usize = 2_000;
+/// Most sliders a custom simulation may have
+const MAX_PARAMETERS: usize = 20;
+/// Most points x is sampled at
+const MAX_POINTS: usize = 10_000;
+/// Most custom simulations one user may keep
+const MAX_PER_USER: i64 = 50;
+/// Points evaluated between checks of the deadline
+const CHECK_EVERY: usize = 256;
+
+/// The signed-in user's custom simulations, most recently changed first
+pub async fn list_custom_simulations(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> Result<Json<Vec<CustomSimulation>>, ApiError> {
+    CustomSimulation::owned_by(&state.db, &user.id).await.map(Json).map_err(ApiError::from)
+}
+
+/// Register a script as a private simulation of the signed-in user
+pub async fn create_custom_simulation(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Json(request): Json<CustomSimulationRequest>,
+) -> Result<(StatusCode, Json<CustomSimulation>), ApiError> {
+    check_definition(&request)?;
+    if CustomSimulation::count_owned_by(&state.db, &user.id).await? >= MAX_PER_USER {
+        return Err(ApiError::Conflict(format!(
+            "you already have {MAX_PER_USER} custom simulations; delete one to add another"
+        )));
+    }
+    let now = Utc::now();
+    let simulation = CustomSimulation {
+        id: Uuid::new_v4().to_string(),
+        owner_id: user.id,
+        name: request.name.trim().to_string(),
+        description: request.description,
+        parameters: request.parameters,
+        script: request.script,
+        domain: request.domain,
+        output: request.output,
+        created_at: now,
+        updated_at: now,
+    };
+    simulation.insert(&state.db).await?;
+    Ok((StatusCode::CREATED, Json(simulation)))
+}
+
+pub async fn get_custom_simulation(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(id): Path<String>,
+) -> Result<Json<CustomSimulation>, ApiError> {
+    owned(&state, &user, &id).await.map(Json)
+}
+
+/// Replace a custom simulation's definition
+pub async fn update_custom_simulation(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(id): Path<String>,
+    Json(request): Json<CustomSimulationRequest>,
+) -> Result<Json<CustomSimulation>, ApiError> {
+    check_definition(&request)?;
+    let mut simulation = owned(&state, &user, &id).await?;
+    simulation.name = request.name.trim().to_string();
+    simulation.description = request.description;
+    simulation.parameters = request.parameters;
+    simulation.script = request.script;
+    simulation.domain = request.domain;
+    simulation.output = request.output;
+    simulation.updated_at = Utc::now();
+    simulation.update(&state.db).await?;
+    Ok(Json(simulation))
+}
+
+pub async fn delete_custom_simulation(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let simulation = owned(&state, &user, &id).await?;
+    CustomSimulation::delete(&state.db, &simulation.id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Evaluate the script at every point of its domain for the given slider
+/// values, within `SCRIPT_TIME_LIMIT`
+pub async fn run_custom_simulation(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(id): Path<String>,
+    Json(request): Json<CustomRunRequest>,
+) -> Result<Json<CustomRun>, ApiError> {
+    let simulation = owned(&state, &user, &id).await?;
+    evaluate(&simulation.id, &simulation.parameters, &simulation.script, &simulation.domain, &request.parameters)
+        .await
+        .map(Json)
+}
+
+/// Sample a scripted simulation across its domain for the slider values
+/// `raw`, the defaults standing in for those left out
+pub(crate) async fn evaluate(
+    id: &str,
+    parameters: &[ParameterDefinition],
+    script: &str,
+    domain: &Domain,
+    raw: &Map<String, Value>,
+) -> Result<CustomRun, ApiError> {
+    let schema: Vec<SimulationParameter> = parameters.iter().map(ParameterDefinition::to_parameter).collect();
+    let params = Params::validate(&schema, raw).map_err(ApiError::InvalidParameters)?;
+    let names: Vec<&str> = parameters.iter().map(|p| p.name.as_str()).collect();
+    let script = Script::parse(script, &names).map_err(|message| {
+        ApiError::InvalidParameters(vec![ParameterError::new("script", message, None)])
+    })?;
+
+    let domain = domain.clone();
+    let inputs: BTreeMap<String, f64> = names.iter().map(|&name| (name.to_string(), params.f64(name))).collect();
+    let progress = Progress::default().with_time_limit(SCRIPT_TIME_LIMIT);
+    let (x, y) = trace::spawn_blocking(move || {
+        let step = (domain.max - domain.min) / (domain.points - 1) as f64;
+        let x: Vec<f64> = (0..domain.points).map(|i| domain.min + i as f64 * step).collect();
+        let mut values = inputs;
+        let mut y = Vec::with_capacity(x.len());
+        for chunk in x.chunks(CHECK_EVERY) {
+            progress.check()?;
+            for &point in chunk {
+                values.insert(VARIABLE.to_string(), point);
+                y.push(script.eval(&mut values));
+            }
+        }
+        Ok::<_, SimulationError>((x, y))
+    })
+    .await
+    .map_err(|e| {
+        tracing::error!("Scripted simulation {} failed: {}", id, e);
+        ApiError::Internal
+    })??;
+
+    Ok(CustomRun {
+        simulation_id: id.to_string(),
+        parameters: params.values().clone(),
+        undefined: y.iter().filter(|value| !value.is_finite()).count(),
+        x,
+        y,
+        computed_at: Utc::now(),
+    })
+}
+
+/// A custom simulation of the user's own; anyone else's is reported
+/// missing, as it is private
+async fn owned(state: &AppState, user: &AuthUser, id: &str) -> Result<CustomSimulation, ApiError> {
+    CustomSimulation::find(&state.db, id)
+        .await?
+        .filter(|simulation| simulation.owner_id == user.id)
+        .ok_or_else(|| ApiError::not_found("custom simulation", id))
+}
+
+/// Every problem with a definition: text lengths, sliders as the content
+/// files declare them, a script that parses and reads only its sliders and
+/// `x`, and a domain within the limits
+pub(crate) fn check_definition(request: &CustomSimulationRequest) -> Result<(), ApiError> {
+    let mut errors = Vec::new();
+    let name_length = request.name.trim().chars().count();
+    if name_length == 0 || name_length > MAX_NAME_LENGTH {
+        errors.push(ParameterError::new(
+            "name",
+            format!("must be 1 to {MAX_NAME_LENGTH} characters"),
+            Some(&json!(request.name)),
+        ));
+    }
+    if request.description.chars().count() > MAX_DESCRIPTION_LENGTH {
+        errors.push(ParameterError::new(
+            "description",
+            format!("must be at most {MAX_DESCRIPTION_LENGTH} characters"),
+            None,
+        ));
+    }
+
+    if request.parameters.len() > MAX_PARAMETERS {
+        errors.push(ParameterError::new("parameters", format!("may hold at most {MAX_PARAMETERS} sliders"), None));
+    }
+    let mut seen = HashSet::new();
+    for (i, parameter) in request.parameters.iter().enumerate() {
+        let field = format!("parameters[{i}]");
+        if parameter.param_type != "slider" {
+            errors.push(ParameterError::new(&field, "must be a slider", Some(&json!(parameter.param_type))));
+            continue;
+        }
+        for problem in parameter.problems() {
+            errors.push(ParameterError::new(&field, problem, None));
+        }
+        if parameter.name == VARIABLE {
+            errors.push(ParameterError::new(&field, format!("`{VARIABLE}` is the variable f is sampled along"), None));
+        }
+        if !seen.insert(parameter.name.as_str()) {
+            errors.push(ParameterError::new(&field, format!("`{}` is declared twice", parameter.name), None));
+        }
+    }
+
+    let names: Vec<&str> = request.parameters.iter().map(|p| p.name.as_str()).collect();
+    if let Err(message) = Script::parse(&request.script, &names) {
+        errors.push(ParameterError::new("script", message, None));
+    }
+
+    let domain = &request.domain;
+    if !(domain.min.is_finite() && domain.max.is_finite() && domain.min < domain.max) {
+        errors.push(ParameterError::new("domain", "needs finite min < max", None));
+    }
+    if !(2..=MAX_POINTS).contains(&domain.points) {
+        errors.push(ParameterError::new(
+            "domain.points",
+            format!("must be between 2 and {MAX_POINTS}"),
+            Some(&json!(domain.points)),
+        ));
+    }
+    for (field, label, unit) in [
+        ("domain", &domain.label, &domain.unit),
+        ("output", &request.output.label, &request.output.unit),
+    ] {
+        if label.trim().is_empty() || label.chars().count() > MAX_NAME_LENGTH {
+            errors.push(ParameterError::new(
+                &format!("{field}.label"),
+                format!("must be 1 to {MAX_NAME_LENGTH} characters"),
+                None,
+            ));
+        }
+        if let Some(unit) = unit {
+            if units::find(unit).is_none_or(|known| known.symbol != unit) {
+                errors.push(ParameterError::new(
+                    &format!("{field}.unit"),
+                    "must be a symbol from /api/v1/units",
+                    Some(&json!(unit)),
+                ));
+            }
+        }
+    }
+    if errors.is_empty() { Ok(()) } else { Err(ApiError::InvalidParameters(errors)) }
+}
+
+#[derive(Deserialize)]
+pub struct CustomSimulationRequest {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub parameters: Vec<ParameterDefinition>,
+    pub script: String,
+    pub domain: Domain,
+    pub output: Output,
+}
+
+#[derive(Deserialize)]
+pub struct CustomRunRequest {
+    /// Slider values by name; those left out take their defaults
+    #[serde(default)]
+    pub parameters: Map<String, Value>,
+}
+
+#[derive(Serialize)]
+pub struct CustomRun {
+    pub simulation_id: String,
+    /// Slider values used, defaults included
+    pub parameters: Map<String, Value>,
+    pub x: Vec<f64>,
+    /// f at each x; null where it is not a finite number
+    pub y: Vec<f64>,
+    /// Points where f is not a finite number
+    pub undefined: usize,
+    pub computed_at: DateTime<Utc>,
+}
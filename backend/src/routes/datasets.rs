@@ -0,0 +1,37 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use serde::Deserialize;
+
+use crate::routes::error::ApiError;
+use crate::services::datasets::{Dataset, DatasetSummary};
+use crate::state::AppState;
+
+#[derive(Deserialize)]
+pub struct DatasetQuery {
+    /// Only datasets for this simulation
+    pub simulation: Option<String>,
+}
+
+/// List the experimental datasets runs can be compared with, without their
+/// data
+pub async fn list_datasets(
+    State(state): State<AppState>,
+    Query(query): Query<DatasetQuery>,
+) -> Json<Vec<DatasetSummary>> {
+    Json(
+        state
+            .datasets
+            .iter()
+            .filter(|dataset| query.simulation.as_ref().is_none_or(|id| dataset.simulation == *id))
+            .map(Dataset::summary)
+            .collect(),
+    )
+}
+
+/// Get a dataset with its measurements and the run parameters that
+/// reproduce the experiment
+pub async fn get_dataset(State(state): State<AppState>, Path(id): Path<String>) -> Result<Json<Dataset>, ApiError> {
+    state.datasets.get(&id).cloned().map(Json).ok_or_else(|| ApiError::not_found("dataset", &id))
+}
@@ -0,0 +1,231 @@
+// Errors returned by the simulation routes, as RFC 7807 problem details
+
+use axum::{
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+
+use crate::services::embeddings::EmbeddingsError;
+use crate::services::ipfs::IpfsError;
+use crate::services::lti::LtiError;
+use crate::services::trace::request_id;
+use crate::services::tutor::TutorError;
+use crate::simulations::params::ParameterError;
+use crate::simulations::SimulationError;
+
+/// Page describing every error code; each problem's `type` links to its section
+const ERROR_DOCS: &str = "https://github.com/desci-intelligent-universe/physics-tutorial/blob/main/docs/ERRORS.md";
+
+/// Failure of a simulation, result, quiz, job, course or LTI request
+pub enum ApiError {
+    /// No such simulation, result, quiz or job
+    NotFound { resource: &'static str, id: String },
+    InvalidParameters(Vec<ParameterError>),
+    /// The LTI launch could not be verified
+    Unauthorized(String),
+    /// Signed in, but not allowed to change this resource
+    Forbidden(&'static str),
+    /// The simulation or result cannot be served in the requested form
+    Unsupported(&'static str),
+    /// The request conflicts with the current state, e.g. cancelling a
+    /// finished job
+    Conflict(String),
+    /// Too many background jobs are already waiting
+    Busy,
+    /// The server is shutting down and takes no new jobs
+    ShuttingDown,
+    /// The client used up its request budget; it may retry after this many
+    /// seconds
+    RateLimited(u64),
+    /// A learning platform, IPFS node or model API we called did not answer
+    /// properly
+    Upstream(String),
+    /// The run needed more time or output than one request may use
+    BudgetExceeded(String),
+    Storage,
+    Internal,
+}
+
+impl ApiError {
+    pub fn not_found(resource: &'static str, id: &str) -> Self {
+        Self::NotFound {
+            resource,
+            id: id.to_string(),
+        }
+    }
+
+    fn status(&self) -> StatusCode {
+        match self {
+            Self::NotFound { .. } => StatusCode::NOT_FOUND,
+            Self::InvalidParameters(_) | Self::BudgetExceeded(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            Self::Forbidden(_) => StatusCode::FORBIDDEN,
+            Self::Unsupported(_) => StatusCode::BAD_REQUEST,
+            Self::Conflict(_) => StatusCode::CONFLICT,
+            Self::Busy | Self::ShuttingDown => StatusCode::SERVICE_UNAVAILABLE,
+            Self::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
+            Self::Upstream(_) => StatusCode::BAD_GATEWAY,
+            Self::Storage | Self::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// Stable machine-readable code and short human title
+    fn code(&self) -> (&'static str, &'static str) {
+        match self {
+            Self::NotFound { .. } => ("not_found", "Not found"),
+            Self::InvalidParameters(_) => ("invalid_parameters", "Invalid parameters"),
+            Self::Unauthorized(_) => ("unauthorized", "Unauthorized"),
+            Self::Forbidden(_) => ("forbidden", "Forbidden"),
+            Self::Unsupported(_) => ("unsupported", "Not supported"),
+            Self::Conflict(_) => ("conflict", "Conflict"),
+            Self::Busy => ("busy", "Server busy"),
+            Self::ShuttingDown => ("shutting_down", "Server shutting down"),
+            Self::RateLimited(_) => ("rate_limited", "Too many requests"),
+            Self::Upstream(_) => ("upstream_error", "Upstream error"),
+            Self::BudgetExceeded(_) => ("budget_exceeded", "Compute budget exceeded"),
+            Self::Storage => ("storage_error", "Storage error"),
+            Self::Internal => ("internal_error", "Internal error"),
+        }
+    }
+}
+
+impl From<SimulationError> for ApiError {
+    fn from(err: SimulationError) -> Self {
+        match err {
+            SimulationError::InvalidParameters(errors) => Self::InvalidParameters(errors),
+            SimulationError::TimeLimitExceeded(_)
+            | SimulationError::OutputTooLarge { .. }
+            | SimulationError::FuelExhausted(_) => Self::BudgetExceeded(err.to_string()),
+            // Logged with the run's parameters where it failed
+            SimulationError::Failed(_) => Self::Internal,
+            // Only background jobs can be cancelled
+            SimulationError::Cancelled => Self::Internal,
+        }
+    }
+}
+
+impl From<sqlx::Error> for ApiError {
+    fn from(err: sqlx::Error) -> Self {
+        tracing::error!("Storage error: {}", err);
+        Self::Storage
+    }
+}
+
+impl From<LtiError> for ApiError {
+    fn from(err: LtiError) -> Self {
+        match err {
+            LtiError::NotConfigured => Self::Unsupported("LTI is not configured on this server"),
+            LtiError::UnknownPlatform(issuer) => Self::not_found("LTI platform", &issuer),
+            LtiError::InvalidLaunch(_) => Self::Unauthorized(err.to_string()),
+            LtiError::Platform(_) => {
+                tracing::warn!("LTI platform request failed: {}", err);
+                Self::Upstream(err.to_string())
+            }
+        }
+    }
+}
+
+impl From<IpfsError> for ApiError {
+    fn from(err: IpfsError) -> Self {
+        match err {
+            IpfsError::NotConfigured => Self::Unsupported("IPFS is not configured on this server"),
+            IpfsError::Node(_) => {
+                tracing::warn!("{}", err);
+                Self::Upstream(err.to_string())
+            }
+        }
+    }
+}
+
+impl From<EmbeddingsError> for ApiError {
+    fn from(err: EmbeddingsError) -> Self {
+        match err {
+            EmbeddingsError::Provider(_) => {
+                tracing::warn!("{}", err);
+                Self::Upstream(err.to_string())
+            }
+            EmbeddingsError::Storage(_) => {
+                tracing::error!("{}", err);
+                Self::Storage
+            }
+        }
+    }
+}
+
+impl From<TutorError> for ApiError {
+    fn from(err: TutorError) -> Self {
+        match err {
+            TutorError::NotConfigured => Self::Unsupported("AI tutoring is not configured on this server"),
+            TutorError::Provider(_) => {
+                tracing::warn!("{}", err);
+                Self::Upstream(err.to_string())
+            }
+        }
+    }
+}
+
+/// RFC 7807 problem details body
+#[derive(Serialize)]
+pub struct Problem {
+    /// Link to the documentation of this error code
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub title: &'static str,
+    pub status: u16,
+    pub detail: String,
+    pub code: &'static str,
+    /// First offending parameter, for invalid parameters
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parameter: Option<String>,
+    /// Every invalid parameter with its reason
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<ParameterError>,
+    /// ID of the failed request, as in the `X-Request-Id` header, for
+    /// finding it in the server logs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        let (code, title) = self.code();
+        let retry_after = match self {
+            Self::RateLimited(seconds) => Some(seconds),
+            _ => None,
+        };
+        let (detail, fields) = match self {
+            Self::NotFound { resource, id } => (format!("{resource} `{id}` does not exist"), Vec::new()),
+            Self::InvalidParameters(fields) => {
+                ("One or more simulation parameters are invalid".to_string(), fields)
+            }
+            Self::Forbidden(detail) | Self::Unsupported(detail) => (detail.to_string(), Vec::new()),
+            Self::Unauthorized(detail)
+            | Self::Conflict(detail)
+            | Self::Upstream(detail)
+            | Self::BudgetExceeded(detail) => (detail, Vec::new()),
+            Self::Busy => ("too many jobs are queued; try again later".to_string(), Vec::new()),
+            Self::ShuttingDown => ("the server is restarting; submit the job again shortly".to_string(), Vec::new()),
+            Self::RateLimited(seconds) => (format!("request limit reached; try again in {seconds} s"), Vec::new()),
+            Self::Storage => ("results could not be read or saved".to_string(), Vec::new()),
+            Self::Internal => ("the computation failed unexpectedly".to_string(), Vec::new()),
+        };
+        let problem = Problem {
+            kind: format!("{ERROR_DOCS}#{code}"),
+            title,
+            status: status.as_u16(),
+            detail,
+            code,
+            parameter: fields.first().map(|f| f.name.clone()),
+            fields,
+            request_id: request_id(),
+        };
+        let mut response = (status, [(header::CONTENT_TYPE, "application/problem+json")], Json(problem)).into_response();
+        if let Some(seconds) = retry_after {
+            response.headers_mut().insert(header::RETRY_AFTER, seconds.into());
+        }
+        response
+    }
+}
@@ -0,0 +1,154 @@
+use std::time::{Duration, Instant};
+
+use axum::{extract::State, http::StatusCode, Json};
+use serde::Serialize;
+
+use crate::services::bundle::CODE_COMMIT;
+use crate::state::AppState;
+
+/// Longest wait for the database before the instance is reported not ready
+const DATABASE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Liveness probe: the process is up and serving requests. Depends on
+/// nothing else, so a database outage does not get the instance restarted.
+pub async fn healthz() -> Json<Probe> {
+    Json(Probe { status: "ok" })
+}
+
+/// Readiness probe. The instance is not ready, with status 503, when the
+/// database does not answer or it is shutting down; an unreachable Redis
+/// cache or a full job queue only degrade it, since requests can still be
+/// served.
+pub async fn readyz(State(state): State<AppState>) -> (StatusCode, Json<Readiness>) {
+    let started = Instant::now();
+    let query = sqlx::query("SELECT 1").execute(&state.db);
+    let database = match tokio::time::timeout(DATABASE_TIMEOUT, query).await {
+        Ok(Ok(_)) => Check::ok(started.elapsed()),
+        Ok(Err(err)) => {
+            tracing::warn!("Readiness check: database unavailable: {}", err);
+            Check::failing(started.elapsed(), "database query failed")
+        }
+        Err(_) => Check::failing(started.elapsed(), "database did not answer in time"),
+    };
+
+    let started = Instant::now();
+    let cache = match state.cache.redis_reachable().await {
+        None => Check {
+            status: "ok",
+            latency_ms: None,
+            detail: Some("in memory only".to_string()),
+        },
+        Some(true) => Check::ok(started.elapsed()),
+        Some(false) => Check::failing(started.elapsed(), "Redis is unreachable; caching in memory"),
+    };
+
+    let (queued, running) = state.jobs.depth();
+    let capacity = state.jobs.capacity();
+    let pending = format!("{} of {} pending", queued + running, capacity);
+    let shutting_down = !state.jobs.is_accepting();
+    let jobs = Check {
+        status: if queued + running < capacity && !shutting_down { "ok" } else { "failing" },
+        latency_ms: None,
+        detail: Some(if shutting_down { format!("shutting down, {pending}") } else { pending }),
+    };
+
+    let (status, code) = match (database.status, cache.status, jobs.status) {
+        ("failing", _, _) => ("not_ready", StatusCode::SERVICE_UNAVAILABLE),
+        _ if shutting_down => ("not_ready", StatusCode::SERVICE_UNAVAILABLE),
+        (_, "failing", _) | (_, _, "failing") => ("degraded", StatusCode::OK),
+        _ => ("ready", StatusCode::OK),
+    };
+    (
+        code,
+        Json(Readiness {
+            status,
+            checks: Checks { database, cache, jobs },
+        }),
+    )
+}
+
+/// What is running: release, commit, build time and the simulations served
+pub async fn version(State(state): State<AppState>) -> Json<BuildInfo> {
+    Json(BuildInfo {
+        service: "physics-tutorial-api",
+        version: env!("CARGO_PKG_VERSION"),
+        git_commit: CODE_COMMIT,
+        build_date: env!("BUILD_DATE"),
+        simulations: state
+            .simulations
+            .iter()
+            .map(|s| SimulationVersion {
+                id: s.id(),
+                version: s.version(),
+            })
+            .collect(),
+    })
+}
+
+#[derive(Serialize)]
+pub struct Probe {
+    pub status: &'static str,
+}
+
+#[derive(Serialize)]
+pub struct Readiness {
+    /// `ready`, `degraded` or `not_ready`
+    pub status: &'static str,
+    pub checks: Checks,
+}
+
+#[derive(Serialize)]
+pub struct Checks {
+    pub database: Check,
+    pub cache: Check,
+    pub jobs: Check,
+}
+
+#[derive(Serialize)]
+pub struct Check {
+    /// `ok` or `failing`
+    pub status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl Check {
+    fn ok(latency: Duration) -> Self {
+        Self {
+            status: "ok",
+            latency_ms: Some(milliseconds(latency)),
+            detail: None,
+        }
+    }
+
+    fn failing(latency: Duration, detail: &str) -> Self {
+        Self {
+            status: "failing",
+            latency_ms: Some(milliseconds(latency)),
+            detail: Some(detail.to_string()),
+        }
+    }
+}
+
+fn milliseconds(duration: Duration) -> f64 {
+    (duration.as_secs_f64() * 1e4).round() / 10.0
+}
+
+#[derive(Serialize)]
+pub struct BuildInfo {
+    pub service: &'static str,
+    pub version: &'static str,
+    pub git_commit: &'static str,
+    /// When the server was built (RFC 3339)
+    pub build_date: &'static str,
+    /// Simulations served, with their computation versions
+    pub simulations: Vec<SimulationVersion>,
+}
+
+#[derive(Serialize)]
+pub struct SimulationVersion {
+    pub id: &'static str,
+    pub version: u32,
+}
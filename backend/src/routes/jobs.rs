@@ -0,0 +1,157 @@
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::sse::{Event, KeepAlive, Sse},
+    Json,
+};
+use futures_util::stream::{self, Stream};
+
+use crate::routes::error::ApiError;
+use crate::routes::simulations::RunSimulationRequest;
+use crate::models::job::PendingJob;
+use crate::services::jobs::{CancelError, JobQueue, JobStatus, JobView, SubmitError};
+use crate::simulations::params::{fill_seed, random_seed, Params};
+use crate::state::AppState;
+
+/// How often an event stream looks for news from its job
+const EVENT_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Queue a simulation run in the background.
+///
+/// Parameters are validated straight away; the run itself happens later, so
+/// the job ID comes back immediately for polling at `/api/v1/jobs/:id`.
+pub async fn submit_job(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(mut request): Json<RunSimulationRequest>,
+) -> Result<(StatusCode, Json<JobView>), ApiError> {
+    let simulation = state.simulations.get(&id).ok_or_else(|| ApiError::not_found("simulation", &id))?;
+    let schema = simulation.parameters();
+    fill_seed(&schema, &mut request.parameters, random_seed());
+    let params = Params::validate(&schema, &request.parameters).map_err(ApiError::InvalidParameters)?;
+    let job = state
+        .jobs
+        .submit(simulation, params, state.db.clone(), state.attester.clone())
+        .map_err(|e| match e {
+            SubmitError::QueueFull => ApiError::Busy,
+            SubmitError::ShuttingDown => ApiError::ShuttingDown,
+        })?;
+    Ok((StatusCode::ACCEPTED, Json(job)))
+}
+
+/// Queue the jobs saved at the last shutdown again. Run at startup, after
+/// the catalog is loaded; jobs of simulations no longer served are dropped.
+pub async fn resume_pending_jobs(state: &AppState) -> Result<(), sqlx::Error> {
+    for pending in PendingJob::take_all(&state.db).await? {
+        let Some(simulation) = state.simulations.get(&pending.simulation_id) else {
+            tracing::warn!("Dropping saved job {}: simulation {} is not served", pending.id, pending.simulation_id);
+            continue;
+        };
+        let id = pending.id.clone();
+        if state.jobs.resume(pending, simulation, state.db.clone(), state.attester.clone()).is_ok() {
+            tracing::info!("Resumed job {} saved at the last shutdown", id);
+        }
+    }
+    Ok(())
+}
+
+/// Stop taking jobs, let running ones finish within the configured
+/// deadline and save the rest to run after the restart
+pub async fn drain_jobs(state: &AppState) {
+    let pending = state.jobs.drain(state.config.jobs.drain_timeout()).await;
+    for job in &pending {
+        if let Err(err) = job.insert(&state.db).await {
+            tracing::error!("Failed to save job {} for after the restart: {}", job.id, err);
+        }
+    }
+    if !pending.is_empty() {
+        tracing::info!("Saved {} unfinished jobs to run after the restart", pending.len());
+    }
+}
+
+/// Status and progress of a background job; once done it names the stored
+/// result to fetch
+pub async fn get_job(State(state): State<AppState>, Path(id): Path<String>) -> Result<Json<JobView>, ApiError> {
+    state.jobs.status(&id).map(Json).ok_or_else(|| ApiError::not_found("job", &id))
+}
+
+/// Cancel a queued or running job.
+///
+/// Running simulations stop at their next progress check, so the returned
+/// status may still read `running`; poll until it reads `cancelled`.
+pub async fn cancel_job(State(state): State<AppState>, Path(id): Path<String>) -> Result<Json<JobView>, ApiError> {
+    state.jobs.cancel(&id).map(Json).map_err(|e| match e {
+        CancelError::NotFound => ApiError::not_found("job", &id),
+        CancelError::Finished => ApiError::Conflict(format!("job `{id}` has already finished")),
+    })
+}
+
+/// Follow a job as Server-Sent Events, for clients without WebSockets.
+///
+/// `progress` events carry the status and percentage complete whenever they
+/// change and `partial` events the latest intermediate result the simulation
+/// has published (e.g. Monte Carlo running averages). The stream ends with
+/// a `done`, `failed` or `cancelled` event holding the full job status.
+pub async fn job_events(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ApiError> {
+    state.jobs.status(&id).ok_or_else(|| ApiError::not_found("job", &id))?;
+    let watch = JobWatch {
+        jobs: state.jobs.clone(),
+        id,
+        reported: None,
+        partial: 0,
+        finished: false,
+    };
+    let events = stream::unfold(watch, |mut watch| async move {
+        let event = watch.next().await?;
+        Some((Ok(event), watch))
+    });
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+/// What an event stream has already told its client
+struct JobWatch {
+    jobs: Arc<JobQueue>,
+    id: String,
+    reported: Option<(JobStatus, f64)>,
+    /// Sequence number of the last partial result sent
+    partial: u64,
+    finished: bool,
+}
+
+impl JobWatch {
+    /// Wait for the next event, or `None` once the final one has been sent
+    async fn next(&mut self) -> Option<Event> {
+        while !self.finished {
+            // The job may expire while someone is still watching
+            let job = self.jobs.status(&self.id)?;
+            if let Some((sequence, partial)) = self.jobs.partial(&self.id) {
+                if sequence > self.partial {
+                    self.partial = sequence;
+                    return Some(Event::default().event("partial").data(partial.to_string()));
+                }
+            }
+            if self.reported != Some((job.status, job.progress)) {
+                self.reported = Some((job.status, job.progress));
+                let progress = serde_json::json!({
+                    "status": job.status,
+                    "percent": (job.progress * 1000.0).round() / 10.0,
+                });
+                return Some(Event::default().event("progress").data(progress.to_string()));
+            }
+            if job.status.is_final() {
+                self.finished = true;
+                let view = serde_json::to_string(&job).unwrap_or_default();
+                return Some(Event::default().event(job.status.name()).data(view));
+            }
+            tokio::time::sleep(EVENT_POLL_INTERVAL).await;
+        }
+        None
+    }
+}
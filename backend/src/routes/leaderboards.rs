@@ -0,0 +1,229 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+use crate::models::classroom::{Classroom, ClassMember};
+use crate::models::leaderboard::{self, LeaderboardSettings, Participant, RANK_BY};
+use crate::routes::auth::AuthUser;
+use crate::routes::classes::{taught_class, visible_class};
+use crate::routes::error::ApiError;
+use crate::simulations::params::ParameterError;
+use crate::state::AppState;
+
+/// Words pseudonyms are made of: an adjective and a particle
+const ADJECTIVES: [&str; 16] = [
+    "Curious", "Bright", "Swift", "Quiet", "Bold", "Steady", "Clever", "Lucky",
+    "Brave", "Calm", "Keen", "Nimble", "Patient", "Radiant", "Gentle", "Daring",
+];
+const PARTICLES: [&str; 16] = [
+    "Photon", "Electron", "Quark", "Neutrino", "Muon", "Proton", "Neutron", "Gluon",
+    "Boson", "Positron", "Phonon", "Pion", "Kaon", "Tau", "Fermion", "Graviton",
+];
+
+/// A class's leaderboard. Students see it once the teacher turns it on, and
+/// it lists only the students who opted in, ranked by the teacher's choice
+/// of XP or completed simulations; with `anonymize` they appear under
+/// pseudonyms that stay the same within the class. The teacher always sees
+/// it, with every student's name.
+pub async fn get_leaderboard(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(id): Path<String>,
+) -> Result<Json<Leaderboard>, ApiError> {
+    let class = visible_class(&state, &user, &id).await?;
+    let settings = settings(&state, &class.id).await?;
+    if class.teacher_id != user.id && !settings.enabled {
+        return Err(ApiError::Forbidden("the teacher has not turned on this class's leaderboard"));
+    }
+    leaderboard(&state, &user, class, settings).await.map(Json)
+}
+
+/// Turn a class's leaderboard on or off and choose how it ranks and shows
+/// students. Teacher only.
+pub async fn update_leaderboard(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(id): Path<String>,
+    Json(request): Json<LeaderboardRequest>,
+) -> Result<Json<Leaderboard>, ApiError> {
+    let class = taught_class(&state, &user, &id).await?;
+    let mut settings = settings(&state, &class.id).await?;
+    if let Some(rank_by) = request.rank_by {
+        if !RANK_BY.contains(&rank_by.as_str()) {
+            return Err(ApiError::InvalidParameters(vec![ParameterError::new(
+                "rank_by",
+                format!("must be one of: {}", RANK_BY.join(", ")),
+                Some(&json!(rank_by)),
+            )]));
+        }
+        settings.rank_by = rank_by;
+    }
+    settings.enabled = request.enabled;
+    settings.anonymize = request.anonymize.unwrap_or(settings.anonymize);
+    settings.updated_at = Some(Utc::now());
+    settings.save(&state.db).await?;
+    leaderboard(&state, &user, class, settings).await.map(Json)
+}
+
+/// Put the signed-in student on their class's leaderboard
+pub async fn join_leaderboard(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let class = student_class(&state, &user, &id).await?;
+    leaderboard::opt_in(&state.db, &class.id, &user.id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Take the signed-in student off their class's leaderboard
+pub async fn leave_leaderboard(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let class = student_class(&state, &user, &id).await?;
+    leaderboard::opt_out(&state.db, &class.id, &user.id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// A class the user attends; teachers are not ranked
+async fn student_class(state: &AppState, user: &AuthUser, id: &str) -> Result<Classroom, ApiError> {
+    let class = visible_class(state, user, id).await?;
+    if class.teacher_id == user.id {
+        return Err(ApiError::Forbidden("only students appear on the leaderboard"));
+    }
+    Ok(class)
+}
+
+async fn settings(state: &AppState, class_id: &str) -> Result<LeaderboardSettings, ApiError> {
+    Ok(LeaderboardSettings::find(&state.db, class_id)
+        .await?
+        .unwrap_or_else(|| LeaderboardSettings::off(class_id)))
+}
+
+/// The ranking as `user` may see it
+async fn leaderboard(
+    state: &AppState,
+    user: &AuthUser,
+    class: Classroom,
+    settings: LeaderboardSettings,
+) -> Result<Leaderboard, ApiError> {
+    let members: Vec<ClassMember> = Classroom::members(&state.db, &class.id).await?;
+    let mut participants = leaderboard::participants(&state.db, &class.id).await?;
+    let by_xp = settings.rank_by == "xp";
+    let score = |p: &Participant| if by_xp { (p.xp, p.completions) } else { (p.completions, p.xp) };
+    // Break ties by the name shown, so the order gives no hint of who is
+    // behind a pseudonym
+    let shown = |p: &Participant| {
+        if settings.anonymize {
+            pseudonym(&class.id, &p.user_id)
+        } else {
+            p.display_name.clone()
+        }
+    };
+    participants.sort_by(|a, b| {
+        score(b).cmp(&score(a)).then_with(|| shown(a).cmp(&shown(b))).then_with(|| a.user_id.cmp(&b.user_id))
+    });
+
+    let teaching = class.teacher_id == user.id;
+    let mut names: Vec<String> = Vec::with_capacity(participants.len());
+    let mut entries = Vec::with_capacity(participants.len());
+    for (i, participant) in participants.iter().enumerate() {
+        // Ties share the rank of the first student with that score
+        let rank = match i {
+            0 => 1,
+            _ if score(&participants[i - 1]).0 == score(participant).0 => entries.last().map_or(1, |e: &Entry| e.rank),
+            _ => i + 1,
+        };
+        let name = if settings.anonymize {
+            let mut name = shown(participant);
+            let base = name.clone();
+            let mut n = 1;
+            while names.contains(&name) {
+                n += 1;
+                name = format!("{base} {n}");
+            }
+            name
+        } else {
+            participant.display_name.clone()
+        };
+        names.push(name.clone());
+        entries.push(Entry {
+            rank,
+            name,
+            you: participant.user_id == user.id,
+            xp: participant.xp,
+            completions: participant.completions,
+            user_id: teaching.then(|| participant.user_id.clone()),
+            display_name: (teaching && settings.anonymize).then(|| participant.display_name.clone()),
+        });
+    }
+
+    Ok(Leaderboard {
+        class_id: class.id,
+        opted_in: (!teaching).then(|| participants.iter().any(|p| p.user_id == user.id)),
+        participants: participants.len(),
+        student_count: members.len(),
+        settings,
+        entries,
+    })
+}
+
+/// A name for a student that stays the same within a class and says
+/// nothing about who they are
+fn pseudonym(class_id: &str, user_id: &str) -> String {
+    let digest = Sha256::new().chain_update(class_id).chain_update([0]).chain_update(user_id).finalize();
+    format!(
+        "{} {}",
+        ADJECTIVES[usize::from(digest[0]) % ADJECTIVES.len()],
+        PARTICLES[usize::from(digest[1]) % PARTICLES.len()]
+    )
+}
+
+#[derive(Deserialize)]
+pub struct LeaderboardRequest {
+    pub enabled: bool,
+    /// Unchanged when left out
+    pub anonymize: Option<bool>,
+    /// Unchanged when left out
+    pub rank_by: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct Leaderboard {
+    pub class_id: String,
+    pub settings: LeaderboardSettings,
+    /// Whether the signed-in student is on the leaderboard; absent for the
+    /// teacher
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub opted_in: Option<bool>,
+    /// Students who opted in
+    pub participants: usize,
+    pub student_count: usize,
+    pub entries: Vec<Entry>,
+}
+
+#[derive(Serialize)]
+pub struct Entry {
+    /// From 1; tied students share a rank
+    pub rank: usize,
+    /// Pseudonym when the leaderboard is anonymized, else display name
+    pub name: String,
+    /// Whether this is the signed-in student
+    pub you: bool,
+    pub xp: u32,
+    pub completions: u32,
+    /// For the teacher only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
+    /// Real name behind a pseudonym, for the teacher only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+}
@@ -0,0 +1,265 @@
+use axum::{
+    extract::{Query, State},
+    http::{header, HeaderMap},
+    response::{Html, IntoResponse, Redirect, Response},
+    Form, Json,
+};
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::models::classroom::Classroom;
+use crate::models::lti::{self, GradeLink};
+use crate::models::user::User;
+use crate::routes::auth::{issue_tokens, AuthError};
+use crate::routes::classes::new_class;
+use crate::routes::error::ApiError;
+use crate::services::lti::{check_state_cookie, Launch, LoginInitiation, MessageType};
+use crate::services::markdown::escape_html;
+use crate::state::AppState;
+
+/// Platform login initiation sent as a query string
+pub async fn login(
+    State(state): State<AppState>,
+    Query(request): Query<LoginInitiation>,
+) -> Result<Response, ApiError> {
+    login_redirect(&state, &request)
+}
+
+/// Platform login initiation sent as a form post
+pub async fn login_form(
+    State(state): State<AppState>,
+    Form(request): Form<LoginInitiation>,
+) -> Result<Response, ApiError> {
+    login_redirect(&state, &request)
+}
+
+/// Redirect to the platform, leaving the login's state cookie
+fn login_redirect(state: &AppState, request: &LoginInitiation) -> Result<Response, ApiError> {
+    let (url, login_state) = state.lti.login_redirect(request)?;
+    Ok(([(header::SET_COOKIE, state.lti.state_cookie(&login_state))], Redirect::to(&url)).into_response())
+}
+
+/// Complete a launch: verify the platform's id_token, sign the user in to
+/// their linked account and class, then continue to the web app. Deep
+/// linking requests get a page to pick a simulation instead. The launch
+/// must come from the browser the login started in.
+pub async fn launch(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Form(form): Form<LaunchForm>,
+) -> Result<Response, ApiError> {
+    let cookies = headers.get_all(header::COOKIE).iter().filter_map(|value| value.to_str().ok());
+    check_state_cookie(cookies, &form.state)?;
+    let login = state.lti.take_login(&form.state)?;
+    let expired = [(header::SET_COOKIE, state.lti.expired_state_cookie(&form.state))];
+    let launch = state.lti.verify_launch(&form.id_token, &login).await?;
+    let user = launch_user(&state, &launch).await?;
+    let class_id = launch_class(&state, &launch, &user).await?;
+
+    if launch.message_type == MessageType::DeepLinking {
+        if !launch.is_instructor() {
+            return Err(ApiError::Forbidden("only instructors can add simulations to a course"));
+        }
+        let session = state.lti.begin_deep_link(&launch)?;
+        return Ok((expired, Html(picker_page(&state, &session))).into_response());
+    }
+
+    let simulation_id = launch.simulation_id().filter(|id| state.simulations.get(id).is_some());
+    if let (Some(simulation_id), Some(lineitem)) = (simulation_id, &launch.lineitem) {
+        GradeLink {
+            user_id: user.id.clone(),
+            simulation_id: simulation_id.to_string(),
+            issuer: launch.issuer.clone(),
+            client_id: Some(launch.client_id.clone()),
+            subject: launch.subject.clone(),
+            lineitem: lineitem.clone(),
+        }
+        .upsert(&state.db)
+        .await?;
+    }
+
+    let tokens = issue_tokens(&state, user).await.map_err(|err| match err {
+        AuthError::Storage(e) => ApiError::from(e),
+        _ => ApiError::Internal,
+    })?;
+    // Tokens travel in the fragment so they never reach server logs
+    let mut fragment = format!(
+        "access_token={}&refresh_token={}&expires_in={}",
+        tokens.access_token, tokens.refresh_token, tokens.expires_in
+    );
+    if let Some(simulation_id) = simulation_id {
+        fragment.push_str(&format!("&simulation={simulation_id}"));
+    }
+    if let Some(class_id) = class_id {
+        fragment.push_str(&format!("&class={class_id}"));
+    }
+    Ok((expired, Redirect::to(&format!("{}/#{}", state.lti.frontend_url, fragment))).into_response())
+}
+
+/// Return the instructor's chosen simulation to the platform as a signed
+/// deep linking response
+pub async fn deep_link(State(state): State<AppState>, Form(form): Form<DeepLinkForm>) -> Result<Html<String>, ApiError> {
+    let simulation = state
+        .simulations
+        .get(&form.simulation_id)
+        .ok_or_else(|| ApiError::not_found("simulation", &form.simulation_id))?;
+    let session = state.lti.take_deep_link(&form.session)?;
+    let info = simulation.info();
+    let mut item = json!({
+        "type": "ltiResourceLink",
+        "title": info.name,
+        "text": info.description,
+        "url": state.lti.launch_url(),
+        "custom": { "simulation_id": info.id },
+    });
+    // Quiz scores go to a gradebook column of their own
+    if simulation.quiz().is_some() {
+        item["lineItem"] = json!({
+            "scoreMaximum": 100,
+            "label": format!("{} quiz", info.name),
+            "resourceId": info.id,
+        });
+    }
+    let jwt = state.lti.deep_linking_response(&session, vec![item])?;
+    Ok(Html(auto_post_page(&session.return_url, "JWT", &jwt)))
+}
+
+/// The tool's public keys, for platforms to verify our messages
+pub async fn jwks(State(state): State<AppState>) -> Json<Value> {
+    Json(state.lti.jwks())
+}
+
+/// The account linked to the platform user. A first launch links an
+/// account with the same email, or creates one that can only sign in
+/// through the platform.
+async fn launch_user(state: &AppState, launch: &Launch) -> Result<User, ApiError> {
+    if let Some(user_id) = lti::find_identity(&state.db, &launch.issuer, &launch.subject).await? {
+        if let Some(user) = User::find(&state.db, &user_id).await? {
+            return Ok(user);
+        }
+    }
+
+    let email = launch.email.as_deref().map(|email| email.trim().to_lowercase());
+    let existing = match &email {
+        Some(email) => User::find_by_email(&state.db, email).await?,
+        None => None,
+    };
+    let user = match existing {
+        Some(user) => user,
+        None => {
+            let id = Uuid::new_v4().to_string();
+            let user = User {
+                email: email.unwrap_or_else(|| format!("lti-{id}@users.invalid")),
+                display_name: launch.name.clone().unwrap_or_else(|| "LMS user".to_string()),
+                // Not a valid hash, so password sign-in always fails
+                password_hash: "lti".to_string(),
+                created_at: Utc::now().to_rfc3339(),
+                id,
+            };
+            user.insert(&state.db).await?;
+            user
+        }
+    };
+    lti::link_identity(&state.db, &launch.issuer, &launch.subject, &user.id).await?;
+    Ok(user)
+}
+
+/// The class standing in for the platform course. The first instructor to
+/// launch creates it; learners are enrolled as they launch.
+async fn launch_class(state: &AppState, launch: &Launch, user: &User) -> Result<Option<String>, ApiError> {
+    let Some((context_id, title)) = &launch.context else {
+        return Ok(None);
+    };
+    let class = match lti::find_context(&state.db, &launch.issuer, context_id).await? {
+        Some(class_id) => Classroom::find(&state.db, &class_id).await?,
+        None => None,
+    };
+    match class {
+        Some(class) => {
+            if !launch.is_instructor() && class.teacher_id != user.id {
+                Classroom::add_member(&state.db, &class.id, &user.id).await?;
+            }
+            Ok(Some(class.id))
+        }
+        None if launch.is_instructor() => {
+            let class = new_class(state, &user.id, title).await?;
+            lti::link_context(&state.db, &launch.issuer, context_id, &class.id).await?;
+            Ok(Some(class.id))
+        }
+        None => {
+            tracing::debug!("No class yet for LTI context {} of {}", context_id, launch.issuer);
+            Ok(None)
+        }
+    }
+}
+
+/// Page listing every simulation, each posting its choice to the deep
+/// linking endpoint
+fn picker_page(state: &AppState, session: &str) -> String {
+    let items: String = state
+        .simulations
+        .iter()
+        .map(|simulation| {
+            let info = simulation.info();
+            format!(
+                r#"<li><form method="post" action="/api/v1/lti/deep-link"><input type="hidden" name="session" value="{session}"><input type="hidden" name="simulation_id" value="{id}"><button type="submit">{name}</button> <span>{description}</span></form></li>"#,
+                session = escape_html(session),
+                id = escape_html(&info.id),
+                name = escape_html(&info.name),
+                description = escape_html(&info.description),
+            )
+        })
+        .collect();
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8" />
+  <title>Add a simulation</title>
+</head>
+<body>
+  <h1>Add a simulation</h1>
+  <ul>{items}</ul>
+</body>
+</html>
+"#
+    )
+}
+
+/// Page that immediately posts one field to `action`
+fn auto_post_page(action: &str, name: &str, value: &str) -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8" />
+  <title>Returning to your course</title>
+</head>
+<body onload="document.forms[0].submit()">
+  <form method="post" action="{action}">
+    <input type="hidden" name="{name}" value="{value}" />
+    <noscript><button type="submit">Continue</button></noscript>
+  </form>
+</body>
+</html>
+"#,
+        action = escape_html(action),
+        name = escape_html(name),
+        value = escape_html(value),
+    )
+}
+
+/// Authentication response posted by the platform
+#[derive(Deserialize)]
+pub struct LaunchForm {
+    pub id_token: String,
+    pub state: String,
+}
+
+#[derive(Deserialize)]
+pub struct DeepLinkForm {
+    pub session: String,
+    pub simulation_id: String,
+}
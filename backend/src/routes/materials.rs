@@ -0,0 +1,8 @@
+use axum::Json;
+
+use crate::physics::materials::{Metal, METALS};
+
+/// List the metals and work functions used by the photoelectric effect simulation
+pub async fn list_materials() -> Json<&'static [Metal]> {
+    Json(METALS)
+}
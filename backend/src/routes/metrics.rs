@@ -0,0 +1,46 @@
+use std::sync::atomic::Ordering;
+use std::time::Instant;
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::header,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use crate::services::metrics::Gauges;
+use crate::state::AppState;
+
+/// Prometheus scrape endpoint, in the text exposition format
+pub async fn metrics(State(state): State<AppState>) -> impl IntoResponse {
+    let lookups = state.cache.lookups();
+    let (jobs_queued, jobs_running) = state.jobs.depth();
+    let gauges = Gauges {
+        cache_memory_hits: lookups.memory.load(Ordering::Relaxed),
+        cache_redis_hits: lookups.redis.load(Ordering::Relaxed),
+        cache_misses: lookups.miss.load(Ordering::Relaxed),
+        jobs_queued,
+        jobs_running,
+    };
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4; charset=utf-8")],
+        state.metrics.render(&gauges),
+    )
+}
+
+/// Middleware timing every request by its route template. Requests that
+/// match no route are counted together, so probing random paths cannot
+/// create new series.
+pub async fn track_requests(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let started = Instant::now();
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or("unmatched".to_string(), |path| path.as_str().to_string());
+    let response = next.run(request).await;
+    state
+        .metrics
+        .observe_request(&method, &route, response.status().as_u16(), started.elapsed());
+    response
+}
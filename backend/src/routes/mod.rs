@@ -1,3 +1,37 @@
 pub mod simulations;
 pub mod ai;
 pub mod progress;
+pub mod openapi;
+pub mod stream;
+pub mod auth;
+pub mod quiz;
+pub mod materials;
+pub mod constants;
+pub mod audio;
+pub mod jobs;
+pub mod error;
+pub mod topics;
+pub mod curriculum;
+pub mod courses;
+pub mod classes;
+pub mod assignments;
+pub mod lti;
+pub mod translations;
+pub mod catalog;
+pub mod content;
+pub mod attestation;
+pub mod citation;
+pub mod metrics;
+pub mod request_id;
+pub mod rate_limit;
+pub mod health;
+pub mod datasets;
+pub mod analysis;
+pub mod custom_simulations;
+pub mod contributions;
+pub mod search;
+pub mod tutor;
+pub mod recommendations;
+pub mod achievements;
+pub mod leaderboards;
+pub mod bookmarks;
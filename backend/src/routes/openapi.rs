@@ -0,0 +1,3330 @@
+use axum::{extract::State, response::Html, Json};
+use serde_json::{json, Map, Value};
+
+use crate::state::AppState;
+
+/// A documented route. Path parameters are picked up from `{name}` segments.
+struct Endpoint {
+    method: &'static str,
+    path: &'static str,
+    tag: &'static str,
+    summary: &'static str,
+    /// Component schema name of the JSON request body
+    request: Option<&'static str>,
+    /// Component schema name of the success response, if it is JSON
+    response: Option<&'static str>,
+    /// Query parameters as (name, description)
+    query: &'static [(&'static str, &'static str)],
+    /// Whether a bearer access token is required
+    auth: bool,
+}
+
+const ENDPOINTS: &[Endpoint] = &[
+    Endpoint {
+        method: "get",
+        path: "/health",
+        tag: "system",
+        summary: "Health check",
+        request: None,
+        response: Some("HealthResponse"),
+        query: &[],
+        auth: false,
+    },
+    Endpoint {
+        method: "get",
+        path: "/healthz",
+        tag: "system",
+        summary: "Liveness probe: the process is serving requests",
+        request: None,
+        response: Some("Probe"),
+        query: &[],
+        auth: false,
+    },
+    Endpoint {
+        method: "get",
+        path: "/readyz",
+        tag: "system",
+        summary: "Readiness probe checking the database, the Redis cache and the job queue; 503 when the database is \
+                  unavailable or the server is shutting down",
+        request: None,
+        response: Some("Readiness"),
+        query: &[],
+        auth: false,
+    },
+    Endpoint {
+        method: "get",
+        path: "/version",
+        tag: "system",
+        summary: "Release, git commit, build time and the simulations served with their computation versions",
+        request: None,
+        response: Some("BuildInfo"),
+        query: &[],
+        auth: false,
+    },
+    Endpoint {
+        method: "get",
+        path: "/metrics",
+        tag: "system",
+        summary: "Prometheus metrics (text/plain exposition format): request latencies by route, simulation runs and \
+                  compute times by simulation, result cache lookups and job queue depth",
+        request: None,
+        response: None,
+        query: &[],
+        auth: false,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/simulations",
+        tag: "simulations",
+        summary: "List simulations, filtered, searched and sorted, one page at a time",
+        request: None,
+        response: Some("SimulationPage"),
+        query: &[
+            ("topic", "Only simulations with this topic label (case-insensitive) or under this taxonomy topic ID"),
+            ("difficulty", "beginner, intermediate or advanced"),
+            ("q", "Words that must all appear in the name, description, ID or topics"),
+            ("sort", "name, difficulty, time or id; prefix with - for descending"),
+            ("page", "Page number, from 1 (default 1)"),
+            ("per_page", "Simulations per page, 1-100 (default 20)"),
+            ("lang", "Comma-separated languages for names and descriptions, overriding Accept-Language"),
+        ],
+        auth: false,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/simulations/{id}",
+        tag: "simulations",
+        summary: "Get simulation details, parameters and theory, translated per Accept-Language where translations exist",
+        request: None,
+        response: Some("SimulationDetails"),
+        query: &[("lang", "Comma-separated languages, overriding Accept-Language; falls back to English")],
+        auth: false,
+    },
+    Endpoint {
+        method: "post",
+        path: "/api/v1/simulations/{id}/run",
+        tag: "simulations",
+        summary: "Run a simulation with the given parameters; repeated runs are answered from the result cache",
+        request: Some("RunSimulationRequest"),
+        response: Some("RunSimulationResponse"),
+        query: &[],
+        auth: false,
+    },
+    Endpoint {
+        method: "post",
+        path: "/api/v1/simulations/{id}/sweep",
+        tag: "simulations",
+        summary: "Run a simulation across a range of values of one parameter",
+        request: Some("SweepRequest"),
+        response: Some("SweepResult"),
+        query: &[],
+        auth: false,
+    },
+    Endpoint {
+        method: "post",
+        path: "/api/v1/simulations/{id}/compare",
+        tag: "simulations",
+        summary: "Run a simulation with several parameter sets and report how the outputs differ",
+        request: Some("CompareRequest"),
+        response: Some("CompareResult"),
+        query: &[],
+        auth: false,
+    },
+    Endpoint {
+        method: "post",
+        path: "/api/v1/simulations/{id}/jobs",
+        tag: "jobs",
+        summary: "Queue a long simulation run in the background; responds 202 with the job to poll",
+        request: Some("RunSimulationRequest"),
+        response: Some("Job"),
+        query: &[],
+        auth: false,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/jobs/{job_id}",
+        tag: "jobs",
+        summary: "Status and progress of a background job, with the result ID once it is done",
+        request: None,
+        response: Some("Job"),
+        query: &[],
+        auth: false,
+    },
+    Endpoint {
+        method: "delete",
+        path: "/api/v1/jobs/{job_id}",
+        tag: "jobs",
+        summary: "Cancel a queued or running job; running simulations stop at their next progress check",
+        request: None,
+        response: Some("Job"),
+        query: &[],
+        auth: false,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/jobs/{job_id}/events",
+        tag: "jobs",
+        summary: "Server-Sent Events stream of a job: `progress` and `partial` events, then `done`, `failed` or `cancelled` (text/event-stream)",
+        request: None,
+        response: None,
+        query: &[],
+        auth: false,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/simulations/doppler/audio",
+        tag: "simulations",
+        summary: "Synthesize the sound heard in the Doppler simulation as a 16-bit mono WAV clip (audio/wav)",
+        request: None,
+        response: None,
+        query: &[
+            ("source_speed", "Source speed (m/s)"),
+            ("frequency", "Source frequency (Hz)"),
+            ("observer_distance", "Listener distance from the road (m)"),
+            ("observer_position", "Listener position along the road (m)"),
+            ("duration", "Duration of the pass (s)"),
+        ],
+        auth: false,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/simulations/results/{result_id}",
+        tag: "simulations",
+        summary: "Fetch a stored simulation result",
+        request: None,
+        response: Some("SimulationResult"),
+        query: &[],
+        auth: false,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/simulations/results/{result_id}/export",
+        tag: "simulations",
+        summary: "Export a stored result as a table with labeled columns and units (CSV, JSON lines or Apache Parquet)",
+        request: None,
+        response: None,
+        query: &[
+            ("format", "csv (default), jsonl or parquet"),
+            ("table", "Name of a column in the table to export; defaults to the table with the most columns"),
+        ],
+        auth: false,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/simulations/results/{result_id}/animation",
+        tag: "simulations",
+        summary: "Render a stored frame-sequence or double-pendulum result as a looping animated GIF (image/gif)",
+        request: None,
+        response: None,
+        query: &[("fps", "Frames per second, 1 to 50 (default 20)")],
+        auth: false,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/simulations/results/{result_id}/bundle",
+        tag: "simulations",
+        summary: "Download a reproducibility bundle (application/zip): parameters, raw data, CSV tables, a methods \
+                  description, the simulation version and code commit, and SHA-256 checksums",
+        request: None,
+        response: None,
+        query: &[],
+        auth: false,
+    },
+    Endpoint {
+        method: "post",
+        path: "/api/v1/simulations/results/{result_id}/pin",
+        tag: "simulations",
+        summary: "Pin a result's reproducibility bundle to IPFS and return its CID; pinning again returns the same pin",
+        request: None,
+        response: Some("ResultPin"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/simulations/results/by-cid/{cid}",
+        tag: "simulations",
+        summary: "Fetch a pinned bundle from IPFS (application/zip), verified against the SHA-256 recorded when it was \
+                  pinned; the ETag carries that hash",
+        request: None,
+        response: None,
+        query: &[],
+        auth: false,
+    },
+    Endpoint {
+        method: "post",
+        path: "/api/v1/simulations/results/{result_id}/replay",
+        tag: "simulations",
+        summary: "Recompute a stored result from its parameters and report whether it is reproduced exactly",
+        request: None,
+        response: Some("ReplayResult"),
+        query: &[(
+            "version",
+            "stored (default) replays the computation version that produced the result; current runs today's code",
+        )],
+        auth: false,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/simulations/{id}/selftest",
+        tag: "simulations",
+        summary: "Rerun the simulation with the canonical parameters of its golden file and check the output against the recorded one",
+        request: None,
+        response: Some("SelftestReport"),
+        query: &[(
+            "record",
+            "true returns the golden file for the current code and default parameters instead of checking",
+        )],
+        auth: false,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/attestation/keys",
+        tag: "simulations",
+        summary: "Ed25519 public keys this server has signed results with, including rotated ones",
+        request: None,
+        response: Some("AttestationKeys"),
+        query: &[],
+        auth: false,
+    },
+    Endpoint {
+        method: "post",
+        path: "/api/v1/attestation/verify",
+        tag: "simulations",
+        summary: "Check that a result, as returned by the API, was signed by this server and is unchanged",
+        request: Some("VerifyRequest"),
+        response: Some("Verification"),
+        query: &[],
+        auth: false,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/simulations/{id}/translations",
+        tag: "simulations",
+        summary: "Uploaded translations of a simulation's text, by locale",
+        request: None,
+        response: Some("TranslationList"),
+        query: &[],
+        auth: false,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/simulations/{id}/theory",
+        tag: "simulations",
+        summary: "The theory section alone, translated per Accept-Language: Markdown with LaTeX math (text/markdown), or \
+                  a sanitized HTML fragment (text/html) with math left in KaTeX-ready spans",
+        request: None,
+        response: None,
+        query: &[
+            ("format", "markdown (default) or html"),
+            ("lang", "Comma-separated languages, overriding Accept-Language; falls back to English"),
+        ],
+        auth: false,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/simulations/{id}/related",
+        tag: "search",
+        summary: "Simulations whose content is closest in meaning to this one's, by embedding similarity",
+        request: None,
+        response: Some("RelatedSimulations"),
+        query: &[("limit", "Most simulations returned, 1 to 20 (default 5)")],
+        auth: false,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/simulations/{id}/citation",
+        tag: "simulations",
+        summary: "How to cite a simulation: the server release and simulation version, and the works its physics comes \
+                  from, as CSL-JSON and BibTeX",
+        request: None,
+        response: Some("Citation"),
+        query: &[(
+            "format",
+            "json (default) for both, csl for a CSL-JSON array (application/vnd.citationstyles.csl+json) or bibtex \
+             (application/x-bibtex)",
+        )],
+        auth: false,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/simulations/{id}/quiz",
+        tag: "quizzes",
+        summary: "Get the quiz for a simulation (answer key omitted)",
+        request: None,
+        response: Some("Quiz"),
+        query: &[],
+        auth: false,
+    },
+    Endpoint {
+        method: "post",
+        path: "/api/v1/simulations/{id}/quiz",
+        tag: "quizzes",
+        summary: "Submit answers to a simulation's quiz and get them graded; attempts are recorded for the learner's teachers, and only correct answers come back with the answer key",
+        request: Some("QuizSubmission"),
+        response: Some("QuizResult"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "post",
+        path: "/api/v1/simulations/{id}/quiz/questions/{question_id}/hint",
+        tag: "quizzes",
+        summary: "Reveal the learner's next hint on a quiz question; each hint revealed lowers the credit for a correct answer",
+        request: None,
+        response: Some("Hint"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/simulations/{id}/quiz/hints",
+        tag: "quizzes",
+        summary: "Hints the learner has revealed on a simulation's quiz, by question",
+        request: None,
+        response: Some("RevealedHintsList"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/ws/simulations/{id}",
+        tag: "simulations",
+        summary: "WebSocket stream of frames for time-evolving simulations. Query parameters set the initial simulation parameters; each restart with new parameters draws on the compute rate limit.",
+        request: None,
+        response: None,
+        query: &[],
+        auth: false,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/materials",
+        tag: "reference",
+        summary: "List metals and their work functions, as used by the photoelectric effect simulation",
+        request: None,
+        response: Some("MetalList"),
+        query: &[],
+        auth: false,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/constants",
+        tag: "reference",
+        summary: "Physical constants the simulations use (CODATA 2018, SI units), with their standard uncertainties",
+        request: None,
+        response: Some("ConstantList"),
+        query: &[],
+        auth: false,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/units",
+        tag: "reference",
+        summary: "Units parameters are declared in or may be given in, with their dimensions and factors to SI",
+        request: None,
+        response: Some("UnitList"),
+        query: &[],
+        auth: false,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/datasets",
+        tag: "reference",
+        summary: "Experimental datasets run requests can be compared with, without their data",
+        request: None,
+        response: Some("DatasetList"),
+        query: &[("simulation", "Only the datasets for this simulation ID")],
+        auth: false,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/datasets/{id}",
+        tag: "reference",
+        summary: "An experimental dataset with its measurements, uncertainties, run parameters and references",
+        request: None,
+        response: Some("Dataset"),
+        query: &[],
+        auth: false,
+    },
+    Endpoint {
+        method: "post",
+        path: "/api/v1/analysis/fit",
+        tag: "analysis",
+        summary: "Fit a linear, exponential, sinusoidal or Gaussian model to x/y data by least squares, with parameter uncertainties",
+        request: Some("FitRequest"),
+        response: Some("CurveFit"),
+        query: &[],
+        auth: false,
+    },
+    Endpoint {
+        method: "post",
+        path: "/api/v1/analysis/stats",
+        tag: "analysis",
+        summary: "Summarize arrays of measurements (mean, standard deviation, standard error, histogram) and propagate their uncertainties through a formula",
+        request: Some("StatsRequest"),
+        response: Some("StatsResponse"),
+        query: &[],
+        auth: false,
+    },
+    Endpoint {
+        method: "post",
+        path: "/api/v1/analysis/fft",
+        tag: "analysis",
+        summary: "Fourier transform of a sampled signal: amplitude, phase and power spectral density from zero to the Nyquist frequency, with the strongest frequency",
+        request: Some("FftRequest"),
+        response: Some("Spectrum"),
+        query: &[],
+        auth: false,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/search",
+        tag: "search",
+        summary: "Ranked full-text search over simulation names, topics, descriptions, theory and glossary terms, with \
+                  highlighted snippets",
+        request: None,
+        response: Some("SearchPage"),
+        query: &[
+            ("q", "Words to find; entries with all of them rank first, else entries with any of them are returned"),
+            ("kind", "simulation or glossary; both by default"),
+            ("page", "Page number, from 1 (default 1)"),
+            ("per_page", "Hits per page, 1 to 50 (default 10)"),
+        ],
+        auth: false,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/search/semantic",
+        tag: "search",
+        summary: "Simulations ranked by how close their content is in meaning to a question or concept, each with its \
+                  best matching passage",
+        request: None,
+        response: Some("SemanticPage"),
+        query: &[
+            ("q", "Question or description of a concept, up to 1000 characters"),
+            ("page", "Page number, from 1 (default 1)"),
+            ("per_page", "Simulations per page, 1 to 50 (default 10)"),
+        ],
+        auth: false,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/topics",
+        tag: "reference",
+        summary: "Topic taxonomy (e.g. mechanics → oscillations → pendulum) with simulation counts, parents before children",
+        request: None,
+        response: Some("TopicList"),
+        query: &[],
+        auth: false,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/topics/{id}/simulations",
+        tag: "reference",
+        summary: "Simulations filed under a topic or any of its subtopics",
+        request: None,
+        response: Some("TopicSimulations"),
+        query: &[],
+        auth: false,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/curriculum/graph",
+        tag: "reference",
+        summary: "Prerequisite graph between simulations, for drawing a skill tree",
+        request: None,
+        response: Some("CurriculumGraph"),
+        query: &[],
+        auth: false,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/curriculum/order",
+        tag: "reference",
+        summary: "Recommended learning order, prerequisites first",
+        request: None,
+        response: Some("LearningOrder"),
+        query: &[("target", "Only the simulations leading up to this simulation ID, ending with it")],
+        auth: false,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/custom-simulations",
+        tag: "custom simulations",
+        summary: "The signed-in user's scripted simulations, most recently changed first",
+        request: None,
+        response: Some("CustomSimulationList"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "post",
+        path: "/api/v1/custom-simulations",
+        tag: "custom simulations",
+        summary: "Register a script f(parameters, x) as a private simulation of the signed-in user",
+        request: Some("CustomSimulationRequest"),
+        response: Some("CustomSimulation"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/custom-simulations/{custom_simulation_id}",
+        tag: "custom simulations",
+        summary: "Get one of the signed-in user's custom simulations; other users' are not found",
+        request: None,
+        response: Some("CustomSimulation"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "put",
+        path: "/api/v1/custom-simulations/{custom_simulation_id}",
+        tag: "custom simulations",
+        summary: "Replace a custom simulation's definition",
+        request: Some("CustomSimulationRequest"),
+        response: Some("CustomSimulation"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "delete",
+        path: "/api/v1/custom-simulations/{custom_simulation_id}",
+        tag: "custom simulations",
+        summary: "Delete a custom simulation",
+        request: None,
+        response: None,
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "post",
+        path: "/api/v1/custom-simulations/{custom_simulation_id}/run",
+        tag: "custom simulations",
+        summary: "Evaluate a custom simulation's script across its domain, within a 2 s time limit",
+        request: Some("CustomRunRequest"),
+        response: Some("CustomRun"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/contributions",
+        tag: "community library",
+        summary: "The signed-in user's contributions with their reviews, most recently changed first",
+        request: None,
+        response: Some("ContributionList"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "post",
+        path: "/api/v1/contributions",
+        tag: "community library",
+        summary: "Submit a scripted simulation with its summary, difficulty, topics and theory for review",
+        request: Some("ContributionRequest"),
+        response: Some("Contribution"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/contributions/{contribution_id}",
+        tag: "community library",
+        summary: "Get one of the signed-in user's contributions with its reviews; other users' are not found",
+        request: None,
+        response: Some("Contribution"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "put",
+        path: "/api/v1/contributions/{contribution_id}",
+        tag: "community library",
+        summary: "Revise a contribution that is not published, sending it back to review",
+        request: Some("ContributionRequest"),
+        response: Some("Contribution"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "delete",
+        path: "/api/v1/contributions/{contribution_id}",
+        tag: "community library",
+        summary: "Withdraw a contribution that is not published",
+        request: None,
+        response: None,
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/library",
+        tag: "community library",
+        summary: "Published contributions, oldest first",
+        request: None,
+        response: Some("ContributionList"),
+        query: &[],
+        auth: false,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/library/{contribution_id}",
+        tag: "community library",
+        summary: "Get a published contribution",
+        request: None,
+        response: Some("Contribution"),
+        query: &[],
+        auth: false,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/library/{contribution_id}/theory",
+        tag: "community library",
+        summary: "A published contribution's theory as Markdown (text/markdown) or a sanitized HTML fragment (text/html)",
+        request: None,
+        response: None,
+        query: &[("format", "markdown (default) or html")],
+        auth: false,
+    },
+    Endpoint {
+        method: "post",
+        path: "/api/v1/library/{contribution_id}/run",
+        tag: "community library",
+        summary: "Evaluate a published contribution's script across its domain, within a 2 s time limit",
+        request: Some("CustomRunRequest"),
+        response: Some("CustomRun"),
+        query: &[],
+        auth: false,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/courses",
+        tag: "courses",
+        summary: "List published courses",
+        request: None,
+        response: Some("CourseList"),
+        query: &[],
+        auth: false,
+    },
+    Endpoint {
+        method: "post",
+        path: "/api/v1/courses",
+        tag: "courses",
+        summary: "Create a course owned by the signed-in instructor; it stays a draft unless `published` is set",
+        request: Some("CourseRequest"),
+        response: Some("Course"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/courses/{course_id}",
+        tag: "courses",
+        summary: "Get a course with its modules and lessons in order; drafts are only visible to their instructor",
+        request: None,
+        response: Some("CourseView"),
+        query: &[],
+        auth: false,
+    },
+    Endpoint {
+        method: "put",
+        path: "/api/v1/courses/{course_id}",
+        tag: "courses",
+        summary: "Replace a course's title, description and publication state",
+        request: Some("CourseRequest"),
+        response: Some("Course"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "delete",
+        path: "/api/v1/courses/{course_id}",
+        tag: "courses",
+        summary: "Delete a course with all its modules and lessons",
+        request: None,
+        response: None,
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "post",
+        path: "/api/v1/courses/{course_id}/modules",
+        tag: "courses",
+        summary: "Add a module to a course, at `position` or at the end",
+        request: Some("ModuleRequest"),
+        response: Some("CourseModule"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "put",
+        path: "/api/v1/modules/{module_id}",
+        tag: "courses",
+        summary: "Rename a module, and move it when `position` is given",
+        request: Some("ModuleRequest"),
+        response: Some("CourseModule"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "delete",
+        path: "/api/v1/modules/{module_id}",
+        tag: "courses",
+        summary: "Delete a module with its lessons",
+        request: None,
+        response: None,
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "post",
+        path: "/api/v1/modules/{module_id}/lessons",
+        tag: "courses",
+        summary: "Add a lesson to a module, at `position` or at the end",
+        request: Some("LessonRequest"),
+        response: Some("Lesson"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/lessons/{lesson_id}",
+        tag: "courses",
+        summary: "Get one lesson",
+        request: None,
+        response: Some("Lesson"),
+        query: &[],
+        auth: false,
+    },
+    Endpoint {
+        method: "put",
+        path: "/api/v1/lessons/{lesson_id}",
+        tag: "courses",
+        summary: "Replace a lesson's title and items, and move it when `position` is given",
+        request: Some("LessonRequest"),
+        response: Some("Lesson"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "delete",
+        path: "/api/v1/lessons/{lesson_id}",
+        tag: "courses",
+        summary: "Delete a lesson",
+        request: None,
+        response: None,
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/instructor/courses",
+        tag: "courses",
+        summary: "List the signed-in instructor's courses, drafts included",
+        request: None,
+        response: Some("CourseList"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/classes",
+        tag: "classes",
+        summary: "Classes the signed-in user teaches or has joined",
+        request: None,
+        response: Some("ClassList"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "post",
+        path: "/api/v1/classes",
+        tag: "classes",
+        summary: "Create a class taught by the signed-in user, with a fresh join code",
+        request: Some("CreateClassRequest"),
+        response: Some("Class"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "post",
+        path: "/api/v1/classes/join",
+        tag: "classes",
+        summary: "Join a class with its join code",
+        request: Some("JoinClassRequest"),
+        response: Some("Class"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/classes/{class_id}",
+        tag: "classes",
+        summary: "Get a class; its teacher also sees the join code and roster",
+        request: None,
+        response: Some("Class"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "delete",
+        path: "/api/v1/classes/{class_id}",
+        tag: "classes",
+        summary: "Delete a class; students keep their progress",
+        request: None,
+        response: None,
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "post",
+        path: "/api/v1/classes/{class_id}/join-code",
+        tag: "classes",
+        summary: "Replace the join code; enrolled students stay",
+        request: None,
+        response: Some("Class"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "delete",
+        path: "/api/v1/classes/{class_id}/students/{user_id}",
+        tag: "classes",
+        summary: "Remove a student; students may remove themselves",
+        request: None,
+        response: None,
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/classes/{class_id}/progress",
+        tag: "classes",
+        summary: "Per-student simulation completions and quiz scores, with class totals (teacher only)",
+        request: None,
+        response: Some("ClassProgress"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/classes/{class_id}/gradebook.csv",
+        tag: "classes",
+        summary: "Gradebook for LMS import (text/csv): one row per student with name, ID and email, then assignment grades and best quiz scores as percentages (teacher only)",
+        request: None,
+        response: None,
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/classes/{class_id}/leaderboard",
+        tag: "classes",
+        summary: "Opted-in students ranked by XP or completed simulations, under pseudonyms if the teacher chose so; students may view it once the teacher turns it on",
+        request: None,
+        response: Some("Leaderboard"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "put",
+        path: "/api/v1/classes/{class_id}/leaderboard",
+        tag: "classes",
+        summary: "Turn the leaderboard on or off and choose what it ranks by and whether it shows pseudonyms (teacher only)",
+        request: Some("LeaderboardRequest"),
+        response: Some("Leaderboard"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "put",
+        path: "/api/v1/classes/{class_id}/leaderboard/me",
+        tag: "classes",
+        summary: "Opt in to the class leaderboard (students only)",
+        request: None,
+        response: None,
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "delete",
+        path: "/api/v1/classes/{class_id}/leaderboard/me",
+        tag: "classes",
+        summary: "Opt out of the class leaderboard (students only)",
+        request: None,
+        response: None,
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/classes/{class_id}/assignments",
+        tag: "classes",
+        summary: "Assignments of a class, soonest due first",
+        request: None,
+        response: Some("AssignmentList"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "post",
+        path: "/api/v1/classes/{class_id}/assignments",
+        tag: "classes",
+        summary: "Set an assignment on a simulation, with required explorations, questions and an optional due date (teacher only)",
+        request: Some("AssignmentRequest"),
+        response: Some("Assignment"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/assignments/{assignment_id}",
+        tag: "classes",
+        summary: "Get an assignment",
+        request: None,
+        response: Some("Assignment"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "put",
+        path: "/api/v1/assignments/{assignment_id}",
+        tag: "classes",
+        summary: "Replace an assignment (teacher only)",
+        request: Some("AssignmentRequest"),
+        response: Some("Assignment"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "delete",
+        path: "/api/v1/assignments/{assignment_id}",
+        tag: "classes",
+        summary: "Delete an assignment with its submissions (teacher only)",
+        request: None,
+        response: None,
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/assignments/{assignment_id}/submission",
+        tag: "classes",
+        summary: "The signed-in student's submission, with grade and feedback once graded",
+        request: None,
+        response: Some("Submission"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "post",
+        path: "/api/v1/assignments/{assignment_id}/submission",
+        tag: "classes",
+        summary: "Hand in stored result IDs and answers; allowed until graded, marked late after the due date",
+        request: Some("SubmitAssignmentRequest"),
+        response: Some("Submission"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/assignments/{assignment_id}/submissions",
+        tag: "classes",
+        summary: "Every student of the class with their submission, if any (teacher only)",
+        request: None,
+        response: Some("AssignmentSubmissions"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "put",
+        path: "/api/v1/submissions/{submission_id}/grade",
+        tag: "classes",
+        summary: "Grade a submission (teacher only)",
+        request: Some("GradeRequest"),
+        response: Some("Submission"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/lti/login",
+        tag: "lti",
+        summary: "LTI 1.3 login initiation from a registered platform; redirects to its authorization endpoint",
+        request: None,
+        response: None,
+        query: &[
+            ("iss", "Platform issuer"),
+            ("login_hint", "Opaque user hint, passed back to the platform"),
+            ("target_link_uri", "Where the launch is headed"),
+            ("lti_message_hint", "Opaque message hint, passed back to the platform"),
+            ("client_id", "Our client ID, when the platform registered the tool more than once"),
+            ("lti_deployment_id", "Deployment the launch belongs to"),
+        ],
+        auth: false,
+    },
+    Endpoint {
+        method: "post",
+        path: "/api/v1/lti/login",
+        tag: "lti",
+        summary: "LTI 1.3 login initiation sent as a form post",
+        request: None,
+        response: None,
+        query: &[],
+        auth: false,
+    },
+    Endpoint {
+        method: "post",
+        path: "/api/v1/lti/launch",
+        tag: "lti",
+        summary: "Verify a platform's id_token (form post with state), sign the user in and redirect to the simulation; deep linking requests get a simulation picker",
+        request: None,
+        response: None,
+        query: &[],
+        auth: false,
+    },
+    Endpoint {
+        method: "post",
+        path: "/api/v1/lti/deep-link",
+        tag: "lti",
+        summary: "Send the picked simulation back to the platform as a signed deep linking response",
+        request: None,
+        response: None,
+        query: &[],
+        auth: false,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/lti/jwks",
+        tag: "lti",
+        summary: "Public keys platforms verify our deep linking responses and grade passback requests with",
+        request: None,
+        response: Some("Jwks"),
+        query: &[],
+        auth: false,
+    },
+    Endpoint {
+        method: "put",
+        path: "/api/v1/admin/simulations/{id}/translations/{locale}",
+        tag: "admin",
+        summary: "Upload a simulation's name, description, theory and parameter labels in one locale (administrators only)",
+        request: Some("TranslationRequest"),
+        response: Some("Translation"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "delete",
+        path: "/api/v1/admin/simulations/{id}/translations/{locale}",
+        tag: "admin",
+        summary: "Delete a translation (administrators only)",
+        request: None,
+        response: None,
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "post",
+        path: "/api/v1/admin/catalog/reload",
+        tag: "admin",
+        summary: "Read the simulation content files again; nothing changes if any file is invalid (administrators only)",
+        request: None,
+        response: Some("CatalogReload"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/admin/simulations",
+        tag: "admin",
+        summary: "Every simulation with the source of its content, retirement and waiting draft (administrators only)",
+        request: None,
+        response: Some("ContentSummaryList"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/admin/simulations/{id}",
+        tag: "admin",
+        summary: "The content being served and the draft, if any (administrators only)",
+        request: None,
+        response: Some("ContentEntry"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "put",
+        path: "/api/v1/admin/simulations/{id}/draft",
+        tag: "admin",
+        summary: "Save a draft of a simulation's text and parameters (administrators only)",
+        request: Some("SimulationContent"),
+        response: Some("ContentRevision"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "delete",
+        path: "/api/v1/admin/simulations/{id}/draft",
+        tag: "admin",
+        summary: "Discard the draft (administrators only)",
+        request: None,
+        response: None,
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/admin/simulations/{id}/draft/preview",
+        tag: "admin",
+        summary: "The listing entry and details page as they would be served once the draft is published (administrators only)",
+        request: None,
+        response: Some("ContentPreview"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "post",
+        path: "/api/v1/admin/simulations/{id}/publish",
+        tag: "admin",
+        summary: "Serve the draft in place of the content file (administrators only)",
+        request: None,
+        response: Some("ContentEntry"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "delete",
+        path: "/api/v1/admin/simulations/{id}/published",
+        tag: "admin",
+        summary: "Drop published content and serve the content file again (administrators only)",
+        request: None,
+        response: None,
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "post",
+        path: "/api/v1/admin/simulations/{id}/retire",
+        tag: "admin",
+        summary: "Withdraw a simulation from listings and lookups (administrators only)",
+        request: None,
+        response: None,
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "post",
+        path: "/api/v1/admin/simulations/{id}/restore",
+        tag: "admin",
+        summary: "Return a retired simulation to the catalog (administrators only)",
+        request: None,
+        response: None,
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/admin/contributions",
+        tag: "admin",
+        summary: "Contributions in one status, the longest waiting first (administrators only)",
+        request: None,
+        response: Some("ContributionList"),
+        query: &[("status", "pending (default), approved, rejected or published")],
+        auth: true,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/admin/contributions/{contribution_id}",
+        tag: "admin",
+        summary: "Any contribution with its reviews (administrators only)",
+        request: None,
+        response: Some("Contribution"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "post",
+        path: "/api/v1/admin/contributions/{contribution_id}/review",
+        tag: "admin",
+        summary: "Approve or reject a pending contribution with a comment; authors cannot review their own (administrators only)",
+        request: Some("ReviewRequest"),
+        response: Some("Contribution"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "post",
+        path: "/api/v1/admin/contributions/{contribution_id}/publish",
+        tag: "admin",
+        summary: "Put an approved contribution in the community library (administrators only)",
+        request: None,
+        response: Some("Contribution"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "delete",
+        path: "/api/v1/admin/contributions/{contribution_id}/publish",
+        tag: "admin",
+        summary: "Take a contribution out of the library; it stays approved (administrators only)",
+        request: None,
+        response: None,
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "post",
+        path: "/api/v1/auth/register",
+        tag: "auth",
+        summary: "Create an account and receive a token pair",
+        request: Some("RegisterRequest"),
+        response: Some("TokenResponse"),
+        query: &[],
+        auth: false,
+    },
+    Endpoint {
+        method: "post",
+        path: "/api/v1/auth/login",
+        tag: "auth",
+        summary: "Exchange email and password for a token pair",
+        request: Some("LoginRequest"),
+        response: Some("TokenResponse"),
+        query: &[],
+        auth: false,
+    },
+    Endpoint {
+        method: "post",
+        path: "/api/v1/auth/refresh",
+        tag: "auth",
+        summary: "Exchange a single-use refresh token for a new token pair",
+        request: Some("RefreshRequest"),
+        response: Some("TokenResponse"),
+        query: &[],
+        auth: false,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/auth/me",
+        tag: "auth",
+        summary: "Get the signed-in user",
+        request: None,
+        response: Some("User"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "post",
+        path: "/api/v1/ai/ask",
+        tag: "ai",
+        summary: "Ask the assistant a physics question",
+        request: Some("AskQuestionRequest"),
+        response: Some("AskQuestionResponse"),
+        query: &[],
+        auth: false,
+    },
+    Endpoint {
+        method: "post",
+        path: "/api/v1/tutor/chat",
+        tag: "ai",
+        summary: "Ask the AI tutor about a simulation, grounded in its theory, the student's parameters and latest result; \
+                  the answer streams as Server-Sent Events: `delta` events with {content}, then `done` with {model} or \
+                  `error` with {detail} (text/event-stream)",
+        request: Some("TutorChatRequest"),
+        response: None,
+        query: &[("lang", "Comma-separated languages of the theory given to the tutor, overriding Accept-Language")],
+        auth: true,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/progress",
+        tag: "progress",
+        summary: "Get user progress",
+        request: None,
+        response: Some("UserProgress"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "post",
+        path: "/api/v1/progress/{simulation_id}",
+        tag: "progress",
+        summary: "Record progress on a simulation. Completion is sticky, the best score is kept and time spent accumulates.",
+        request: Some("SaveProgressRequest"),
+        response: Some("UserProgress"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/recommendations",
+        tag: "progress",
+        summary: "Suggest what the learner should study next and the difficulty level they are working at, from their completions and best quiz scores",
+        request: None,
+        response: Some("Recommendations"),
+        query: &[
+            ("strategy", "mastery (default): next step at the learner's level; review: quizzes not yet passed; challenge: harder simulations they are ready for"),
+            ("limit", "Most suggestions to return, 1 to 10 (default 3)"),
+        ],
+        auth: true,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/users/me/achievements",
+        tag: "progress",
+        summary: "The learner's experience points, activity streaks and every badge, earned or not, with their progress towards it",
+        request: None,
+        response: Some("AchievementSummary"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/v1/users/me/bookmarks",
+        tag: "progress",
+        summary: "The learner's bookmarked simulations and results, newest first",
+        request: None,
+        response: Some("BookmarkList"),
+        query: &[("simulation_id", "Only bookmarks of this simulation and its results")],
+        auth: true,
+    },
+    Endpoint {
+        method: "post",
+        path: "/api/v1/users/me/bookmarks",
+        tag: "progress",
+        summary: "Bookmark a simulation, or a stored result of it, with an optional note",
+        request: Some("BookmarkRequest"),
+        response: Some("Bookmark"),
+        query: &[],
+        auth: true,
+    },
+    Endpoint {
+        method: "delete",
+        path: "/api/v1/users/me/bookmarks/{bookmark_id}",
+        tag: "progress",
+        summary: "Remove a bookmark",
+        request: None,
+        response: None,
+        query: &[],
+        auth: true,
+    },
+];
+
+/// Serve the OpenAPI document
+pub async fn openapi_json(State(state): State<AppState>) -> Json<Value> {
+    let simulation_ids: Vec<&str> = state.simulations.iter().map(|s| s.id()).collect();
+    Json(build_spec(&simulation_ids))
+}
+
+/// Swagger UI pointed at the generated document
+pub async fn swagger_ui() -> Html<&'static str> {
+    Html(SWAGGER_UI_HTML)
+}
+
+const SWAGGER_UI_HTML: &str = r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8" />
+  <title>DIU Physics Tutorial API</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css" />
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js" crossorigin></script>
+  <script>
+    window.onload = () => {
+      window.ui = SwaggerUIBundle({ url: "/api/openapi.json", dom_id: "#swagger-ui" });
+    };
+  </script>
+</body>
+</html>
+"##;
+
+fn build_spec(simulation_ids: &[&str]) -> Value {
+    let mut paths = Map::new();
+    for endpoint in ENDPOINTS {
+        let item = paths
+            .entry(endpoint.path.to_string())
+            .or_insert_with(|| json!({}));
+        item[endpoint.method] = operation(endpoint, simulation_ids);
+    }
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "DIU Physics Tutorial API",
+            "description": env!("CARGO_PKG_DESCRIPTION"),
+            "version": env!("CARGO_PKG_VERSION"),
+            "license": { "name": "MIT" },
+        },
+        "paths": paths,
+        "components": {
+            "schemas": schemas(),
+            "securitySchemes": {
+                "bearerAuth": { "type": "http", "scheme": "bearer", "bearerFormat": "JWT" },
+            },
+        },
+    })
+}
+
+fn operation(endpoint: &Endpoint, simulation_ids: &[&str]) -> Value {
+    let mut parameters: Vec<Value> = endpoint
+        .path
+        .split('/')
+        .filter_map(|segment| segment.strip_prefix('{')?.strip_suffix('}'))
+        .map(|name| {
+            let mut schema = json!({ "type": "string" });
+            if name == "id" || name == "simulation_id" {
+                schema["enum"] = json!(simulation_ids);
+            }
+            json!({ "name": name, "in": "path", "required": true, "schema": schema })
+        })
+        .collect();
+    parameters.extend(endpoint.query.iter().map(|(name, description)| {
+        json!({
+            "name": name,
+            "in": "query",
+            "required": false,
+            "description": description,
+            "schema": { "type": "string" },
+        })
+    }));
+
+    let success = match endpoint.response {
+        Some(schema) => json!({
+            "description": "Success",
+            "content": { "application/json": { "schema": schema_ref(schema) } },
+        }),
+        None => json!({ "description": "Success" }),
+    };
+    let mut responses = json!({ "200": success });
+    if endpoint.path.contains('{') {
+        responses["404"] = json!({ "description": "Not found" });
+        // Account and progress routes still answer with bare status codes
+        if !matches!(endpoint.tag, "auth" | "progress") {
+            responses["404"]["content"] = json!({ "application/problem+json": { "schema": schema_ref("Problem") } });
+        }
+    }
+    if endpoint.auth {
+        responses["401"] = json!({
+            "description": "Missing, invalid or expired access token",
+            "content": { "application/json": { "schema": schema_ref("AuthErrorBody") } },
+        });
+    }
+    if matches!(endpoint.tag, "courses" | "classes") && endpoint.auth && endpoint.path.contains('{') {
+        responses["403"] = json!({
+            "description": "Only the course's instructor or the class's teacher may do this",
+            "content": { "application/problem+json": { "schema": schema_ref("Problem") } },
+        });
+    }
+    if endpoint.tag == "admin" {
+        responses["403"] = json!({
+            "description": "Administrator rights are required",
+            "content": { "application/json": { "schema": schema_ref("AuthErrorBody") } },
+        });
+    }
+    let validated = (endpoint.method == "post"
+        && ["/run", "/sweep", "/quiz"].iter().any(|suffix| endpoint.path.ends_with(suffix)))
+        || endpoint.request.is_some() && matches!(endpoint.tag, "courses" | "classes" | "admin")
+        || endpoint.path == "/api/v1/admin/catalog/reload";
+    if validated {
+        responses["422"] = json!({
+            "description": "Invalid parameters",
+            "content": { "application/problem+json": { "schema": schema_ref("Problem") } },
+        });
+    }
+    if !matches!(endpoint.path, "/health" | "/healthz" | "/readyz" | "/version" | "/metrics") {
+        responses["429"] = json!({
+            "description": "Request budget used up; retry after the number of seconds in Retry-After",
+            "headers": { "Retry-After": { "schema": { "type": "integer" } } },
+            "content": { "application/problem+json": { "schema": schema_ref("Problem") } },
+        });
+    }
+
+    let mut op = json!({
+        "tags": [endpoint.tag],
+        "summary": endpoint.summary,
+        "parameters": parameters,
+        "responses": responses,
+    });
+    if endpoint.auth {
+        op["security"] = json!([{ "bearerAuth": [] }]);
+    }
+    if let Some(schema) = endpoint.request {
+        op["requestBody"] = json!({
+            "required": true,
+            "content": { "application/json": { "schema": schema_ref(schema) } },
+        });
+    }
+    op
+}
+
+fn schema_ref(name: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{}", name) })
+}
+
+fn schemas() -> Value {
+    json!({
+        "HealthResponse": {
+            "type": "object",
+            "properties": {
+                "status": { "type": "string" },
+                "version": { "type": "string" },
+                "service": { "type": "string" },
+            },
+        },
+        "Probe": {
+            "type": "object",
+            "properties": { "status": { "type": "string", "enum": ["ok"] } },
+        },
+        "Check": {
+            "type": "object",
+            "properties": {
+                "status": { "type": "string", "enum": ["ok", "failing"] },
+                "latency_ms": { "type": "number" },
+                "detail": { "type": "string" },
+            },
+        },
+        "Readiness": {
+            "type": "object",
+            "properties": {
+                "status": { "type": "string", "enum": ["ready", "degraded", "not_ready"] },
+                "checks": {
+                    "type": "object",
+                    "properties": {
+                        "database": schema_ref("Check"),
+                        "cache": schema_ref("Check"),
+                        "jobs": schema_ref("Check"),
+                    },
+                },
+            },
+        },
+        "BuildInfo": {
+            "type": "object",
+            "properties": {
+                "service": { "type": "string" },
+                "version": { "type": "string" },
+                "git_commit": { "type": "string" },
+                "build_date": { "type": "string", "format": "date-time" },
+                "simulations": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": { "id": { "type": "string" }, "version": { "type": "integer" } },
+                    },
+                },
+            },
+        },
+        "SimulationInfo": {
+            "type": "object",
+            "properties": {
+                "id": { "type": "string" },
+                "name": { "type": "string" },
+                "description": { "type": "string" },
+                "difficulty": { "type": "string", "enum": ["beginner", "intermediate", "advanced"] },
+                "estimated_time_minutes": { "type": "integer" },
+                "topics": { "type": "array", "items": { "type": "string" } },
+                "topic_ids": { "type": "array", "items": { "type": "string" }, "description": "Taxonomy topics the labels in `topics` belong to" },
+                "prerequisites": { "type": "array", "items": { "type": "string" }, "description": "Simulation IDs to work through first" },
+            },
+        },
+        "SimulationPage": {
+            "type": "object",
+            "properties": {
+                "items": { "type": "array", "items": schema_ref("SimulationInfo") },
+                "total": { "type": "integer", "description": "Simulations matching the filters, across all pages" },
+                "page": { "type": "integer" },
+                "per_page": { "type": "integer" },
+                "total_pages": { "type": "integer" },
+            },
+        },
+        "SearchPage": {
+            "type": "object",
+            "properties": {
+                "matched": {
+                    "type": "string",
+                    "enum": ["all", "any"],
+                    "description": "any when no entry contains every word and the items contain some of them",
+                },
+                "items": { "type": "array", "items": schema_ref("SearchHit") },
+                "total": { "type": "integer", "description": "Matches across all pages" },
+                "page": { "type": "integer" },
+                "per_page": { "type": "integer" },
+                "total_pages": { "type": "integer" },
+            },
+        },
+        "SearchHit": {
+            "type": "object",
+            "properties": {
+                "kind": { "type": "string", "enum": ["simulation", "glossary"] },
+                "simulation_id": { "type": "string", "description": "The simulation, or the one whose theory defines the term" },
+                "title": { "type": "string", "description": "Simulation name or glossary term" },
+                "snippet": { "type": "string", "description": "Best matching passage, or the term's definition, as escaped HTML with the matched words in <mark>" },
+                "score": { "type": "number", "description": "BM25 relevance; higher is better" },
+            },
+        },
+        "SemanticPage": {
+            "type": "object",
+            "properties": {
+                "model": { "type": "string", "description": "Embedding model the ranking comes from; builtin-tfidf without an embeddings API" },
+                "items": { "type": "array", "items": schema_ref("SemanticHit") },
+                "total": { "type": "integer", "description": "Matching simulations across all pages" },
+                "page": { "type": "integer" },
+                "per_page": { "type": "integer" },
+                "total_pages": { "type": "integer" },
+            },
+        },
+        "SemanticHit": {
+            "type": "object",
+            "properties": {
+                "simulation_id": { "type": "string" },
+                "name": { "type": "string" },
+                "section": { "type": "string", "nullable": true, "description": "Theory section of the best matching passage; null for the simulation's overview" },
+                "excerpt": { "type": "string", "description": "Start of the passage, as plain text" },
+                "score": { "type": "number", "description": "Cosine similarity of the passage and the query" },
+            },
+        },
+        "RelatedSimulations": {
+            "type": "object",
+            "properties": {
+                "simulation_id": { "type": "string" },
+                "model": { "type": "string", "description": "Embedding model the similarities come from" },
+                "items": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "simulation_id": { "type": "string" },
+                            "name": { "type": "string" },
+                            "summary": { "type": "string" },
+                            "score": { "type": "number", "description": "Cosine similarity of the two simulations' content" },
+                        },
+                    },
+                },
+            },
+        },
+        "SimulationParameter": {
+            "type": "object",
+            "properties": {
+                "name": { "type": "string" },
+                "label": { "type": "string" },
+                "param_type": { "type": "string", "enum": ["slider", "toggle", "select", "curve", "formula"] },
+                "min": { "type": "number", "nullable": true },
+                "max": { "type": "number", "nullable": true },
+                "default": { "description": "A number for sliders, a boolean for toggles, one of `options` for selects, an array of 2 to 200 numbers for curves, the text of a formula" },
+                "step": { "type": "number", "nullable": true },
+                "options": { "type": "array", "items": { "type": "string" }, "description": "Allowed values of a select parameter" },
+                "unit": {
+                    "type": "string",
+                    "description": "Unit of a slider's value, range and step; runs may give the value in another unit of the same dimension. A curve's points and a formula's values are always in its unit",
+                },
+                "dimension": { "type": "string", "description": "What the unit measures, e.g. `length`" },
+                "variables": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Variables a formula may use besides `pi` and `e`",
+                },
+            },
+        },
+        "SimulationDetails": {
+            "type": "object",
+            "properties": {
+                "id": { "type": "string" },
+                "name": { "type": "string" },
+                "description": { "type": "string" },
+                "parameters": { "type": "array", "items": schema_ref("SimulationParameter") },
+                "theory": { "type": "string", "description": "Markdown with LaTeX math" },
+            },
+        },
+        "RunSimulationRequest": {
+            "type": "object",
+            "required": ["parameters"],
+            "properties": {
+                "parameters": {
+                    "type": "object",
+                    "description": "Values keyed by parameter name; omitted parameters take their declared default, except the `seed` of a stochastic simulation, which is drawn at random and recorded in the result. A slider with a unit also takes `{\"value\": 0.55, \"unit\": \"μm\"}` or `\"0.55 μm\"`, converted to its own unit.",
+                    "additionalProperties": true,
+                },
+                "validate": {
+                    "type": "boolean",
+                    "default": false,
+                    "description": "Also compare the numerical output with the analytic solution, for simulations and parameters that have one (projectile motion without drag, bound Kepler orbits, uniform Lorentz fields, step-driven circuits)",
+                },
+                "compare_to_dataset": {
+                    "type": "string",
+                    "description": "ID of a dataset from `/api/v1/datasets` for this simulation to compare the run with",
+                },
+                "noise": {
+                    "type": "object",
+                    "description": "Record an output as a detector would, with pixels, shot noise, dark counts and readout noise. Simulations with a detector signal (double slit, blackbody, hydrogen atom, tunneling, photoelectric effect) record it by default; others need output.",
+                    "properties": {
+                        "output": { "type": "string", "description": "Dotted path of an intensity-like numeric array" },
+                        "position": { "type": "string", "description": "Dotted path of the increasing positions it is sampled at" },
+                        "peak_counts": { "type": "number", "default": 1000, "description": "Expected count in the brightest pixel" },
+                        "shot_noise": { "type": "boolean", "default": true, "description": "Draw counts from a Poisson distribution" },
+                        "readout_noise": { "type": "number", "default": 0, "description": "Standard deviation of Gaussian readout noise, in counts" },
+                        "dark_counts": { "type": "number", "default": 0, "description": "Mean dark count per pixel" },
+                        "pixel_size": { "type": "number", "description": "Pixel width in the units of position; the output's own sampling by default" },
+                        "seed": { "type": "integer", "description": "Seed of the noise; random and reported back by default" },
+                    },
+                },
+            },
+        },
+        "SimulationResult": {
+            "type": "object",
+            "properties": {
+                "id": { "type": "string", "format": "uuid" },
+                "simulation_id": { "type": "string" },
+                "simulation_version": { "type": "integer", "description": "Version of the computation that produced the data" },
+                "parameters": { "type": "object", "additionalProperties": true },
+                "data": { "type": "object", "additionalProperties": true },
+                "compute_backend": { "type": "string", "enum": ["cpu", "gpu"], "description": "Hardware the data was computed on" },
+                "computed_at": { "type": "string", "format": "date-time" },
+                "seed": { "type": "integer", "description": "Seed of a stochastic simulation's random numbers; running with it again reproduces the result exactly" },
+                "attestation": { "$ref": "#/components/schemas/Attestation" },
+            },
+        },
+        "Attestation": {
+            "type": "object",
+            "description": "Ed25519 signature over the SHA-256 of the RFC 8785 canonical JSON of {backend, data, parameters, simulation_id, simulation_version}; absent on results stored before results were signed",
+            "properties": {
+                "algorithm": { "type": "string", "enum": ["Ed25519"] },
+                "key_id": { "type": "string" },
+                "backend": { "type": "string", "description": "Server version and commit that computed the result" },
+                "sha256": { "type": "string", "description": "Hash of the canonical claim, hex encoded" },
+                "signature": { "type": "string", "format": "byte", "description": "Signature of the 32 hash bytes" },
+            },
+        },
+        "AttestationKeys": {
+            "type": "object",
+            "properties": {
+                "current": { "type": "string", "description": "Key new results are signed with" },
+                "backend": { "type": "string" },
+                "keys": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "key_id": { "type": "string" },
+                            "algorithm": { "type": "string" },
+                            "public_key": { "type": "string", "format": "byte", "description": "Raw 32-byte public key" },
+                            "first_used_at": { "type": "string", "format": "date-time" },
+                        },
+                    },
+                },
+            },
+        },
+        "VerifyRequest": {
+            "type": "object",
+            "description": "A simulation result as returned by the API; fields other than these are ignored",
+            "required": ["simulation_id", "parameters", "data", "attestation"],
+            "properties": {
+                "simulation_id": { "type": "string" },
+                "simulation_version": { "type": "integer", "default": 1 },
+                "parameters": { "type": "object", "additionalProperties": true },
+                "data": { "type": "object", "additionalProperties": true },
+                "attestation": { "$ref": "#/components/schemas/Attestation" },
+            },
+        },
+        "Verification": {
+            "type": "object",
+            "properties": {
+                "valid": { "type": "boolean" },
+                "reason": { "type": "string", "description": "Why the attestation does not hold" },
+                "key_id": { "type": "string" },
+                "backend": { "type": "string" },
+            },
+        },
+        "ResultPin": {
+            "type": "object",
+            "properties": {
+                "result_id": { "type": "string", "format": "uuid" },
+                "cid": { "type": "string", "description": "IPFS content identifier of the bundle" },
+                "sha256": { "type": "string", "description": "SHA-256 of the bundle, hex encoded" },
+                "size": { "type": "integer", "description": "Bundle size (bytes)" },
+                "pinned_at": { "type": "string", "format": "date-time" },
+                "gateway_url": { "type": "string", "format": "uri", "description": "The bundle on a public IPFS gateway" },
+            },
+        },
+        "ReplayResult": {
+            "type": "object",
+            "properties": {
+                "result_id": { "type": "string", "format": "uuid" },
+                "simulation_id": { "type": "string" },
+                "stored_version": { "type": "integer", "description": "Version that produced the stored result" },
+                "replayed_version": { "type": "integer", "description": "Version that produced data" },
+                "current_version": { "type": "integer" },
+                "identical": { "type": "boolean", "description": "Whether data equals the stored data exactly" },
+                "differences": {
+                    "type": "object",
+                    "description": "Numeric outputs that changed, by dotted path, with their values before and after",
+                    "additionalProperties": true,
+                },
+                "data": { "type": "object", "additionalProperties": true },
+            },
+        },
+        "SelftestReport": {
+            "type": "object",
+            "properties": {
+                "simulation_id": { "type": "string" },
+                "simulation_version": { "type": "integer", "description": "Version the golden output was recorded with, and rerun" },
+                "current_version": { "type": "integer" },
+                "passed": { "type": "boolean" },
+                "failed": { "type": "integer", "description": "Checks that failed" },
+                "tolerance": {
+                    "type": "object",
+                    "description": "An output passes when |actual − expected| ≤ absolute + relative · |expected|",
+                    "properties": {
+                        "relative": { "type": "number" },
+                        "absolute": { "type": "number" },
+                    },
+                },
+                "parameters": { "type": "object", "additionalProperties": true },
+                "checks": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "output": { "type": "string", "description": "Dotted path into the run data" },
+                            "passed": { "type": "boolean" },
+                            "expected": { "description": "A number, a string or boolean, or for arrays {length, min, max, mean, samples}" },
+                            "actual": { "description": "As expected; absent when the run no longer has this output" },
+                            "deviation": { "type": "number", "description": "Largest |actual − expected| over the compared numbers" },
+                        },
+                    },
+                },
+            },
+        },
+        "DerivedQuantity": {
+            "type": "object",
+            "properties": {
+                "name": { "type": "string", "description": "Key, unique within a run" },
+                "label": { "type": "string" },
+                "value": { "type": "number" },
+                "unit": { "type": "string", "description": "Empty for dimensionless quantities" },
+                "formula": { "type": "string", "description": "LaTeX" },
+            },
+        },
+        "Validation": {
+            "type": "object",
+            "properties": {
+                "output": { "type": "string", "description": "Dotted path of the compared output in data" },
+                "analytic": { "type": "string", "description": "LaTeX of the analytic solution" },
+                "max_deviation": { "type": "number", "description": "Largest |numerical − analytic| over the compared points, in unit" },
+                "max_relative_deviation": { "type": "number", "description": "max_deviation over the largest |analytic| value" },
+                "unit": { "type": "string", "description": "Empty for dimensionless outputs" },
+                "points": { "type": "integer" },
+            },
+        },
+        "RunSimulationResponse": {
+            "type": "object",
+            "properties": {
+                "id": { "type": "string", "format": "uuid" },
+                "simulation_id": { "type": "string" },
+                "simulation_version": { "type": "integer", "description": "Version of the computation that produced the data" },
+                "parameters": { "type": "object", "additionalProperties": true },
+                "data": { "type": "object", "additionalProperties": true },
+                "compute_backend": { "type": "string", "enum": ["cpu", "gpu"], "description": "Hardware the data was computed on" },
+                "computed_at": { "type": "string", "format": "date-time", "description": "When the result was first computed; earlier than the request on a cache hit" },
+                "attestation": { "$ref": "#/components/schemas/Attestation" },
+                "units": {
+                    "type": "object",
+                    "description": "Unit of each value in `parameters` that has one, by parameter name",
+                    "additionalProperties": { "type": "string" },
+                },
+                "derived": {
+                    "type": "array",
+                    "items": schema_ref("DerivedQuantity"),
+                    "description": "Quantities worked out from the run, such as a fringe spacing or an orbital period; those undefined for the run are left out",
+                },
+                "validation": {
+                    "type": "array",
+                    "items": schema_ref("Validation"),
+                    "description": "Present when the request set validate; empty when there is no analytic solution for these parameters",
+                },
+                "dataset_comparison": schema_ref("DatasetComparison"),
+                "detector": {
+                    "type": "object",
+                    "description": "Present when the request set noise",
+                    "properties": {
+                        "output": { "type": "string" },
+                        "position": { "type": "string" },
+                        "seed": { "type": "integer" },
+                        "counts_per_unit": { "type": "number", "description": "Expected counts per unit of the output" },
+                        "x": { "type": "array", "items": { "type": "number" }, "description": "Pixel centres, in the units of position" },
+                        "expected": { "type": "array", "items": { "type": "number" }, "description": "Mean count of each pixel, dark counts included" },
+                        "counts": { "type": "array", "items": { "type": "number" }, "description": "Recorded count of each pixel" },
+                    },
+                },
+                "cache": {
+                    "type": "object",
+                    "properties": {
+                        "hit": { "type": "boolean" },
+                        "key": { "type": "string", "description": "Simulation ID and SHA-256 of the canonical parameters" },
+                    },
+                },
+            },
+        },
+        "Job": {
+            "type": "object",
+            "properties": {
+                "id": { "type": "string", "format": "uuid" },
+                "simulation_id": { "type": "string" },
+                "status": { "type": "string", "enum": ["queued", "running", "done", "failed", "cancelled"] },
+                "progress": { "type": "number", "description": "Completed fraction from 0 to 1" },
+                "result_id": { "type": "string", "format": "uuid", "nullable": true, "description": "Fetch from /api/v1/simulations/results/{result_id} once done" },
+                "error": { "nullable": true, "description": "Parameter errors or a message when the job failed, including running over its time or output budget" },
+                "created_at": { "type": "string", "format": "date-time" },
+                "started_at": { "type": "string", "format": "date-time", "nullable": true },
+                "finished_at": { "type": "string", "format": "date-time", "nullable": true },
+            },
+        },
+        "SweepRequest": {
+            "type": "object",
+            "required": ["parameter", "start", "stop", "step"],
+            "properties": {
+                "parameter": { "type": "string" },
+                "start": { "type": "number" },
+                "stop": { "type": "number" },
+                "step": { "type": "number" },
+                "parameters": { "type": "object", "additionalProperties": true },
+            },
+        },
+        "SweepResult": {
+            "type": "object",
+            "properties": {
+                "simulation_id": { "type": "string" },
+                "parameter": { "type": "string" },
+                "seed": { "type": "integer", "description": "Seed shared by every point of a stochastic simulation's sweep" },
+                "points": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "value": { "type": "number" },
+                            "data": { "type": "object", "additionalProperties": true },
+                        },
+                    },
+                },
+                "computed_at": { "type": "string", "format": "date-time" },
+            },
+        },
+        "CompareRequest": {
+            "type": "object",
+            "required": ["runs"],
+            "properties": {
+                "runs": {
+                    "type": "array",
+                    "minItems": 2,
+                    "maxItems": 8,
+                    "items": { "type": "object", "additionalProperties": true },
+                },
+            },
+        },
+        "CompareResult": {
+            "type": "object",
+            "properties": {
+                "simulation_id": { "type": "string" },
+                "runs": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "parameters": { "type": "object", "additionalProperties": true },
+                            "data": { "type": "object", "additionalProperties": true },
+                        },
+                    },
+                },
+                "varied": { "type": "array", "items": { "type": "string" } },
+                "differences": {
+                    "type": "object",
+                    "description": "Keyed by dotted output path: values, change and relative_change for scalars, max_abs_change for arrays",
+                    "additionalProperties": true,
+                },
+                "computed_at": { "type": "string", "format": "date-time" },
+            },
+        },
+        "Metal": {
+            "type": "object",
+            "properties": {
+                "id": { "type": "string", "description": "Value of the photoelectric simulation's metal parameter" },
+                "name": { "type": "string" },
+                "symbol": { "type": "string" },
+                "work_function": { "type": "number", "description": "Work function in eV" },
+            },
+        },
+        "MetalList": {
+            "type": "array",
+            "items": schema_ref("Metal"),
+        },
+        "Constant": {
+            "type": "object",
+            "properties": {
+                "id": { "type": "string" },
+                "name": { "type": "string" },
+                "symbol": { "type": "string" },
+                "value": { "type": "number" },
+                "uncertainty": { "type": "number", "description": "Standard uncertainty in the same unit; 0 for exact values" },
+                "unit": { "type": "string", "description": "SI unit, e.g. `J K^-1`; empty for dimensionless constants" },
+            },
+        },
+        "ConstantList": {
+            "type": "array",
+            "items": schema_ref("Constant"),
+        },
+        "Unit": {
+            "type": "object",
+            "properties": {
+                "symbol": { "type": "string" },
+                "name": { "type": "string" },
+                "dimension": { "type": "string", "description": "What the unit measures; only units of the same dimension convert" },
+                "scale": { "type": "number", "description": "A value x in this unit is x * scale + offset in the SI unit of its dimension" },
+                "offset": { "type": "number" },
+                "aliases": { "type": "array", "items": { "type": "string" }, "description": "Other spellings accepted in requests" },
+            },
+        },
+        "UnitList": {
+            "type": "array",
+            "items": schema_ref("Unit"),
+        },
+        "FitRequest": {
+            "type": "object",
+            "required": ["model", "x", "y"],
+            "properties": {
+                "model": { "type": "string", "enum": ["linear", "exponential", "sinusoidal", "gaussian"] },
+                "x": { "type": "array", "items": { "type": "number" }, "maxItems": 10000 },
+                "y": { "type": "array", "items": { "type": "number" }, "description": "One value per x" },
+                "uncertainty": { "type": "array", "items": { "type": "number" }, "description": "One standard deviation of each y; without it every point counts the same" },
+                "initial": {
+                    "type": "object",
+                    "additionalProperties": { "type": "number" },
+                    "description": "Starting values by parameter name, replacing the automatic guess",
+                },
+            },
+        },
+        "CurveFit": {
+            "type": "object",
+            "properties": {
+                "model": { "type": "string" },
+                "formula": { "type": "string", "description": "LaTeX of the model" },
+                "parameters": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "name": { "type": "string", "description": "slope and intercept; amplitude, rate and offset; amplitude, angular_frequency, phase and offset; or amplitude, mean and standard_deviation" },
+                            "value": { "type": "number" },
+                            "uncertainty": { "type": "number", "nullable": true, "description": "One standard deviation" },
+                        },
+                    },
+                },
+                "uncertainties": { "type": "string", "enum": ["measurements", "scatter"], "description": "Whether parameter uncertainties come from the given uncertainties or the scatter about the fit" },
+                "chi_squared": { "type": "number" },
+                "degrees_of_freedom": { "type": "integer" },
+                "reduced_chi_squared": { "type": "number", "nullable": true },
+                "p_value": { "type": "number", "nullable": true, "description": "Only for data with uncertainties" },
+                "r_squared": { "type": "number", "nullable": true },
+                "converged": { "type": "boolean" },
+                "iterations": { "type": "integer" },
+                "residuals": { "type": "array", "items": { "type": "number" }, "description": "y − model at each point, in request order" },
+            },
+        },
+        "StatsRequest": {
+            "type": "object",
+            "properties": {
+                "measurements": {
+                    "type": "object",
+                    "additionalProperties": { "type": "array", "items": { "type": "number" } },
+                    "description": "Repeated measurements by name; names may be used in propagate",
+                },
+                "quantities": {
+                    "type": "object",
+                    "additionalProperties": {
+                        "type": "object",
+                        "required": ["value"],
+                        "properties": {
+                            "value": { "type": "number" },
+                            "uncertainty": { "type": "number", "minimum": 0, "default": 0 },
+                        },
+                    },
+                    "description": "Quantities known to within an uncertainty, by name",
+                },
+                "bins": { "type": "integer", "minimum": 1, "maximum": 200, "description": "Histogram bins; Sturges' rule by default" },
+                "propagate": { "type": "string", "description": "Formula over the names, e.g. `4*pi^2*L/T^2`, with + - * / ^, parentheses, pi, e, sqrt, exp, ln, log10, sin, cos, tan, asin, acos, atan, sinh, cosh, tanh, abs and step" },
+            },
+        },
+        "StatsResponse": {
+            "type": "object",
+            "properties": {
+                "measurements": {
+                    "type": "object",
+                    "additionalProperties": {
+                        "type": "object",
+                        "properties": {
+                            "count": { "type": "integer" },
+                            "mean": { "type": "number" },
+                            "median": { "type": "number" },
+                            "standard_deviation": { "type": "number", "nullable": true, "description": "Sample standard deviation (n − 1); null for one value" },
+                            "standard_error": { "type": "number", "nullable": true, "description": "Standard deviation of the mean" },
+                            "min": { "type": "number" },
+                            "max": { "type": "number" },
+                            "histogram": {
+                                "type": "object",
+                                "properties": {
+                                    "edges": { "type": "array", "items": { "type": "number" } },
+                                    "counts": { "type": "array", "items": { "type": "integer" } },
+                                },
+                            },
+                        },
+                    },
+                },
+                "propagation": {
+                    "type": "object",
+                    "description": "Present when the request has propagate",
+                    "properties": {
+                        "expression": { "type": "string" },
+                        "value": { "type": "number" },
+                        "uncertainty": { "type": "number", "description": "First-order, for independent quantities" },
+                        "relative_uncertainty": { "type": "number", "nullable": true },
+                        "contributions": {
+                            "type": "array",
+                            "items": {
+                                "type": "object",
+                                "properties": {
+                                    "name": { "type": "string" },
+                                    "value": { "type": "number", "description": "The mean, for measured quantities" },
+                                    "uncertainty": { "type": "number", "description": "The standard error, for measured quantities" },
+                                    "partial_derivative": { "type": "number" },
+                                    "contribution": { "type": "number", "description": "|∂f/∂x| σ" },
+                                    "variance_fraction": { "type": "number" },
+                                },
+                            },
+                        },
+                    },
+                },
+            },
+        },
+        "FftRequest": {
+            "type": "object",
+            "required": ["signal"],
+            "properties": {
+                "signal": { "type": "array", "items": { "type": "number" }, "minItems": 2, "maxItems": 262144, "description": "Equally spaced samples; any length" },
+                "sample_rate": { "type": "number", "default": 1, "description": "Samples per unit time, e.g. Hz" },
+                "window": { "type": "string", "enum": ["rectangular", "hann", "hamming", "blackman"], "default": "hann" },
+            },
+        },
+        "Spectrum": {
+            "type": "object",
+            "properties": {
+                "samples": { "type": "integer" },
+                "sample_rate": { "type": "number" },
+                "window": { "type": "string" },
+                "resolution": { "type": "number", "description": "Frequency spacing, sample_rate / samples" },
+                "nyquist": { "type": "number", "description": "sample_rate / 2" },
+                "frequencies": { "type": "array", "items": { "type": "number" }, "description": "From 0 to the Nyquist frequency" },
+                "amplitude": { "type": "array", "items": { "type": "number" }, "description": "One-sided amplitude in signal units, corrected for the window's gain" },
+                "phase": { "type": "array", "items": { "type": "number" }, "description": "Radians, relative to a cosine starting at the first sample" },
+                "power_density": { "type": "array", "items": { "type": "number" }, "description": "One-sided power spectral density, signal units² per unit frequency" },
+                "peak": {
+                    "type": "object",
+                    "description": "Strongest frequency above zero; absent for a constant signal",
+                    "properties": {
+                        "frequency": { "type": "number", "description": "Interpolated between the strongest bin and its neighbours" },
+                        "amplitude": { "type": "number" },
+                    },
+                },
+            },
+        },
+        "DatasetSummary": {
+            "type": "object",
+            "properties": {
+                "id": { "type": "string" },
+                "name": { "type": "string" },
+                "simulation": { "type": "string", "description": "ID of the simulation the data is compared with" },
+                "description": { "type": "string" },
+                "points": { "type": "integer" },
+                "references": { "type": "array", "items": schema_ref("Reference") },
+            },
+        },
+        "DatasetList": {
+            "type": "array",
+            "items": schema_ref("DatasetSummary"),
+        },
+        "Dataset": {
+            "type": "object",
+            "properties": {
+                "id": { "type": "string" },
+                "name": { "type": "string" },
+                "simulation": { "type": "string" },
+                "description": { "type": "string", "description": "Where the data comes from and how it was converted" },
+                "parameters": { "type": "object", "additionalProperties": true, "description": "Run parameters that reproduce the experiment" },
+                "x_output": { "type": "string", "description": "Dotted path of the output array the x values are positions in; absent for scalar measurements" },
+                "y_output": { "type": "string", "description": "Dotted path of the output the y values measure" },
+                "x_unit": { "type": "string" },
+                "y_unit": { "type": "string" },
+                "x": { "type": "array", "items": { "type": "number" } },
+                "y": { "type": "array", "items": { "type": "number" } },
+                "uncertainty": { "type": "array", "items": { "type": "number" }, "description": "One standard deviation of each y" },
+                "labels": { "type": "array", "items": { "type": "string" }, "description": "Name of each point, e.g. the experiment it comes from" },
+                "references": { "type": "array", "items": schema_ref("Reference") },
+            },
+        },
+        "DatasetComparison": {
+            "type": "object",
+            "description": "Present when the request set compare_to_dataset",
+            "properties": {
+                "dataset": { "type": "string" },
+                "points": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "x": { "type": "number" },
+                            "label": { "type": "string" },
+                            "observed": { "type": "number" },
+                            "uncertainty": { "type": "number" },
+                            "model": { "type": "number", "description": "Run output at x, interpolated between samples" },
+                            "residual": { "type": "number", "description": "observed − model" },
+                            "pull": { "type": "number", "description": "Residual in standard deviations" },
+                        },
+                    },
+                },
+                "chi_squared": { "type": "number" },
+                "degrees_of_freedom": { "type": "integer", "description": "Compared points; the run fits no parameters to the data" },
+                "reduced_chi_squared": { "type": "number", "nullable": true },
+                "p_value": { "type": "number", "nullable": true, "description": "Probability of a χ² at least this large if the model were right" },
+                "outside_range": { "type": "integer", "description": "Points outside the run's output, left out of χ²" },
+            },
+        },
+        "Topic": {
+            "type": "object",
+            "properties": {
+                "id": { "type": "string" },
+                "name": { "type": "string" },
+                "description": { "type": "string" },
+                "parent": { "type": "string", "nullable": true },
+                "children": { "type": "array", "items": { "type": "string" } },
+                "path": { "type": "array", "items": { "type": "string" }, "description": "Topic IDs from the root down to this one" },
+                "simulation_count": { "type": "integer", "description": "Simulations under this topic or its subtopics" },
+            },
+        },
+        "TopicList": {
+            "type": "array",
+            "items": schema_ref("Topic"),
+        },
+        "TopicSimulations": {
+            "type": "object",
+            "properties": {
+                "topic": schema_ref("Topic"),
+                "simulations": { "type": "array", "items": schema_ref("SimulationInfo") },
+            },
+        },
+        "CurriculumNode": {
+            "type": "object",
+            "properties": {
+                "id": { "type": "string" },
+                "name": { "type": "string" },
+                "difficulty": { "type": "string", "enum": ["beginner", "intermediate", "advanced"] },
+                "prerequisites": { "type": "array", "items": { "type": "string" } },
+            },
+        },
+        "CurriculumGraph": {
+            "type": "object",
+            "properties": {
+                "nodes": { "type": "array", "items": schema_ref("CurriculumNode") },
+                "edges": {
+                    "type": "array",
+                    "description": "From a prerequisite to the simulation that needs it",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "from": { "type": "string" },
+                            "to": { "type": "string" },
+                        },
+                    },
+                },
+            },
+        },
+        "LearningOrder": {
+            "type": "object",
+            "properties": {
+                "target": { "type": "string", "nullable": true },
+                "simulations": { "type": "array", "items": schema_ref("CurriculumNode") },
+            },
+        },
+        "Question": {
+            "type": "object",
+            "required": ["id", "prompt", "type"],
+            "properties": {
+                "id": { "type": "string" },
+                "prompt": { "type": "string" },
+                "type": { "type": "string", "enum": ["multiple_choice", "numeric"] },
+                "choices": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Present for multiple_choice; answer with the index of a choice",
+                },
+                "unit": { "type": "string", "description": "Present for numeric; unit the answer is expected in" },
+                "hint_count": { "type": "integer", "description": "Hints that can be revealed one at a time" },
+            },
+        },
+        "Citation": {
+            "type": "object",
+            "properties": {
+                "simulation_id": { "type": "string" },
+                "simulation_version": { "type": "integer" },
+                "software": { "type": "object", "description": "CSL-JSON item of type software citing the simulation" },
+                "references": {
+                    "type": "array",
+                    "items": { "type": "object" },
+                    "description": "CSL-JSON items for the papers and books the physics comes from",
+                },
+                "bibtex": { "type": "string", "description": "The software and references as BibTeX entries" },
+            },
+        },
+        "Quiz": {
+            "type": "object",
+            "properties": {
+                "simulation_id": { "type": "string" },
+                "title": { "type": "string" },
+                "questions": { "type": "array", "items": schema_ref("Question") },
+            },
+        },
+        "QuizSubmission": {
+            "type": "object",
+            "required": ["answers"],
+            "properties": {
+                "answers": {
+                    "type": "object",
+                    "description": "Answers keyed by question ID: a choice index or a number",
+                    "additionalProperties": { "type": "number" },
+                },
+            },
+        },
+        "QuestionResult": {
+            "type": "object",
+            "properties": {
+                "question_id": { "type": "string" },
+                "correct": { "type": "boolean" },
+                "hints_used": { "type": "integer" },
+                "credit": { "type": "number", "description": "Share of the question's point earned, from 0 to 1" },
+                "submitted": { "nullable": true },
+                "expected": { "type": "number", "description": "The answer key; only for a correct answer" },
+                "explanation": { "type": "string", "description": "Only for a correct answer" },
+            },
+        },
+        "QuizResult": {
+            "type": "object",
+            "properties": {
+                "simulation_id": { "type": "string" },
+                "score": {
+                    "type": "number",
+                    "description": "Percentage of the credit earned; each hint revealed takes 0.25 off a correct answer",
+                },
+                "correct": { "type": "integer" },
+                "total": { "type": "integer" },
+                "hints_used": { "type": "integer" },
+                "results": { "type": "array", "items": schema_ref("QuestionResult") },
+            },
+        },
+        "Hint": {
+            "type": "object",
+            "properties": {
+                "question_id": { "type": "string" },
+                "level": { "type": "integer", "description": "Position of the hint on the question's ladder, from 1" },
+                "hint": { "type": "string" },
+                "remaining": { "type": "integer" },
+                "credit": { "type": "number", "description": "Share of the question's point a correct answer now earns" },
+            },
+        },
+        "RevealedHints": {
+            "type": "object",
+            "properties": {
+                "question_id": { "type": "string" },
+                "hints": { "type": "array", "items": { "type": "string" } },
+                "remaining": { "type": "integer" },
+                "credit": { "type": "number" },
+            },
+        },
+        "Recommendations": {
+            "type": "object",
+            "properties": {
+                "strategy": { "type": "string", "enum": ["mastery", "review", "challenge"] },
+                "level": { "type": "string", "enum": ["beginner", "intermediate", "advanced"], "description": "Difficulty the learner is working at" },
+                "mastery_score": { "type": "number", "description": "Best quiz score (percent) that masters a simulation" },
+                "mastered": { "type": "array", "items": { "type": "string" } },
+                "items": { "type": "array", "items": schema_ref("Recommendation") },
+            },
+        },
+        "AchievementSummary": {
+            "type": "object",
+            "properties": {
+                "xp": { "type": "integer", "description": "Points from activity and badges" },
+                "streak": {
+                    "type": "object",
+                    "properties": {
+                        "current": { "type": "integer", "description": "Days in the streak ending today or yesterday" },
+                        "longest": { "type": "integer" },
+                        "last_active": { "type": "string", "format": "date", "nullable": true },
+                    },
+                },
+                "earned": { "type": "integer" },
+                "badges": { "type": "array", "items": schema_ref("BadgeStatus") },
+            },
+        },
+        "BadgeStatus": {
+            "type": "object",
+            "properties": {
+                "id": { "type": "string" },
+                "name": { "type": "string" },
+                "description": { "type": "string" },
+                "icon": { "type": "string" },
+                "xp": { "type": "integer" },
+                "earned_at": { "type": "string", "format": "date-time", "nullable": true },
+                "progress": { "type": "integer", "description": "Simulations the badge's condition has been met on" },
+                "needed": { "type": "integer" },
+            },
+        },
+        "BookmarkRequest": {
+            "type": "object",
+            "properties": {
+                "simulation_id": { "type": "string", "description": "Required unless result_id is given" },
+                "result_id": { "type": "string", "format": "uuid", "description": "A stored result to bookmark rather than the simulation" },
+                "note": { "type": "string", "maxLength": 2000 },
+            },
+        },
+        "Bookmark": {
+            "type": "object",
+            "properties": {
+                "id": { "type": "string", "format": "uuid" },
+                "simulation_id": { "type": "string" },
+                "simulation_name": { "type": "string", "description": "Absent when the simulation is no longer in the catalog" },
+                "result_id": { "type": "string", "format": "uuid", "description": "Absent for a bookmark of the simulation" },
+                "note": { "type": "string" },
+                "created_at": { "type": "string", "format": "date-time" },
+            },
+        },
+        "BookmarkList": {
+            "type": "array",
+            "items": schema_ref("Bookmark"),
+        },
+        "Recommendation": {
+            "type": "object",
+            "properties": {
+                "simulation_id": { "type": "string" },
+                "name": { "type": "string" },
+                "difficulty": { "type": "string" },
+                "reason": { "type": "string" },
+                "quiz_score": { "type": "number", "nullable": true, "description": "Best quiz score so far" },
+            },
+        },
+        "RevealedHintsList": {
+            "type": "array",
+            "items": schema_ref("RevealedHints"),
+        },
+        "ParameterError": {
+            "type": "object",
+            "properties": {
+                "name": { "type": "string" },
+                "message": { "type": "string" },
+                "value": {},
+            },
+        },
+        "Problem": {
+            "type": "object",
+            "description": "RFC 7807 problem details, returned by the simulation, result, quiz and job routes",
+            "properties": {
+                "type": { "type": "string", "format": "uri", "description": "Documentation of the error code" },
+                "title": { "type": "string" },
+                "status": { "type": "integer" },
+                "detail": { "type": "string" },
+                "code": {
+                    "type": "string",
+                    "enum": ["not_found", "invalid_parameters", "unsupported", "conflict", "busy", "shutting_down", "rate_limited", "budget_exceeded", "storage_error", "internal_error"],
+                },
+                "parameter": { "type": "string", "description": "First offending parameter, for invalid_parameters" },
+                "fields": { "type": "array", "items": schema_ref("ParameterError") },
+                "request_id": { "type": "string", "description": "Same as the X-Request-Id response header" },
+            },
+        },
+        "CustomSimulationRequest": {
+            "type": "object",
+            "required": ["name", "script", "domain", "output"],
+            "properties": {
+                "name": { "type": "string" },
+                "description": { "type": "string" },
+                "parameters": {
+                    "type": "array",
+                    "items": schema_ref("ParameterDefinition"),
+                    "maxItems": 20,
+                    "description": "Sliders the script reads by name; only sliders are allowed",
+                },
+                "script": {
+                    "type": "string",
+                    "maxLength": 4000,
+                    "description": "Lines `name = expression` naming intermediate quantities, then one expression giving f; `;` also separates statements and `#` starts a comment. Expressions use the language of the statistics endpoint's `propagate`, reading the sliders, `x` and earlier names",
+                },
+                "domain": schema_ref("CustomDomain"),
+                "output": schema_ref("CustomOutput"),
+            },
+        },
+        "CustomDomain": {
+            "type": "object",
+            "required": ["label", "min", "max", "points"],
+            "properties": {
+                "label": { "type": "string" },
+                "unit": { "type": "string", "description": "Symbol from `/api/v1/units`" },
+                "min": { "type": "number" },
+                "max": { "type": "number" },
+                "points": { "type": "integer", "minimum": 2, "maximum": 10000, "description": "Evenly spaced values of x from min to max" },
+            },
+        },
+        "CustomOutput": {
+            "type": "object",
+            "required": ["label"],
+            "properties": {
+                "label": { "type": "string" },
+                "unit": { "type": "string", "description": "Symbol from `/api/v1/units`" },
+            },
+        },
+        "CustomSimulation": {
+            "type": "object",
+            "properties": {
+                "id": { "type": "string", "format": "uuid" },
+                "owner_id": { "type": "string", "format": "uuid" },
+                "name": { "type": "string" },
+                "description": { "type": "string" },
+                "parameters": { "type": "array", "items": schema_ref("ParameterDefinition") },
+                "script": { "type": "string" },
+                "domain": schema_ref("CustomDomain"),
+                "output": schema_ref("CustomOutput"),
+                "created_at": { "type": "string", "format": "date-time" },
+                "updated_at": { "type": "string", "format": "date-time" },
+            },
+        },
+        "CustomSimulationList": {
+            "type": "array",
+            "items": schema_ref("CustomSimulation"),
+        },
+        "CustomRunRequest": {
+            "type": "object",
+            "properties": {
+                "parameters": {
+                    "type": "object",
+                    "additionalProperties": true,
+                    "description": "Slider values by name, in the slider's unit or another of the same dimension; those left out take their defaults",
+                },
+            },
+        },
+        "CustomRun": {
+            "type": "object",
+            "properties": {
+                "simulation_id": { "type": "string", "format": "uuid" },
+                "parameters": { "type": "object", "additionalProperties": true, "description": "Slider values used, defaults included" },
+                "x": { "type": "array", "items": { "type": "number" } },
+                "y": { "type": "array", "items": { "type": "number", "nullable": true }, "description": "f at each x; null where it is not a finite number" },
+                "undefined": { "type": "integer", "description": "Points where f is not a finite number" },
+                "computed_at": { "type": "string", "format": "date-time" },
+            },
+        },
+        "ContributionRequest": {
+            "type": "object",
+            "required": ["name", "script", "domain", "output", "summary", "difficulty", "theory"],
+            "description": "The fields of CustomSimulationRequest, with the metadata and theory of a catalog entry",
+            "properties": {
+                "name": { "type": "string" },
+                "description": { "type": "string" },
+                "parameters": { "type": "array", "items": schema_ref("ParameterDefinition"), "maxItems": 20 },
+                "script": { "type": "string", "maxLength": 4000 },
+                "domain": schema_ref("CustomDomain"),
+                "output": schema_ref("CustomOutput"),
+                "summary": { "type": "string", "maxLength": 300 },
+                "difficulty": { "type": "string", "enum": ["beginner", "intermediate", "advanced"] },
+                "topics": { "type": "array", "items": { "type": "string", "maxLength": 100 }, "maxItems": 10 },
+                "theory": { "type": "string", "maxLength": 20000, "description": "Markdown with LaTeX math" },
+            },
+        },
+        "Contribution": {
+            "type": "object",
+            "properties": {
+                "id": { "type": "string", "format": "uuid" },
+                "author_id": { "type": "string", "format": "uuid" },
+                "status": { "type": "string", "enum": ["pending", "approved", "rejected", "published"] },
+                "name": { "type": "string" },
+                "summary": { "type": "string" },
+                "description": { "type": "string" },
+                "difficulty": { "type": "string" },
+                "topics": { "type": "array", "items": { "type": "string" } },
+                "theory": { "type": "string" },
+                "parameters": { "type": "array", "items": schema_ref("ParameterDefinition") },
+                "script": { "type": "string" },
+                "domain": schema_ref("CustomDomain"),
+                "output": schema_ref("CustomOutput"),
+                "created_at": { "type": "string", "format": "date-time" },
+                "updated_at": { "type": "string", "format": "date-time" },
+                "published_at": { "type": "string", "format": "date-time", "nullable": true },
+                "reviews": {
+                    "type": "array",
+                    "items": schema_ref("ContributionReview"),
+                    "description": "Review decisions, oldest first; absent from the library and the review queue",
+                },
+            },
+        },
+        "ContributionList": {
+            "type": "array",
+            "items": schema_ref("Contribution"),
+        },
+        "ContributionReview": {
+            "type": "object",
+            "properties": {
+                "id": { "type": "string", "format": "uuid" },
+                "reviewer_id": { "type": "string", "format": "uuid" },
+                "decision": { "type": "string", "enum": ["approved", "rejected"] },
+                "comment": { "type": "string" },
+                "created_at": { "type": "string", "format": "date-time" },
+            },
+        },
+        "ReviewRequest": {
+            "type": "object",
+            "required": ["decision"],
+            "properties": {
+                "decision": { "type": "string", "enum": ["approve", "reject"] },
+                "comment": { "type": "string", "maxLength": 2000, "description": "Required when rejecting" },
+            },
+        },
+        "Course": {
+            "type": "object",
+            "properties": {
+                "id": { "type": "string", "format": "uuid" },
+                "owner_id": { "type": "string", "format": "uuid" },
+                "title": { "type": "string" },
+                "description": { "type": "string" },
+                "published": { "type": "boolean" },
+                "created_at": { "type": "string", "format": "date-time" },
+                "updated_at": { "type": "string", "format": "date-time" },
+            },
+        },
+        "CourseList": {
+            "type": "array",
+            "items": schema_ref("Course"),
+        },
+        "CourseRequest": {
+            "type": "object",
+            "required": ["title"],
+            "properties": {
+                "title": { "type": "string", "maxLength": 200 },
+                "description": { "type": "string" },
+                "published": { "type": "boolean", "default": false },
+            },
+        },
+        "CourseModule": {
+            "type": "object",
+            "properties": {
+                "id": { "type": "string", "format": "uuid" },
+                "course_id": { "type": "string", "format": "uuid" },
+                "position": { "type": "integer", "description": "Place within the course, from 0" },
+                "title": { "type": "string" },
+                "description": { "type": "string" },
+            },
+        },
+        "ModuleRequest": {
+            "type": "object",
+            "required": ["title"],
+            "properties": {
+                "title": { "type": "string", "maxLength": 200 },
+                "description": { "type": "string" },
+                "position": { "type": "integer", "description": "Place within the course, from 0; past the end means last" },
+            },
+        },
+        "LessonItem": {
+            "type": "object",
+            "required": ["type"],
+            "description": "`text` carries `body`; `simulation`, `theory` and `quiz` refer to a simulation by `simulation_id`",
+            "properties": {
+                "type": { "type": "string", "enum": ["text", "simulation", "theory", "quiz"] },
+                "body": { "type": "string" },
+                "simulation_id": { "type": "string" },
+                "parameters": { "type": "object", "description": "Starting parameters for a `simulation` item" },
+            },
+        },
+        "Lesson": {
+            "type": "object",
+            "properties": {
+                "id": { "type": "string", "format": "uuid" },
+                "module_id": { "type": "string", "format": "uuid" },
+                "position": { "type": "integer", "description": "Place within the module, from 0" },
+                "title": { "type": "string" },
+                "items": { "type": "array", "items": schema_ref("LessonItem") },
+            },
+        },
+        "LessonRequest": {
+            "type": "object",
+            "required": ["title"],
+            "properties": {
+                "title": { "type": "string", "maxLength": 200 },
+                "items": { "type": "array", "items": schema_ref("LessonItem") },
+                "position": { "type": "integer", "description": "Place within the module, from 0; past the end means last" },
+            },
+        },
+        "CourseView": {
+            "type": "object",
+            "description": "A course with `modules`, each with its `lessons`, in order",
+            "properties": {
+                "id": { "type": "string", "format": "uuid" },
+                "owner_id": { "type": "string", "format": "uuid" },
+                "title": { "type": "string" },
+                "description": { "type": "string" },
+                "published": { "type": "boolean" },
+                "created_at": { "type": "string", "format": "date-time" },
+                "updated_at": { "type": "string", "format": "date-time" },
+                "modules": {
+                    "type": "array",
+                    "items": {
+                        "allOf": [
+                            schema_ref("CourseModule"),
+                            { "type": "object", "properties": { "lessons": { "type": "array", "items": schema_ref("Lesson") } } },
+                        ],
+                    },
+                },
+            },
+        },
+        "Class": {
+            "type": "object",
+            "properties": {
+                "id": { "type": "string", "format": "uuid" },
+                "teacher_id": { "type": "string", "format": "uuid" },
+                "name": { "type": "string" },
+                "join_code": { "type": "string", "description": "Teacher only" },
+                "created_at": { "type": "string", "format": "date-time" },
+                "role": { "type": "string", "enum": ["teacher", "student"] },
+                "student_count": { "type": "integer" },
+                "students": { "type": "array", "items": schema_ref("ClassMember"), "description": "Teacher only" },
+            },
+        },
+        "ClassList": {
+            "type": "array",
+            "items": schema_ref("Class"),
+        },
+        "ClassMember": {
+            "type": "object",
+            "properties": {
+                "user_id": { "type": "string", "format": "uuid" },
+                "display_name": { "type": "string" },
+                "email": { "type": "string", "format": "email" },
+                "joined_at": { "type": "string", "format": "date-time" },
+            },
+        },
+        "CreateClassRequest": {
+            "type": "object",
+            "required": ["name"],
+            "properties": {
+                "name": { "type": "string", "maxLength": 200 },
+            },
+        },
+        "JoinClassRequest": {
+            "type": "object",
+            "required": ["join_code"],
+            "properties": {
+                "join_code": { "type": "string", "description": "Case-insensitive" },
+            },
+        },
+        "LeaderboardRequest": {
+            "type": "object",
+            "required": ["enabled"],
+            "properties": {
+                "enabled": { "type": "boolean", "description": "Whether students can see the leaderboard" },
+                "anonymize": { "type": "boolean", "description": "Show students to each other under pseudonyms; unchanged when left out" },
+                "rank_by": { "type": "string", "enum": ["xp", "completions"], "description": "Unchanged when left out" },
+            },
+        },
+        "Leaderboard": {
+            "type": "object",
+            "properties": {
+                "class_id": { "type": "string", "format": "uuid" },
+                "settings": {
+                    "type": "object",
+                    "properties": {
+                        "enabled": { "type": "boolean" },
+                        "anonymize": { "type": "boolean" },
+                        "rank_by": { "type": "string", "enum": ["xp", "completions"] },
+                        "updated_at": { "type": "string", "format": "date-time", "nullable": true },
+                    },
+                },
+                "opted_in": { "type": "boolean", "description": "Whether the signed-in student is on the leaderboard; absent for the teacher" },
+                "participants": { "type": "integer", "description": "Students who opted in" },
+                "student_count": { "type": "integer" },
+                "entries": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "rank": { "type": "integer", "description": "From 1; tied students share a rank" },
+                            "name": { "type": "string", "description": "Pseudonym when anonymized, else display name" },
+                            "you": { "type": "boolean" },
+                            "xp": { "type": "integer" },
+                            "completions": { "type": "integer" },
+                            "user_id": { "type": "string", "format": "uuid", "description": "Teacher only" },
+                            "display_name": { "type": "string", "description": "Real name behind a pseudonym; teacher only" },
+                        },
+                    },
+                },
+            },
+        },
+        "ClassProgress": {
+            "type": "object",
+            "properties": {
+                "class_id": { "type": "string", "format": "uuid" },
+                "name": { "type": "string" },
+                "students": {
+                    "type": "array",
+                    "items": {
+                        "allOf": [
+                            schema_ref("ClassMember"),
+                            {
+                                "type": "object",
+                                "properties": {
+                                    "completed_simulations": { "type": "integer" },
+                                    "total_time_minutes": { "type": "integer" },
+                                    "average_quiz_score": { "type": "number", "nullable": true, "description": "Mean of the best score on each quiz taken" },
+                                    "simulations": { "type": "array", "items": schema_ref("StudentSimulation") },
+                                },
+                            },
+                        ],
+                    },
+                },
+                "simulations": { "type": "array", "items": schema_ref("SimulationTotals") },
+            },
+        },
+        "StudentSimulation": {
+            "type": "object",
+            "properties": {
+                "simulation_id": { "type": "string" },
+                "completed": { "type": "boolean" },
+                "completed_at": { "type": "string", "format": "date-time", "nullable": true },
+                "score": { "type": "number", "nullable": true },
+                "time_spent_minutes": { "type": "integer" },
+                "best_quiz_score": { "type": "number", "nullable": true },
+                "quiz_attempts": { "type": "integer" },
+                "last_quiz_at": { "type": "string", "format": "date-time", "nullable": true },
+            },
+        },
+        "SimulationTotals": {
+            "type": "object",
+            "properties": {
+                "simulation_id": { "type": "string" },
+                "started": { "type": "integer", "description": "Students with any progress or quiz attempt" },
+                "completed": { "type": "integer" },
+                "quiz_takers": { "type": "integer" },
+                "average_best_quiz_score": { "type": "number", "nullable": true },
+            },
+        },
+        "Exploration": {
+            "type": "object",
+            "properties": {
+                "id": { "type": "string" },
+                "description": { "type": "string" },
+                "parameters": { "type": "object", "description": "Values a handed-in result must have been run with" },
+            },
+        },
+        "AssignmentQuestion": {
+            "type": "object",
+            "properties": {
+                "id": { "type": "string" },
+                "prompt": { "type": "string" },
+            },
+        },
+        "Assignment": {
+            "type": "object",
+            "properties": {
+                "id": { "type": "string", "format": "uuid" },
+                "class_id": { "type": "string", "format": "uuid" },
+                "simulation_id": { "type": "string" },
+                "title": { "type": "string" },
+                "instructions": { "type": "string" },
+                "explorations": { "type": "array", "items": schema_ref("Exploration") },
+                "questions": { "type": "array", "items": schema_ref("AssignmentQuestion") },
+                "due_at": { "type": "string", "format": "date-time", "nullable": true },
+                "created_at": { "type": "string", "format": "date-time" },
+                "updated_at": { "type": "string", "format": "date-time" },
+            },
+        },
+        "AssignmentList": {
+            "type": "array",
+            "items": schema_ref("Assignment"),
+        },
+        "AssignmentRequest": {
+            "type": "object",
+            "required": ["simulation_id", "title"],
+            "properties": {
+                "simulation_id": { "type": "string" },
+                "title": { "type": "string", "maxLength": 200 },
+                "instructions": { "type": "string" },
+                "explorations": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["parameters"],
+                        "properties": {
+                            "id": { "type": "string", "description": "Defaults to exploration-<n>" },
+                            "description": { "type": "string" },
+                            "parameters": { "type": "object" },
+                        },
+                    },
+                },
+                "questions": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["prompt"],
+                        "properties": {
+                            "id": { "type": "string", "description": "Defaults to q<n>" },
+                            "prompt": { "type": "string" },
+                        },
+                    },
+                },
+                "due_at": { "type": "string", "format": "date-time" },
+            },
+        },
+        "Submission": {
+            "type": "object",
+            "properties": {
+                "id": { "type": "string", "format": "uuid" },
+                "assignment_id": { "type": "string", "format": "uuid" },
+                "student_id": { "type": "string", "format": "uuid" },
+                "result_ids": { "type": "array", "items": { "type": "string" } },
+                "answers": { "type": "object", "additionalProperties": { "type": "string" } },
+                "explorations_completed": { "type": "array", "items": { "type": "string" } },
+                "submitted_at": { "type": "string", "format": "date-time" },
+                "late": { "type": "boolean" },
+                "grade": { "type": "number", "nullable": true },
+                "feedback": { "type": "string", "nullable": true },
+                "graded_at": { "type": "string", "format": "date-time", "nullable": true },
+            },
+        },
+        "SubmitAssignmentRequest": {
+            "type": "object",
+            "properties": {
+                "result_ids": { "type": "array", "items": { "type": "string" }, "description": "Stored results of the assignment's simulation" },
+                "answers": { "type": "object", "additionalProperties": { "type": "string" }, "description": "Keyed by question ID" },
+            },
+        },
+        "AssignmentSubmissions": {
+            "type": "object",
+            "properties": {
+                "assignment_id": { "type": "string", "format": "uuid" },
+                "submitted": { "type": "integer" },
+                "graded": { "type": "integer" },
+                "students": {
+                    "type": "array",
+                    "items": {
+                        "allOf": [
+                            schema_ref("ClassMember"),
+                            { "type": "object", "properties": { "submission": schema_ref("Submission") } },
+                        ],
+                    },
+                },
+            },
+        },
+        "GradeRequest": {
+            "type": "object",
+            "required": ["grade"],
+            "properties": {
+                "grade": { "type": "number", "minimum": 0, "maximum": 100 },
+                "feedback": { "type": "string" },
+            },
+        },
+        "Jwks": {
+            "type": "object",
+            "properties": {
+                "keys": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "kty": { "type": "string", "enum": ["RSA"] },
+                            "alg": { "type": "string", "enum": ["RS256"] },
+                            "use": { "type": "string", "enum": ["sig"] },
+                            "kid": { "type": "string" },
+                            "n": { "type": "string", "description": "Modulus, base64url" },
+                            "e": { "type": "string", "description": "Exponent, base64url" },
+                        },
+                    },
+                },
+            },
+        },
+        "TranslationRequest": {
+            "type": "object",
+            "description": "Fields left out fall back to the next language in the chain",
+            "properties": {
+                "name": { "type": "string" },
+                "description": { "type": "string" },
+                "theory": { "type": "string", "description": "Markdown with LaTeX math" },
+                "parameter_labels": { "type": "object", "additionalProperties": { "type": "string" }, "description": "Labels keyed by parameter name" },
+            },
+        },
+        "Translation": {
+            "allOf": [
+                schema_ref("TranslationRequest"),
+                {
+                    "type": "object",
+                    "properties": {
+                        "simulation_id": { "type": "string" },
+                        "locale": { "type": "string", "description": "Lowercase BCP 47 tag, e.g. pt-br" },
+                        "updated_at": { "type": "string", "format": "date-time" },
+                    },
+                },
+            ],
+        },
+        "TranslationList": { "type": "array", "items": schema_ref("Translation") },
+        "ParameterDefinition": {
+            "type": "object",
+            "required": ["name", "label", "type", "default"],
+            "properties": {
+                "name": { "type": "string" },
+                "label": { "type": "string" },
+                "type": { "type": "string", "enum": ["slider", "toggle", "select", "curve", "formula"] },
+                "min": { "type": "number" },
+                "max": { "type": "number" },
+                "default": { "description": "A number for sliders, a boolean for toggles, one of `options` for selects, an array of 2 to 200 numbers for curves, the text of a formula" },
+                "step": { "type": "number" },
+                "options": { "type": "array", "items": { "type": "string" } },
+                "unit": { "type": "string", "description": "Symbol from `/api/v1/units` of the unit the simulation computes a slider, curve or formula in" },
+                "variables": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Variables a formula may use besides `pi` and `e`; only formulas take them",
+                },
+            },
+        },
+        "SimulationContent": {
+            "type": "object",
+            "required": ["name", "summary", "description", "difficulty", "estimated_time_minutes", "theory"],
+            "properties": {
+                "id": { "type": "string", "description": "Taken from the URL when left out" },
+                "name": { "type": "string" },
+                "summary": { "type": "string", "description": "One line shown in listings" },
+                "description": { "type": "string", "description": "Introduction shown above the controls" },
+                "difficulty": { "type": "string", "enum": ["beginner", "intermediate", "advanced"] },
+                "estimated_time_minutes": { "type": "integer" },
+                "topics": { "type": "array", "items": { "type": "string" } },
+                "parameters": {
+                    "type": "array",
+                    "items": schema_ref("ParameterDefinition"),
+                    "description": "Names, types and options must match the simulation code; labels, ranges and defaults may change",
+                },
+                "theory": { "type": "string", "description": "Markdown with LaTeX math" },
+                "references": {
+                    "type": "array",
+                    "items": schema_ref("Reference"),
+                    "description": "Works the physics comes from, listed in citations",
+                },
+            },
+        },
+        "Reference": {
+            "type": "object",
+            "required": ["type", "authors", "title", "year"],
+            "properties": {
+                "type": { "type": "string", "enum": ["article", "book"] },
+                "authors": { "type": "array", "items": { "type": "string" }, "description": "\"Family, Given\"" },
+                "title": { "type": "string" },
+                "year": { "type": "integer" },
+                "journal": { "type": "string", "description": "Required for articles" },
+                "publisher": { "type": "string", "description": "Required for books" },
+                "edition": { "type": "string" },
+                "volume": { "type": "string" },
+                "issue": { "type": "string" },
+                "pages": { "type": "string", "description": "Page range, e.g. 1-16" },
+                "doi": { "type": "string" },
+                "url": { "type": "string", "format": "uri" },
+            },
+        },
+        "ContentRevision": {
+            "type": "object",
+            "properties": {
+                "content": schema_ref("SimulationContent"),
+                "updated_by": { "type": "string", "format": "uuid" },
+                "updated_at": { "type": "string", "format": "date-time" },
+            },
+        },
+        "ContentSummary": {
+            "type": "object",
+            "properties": {
+                "id": { "type": "string" },
+                "name": { "type": "string" },
+                "source": { "type": "string", "enum": ["file", "published"] },
+                "retired": { "type": "boolean" },
+                "draft_updated_at": { "type": "string", "format": "date-time", "nullable": true },
+            },
+        },
+        "ContentSummaryList": { "type": "array", "items": schema_ref("ContentSummary") },
+        "ContentEntry": {
+            "type": "object",
+            "properties": {
+                "id": { "type": "string" },
+                "source": { "type": "string", "enum": ["file", "published"] },
+                "retired": { "type": "boolean" },
+                "content": schema_ref("SimulationContent"),
+                "draft": { "allOf": [schema_ref("ContentRevision")], "nullable": true },
+            },
+        },
+        "ContentPreview": {
+            "type": "object",
+            "properties": {
+                "listing": schema_ref("SimulationInfo"),
+                "details": schema_ref("SimulationDetails"),
+            },
+        },
+        "CatalogReload": {
+            "type": "object",
+            "properties": {
+                "simulations": { "type": "integer", "description": "Simulations whose content was loaded" },
+                "directory": { "type": "string" },
+            },
+        },
+        "User": {
+            "type": "object",
+            "properties": {
+                "id": { "type": "string", "format": "uuid" },
+                "email": { "type": "string", "format": "email" },
+                "display_name": { "type": "string" },
+                "created_at": { "type": "string", "format": "date-time" },
+            },
+        },
+        "RegisterRequest": {
+            "type": "object",
+            "required": ["email", "password", "display_name"],
+            "properties": {
+                "email": { "type": "string", "format": "email" },
+                "password": { "type": "string", "minLength": 8 },
+                "display_name": { "type": "string" },
+            },
+        },
+        "LoginRequest": {
+            "type": "object",
+            "required": ["email", "password"],
+            "properties": {
+                "email": { "type": "string", "format": "email" },
+                "password": { "type": "string" },
+            },
+        },
+        "RefreshRequest": {
+            "type": "object",
+            "required": ["refresh_token"],
+            "properties": {
+                "refresh_token": { "type": "string" },
+            },
+        },
+        "TokenResponse": {
+            "type": "object",
+            "properties": {
+                "access_token": { "type": "string" },
+                "refresh_token": { "type": "string" },
+                "token_type": { "type": "string", "enum": ["Bearer"] },
+                "expires_in": { "type": "integer", "description": "Access token lifetime in seconds" },
+                "user": schema_ref("User"),
+            },
+        },
+        "AuthErrorBody": {
+            "type": "object",
+            "properties": {
+                "error": { "type": "string" },
+                "message": { "type": "string" },
+                "request_id": { "type": "string", "description": "Same as the X-Request-Id response header" },
+            },
+        },
+        "AskQuestionRequest": {
+            "type": "object",
+            "required": ["question"],
+            "properties": {
+                "question": { "type": "string" },
+                "context": { "type": "string", "nullable": true },
+            },
+        },
+        "TutorChatRequest": {
+            "type": "object",
+            "required": ["simulation_id", "messages"],
+            "properties": {
+                "simulation_id": { "type": "string", "description": "Simulation the student has open" },
+                "messages": {
+                    "type": "array",
+                    "description": "The conversation so far, oldest first, 1 to 20 messages of up to 4000 characters ending with the student's question",
+                    "items": {
+                        "type": "object",
+                        "required": ["role", "content"],
+                        "properties": {
+                            "role": { "type": "string", "enum": ["user", "assistant"] },
+                            "content": { "type": "string" },
+                        },
+                    },
+                },
+                "parameters": { "type": "object", "nullable": true, "description": "Parameter values the student has set; defaults fill in the rest, and the result's are used when left out" },
+                "result_id": { "type": "string", "nullable": true, "description": "Stored result of this simulation the student last computed, summarized for the tutor" },
+            },
+        },
+        "SuggestedExperiment": {
+            "type": "object",
+            "properties": {
+                "simulation_id": { "type": "string" },
+                "title": { "type": "string" },
+                "description": { "type": "string" },
+            },
+        },
+        "AskQuestionResponse": {
+            "type": "object",
+            "properties": {
+                "answer": { "type": "string" },
+                "related_topics": { "type": "array", "items": { "type": "string" } },
+                "suggested_experiments": { "type": "array", "items": schema_ref("SuggestedExperiment") },
+            },
+        },
+        "CompletedSimulation": {
+            "type": "object",
+            "properties": {
+                "simulation_id": { "type": "string" },
+                "completed_at": { "type": "string", "format": "date-time" },
+                "score": { "type": "number", "nullable": true },
+                "time_spent_minutes": { "type": "integer" },
+            },
+        },
+        "CurrentSimulation": {
+            "type": "object",
+            "properties": {
+                "simulation_id": { "type": "string" },
+                "started_at": { "type": "string", "format": "date-time" },
+                "last_parameters": { "type": "object", "nullable": true },
+            },
+        },
+        "Achievement": {
+            "type": "object",
+            "properties": {
+                "id": { "type": "string" },
+                "name": { "type": "string" },
+                "description": { "type": "string" },
+                "icon": { "type": "string" },
+                "earned_at": { "type": "string", "format": "date-time" },
+            },
+        },
+        "UserProgress": {
+            "type": "object",
+            "properties": {
+                "user_id": { "type": "string" },
+                "completed_simulations": { "type": "array", "items": schema_ref("CompletedSimulation") },
+                "current_simulation": { "allOf": [schema_ref("CurrentSimulation")], "nullable": true },
+                "total_time_minutes": { "type": "integer" },
+                "achievements": { "type": "array", "items": schema_ref("Achievement") },
+                "last_activity": { "type": "string", "format": "date-time" },
+            },
+        },
+        "SaveProgressRequest": {
+            "type": "object",
+            "required": ["completed", "time_spent_minutes"],
+            "properties": {
+                "completed": { "type": "boolean" },
+                "score": { "type": "number", "nullable": true },
+                "time_spent_minutes": { "type": "integer" },
+                "parameters": { "type": "object", "nullable": true },
+            },
+        },
+    })
+}
@@ -1,93 +1,123 @@
-use axum::{http::StatusCode, Json};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 
-/// Get user progress (mock implementation for MVP)
-pub async fn get_progress() -> Json<UserProgress> {
-    // TODO: Get from database based on authenticated user
-    Json(UserProgress {
-        user_id: "demo-user".to_string(),
-        completed_simulations: vec![],
-        current_simulation: None,
-        total_time_minutes: 0,
-        achievements: vec![],
-        last_activity: Utc::now(),
-    })
+use crate::models::progress::SimulationProgress;
+use crate::routes::auth::AuthUser;
+use crate::state::AppState;
+
+/// Get the signed-in user's progress across all simulations
+pub async fn get_progress(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> Result<Json<UserProgress>, StatusCode> {
+    load_progress(&state, &user.id).await.map(Json)
 }
 
-/// Save user progress
+/// Record progress on one simulation and return the updated summary
 pub async fn save_progress(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(simulation_id): Path<String>,
     Json(request): Json<SaveProgressRequest>,
 ) -> Result<Json<UserProgress>, StatusCode> {
-    // TODO: Save to database
+    if state.simulations.get(&simulation_id).is_none() {
+        return Err(StatusCode::NOT_FOUND);
+    }
     tracing::info!(
-        "Saving progress: simulation={}, completed={}",
-        request.simulation_id,
+        "Saving progress: user={}, simulation={}, completed={}",
+        user.id,
+        simulation_id,
         request.completed
     );
-    
-    // Check achievements BEFORE moving request fields
-    let achievements = check_achievements(&request);
-    
-    // Clone parameters before moving
-    let parameters = request.parameters.clone();
-    
-    let progress = UserProgress {
-        user_id: "demo-user".to_string(),
-        completed_simulations: if request.completed {
-            vec![CompletedSimulation {
-                simulation_id: request.simulation_id.clone(),
-                completed_at: Utc::now(),
-                score: request.score,
-                time_spent_minutes: request.time_spent_minutes,
-            }]
-        } else {
-            vec![]
-        },
-        current_simulation: if !request.completed {
-            Some(CurrentSimulation {
-                simulation_id: request.simulation_id,
-                started_at: Utc::now(),
-                last_parameters: parameters,
-            })
-        } else {
-            None
-        },
-        total_time_minutes: request.time_spent_minutes,
-        achievements,
-        last_activity: Utc::now(),
+
+    let now = Utc::now();
+    let record = SimulationProgress {
+        user_id: user.id.clone(),
+        simulation_id,
+        completed: request.completed,
+        score: request.score,
+        time_spent_minutes: request.time_spent_minutes,
+        last_parameters: request.parameters,
+        started_at: now,
+        completed_at: request.completed.then_some(now),
+        updated_at: now,
     };
-    
-    Ok(Json(progress))
+    record.record(&state.db).await.map_err(|e| {
+        tracing::error!("Failed to save progress: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    load_progress(&state, &user.id).await.map(Json)
+}
+
+async fn load_progress(state: &AppState, user_id: &str) -> Result<UserProgress, StatusCode> {
+    let records = SimulationProgress::for_user(&state.db, user_id).await.map_err(|e| {
+        tracing::error!("Failed to load progress for {}: {}", user_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let mut completed_simulations: Vec<CompletedSimulation> = records
+        .iter()
+        .filter_map(|r| {
+            Some(CompletedSimulation {
+                simulation_id: r.simulation_id.clone(),
+                completed_at: r.completed_at.filter(|_| r.completed)?,
+                score: r.score,
+                time_spent_minutes: r.time_spent_minutes,
+            })
+        })
+        .collect();
+    completed_simulations.sort_by_key(|c| c.completed_at);
+
+    // Records are ordered most recent first
+    let current_simulation = records.iter().find(|r| !r.completed).map(|r| CurrentSimulation {
+        simulation_id: r.simulation_id.clone(),
+        started_at: r.started_at,
+        last_parameters: r.last_parameters.clone(),
+    });
+
+    Ok(UserProgress {
+        user_id: user_id.to_string(),
+        achievements: check_achievements(&completed_simulations),
+        completed_simulations,
+        current_simulation,
+        total_time_minutes: records.iter().map(|r| r.time_spent_minutes).sum(),
+        last_activity: records.first().map(|r| r.updated_at).unwrap_or_else(Utc::now),
+    })
 }
 
-fn check_achievements(request: &SaveProgressRequest) -> Vec<Achievement> {
+/// Achievements are derived from stored progress, so they are earned once
+/// and keep the time of the completion that earned them
+fn check_achievements(completed: &[CompletedSimulation]) -> Vec<Achievement> {
     let mut achievements = vec![];
-    
+
     // First simulation completed
-    if request.completed {
+    if let Some(first) = completed.first() {
         achievements.push(Achievement {
             id: "first-experiment".to_string(),
             name: "First Experiment".to_string(),
             description: "Completed your first quantum physics simulation".to_string(),
             icon: "🔬".to_string(),
-            earned_at: Utc::now(),
+            earned_at: first.completed_at,
         });
     }
-    
+
     // High score
-    if let Some(score) = request.score {
-        if score >= 90.0 {
-            achievements.push(Achievement {
-                id: "quantum-master".to_string(),
-                name: "Quantum Master".to_string(),
-                description: "Achieved a score of 90% or higher".to_string(),
-                icon: "🏆".to_string(),
-                earned_at: Utc::now(),
-            });
-        }
+    if let Some(high) = completed.iter().find(|c| c.score.is_some_and(|s| s >= 90.0)) {
+        achievements.push(Achievement {
+            id: "quantum-master".to_string(),
+            name: "Quantum Master".to_string(),
+            description: "Achieved a score of 90% or higher".to_string(),
+            icon: "🏆".to_string(),
+            earned_at: high.completed_at,
+        });
     }
-    
+
     achievements
 }
 
@@ -127,7 +157,6 @@ pub struct Achievement {
 
 #[derive(Deserialize)]
 pub struct SaveProgressRequest {
-    pub simulation_id: String,
     pub completed: bool,
     pub score: Option<f64>,
     pub time_spent_minutes: u32,
@@ -0,0 +1,152 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+
+use std::collections::HashMap;
+
+use chrono::Utc;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::models::progress::{HintUsage, QuizAttempt};
+use crate::models::quiz::{credit, Quiz, QuizResult, Submission};
+use crate::routes::achievements::emit;
+use crate::routes::auth::AuthUser;
+use crate::routes::error::ApiError;
+use crate::services::achievements::Event;
+use crate::simulations::params::ParameterError;
+use crate::state::AppState;
+
+/// Get the quiz for a simulation, without its answer key
+pub async fn get_quiz(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<Quiz>, ApiError> {
+    let simulation = state.simulations.get(&id).ok_or_else(|| ApiError::not_found("simulation", &id))?;
+    simulation.quiz().map(Json).ok_or_else(|| ApiError::not_found("quiz", &id))
+}
+
+/// Grade the signed-in learner's answers to a simulation's quiz. Answers
+/// earn less for the hints the learner revealed, and every attempt is
+/// recorded for their teachers and, for learners who launched the
+/// simulation from an LMS, reported to its gradebook. Only correct answers
+/// come back with the answer key.
+pub async fn submit_quiz(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(id): Path<String>,
+    Json(submission): Json<Submission>,
+) -> Result<Json<QuizResult>, ApiError> {
+    let simulation = state.simulations.get(&id).ok_or_else(|| ApiError::not_found("simulation", &id))?;
+    let quiz = simulation.quiz().ok_or_else(|| ApiError::not_found("quiz", &id))?;
+    let hints: HashMap<String, u32> = HintUsage::for_quiz(&state.db, &user.id, &id)
+        .await?
+        .into_iter()
+        .map(|usage| (usage.question_id, usage.revealed))
+        .collect();
+    let result = quiz.grade(&submission, &hints).map_err(|unknown| {
+        ApiError::InvalidParameters(
+            unknown
+                .into_iter()
+                .map(|question_id| ParameterError::new(&question_id, "not a question in this quiz", None))
+                .collect(),
+        )
+    })?;
+
+    let attempt = QuizAttempt {
+        id: Uuid::new_v4().to_string(),
+        user_id: user.id,
+        simulation_id: result.simulation_id.clone(),
+        score: result.score,
+        correct: result.correct as u32,
+        total: result.total as u32,
+        submitted_at: Utc::now(),
+    };
+    attempt.insert(&state.db).await?;
+    emit(&state, attempt.user_id.clone(), Event::quiz(&result));
+    if state.lti.is_enabled() {
+        let (lti, db) = (state.lti.clone(), state.db.clone());
+        tokio::spawn(async move {
+            lti.pass_back(&db, &attempt.user_id, &attempt.simulation_id, attempt.score).await;
+        });
+    }
+    Ok(Json(result))
+}
+
+/// Reveal the signed-in learner's next hint on a quiz question. Each hint
+/// gives away more than the last and costs `HINT_PENALTY` of the credit for
+/// a correct answer; asking again at the top of the ladder repeats the last
+/// hint at no further cost.
+pub async fn reveal_hint(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path((id, question_id)): Path<(String, String)>,
+) -> Result<Json<Hint>, ApiError> {
+    let simulation = state.simulations.get(&id).ok_or_else(|| ApiError::not_found("simulation", &id))?;
+    let quiz = simulation.quiz().ok_or_else(|| ApiError::not_found("quiz", &id))?;
+    let question = quiz
+        .questions
+        .iter()
+        .find(|question| question.id == question_id)
+        .ok_or_else(|| ApiError::not_found("question", &question_id))?;
+    if question.hints.is_empty() {
+        return Err(ApiError::Unsupported("this question has no hints"));
+    }
+    let available = question.hints.len() as u32;
+    let level = HintUsage::reveal(&state.db, &user.id, &id, &question_id, available).await?;
+    Ok(Json(Hint {
+        question_id,
+        level,
+        hint: question.hints[level as usize - 1].clone(),
+        remaining: available - level,
+        credit: credit(level),
+    }))
+}
+
+/// The hints the signed-in learner has revealed on a simulation's quiz, by
+/// question, so a client can show them again
+pub async fn list_hints(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<RevealedHints>>, ApiError> {
+    let simulation = state.simulations.get(&id).ok_or_else(|| ApiError::not_found("simulation", &id))?;
+    let quiz = simulation.quiz().ok_or_else(|| ApiError::not_found("quiz", &id))?;
+    let usage = HintUsage::for_quiz(&state.db, &user.id, &id).await?;
+    let revealed = quiz
+        .questions
+        .iter()
+        .filter_map(|question| {
+            let level = usage.iter().find(|usage| usage.question_id == question.id)?.revealed;
+            Some(RevealedHints {
+                question_id: question.id.clone(),
+                hints: question.hints.iter().take(level as usize).cloned().collect(),
+                remaining: question.hints.len().saturating_sub(level as usize) as u32,
+                credit: credit(level),
+            })
+        })
+        .collect();
+    Ok(Json(revealed))
+}
+
+#[derive(Serialize)]
+pub struct Hint {
+    pub question_id: String,
+    /// Position of this hint on the ladder, from 1
+    pub level: u32,
+    pub hint: String,
+    /// Hints still to reveal on this question
+    pub remaining: u32,
+    /// Share of the question's point a correct answer now earns
+    pub credit: f64,
+}
+
+#[derive(Serialize)]
+pub struct RevealedHints {
+    pub question_id: String,
+    /// Revealed so far, least revealing first
+    pub hints: Vec<String>,
+    pub remaining: u32,
+    pub credit: f64,
+}
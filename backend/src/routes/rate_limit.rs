@@ -0,0 +1,94 @@
+use std::net::{IpAddr, SocketAddr};
+
+use axum::{
+    extract::{ConnectInfo, MatchedPath, Request, State},
+    http::{HeaderMap, Method},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use crate::routes::auth::AuthUser;
+use crate::routes::error::ApiError;
+use crate::services::rate_limit::{Budget, Client};
+use crate::state::AppState;
+
+/// Routes that start a computation or call out to a model, and so draw on
+/// the compute budget. Logging in and registering count too: each hashes a
+/// password with 600,000 rounds of PBKDF2, and the stricter budget also
+/// slows down password guessing.
+const COMPUTE_ROUTES: [(Method, &str); 19] = [
+    (Method::POST, "/api/v1/auth/register"),
+    (Method::POST, "/api/v1/auth/login"),
+    (Method::POST, "/api/v1/simulations/:id/run"),
+    (Method::POST, "/api/v1/simulations/:id/sweep"),
+    (Method::POST, "/api/v1/simulations/:id/compare"),
+    (Method::GET, "/api/v1/simulations/:id/selftest"),
+    (Method::POST, "/api/v1/simulations/:id/jobs"),
+    (Method::POST, "/api/v1/simulations/results/:id/replay"),
+    (Method::GET, "/api/v1/simulations/results/:id/animation"),
+    (Method::GET, "/api/v1/simulations/doppler/audio"),
+    (Method::GET, "/api/v1/ws/simulations/:id"),
+    (Method::POST, "/api/v1/custom-simulations/:id/run"),
+    (Method::POST, "/api/v1/library/:id/run"),
+    (Method::POST, "/api/v1/analysis/fit"),
+    (Method::POST, "/api/v1/analysis/stats"),
+    (Method::POST, "/api/v1/analysis/fft"),
+    (Method::GET, "/api/v1/search/semantic"),
+    (Method::POST, "/api/v1/ai/ask"),
+    (Method::POST, "/api/v1/tutor/chat"),
+];
+
+/// Routes monitoring systems and orchestrators poll, which are never limited
+const EXEMPT_ROUTES: [&str; 5] = ["/health", "/healthz", "/readyz", "/version", "/metrics"];
+
+/// Middleware applying the rate limits: by account for requests with a
+/// valid access token, by client address otherwise. Refused requests get a
+/// 429 problem with `Retry-After`. The client is left in the request's
+/// extensions.
+pub async fn limit_requests(
+    State(state): State<AppState>,
+    connection: Option<ConnectInfo<SocketAddr>>,
+    user: Option<AuthUser>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let route = request.extensions().get::<MatchedPath>().map(|path| path.as_str().to_string());
+    if route.as_deref().is_some_and(|route| EXEMPT_ROUTES.contains(&route)) {
+        return next.run(request).await;
+    }
+    let budget = match &route {
+        Some(route) if COMPUTE_ROUTES.iter().any(|(method, path)| method == request.method() && path == route) => {
+            Budget::Compute
+        }
+        _ => Budget::Read,
+    };
+
+    let client = match user {
+        Some(user) => Client::User(user.id),
+        None => {
+            let peer = connection.map(|ConnectInfo(addr)| addr.ip());
+            let forwarded = state.rate_limiter.trust_forwarded.then(|| forwarded_for(request.headers())).flatten();
+            match forwarded.or(peer) {
+                Some(ip) => Client::Ip(ip),
+                // Not served over TCP, e.g. in tests
+                None => return next.run(request).await,
+            }
+        }
+    };
+
+    // Long-lived handlers, such as simulation streams, charge later work
+    // to the same client
+    let mut request = request;
+    request.extensions_mut().insert(client.clone());
+    match state.rate_limiter.check(client, budget) {
+        Ok(()) => next.run(request).await,
+        Err(wait) => ApiError::RateLimited(wait.as_secs_f64().ceil().max(1.0) as u64).into_response(),
+    }
+}
+
+/// Address the nearest proxy saw the request come from: the last entry of
+/// `X-Forwarded-For`, which the client cannot forge
+fn forwarded_for(headers: &HeaderMap) -> Option<IpAddr> {
+    let header = headers.get("x-forwarded-for")?.to_str().ok()?;
+    header.rsplit(',').next()?.trim().parse().ok()
+}
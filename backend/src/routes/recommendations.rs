@@ -0,0 +1,80 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::models::progress::{QuizAttempt, SimulationProgress};
+use crate::routes::auth::AuthUser;
+use crate::routes::error::ApiError;
+use crate::services::recommendations::{strategy, Learner, Recommendation, MASTERY_SCORE, STRATEGIES};
+use crate::simulations::params::ParameterError;
+use crate::state::AppState;
+
+/// Default and largest number of recommendations
+const LIMIT: (usize, usize) = (3, 10);
+
+/// What the signed-in learner should study next, and the difficulty level
+/// they are working at, from their completions and best quiz scores.
+/// `strategy` picks how suggestions are chosen: `mastery` (the default) for
+/// the next step at their level, `review` for quizzes not yet passed and
+/// `challenge` for harder simulations they are ready for.
+pub async fn recommendations(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Query(query): Query<RecommendationQuery>,
+) -> Result<Json<Recommendations>, ApiError> {
+    let mut errors = Vec::new();
+    let name = query.strategy.as_deref().unwrap_or(STRATEGIES[0].name());
+    let chosen = strategy(name);
+    if chosen.is_none() {
+        let names: Vec<&str> = STRATEGIES.iter().map(|s| s.name()).collect();
+        errors.push(ParameterError::new(
+            "strategy",
+            format!("must be one of: {}", names.join(", ")),
+            Some(&name.into()),
+        ));
+    }
+    let limit = query.limit.unwrap_or(LIMIT.0);
+    if !(1..=LIMIT.1).contains(&limit) {
+        errors.push(ParameterError::new(
+            "limit",
+            format!("must be between 1 and {}", LIMIT.1),
+            Some(&limit.into()),
+        ));
+    }
+    let Some(chosen) = chosen.filter(|_| errors.is_empty()) else {
+        return Err(ApiError::InvalidParameters(errors));
+    };
+
+    let progress = SimulationProgress::for_user(&state.db, &user.id).await?;
+    let quizzes = QuizAttempt::summaries_for_user(&state.db, &user.id).await?;
+    let learner = Learner::new(&state.simulations, &progress, &quizzes);
+    let mut items = chosen.recommend(&learner);
+    items.truncate(limit);
+    Ok(Json(Recommendations {
+        strategy: chosen.name(),
+        level: learner.level(),
+        mastery_score: MASTERY_SCORE,
+        mastered: learner.mastered(),
+        items,
+    }))
+}
+
+#[derive(Deserialize)]
+pub struct RecommendationQuery {
+    pub strategy: Option<String>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub struct Recommendations {
+    pub strategy: &'static str,
+    /// Difficulty the learner is working at
+    pub level: &'static str,
+    /// Best quiz score (percent) that masters a simulation
+    pub mastery_score: f64,
+    /// Simulations the learner has mastered, in learning order
+    pub mastered: Vec<&'static str>,
+    pub items: Vec<Recommendation>,
+}
@@ -0,0 +1,48 @@
+use axum::{
+    body::Body,
+    extract::{MatchedPath, Request},
+    http::{HeaderValue, Request as HttpRequest},
+    middleware::Next,
+    response::Response,
+};
+use tracing::Span;
+use uuid::Uuid;
+
+use crate::services::trace::{accept_request_id, with_request_id, REQUEST_ID_HEADER};
+
+/// Middleware giving every request an ID: the client's `X-Request-Id` when
+/// it is acceptable, a new UUID otherwise. The ID is set on the request for
+/// the tracing span, available to handlers through
+/// [`crate::services::trace::request_id`] and returned in the response.
+pub async fn assign_request_id(mut request: Request, next: Next) -> Response {
+    let id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| accept_request_id(id))
+        .map_or_else(|| Uuid::new_v4().to_string(), str::to_string);
+    let header = HeaderValue::from_str(&id).expect("request IDs are visible ASCII");
+    request.headers_mut().insert(REQUEST_ID_HEADER, header.clone());
+
+    let mut response = with_request_id(id, next.run(request)).await;
+    response.headers_mut().insert(REQUEST_ID_HEADER, header);
+    response
+}
+
+/// Span of one request, named by its route template so requests to the
+/// same endpoint group together in logs
+pub fn request_span(request: &HttpRequest<Body>) -> Span {
+    let route = request.extensions().get::<MatchedPath>().map_or("unmatched", |path| path.as_str());
+    let id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    tracing::info_span!(
+        "request",
+        request_id = %id,
+        method = %request.method(),
+        route = %route,
+        uri = %request.uri(),
+    )
+}
@@ -0,0 +1,270 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::models::search::{self, SearchHit};
+use crate::routes::error::ApiError;
+use crate::services::embeddings::Passage;
+use crate::services::search::{highlight, match_expression, KINDS};
+use crate::simulations::params::ParameterError;
+use crate::state::AppState;
+
+/// Default and largest page size
+const PER_PAGE: (usize, usize) = (10, 50);
+/// Longest semantic search query (characters)
+const MAX_QUERY_LENGTH: usize = 1_000;
+/// Default and largest number of related simulations
+const RELATED: (usize, usize) = (5, 20);
+/// Longest passage excerpt returned with a semantic match (characters)
+const EXCERPT_LENGTH: usize = 300;
+
+/// Ranked full-text search over simulation names, topics, descriptions,
+/// theory and glossary terms. Entries with every word of `q` come first; if
+/// there are none, entries with any of them are returned instead, as
+/// reported by `matched`.
+pub async fn search(
+    State(state): State<AppState>,
+    Query(query): Query<SearchQuery>,
+) -> Result<Json<SearchPage>, ApiError> {
+    let mut errors = Vec::new();
+    let text = query.q.as_deref().unwrap_or("");
+    let all = match_expression(text, false);
+    if all.is_none() {
+        errors.push(ParameterError::new("q", "must contain at least one word", Some(&text.into())));
+    }
+    if let Some(kind) = query.kind.as_deref() {
+        if !KINDS.contains(&kind) {
+            errors.push(ParameterError::new(
+                "kind",
+                format!("must be one of: {}", KINDS.join(", ")),
+                Some(&kind.into()),
+            ));
+        }
+    }
+    let (page, per_page) = check_page(query.page, query.per_page, &mut errors);
+    let Some(all) = all.filter(|_| errors.is_empty()) else {
+        return Err(ApiError::InvalidParameters(errors));
+    };
+
+    state.search.refresh(&state.db, &state.simulations).await?;
+    let kind = query.kind.as_deref();
+    let mut matched = "all";
+    let mut expression = all;
+    let mut total = search::count(&state.db, &expression, kind).await?;
+    if total == 0 {
+        if let Some(any) = match_expression(text, true).filter(|any| *any != expression) {
+            matched = "any";
+            total = search::count(&state.db, &any, kind).await?;
+            expression = any;
+        }
+    }
+    let mut items = search::search(&state.db, &expression, kind, per_page, (page - 1) * per_page).await?;
+    for item in &mut items {
+        item.snippet = highlight(&item.snippet);
+    }
+
+    let total = total as usize;
+    Ok(Json(SearchPage {
+        matched,
+        items,
+        total,
+        page,
+        per_page,
+        total_pages: total.div_ceil(per_page),
+    }))
+}
+
+/// Simulations whose content is closest in meaning to `q`, a question or
+/// a description of a concept, each with its best matching passage
+pub async fn semantic_search(
+    State(state): State<AppState>,
+    Query(query): Query<SemanticQuery>,
+) -> Result<Json<SemanticPage>, ApiError> {
+    let mut errors = Vec::new();
+    let text = query.q.as_deref().unwrap_or("").trim();
+    if !text.chars().any(char::is_alphanumeric) || text.chars().count() > MAX_QUERY_LENGTH {
+        errors.push(ParameterError::new(
+            "q",
+            format!("must contain a word and be at most {MAX_QUERY_LENGTH} characters"),
+            None,
+        ));
+    }
+    let (page, per_page) = check_page(query.page, query.per_page, &mut errors);
+    if !errors.is_empty() {
+        return Err(ApiError::InvalidParameters(errors));
+    }
+
+    let index = state.embeddings.index(&state.db, &state.simulations).await?;
+    let vector = state.embeddings.embed_query(&index, text).await?;
+    let matches = index.search(&vector);
+    let total = matches.len();
+    let items = matches
+        .into_iter()
+        .skip((page - 1) * per_page)
+        .take(per_page)
+        .map(|(passage, score)| semantic_hit(&state, passage, score))
+        .collect();
+    Ok(Json(SemanticPage {
+        model: state.embeddings.model().to_string(),
+        items,
+        total,
+        page,
+        per_page,
+        total_pages: total.div_ceil(per_page),
+    }))
+}
+
+/// Simulations whose content is closest to this one's, most similar first
+pub async fn related_simulations(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<RelatedQuery>,
+) -> Result<Json<RelatedSimulations>, ApiError> {
+    if state.simulations.get(&id).is_none() {
+        return Err(ApiError::not_found("simulation", &id));
+    }
+    let limit = query.limit.unwrap_or(RELATED.0);
+    if !(1..=RELATED.1).contains(&limit) {
+        return Err(ApiError::InvalidParameters(vec![ParameterError::new(
+            "limit",
+            format!("must be between 1 and {}", RELATED.1),
+            Some(&limit.into()),
+        )]));
+    }
+
+    let index = state.embeddings.index(&state.db, &state.simulations).await?;
+    let catalog = state.simulations.catalog();
+    let items = index
+        .related(&id)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|(related, score)| {
+            let content = catalog.get(related)?;
+            Some(RelatedSimulation {
+                simulation_id: related.to_string(),
+                name: content.name.clone(),
+                summary: content.summary.clone(),
+                score,
+            })
+        })
+        .take(limit)
+        .collect();
+    Ok(Json(RelatedSimulations {
+        simulation_id: id,
+        model: state.embeddings.model().to_string(),
+        items,
+    }))
+}
+
+fn semantic_hit(state: &AppState, passage: &Passage, score: f32) -> SemanticHit {
+    let mut excerpt: String = passage.text.chars().take(EXCERPT_LENGTH).collect();
+    if excerpt.len() < passage.text.len() {
+        if let Some(end) = excerpt.rfind(' ') {
+            excerpt.truncate(end);
+        }
+        excerpt.push('…');
+    }
+    SemanticHit {
+        simulation_id: passage.simulation_id.clone(),
+        name: state.simulations.catalog().get(&passage.simulation_id).map(|c| c.name.clone()).unwrap_or_default(),
+        section: passage.section.clone(),
+        excerpt,
+        score,
+    }
+}
+
+/// Page and page size, defaults filled in, with any problems added to
+/// `errors`
+fn check_page(page: Option<usize>, per_page: Option<usize>, errors: &mut Vec<ParameterError>) -> (usize, usize) {
+    let page = page.unwrap_or(1);
+    if page == 0 {
+        errors.push(ParameterError::new("page", "must be at least 1", Some(&page.into())));
+    }
+    let per_page = per_page.unwrap_or(PER_PAGE.0);
+    if !(1..=PER_PAGE.1).contains(&per_page) {
+        errors.push(ParameterError::new(
+            "per_page",
+            format!("must be between 1 and {}", PER_PAGE.1),
+            Some(&per_page.into()),
+        ));
+    }
+    (page, per_page)
+}
+
+#[derive(Deserialize)]
+pub struct SearchQuery {
+    pub q: Option<String>,
+    /// `simulation` or `glossary`; both when left out
+    pub kind: Option<String>,
+    pub page: Option<usize>,
+    pub per_page: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub struct SearchPage {
+    /// `all` when the items contain every word of the query, `any` when
+    /// none did and the items contain some of them
+    pub matched: &'static str,
+    pub items: Vec<SearchHit>,
+    /// Matches across all pages
+    pub total: usize,
+    pub page: usize,
+    pub per_page: usize,
+    pub total_pages: usize,
+}
+
+#[derive(Deserialize)]
+pub struct SemanticQuery {
+    pub q: Option<String>,
+    pub page: Option<usize>,
+    pub per_page: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub struct SemanticPage {
+    /// Embedding model the ranking comes from
+    pub model: String,
+    pub items: Vec<SemanticHit>,
+    /// Matching simulations across all pages
+    pub total: usize,
+    pub page: usize,
+    pub per_page: usize,
+    pub total_pages: usize,
+}
+
+#[derive(Serialize)]
+pub struct SemanticHit {
+    pub simulation_id: String,
+    pub name: String,
+    /// Theory section of the best matching passage; `null` for the
+    /// simulation's overview
+    pub section: Option<String>,
+    /// Start of the passage, as plain text
+    pub excerpt: String,
+    /// Cosine similarity of the passage and the query
+    pub score: f32,
+}
+
+#[derive(Deserialize)]
+pub struct RelatedQuery {
+    pub limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub struct RelatedSimulations {
+    pub simulation_id: String,
+    /// Embedding model the similarities come from
+    pub model: String,
+    pub items: Vec<RelatedSimulation>,
+}
+
+#[derive(Serialize)]
+pub struct RelatedSimulation {
+    pub simulation_id: String,
+    pub name: String,
+    pub summary: String,
+    /// Cosine similarity of the two simulations' content
+    pub score: f32,
+}
@@ -1,206 +1,1055 @@
+use std::collections::BTreeMap;
+
 use axum::{
-    extract::Path,
-    http::StatusCode,
+    extract::{Path, Query, State},
+    http::{header, HeaderName},
+    response::{IntoResponse, Response},
     Json,
 };
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use uuid::Uuid;
 
-/// List all available simulations
-pub async fn list_simulations() -> Json<Vec<SimulationInfo>> {
-    Json(vec![
-        SimulationInfo {
-            id: "double-slit".to_string(),
-            name: "Double-Slit Experiment".to_string(),
-            description: "Explore wave-particle duality through the classic quantum experiment".to_string(),
-            difficulty: "beginner".to_string(),
-            estimated_time_minutes: 15,
-            topics: vec!["wave-particle duality".to_string(), "interference".to_string(), "quantum measurement".to_string()],
-        },
-        SimulationInfo {
-            id: "quantum-tunneling".to_string(),
-            name: "Quantum Tunneling".to_string(),
-            description: "Visualize how particles can pass through potential barriers".to_string(),
-            difficulty: "intermediate".to_string(),
-            estimated_time_minutes: 20,
-            topics: vec!["tunneling".to_string(), "potential barriers".to_string(), "probability".to_string()],
-        },
-        SimulationInfo {
-            id: "hydrogen-atom".to_string(),
-            name: "Hydrogen Atom Orbitals".to_string(),
-            description: "Interactive 3D visualization of electron orbitals".to_string(),
-            difficulty: "intermediate".to_string(),
-            estimated_time_minutes: 25,
-            topics: vec!["orbitals".to_string(), "energy levels".to_string(), "spectral lines".to_string()],
-        },
-    ])
-}
-
-/// Get simulation details by ID
-pub async fn get_simulation(Path(id): Path<String>) -> Result<Json<SimulationDetails>, StatusCode> {
-    match id.as_str() {
-        "double-slit" => Ok(Json(SimulationDetails {
-            id: "double-slit".to_string(),
-            name: "Double-Slit Experiment".to_string(),
-            description: "The double-slit experiment demonstrates the fundamentally probabilistic nature of quantum mechanical phenomena.".to_string(),
-            parameters: vec![
-                SimulationParameter {
-                    name: "wavelength".to_string(),
-                    label: "Wavelength (nm)".to_string(),
-                    param_type: "slider".to_string(),
-                    min: Some(400.0),
-                    max: Some(700.0),
-                    default: 550.0,
-                    step: Some(10.0),
-                },
-                SimulationParameter {
-                    name: "slit_separation".to_string(),
-                    label: "Slit Separation (mm)".to_string(),
-                    param_type: "slider".to_string(),
-                    min: Some(0.01),
-                    max: Some(1.0),
-                    default: 0.1,
-                    step: Some(0.01),
-                },
-                SimulationParameter {
-                    name: "observer_mode".to_string(),
-                    label: "Observer Mode".to_string(),
-                    param_type: "toggle".to_string(),
-                    min: None,
-                    max: None,
-                    default: 0.0,
-                    step: None,
-                },
-            ],
-            theory: r#"
-## Wave-Particle Duality
-
-When particles like electrons or photons pass through two slits, they create an interference pattern on a detection screen - a behavior characteristic of waves.
-
-However, when we try to observe which slit the particle passes through, the interference pattern disappears, and we see two bands - particle behavior.
-
-### Key Concepts:
-1. **Superposition**: The particle exists in a superposition of passing through both slits
-2. **Wave function**: Describes the probability amplitude of the particle's position
-3. **Measurement**: Observing the particle collapses the wave function
-
-### Mathematical Description:
-The intensity pattern is given by:
-$$I(θ) = I_0 \cos^2\left(\frac{πd\sin(θ)}{λ}\right)$$
-
-Where:
-- $d$ is the slit separation
-- $λ$ is the wavelength
-- $θ$ is the angle from the center
-"#.to_string(),
-        })),
-        _ => Err(StatusCode::NOT_FOUND),
-    }
-}
-
-/// Run a simulation with given parameters
+use crate::models::pin::ResultPin;
+use crate::models::simulation::{SimulationDetails, SimulationInfo, SimulationParameter, SimulationResult, DIFFICULTIES};
+use crate::models::topic::{find_topic, topics_for_labels};
+use crate::numerics::parallel::par_map;
+use crate::routes::achievements::emit;
+use crate::routes::auth::AuthUser;
+use crate::routes::error::ApiError;
+use crate::routes::translations::{language_headers, localizer, RequestedLocales};
+use crate::services::achievements::Event;
+use crate::services::animation;
+use crate::services::budget::{check_output_size, REQUEST_TIME_LIMIT};
+use crate::services::bundle::build_bundle;
+use crate::services::cache::cache_key;
+use crate::services::compare::differences;
+use crate::services::datasets::DatasetComparison;
+use crate::services::export;
+use crate::services::gif::encode_gif;
+use crate::services::golden::{self, Check, Golden, Tolerance};
+use crate::services::ipfs::{is_cid, IpfsError};
+use crate::services::markdown;
+use crate::services::noise::{Detector, NoiseModel};
+use crate::services::parquet::encode_parquet;
+use crate::services::trace;
+use crate::simulations::params::{fill_seed, random_seed, recorded_seed, ParameterError, Params, SEED};
+use crate::simulations::{Derived, Progress, Simulation, SimulationError, Validation, kernel_version};
+use crate::state::AppState;
+
+/// Default and largest page sizes of the simulation catalog
+const PER_PAGE: (usize, usize) = (20, 100);
+/// Fields the catalog can be sorted by
+const SORT_FIELDS: [&str; 4] = ["name", "difficulty", "time", "id"];
+
+/// List simulations, optionally filtered, searched and sorted, one page at
+/// a time.
+///
+/// `topic` matches a topic label exactly (ignoring case) or the ID of a
+/// taxonomy topic, including its subtopics; `difficulty` matches exactly.
+/// Every word of `q` must appear in the name, description, ID or a topic. `sort` takes a
+/// field name, prefixed with `-` for descending order; without it the
+/// catalog keeps its registration order. Names and descriptions are
+/// translated into the requested language, and searched and sorted as
+/// translated.
+pub async fn list_simulations(
+    State(state): State<AppState>,
+    locales: RequestedLocales,
+    Query(query): Query<ListQuery>,
+) -> Result<([(HeaderName, String); 2], Json<SimulationPage>), ApiError> {
+    let mut errors = Vec::new();
+    let taxonomy_topic = query.topic.as_deref().and_then(find_topic);
+    let difficulty = query.difficulty.as_deref().map(str::to_lowercase);
+    if let Some(difficulty) = &difficulty {
+        if !DIFFICULTIES.contains(&difficulty.as_str()) {
+            errors.push(ParameterError::new(
+                "difficulty",
+                format!("must be one of: {}", DIFFICULTIES.join(", ")),
+                Some(&difficulty.as_str().into()),
+            ));
+        }
+    }
+    let sort = query.sort.as_deref().map(|sort| match sort.strip_prefix('-') {
+        Some(field) => (field, true),
+        None => (sort, false),
+    });
+    if let Some((field, _)) = sort {
+        if !SORT_FIELDS.contains(&field) {
+            errors.push(ParameterError::new(
+                "sort",
+                format!("must be one of: {} (prefix with - to reverse)", SORT_FIELDS.join(", ")),
+                Some(&field.into()),
+            ));
+        }
+    }
+    let page = query.page.unwrap_or(1);
+    if page == 0 {
+        errors.push(ParameterError::new("page", "must be at least 1", Some(&page.into())));
+    }
+    let per_page = query.per_page.unwrap_or(PER_PAGE.0);
+    if !(1..=PER_PAGE.1).contains(&per_page) {
+        errors.push(ParameterError::new(
+            "per_page",
+            format!("must be between 1 and {}", PER_PAGE.1),
+            Some(&per_page.into()),
+        ));
+    }
+    if !errors.is_empty() {
+        return Err(ApiError::InvalidParameters(errors));
+    }
+
+    let localizer = localizer(&state, &locales).await?;
+    let topic = query.topic.as_deref().map(str::to_lowercase);
+    let terms: Vec<String> = query.q.as_deref().unwrap_or("").split_whitespace().map(str::to_lowercase).collect();
+    let mut matches: Vec<SimulationInfo> = state
+        .simulations
+        .iter()
+        .map(|s| {
+            let mut info = s.info();
+            localizer.info(&mut info);
+            info
+        })
+        .filter(|info| difficulty.as_ref().is_none_or(|d| info.difficulty.eq_ignore_ascii_case(d)))
+        .filter(|info| {
+            topic.as_ref().is_none_or(|t| {
+                info.topics.iter().any(|topic| topic.to_lowercase() == *t)
+                    || taxonomy_topic
+                        .is_some_and(|parent| topics_for_labels(&info.topics).iter().any(|topic| parent.contains(topic)))
+            })
+        })
+        .filter(|info| {
+            let text = format!("{} {} {} {}", info.id, info.name, info.description, info.topics.join(" ")).to_lowercase();
+            terms.iter().all(|term| text.contains(term.as_str()))
+        })
+        .collect();
+
+    if let Some((field, descending)) = sort {
+        let rank = |info: &SimulationInfo| DIFFICULTIES.iter().position(|d| *d == info.difficulty);
+        matches.sort_by(|a, b| {
+            let order = match field {
+                "name" => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+                "difficulty" => rank(a).cmp(&rank(b)),
+                "time" => a.estimated_time_minutes.cmp(&b.estimated_time_minutes),
+                _ => a.id.cmp(&b.id),
+            };
+            if descending { order.reverse() } else { order }
+        });
+    }
+
+    let total = matches.len();
+    let items = matches
+        .into_iter()
+        .skip((page - 1) * per_page)
+        .take(per_page)
+        .map(|info| {
+            let prerequisites = state.simulations.get(&info.id).map(|s| s.prerequisites()).unwrap_or_default();
+            CatalogEntry::new(info, prerequisites)
+        })
+        .collect();
+    let page = SimulationPage {
+        items,
+        total,
+        page,
+        per_page,
+        total_pages: total.div_ceil(per_page),
+    };
+    Ok((language_headers(localizer.content_language()), Json(page)))
+}
+
+/// Get simulation details by ID, translated into the requested language
+/// where translations exist
+pub async fn get_simulation(
+    State(state): State<AppState>,
+    locales: RequestedLocales,
+    Path(id): Path<String>,
+) -> Result<([(HeaderName, String); 2], Json<SimulationDetails>), ApiError> {
+    let simulation = state.simulations.get(&id).ok_or_else(|| ApiError::not_found("simulation", &id))?;
+    let mut details = simulation.details();
+    let locale = localizer(&state, &locales).await?.details(&mut details).to_string();
+    Ok((language_headers(&locale), Json(details)))
+}
+
+/// The theory section alone, translated like the details: the Markdown as
+/// written, or with `format=html` rendered to a sanitized HTML fragment
+/// for clients that do not render Markdown and math themselves
+pub async fn get_theory(
+    State(state): State<AppState>,
+    locales: RequestedLocales,
+    Path(id): Path<String>,
+    Query(query): Query<TheoryQuery>,
+) -> Result<Response, ApiError> {
+    let simulation = state.simulations.get(&id).ok_or_else(|| ApiError::not_found("simulation", &id))?;
+    let mut details = simulation.details();
+    let locale = localizer(&state, &locales).await?.details(&mut details).to_string();
+    let (content_type, body) = theory_body(details.theory, query.format.as_deref())?;
+    Ok((language_headers(&locale), [(header::CONTENT_TYPE, content_type)], body).into_response())
+}
+
+/// Content type and body of a theory section in the requested format
+pub(crate) fn theory_body(theory: String, format: Option<&str>) -> Result<(&'static str, String), ApiError> {
+    match format.unwrap_or("markdown") {
+        "markdown" => Ok(("text/markdown; charset=utf-8", theory)),
+        "html" => Ok(("text/html; charset=utf-8", markdown::to_html(&theory))),
+        other => Err(ApiError::InvalidParameters(vec![ParameterError::new(
+            "format",
+            "must be one of: markdown, html",
+            Some(&other.into()),
+        )])),
+    }
+}
+
+/// Run a simulation with given parameters.
+///
+/// A run with the same validated parameters as an earlier one returns the
+/// cached result. Otherwise the computation runs on the blocking pool so
+/// long simulations do not stall other requests on the async runtime, and
+/// is held to the request compute budget.
 pub async fn run_simulation(
+    State(state): State<AppState>,
+    user: Option<AuthUser>,
     Path(id): Path<String>,
-    Json(params): Json<RunSimulationRequest>,
-) -> Result<Json<SimulationResult>, StatusCode> {
-    match id.as_str() {
-        "double-slit" => {
-            let wavelength = params.parameters.get("wavelength")
-                .and_then(|v| v.as_f64())
-                .unwrap_or(550.0);
-            let slit_separation = params.parameters.get("slit_separation")
-                .and_then(|v| v.as_f64())
-                .unwrap_or(0.1);
-            let observer_mode = params.parameters.get("observer_mode")
-                .and_then(|v| v.as_bool())
-                .unwrap_or(false);
-
-            // Calculate interference pattern
-            let pattern = calculate_interference_pattern(wavelength, slit_separation, observer_mode);
-
-            Ok(Json(SimulationResult {
-                id: Uuid::new_v4().to_string(),
-                simulation_id: id,
-                data: serde_json::json!({
-                    "pattern": pattern,
-                    "wavelength": wavelength,
-                    "slit_separation": slit_separation,
-                    "observer_mode": observer_mode,
-                }),
-                computed_at: chrono::Utc::now().to_rfc3339(),
-            }))
-        }
-        _ => Err(StatusCode::NOT_FOUND),
-    }
-}
-
-/// Calculate interference pattern for double-slit experiment
-fn calculate_interference_pattern(wavelength_nm: f64, slit_separation_mm: f64, observer_mode: bool) -> Vec<f64> {
-    let num_points = 200;
-    let wavelength_m = wavelength_nm * 1e-9;
-    let slit_separation_m = slit_separation_mm * 1e-3;
-    let screen_distance = 1.0; // 1 meter
-
-    (0..num_points)
-        .map(|i| {
-            let x = (i as f64 - num_points as f64 / 2.0) * 0.001; // -10cm to +10cm
-            let theta = (x / screen_distance).atan();
-            
-            if observer_mode {
-                // Particle behavior: two distinct bands (Gaussian distributions)
-                let band1 = (-((theta + 0.05_f64).powi(2)) / 0.001).exp();
-                let band2 = (-((theta - 0.05_f64).powi(2)) / 0.001).exp();
-                (band1 + band2) * 0.5
-            } else {
-                // Wave behavior: interference pattern
-                let phase = std::f64::consts::PI * slit_separation_m * theta.sin() / wavelength_m;
-                phase.cos().powi(2)
+    Json(mut request): Json<RunSimulationRequest>,
+) -> Result<Json<RunSimulationResponse>, ApiError> {
+    let simulation = state.simulations.get(&id).ok_or_else(|| ApiError::not_found("simulation", &id))?;
+    let schema = simulation.parameters();
+    fill_seed(&schema, &mut request.parameters, random_seed());
+    let params = Params::validate(&schema, &request.parameters).map_err(ApiError::InvalidParameters)?;
+    let dataset = match &request.compare_to_dataset {
+        Some(dataset_id) => {
+            let dataset = state.datasets.get(dataset_id).ok_or_else(|| ApiError::not_found("dataset", dataset_id))?;
+            if dataset.simulation != id {
+                return Err(ApiError::InvalidParameters(vec![ParameterError::new(
+                    "compare_to_dataset",
+                    format!("is a dataset for {}", dataset.simulation),
+                    Some(&dataset_id.as_str().into()),
+                )]));
             }
-        })
+            Some(dataset)
+        }
+        None => None,
+    };
+    let noise = match &request.noise {
+        Some(model) => Some((model, model.check(simulation.signal()).map_err(ApiError::InvalidParameters)?)),
+        None => None,
+    };
+    let record = |data: &serde_json::Value| {
+        noise
+            .as_ref()
+            .map(|(model, (output, position))| model.record(data, output, position.as_deref()))
+            .transpose()
+            .map_err(|e| ApiError::InvalidParameters(vec![e]))
+    };
+    let units = parameter_units(&schema);
+    let parameters = params.values().clone();
+    let key = cache_key(&id, simulation.version(), &parameters);
+    if let Some(result) = state.cache.get(&key).await {
+        let derived = derived_quantities(simulation.as_ref(), &params, &result.data);
+        let validation = request.validate.then(|| simulation.validate(&params, &result.data));
+        let dataset_comparison = dataset.map(|dataset| dataset.compare(&result.data));
+        let detector = record(&result.data)?;
+        if let Some(user) = user {
+            emit(&state, user.id, Event::run(&id, &parameters, &derived));
+        }
+        return Ok(Json(RunSimulationResponse {
+            result,
+            units,
+            derived,
+            validation,
+            dataset_comparison,
+            detector,
+            cache: CacheStatus { hit: true, key },
+        }));
+    }
+
+    let worker = simulation.clone();
+    let attester = state.attester.clone();
+    let signed = parameters.clone();
+    let progress = Progress::default().with_time_limit(REQUEST_TIME_LIMIT);
+    let validate = request.validate;
+    let (data, attestation, derived, validation) = trace::spawn_blocking(move || {
+        let data = worker.run_with_progress(&params, &progress)?;
+        check_output_size([&data])?;
+        let attestation = attester.attest_run(worker.id(), worker.version(), &signed, &data);
+        let derived = derived_quantities(worker.as_ref(), &params, &data);
+        let validation = validate.then(|| worker.validate(&params, &data));
+        Ok::<_, SimulationError>((data, attestation, derived, validation))
+    })
+    .await
+    .map_err(|e| {
+        tracing::error!("Simulation {} failed: {}", id, e);
+        ApiError::Internal
+    })??;
+
+    let dataset_comparison = dataset.map(|dataset| dataset.compare(&data));
+    let detector = record(&data)?;
+    if let Some(user) = user {
+        emit(&state, user.id, Event::run(&id, &parameters, &derived));
+    }
+    let parameters = serde_json::Value::Object(parameters);
+    let result = SimulationResult {
+        id: Uuid::new_v4().to_string(),
+        simulation_version: simulation.version(),
+        simulation_id: id,
+        seed: recorded_seed(&parameters),
+        parameters,
+        data,
+        computed_at: chrono::Utc::now().to_rfc3339(),
+        attestation: Some(attestation),
+        compute_backend: simulation.backend(),
+    };
+    result.insert(&state.db).await?;
+    state.cache.insert(&key, &result).await;
+
+    Ok(Json(RunSimulationResponse {
+        result,
+        units,
+        derived,
+        validation,
+        dataset_comparison,
+        detector,
+        cache: CacheStatus { hit: false, key },
+    }))
+}
+
+/// The simulation's derived quantities that are defined for this run
+pub(crate) fn derived_quantities(simulation: &dyn Simulation, params: &Params, data: &serde_json::Value) -> Vec<Derived> {
+    let mut derived = simulation.derived(params, data);
+    derived.retain(|quantity| quantity.value.is_finite());
+    derived
+}
+
+/// Unit of each parameter that has one
+fn parameter_units(schema: &[SimulationParameter]) -> BTreeMap<String, String> {
+    schema
+        .iter()
+        .filter_map(|param| Some((param.name.clone(), param.unit.clone()?)))
         .collect()
 }
 
-// Data structures
+/// Upper bound on the number of points in one sweep
+const MAX_SWEEP_POINTS: usize = 50;
+
+/// Run a simulation across a range of values of one parameter.
+///
+/// Every point is validated before any computation starts, and the points
+/// are then computed in parallel off the async runtime. The whole sweep
+/// shares one request compute budget.
+pub async fn sweep_simulation(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(request): Json<SweepRequest>,
+) -> Result<Json<SweepResult>, ApiError> {
+    let simulation = state.simulations.get(&id).ok_or_else(|| ApiError::not_found("simulation", &id))?;
+    let values = sweep_values(&request).map_err(|e| ApiError::InvalidParameters(vec![e]))?;
+
+    let schema = simulation.parameters();
+    if !schema.iter().any(|p| p.name == request.parameter) {
+        return Err(ApiError::InvalidParameters(vec![ParameterError::new(
+            "parameter",
+            format!("unknown parameter `{}`", request.parameter),
+            None,
+        )]));
+    }
+    // One seed for every point, so only the swept parameter changes
+    let mut shared = request.parameters.clone();
+    fill_seed(&schema, &mut shared, random_seed());
+    let points = values
+        .iter()
+        .map(|&value| {
+            let mut raw = shared.clone();
+            raw.insert(request.parameter.clone(), value.into());
+            Params::validate(&schema, &raw)
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(ApiError::InvalidParameters)?;
+
+    let worker = simulation.clone();
+    let outputs = trace::spawn_blocking(move || run_within_budget(worker.as_ref(), points))
+        .await
+        .map_err(|e| {
+            tracing::error!("Sweep of {} failed: {}", id, e);
+            ApiError::Internal
+        })??;
+    check_output_size(&outputs)?;
+
+    let points = values
+        .into_iter()
+        .zip(outputs)
+        .map(|(value, data)| SweepPoint { value, data })
+        .collect();
+
+    Ok(Json(SweepResult {
+        simulation_id: id,
+        seed: (request.parameter != SEED).then(|| recorded_seed(&serde_json::Value::Object(shared))).flatten(),
+        parameter: request.parameter,
+        points,
+        computed_at: chrono::Utc::now().to_rfc3339(),
+    }))
+}
+
+/// Fewest and most parameter sets in one comparison
+const COMPARE_RUNS: (usize, usize) = (2, 8);
+
+/// Run a simulation with several parameter sets side by side.
+///
+/// Outputs come back in request order for overlay plots, together with the
+/// parameters that vary and the change of every numeric output relative to
+/// the first run.
+pub async fn compare_simulation(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(request): Json<CompareRequest>,
+) -> Result<Json<CompareResult>, ApiError> {
+    let simulation = state.simulations.get(&id).ok_or_else(|| ApiError::not_found("simulation", &id))?;
+    let (min_runs, max_runs) = COMPARE_RUNS;
+    if !(min_runs..=max_runs).contains(&request.runs.len()) {
+        return Err(ApiError::InvalidParameters(vec![ParameterError::new(
+            "runs",
+            format!("must contain between {} and {} parameter sets", min_runs, max_runs),
+            None,
+        )]));
+    }
+
+    let schema = simulation.parameters();
+    let mut points = Vec::with_capacity(request.runs.len());
+    let mut errors = Vec::new();
+    // Runs that give no seed share one, so it does not count as varied
+    let seed = random_seed();
+    for (i, raw) in request.runs.iter().enumerate() {
+        let mut raw = raw.clone();
+        fill_seed(&schema, &mut raw, seed);
+        match Params::validate(&schema, &raw) {
+            Ok(params) => points.push(params),
+            Err(fields) => errors.extend(fields.into_iter().map(|e| ParameterError {
+                name: format!("runs[{}].{}", i, e.name),
+                ..e
+            })),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(ApiError::InvalidParameters(errors));
+    }
+
+    let parameters: Vec<serde_json::Map<String, serde_json::Value>> =
+        points.iter().map(|p| p.values().clone()).collect();
+    let varied = schema
+        .iter()
+        .filter(|p| parameters.iter().any(|values| values.get(&p.name) != parameters[0].get(&p.name)))
+        .map(|p| p.name.clone())
+        .collect();
+
+    let worker = simulation.clone();
+    let outputs = trace::spawn_blocking(move || run_within_budget(worker.as_ref(), points))
+        .await
+        .map_err(|e| {
+            tracing::error!("Comparison of {} failed: {}", id, e);
+            ApiError::Internal
+        })??;
+    check_output_size(&outputs)?;
+
+    Ok(Json(CompareResult {
+        simulation_id: id,
+        varied,
+        differences: differences(&outputs),
+        runs: parameters
+            .into_iter()
+            .zip(outputs)
+            .map(|(parameters, data)| CompareRun { parameters, data })
+            .collect(),
+        computed_at: chrono::Utc::now().to_rfc3339(),
+    }))
+}
+
+fn sweep_values(request: &SweepRequest) -> Result<Vec<f64>, ParameterError> {
+    if !(request.step > 0.0 && request.step.is_finite()) {
+        return Err(ParameterError::new("step", "must be a positive number", None));
+    }
+    if !(request.start.is_finite() && request.stop.is_finite()) || request.stop < request.start {
+        return Err(ParameterError::new("stop", "must not be less than start", None));
+    }
+    let count = ((request.stop - request.start) / request.step + 1e-9).floor() as usize + 1;
+    if count > MAX_SWEEP_POINTS {
+        return Err(ParameterError::new(
+            "step",
+            format!("sweep would produce {} points; the limit is {}", count, MAX_SWEEP_POINTS),
+            None,
+        ));
+    }
+    Ok((0..count).map(|i| request.start + i as f64 * request.step).collect())
+}
+
+/// Fetch a previously computed result by its ID
+pub async fn get_result(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<SimulationResult>, ApiError> {
+    let result = SimulationResult::find(&state.db, &id).await?;
+    result.map(Json).ok_or_else(|| ApiError::not_found("result", &id))
+}
+
+/// Recompute a stored result and check that it comes out bit-for-bit the
+/// same.
+///
+/// The stored parameters are run through the computation version that
+/// produced the result, so results stay reproducible after a simulation's
+/// formulas change. `?version=current` runs the current computation
+/// instead, to see what a fix changed.
+pub async fn replay_result(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<ReplayQuery>,
+) -> Result<Json<ReplayResponse>, ApiError> {
+    let result = SimulationResult::find(&state.db, &id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("result", &id))?;
+    let simulation = state
+        .simulations
+        .get(&result.simulation_id)
+        .ok_or_else(|| ApiError::not_found("simulation", &result.simulation_id))?;
+
+    let stored = result.parameters.as_object().cloned().unwrap_or_default();
+    let (version, params) = match query.version.as_deref() {
+        None | Some("stored") => (result.simulation_version, Params::from_stored(stored)),
+        // The current code may read parameters added since
+        Some("current") => (
+            simulation.version(),
+            Params::validate(&simulation.parameters(), &stored).map_err(ApiError::InvalidParameters)?,
+        ),
+        Some(other) => {
+            return Err(ApiError::InvalidParameters(vec![ParameterError::new(
+                "version",
+                "must be one of: stored, current",
+                Some(&other.into()),
+            )]))
+        }
+    };
+
+    let worker = simulation.clone();
+    let progress = Progress::default().with_time_limit(REQUEST_TIME_LIMIT);
+    let data = trace::spawn_blocking(move || worker.run_version(version, &params, &progress))
+        .await
+        .map_err(|e| {
+            tracing::error!("Replay of result {} failed: {}", id, e);
+            ApiError::Internal
+        })?
+        .ok_or_else(|| {
+            ApiError::Conflict(format!(
+                "version {} of {} can no longer be run",
+                version, result.simulation_id
+            ))
+        })??;
+
+    let outputs = [result.data, data];
+    Ok(Json(ReplayResponse {
+        result_id: id,
+        simulation_id: result.simulation_id,
+        stored_version: result.simulation_version,
+        replayed_version: version,
+        current_version: simulation.version(),
+        identical: outputs[0] == outputs[1],
+        differences: differences(&outputs),
+        data: outputs.into_iter().nth(1).unwrap_or_default(),
+    }))
+}
+
+/// Rerun a simulation with the canonical parameters of its golden file and
+/// check the output against the recorded one, so a deployment can confirm
+/// its physics after an upgrade.
+///
+/// The recorded computation version is run, as in replays. `?record=true`
+/// instead runs the current version with default parameters and returns
+/// the golden file for it, to be saved as `content/golden/<id>.json`.
+pub async fn selftest_simulation(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<SelftestQuery>,
+) -> Result<Response, ApiError> {
+    let simulation = state.simulations.get(&id).ok_or_else(|| ApiError::not_found("simulation", &id))?;
+    let golden = if query.record {
+        None
+    } else {
+        let golden = golden::load(&state.config.content.dir, &id).map_err(|e| {
+            tracing::error!("Failed to load golden output of {}: {}", id, e);
+            ApiError::Internal
+        })?;
+        Some(golden.ok_or_else(|| ApiError::not_found("golden output", &id))?)
+    };
+    let (version, params) = match &golden {
+        // A `simd` build checks its own version of the kernels against the
+        // default build's golden files, which leave room for rounding
+        Some(golden) if kernel_version(golden.simulation_version) == simulation.version() => {
+            (simulation.version(), Params::from_stored(golden.parameters.clone()))
+        }
+        Some(golden) => (golden.simulation_version, Params::from_stored(golden.parameters.clone())),
+        None => (
+            simulation.version(),
+            Params::validate(&simulation.parameters(), &serde_json::Map::new()).map_err(ApiError::InvalidParameters)?,
+        ),
+    };
+    let parameters = params.values().clone();
+
+    let worker = simulation.clone();
+    let progress = Progress::default().with_time_limit(REQUEST_TIME_LIMIT);
+    let data = trace::spawn_blocking(move || worker.run_version(version, &params, &progress))
+        .await
+        .map_err(|e| {
+            tracing::error!("Self-test of {} failed: {}", id, e);
+            ApiError::Internal
+        })?
+        .ok_or_else(|| ApiError::Conflict(format!("version {} of {} can no longer be run", version, id)))??;
+
+    let Some(golden) = golden else {
+        return Ok(Json(Golden::record(version, parameters, &data)).into_response());
+    };
+    let checks = golden.check(&data);
+    let failed = checks.iter().filter(|check| !check.passed).count();
+    if failed > 0 {
+        tracing::warn!("Self-test of {} failed {} of {} checks", id, failed, checks.len());
+    }
+    Ok(Json(SelftestReport {
+        simulation_id: id,
+        simulation_version: version,
+        current_version: simulation.version(),
+        passed: failed == 0,
+        failed,
+        tolerance: golden.tolerance,
+        parameters,
+        checks,
+    })
+    .into_response())
+}
+
+/// Export a stored result as a table, for spreadsheets and data analysis.
+///
+/// Arrays in the result are flattened into labeled columns and grouped into
+/// tables by length. The main table is returned unless `table` names a
+/// column of another one. Parquet files also carry the run's parameters and
+/// timestamps in their metadata.
+pub async fn export_result(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<ExportQuery>,
+) -> Result<Response, ApiError> {
+    let result = SimulationResult::find(&state.db, &id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("result", &id))?;
+
+    let format = query.format.as_deref().unwrap_or("csv");
+    if !matches!(format, "csv" | "jsonl" | "parquet") {
+        return Err(ApiError::InvalidParameters(vec![ParameterError::new(
+            "format",
+            "must be one of: csv, jsonl, parquet",
+            Some(&format.into()),
+        )]));
+    }
+
+    let units = state.simulations.get(&result.simulation_id).map_or(&[][..], |s| s.units());
+    let tables = export::tables(&result.data, units);
+    let table = match &query.table {
+        Some(name) => tables.iter().find(|t| t.columns.iter().any(|c| &c.path == name)),
+        None => tables.first(),
+    }
+    .ok_or_else(|| {
+        let message = match &query.table {
+            Some(_) => "no exported column has this name",
+            None => "result has no tabular data",
+        };
+        ApiError::InvalidParameters(vec![ParameterError::new(
+            "table",
+            message,
+            query.table.as_ref().map(|t| t.as_str().into()).as_ref(),
+        )])
+    })?;
+
+    let (body, content_type, extension) = match format {
+        "csv" => (table.to_csv().into_bytes(), "text/csv; charset=utf-8", "csv"),
+        "jsonl" => (table.to_json_lines().into_bytes(), "application/x-ndjson", "jsonl"),
+        _ => {
+            let metadata = [
+                ("simulation_id", result.simulation_id.clone()),
+                ("result_id", result.id.clone()),
+                ("simulation_version", result.simulation_version.to_string()),
+                ("parameters", result.parameters.to_string()),
+                ("computed_at", result.computed_at.clone()),
+                ("exported_at", chrono::Utc::now().to_rfc3339()),
+            ];
+            (encode_parquet(table, &metadata), "application/vnd.apache.parquet", "parquet")
+        }
+    };
+    let disposition = format!("attachment; filename=\"{}-{}.{}\"", result.simulation_id, id, extension);
+    Ok(([(header::CONTENT_TYPE, content_type.to_string()), (header::CONTENT_DISPOSITION, disposition)], body)
+        .into_response())
+}
+
+/// Download a stored result as a ZIP archive with its parameters, raw data,
+/// CSV tables, a methods description and the code versions that produced
+/// it, to attach to lab reports or archive
+pub async fn bundle_result(State(state): State<AppState>, Path(id): Path<String>) -> Result<Response, ApiError> {
+    let result = SimulationResult::find(&state.db, &id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("result", &id))?;
+
+    Ok(zip_response(&result, bundle(&state, &result)))
+}
+
+/// Pin a stored result's bundle to IPFS and return its CID. A result is
+/// pinned once; later calls return the same pin.
+pub async fn pin_result(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(id): Path<String>,
+) -> Result<Json<PinResponse>, ApiError> {
+    if let Some(pin) = ResultPin::for_result(&state.db, &id).await? {
+        return Ok(Json(PinResponse::new(&state, pin)));
+    }
+    if !state.ipfs.is_enabled() {
+        return Err(IpfsError::NotConfigured.into());
+    }
+    let result = SimulationResult::find(&state.db, &id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("result", &id))?;
+
+    let bundle = bundle(&state, &result);
+    let pin = ResultPin {
+        result_id: id.clone(),
+        sha256: sha256_hex(&bundle),
+        size: bundle.len() as i64,
+        cid: state.ipfs.add(&bundle_filename(&result), bundle).await?,
+        pinned_by: auth.id,
+        pinned_at: chrono::Utc::now(),
+    };
+    pin.insert(&state.db).await?;
+    tracing::info!("Result {} pinned to IPFS as {}", id, pin.cid);
+    Ok(Json(PinResponse::new(&state, pin)))
+}
+
+/// Fetch a pinned bundle back from IPFS by its CID, after checking that it
+/// is byte-for-byte the bundle that was pinned
+pub async fn bundle_by_cid(State(state): State<AppState>, Path(cid): Path<String>) -> Result<Response, ApiError> {
+    if !is_cid(&cid) {
+        return Err(ApiError::InvalidParameters(vec![ParameterError::new(
+            "cid",
+            "must be an IPFS content identifier",
+            Some(&cid.as_str().into()),
+        )]));
+    }
+    let pin = ResultPin::for_cid(&state.db, &cid)
+        .await?
+        .ok_or_else(|| ApiError::not_found("pinned result", &cid))?;
+
+    let bundle = state.ipfs.cat(&cid).await?;
+    if sha256_hex(&bundle) != pin.sha256 {
+        tracing::warn!("Content of {} from IPFS does not match the bundle pinned for {}", cid, pin.result_id);
+        return Err(ApiError::Upstream(format!("content of {cid} does not match the pinned bundle")));
+    }
+    let disposition = format!("attachment; filename=\"{}-{}.zip\"", pin.result_id, cid);
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/zip".to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+            (header::ETAG, format!("\"{}\"", pin.sha256)),
+        ],
+        bundle,
+    )
+        .into_response())
+}
+
+fn bundle(state: &AppState, result: &SimulationResult) -> Vec<u8> {
+    // Results of retired simulations are still worth archiving
+    let simulation = state.simulations.get_any(&result.simulation_id);
+    let details = simulation.as_ref().map(|s| s.details());
+    let units = simulation.as_ref().map_or(&[][..], |s| s.units());
+    build_bundle(result, details.as_ref(), units)
+}
+
+fn bundle_filename(result: &SimulationResult) -> String {
+    format!("{}-{}.zip", result.simulation_id, result.id)
+}
+
+fn zip_response(result: &SimulationResult, body: Vec<u8>) -> Response {
+    let disposition = format!("attachment; filename=\"{}\"", bundle_filename(result));
+    ([(header::CONTENT_TYPE, "application/zip".to_string()), (header::CONTENT_DISPOSITION, disposition)], body)
+        .into_response()
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes).iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Slowest and fastest frame rates offered for animations
+const FPS_RANGE: (u32, u32) = (1, 50);
+
+/// Render a stored time-dependent result as a looping animated GIF.
+///
+/// Results carrying a frame sequence are drawn as an animated curve; double
+/// pendulum runs are played back in real time at the requested frame rate.
+pub async fn animate_result(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<AnimationQuery>,
+) -> Result<Response, ApiError> {
+    let fps = query.fps.unwrap_or(20);
+    if !(FPS_RANGE.0..=FPS_RANGE.1).contains(&fps) {
+        return Err(ApiError::InvalidParameters(vec![ParameterError::new(
+            "fps",
+            format!("must be between {} and {}", FPS_RANGE.0, FPS_RANGE.1),
+            Some(&fps.into()),
+        )]));
+    }
+    let result = SimulationResult::find(&state.db, &id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("result", &id))?;
+
+    let gif = trace::spawn_blocking(move || {
+        animation::render(&result.data, fps).map(|animation| {
+            encode_gif(animation::WIDTH, animation::HEIGHT, &animation::PALETTE, &animation.frames, animation.delay)
+        })
+    })
+    .await
+    .map_err(|e| {
+        tracing::error!("Rendering animation of result {} failed: {}", id, e);
+        ApiError::Internal
+    })?
+    .ok_or(ApiError::Unsupported("result has no time sequence to animate"))?;
+
+    Ok(([(header::CONTENT_TYPE, "image/gif")], gif).into_response())
+}
+
+#[derive(Deserialize)]
+pub struct AnimationQuery {
+    /// Frames per second, 20 by default
+    pub fps: Option<u32>,
+}
 
 #[derive(Serialize)]
-pub struct SimulationInfo {
-    pub id: String,
-    pub name: String,
-    pub description: String,
-    pub difficulty: String,
-    pub estimated_time_minutes: u32,
-    pub topics: Vec<String>,
+pub struct PinResponse {
+    #[serde(flatten)]
+    pub pin: ResultPin,
+    /// The bundle on a public IPFS gateway
+    pub gateway_url: String,
+}
+
+impl PinResponse {
+    fn new(state: &AppState, pin: ResultPin) -> Self {
+        Self {
+            gateway_url: state.ipfs.gateway_link(&pin.cid),
+            pin,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ReplayQuery {
+    /// "stored" (default) or "current"
+    pub version: Option<String>,
 }
 
 #[derive(Serialize)]
-pub struct SimulationDetails {
-    pub id: String,
-    pub name: String,
-    pub description: String,
-    pub parameters: Vec<SimulationParameter>,
-    pub theory: String,
+pub struct ReplayResponse {
+    pub result_id: String,
+    pub simulation_id: String,
+    /// Version that computed the stored result
+    pub stored_version: u32,
+    /// Version that computed `data`
+    pub replayed_version: u32,
+    pub current_version: u32,
+    /// Whether `data` equals the stored data exactly
+    pub identical: bool,
+    /// Numeric outputs that changed, by dotted path, as in comparisons
+    pub differences: serde_json::Map<String, serde_json::Value>,
+    pub data: serde_json::Value,
+}
+
+#[derive(Deserialize)]
+pub struct SelftestQuery {
+    /// Return a golden file for the current code instead of checking
+    #[serde(default)]
+    pub record: bool,
 }
 
 #[derive(Serialize)]
-pub struct SimulationParameter {
-    pub name: String,
-    pub label: String,
-    pub param_type: String,
-    pub min: Option<f64>,
-    pub max: Option<f64>,
-    pub default: f64,
-    pub step: Option<f64>,
+pub struct SelftestReport {
+    pub simulation_id: String,
+    /// Version the golden output was recorded with, and rerun
+    pub simulation_version: u32,
+    pub current_version: u32,
+    pub passed: bool,
+    /// Checks that failed
+    pub failed: usize,
+    pub tolerance: Tolerance,
+    pub parameters: serde_json::Map<String, serde_json::Value>,
+    pub checks: Vec<Check>,
+}
+
+#[derive(Deserialize)]
+pub struct TheoryQuery {
+    /// "markdown" (default) or "html"
+    pub format: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ExportQuery {
+    /// "csv" (default), "jsonl" or "parquet"
+    pub format: Option<String>,
+    /// Name of any column in the table to export
+    pub table: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ListQuery {
+    pub topic: Option<String>,
+    pub difficulty: Option<String>,
+    /// Free-text search
+    pub q: Option<String>,
+    pub sort: Option<String>,
+    pub page: Option<usize>,
+    pub per_page: Option<usize>,
+}
+
+/// Catalog listing of a simulation, linked to the topic taxonomy
+#[derive(Serialize)]
+pub struct CatalogEntry {
+    #[serde(flatten)]
+    pub info: SimulationInfo,
+    /// Taxonomy topics its topic labels belong to, see `/api/v1/topics`
+    pub topic_ids: Vec<&'static str>,
+    /// Simulations to work through first, see `/api/v1/curriculum/graph`
+    pub prerequisites: &'static [&'static str],
+}
+
+impl CatalogEntry {
+    pub fn new(info: SimulationInfo, prerequisites: &'static [&'static str]) -> Self {
+        let topic_ids = topics_for_labels(&info.topics).iter().map(|topic| topic.id).collect();
+        Self {
+            info,
+            topic_ids,
+            prerequisites,
+        }
+    }
+}
+
+/// One page of the simulation catalog
+#[derive(Serialize)]
+pub struct SimulationPage {
+    pub items: Vec<CatalogEntry>,
+    /// Simulations matching the filters, across all pages
+    pub total: usize,
+    pub page: usize,
+    pub per_page: usize,
+    pub total_pages: usize,
 }
 
 #[derive(Deserialize)]
 pub struct RunSimulationRequest {
     pub parameters: serde_json::Map<String, serde_json::Value>,
+    /// Also compare the run with the analytic solution, where there is one
+    #[serde(default)]
+    pub validate: bool,
+    /// ID of an experimental dataset to compare the run with
+    #[serde(default)]
+    pub compare_to_dataset: Option<String>,
+    /// Detector to record an output with, as noisy counts
+    #[serde(default)]
+    pub noise: Option<NoiseModel>,
+}
+
+#[derive(Serialize)]
+pub struct RunSimulationResponse {
+    #[serde(flatten)]
+    pub result: SimulationResult,
+    /// Units of the values in `parameters`, whatever units the request used
+    pub units: BTreeMap<String, String>,
+    /// Quantities worked out from the run, with their units and formulas
+    pub derived: Vec<Derived>,
+    /// Deviations from the analytic solution, present when the request
+    /// asked for them and empty when these parameters have none
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validation: Option<Vec<Validation>>,
+    /// Residuals and χ² against the requested dataset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dataset_comparison: Option<DatasetComparison>,
+    /// The output as the requested noise model records it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detector: Option<Detector>,
+    pub cache: CacheStatus,
 }
 
+/// Whether a run was answered from the result cache
 #[derive(Serialize)]
-pub struct SimulationResult {
-    pub id: String,
+pub struct CacheStatus {
+    pub hit: bool,
+    /// Simulation ID and hash of the canonical parameters
+    pub key: String,
+}
+
+#[derive(Deserialize)]
+pub struct SweepRequest {
+    /// Name of the parameter to vary
+    pub parameter: String,
+    pub start: f64,
+    pub stop: f64,
+    pub step: f64,
+    /// Values for the other parameters, held fixed across the sweep
+    #[serde(default)]
+    pub parameters: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Serialize)]
+pub struct SweepResult {
     pub simulation_id: String,
+    pub parameter: String,
+    /// Seed shared by every point of a stochastic simulation's sweep
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    pub points: Vec<SweepPoint>,
+    pub computed_at: String,
+}
+
+#[derive(Serialize)]
+pub struct SweepPoint {
+    pub value: f64,
     pub data: serde_json::Value,
+}
+
+#[derive(Deserialize)]
+pub struct CompareRequest {
+    /// Parameter sets to run; values left out take their defaults
+    pub runs: Vec<serde_json::Map<String, serde_json::Value>>,
+}
+
+#[derive(Serialize)]
+pub struct CompareResult {
+    pub simulation_id: String,
+    pub runs: Vec<CompareRun>,
+    /// Parameters whose value is not the same in every run
+    pub varied: Vec<String>,
+    /// Changes of numeric outputs relative to the first run, by dotted path
+    pub differences: serde_json::Map<String, serde_json::Value>,
     pub computed_at: String,
 }
+
+#[derive(Serialize)]
+pub struct CompareRun {
+    /// Validated parameters, with defaults filled in
+    pub parameters: serde_json::Map<String, serde_json::Value>,
+    pub data: serde_json::Value,
+}
+
+/// Run every parameter set in parallel under one shared time limit. Points
+/// not yet started once the time is up are skipped.
+fn run_within_budget(
+    simulation: &dyn Simulation,
+    points: Vec<Params>,
+) -> Result<Vec<serde_json::Value>, SimulationError> {
+    let progress = Progress::default().with_time_limit(REQUEST_TIME_LIMIT);
+    let span = tracing::Span::current();
+    par_map(points, |params| {
+        span.in_scope(|| {
+            progress.check()?;
+            simulation.run_with_progress(&params, &progress)
+        })
+    })
+    .into_iter()
+    .collect()
+}
+
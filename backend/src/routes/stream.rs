@@ -0,0 +1,248 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::Response,
+    Extension,
+};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tracing::Instrument;
+
+use crate::routes::error::ApiError;
+use crate::services::rate_limit::{Budget, Client, RateLimiter};
+use crate::services::trace;
+use crate::simulations::params::{ParameterError, Params};
+use crate::simulations::{FrameStream, Simulation, SimulationError};
+use crate::state::AppState;
+use crate::websocket::{Message, WebSocket, WebSocketUpgrade, WebSocketWriter};
+
+/// Stream frames of a time-evolving simulation over a WebSocket.
+///
+/// Initial parameters come from the query string. Once connected the client
+/// can send `{"action": "pause"}`, `{"action": "resume"}` or
+/// `{"action": "set_parameters", "parameters": {...}}` to restart the run.
+/// Each restart draws on the client's compute budget like a new run; one
+/// over the limit is refused with an error message and the current run
+/// carries on.
+pub async fn stream_simulation(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<HashMap<String, String>>,
+    client: Option<Extension<Client>>,
+    ws: WebSocketUpgrade,
+) -> Result<Response, ApiError> {
+    let simulation = state.simulations.get(&id).ok_or_else(|| ApiError::not_found("simulation", &id))?;
+    let params = Params::validate(&simulation.parameters(), &query_parameters(&query))
+        .map_err(ApiError::InvalidParameters)?;
+    // Setting up a stream can take a while (initial states, lattices)
+    let worker = simulation.clone();
+    let setup = params.clone();
+    let stream = trace::spawn_blocking(move || worker.stream(&setup))
+        .await
+        .map_err(|e| {
+            tracing::error!("Stream setup of {} failed: {}", id, e);
+            ApiError::Internal
+        })??
+        .ok_or(ApiError::Unsupported("simulation does not produce a frame stream"))?;
+
+    // The session outlives the handler; keep logging under the request's span
+    let span = tracing::Span::current();
+    let restarts = Restarts {
+        limiter: state.rate_limiter.clone(),
+        client: client.map(|Extension(client)| client),
+    };
+    Ok(ws.on_upgrade(move |socket| run_session(socket, simulation, params, stream, restarts).instrument(span)))
+}
+
+/// Where restarts of a session are counted
+struct Restarts {
+    limiter: Arc<RateLimiter>,
+    /// Absent when the rate limits do not apply, e.g. not served over TCP
+    client: Option<Client>,
+}
+
+impl Restarts {
+    /// Take a restart from the compute budget, or describe how long to wait
+    fn allow(&self) -> Result<(), Value> {
+        let Some(client) = &self.client else { return Ok(()) };
+        self.limiter.check(client.clone(), Budget::Compute).map_err(|wait| {
+            let seconds = wait.as_secs_f64().ceil().max(1.0) as u64;
+            serde_json::json!({
+                "type": "error",
+                "message": format!("Too many restarts; try again in {seconds} s"),
+                "retry_after": seconds,
+            })
+        })
+    }
+}
+
+/// Query strings carry everything as text; recover booleans and numbers
+pub fn query_parameters(query: &HashMap<String, String>) -> Map<String, Value> {
+    query
+        .iter()
+        .map(|(name, raw)| {
+            let value = match raw.as_str() {
+                "true" => Value::Bool(true),
+                "false" => Value::Bool(false),
+                _ => raw
+                    .parse::<f64>()
+                    .map(Value::from)
+                    .unwrap_or_else(|_| Value::String(raw.clone())),
+            };
+            (name.clone(), value)
+        })
+        .collect()
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+enum StreamControl {
+    Pause,
+    Resume,
+    SetParameters { parameters: Map<String, Value> },
+}
+
+async fn run_session(
+    socket: WebSocket,
+    simulation: Arc<dyn Simulation>,
+    params: Params,
+    stream: Box<dyn FrameStream>,
+    restarts: Restarts,
+) {
+    let (mut reader, mut writer) = socket.split();
+    let (tx, mut rx) = mpsc::channel(16);
+    let reader_task = tokio::spawn(async move {
+        while let Some(message) = reader.recv().await {
+            if tx.send(message).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    if let Err(e) = drive_session(&mut writer, &mut rx, simulation, params, stream, restarts).await {
+        tracing::debug!("Simulation stream closed: {}", e);
+    }
+    reader_task.abort();
+}
+
+async fn drive_session(
+    writer: &mut WebSocketWriter,
+    rx: &mut mpsc::Receiver<Message>,
+    simulation: Arc<dyn Simulation>,
+    params: Params,
+    mut stream: Box<dyn FrameStream>,
+    restarts: Restarts,
+) -> std::io::Result<()> {
+    send(writer, started_message(&params, stream.as_ref())).await?;
+    let mut running = true;
+
+    loop {
+        // While running, only pick up messages that have already arrived so
+        // frames keep flowing; while paused or finished, wait for the client.
+        let message = if running {
+            match rx.try_recv() {
+                Ok(message) => Some(message),
+                Err(mpsc::error::TryRecvError::Empty) => None,
+                Err(mpsc::error::TryRecvError::Disconnected) => return Ok(()),
+            }
+        } else {
+            match rx.recv().await {
+                Some(message) => Some(message),
+                None => return Ok(()),
+            }
+        };
+
+        if let Some(message) = message {
+            match message {
+                Message::Text(text) => match serde_json::from_str::<StreamControl>(&text) {
+                    Ok(StreamControl::Pause) => {
+                        running = false;
+                        send(writer, serde_json::json!({ "type": "paused" })).await?;
+                    }
+                    Ok(StreamControl::Resume) => {
+                        running = true;
+                        send(writer, serde_json::json!({ "type": "resumed" })).await?;
+                    }
+                    Ok(StreamControl::SetParameters { parameters }) => {
+                        let restarted = restarts
+                            .allow()
+                            .and_then(|()| tokio::task::block_in_place(|| restart(simulation.as_ref(), &parameters)));
+                        match restarted {
+                            Ok((params, new_stream)) => {
+                                stream = new_stream;
+                                running = true;
+                                send(writer, started_message(&params, stream.as_ref())).await?;
+                            }
+                            Err(error) => send(writer, error).await?,
+                        }
+                    }
+                    Err(e) => {
+                        send(writer, serde_json::json!({
+                            "type": "error",
+                            "message": format!("unrecognized control message: {}", e),
+                        }))
+                        .await?
+                    }
+                },
+                Message::Ping(payload) => writer.send_pong(&payload).await?,
+                Message::Close => return writer.close().await,
+                Message::ProtocolError(reason) => {
+                    tracing::debug!("WebSocket protocol error: {}", reason);
+                    return writer.close_protocol_error().await;
+                }
+                Message::Binary | Message::Pong => {}
+            }
+            continue;
+        }
+
+        // Frames can take a while to compute; keep the async workers free
+        match tokio::task::block_in_place(|| stream.next_frame()) {
+            Some(frame) => send(writer, serde_json::json!({ "type": "frame", "frame": frame })).await?,
+            None => {
+                running = false;
+                send(writer, serde_json::json!({ "type": "finished" })).await?;
+            }
+        }
+    }
+}
+
+/// Validate new parameters and build a fresh stream, or describe the failure
+fn restart(
+    simulation: &dyn Simulation,
+    raw: &Map<String, Value>,
+) -> Result<(Params, Box<dyn FrameStream>), Value> {
+    let invalid = |fields: Vec<ParameterError>| {
+        serde_json::json!({
+            "type": "error",
+            "message": "One or more simulation parameters are invalid",
+            "fields": fields,
+        })
+    };
+    let params = Params::validate(&simulation.parameters(), raw).map_err(invalid)?;
+    match simulation.stream(&params) {
+        Ok(Some(stream)) => Ok((params, stream)),
+        Ok(None) => Err(serde_json::json!({
+            "type": "error",
+            "message": "Simulation does not support streaming",
+        })),
+        Err(SimulationError::InvalidParameters(fields)) => Err(invalid(fields)),
+        Err(other) => Err(serde_json::json!({
+            "type": "error",
+            "message": other.to_string(),
+        })),
+    }
+}
+
+fn started_message(params: &Params, stream: &dyn FrameStream) -> Value {
+    serde_json::json!({
+        "type": "started",
+        "parameters": params.values(),
+        "header": stream.header(),
+    })
+}
+
+async fn send(writer: &mut WebSocketWriter, message: Value) -> std::io::Result<()> {
+    writer.send_text(&message.to_string()).await
+}
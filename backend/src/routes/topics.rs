@@ -0,0 +1,65 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use serde::Serialize;
+
+use crate::models::topic::{find_topic, topics_for_labels, Topic, TOPICS};
+use crate::routes::error::ApiError;
+use crate::routes::simulations::CatalogEntry;
+use crate::state::AppState;
+
+/// A topic with its place in the tree
+#[derive(Serialize)]
+pub struct TopicView {
+    #[serde(flatten)]
+    pub topic: &'static Topic,
+    pub children: Vec<&'static str>,
+    /// Topic IDs from the root down to this one, for breadcrumbs
+    pub path: Vec<&'static str>,
+    /// Simulations under this topic or any of its subtopics
+    pub simulation_count: usize,
+}
+
+#[derive(Serialize)]
+pub struct TopicSimulations {
+    pub topic: TopicView,
+    pub simulations: Vec<CatalogEntry>,
+}
+
+/// List the topic taxonomy, parents before their children
+pub async fn list_topics(State(state): State<AppState>) -> Json<Vec<TopicView>> {
+    Json(TOPICS.iter().map(|topic| view(&state, topic)).collect())
+}
+
+/// Simulations filed under a topic or any of its subtopics
+pub async fn topic_simulations(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<TopicSimulations>, ApiError> {
+    let topic = find_topic(&id).ok_or_else(|| ApiError::not_found("topic", &id))?;
+    let simulations = state
+        .simulations
+        .iter()
+        .filter(|s| covers(topic, &s.info().topics))
+        .map(|s| CatalogEntry::new(s.info(), s.prerequisites()))
+        .collect();
+    Ok(Json(TopicSimulations {
+        topic: view(&state, topic),
+        simulations,
+    }))
+}
+
+fn view(state: &AppState, topic: &'static Topic) -> TopicView {
+    TopicView {
+        topic,
+        children: topic.children().map(|child| child.id).collect(),
+        path: topic.path(),
+        simulation_count: state.simulations.iter().filter(|s| covers(topic, &s.info().topics)).count(),
+    }
+}
+
+/// Whether any of a simulation's topic labels falls under `topic`
+fn covers(topic: &Topic, labels: &[String]) -> bool {
+    topics_for_labels(labels).iter().any(|t| topic.contains(t))
+}
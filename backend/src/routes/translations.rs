@@ -0,0 +1,168 @@
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Path, Query, State},
+    http::{header, request::Parts, HeaderName, StatusCode},
+    Json,
+};
+use chrono::Utc;
+use serde::Deserialize;
+
+use crate::models::translation::Translation;
+use crate::routes::auth::AdminUser;
+use crate::routes::error::ApiError;
+use crate::services::i18n::{fallback_chain, normalize_locale, parse_accept_language, Localizer, DEFAULT_LOCALE};
+use crate::simulations::params::ParameterError;
+use crate::state::AppState;
+
+/// Longest translated name (characters)
+const MAX_NAME_LENGTH: usize = 200;
+/// Longest translated description (characters)
+const MAX_DESCRIPTION_LENGTH: usize = 2_000;
+/// Longest translated theory section (characters)
+const MAX_THEORY_LENGTH: usize = 100_000;
+
+/// Locales the client asked for, most preferred first: the comma-separated
+/// `?lang=` if given, otherwise `Accept-Language`
+pub struct RequestedLocales(pub Vec<String>);
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for RequestedLocales {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let lang = Query::<LangQuery>::try_from_uri(&parts.uri).ok().and_then(|query| query.0.lang);
+        if let Some(lang) = lang {
+            let locales: Vec<String> = lang.split(',').filter_map(normalize_locale).collect();
+            if !locales.is_empty() {
+                return Ok(Self(locales));
+            }
+        }
+        let header = parts
+            .headers
+            .get(header::ACCEPT_LANGUAGE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("");
+        Ok(Self(parse_accept_language(header)))
+    }
+}
+
+/// Load the translations along the requested locales' fallback chain
+pub async fn localizer(state: &AppState, locales: &RequestedLocales) -> Result<Localizer, ApiError> {
+    let chain = fallback_chain(&locales.0);
+    let stored: Vec<String> = chain.iter().filter(|locale| *locale != DEFAULT_LOCALE).cloned().collect();
+    let translations = Translation::for_locales(&state.db, &stored).await?;
+    Ok(Localizer::new(chain, translations))
+}
+
+/// `Content-Language` of a localized response, which varies with
+/// `Accept-Language`
+pub fn language_headers(locale: &str) -> [(HeaderName, String); 2] {
+    [
+        (header::CONTENT_LANGUAGE, locale.to_string()),
+        (header::VARY, header::ACCEPT_LANGUAGE.to_string()),
+    ]
+}
+
+/// Every uploaded translation of a simulation
+pub async fn list_translations(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<Translation>>, ApiError> {
+    state.simulations.get(&id).ok_or_else(|| ApiError::not_found("simulation", &id))?;
+    Translation::for_simulation(&state.db, &id).await.map(Json).map_err(ApiError::from)
+}
+
+/// Upload a simulation's text in one locale, replacing any earlier upload
+pub async fn put_translation(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    Path((id, locale)): Path<(String, String)>,
+    Json(request): Json<TranslationRequest>,
+) -> Result<Json<Translation>, ApiError> {
+    let simulation = state.simulations.get(&id).ok_or_else(|| ApiError::not_found("simulation", &id))?;
+    let parameters = simulation.parameters();
+
+    let mut errors = Vec::new();
+    let normalized = normalize_locale(&locale);
+    match &normalized {
+        None => {
+            errors.push(ParameterError::new("locale", "must be a BCP 47 language tag", Some(&locale.as_str().into())))
+        }
+        Some(tag) if tag == DEFAULT_LOCALE => {
+            errors.push(ParameterError::new("locale", "English is built in", Some(&locale.as_str().into())))
+        }
+        Some(_) => {}
+    }
+    for (field, text, limit) in [
+        ("name", &request.name, MAX_NAME_LENGTH),
+        ("description", &request.description, MAX_DESCRIPTION_LENGTH),
+        ("theory", &request.theory, MAX_THEORY_LENGTH),
+    ] {
+        if text.as_ref().is_some_and(|text| text.trim().is_empty() || text.chars().count() > limit) {
+            errors.push(ParameterError::new(field, format!("must be 1 to {limit} characters"), None));
+        }
+    }
+    for (name, label) in &request.parameter_labels {
+        let field = format!("parameter_labels.{name}");
+        if !parameters.iter().any(|p| &p.name == name) {
+            errors.push(ParameterError::new(&field, "not a parameter of this simulation", None));
+        } else if label.trim().is_empty() || label.chars().count() > MAX_NAME_LENGTH {
+            errors.push(ParameterError::new(&field, format!("must be 1 to {MAX_NAME_LENGTH} characters"), None));
+        }
+    }
+    if request.name.is_none()
+        && request.description.is_none()
+        && request.theory.is_none()
+        && request.parameter_labels.is_empty()
+    {
+        errors.push(ParameterError::new("theory", "a translation needs at least one field", None));
+    }
+    if !errors.is_empty() {
+        return Err(ApiError::InvalidParameters(errors));
+    }
+
+    let translation = Translation {
+        simulation_id: id,
+        locale: normalized.unwrap_or_default(),
+        name: request.name,
+        description: request.description,
+        theory: request.theory,
+        parameter_labels: request.parameter_labels,
+        updated_at: Utc::now(),
+    };
+    translation.upsert(&state.db).await?;
+    tracing::info!("Translation {}/{} uploaded by {}", translation.simulation_id, translation.locale, admin.id);
+    Ok(Json(translation))
+}
+
+/// Remove a translation; the locale falls back along its chain again
+pub async fn delete_translation(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    Path((id, locale)): Path<(String, String)>,
+) -> Result<StatusCode, ApiError> {
+    let normalized = normalize_locale(&locale).unwrap_or_default();
+    if Translation::delete(&state.db, &id, &normalized).await? {
+        tracing::info!("Translation {}/{} deleted by {}", id, normalized, admin.id);
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(ApiError::not_found("translation", &format!("{id}/{locale}")))
+    }
+}
+
+#[derive(Deserialize)]
+struct LangQuery {
+    lang: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct TranslationRequest {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub theory: Option<String>,
+    #[serde(default)]
+    pub parameter_labels: BTreeMap<String, String>,
+}
@@ -0,0 +1,161 @@
+use std::convert::Infallible;
+
+use axum::{
+    extract::State,
+    response::sse::{Event, KeepAlive, Sse},
+    Json,
+};
+use futures_util::stream::{self, Stream};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+use crate::models::simulation::SimulationResult;
+use crate::routes::auth::AuthUser;
+use crate::routes::error::ApiError;
+use crate::routes::simulations::derived_quantities;
+use crate::routes::translations::{localizer, RequestedLocales};
+use crate::services::tutor::{result_summary, system_prompt, ChatMessage, Reply, TutorError};
+use crate::simulations::params::{ParameterError, Params};
+use crate::state::AppState;
+
+/// Most messages of a conversation sent with one question
+const MAX_MESSAGES: usize = 20;
+/// Longest message (characters)
+const MAX_MESSAGE_LENGTH: usize = 4_000;
+
+/// Ask the AI tutor about a simulation.
+///
+/// The conversation is sent to the configured model behind a prompt holding
+/// the simulation's theory (translated like the details), the student's
+/// parameter values and a summary of the result they last computed, and the
+/// answer is streamed back as Server-Sent Events: `delta` events with the
+/// next piece of text as it is written, then `done` naming the model, or
+/// `error` if the model fails partway.
+pub async fn chat(
+    State(state): State<AppState>,
+    _user: AuthUser,
+    locales: RequestedLocales,
+    Json(request): Json<ChatRequest>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ApiError> {
+    if !state.tutor.is_enabled() {
+        return Err(TutorError::NotConfigured.into());
+    }
+    check_messages(&request.messages)?;
+    let simulation = state
+        .simulations
+        .get(&request.simulation_id)
+        .ok_or_else(|| ApiError::not_found("simulation", &request.simulation_id))?;
+    let mut details = simulation.details();
+    localizer(&state, &locales).await?.details(&mut details);
+
+    let result = match &request.result_id {
+        Some(id) => {
+            let result = SimulationResult::find(&state.db, id)
+                .await?
+                .ok_or_else(|| ApiError::not_found("result", id))?;
+            if result.simulation_id != simulation.id() {
+                return Err(ApiError::InvalidParameters(vec![ParameterError::new(
+                    "result_id",
+                    format!("is a result of {}, not {}", result.simulation_id, simulation.id()),
+                    Some(&json!(id)),
+                )]));
+            }
+            Some(result)
+        }
+        None => None,
+    };
+
+    // The parameters the student has set, else those of the result
+    let raw = match (&request.parameters, &result) {
+        (Some(parameters), _) => parameters.clone(),
+        (None, Some(result)) => result.parameters.as_object().cloned().unwrap_or_default(),
+        (None, None) => Map::new(),
+    };
+    let params = Params::validate(&simulation.parameters(), &raw).map_err(ApiError::InvalidParameters)?;
+    let summary = result.as_ref().map(|result| {
+        let stored = result.parameters.as_object().cloned().unwrap_or_default();
+        let derived = Params::validate(&simulation.parameters(), &stored)
+            .map(|stored| derived_quantities(simulation.as_ref(), &stored, &result.data))
+            .unwrap_or_default();
+        result_summary(&result.data, &derived)
+    });
+
+    let mut messages = vec![ChatMessage {
+        role: "system".to_string(),
+        content: system_prompt(&details, params.values(), summary.as_deref()),
+    }];
+    messages.extend(request.messages);
+    let reply = state.tutor.chat(&messages).await?;
+
+    let model = state.tutor.model().unwrap_or_default().to_string();
+    let events = stream::unfold(Some(reply), move |reply: Option<Reply>| {
+        let model = model.clone();
+        async move {
+            let mut reply = reply?;
+            let event = match reply.next().await {
+                Ok(Some(piece)) => {
+                    let event = Event::default().event("delta").data(json!({ "content": piece }).to_string());
+                    return Some((Ok(event), Some(reply)));
+                }
+                Ok(None) => Event::default().event("done").data(json!({ "model": model }).to_string()),
+                Err(err) => {
+                    tracing::warn!("{}", err);
+                    Event::default().event("error").data(json!({ "detail": err.to_string() }).to_string())
+                }
+            };
+            Some((Ok(event), None))
+        }
+    });
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+/// Every problem with a conversation: it holds 1 to `MAX_MESSAGES` of the
+/// student's messages and the tutor's earlier answers, and ends with the
+/// student's question
+fn check_messages(messages: &[ChatMessage]) -> Result<(), ApiError> {
+    let mut errors = Vec::new();
+    if messages.is_empty() || messages.len() > MAX_MESSAGES {
+        errors.push(ParameterError::new(
+            "messages",
+            format!("must hold 1 to {MAX_MESSAGES} messages"),
+            Some(&messages.len().into()),
+        ));
+    }
+    for (i, message) in messages.iter().enumerate() {
+        let field = format!("messages[{i}]");
+        if message.role != "user" && message.role != "assistant" {
+            errors.push(ParameterError::new(
+                &format!("{field}.role"),
+                "must be user or assistant",
+                Some(&json!(message.role)),
+            ));
+        }
+        let length = message.content.trim().chars().count();
+        if length == 0 || length > MAX_MESSAGE_LENGTH {
+            errors.push(ParameterError::new(
+                &format!("{field}.content"),
+                format!("must be 1 to {MAX_MESSAGE_LENGTH} characters"),
+                None,
+            ));
+        }
+    }
+    if messages.last().is_some_and(|message| message.role != "user") {
+        errors.push(ParameterError::new("messages", "must end with the student's question", None));
+    }
+    if errors.is_empty() { Ok(()) } else { Err(ApiError::InvalidParameters(errors)) }
+}
+
+#[derive(Deserialize)]
+pub struct ChatRequest {
+    /// Simulation the student has open
+    pub simulation_id: String,
+    /// The conversation so far, oldest first, ending with the question
+    pub messages: Vec<ChatMessage>,
+    /// Parameter values the student has set; defaults fill in the rest.
+    /// Those of the result when left out.
+    #[serde(default)]
+    pub parameters: Option<Map<String, Value>>,
+    /// Stored result the student last computed
+    #[serde(default)]
+    pub result_id: Option<String>,
+}
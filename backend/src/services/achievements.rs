@@ -0,0 +1,268 @@
+// Badges, streaks and experience points
+//
+// Simulation runs and graded quizzes of signed-in learners are turned into
+// events. Each event earns experience points (XP), at most once a day for
+// each kind of event and simulation, and is checked against the badge
+// definitions in `content/badges/<id>.toml`. A badge names the kind of
+// event it watches, optionally a simulation, and a `when` formula in the
+// expression language over the event's values; it is earned once the
+// formula is positive on `count` different simulations. Runs offer their
+// numeric parameters (toggles as 0 or 1) and derived quantities, quizzes
+// `score`, `correct`, `total` and `hints_used`.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use chrono::{Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::SqlitePool;
+
+use crate::models::achievement::{self, Standing};
+use crate::models::quiz::QuizResult;
+use crate::services::expression::Expression;
+use crate::services::toml;
+use crate::simulations::catalog::CatalogError;
+use crate::simulations::{Derived, SimulationRegistry};
+
+pub const RUN: &str = "run";
+pub const QUIZ: &str = "quiz";
+pub const EVENTS: [&str; 2] = [RUN, QUIZ];
+/// Values a quiz event offers to badge formulas
+const QUIZ_VALUES: [&str; 4] = ["score", "correct", "total", "hints_used"];
+/// XP for running a simulation
+const RUN_XP: u32 = 5;
+/// XP for a perfect quiz; lower scores earn their share
+const QUIZ_XP: f64 = 20.0;
+
+/// A badge as defined in its content file
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Badge {
+    /// Taken from the file name
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    /// An emoji
+    pub icon: String,
+    /// Points the badge is worth
+    #[serde(default)]
+    pub xp: u32,
+    /// `run` or `quiz`
+    pub event: String,
+    /// Only events on this simulation count; any simulation when absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub simulation: Option<String>,
+    /// Formula over the event's values that must be positive; every event
+    /// counts when absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub when: Option<String>,
+    /// Different simulations the condition must be met on
+    #[serde(default = "one")]
+    pub count: u32,
+    #[serde(skip)]
+    condition: Option<Expression>,
+}
+
+fn one() -> u32 {
+    1
+}
+
+impl Badge {
+    /// Problems with the definition
+    fn problems(&mut self, registry: &SimulationRegistry) -> Vec<String> {
+        let mut problems = Vec::new();
+        if !EVENTS.contains(&self.event.as_str()) {
+            problems.push(format!("event must be one of: {}", EVENTS.join(", ")));
+        }
+        if let Some(simulation) = &self.simulation {
+            if registry.get_any(simulation).is_none() {
+                problems.push(format!("simulation {simulation} does not exist"));
+            }
+            if self.count != 1 {
+                problems.push("count must be 1 for a badge on one simulation".to_string());
+            }
+        }
+        if self.count == 0 {
+            problems.push("count must be at least 1".to_string());
+        }
+        if let Some(when) = &self.when {
+            match Expression::parse(when) {
+                Ok(expression) => {
+                    if self.event == QUIZ {
+                        let unknown: Vec<&str> =
+                            expression.variables().into_iter().filter(|name| !QUIZ_VALUES.contains(name)).collect();
+                        if !unknown.is_empty() {
+                            problems.push(format!(
+                                "when uses {}; quiz badges may use {}",
+                                unknown.join(", "),
+                                QUIZ_VALUES.join(", ")
+                            ));
+                        }
+                    }
+                    self.condition = Some(expression);
+                }
+                Err(message) => problems.push(format!("when {message}")),
+            }
+        }
+        problems
+    }
+
+    fn matches(&self, event: &Event) -> bool {
+        self.event == event.kind
+            && self.simulation.as_ref().is_none_or(|simulation| *simulation == event.simulation_id)
+            && self.condition.as_ref().is_none_or(|condition| condition.eval(&event.values) > 0.0)
+    }
+}
+
+/// Something a learner did that may earn points and badges
+pub struct Event {
+    /// One of `EVENTS`
+    kind: &'static str,
+    simulation_id: String,
+    values: BTreeMap<String, f64>,
+    xp: u32,
+}
+
+impl Event {
+    /// A simulation run with its validated parameters and derived quantities
+    pub fn run(simulation_id: &str, parameters: &Map<String, Value>, derived: &[Derived]) -> Self {
+        let mut values: BTreeMap<String, f64> = parameters
+            .iter()
+            .filter_map(|(name, value)| {
+                let number = match value {
+                    Value::Bool(on) => f64::from(u8::from(*on)),
+                    other => other.as_f64()?,
+                };
+                Some((name.clone(), number))
+            })
+            .collect();
+        values.extend(derived.iter().map(|quantity| (quantity.name.clone(), quantity.value)));
+        Self {
+            kind: RUN,
+            simulation_id: simulation_id.to_string(),
+            values,
+            xp: RUN_XP,
+        }
+    }
+
+    /// A graded quiz
+    pub fn quiz(result: &QuizResult) -> Self {
+        let values = BTreeMap::from([
+            ("score".to_string(), result.score),
+            ("correct".to_string(), result.correct as f64),
+            ("total".to_string(), result.total as f64),
+            ("hints_used".to_string(), f64::from(result.hints_used)),
+        ]);
+        Self {
+            kind: QUIZ,
+            simulation_id: result.simulation_id.clone(),
+            values,
+            xp: (QUIZ_XP * result.score / 100.0).round() as u32,
+        }
+    }
+}
+
+/// Every badge definition
+#[derive(Default)]
+pub struct Achievements {
+    badges: Vec<Badge>,
+}
+
+impl Achievements {
+    /// Read every `<id>.toml` in `dir`; a missing directory means no badges
+    pub fn load(dir: &Path, registry: &SimulationRegistry) -> Result<Self, CatalogError> {
+        let mut errors = CatalogError::default();
+        let mut badges = Vec::new();
+        let Ok(listing) = fs::read_dir(dir) else {
+            return Ok(Self::default());
+        };
+        let mut files: Vec<_> = listing
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+            .collect();
+        files.sort();
+        for path in files {
+            let (Some(id), Some(file)) = (path.file_stem(), path.file_name()) else { continue };
+            let (id, file) = (id.to_string_lossy().to_string(), file.to_string_lossy().to_string());
+            let badge = fs::read_to_string(&path)
+                .map_err(|err| err.to_string())
+                .and_then(|text| toml::parse(&text).map_err(|err| err.to_string()))
+                .and_then(|value| serde_json::from_value::<Badge>(value).map_err(|err| err.to_string()));
+            match badge {
+                Ok(mut badge) => {
+                    if !badge.id.is_empty() && badge.id != id {
+                        errors.push(&file, format!("id must be \"{id}\", the file name"));
+                        continue;
+                    }
+                    badge.id = id;
+                    let problems = badge.problems(registry);
+                    if problems.is_empty() {
+                        badges.push(badge);
+                    }
+                    for problem in problems {
+                        errors.push(&file, problem);
+                    }
+                }
+                Err(message) => errors.push(&file, message),
+            }
+        }
+        if errors.problems.is_empty() {
+            Ok(Self { badges })
+        } else {
+            Err(errors)
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Badge> {
+        self.badges.iter()
+    }
+
+    /// Award a learner the points of an event and any badges it completes,
+    /// returning the badges newly earned
+    pub async fn record(&self, pool: &SqlitePool, user_id: &str, event: &Event) -> Result<Vec<&Badge>, sqlx::Error> {
+        let today = Utc::now().date_naive();
+        achievement::record_activity(pool, user_id, today, event.kind, &event.simulation_id, event.xp).await?;
+        let mut earned = Vec::new();
+        for badge in self.badges.iter().filter(|badge| badge.matches(event)) {
+            let count = achievement::count_towards(pool, user_id, &badge.id, &event.simulation_id).await?;
+            if count >= badge.count && achievement::award(pool, user_id, &badge.id, badge.xp).await? {
+                earned.push(badge);
+            }
+        }
+        Ok(earned)
+    }
+}
+
+/// Consecutive days of activity
+#[derive(Serialize)]
+pub struct Streak {
+    /// Days in the streak that ends today or yesterday; 0 if there is none
+    pub current: u32,
+    pub longest: u32,
+    pub last_active: Option<NaiveDate>,
+}
+
+/// Streaks in a learner's active days, oldest first
+pub fn streak(standing: &Standing) -> Streak {
+    let days = &standing.active_days;
+    let mut longest = 0;
+    let mut run = 0;
+    for (i, day) in days.iter().enumerate() {
+        run = if i > 0 && days[i - 1] + Duration::days(1) == *day { run + 1 } else { 1 };
+        longest = longest.max(run);
+    }
+    let today = Utc::now().date_naive();
+    let last_active = days.last().copied();
+    let current = match last_active {
+        Some(day) if day + Duration::days(1) >= today => run,
+        _ => 0,
+    };
+    Streak {
+        current,
+        longest,
+        last_active,
+    }
+}
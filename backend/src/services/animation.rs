@@ -0,0 +1,254 @@
+// Server-side rendering of time-dependent results as animation frames
+
+use serde_json::Value;
+
+/// Frame size in pixels
+pub const WIDTH: u16 = 640;
+pub const HEIGHT: u16 = 360;
+
+/// Longest animation rendered, in frames
+const MAX_FRAMES: usize = 500;
+/// Margin around the plot area (px)
+const MARGIN: i32 = 20;
+/// Output frames of bob trail drawn behind each pendulum
+const TRAIL_FRAMES: usize = 30;
+
+pub const PALETTE: [[u8; 3]; 8] = [
+    [255, 255, 255], // background
+    [40, 40, 40],    // axes, rods
+    [215, 215, 215], // grid, progress track
+    [150, 150, 150], // potential
+    [31, 119, 180],  // main curve, first pendulum
+    [214, 39, 40],   // second pendulum
+    [174, 199, 232], // trail of the first pendulum
+    [255, 152, 150], // trail of the second pendulum
+];
+
+const BACKGROUND: u8 = 0;
+const AXIS: u8 = 1;
+const GRID: u8 = 2;
+const POTENTIAL: u8 = 3;
+const CURVE: u8 = 4;
+const SECOND: u8 = 5;
+const TRAIL: u8 = 6;
+const SECOND_TRAIL: u8 = 7;
+
+/// Indexed-colour frames ready for encoding
+pub struct Animation {
+    pub frames: Vec<Vec<u8>>,
+    /// Time each frame is shown (1/100 s)
+    pub delay: u16,
+}
+
+/// Render the frames of a result, or `None` if it has no time sequence.
+///
+/// Results with `frames` and `x` arrays (wave packets, standing waves, heat
+/// flow) are drawn as an animated curve; double-pendulum results are drawn
+/// as the two swinging pendulums played back in real time.
+pub fn render(data: &Value, fps: u32) -> Option<Animation> {
+    let delay = (100 / fps).max(2) as u16;
+    let frames = if data.get("pendulum_a").is_some() {
+        render_pendulums(data, fps)?
+    } else {
+        render_curves(data)?
+    };
+    Some(Animation { frames, delay })
+}
+
+fn render_curves(data: &Value) -> Option<Vec<Vec<u8>>> {
+    let x = series(data.get("x")?)?;
+    let frames: Vec<Vec<f64>> = data.get("frames")?.as_array()?.iter().map(series).collect::<Option<_>>()?;
+    if x.len() < 2 || frames.is_empty() || frames.iter().any(|f| f.len() != x.len()) {
+        return None;
+    }
+    let potential = data.get("potential").and_then(series).filter(|p| p.len() == x.len());
+
+    let (x_min, x_max) = range(x.iter());
+    let (mut y_min, mut y_max) = range(frames.iter().flatten().chain([&0.0]));
+    let pad = 0.05 * (y_max - y_min).max(f64::EPSILON);
+    y_min -= pad;
+    y_max += pad;
+
+    let left = MARGIN;
+    let right = WIDTH as i32 - MARGIN;
+    let top = MARGIN;
+    let bottom = HEIGHT as i32 - 2 * MARGIN;
+    let to_px = |value: f64, min: f64, max: f64, from: i32, to: i32| {
+        from + ((value - min) / (max - min) * (to - from) as f64).round() as i32
+    };
+    let polyline = |canvas: &mut Canvas, ys: &[f64], min: f64, max: f64, color: u8, thickness: i32| {
+        let points: Vec<(i32, i32)> = x
+            .iter()
+            .zip(ys)
+            .filter(|(_, y)| y.is_finite())
+            .map(|(&x, &y)| (to_px(x, x_min, x_max, left, right), to_px(y, min, max, bottom, top)))
+            .collect();
+        for pair in points.windows(2) {
+            canvas.line(pair[0], pair[1], color, thickness);
+        }
+    };
+
+    let stride = frames.len().div_ceil(MAX_FRAMES);
+    let shown: Vec<&Vec<f64>> = frames.iter().step_by(stride).collect();
+    let rendered = shown
+        .iter()
+        .enumerate()
+        .map(|(i, frame)| {
+            let mut canvas = Canvas::new();
+            if let Some(potential) = &potential {
+                let (p_min, p_max) = range(potential.iter());
+                if p_max > p_min {
+                    polyline(&mut canvas, potential, p_min, p_max + 0.1 * (p_max - p_min), POTENTIAL, 1);
+                }
+            }
+            let zero = to_px(0.0, y_min, y_max, bottom, top);
+            canvas.line((left, zero), (right, zero), GRID, 1);
+            canvas.rectangle((left, top), (right, bottom), AXIS);
+            polyline(&mut canvas, frame, y_min, y_max, CURVE, 2);
+            canvas.progress(i, shown.len());
+            canvas.pixels
+        })
+        .collect();
+    Some(rendered)
+}
+
+fn render_pendulums(data: &Value, fps: u32) -> Option<Vec<Vec<u8>>> {
+    let t = series(data.get("t")?)?;
+    let pendulums: Vec<[Vec<f64>; 4]> = ["pendulum_a", "pendulum_b"]
+        .iter()
+        .map(|name| {
+            let p = data.get(*name)?;
+            Some([series(p.get("x1")?)?, series(p.get("y1")?)?, series(p.get("x2")?)?, series(p.get("y2")?)?])
+        })
+        .collect::<Option<_>>()?;
+    if t.len() < 2 || pendulums.iter().flatten().any(|s| s.len() != t.len()) {
+        return None;
+    }
+
+    let extent = pendulums.iter().flatten().fold(0.0f64, |m, s| s.iter().fold(m, |m, v| m.max(v.abs())));
+    let scale = (HEIGHT as i32 / 2 - MARGIN) as f64 / extent.max(f64::EPSILON);
+    let pivot = (WIDTH as i32 / 2, HEIGHT as i32 / 2);
+    let to_px = |x: f64, y: f64| {
+        (pivot.0 + (x * scale).round() as i32, pivot.1 - (y * scale).round() as i32)
+    };
+
+    // Real-time playback: one output frame every 1/fps seconds
+    let dt = t[1] - t[0];
+    let duration = t[t.len() - 1] - t[0];
+    let count = ((duration * fps as f64) as usize + 1).min(MAX_FRAMES);
+    let samples: Vec<usize> = (0..count)
+        .map(|k| ((k as f64 / fps as f64 / dt).round() as usize).min(t.len() - 1))
+        .collect();
+
+    let rendered = (0..count)
+        .map(|k| {
+            let mut canvas = Canvas::new();
+            let trail = &samples[k.saturating_sub(TRAIL_FRAMES)..=k];
+            // Second pendulum underneath so the first stays visible
+            for (p, (color, trail_color)) in pendulums.iter().zip([(CURVE, TRAIL), (SECOND, SECOND_TRAIL)]).rev() {
+                let [x1, y1, x2, y2] = p;
+                for pair in trail.windows(2) {
+                    canvas.line(to_px(x2[pair[0]], y2[pair[0]]), to_px(x2[pair[1]], y2[pair[1]]), trail_color, 1);
+                }
+                let i = samples[k];
+                let upper = to_px(x1[i], y1[i]);
+                let lower = to_px(x2[i], y2[i]);
+                canvas.line(pivot, upper, AXIS, 2);
+                canvas.line(upper, lower, AXIS, 2);
+                canvas.disc(upper, 6, color);
+                canvas.disc(lower, 6, color);
+            }
+            canvas.disc(pivot, 3, AXIS);
+            canvas.progress(k, count);
+            canvas.pixels
+        })
+        .collect();
+    Some(rendered)
+}
+
+/// Numbers of a JSON array, with nulls as NaN
+fn series(value: &Value) -> Option<Vec<f64>> {
+    value.as_array()?.iter().map(|v| if v.is_null() { Some(f64::NAN) } else { v.as_f64() }).collect()
+}
+
+fn range<'a>(values: impl Iterator<Item = &'a f64>) -> (f64, f64) {
+    let (min, max) = values
+        .filter(|v| v.is_finite())
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+    if min.is_finite() { (min, max) } else { (0.0, 1.0) }
+}
+
+/// Indexed-colour frame with a few drawing primitives
+struct Canvas {
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    fn new() -> Self {
+        Self { pixels: vec![BACKGROUND; WIDTH as usize * HEIGHT as usize] }
+    }
+
+    fn set(&mut self, x: i32, y: i32, color: u8) {
+        if (0..WIDTH as i32).contains(&x) && (0..HEIGHT as i32).contains(&y) {
+            self.pixels[y as usize * WIDTH as usize + x as usize] = color;
+        }
+    }
+
+    /// Bresenham line, stamped with a square pen `thickness` pixels wide
+    fn line(&mut self, from: (i32, i32), to: (i32, i32), color: u8, thickness: i32) {
+        let (mut x, mut y) = from;
+        let dx = (to.0 - x).abs();
+        let dy = -(to.1 - y).abs();
+        let sx = if x < to.0 { 1 } else { -1 };
+        let sy = if y < to.1 { 1 } else { -1 };
+        let mut error = dx + dy;
+        loop {
+            for ox in 0..thickness {
+                for oy in 0..thickness {
+                    self.set(x + ox - thickness / 2, y + oy - thickness / 2, color);
+                }
+            }
+            if (x, y) == to {
+                break;
+            }
+            let e2 = 2 * error;
+            if e2 >= dy {
+                error += dy;
+                x += sx;
+            }
+            if e2 <= dx {
+                error += dx;
+                y += sy;
+            }
+        }
+    }
+
+    fn rectangle(&mut self, corner: (i32, i32), opposite: (i32, i32), color: u8) {
+        let (x0, y0) = corner;
+        let (x1, y1) = opposite;
+        self.line((x0, y0), (x1, y0), color, 1);
+        self.line((x1, y0), (x1, y1), color, 1);
+        self.line((x1, y1), (x0, y1), color, 1);
+        self.line((x0, y1), (x0, y0), color, 1);
+    }
+
+    fn disc(&mut self, centre: (i32, i32), radius: i32, color: u8) {
+        for dy in -radius..=radius {
+            for dx in -radius..=radius {
+                if dx * dx + dy * dy <= radius * radius {
+                    self.set(centre.0 + dx, centre.1 + dy, color);
+                }
+            }
+        }
+    }
+
+    /// Bar along the bottom edge showing how far through the animation we are
+    fn progress(&mut self, frame: usize, frames: usize) {
+        let y = HEIGHT as i32 - MARGIN / 2;
+        let left = MARGIN;
+        let right = WIDTH as i32 - MARGIN;
+        self.line((left, y), (right, y), GRID, 3);
+        let done = left + ((right - left) as f64 * (frame + 1) as f64 / frames as f64).round() as i32;
+        self.line((left, y), (done, y), CURVE, 3);
+    }
+}
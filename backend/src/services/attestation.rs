@@ -0,0 +1,242 @@
+// Signed attestations of simulation results
+//
+// Every computed result carries an Ed25519 signature by this server over
+// the SHA-256 of its claim
+//
+//     {"backend": …, "data": …, "parameters": …, "simulation_id": …, "simulation_version": …}
+//
+// written as RFC 8785 canonical JSON (sorted keys, no whitespace, numbers
+// as JavaScript prints them), where `backend` names the server version and
+// commit. Anyone holding the public key can check that a result was
+// produced by that build and has not been altered. The signing key is
+// configured as `attestation.key` (`ATTESTATION_KEY`); every key the server has signed with is kept, so
+// results stay verifiable after rotation.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+
+use crate::config::AttestationConfig;
+use crate::services::bundle::CODE_COMMIT;
+use crate::services::ed25519::{self, SigningKey, PUBLIC_KEY_LENGTH, SIGNATURE_LENGTH};
+
+pub const ALGORITHM: &str = "Ed25519";
+
+/// The signature attached to a result
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Attestation {
+    pub algorithm: String,
+    /// Key that signed, as listed by the attestation keys endpoint
+    pub key_id: String,
+    /// Server build that computed the result
+    pub backend: String,
+    /// SHA-256 of the claim, hex encoded
+    pub sha256: String,
+    /// Signature of the 32 hash bytes, base64 encoded
+    pub signature: String,
+}
+
+/// The fields of a result an attestation covers
+pub struct Claim<'a> {
+    pub simulation_id: &'a str,
+    pub simulation_version: u32,
+    pub parameters: &'a Value,
+    pub data: &'a Value,
+}
+
+impl Claim<'_> {
+    fn digest(&self, backend: &str) -> [u8; 32] {
+        let claim = json!({
+            "backend": backend,
+            "data": self.data,
+            "parameters": self.parameters,
+            "simulation_id": self.simulation_id,
+            "simulation_version": self.simulation_version,
+        });
+        let mut canonical = String::new();
+        write_canonical(&claim, &mut canonical);
+        Sha256::digest(canonical.as_bytes()).into()
+    }
+}
+
+/// RFC 8785 canonical JSON, so verifiers in any language can rebuild the
+/// exact bytes that were hashed
+fn write_canonical(value: &Value, out: &mut String) {
+    match value {
+        Value::Object(map) => {
+            // Keys sort by UTF-16 code units
+            let mut entries: Vec<(&String, &Value)> = map.iter().collect();
+            entries.sort_by(|a, b| a.0.encode_utf16().cmp(b.0.encode_utf16()));
+            out.push('{');
+            for (i, (key, value)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&Value::from(key.as_str()).to_string());
+                out.push(':');
+                write_canonical(value, out);
+            }
+            out.push('}');
+        }
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical(item, out);
+            }
+            out.push(']');
+        }
+        Value::Number(number) => out.push_str(&javascript_number(number.as_f64().unwrap_or(0.0))),
+        value => out.push_str(&value.to_string()),
+    }
+}
+
+/// A number as JavaScript's `Number.prototype.toString` writes it: the
+/// shortest digits that round-trip, in plain notation from 1e-6 up to 1e21
+/// and in exponent notation outside it
+fn javascript_number(x: f64) -> String {
+    if x == 0.0 || !x.is_finite() {
+        return "0".to_string();
+    }
+    // Shortest round-trip digits and decimal exponent, e.g. "3.017e-5"
+    let scientific = format!("{:e}", x.abs());
+    let (mantissa, exponent) = scientific.split_once('e').unwrap_or((&scientific, "0"));
+    let digits: String = mantissa.chars().filter(|c| c.is_ascii_digit()).collect();
+    let k = digits.len() as i32;
+    let n = exponent.parse::<i32>().unwrap_or(0) + 1;
+
+    let magnitude = if k <= n && n <= 21 {
+        format!("{digits}{}", "0".repeat((n - k) as usize))
+    } else if 0 < n && n <= 21 {
+        format!("{}.{}", &digits[..n as usize], &digits[n as usize..])
+    } else if -6 < n && n <= 0 {
+        format!("0.{}{digits}", "0".repeat(-n as usize))
+    } else {
+        let sign = if n > 0 { "+" } else { "-" };
+        let fraction = if k > 1 { format!(".{}", &digits[1..]) } else { String::new() };
+        format!("{}{fraction}e{sign}{}", &digits[..1], (n - 1).abs())
+    };
+    if x < 0.0 {
+        format!("-{magnitude}")
+    } else {
+        magnitude
+    }
+}
+
+/// Why an attestation does not hold
+#[derive(Debug, PartialEq)]
+pub enum Invalid {
+    Algorithm,
+    /// The result was changed after it was signed
+    Hash,
+    Signature,
+}
+
+impl Invalid {
+    pub fn reason(&self) -> &'static str {
+        match self {
+            Self::Algorithm => "only Ed25519 attestations are supported",
+            Self::Hash => "the result does not match the signed hash",
+            Self::Signature => "the signature is not valid for this key",
+        }
+    }
+}
+
+/// Check an attestation against the result it is attached to and the
+/// public key named by its `key_id`
+pub fn check(attestation: &Attestation, claim: &Claim, public_key: &[u8]) -> Result<(), Invalid> {
+    if attestation.algorithm != ALGORITHM {
+        return Err(Invalid::Algorithm);
+    }
+    let digest = claim.digest(&attestation.backend);
+    if hex(&digest) != attestation.sha256 {
+        return Err(Invalid::Hash);
+    }
+    let public_key: &[u8; PUBLIC_KEY_LENGTH] = public_key.try_into().map_err(|_| Invalid::Signature)?;
+    let signature = STANDARD.decode(&attestation.signature).map_err(|_| Invalid::Signature)?;
+    let signature: &[u8; SIGNATURE_LENGTH] = signature.as_slice().try_into().map_err(|_| Invalid::Signature)?;
+    if ed25519::verify(public_key, &digest, signature) {
+        Ok(())
+    } else {
+        Err(Invalid::Signature)
+    }
+}
+
+/// Signs results with this server's key
+pub struct Attester {
+    key: SigningKey,
+    pub key_id: String,
+    /// Server version and commit recorded in every attestation
+    pub backend: String,
+}
+
+impl Attester {
+    /// The configured key, or a random one for this process when none is
+    /// configured. A configured key that does not decode is fatal;
+    /// `Config::load` reports it before the server gets here.
+    pub fn from_config(config: &AttestationConfig) -> Self {
+        let seed = match config.key.as_deref() {
+            Some(key) => decode_seed(key).expect("attestation.key is not 32 base64-encoded bytes"),
+            None => {
+                tracing::warn!("No attestation key is configured; results are signed with a random key for this process");
+                rand::random()
+            }
+        };
+        Self::new(&seed)
+    }
+
+    pub fn new(seed: &[u8; 32]) -> Self {
+        let key = SigningKey::from_seed(seed);
+        let key_id = hex(&Sha256::digest(key.public_key())[..8]);
+        Self {
+            key,
+            key_id,
+            backend: format!("physics-tutorial-api {} ({})", env!("CARGO_PKG_VERSION"), CODE_COMMIT),
+        }
+    }
+
+    /// Public key, base64 encoded
+    pub fn public_key(&self) -> String {
+        STANDARD.encode(self.key.public_key())
+    }
+
+    pub fn attest(&self, claim: &Claim) -> Attestation {
+        let digest = claim.digest(&self.backend);
+        Attestation {
+            algorithm: ALGORITHM.to_string(),
+            key_id: self.key_id.clone(),
+            backend: self.backend.clone(),
+            sha256: hex(&digest),
+            signature: STANDARD.encode(self.key.sign(&digest)),
+        }
+    }
+
+    /// Attestation of a freshly computed result
+    pub fn attest_run(
+        &self,
+        simulation_id: &str,
+        simulation_version: u32,
+        parameters: &Map<String, Value>,
+        data: &Value,
+    ) -> Attestation {
+        let parameters = Value::Object(parameters.clone());
+        self.attest(&Claim {
+            simulation_id,
+            simulation_version,
+            parameters: &parameters,
+            data,
+        })
+    }
+}
+
+/// Ed25519 seed from 32 base64-encoded bytes
+pub fn decode_seed(key: &str) -> Option<[u8; 32]> {
+    STANDARD.decode(key.trim()).ok().and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
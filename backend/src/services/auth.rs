@@ -0,0 +1,328 @@
+// Authentication primitives: password hashing, signed access tokens and
+// opaque refresh tokens
+
+use std::collections::HashSet;
+
+use base64::{
+    engine::general_purpose::{STANDARD_NO_PAD, URL_SAFE_NO_PAD},
+    Engine,
+};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::config::AuthConfig;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// PBKDF2 work factor (OWASP recommendation for HMAC-SHA256)
+const PBKDF2_ITERATIONS: u32 = 600_000;
+const SALT_LEN: usize = 16;
+const HASH_SCHEME: &str = "pbkdf2-sha256";
+
+/// Lifetime of an access token in seconds
+pub const ACCESS_TOKEN_TTL_SECS: i64 = 15 * 60;
+/// Lifetime of a refresh token in seconds
+pub const REFRESH_TOKEN_TTL_SECS: i64 = 30 * 24 * 60 * 60;
+
+/// Hash a password for storage as `pbkdf2-sha256$<iterations>$<salt>$<hash>`
+pub fn hash_password(password: &str) -> String {
+    let mut salt = [0u8; SALT_LEN];
+    rand::thread_rng().fill_bytes(&mut salt);
+    let hash = pbkdf2_sha256(password.as_bytes(), &salt, PBKDF2_ITERATIONS);
+    format!(
+        "{}${}${}${}",
+        HASH_SCHEME,
+        PBKDF2_ITERATIONS,
+        STANDARD_NO_PAD.encode(salt),
+        STANDARD_NO_PAD.encode(hash)
+    )
+}
+
+/// Check a password against a stored hash in constant time
+pub fn verify_password(password: &str, stored: &str) -> bool {
+    let parts: Vec<&str> = stored.split('$').collect();
+    let [scheme, iterations, salt, hash] = parts.as_slice() else {
+        return false;
+    };
+    if *scheme != HASH_SCHEME {
+        return false;
+    }
+    let (Ok(iterations), Ok(salt), Ok(expected)) = (
+        iterations.parse::<u32>(),
+        STANDARD_NO_PAD.decode(salt),
+        STANDARD_NO_PAD.decode(hash),
+    ) else {
+        return false;
+    };
+    let actual = pbkdf2_sha256(password.as_bytes(), &salt, iterations);
+    constant_time_eq(&actual, &expected)
+}
+
+/// PBKDF2-HMAC-SHA256 producing a single 32-byte block (RFC 8018)
+fn pbkdf2_sha256(password: &[u8], salt: &[u8], iterations: u32) -> [u8; 32] {
+    let keyed = HmacSha256::new_from_slice(password).expect("HMAC accepts keys of any length");
+
+    let mut mac = keyed.clone();
+    mac.update(salt);
+    mac.update(&1u32.to_be_bytes());
+    let mut block: [u8; 32] = mac.finalize().into_bytes().into();
+    let mut output = block;
+
+    for _ in 1..iterations {
+        let mut mac = keyed.clone();
+        mac.update(&block);
+        block = mac.finalize().into_bytes().into();
+        output.iter_mut().zip(block.iter()).for_each(|(o, b)| *o ^= b);
+    }
+    output
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Emails of the accounts with administrator rights, as compared with
+/// account emails
+pub fn admin_emails(config: &AuthConfig) -> HashSet<String> {
+    config
+        .admin_emails
+        .iter()
+        .map(|email| email.trim().to_lowercase())
+        .filter(|email| !email.is_empty())
+        .collect()
+}
+
+/// Claims carried by an access token
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Claims {
+    /// User ID
+    pub sub: String,
+    pub email: String,
+    pub iat: i64,
+    pub exp: i64,
+}
+
+/// Secret used to sign access tokens (HS256 JWTs)
+pub struct TokenKeys {
+    secret: Vec<u8>,
+}
+
+impl TokenKeys {
+    /// The configured signing secret, or a random one. A generated secret
+    /// invalidates all tokens whenever the server restarts.
+    pub fn from_config(config: &AuthConfig) -> Self {
+        match &config.jwt_secret {
+            Some(secret) => Self {
+                secret: secret.clone().into_bytes(),
+            },
+            None => {
+                tracing::warn!("No JWT secret is configured; using a random secret for this process");
+                let mut secret = vec![0u8; 32];
+                rand::thread_rng().fill_bytes(&mut secret);
+                Self { secret }
+            }
+        }
+    }
+
+    /// Issue a signed access token for a user
+    pub fn issue_access_token(&self, user_id: &str, email: &str) -> String {
+        let now = Utc::now().timestamp();
+        let claims = Claims {
+            sub: user_id.to_string(),
+            email: email.to_string(),
+            iat: now,
+            exp: now + ACCESS_TOKEN_TTL_SECS,
+        };
+        let header = URL_SAFE_NO_PAD.encode(br#"{"alg":"HS256","typ":"JWT"}"#);
+        let payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&claims).unwrap_or_default());
+        let signing_input = format!("{}.{}", header, payload);
+        let signature = URL_SAFE_NO_PAD.encode(self.sign(signing_input.as_bytes()));
+        format!("{}.{}", signing_input, signature)
+    }
+
+    /// Verify signature and expiry, returning the claims of a valid token
+    pub fn verify_access_token(&self, token: &str) -> Option<Claims> {
+        let (signing_input, signature) = token.rsplit_once('.')?;
+        let (header, payload) = signing_input.split_once('.')?;
+
+        let header: serde_json::Value = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(header).ok()?).ok()?;
+        if header.get("alg")?.as_str()? != "HS256" {
+            return None;
+        }
+
+        let signature = URL_SAFE_NO_PAD.decode(signature).ok()?;
+        let mut mac = HmacSha256::new_from_slice(&self.secret).ok()?;
+        mac.update(signing_input.as_bytes());
+        mac.verify_slice(&signature).ok()?;
+
+        let claims: Claims = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload).ok()?).ok()?;
+        (claims.exp > Utc::now().timestamp()).then_some(claims)
+    }
+
+    fn sign(&self, data: &[u8]) -> Vec<u8> {
+        let mut mac = HmacSha256::new_from_slice(&self.secret).expect("HMAC accepts keys of any length");
+        mac.update(data);
+        mac.finalize().into_bytes().to_vec()
+    }
+}
+
+/// Generate a random opaque refresh token
+pub fn generate_refresh_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Characters of class join codes, without look-alikes such as 0/O and 1/I
+const JOIN_CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const JOIN_CODE_LENGTH: usize = 8;
+
+/// Generate a short code students type to join a class
+pub fn generate_join_code() -> String {
+    let mut rng = rand::thread_rng();
+    (0..JOIN_CODE_LENGTH)
+        .map(|_| JOIN_CODE_ALPHABET[rng.gen_range(0..JOIN_CODE_ALPHABET.len())] as char)
+        .collect()
+}
+
+/// Refresh tokens are stored hashed so a database leak cannot be replayed
+pub fn hash_refresh_token(token: &str) -> String {
+    hex_encode(&Sha256::digest(token.as_bytes()))
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(bytes: &[u8]) -> String {
+        hex_encode(bytes)
+    }
+
+    #[test]
+    fn pbkdf2_vectors() {
+        // The inputs of RFC 6070 with HMAC-SHA256
+        assert_eq!(
+            hex(&pbkdf2_sha256(b"password", b"salt", 1)),
+            "120fb6cffcf8b32c43e7225256c4f837a86548c92ccc35480805987cb70be17b"
+        );
+        assert_eq!(
+            hex(&pbkdf2_sha256(b"password", b"salt", 2)),
+            "ae4d0c95af6b46d32d0adff928f06dd02a303f8ef3c251dfd6e2d85a95474c43"
+        );
+        assert_eq!(
+            hex(&pbkdf2_sha256(b"password", b"salt", 4096)),
+            "c5e478d59288c841aa530db6845c4c8d962893a001ce4e11a4963873aa98134a"
+        );
+        // RFC 7914 section 11, first 32 bytes of each
+        assert_eq!(
+            hex(&pbkdf2_sha256(b"passwd", b"salt", 1)),
+            "55ac046e56e3089fec1691c22544b605f94185216dde0465e68b9d57c20dacbc"
+        );
+        assert_eq!(
+            hex(&pbkdf2_sha256(b"Password", b"NaCl", 80_000)),
+            "4ddcd8f60b98be21830cee5ef22701f9641a4418d04c0414aeff08876b34ab56"
+        );
+    }
+
+    #[test]
+    fn verifies_passwords() {
+        // At the full work factor once; the cases below use fewer iterations
+        let stored = hash_password("correct horse");
+        assert!(stored.starts_with("pbkdf2-sha256$600000$"));
+        assert!(verify_password("correct horse", &stored));
+
+        let salt = STANDARD_NO_PAD.encode(b"0123456789abcdef");
+        let hash = STANDARD_NO_PAD.encode(pbkdf2_sha256(b"secret", b"0123456789abcdef", 10));
+        assert!(verify_password("secret", &format!("pbkdf2-sha256$10${salt}${hash}")));
+        assert!(!verify_password("secreT", &format!("pbkdf2-sha256$10${salt}${hash}")));
+        assert!(!verify_password("secret", &format!("pbkdf2-sha256$11${salt}${hash}")));
+        assert!(!verify_password("secret", &format!("argon2id$10${salt}${hash}")));
+        assert!(!verify_password("secret", &format!("pbkdf2-sha256$10${salt}")));
+        assert!(!verify_password("secret", &format!("pbkdf2-sha256$10${salt}$!!")));
+        assert!(!verify_password("secret", ""));
+    }
+
+    fn keys(secret: &str) -> TokenKeys {
+        TokenKeys::from_config(&AuthConfig {
+            jwt_secret: Some(secret.to_string()),
+            admin_emails: Vec::new(),
+        })
+    }
+
+    /// A token over any header and claims, signed with `keys`
+    fn token(keys: &TokenKeys, header: &str, claims: &Claims) -> String {
+        let signing_input = format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(header),
+            URL_SAFE_NO_PAD.encode(serde_json::to_vec(claims).unwrap_or_default())
+        );
+        let signature = URL_SAFE_NO_PAD.encode(keys.sign(signing_input.as_bytes()));
+        format!("{signing_input}.{signature}")
+    }
+
+    fn claims(exp: i64) -> Claims {
+        Claims {
+            sub: "user".to_string(),
+            email: "user@example.com".to_string(),
+            iat: exp - ACCESS_TOKEN_TTL_SECS,
+            exp,
+        }
+    }
+
+    #[test]
+    fn accepts_issued_tokens() {
+        let keys = keys("secret");
+        let claims = keys.verify_access_token(&keys.issue_access_token("user", "user@example.com"));
+        let claims = claims.unwrap_or_else(|| panic!("token rejected"));
+        assert_eq!((claims.sub.as_str(), claims.email.as_str()), ("user", "user@example.com"));
+        assert_eq!(claims.exp - claims.iat, ACCESS_TOKEN_TTL_SECS);
+    }
+
+    #[test]
+    fn rejects_tampered_tokens() {
+        let keys = keys("secret");
+        let token = keys.issue_access_token("user", "user@example.com");
+        assert!(self::keys("other secret").verify_access_token(&token).is_none());
+
+        let parts: Vec<&str> = token.split('.').collect();
+        let forged = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&claims(i64::MAX)).unwrap_or_default());
+        assert!(keys.verify_access_token(&format!("{}.{}.{}", parts[0], forged, parts[2])).is_none());
+        let mut signature = URL_SAFE_NO_PAD.decode(parts[2]).unwrap_or_default();
+        signature[0] ^= 1;
+        let altered = format!("{}.{}.{}", parts[0], parts[1], URL_SAFE_NO_PAD.encode(signature));
+        assert!(keys.verify_access_token(&altered).is_none());
+        assert!(keys.verify_access_token(&format!("{}.{}", parts[0], parts[1])).is_none());
+        assert!(keys.verify_access_token("").is_none());
+    }
+
+    #[test]
+    fn rejects_other_algorithms() {
+        let keys = keys("secret");
+        let valid = claims(Utc::now().timestamp() + 60);
+        assert!(keys.verify_access_token(&token(&keys, r#"{"alg":"HS256","typ":"JWT"}"#, &valid)).is_some());
+        for header in [r#"{"alg":"none","typ":"JWT"}"#, r#"{"alg":"HS512","typ":"JWT"}"#, r#"{"typ":"JWT"}"#] {
+            assert!(keys.verify_access_token(&token(&keys, header, &valid)).is_none(), "{header}");
+        }
+        // An unsigned token
+        let unsigned = token(&keys, r#"{"alg":"none"}"#, &valid);
+        let (signing_input, _) = unsigned.rsplit_once('.').unwrap_or_default();
+        assert!(keys.verify_access_token(&format!("{signing_input}.")).is_none());
+    }
+
+    #[test]
+    fn rejects_expired_tokens() {
+        let keys = keys("secret");
+        let header = r#"{"alg":"HS256","typ":"JWT"}"#;
+        let now = Utc::now().timestamp();
+        assert!(keys.verify_access_token(&token(&keys, header, &claims(now - 1))).is_none());
+        assert!(keys.verify_access_token(&token(&keys, header, &claims(now))).is_none());
+        assert!(keys.verify_access_token(&token(&keys, header, &claims(now + 60))).is_some());
+    }
+}
@@ -0,0 +1,58 @@
+// Compute budgets for a single request
+//
+// Parameter limits keep each simulation bounded on their own, but sweeps
+// and comparisons multiply that cost. Every request therefore gets a wall
+// time limit on the blocking thread and a cap on the size of what it
+// returns, which is also what it holds in memory at the end.
+
+use std::io::{self, Write};
+use std::time::Duration;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::simulations::SimulationError;
+
+/// Computation time allowed for a synchronous run, sweep or comparison
+pub const REQUEST_TIME_LIMIT: Duration = Duration::from_secs(30);
+/// Computation time allowed for a background job
+pub const JOB_TIME_LIMIT: Duration = Duration::from_secs(10 * 60);
+/// Computation time allowed for one run of a user's script, which should
+/// take milliseconds
+pub const SCRIPT_TIME_LIMIT: Duration = Duration::from_secs(2);
+/// Largest total output of one request, as serialized JSON (bytes)
+pub const MAX_OUTPUT_BYTES: usize = 64_000_000;
+
+/// Fail if the outputs together serialize to more than `MAX_OUTPUT_BYTES`
+pub fn check_output_size<'a>(outputs: impl IntoIterator<Item = &'a Value>) -> Result<(), SimulationError> {
+    let size = outputs.into_iter().map(serialized_size).sum();
+    if size > MAX_OUTPUT_BYTES {
+        return Err(SimulationError::OutputTooLarge {
+            size,
+            limit: MAX_OUTPUT_BYTES,
+        });
+    }
+    Ok(())
+}
+
+/// Length of a value serialized as JSON, without building the string
+pub fn serialized_size(value: &impl Serialize) -> usize {
+    let mut counter = ByteCounter(0);
+    // Writing to the counter cannot fail
+    let _ = serde_json::to_writer(&mut counter, value);
+    counter.0
+}
+
+/// Sink that only counts what is written to it
+struct ByteCounter(usize);
+
+impl Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,130 @@
+// Reproducibility bundles of stored results
+//
+// A bundle is a ZIP archive holding everything needed to cite, check or
+// recompute a result: its parameters, the raw output, the output as CSV
+// tables, a methods description and the versions of the code that produced
+// it, with checksums of every file.
+
+use chrono::Utc;
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+
+use crate::models::simulation::{SimulationDetails, SimulationResult};
+use crate::services::export;
+use crate::services::zip::encode_zip;
+
+/// Commit of the code this server was built from
+pub const CODE_COMMIT: &str = env!("GIT_COMMIT");
+
+/// Build the bundle of a result. `details` describes the simulation when
+/// it is still available.
+pub fn build_bundle(
+    result: &SimulationResult,
+    details: Option<&SimulationDetails>,
+    units: &[(&str, &'static str)],
+) -> Vec<u8> {
+    let exported_at = Utc::now();
+    let tables = export::tables(&result.data, units);
+
+    let mut files: Vec<(String, Vec<u8>)> = vec![
+        ("README.md".to_string(), methods(result, details, tables.len(), &exported_at.to_rfc3339()).into_bytes()),
+        ("parameters.json".to_string(), pretty(&result.parameters)),
+        // Compact, so the file holds exactly the stored numbers
+        ("data.json".to_string(), result.data.to_string().into_bytes()),
+    ];
+    for (i, table) in tables.iter().enumerate() {
+        files.push((format!("table-{}.csv", i + 1), table.to_csv().into_bytes()));
+    }
+
+    let checksums: Map<String, Value> = files
+        .iter()
+        .map(|(name, contents)| {
+            let digest = Sha256::digest(contents);
+            (name.clone(), digest.iter().map(|b| format!("{b:02x}")).collect::<String>().into())
+        })
+        .collect();
+    let manifest = json!({
+        "result_id": result.id,
+        "simulation_id": result.simulation_id,
+        "simulation_name": details.map(|d| d.name.as_str()),
+        "simulation_version": result.simulation_version,
+        "compute_backend": result.compute_backend,
+        "code_version": env!("CARGO_PKG_VERSION"),
+        "code_commit": CODE_COMMIT,
+        "computed_at": result.computed_at,
+        "attestation": result.attestation,
+        "exported_at": exported_at.to_rfc3339(),
+        "sha256": checksums,
+    });
+    files.push(("manifest.json".to_string(), pretty(&manifest)));
+
+    encode_zip(&files, exported_at)
+}
+
+/// Human-readable account of how the result was produced
+fn methods(result: &SimulationResult, details: Option<&SimulationDetails>, tables: usize, exported_at: &str) -> String {
+    let name = details.map_or(result.simulation_id.as_str(), |d| d.name.as_str());
+    let mut text = format!("# {name}\n\n");
+    if let Some(details) = details {
+        text.push_str(&format!("{}\n\n", details.description));
+    }
+
+    text.push_str("## Provenance\n\n");
+    text.push_str(&format!("- Result `{}`, computed {}\n", result.id, result.computed_at));
+    text.push_str(&format!(
+        "- Simulation `{}`, computation version {}\n",
+        result.simulation_id, result.simulation_version
+    ));
+    text.push_str(&format!(
+        "- physics-tutorial-api {}, commit `{}`\n",
+        env!("CARGO_PKG_VERSION"),
+        CODE_COMMIT
+    ));
+    text.push_str(&format!("- Bundle exported {exported_at}\n\n"));
+
+    text.push_str("## Parameters\n\n| Parameter | Value |\n| --- | --- |\n");
+    if let Some(parameters) = result.parameters.as_object() {
+        for (key, value) in parameters {
+            let label = details
+                .and_then(|d| d.parameters.iter().find(|p| &p.name == key))
+                .map_or(key.clone(), |p| format!("{} (`{}`)", p.label, key));
+            let value = match value {
+                Value::String(s) => s.clone(),
+                value => value.to_string(),
+            };
+            text.push_str(&format!("| {label} | {value} |\n"));
+        }
+    }
+
+    if let Some(details) = details {
+        text.push_str("\n## Method\n\n");
+        text.push_str(details.theory.trim());
+        text.push('\n');
+    }
+
+    text.push_str("\n## Files\n\n");
+    text.push_str("- `parameters.json`: the validated parameters the simulation ran with\n");
+    text.push_str("- `data.json`: the raw output, exactly as stored\n");
+    match tables {
+        0 => {}
+        1 => text.push_str("- `table-1.csv`: the output's data series, with units in the column headers\n"),
+        n => text.push_str(&format!(
+            "- `table-1.csv` to `table-{n}.csv`: the output's data series grouped by length, main data set first, \
+             with units in the column headers\n"
+        )),
+    }
+    text.push_str("- `manifest.json`: identifiers, versions, the server's signed attestation of the result and SHA-256 checksums of the other files\n");
+
+    text.push_str("\n## Reproducing\n\n");
+    text.push_str(&format!(
+        "`POST /api/v1/simulations/results/{}/replay` recomputes the result with computation version {} and \
+         reports whether it is reproduced exactly. To run it afresh, send the contents of `parameters.json` as \
+         `parameters` to `POST /api/v1/simulations/{}/run`.\n",
+        result.id, result.simulation_version, result.simulation_id
+    ));
+    text
+}
+
+fn pretty(value: &Value) -> Vec<u8> {
+    serde_json::to_vec_pretty(value).unwrap_or_default()
+}
@@ -0,0 +1,278 @@
+// Citation metadata for simulations
+//
+// A simulation is cited as software: this server at its release, naming
+// the simulation's computation version, alongside the papers and books its
+// physics comes from. Everything is given as CSL-JSON, which reference
+// managers such as Zotero import directly, and as BibTeX for LaTeX reports.
+
+use chrono::{Datelike, NaiveDate};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+use crate::config::CitationConfig;
+use crate::services::bundle::CODE_COMMIT;
+use crate::simulations::catalog::Reference;
+
+const PROJECT: &str = "DIU Physics Tutorial";
+const PUBLISHER: &str = "DeSci Intelligent Universe";
+
+/// How the server itself is cited
+pub struct Software {
+    /// DOI minted for this release, e.g. on Zenodo
+    pub doi: Option<String>,
+}
+
+impl Software {
+    pub fn from_config(config: &CitationConfig) -> Self {
+        Self {
+            doi: config.doi.as_ref().map(|doi| doi.trim().to_string()),
+        }
+    }
+}
+
+/// Whether text is a DOI of the form `10.prefix/suffix`
+pub fn is_doi(doi: &str) -> bool {
+    let doi = doi.trim();
+    doi.starts_with("10.") && doi.contains('/')
+}
+
+/// The simulation being cited
+pub struct Cited<'a> {
+    pub id: &'a str,
+    pub name: &'a str,
+    pub version: u32,
+    pub references: &'a [Reference],
+}
+
+/// Citation metadata of one simulation
+#[derive(Serialize)]
+pub struct Citation {
+    pub simulation_id: String,
+    pub simulation_version: u32,
+    /// CSL-JSON item for the simulation itself
+    pub software: Value,
+    /// CSL-JSON items for the works the physics comes from
+    pub references: Vec<Value>,
+    /// All of the above as BibTeX entries
+    pub bibtex: String,
+}
+
+impl Citation {
+    /// Every item, software first, as a CSL-JSON array
+    pub fn csl(&self) -> Value {
+        let mut items = vec![self.software.clone()];
+        items.extend(self.references.iter().cloned());
+        Value::Array(items)
+    }
+}
+
+pub fn cite(software: &Software, simulation: &Cited, accessed: NaiveDate) -> Citation {
+    let software_key = format!("physics-tutorial-{}", simulation.id);
+    let version = format!("{}, simulation version {}", env!("CARGO_PKG_VERSION"), simulation.version);
+    let date = json!({ "date-parts": [[accessed.year(), accessed.month(), accessed.day()]] });
+
+    let mut item = json!({
+        "id": software_key,
+        "type": "software",
+        "title": format!("{PROJECT}: {}", simulation.name),
+        "author": package_authors().iter().map(|name| person(name)).collect::<Vec<_>>(),
+        "publisher": PUBLISHER,
+        "version": version,
+        "URL": env!("CARGO_PKG_REPOSITORY"),
+        "issued": { "date-parts": [[accessed.year()]] },
+        "accessed": date,
+        "note": format!("Commit {CODE_COMMIT}"),
+    });
+    if let Some(doi) = &software.doi {
+        item["DOI"] = doi.clone().into();
+    }
+
+    let mut keys: Vec<String> = Vec::new();
+    let mut bibtex = software_bibtex(&software_key, software, simulation, &version, accessed);
+    let mut references = Vec::new();
+    for reference in simulation.references {
+        let key = unique_key(&mut keys, reference);
+        bibtex.push('\n');
+        bibtex.push_str(&reference_bibtex(&key, reference));
+        references.push(reference_item(&key, reference));
+    }
+
+    Citation {
+        simulation_id: simulation.id.to_string(),
+        simulation_version: simulation.version,
+        software: item,
+        references,
+        bibtex,
+    }
+}
+
+/// Names of the package authors, without their email addresses
+fn package_authors() -> Vec<&'static str> {
+    env!("CARGO_PKG_AUTHORS")
+        .split(':')
+        .map(|author| author.split('<').next().unwrap_or(author).trim())
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+/// A CSL name from "Family, Given" or "Given Family"
+fn person(name: &str) -> Value {
+    let (family, given) = match name.split_once(',') {
+        Some((family, given)) => (family.trim(), given.trim()),
+        None => match name.rsplit_once(' ') {
+            Some((given, family)) => (family.trim(), given.trim()),
+            None => (name.trim(), ""),
+        },
+    };
+    if given.is_empty() {
+        json!({ "family": family })
+    } else {
+        json!({ "family": family, "given": given })
+    }
+}
+
+fn reference_item(key: &str, reference: &Reference) -> Value {
+    let mut item = Map::new();
+    item.insert("id".into(), key.into());
+    let kind = if reference.kind == "book" { "book" } else { "article-journal" };
+    item.insert("type".into(), kind.into());
+    item.insert("title".into(), reference.title.clone().into());
+    item.insert("author".into(), reference.authors.iter().map(|a| person(a)).collect());
+    item.insert("issued".into(), json!({ "date-parts": [[reference.year]] }));
+    let optional = [
+        ("container-title", &reference.journal),
+        ("publisher", &reference.publisher),
+        ("edition", &reference.edition),
+        ("volume", &reference.volume),
+        ("issue", &reference.issue),
+        ("page", &reference.pages),
+        ("DOI", &reference.doi),
+        ("URL", &reference.url),
+    ];
+    for (field, value) in optional {
+        if let Some(value) = value {
+            item.insert(field.into(), value.clone().into());
+        }
+    }
+    Value::Object(item)
+}
+
+fn software_bibtex(key: &str, software: &Software, simulation: &Cited, version: &str, accessed: NaiveDate) -> String {
+    let authors: Vec<String> = package_authors().iter().map(|name| bibtex_name(name)).collect();
+    let mut fields = vec![
+        ("author", escape(&authors.join(" and "))),
+        ("title", format!("{{{}: {}}}", escape(PROJECT), escape(simulation.name))),
+        ("howpublished", escape(PUBLISHER)),
+        ("version", escape(version)),
+        ("year", accessed.year().to_string()),
+        ("url", env!("CARGO_PKG_REPOSITORY").to_string()),
+        ("urldate", accessed.format("%Y-%m-%d").to_string()),
+        ("note", escape(&format!("Commit {CODE_COMMIT}"))),
+    ];
+    if let Some(doi) = &software.doi {
+        fields.push(("doi", doi.clone()));
+    }
+    entry("misc", key, &fields)
+}
+
+fn reference_bibtex(key: &str, reference: &Reference) -> String {
+    let authors: Vec<String> = reference.authors.iter().map(|a| escape(a)).collect();
+    let mut fields = vec![
+        ("author", authors.join(" and ")),
+        // Double braces keep the title's capitalization
+        ("title", format!("{{{}}}", escape(&reference.title))),
+        ("year", reference.year.to_string()),
+    ];
+    let optional = [
+        ("journal", &reference.journal),
+        ("publisher", &reference.publisher),
+        ("edition", &reference.edition),
+        ("volume", &reference.volume),
+        ("number", &reference.issue),
+    ];
+    for (field, value) in optional {
+        if let Some(value) = value {
+            fields.push((field, escape(value)));
+        }
+    }
+    if let Some(pages) = &reference.pages {
+        fields.push(("pages", escape(&pages.replace('-', "--"))));
+    }
+    if let Some(doi) = &reference.doi {
+        fields.push(("doi", doi.clone()));
+    }
+    if let Some(url) = &reference.url {
+        fields.push(("url", url.clone()));
+    }
+    let kind = if reference.kind == "book" { "book" } else { "article" };
+    entry(kind, key, &fields)
+}
+
+fn entry(kind: &str, key: &str, fields: &[(&str, String)]) -> String {
+    let mut text = format!("@{kind}{{{key},\n");
+    for (field, value) in fields {
+        text.push_str(&format!("  {field} = {{{value}}},\n"));
+    }
+    text.push_str("}\n");
+    text
+}
+
+/// "Family, Given" as BibTeX expects it, from either order
+fn bibtex_name(name: &str) -> String {
+    match name.split_once(',') {
+        Some(_) => name.to_string(),
+        None => match name.rsplit_once(' ') {
+            Some((given, family)) => format!("{family}, {given}"),
+            None => name.to_string(),
+        },
+    }
+}
+
+/// Characters LaTeX would otherwise read as markup
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\textbackslash{}"),
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '~' => escaped.push_str("\\textasciitilde{}"),
+            '^' => escaped.push_str("\\textasciicircum{}"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Key from the first author's family name and the year, e.g. `young1804`,
+/// with a letter added when two works would share one
+fn unique_key(taken: &mut Vec<String>, reference: &Reference) -> String {
+    let first = reference.authors.first().map_or("anon", |a| a.split(',').next().unwrap_or(a));
+    let family: String = first.chars().filter_map(ascii_letter).collect();
+    let base = format!("{}{}", if family.is_empty() { "anon".to_string() } else { family }, reference.year);
+    let key = (0u8..26)
+        .map(|i| if i == 0 { base.clone() } else { format!("{base}{}", (b'a' + i) as char) })
+        .find(|key| !taken.contains(key))
+        .unwrap_or_else(|| format!("{base}-{}", taken.len()));
+    taken.push(key.clone());
+    key
+}
+
+/// Lowercase ASCII letter for a letter, dropping accents
+fn ascii_letter(c: char) -> Option<char> {
+    let c = c.to_lowercase().next().unwrap_or(c);
+    let base = match c {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' => 'a',
+        'ç' => 'c',
+        'è' | 'é' | 'ê' | 'ë' => 'e',
+        'ì' | 'í' | 'î' | 'ï' => 'i',
+        'ñ' => 'n',
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' => 'o',
+        'ù' | 'ú' | 'û' | 'ü' => 'u',
+        'ý' | 'ÿ' => 'y',
+        c => c,
+    };
+    base.is_ascii_alphabetic().then_some(base)
+}
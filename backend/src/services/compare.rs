@@ -0,0 +1,87 @@
+// Differences between runs of the same simulation
+
+use serde_json::{json, Map, Value};
+
+/// Compare the outputs of several runs against the first.
+///
+/// Numeric values are matched by their dotted path in the data. For each
+/// scalar that differs between runs the result lists every run's value and
+/// its change and relative change from the first run. Numeric arrays of the
+/// same length in every run report the largest absolute change per run.
+/// Outputs present in only some runs are skipped.
+pub fn differences(outputs: &[Value]) -> Map<String, Value> {
+    let Some((baseline, _)) = outputs.split_first() else {
+        return Map::new();
+    };
+    let mut paths = Vec::new();
+    collect_paths(baseline, String::new(), &mut paths);
+
+    let mut differences = Map::new();
+    for path in paths {
+        let values: Vec<&Value> = match outputs.iter().map(|o| o.pointer(&pointer(&path))).collect() {
+            Some(values) => values,
+            None => continue,
+        };
+
+        if let Some(numbers) = values.iter().map(|v| v.as_f64()).collect::<Option<Vec<f64>>>() {
+            if numbers.iter().all(|&n| n == numbers[0]) {
+                continue;
+            }
+            let base = numbers[0];
+            differences.insert(
+                path,
+                json!({
+                    "values": numbers,
+                    "change": numbers.iter().map(|n| n - base).collect::<Vec<_>>(),
+                    "relative_change": numbers
+                        .iter()
+                        .map(|n| (base != 0.0).then(|| (n - base) / base.abs()))
+                        .collect::<Vec<_>>(),
+                }),
+            );
+        } else if let Some(arrays) = values.iter().map(|v| numeric_array(v)).collect::<Option<Vec<_>>>() {
+            let base = &arrays[0];
+            if arrays.iter().any(|a| a.len() != base.len()) {
+                continue;
+            }
+            let max_change: Vec<f64> = arrays
+                .iter()
+                .map(|a| a.iter().zip(base).fold(0.0, |m: f64, (x, y)| m.max((x - y).abs())))
+                .collect();
+            if max_change.iter().any(|&m| m > 0.0) {
+                differences.insert(path, json!({ "max_abs_change": max_change }));
+            }
+        }
+    }
+    differences
+}
+
+/// Paths of every number and numeric array, descending into objects
+fn collect_paths(value: &Value, path: String, paths: &mut Vec<String>) {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map {
+                let child_path = if path.is_empty() { key.clone() } else { format!("{path}.{key}") };
+                collect_paths(child, child_path, paths);
+            }
+        }
+        Value::Number(_) => paths.push(path),
+        Value::Array(_) if numeric_array(value).is_some() => paths.push(path),
+        _ => {}
+    }
+}
+
+fn numeric_array(value: &Value) -> Option<Vec<f64>> {
+    let items = value.as_array()?;
+    if items.is_empty() {
+        return None;
+    }
+    items.iter().map(Value::as_f64).collect()
+}
+
+/// JSON pointer for a dotted path
+pub fn pointer(path: &str) -> String {
+    path.split('.')
+        .map(|segment| format!("/{}", segment.replace('~', "~0").replace('/', "~1")))
+        .collect()
+}
@@ -0,0 +1,88 @@
+// Prerequisite graph between simulations and a learning order through it
+
+use std::collections::HashSet;
+
+use serde::Serialize;
+
+use crate::models::simulation::DIFFICULTIES;
+use crate::simulations::SimulationRegistry;
+
+/// `from` should be done before `to`
+#[derive(Serialize)]
+pub struct Edge {
+    pub from: &'static str,
+    pub to: &'static str,
+}
+
+/// Every prerequisite link between registered simulations. Links to unknown
+/// simulations are skipped.
+pub fn edges(registry: &SimulationRegistry) -> Vec<Edge> {
+    registry
+        .iter()
+        .flat_map(|simulation| {
+            let to = simulation.id();
+            simulation
+                .prerequisites()
+                .iter()
+                .filter(|from| registry.get(from).is_some())
+                .map(move |&from| Edge { from, to })
+        })
+        .collect()
+}
+
+/// Simulation IDs in an order that respects every prerequisite.
+///
+/// With a `target`, only that simulation and everything it depends on are
+/// included. Among the simulations available at each step the easiest comes
+/// first, then the one registered earliest, so the order is stable. If the
+/// links ever form a cycle the simulations on it are appended at the end.
+pub fn learning_order(registry: &SimulationRegistry, target: Option<&str>) -> Vec<&'static str> {
+    let ids: Vec<&'static str> = registry.iter().map(|s| s.id()).collect();
+    let included: HashSet<&'static str> = match target {
+        Some(target) => ancestors(registry, target),
+        None => ids.iter().copied().collect(),
+    };
+    let edges: Vec<Edge> = edges(registry)
+        .into_iter()
+        .filter(|e| included.contains(e.from) && included.contains(e.to))
+        .collect();
+    let rank = |id: &str| {
+        let difficulty = registry.get(id).map(|s| s.info().difficulty);
+        let level = DIFFICULTIES.iter().position(|d| Some(*d) == difficulty.as_deref());
+        (level, ids.iter().position(|i| *i == id))
+    };
+
+    let mut remaining: Vec<&'static str> = ids.iter().copied().filter(|id| included.contains(id)).collect();
+    let mut order = Vec::with_capacity(remaining.len());
+    loop {
+        let next = remaining
+            .iter()
+            .copied()
+            .filter(|id| !edges.iter().any(|e| e.to == *id && remaining.contains(&e.from)))
+            .min_by_key(|id| rank(id));
+        let Some(next) = next else {
+            break;
+        };
+        remaining.retain(|id| *id != next);
+        order.push(next);
+    }
+    if !remaining.is_empty() {
+        tracing::error!("Prerequisites form a cycle through {:?}", remaining);
+        order.extend(remaining);
+    }
+    order
+}
+
+/// `target` and every simulation it depends on, directly or not
+fn ancestors(registry: &SimulationRegistry, target: &str) -> HashSet<&'static str> {
+    let mut found = HashSet::new();
+    let mut pending: Vec<&'static str> = registry.get(target).map(|s| s.id()).into_iter().collect();
+    while let Some(id) = pending.pop() {
+        if found.insert(id) {
+            if let Some(simulation) = registry.get(id) {
+                pending.extend(simulation.prerequisites().iter().filter(|p| registry.get(p).is_some()));
+            }
+        }
+    }
+    found
+}
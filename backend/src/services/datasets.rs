@@ -0,0 +1,266 @@
+// Experimental datasets to hold simulation runs against
+//
+// Each dataset is a content file, `content/datasets/<id>.toml`, holding
+// published measurements with their uncertainties, converted to the units
+// of the simulation output they are compared with, and the run parameters
+// that reproduce the experiment. Points with an `x` are compared with the
+// output curve at that position, interpolated between the run's samples;
+// points without one are measurements of a single scalar output. The
+// comparison reports each residual and the χ² of the run against the data.
+
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::numerics::interpolate::cubic;
+use crate::numerics::special::gamma_q;
+use crate::services::compare::pointer;
+use crate::services::toml;
+use crate::simulations::catalog::{CatalogError, Reference};
+use crate::simulations::params::Params;
+use crate::simulations::{output, series, SimulationRegistry};
+
+/// Published measurements of a quantity a simulation computes
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Dataset {
+    /// Taken from the file name
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    /// ID of the simulation whose output the data is compared with
+    pub simulation: String,
+    /// Where the data comes from and how it was converted
+    pub description: String,
+    /// Run parameters that reproduce the experiment
+    #[serde(default)]
+    pub parameters: Map<String, Value>,
+    /// Dotted path of the output array the `x` values are positions in;
+    /// absent when every point measures the scalar at `y_output`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub x_output: Option<String>,
+    /// Dotted path of the output the `y` values are measurements of
+    pub y_output: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub x_unit: String,
+    #[serde(default)]
+    pub y_unit: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub x: Vec<f64>,
+    pub y: Vec<f64>,
+    /// One standard deviation of each `y`
+    pub uncertainty: Vec<f64>,
+    /// Name of each point, e.g. the experiment it comes from
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
+    pub references: Vec<Reference>,
+}
+
+/// Catalog entry of a dataset, without its data
+#[derive(Serialize)]
+pub struct DatasetSummary {
+    pub id: String,
+    pub name: String,
+    pub simulation: String,
+    pub description: String,
+    pub points: usize,
+    pub references: Vec<Reference>,
+}
+
+/// How well one run reproduces a dataset
+#[derive(Serialize)]
+pub struct DatasetComparison {
+    pub dataset: String,
+    pub points: Vec<Residual>,
+    pub chi_squared: f64,
+    /// Number of compared points; the run fits no parameters to the data
+    pub degrees_of_freedom: usize,
+    /// χ² per degree of freedom, absent when no point could be compared
+    pub reduced_chi_squared: Option<f64>,
+    /// Probability of a χ² at least this large if the model were right
+    pub p_value: Option<f64>,
+    /// Points outside the run's output, left out of χ²
+    pub outside_range: usize,
+}
+
+/// One measurement against the run
+#[derive(Serialize)]
+pub struct Residual {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub x: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    pub observed: f64,
+    pub uncertainty: f64,
+    pub model: f64,
+    /// observed − model
+    pub residual: f64,
+    /// Residual in standard deviations
+    pub pull: f64,
+}
+
+impl Dataset {
+    pub fn summary(&self) -> DatasetSummary {
+        DatasetSummary {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            simulation: self.simulation.clone(),
+            description: self.description.clone(),
+            points: self.y.len(),
+            references: self.references.clone(),
+        }
+    }
+
+    /// Compare a run's output with the data
+    pub fn compare(&self, data: &Value) -> DatasetComparison {
+        let curve = self
+            .x_output
+            .as_ref()
+            .map(|x_output| (series(data, &pointer(x_output)), series(data, &pointer(&self.y_output))));
+        let scalar = output(data, &pointer(&self.y_output));
+
+        let mut points = Vec::with_capacity(self.y.len());
+        let mut outside_range = 0;
+        for (i, (&observed, &uncertainty)) in self.y.iter().zip(&self.uncertainty).enumerate() {
+            let x = self.x.get(i).copied();
+            let model = match (&curve, x) {
+                (Some((xs, ys)), Some(x)) => cubic(xs, ys, x),
+                _ => scalar.is_finite().then_some(scalar),
+            };
+            let Some(model) = model.filter(|model| model.is_finite()) else {
+                outside_range += 1;
+                continue;
+            };
+            let residual = observed - model;
+            points.push(Residual {
+                x,
+                label: self.labels.get(i).cloned(),
+                observed,
+                uncertainty,
+                model,
+                residual,
+                pull: residual / uncertainty,
+            });
+        }
+
+        let chi_squared: f64 = points.iter().map(|point| point.pull * point.pull).sum();
+        let degrees_of_freedom = points.len();
+        let compared = degrees_of_freedom > 0;
+        DatasetComparison {
+            dataset: self.id.clone(),
+            points,
+            chi_squared,
+            degrees_of_freedom,
+            reduced_chi_squared: compared.then(|| chi_squared / degrees_of_freedom as f64),
+            p_value: compared.then(|| gamma_q(degrees_of_freedom as f64 / 2.0, chi_squared / 2.0)),
+            outside_range,
+        }
+    }
+
+    /// Problems that keep the dataset from being served
+    fn problems(&self, registry: &SimulationRegistry) -> Vec<String> {
+        let mut problems = Vec::new();
+        for (field, text) in [("name", &self.name), ("description", &self.description), ("y_output", &self.y_output)] {
+            if text.trim().is_empty() {
+                problems.push(format!("{field} must not be empty"));
+            }
+        }
+        match registry.get(&self.simulation) {
+            Some(simulation) => {
+                if let Err(errors) = Params::validate(&simulation.parameters(), &self.parameters) {
+                    problems.extend(errors.iter().map(|error| format!("parameters.{}: {}", error.name, error.message)));
+                }
+            }
+            None => problems.push(format!("simulation `{}` does not exist", self.simulation)),
+        }
+        if self.y.is_empty() {
+            problems.push("y must hold at least one measurement".to_string());
+        }
+        let n = self.y.len();
+        if self.uncertainty.len() != n {
+            problems.push(format!("uncertainty must have one value per measurement ({n})"));
+        }
+        if self.uncertainty.iter().any(|&sigma| !sigma.is_finite() || sigma <= 0.0) {
+            problems.push("uncertainties must be positive".to_string());
+        }
+        match (&self.x_output, self.x.len()) {
+            (Some(_), len) if len != n => problems.push(format!("x must have one value per measurement ({n})")),
+            (None, 0) => {}
+            (None, _) => problems.push("x needs x_output, the output it is a position in".to_string()),
+            _ => {}
+        }
+        if !self.labels.is_empty() && self.labels.len() != n {
+            problems.push(format!("labels must have one name per measurement ({n})"));
+        }
+        if self.references.is_empty() {
+            problems.push("datasets need a reference to the published data".to_string());
+        }
+        for reference in &self.references {
+            problems.extend(reference.problems());
+        }
+        problems
+    }
+}
+
+/// Every dataset, sorted by ID
+#[derive(Default)]
+pub struct Datasets {
+    datasets: Vec<Dataset>,
+}
+
+impl Datasets {
+    /// Read and check every `<id>.toml` in `dir` against the simulations
+    /// they are for. A missing directory holds no datasets.
+    pub fn load(dir: &Path, registry: &SimulationRegistry) -> Result<Self, CatalogError> {
+        let mut errors = CatalogError::default();
+        let mut datasets = Vec::new();
+        let Ok(listing) = fs::read_dir(dir) else {
+            return Ok(Self::default());
+        };
+        let mut files: Vec<_> = listing
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+            .collect();
+        files.sort();
+        for path in files {
+            let (Some(id), Some(file)) = (path.file_stem(), path.file_name()) else { continue };
+            let (id, file) = (id.to_string_lossy().to_string(), file.to_string_lossy().to_string());
+            let dataset = fs::read_to_string(&path)
+                .map_err(|err| err.to_string())
+                .and_then(|text| toml::parse(&text).map_err(|err| err.to_string()))
+                .and_then(|value| serde_json::from_value::<Dataset>(value).map_err(|err| err.to_string()));
+            match dataset {
+                Ok(mut dataset) => {
+                    if !dataset.id.is_empty() && dataset.id != id {
+                        errors.push(&file, format!("id must be \"{id}\", the file name"));
+                        continue;
+                    }
+                    dataset.id = id;
+                    let problems = dataset.problems(registry);
+                    if problems.is_empty() {
+                        datasets.push(dataset);
+                    }
+                    for problem in problems {
+                        errors.push(&file, problem);
+                    }
+                }
+                Err(message) => errors.push(&file, message),
+            }
+        }
+        if errors.problems.is_empty() {
+            Ok(Self { datasets })
+        } else {
+            Err(errors)
+        }
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Dataset> {
+        self.datasets.iter().find(|dataset| dataset.id == id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Dataset> {
+        self.datasets.iter()
+    }
+}
@@ -0,0 +1,484 @@
+// Ed25519 signatures (RFC 8032)
+//
+// Field elements are five 51-bit limbs and points are in extended twisted
+// Edwards coordinates, added with the complete formulas of Hisil et al.
+// Scalar multiplication is a Montgomery ladder over all 256 bits with
+// branch-free conditional swaps, so its timing does not depend on the
+// secret scalar or nonce. Arithmetic modulo the group order uses big
+// integers, whose running time depends only on the lengths of operands
+// that are reduced hashes or the clamped secret, all of nearly fixed length.
+
+use std::sync::OnceLock;
+
+use rsa::BigUint;
+use sha2::{Digest, Sha512};
+
+pub const PUBLIC_KEY_LENGTH: usize = 32;
+pub const SIGNATURE_LENGTH: usize = 64;
+
+const MASK: u64 = (1 << 51) - 1;
+/// p - 2 = 2^255 - 21, little-endian, for inversion
+const P_MINUS_2: [u8; 32] = exponent(0xeb, 0x7f);
+/// (p + 3) / 8 = 2^252 - 2, for square roots
+const P_PLUS_3_OVER_8: [u8; 32] = exponent(0xfe, 0x0f);
+/// (p - 1) / 4 = 2^253 - 5, for the square root of -1
+const P_MINUS_1_OVER_4: [u8; 32] = exponent(0xfb, 0x1f);
+
+/// Little-endian bytes with all middle bytes 0xff
+const fn exponent(low: u8, high: u8) -> [u8; 32] {
+    let mut bytes = [0xff; 32];
+    bytes[0] = low;
+    bytes[31] = high;
+    bytes
+}
+
+/// An element of the field of integers modulo p = 2^255 - 19, as five
+/// limbs of 51 bits, each allowed a few bits of slack between reductions
+#[derive(Clone, Copy)]
+struct Field([u64; 5]);
+
+impl Field {
+    const ZERO: Self = Self([0; 5]);
+    const ONE: Self = Self([1, 0, 0, 0, 0]);
+
+    fn from_u64(value: u64) -> Self {
+        Self([value & MASK, value >> 51, 0, 0, 0])
+    }
+
+    /// The low 255 bits of little-endian bytes, not necessarily reduced
+    fn from_bytes(bytes: &[u8; 32]) -> Self {
+        let word = |i: usize| {
+            let mut w = [0u8; 8];
+            w.copy_from_slice(&bytes[8 * i..8 * i + 8]);
+            u64::from_le_bytes(w)
+        };
+        let (w0, w1, w2, w3) = (word(0), word(1), word(2), word(3));
+        Self([
+            w0 & MASK,
+            (w0 >> 51 | w1 << 13) & MASK,
+            (w1 >> 38 | w2 << 26) & MASK,
+            (w2 >> 25 | w3 << 39) & MASK,
+            (w3 >> 12) & MASK,
+        ])
+    }
+
+    /// Canonical little-endian bytes
+    fn to_bytes(self) -> [u8; 32] {
+        let mut h = self.carry().0;
+        // Add 19 and see whether it carries past 2^255, i.e. whether h >= p
+        let mut q = (h[0] + 19) >> 51;
+        for limb in &h[1..] {
+            q = (limb + q) >> 51;
+        }
+        h[0] += 19 * q;
+        for i in 0..4 {
+            h[i + 1] += h[i] >> 51;
+            h[i] &= MASK;
+        }
+        h[4] &= MASK;
+
+        let mut bytes = [0u8; 32];
+        let (mut acc, mut bits, mut at) = (0u128, 0, 0);
+        for limb in h {
+            acc |= u128::from(limb) << bits;
+            bits += 51;
+            while bits >= 8 {
+                bytes[at] = acc as u8;
+                acc >>= 8;
+                bits -= 8;
+                at += 1;
+            }
+        }
+        bytes[at] = acc as u8;
+        bytes
+    }
+
+    /// Bring every limb back under 2^52
+    fn carry(self) -> Self {
+        let mut h = self.0;
+        for i in 0..4 {
+            h[i + 1] += h[i] >> 51;
+            h[i] &= MASK;
+        }
+        let top = h[4] >> 51;
+        h[4] &= MASK;
+        h[0] += 19 * top;
+        h[1] += h[0] >> 51;
+        h[0] &= MASK;
+        Self(h)
+    }
+
+    fn add(self, other: Self) -> Self {
+        let (a, b) = (self.0, other.0);
+        Self([a[0] + b[0], a[1] + b[1], a[2] + b[2], a[3] + b[3], a[4] + b[4]]).carry()
+    }
+
+    fn sub(self, other: Self) -> Self {
+        // Add 4p first so that no limb goes negative
+        let (a, b) = (self.0, other.carry().0);
+        Self([
+            a[0] + 0x1f_ffff_ffff_ffb4 - b[0],
+            a[1] + 0x1f_ffff_ffff_fffc - b[1],
+            a[2] + 0x1f_ffff_ffff_fffc - b[2],
+            a[3] + 0x1f_ffff_ffff_fffc - b[3],
+            a[4] + 0x1f_ffff_ffff_fffc - b[4],
+        ])
+        .carry()
+    }
+
+    fn neg(self) -> Self {
+        Self::ZERO.sub(self)
+    }
+
+    fn mul(self, other: Self) -> Self {
+        let (a, b) = (self.0.map(u128::from), other.0.map(u128::from));
+        let b19 = b.map(|limb| 19 * limb);
+        let r = [
+            a[0] * b[0] + a[1] * b19[4] + a[2] * b19[3] + a[3] * b19[2] + a[4] * b19[1],
+            a[0] * b[1] + a[1] * b[0] + a[2] * b19[4] + a[3] * b19[3] + a[4] * b19[2],
+            a[0] * b[2] + a[1] * b[1] + a[2] * b[0] + a[3] * b19[4] + a[4] * b19[3],
+            a[0] * b[3] + a[1] * b[2] + a[2] * b[1] + a[3] * b[0] + a[4] * b19[4],
+            a[0] * b[4] + a[1] * b[3] + a[2] * b[2] + a[3] * b[1] + a[4] * b[0],
+        ];
+        let mut h = [0u64; 5];
+        let mut carry = 0u128;
+        for i in 0..5 {
+            let value = r[i] + carry;
+            h[i] = value as u64 & MASK;
+            carry = value >> 51;
+        }
+        h[0] += carry as u64 * 19;
+        Self(h).carry()
+    }
+
+    fn square(self) -> Self {
+        self.mul(self)
+    }
+
+    /// self^exponent for a public exponent
+    fn pow(self, exponent: &[u8; 32]) -> Self {
+        let mut result = Self::ONE;
+        for byte in exponent.iter().rev() {
+            for bit in (0..8).rev() {
+                result = result.square();
+                if byte >> bit & 1 == 1 {
+                    result = result.mul(self);
+                }
+            }
+        }
+        result
+    }
+
+    fn invert(self) -> Self {
+        self.pow(&P_MINUS_2)
+    }
+
+    fn equals(self, other: Self) -> bool {
+        self.to_bytes() == other.to_bytes()
+    }
+
+    fn is_zero(self) -> bool {
+        self.to_bytes() == [0; 32]
+    }
+
+    fn is_odd(self) -> bool {
+        self.to_bytes()[0] & 1 == 1
+    }
+
+    /// Swap `a` and `b` when `swap` is 1, without branching on it
+    fn swap(a: &mut Self, b: &mut Self, swap: u64) {
+        let mask = 0u64.wrapping_sub(swap);
+        for i in 0..5 {
+            let t = mask & (a.0[i] ^ b.0[i]);
+            a.0[i] ^= t;
+            b.0[i] ^= t;
+        }
+    }
+}
+
+/// Curve constants, computed once
+struct Curve {
+    /// Order of the base point, 2^252 + 27742317777372353535851937790883648493
+    l: BigUint,
+    /// The curve constant d = -121665/121666
+    d: Field,
+    /// Twice d
+    d2: Field,
+    /// A square root of -1
+    sqrt_m1: Field,
+    base: Point,
+}
+
+fn curve() -> &'static Curve {
+    static CURVE: OnceLock<Curve> = OnceLock::new();
+    CURVE.get_or_init(|| {
+        let l = (BigUint::from(1u8) << 252usize)
+            + "27742317777372353535851937790883648493".parse::<BigUint>().expect("valid constant");
+        let d = Field::from_u64(121_665).neg().mul(Field::from_u64(121_666).invert());
+        let mut curve = Curve {
+            l,
+            d,
+            d2: d.add(d),
+            sqrt_m1: Field::from_u64(2).pow(&P_MINUS_1_OVER_4),
+            base: Point::NEUTRAL,
+        };
+        let y = Field::from_u64(4).mul(Field::from_u64(5).invert());
+        let x = recover_x(&curve, y, false).expect("the base point is on the curve");
+        curve.base = Point::affine(x, y);
+        curve
+    })
+}
+
+/// A point in extended coordinates (X : Y : Z : T) with x = X/Z, y = Y/Z
+/// and xy = T/Z
+#[derive(Clone, Copy)]
+struct Point {
+    x: Field,
+    y: Field,
+    z: Field,
+    t: Field,
+}
+
+impl Point {
+    const NEUTRAL: Self = Self {
+        x: Field::ZERO,
+        y: Field::ONE,
+        z: Field::ONE,
+        t: Field::ZERO,
+    };
+
+    fn affine(x: Field, y: Field) -> Self {
+        Self {
+            x,
+            y,
+            z: Field::ONE,
+            t: x.mul(y),
+        }
+    }
+
+    /// The sum of two points; complete, so it also doubles
+    fn add(&self, other: &Self, curve: &Curve) -> Self {
+        let a = self.y.sub(self.x).mul(other.y.sub(other.x));
+        let b = self.y.add(self.x).mul(other.y.add(other.x));
+        let c = self.t.mul(curve.d2).mul(other.t);
+        let d = self.z.add(self.z).mul(other.z);
+        let (e, f, g, h) = (b.sub(a), d.sub(c), d.add(c), b.add(a));
+        Self {
+            x: e.mul(f),
+            y: g.mul(h),
+            z: f.mul(g),
+            t: e.mul(h),
+        }
+    }
+
+    /// `scalar` times the point, with the same sequence of operations for
+    /// every scalar
+    fn mul(&self, scalar: &[u8; 32], curve: &Curve) -> Self {
+        let mut r0 = Self::NEUTRAL;
+        let mut r1 = *self;
+        for i in (0..256).rev() {
+            let bit = u64::from(scalar[i / 8] >> (i % 8) & 1);
+            Self::swap(&mut r0, &mut r1, bit);
+            r1 = r0.add(&r1, curve);
+            r0 = r0.add(&r0, curve);
+            Self::swap(&mut r0, &mut r1, bit);
+        }
+        r0
+    }
+
+    fn swap(a: &mut Self, b: &mut Self, swap: u64) {
+        Field::swap(&mut a.x, &mut b.x, swap);
+        Field::swap(&mut a.y, &mut b.y, swap);
+        Field::swap(&mut a.z, &mut b.z, swap);
+        Field::swap(&mut a.t, &mut b.t, swap);
+    }
+
+    fn equals(&self, other: &Self) -> bool {
+        self.x.mul(other.z).equals(other.x.mul(self.z)) && self.y.mul(other.z).equals(other.y.mul(self.z))
+    }
+
+    /// y in little-endian with the sign of x in the top bit
+    fn compress(&self) -> [u8; 32] {
+        let z_inv = self.z.invert();
+        let mut bytes = self.y.mul(z_inv).to_bytes();
+        if self.x.mul(z_inv).is_odd() {
+            bytes[31] |= 0x80;
+        }
+        bytes
+    }
+
+    fn decompress(curve: &Curve, bytes: &[u8; 32]) -> Option<Self> {
+        let sign = bytes[31] & 0x80 != 0;
+        let mut y_bytes = *bytes;
+        y_bytes[31] &= 0x7f;
+        let y = Field::from_bytes(&y_bytes);
+        // A y of p or more is not an encoding
+        if y.to_bytes() != y_bytes {
+            return None;
+        }
+        let x = recover_x(curve, y, sign)?;
+        Some(Self::affine(x, y))
+    }
+}
+
+/// The x coordinate of the point with this y and sign of x, if there is one
+fn recover_x(curve: &Curve, y: Field, sign: bool) -> Option<Field> {
+    let y2 = y.square();
+    let x2 = y2.sub(Field::ONE).mul(curve.d.mul(y2).add(Field::ONE).invert());
+    if x2.is_zero() {
+        return (!sign).then_some(x2);
+    }
+    let mut x = x2.pow(&P_PLUS_3_OVER_8);
+    if !x.square().equals(x2) {
+        x = x.mul(curve.sqrt_m1);
+    }
+    if !x.square().equals(x2) {
+        return None;
+    }
+    if x.is_odd() != sign {
+        x = x.neg();
+    }
+    Some(x)
+}
+
+fn to_bytes(value: &BigUint) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    let le = value.to_bytes_le();
+    bytes[..le.len()].copy_from_slice(&le);
+    bytes
+}
+
+/// SHA-512 of the parts as an integer modulo the group order
+fn hash_scalar(curve: &Curve, parts: &[&[u8]]) -> BigUint {
+    let mut hasher = Sha512::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    BigUint::from_bytes_le(&hasher.finalize()) % &curve.l
+}
+
+/// A private key with its expanded secret scalar and public key
+pub struct SigningKey {
+    scalar: [u8; 32],
+    prefix: [u8; 32],
+    public: [u8; PUBLIC_KEY_LENGTH],
+}
+
+impl SigningKey {
+    pub fn from_seed(seed: &[u8; 32]) -> Self {
+        let curve = curve();
+        let hash = Sha512::digest(seed);
+        let mut scalar = [0u8; 32];
+        scalar.copy_from_slice(&hash[..32]);
+        scalar[0] &= 248;
+        scalar[31] &= 127;
+        scalar[31] |= 64;
+        let mut prefix = [0u8; 32];
+        prefix.copy_from_slice(&hash[32..]);
+        let public = curve.base.mul(&scalar, curve).compress();
+        Self { scalar, prefix, public }
+    }
+
+    pub fn public_key(&self) -> [u8; PUBLIC_KEY_LENGTH] {
+        self.public
+    }
+
+    pub fn sign(&self, message: &[u8]) -> [u8; SIGNATURE_LENGTH] {
+        let curve = curve();
+        let r = hash_scalar(curve, &[&self.prefix, message]);
+        let r_point = curve.base.mul(&to_bytes(&r), curve).compress();
+        let h = hash_scalar(curve, &[&r_point, &self.public, message]);
+        let s = (r + h * BigUint::from_bytes_le(&self.scalar)) % &curve.l;
+        let mut signature = [0u8; SIGNATURE_LENGTH];
+        signature[..32].copy_from_slice(&r_point);
+        signature[32..].copy_from_slice(&to_bytes(&s));
+        signature
+    }
+}
+
+/// Whether `signature` is a valid signature of `message` by `public_key`
+pub fn verify(public_key: &[u8; PUBLIC_KEY_LENGTH], message: &[u8], signature: &[u8; SIGNATURE_LENGTH]) -> bool {
+    let curve = curve();
+    let Some(a) = Point::decompress(curve, public_key) else {
+        return false;
+    };
+    let mut r_bytes = [0u8; 32];
+    r_bytes.copy_from_slice(&signature[..32]);
+    let Some(r) = Point::decompress(curve, &r_bytes) else {
+        return false;
+    };
+    let mut s = [0u8; 32];
+    s.copy_from_slice(&signature[32..]);
+    if BigUint::from_bytes_le(&s) >= curve.l {
+        return false;
+    }
+    let h = hash_scalar(curve, &[&r_bytes, public_key, message]);
+    curve.base.mul(&s, curve).equals(&r.add(&a.mul(&to_bytes(&h), curve), curve))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bytes<const N: usize>(hex: &str) -> [u8; N] {
+        let mut bytes = [0u8; N];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).unwrap_or_default();
+        }
+        bytes
+    }
+
+    /// Tests 1 to 3 of RFC 8032 section 7.1: seed, public key, message and
+    /// signature
+    const VECTORS: [(&str, &str, &str, &str); 3] = [
+        (
+            "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60",
+            "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+            "",
+            "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b",
+        ),
+        (
+            "4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb",
+            "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c",
+            "72",
+            "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00",
+        ),
+        (
+            "c5aa8df43f9f837bedb7442f31dcb7b166d38535076f094b85ce3a2e0b4458f7",
+            "fc51cd8e6218a1a38da47ed00230f0580816ed13ba3303ac5deb911548908025",
+            "af82",
+            "6291d657deec24024827e69c3abe01a30ce548a284743a445e3680d7db5ac3ac18ff9b538d16f290ae67f760984dc6594a7c15e9716ed28dc027beceea1ec40a",
+        ),
+    ];
+
+    fn message(hex: &str) -> Vec<u8> {
+        (0..hex.len() / 2).map(|i| u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).unwrap_or_default()).collect()
+    }
+
+    #[test]
+    fn rfc_8032_vectors() {
+        for (seed, public, message_hex, signature) in VECTORS {
+            let key = SigningKey::from_seed(&bytes(seed));
+            assert_eq!(key.public_key(), bytes::<32>(public));
+            let message = message(message_hex);
+            assert_eq!(key.sign(&message), bytes::<64>(signature));
+            assert!(verify(&bytes(public), &message, &bytes(signature)));
+        }
+    }
+
+    #[test]
+    fn rejects_altered_signatures() {
+        let (seed, public, _, _) = VECTORS[2];
+        let key = SigningKey::from_seed(&bytes(seed));
+        let signature = key.sign(b"result");
+        assert!(verify(&bytes(public), b"result", &signature));
+        assert!(!verify(&bytes(public), b"resulT", &signature));
+        for i in [0, 31, 32, 63] {
+            let mut altered = signature;
+            altered[i] ^= 1;
+            assert!(!verify(&bytes(public), b"result", &altered));
+        }
+        let (_, other, _, _) = VECTORS[0];
+        assert!(!verify(&bytes(other), b"result", &signature));
+    }
+}
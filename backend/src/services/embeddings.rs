@@ -0,0 +1,460 @@
+// Embeddings for semantic search and related simulations
+//
+// Each simulation being served is split into passages, an overview (name,
+// topics, summary and description) and one per theory section, and every
+// passage is embedded as a unit vector. A query is embedded the same way
+// and passages are ranked by cosine similarity; simulations are related by
+// the similarity of the means of their passages.
+//
+// With `[embeddings] api_url` set, vectors come from an OpenAI-compatible
+// embeddings API, hosted or self-hosted, and are stored by model and text
+// hash (`models::embedding`) so each passage is sent once. Otherwise the
+// built-in model weighs stemmed words by TF-IDF over the passages. It needs
+// no model files or network and finds "observer" and "observation" for
+// "observing", but it relates text only through the words it shares; a
+// neural model also matches paraphrases. Like the full-text index, the
+// vectors are rebuilt on first use after the catalog changes.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use tokio::sync::Mutex;
+
+use crate::config::EmbeddingsConfig;
+use crate::models::embedding;
+use crate::services::markdown;
+use crate::simulations::SimulationRegistry;
+
+/// Name the built-in model is reported under
+pub const BUILTIN_MODEL: &str = "builtin-tfidf";
+/// Longest wait for the embeddings API
+const API_TIMEOUT: Duration = Duration::from_secs(60);
+/// Passages sent to the API in one request
+const BATCH: usize = 64;
+/// Longest passage embedded (characters); longer sections are cut
+const MAX_PASSAGE_LENGTH: usize = 4_000;
+/// Words of at most this many letters are left unstemmed
+const MIN_STEM: usize = 3;
+/// Words that carry no meaning of their own
+const STOPWORDS: [&str; 64] = [
+    "a", "about", "an", "and", "are", "as", "at", "be", "been", "but", "by", "can", "do", "does", "for", "from",
+    "has", "have", "how", "if", "in", "into", "is", "it", "its", "of", "on", "one", "or", "so", "such", "than",
+    "that", "the", "their", "them", "then", "there", "these", "they", "this", "those", "to", "two", "up", "us",
+    "was", "we", "were", "what", "when", "where", "which", "while", "who", "why", "will", "with", "would", "you",
+    "your", "also", "each", "more",
+];
+
+#[derive(Debug)]
+pub enum EmbeddingsError {
+    /// The embeddings API failed or did not answer
+    Provider(String),
+    Storage(sqlx::Error),
+}
+
+impl fmt::Display for EmbeddingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Provider(reason) => write!(f, "embeddings API request failed: {reason}"),
+            Self::Storage(err) => write!(f, "embeddings storage error: {err}"),
+        }
+    }
+}
+
+impl From<sqlx::Error> for EmbeddingsError {
+    fn from(err: sqlx::Error) -> Self {
+        Self::Storage(err)
+    }
+}
+
+fn provider_error(err: reqwest::Error) -> EmbeddingsError {
+    EmbeddingsError::Provider(err.to_string())
+}
+
+/// A piece of a simulation's content embedded as one vector
+pub struct Passage {
+    pub simulation_id: String,
+    /// Heading of the theory section; `None` for the overview, which also
+    /// holds any theory before the first heading
+    pub section: Option<String>,
+    /// Text without markup
+    pub text: String,
+}
+
+/// The catalog's passages and their vectors
+pub struct Index {
+    generation: u64,
+    passages: Vec<Passage>,
+    vectors: Vec<Vec<f32>>,
+    /// Each simulation's mean passage vector, in catalog order
+    simulations: Vec<(String, Vec<f32>)>,
+    /// Terms of the built-in model
+    vocabulary: Option<Vocabulary>,
+}
+
+impl Index {
+    /// Best matching passage of each simulation for a query vector, most
+    /// similar first; simulations with nothing in common are left out
+    pub fn search(&self, query: &[f32]) -> Vec<(&Passage, f32)> {
+        let mut best: Vec<(&Passage, f32)> = Vec::new();
+        let mut position: HashMap<&str, usize> = HashMap::new();
+        for (passage, vector) in self.passages.iter().zip(&self.vectors) {
+            let score = dot(query, vector);
+            if score <= 0.0 {
+                continue;
+            }
+            match position.get(passage.simulation_id.as_str()) {
+                Some(&i) if best[i].1 >= score => {}
+                Some(&i) => best[i] = (passage, score),
+                None => {
+                    position.insert(&passage.simulation_id, best.len());
+                    best.push((passage, score));
+                }
+            }
+        }
+        best.sort_by(|a, b| b.1.total_cmp(&a.1));
+        best
+    }
+
+    /// Other simulations by similarity to one, most similar first; `None`
+    /// when the simulation is not indexed
+    pub fn related(&self, simulation_id: &str) -> Option<Vec<(&str, f32)>> {
+        let (_, vector) = self.simulations.iter().find(|(id, _)| id == simulation_id)?;
+        let mut related: Vec<(&str, f32)> = self
+            .simulations
+            .iter()
+            .filter(|(id, _)| id != simulation_id)
+            .map(|(id, other)| (id.as_str(), dot(vector, other)))
+            .collect();
+        related.sort_by(|a, b| b.1.total_cmp(&a.1));
+        Some(related)
+    }
+}
+
+/// Where vectors come from, and the index built with them
+pub struct Embeddings {
+    api: Option<Api>,
+    index: Mutex<Option<Arc<Index>>>,
+}
+
+/// Client of an OpenAI-compatible embeddings API
+struct Api {
+    url: String,
+    key: Option<String>,
+    model: String,
+    http: reqwest::Client,
+}
+
+impl Embeddings {
+    /// The configured API, or the built-in model when there is none
+    pub fn from_config(config: &EmbeddingsConfig) -> Self {
+        let api = config.api_url.as_ref().zip(config.model.as_ref()).map(|(url, model)| Api {
+            url: format!("{}/embeddings", url.trim_end_matches('/')),
+            key: config.api_key.clone(),
+            model: model.clone(),
+            http: reqwest::Client::builder().timeout(API_TIMEOUT).build().unwrap_or_default(),
+        });
+        Self {
+            api,
+            index: Mutex::new(None),
+        }
+    }
+
+    /// Model the vectors come from
+    pub fn model(&self) -> &str {
+        self.api.as_ref().map_or(BUILTIN_MODEL, |api| &api.model)
+    }
+
+    /// The index of the catalog being served, rebuilt if the catalog changed
+    /// since it was built
+    pub async fn index(
+        &self,
+        pool: &sqlx::SqlitePool,
+        simulations: &SimulationRegistry,
+    ) -> Result<Arc<Index>, EmbeddingsError> {
+        let mut current = self.index.lock().await;
+        let generation = simulations.catalog().generation();
+        if let Some(index) = current.as_ref().filter(|index| index.generation == generation) {
+            return Ok(index.clone());
+        }
+
+        let passages = passages(simulations);
+        let (vectors, vocabulary) = match &self.api {
+            Some(api) => (api.embed_passages(pool, &passages).await?, None),
+            None => {
+                let vocabulary = Vocabulary::new(&passages);
+                (passages.iter().map(|passage| vocabulary.vector(&passage.text)).collect(), Some(vocabulary))
+            }
+        };
+        let mut means: Vec<(String, Vec<f32>)> = Vec::new();
+        for (passage, vector) in passages.iter().zip(&vectors) {
+            if means.last().is_none_or(|(id, _)| *id != passage.simulation_id) {
+                means.push((passage.simulation_id.clone(), vec![0.0; vector.len()]));
+            }
+            if let Some((_, mean)) = means.last_mut().filter(|(_, mean)| mean.len() == vector.len()) {
+                mean.iter_mut().zip(vector).for_each(|(sum, value)| *sum += value);
+            }
+        }
+        means.iter_mut().for_each(|(_, mean)| normalize(mean));
+
+        tracing::info!("Embedded {} passages with {}", passages.len(), self.model());
+        let index = Arc::new(Index {
+            generation,
+            passages,
+            vectors,
+            simulations: means,
+            vocabulary,
+        });
+        *current = Some(index.clone());
+        Ok(index)
+    }
+
+    /// Vector of a query, comparable with those of `index`
+    pub async fn embed_query(&self, index: &Index, query: &str) -> Result<Vec<f32>, EmbeddingsError> {
+        match (&self.api, &index.vocabulary) {
+            (Some(api), _) => {
+                let mut vectors = api.embed(&[query]).await?;
+                Ok(vectors.pop().unwrap_or_default())
+            }
+            (None, Some(vocabulary)) => Ok(vocabulary.vector(query)),
+            (None, None) => Ok(Vec::new()),
+        }
+    }
+}
+
+impl Api {
+    /// Vectors of the passages, from storage where this model embedded the
+    /// same text before and from the API for the rest
+    async fn embed_passages(
+        &self,
+        pool: &sqlx::SqlitePool,
+        passages: &[Passage],
+    ) -> Result<Vec<Vec<f32>>, EmbeddingsError> {
+        let mut vectors = Vec::with_capacity(passages.len());
+        let mut missing = Vec::new();
+        for (i, passage) in passages.iter().enumerate() {
+            let hash = sha256(&passage.text);
+            vectors.push(embedding::find(pool, &self.model, &hash).await?.unwrap_or_default());
+            if vectors[i].is_empty() {
+                missing.push((i, hash));
+            }
+        }
+        for batch in missing.chunks(BATCH) {
+            let texts: Vec<&str> = batch.iter().map(|(i, _)| passages[*i].text.as_str()).collect();
+            for ((i, hash), vector) in batch.iter().zip(self.embed(&texts).await?) {
+                embedding::insert(pool, &self.model, hash, &vector).await?;
+                vectors[*i] = vector;
+            }
+        }
+        Ok(vectors)
+    }
+
+    /// Unit vectors of `texts`, in order
+    async fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>, EmbeddingsError> {
+        let request = self.http.post(&self.url).json(&json!({ "model": self.model, "input": texts }));
+        let request = match &self.key {
+            Some(key) => request.bearer_auth(key),
+            None => request,
+        };
+        let mut response: EmbeddingResponse = request
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(provider_error)?
+            .json()
+            .await
+            .map_err(provider_error)?;
+        if response.data.len() != texts.len() {
+            return Err(EmbeddingsError::Provider(format!(
+                "asked for {} embeddings, got {}",
+                texts.len(),
+                response.data.len()
+            )));
+        }
+        response.data.sort_by_key(|item| item.index);
+        Ok(response
+            .data
+            .into_iter()
+            .map(|item| {
+                let mut vector = item.embedding;
+                normalize(&mut vector);
+                vector
+            })
+            .collect())
+    }
+}
+
+#[derive(Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingItem>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingItem {
+    #[serde(default)]
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+/// Terms of the built-in model: every stem in the passages, with its
+/// position in a vector and its inverse document frequency
+struct Vocabulary {
+    terms: HashMap<String, (usize, f32)>,
+}
+
+impl Vocabulary {
+    fn new(passages: &[Passage]) -> Self {
+        let mut frequencies: HashMap<String, usize> = HashMap::new();
+        for passage in passages {
+            let mut stems = stems(&passage.text);
+            stems.sort_unstable();
+            stems.dedup();
+            for stem in stems {
+                *frequencies.entry(stem).or_default() += 1;
+            }
+        }
+        let count = passages.len() as f32;
+        let mut sorted: Vec<(String, usize)> = frequencies.into_iter().collect();
+        sorted.sort_unstable();
+        let terms = sorted
+            .into_iter()
+            .enumerate()
+            .map(|(position, (stem, frequency))| {
+                let idf = ((1.0 + count) / (1.0 + frequency as f32)).ln() + 1.0;
+                (stem, (position, idf))
+            })
+            .collect();
+        Self { terms }
+    }
+
+    /// Unit vector of a text's TF-IDF weights; words outside the
+    /// vocabulary are ignored, and a text of only those has the zero vector
+    fn vector(&self, text: &str) -> Vec<f32> {
+        let mut counts: HashMap<usize, (usize, f32)> = HashMap::new();
+        for stem in stems(text) {
+            if let Some(&(position, idf)) = self.terms.get(&stem) {
+                counts.entry(position).or_insert((0, idf)).0 += 1;
+            }
+        }
+        let mut vector = vec![0.0; self.terms.len()];
+        for (position, (count, idf)) in counts {
+            vector[position] = (1.0 + (count as f32).ln()) * idf;
+        }
+        normalize(&mut vector);
+        vector
+    }
+}
+
+/// Stems of the meaningful words of a text
+fn stems(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .map(str::to_lowercase)
+        .filter(|word| word.chars().count() > 1 && !STOPWORDS.contains(&word.as_str()))
+        .map(|word| stem(&word))
+        .collect()
+}
+
+/// A word with its common English suffixes removed, so that forms of a
+/// word share a stem: "observing", "observer" and "observation" all give
+/// "observ". Cruder than the Porter stemmer, which only matters in that a
+/// few unrelated words may share a stem.
+fn stem(word: &str) -> String {
+    const SUFFIXES: [&str; 22] = [
+        "ational", "ations", "ation", "ments", "ment", "ness", "ities", "ity", "ings", "ing", "ions", "ion", "ers",
+        "er", "ies", "ied", "ences", "ence", "ed", "es", "ly", "s",
+    ];
+    let length = word.chars().count();
+    if length <= MIN_STEM || !word.is_ascii() {
+        return word.to_string();
+    }
+    let mut stem = word;
+    let strips = |suffix: &&&str| word.ends_with(**suffix) && length - suffix.len() >= MIN_STEM;
+    if let Some(suffix) = SUFFIXES.iter().find(strips).filter(|_| !word.ends_with("ss")) {
+        stem = &word[..word.len() - suffix.len()];
+    }
+    let stem = stem.strip_suffix('e').filter(|rest| rest.len() >= MIN_STEM).unwrap_or(stem);
+    stem.strip_suffix('y').filter(|rest| rest.len() >= MIN_STEM).unwrap_or(stem).to_string()
+}
+
+/// Passages of every simulation being served, grouped by simulation
+fn passages(simulations: &SimulationRegistry) -> Vec<Passage> {
+    let mut passages = Vec::new();
+    for simulation in simulations.iter() {
+        let Some(content) = simulations.catalog().get(simulation.id()) else {
+            continue;
+        };
+        let mut sections = sections(&content.theory).into_iter();
+        let introduction = sections.next().map(|(_, body)| markdown::to_text(&body)).unwrap_or_default();
+        let overview = format!(
+            "{}. {}. {} {} {}",
+            content.name,
+            content.topics.join(", "),
+            content.summary,
+            markdown::to_text(&content.description),
+            introduction
+        );
+        passages.push(passage(simulation.id(), None, &overview));
+        for (heading, body) in sections {
+            let text = markdown::to_text(&body);
+            if !text.trim().is_empty() {
+                let text = format!("{heading}. {text}");
+                passages.push(passage(simulation.id(), Some(heading), &text));
+            }
+        }
+    }
+    passages
+}
+
+fn passage(simulation_id: &str, section: Option<String>, text: &str) -> Passage {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    Passage {
+        simulation_id: simulation_id.to_string(),
+        section,
+        text: text.chars().take(MAX_PASSAGE_LENGTH).collect(),
+    }
+}
+
+/// Theory split at its headings into (heading, Markdown body), after the
+/// text before the first heading under an empty one
+fn sections(theory: &str) -> Vec<(String, String)> {
+    let mut sections = vec![(String::new(), String::new())];
+    let mut fenced = false;
+    for line in theory.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            fenced = !fenced;
+        }
+        let heading = trimmed.trim_start_matches('#');
+        if !fenced && trimmed.starts_with('#') && (heading.is_empty() || heading.starts_with(' ')) {
+            let heading = markdown::to_text(heading.trim().trim_end_matches('#').trim().trim_end_matches(':'));
+            sections.push((heading.trim().to_string(), String::new()));
+        } else if let Some((_, body)) = sections.last_mut() {
+            body.push_str(line);
+            body.push('\n');
+        }
+    }
+    sections
+}
+
+fn sha256(text: &str) -> String {
+    Sha256::digest(text.as_bytes()).iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// Scale a vector to unit length; the zero vector stays as it is
+fn normalize(vector: &mut [f32]) {
+    let norm = vector.iter().map(|value| value * value).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|value| *value /= norm);
+    }
+}
@@ -0,0 +1,164 @@
+// Tabular export of simulation results
+
+use serde_json::{Map, Value};
+
+/// One exported column: a dotted path into the result data and its values
+pub struct Column {
+    pub path: String,
+    pub unit: Option<&'static str>,
+    pub values: Vec<Value>,
+}
+
+impl Column {
+    /// Header text, e.g. "t (s)"
+    pub fn label(&self) -> String {
+        match self.unit {
+            Some(unit) => format!("{} ({})", self.path, unit),
+            None => self.path.clone(),
+        }
+    }
+}
+
+/// Columns of equal length that belong in the same table
+pub struct Table {
+    pub columns: Vec<Column>,
+}
+
+impl Table {
+    pub fn rows(&self) -> usize {
+        self.columns.first().map_or(0, |c| c.values.len())
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::new();
+        let header: Vec<String> = self.columns.iter().map(|c| csv_field(&c.label())).collect();
+        csv.push_str(&header.join(","));
+        csv.push_str("\r\n");
+        for row in 0..self.rows() {
+            let fields: Vec<String> = self
+                .columns
+                .iter()
+                .map(|c| match &c.values[row] {
+                    Value::Null => String::new(),
+                    Value::String(s) => csv_field(s),
+                    value => value.to_string(),
+                })
+                .collect();
+            csv.push_str(&fields.join(","));
+            csv.push_str("\r\n");
+        }
+        csv
+    }
+
+    /// One JSON object per row, keyed by column label
+    pub fn to_json_lines(&self) -> String {
+        let mut lines = String::new();
+        for row in 0..self.rows() {
+            let object: Map<String, Value> =
+                self.columns.iter().map(|c| (c.label(), c.values[row].clone())).collect();
+            lines.push_str(&Value::Object(object).to_string());
+            lines.push('\n');
+        }
+        lines
+    }
+}
+
+/// Split result data into tables.
+///
+/// Every array of scalars becomes a column named by its dotted path, with
+/// array indices as path segments (`spectra.0.radiance`). Columns of the
+/// same length are grouped into one table; tables are ordered by column
+/// count, then row count, so the first is the main data set. Scalars and
+/// arrays shorter than two entries are left out.
+///
+/// `units` maps paths to units, with `*` matching any array index.
+pub fn tables(data: &Value, units: &[(&str, &'static str)]) -> Vec<Table> {
+    let mut columns = Vec::new();
+    collect_columns(data, String::new(), &mut columns);
+
+    let mut tables: Vec<Table> = Vec::new();
+    for (path, values) in columns {
+        if values.len() < 2 {
+            continue;
+        }
+        let unit = unit_for(&path, units);
+        let column = Column { path, unit, values };
+        match tables.iter_mut().find(|t| t.rows() == column.values.len()) {
+            Some(table) => table.columns.push(column),
+            None => tables.push(Table { columns: vec![column] }),
+        }
+    }
+    tables.sort_by(|a, b| b.columns.len().cmp(&a.columns.len()).then(b.rows().cmp(&a.rows())));
+    tables
+}
+
+fn collect_columns(value: &Value, path: String, columns: &mut Vec<(String, Vec<Value>)>) {
+    let join = |segment: &str| {
+        if path.is_empty() {
+            segment.to_string()
+        } else {
+            format!("{path}.{segment}")
+        }
+    };
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map {
+                collect_columns(child, join(key), columns);
+            }
+        }
+        Value::Array(items) if items.iter().all(|v| !v.is_array() && !v.is_object()) => {
+            columns.push((path, items.clone()));
+        }
+        Value::Array(items) => {
+            for (i, child) in items.iter().enumerate() {
+                collect_columns(child, join(&i.to_string()), columns);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn unit_for(path: &str, units: &[(&str, &'static str)]) -> Option<&'static str> {
+    let pattern: Vec<&str> = path
+        .split('.')
+        .map(|segment| if segment.parse::<usize>().is_ok() { "*" } else { segment })
+        .collect();
+    let pattern = pattern.join(".");
+    units.iter().find(|(p, _)| *p == pattern).map(|(_, unit)| *unit)
+}
+
+/// Quote a CSV field when it contains a separator, quote or line break.
+/// Text a spreadsheet would take for a formula, starting with `=`, `+`,
+/// `-`, `@`, a tab or a carriage return, gets a leading `'` so it opens as
+/// text: names and titles come from users.
+pub fn csv_field(text: &str) -> String {
+    let defused;
+    let text = if text.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        defused = format!("'{text}");
+        &defused
+    } else {
+        text
+    };
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_fields_are_quoted_and_formulas_defused() {
+        assert_eq!(csv_field("Ada Lovelace"), "Ada Lovelace");
+        assert_eq!(csv_field("Lovelace, Ada"), "\"Lovelace, Ada\"");
+        assert_eq!(csv_field("the \"slit\""), "\"the \"\"slit\"\"\"");
+        assert_eq!(csv_field("=HYPERLINK(\"http://example.com\")"), "\"'=HYPERLINK(\"\"http://example.com\"\")\"");
+        for text in ["+1+1", "-2+3", "@SUM(A1:A2)", "\t=1", "\r=1"] {
+            assert_eq!(csv_field(text).trim_matches('"'), format!("'{text}"));
+        }
+        assert_eq!(csv_field("a=b"), "a=b");
+    }
+}
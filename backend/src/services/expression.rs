@@ -0,0 +1,334 @@
+// Arithmetic expressions over named quantities
+//
+// Formulas such as `2 * L / t^2` or `sqrt(x^2 + y^2)` are parsed once into
+// a tree and evaluated for any values of their variables. The language has
+// numbers (with exponents), variables, + − * / and ^ (or **, right
+// associative), unary minus, parentheses, the constants `pi` and `e`, and
+// the functions below. Variables shadow the constants.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::f64::consts::{E, PI};
+
+/// A function of one argument formulas may call
+type Function = fn(f64) -> f64;
+
+/// Functions formulas may call, by name
+const FUNCTIONS: [(&str, Function); 15] = [
+    ("sqrt", f64::sqrt),
+    ("exp", f64::exp),
+    ("ln", f64::ln),
+    ("log10", f64::log10),
+    ("sin", f64::sin),
+    ("cos", f64::cos),
+    ("tan", f64::tan),
+    ("asin", f64::asin),
+    ("acos", f64::acos),
+    ("atan", f64::atan),
+    ("sinh", f64::sinh),
+    ("cosh", f64::cosh),
+    ("tanh", f64::tanh),
+    ("abs", f64::abs),
+    ("step", step),
+];
+
+/// Longest formula accepted, in bytes
+const MAX_LENGTH: usize = 1_000;
+/// Deepest nesting of parentheses and operators accepted
+const MAX_DEPTH: usize = 64;
+
+/// A parsed formula
+#[derive(Clone, Debug)]
+pub enum Expression {
+    Number(f64),
+    Variable(String),
+    Negate(Box<Expression>),
+    Binary(Operator, Box<Expression>, Box<Expression>),
+    Call(Function, Box<Expression>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Power,
+}
+
+impl Expression {
+    /// Parse a formula, or say what is wrong with it and where
+    pub fn parse(text: &str) -> Result<Self, String> {
+        if text.len() > MAX_LENGTH {
+            return Err(format!("is longer than {MAX_LENGTH} characters"));
+        }
+        let tokens = tokenize(text)?;
+        let mut parser = Parser { tokens, position: 0, depth: 0 };
+        let expression = parser.sum()?;
+        match parser.tokens.get(parser.position) {
+            None => Ok(expression),
+            Some((token, at)) => Err(format!("unexpected {} at position {}", token.describe(), at + 1)),
+        }
+    }
+
+    /// Names the formula uses as variables, `pi` and `e` included if it
+    /// uses them
+    pub fn variables(&self) -> BTreeSet<&str> {
+        let mut names = BTreeSet::new();
+        self.collect_variables(&mut names);
+        names
+    }
+
+    fn collect_variables<'a>(&'a self, names: &mut BTreeSet<&'a str>) {
+        match self {
+            Self::Number(_) => {}
+            Self::Variable(name) => {
+                names.insert(name);
+            }
+            Self::Negate(operand) | Self::Call(_, operand) => operand.collect_variables(names),
+            Self::Binary(_, left, right) => {
+                left.collect_variables(names);
+                right.collect_variables(names);
+            }
+        }
+    }
+
+    /// Value of the formula; variables missing from `values` take the
+    /// constant of that name, or NaN
+    pub fn eval(&self, values: &BTreeMap<String, f64>) -> f64 {
+        match self {
+            Self::Number(value) => *value,
+            Self::Variable(name) => values.get(name).copied().or_else(|| constant(name)).unwrap_or(f64::NAN),
+            Self::Negate(operand) => -operand.eval(values),
+            Self::Call(function, argument) => function(argument.eval(values)),
+            Self::Binary(operator, left, right) => {
+                let (left, right) = (left.eval(values), right.eval(values));
+                match operator {
+                    Operator::Add => left + right,
+                    Operator::Subtract => left - right,
+                    Operator::Multiply => left * right,
+                    Operator::Divide => left / right,
+                    Operator::Power => left.powf(right),
+                }
+            }
+        }
+    }
+}
+
+/// Unit step: 0 for negative arguments, 1 from zero on, for piecewise
+/// formulas such as `5 * step(x - 1) * step(2 - x)`
+fn step(x: f64) -> f64 {
+    if x >= 0.0 {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+fn constant(name: &str) -> Option<f64> {
+    match name {
+        "pi" => Some(PI),
+        "e" => Some(E),
+        _ => None,
+    }
+}
+
+/// Whether `name` is one of the built-in constants
+pub fn is_constant(name: &str) -> bool {
+    constant(name).is_some()
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(f64),
+    Name(String),
+    Operator(Operator),
+    Open,
+    Close,
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Self::Number(value) => format!("number {value}"),
+            Self::Name(name) => format!("`{name}`"),
+            Self::Operator(operator) => format!(
+                "`{}`",
+                match operator {
+                    Operator::Add => "+",
+                    Operator::Subtract => "-",
+                    Operator::Multiply => "*",
+                    Operator::Divide => "/",
+                    Operator::Power => "^",
+                }
+            ),
+            Self::Open => "`(`".to_string(),
+            Self::Close => "`)`".to_string(),
+        }
+    }
+}
+
+/// Tokens with the character position each starts at
+fn tokenize(text: &str) -> Result<Vec<(Token, usize)>, String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let start = i;
+        let c = chars[i];
+        let token = match c {
+            _ if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            '0'..='9' | '.' => {
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                // An exponent, as in 6.674e-11
+                if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                    let sign = usize::from(i + 1 < chars.len() && matches!(chars[i + 1], '+' | '-'));
+                    if chars.get(i + 1 + sign).is_some_and(char::is_ascii_digit) {
+                        i += 1 + sign;
+                        while i < chars.len() && chars[i].is_ascii_digit() {
+                            i += 1;
+                        }
+                    }
+                }
+                let literal: String = chars[start..i].iter().collect();
+                Token::Number(literal.parse().map_err(|_| format!("`{literal}` at position {} is not a number", start + 1))?)
+            }
+            _ if c.is_alphabetic() || c == '_' => {
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                Token::Name(chars[start..i].iter().collect())
+            }
+            '*' if chars.get(i + 1) == Some(&'*') => {
+                i += 2;
+                Token::Operator(Operator::Power)
+            }
+            _ => {
+                i += 1;
+                match c {
+                    '+' => Token::Operator(Operator::Add),
+                    '-' | '−' => Token::Operator(Operator::Subtract),
+                    '*' | '×' | '·' => Token::Operator(Operator::Multiply),
+                    '/' => Token::Operator(Operator::Divide),
+                    '^' => Token::Operator(Operator::Power),
+                    '(' => Token::Open,
+                    ')' => Token::Close,
+                    _ => return Err(format!("unexpected `{c}` at position {}", start + 1)),
+                }
+            }
+        };
+        tokens.push((token, start));
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent over the tokens, one method per precedence level
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(token, _)| token)
+    }
+
+    fn next(&mut self) -> Result<Token, String> {
+        let token = self.tokens.get(self.position).map(|(token, _)| token.clone());
+        self.position += 1;
+        token.ok_or_else(|| "ends too early".to_string())
+    }
+
+    fn descend(&mut self) -> Result<(), String> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(format!("nests deeper than {MAX_DEPTH} levels"));
+        }
+        Ok(())
+    }
+
+    /// sum := product (('+' | '-') product)*
+    fn sum(&mut self) -> Result<Expression, String> {
+        let mut left = self.product()?;
+        while let Some(Token::Operator(operator @ (Operator::Add | Operator::Subtract))) = self.peek().cloned() {
+            self.position += 1;
+            left = Expression::Binary(operator, Box::new(left), Box::new(self.product()?));
+        }
+        Ok(left)
+    }
+
+    /// product := unary (('*' | '/') unary)*
+    fn product(&mut self) -> Result<Expression, String> {
+        let mut left = self.unary()?;
+        while let Some(Token::Operator(operator @ (Operator::Multiply | Operator::Divide))) = self.peek().cloned() {
+            self.position += 1;
+            left = Expression::Binary(operator, Box::new(left), Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+
+    /// unary := '-' unary | power, so that -x^2 is −(x²)
+    fn unary(&mut self) -> Result<Expression, String> {
+        self.descend()?;
+        let expression = match self.peek() {
+            Some(Token::Operator(Operator::Subtract)) => {
+                self.position += 1;
+                Expression::Negate(Box::new(self.unary()?))
+            }
+            Some(Token::Operator(Operator::Add)) => {
+                self.position += 1;
+                self.unary()?
+            }
+            _ => self.power()?,
+        };
+        self.depth -= 1;
+        Ok(expression)
+    }
+
+    /// power := atom ('^' unary)?
+    fn power(&mut self) -> Result<Expression, String> {
+        let base = self.atom()?;
+        if self.peek() == Some(&Token::Operator(Operator::Power)) {
+            self.position += 1;
+            return Ok(Expression::Binary(Operator::Power, Box::new(base), Box::new(self.unary()?)));
+        }
+        Ok(base)
+    }
+
+    /// atom := number | name | name '(' sum ')' | '(' sum ')'
+    fn atom(&mut self) -> Result<Expression, String> {
+        let at = self.tokens.get(self.position).map(|(_, at)| at + 1);
+        match self.next()? {
+            Token::Number(value) => Ok(Expression::Number(value)),
+            Token::Name(name) if self.peek() == Some(&Token::Open) => {
+                let Some(&(_, function)) = FUNCTIONS.iter().find(|(known, _)| *known == name) else {
+                    let known: Vec<&str> = FUNCTIONS.iter().map(|(name, _)| *name).collect();
+                    return Err(format!("unknown function `{name}`; known are {}", known.join(", ")));
+                };
+                self.position += 1;
+                let argument = self.sum()?;
+                self.close()?;
+                Ok(Expression::Call(function, Box::new(argument)))
+            }
+            Token::Name(name) => Ok(Expression::Variable(name)),
+            Token::Open => {
+                let inner = self.sum()?;
+                self.close()?;
+                Ok(inner)
+            }
+            token => Err(format!("unexpected {} at position {}", token.describe(), at.unwrap_or_default())),
+        }
+    }
+
+    fn close(&mut self) -> Result<(), String> {
+        match self.next() {
+            Ok(Token::Close) => Ok(()),
+            _ => Err("has an unclosed `(`".to_string()),
+        }
+    }
+}
@@ -0,0 +1,344 @@
+// Curve fits of measured or simulated data
+//
+// A fit finds the parameters of one of a few standard models that best
+// describe a set of (x, y) points, with Levenberg–Marquardt least squares.
+// Starting guesses come from the data: the parameter that enters the model
+// nonlinearly (a rate or an angular frequency) is scanned over a grid, the
+// others solved exactly at each grid point, and the best combination
+// refined. Points with uncertainties are weighted by them and give absolute
+// parameter uncertainties and a p-value; without them every point counts
+// the same and the uncertainties are scaled by the scatter about the fit.
+
+use std::collections::BTreeMap;
+use std::f64::consts::PI;
+
+use serde::{Deserialize, Serialize};
+
+use crate::numerics::least_squares::levenberg_marquardt;
+use crate::numerics::linalg::invert;
+use crate::numerics::special::gamma_q;
+
+/// Points in the rate scan of the exponential guess, per sign
+const RATE_GRID: usize = 200;
+/// Rates scanned run from this fraction of 1/span to this multiple of it
+const RATE_RANGE: f64 = 100.0;
+/// Most angular frequencies in the sinusoid scan
+const MAX_FREQUENCY_GRID: usize = 4096;
+/// FWHM of a Gaussian over its standard deviation, 2√(2 ln 2)
+const FWHM_PER_SIGMA: f64 = 2.354_820_045_030_949;
+
+/// Function fitted to the data
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Model {
+    Linear,
+    Exponential,
+    Sinusoidal,
+    Gaussian,
+}
+
+impl Model {
+    /// Name as used in requests
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Linear => "linear",
+            Self::Exponential => "exponential",
+            Self::Sinusoidal => "sinusoidal",
+            Self::Gaussian => "gaussian",
+        }
+    }
+
+    /// Parameter names, in the order the model takes them
+    pub fn parameters(self) -> &'static [&'static str] {
+        match self {
+            Self::Linear => &["slope", "intercept"],
+            Self::Exponential => &["amplitude", "rate", "offset"],
+            Self::Sinusoidal => &["amplitude", "angular_frequency", "phase", "offset"],
+            Self::Gaussian => &["amplitude", "mean", "standard_deviation"],
+        }
+    }
+
+    /// The model in LaTeX, in the parameter names' symbols
+    pub fn formula(self) -> &'static str {
+        match self {
+            Self::Linear => r"y = m x + b",
+            Self::Exponential => r"y = A e^{k x} + c",
+            Self::Sinusoidal => r"y = A \sin(\omega x + \varphi) + c",
+            Self::Gaussian => r"y = A e^{-(x - \mu)^2 / 2\sigma^2}",
+        }
+    }
+
+    fn eval(self, p: &[f64], x: f64) -> f64 {
+        match self {
+            Self::Linear => p[0] * x + p[1],
+            Self::Exponential => p[0] * (p[1] * x).exp() + p[2],
+            Self::Sinusoidal => p[0] * (p[1] * x + p[2]).sin() + p[3],
+            Self::Gaussian => p[0] * (-(x - p[1]).powi(2) / (2.0 * p[2] * p[2])).exp(),
+        }
+    }
+
+    /// Starting parameters read off the points, which are sorted by x
+    fn initial(self, xs: &[f64], ys: &[f64]) -> Vec<f64> {
+        let span = xs[xs.len() - 1] - xs[0];
+        let mean = ys.iter().sum::<f64>() / ys.len() as f64;
+        if span <= 0.0 {
+            return match self {
+                Self::Linear => vec![0.0, mean],
+                Self::Exponential => vec![mean, 0.0, 0.0],
+                Self::Sinusoidal => vec![0.0, 1.0, 0.0, mean],
+                Self::Gaussian => vec![mean, xs[0], 1.0],
+            };
+        }
+        match self {
+            Self::Linear => linear_fit(&[|x| x, |_| 1.0], xs, ys)
+                .map(|(coefficients, _)| coefficients)
+                .unwrap_or_else(|| vec![0.0, mean]),
+            Self::Exponential => {
+                // Scan k with e^{k(x − x₀)}, which cannot overflow over the
+                // span, then move the amplitude to x = 0
+                let x0 = xs[0];
+                let scale = RATE_RANGE * RATE_RANGE;
+                let rates = (0..RATE_GRID).map(|i| (scale.powf(i as f64 / (RATE_GRID - 1) as f64) / RATE_RANGE) / span);
+                rates
+                    .flat_map(|rate| [rate, -rate])
+                    .filter_map(|rate| {
+                        let shifted: Vec<f64> = xs.iter().map(|x| (rate * (x - x0)).exp()).collect();
+                        linear_fit(&[|x| x, |_| 1.0], &shifted, ys)
+                            .map(|(c, residual)| (vec![c[0] * (-rate * x0).exp(), rate, c[1]], residual))
+                    })
+                    .min_by(|a, b| a.1.total_cmp(&b.1))
+                    .map(|(params, _)| params)
+                    .unwrap_or_else(|| vec![mean, 0.0, 0.0])
+            }
+            Self::Sinusoidal => {
+                // From half a period over the span up to the Nyquist frequency
+                // of the average spacing, in steps that turn the phase across
+                // the span by a quarter
+                let lowest = PI / span;
+                let highest = PI * (xs.len() - 1) as f64 / span;
+                let count = ((highest - lowest) / (0.5 * lowest)).ceil() as usize + 1;
+                let count = count.clamp(2, MAX_FREQUENCY_GRID);
+                (0..count)
+                    .map(|i| lowest + (highest - lowest) * i as f64 / (count - 1) as f64)
+                    .filter_map(|omega| {
+                        let (sines, cosines): (Vec<f64>, Vec<f64>) =
+                            xs.iter().map(|x| ((omega * x).sin(), (omega * x).cos())).unzip();
+                        linear_fit_columns(&[&sines, &cosines, &vec![1.0; xs.len()]], ys).map(|(c, residual)| {
+                            // a sin ωx + b cos ωx = A sin(ωx + φ)
+                            (vec![c[0].hypot(c[1]), omega, c[1].atan2(c[0]), c[2]], residual)
+                        })
+                    })
+                    .min_by(|a, b| a.1.total_cmp(&b.1))
+                    .map(|(params, _)| params)
+                    .unwrap_or_else(|| vec![0.0, lowest, 0.0, mean])
+            }
+            Self::Gaussian => {
+                let peak = (0..ys.len()).max_by(|&a, &b| ys[a].abs().total_cmp(&ys[b].abs())).unwrap_or(0);
+                let half = ys[peak].abs() / 2.0;
+                let left = (0..peak).rev().find(|&i| ys[i].abs() < half).map_or(xs[0], |i| xs[i]);
+                let right = (peak + 1..ys.len()).find(|&i| ys[i].abs() < half).map_or(xs[xs.len() - 1], |i| xs[i]);
+                let width = (right - left) / FWHM_PER_SIGMA;
+                let width = if width > 0.0 { width } else { span / xs.len() as f64 };
+                vec![ys[peak], xs[peak], width]
+            }
+        }
+    }
+
+    /// Turn parameters for x measured from `origin` into parameters for x
+    /// measured from zero, returning the derivatives of each new parameter
+    /// with respect to the old ones. Moving by −origin undoes it.
+    fn move_origin(self, p: &mut [f64], origin: f64) -> Vec<Vec<f64>> {
+        let mut jacobian: Vec<Vec<f64>> =
+            (0..p.len()).map(|i| (0..p.len()).map(|j| if i == j { 1.0 } else { 0.0 }).collect()).collect();
+        match self {
+            Self::Linear => {
+                p[1] -= p[0] * origin;
+                jacobian[1][0] = -origin;
+            }
+            Self::Exponential => {
+                let factor = (-p[1] * origin).exp();
+                p[0] *= factor;
+                jacobian[0][0] = factor;
+                jacobian[0][1] = -origin * p[0];
+            }
+            Self::Sinusoidal => {
+                p[2] -= p[1] * origin;
+                jacobian[2][1] = -origin;
+            }
+            Self::Gaussian => p[1] += origin,
+        }
+        jacobian
+    }
+
+    /// Put equivalent solutions in one form: a positive amplitude and a
+    /// phase in (−π, π] for sinusoids, a positive width for Gaussians
+    fn normalize(self, p: &mut [f64]) {
+        match self {
+            Self::Sinusoidal => {
+                if p[1] < 0.0 {
+                    p[1] = -p[1];
+                    p[2] = -p[2];
+                    p[0] = -p[0];
+                }
+                if p[0] < 0.0 {
+                    p[0] = -p[0];
+                    p[2] += PI;
+                }
+                p[2] = PI - (PI - p[2]).rem_euclid(2.0 * PI);
+            }
+            Self::Gaussian => p[2] = p[2].abs(),
+            Self::Linear | Self::Exponential => {}
+        }
+    }
+}
+
+/// Where the parameter uncertainties come from
+#[derive(Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UncertaintySource {
+    /// The uncertainties given with the points
+    Measurements,
+    /// The scatter of the points about the fit, for unweighted data
+    Scatter,
+}
+
+/// Fitted value of one model parameter
+#[derive(Serialize)]
+pub struct FittedParameter {
+    pub name: &'static str,
+    pub value: f64,
+    /// One standard deviation; absent when the data cannot pin the
+    /// parameter down, or leaves no degrees of freedom to estimate it from
+    pub uncertainty: Option<f64>,
+}
+
+/// Best fit of a model to data
+#[derive(Serialize)]
+pub struct CurveFit {
+    pub model: Model,
+    pub formula: &'static str,
+    pub parameters: Vec<FittedParameter>,
+    pub uncertainties: UncertaintySource,
+    pub chi_squared: f64,
+    /// Points less fitted parameters
+    pub degrees_of_freedom: usize,
+    pub reduced_chi_squared: Option<f64>,
+    /// Probability of a χ² at least this large if the model were right;
+    /// only for points with uncertainties
+    pub p_value: Option<f64>,
+    /// Fraction of the variance of y the model accounts for
+    pub r_squared: Option<f64>,
+    /// Whether the fit settled at a minimum within the iteration limit
+    pub converged: bool,
+    pub iterations: usize,
+    /// y − model at each point, in request order
+    pub residuals: Vec<f64>,
+}
+
+/// Fit `model` to the points. `sigmas` are the uncertainties of the ys, if
+/// measured; `guesses` override starting values by parameter name.
+pub fn fit(model: Model, xs: &[f64], ys: &[f64], sigmas: Option<&[f64]>, guesses: &BTreeMap<String, f64>) -> CurveFit {
+    // Fitting about the mean x keeps the parameters from being strongly
+    // correlated, or an exponential's amplitude from overflowing, when the
+    // data lies far from x = 0
+    let origin = xs.iter().sum::<f64>() / xs.len() as f64;
+    let shifted: Vec<f64> = xs.iter().map(|x| x - origin).collect();
+    let mut order: Vec<usize> = (0..xs.len()).collect();
+    order.sort_by(|&a, &b| xs[a].total_cmp(&xs[b]));
+    let sorted_xs: Vec<f64> = order.iter().map(|&i| shifted[i]).collect();
+    let sorted_ys: Vec<f64> = order.iter().map(|&i| ys[i]).collect();
+    let mut initial = model.initial(&sorted_xs, &sorted_ys);
+    if !guesses.is_empty() {
+        model.move_origin(&mut initial, origin);
+        for (name, value) in model.parameters().iter().zip(initial.iter_mut()) {
+            if let Some(&guess) = guesses.get(*name) {
+                *value = guess;
+            }
+        }
+        model.move_origin(&mut initial, -origin);
+    }
+
+    let unweighted = vec![1.0; xs.len()];
+    let weights = sigmas.unwrap_or(&unweighted);
+    let result = levenberg_marquardt(|p, x| model.eval(p, x), &shifted, ys, weights, &initial);
+    let residuals: Vec<f64> = shifted.iter().zip(ys).map(|(&x, &y)| y - model.eval(&result.params, x)).collect();
+    let mut values = result.params;
+    let jacobian = model.move_origin(&mut values, origin);
+    model.normalize(&mut values);
+
+    let degrees_of_freedom = xs.len().saturating_sub(values.len());
+    let reduced_chi_squared = (degrees_of_freedom > 0).then(|| result.chi_squared / degrees_of_freedom as f64);
+    // Without measured uncertainties, σ² is estimated from the residuals
+    let variance_scale = match sigmas {
+        Some(_) => Some(1.0),
+        None => reduced_chi_squared,
+    };
+    let parameters = model
+        .parameters()
+        .iter()
+        .zip(&values)
+        .zip(&jacobian)
+        .map(|((&name, &value), gradient)| FittedParameter {
+            name,
+            value,
+            // Propagated through the change of origin: σ² = ∇ᵀ C ∇
+            uncertainty: result
+                .covariance
+                .as_ref()
+                .zip(variance_scale)
+                .map(|(covariance, scale)| {
+                    let variance: f64 = covariance
+                        .iter()
+                        .zip(gradient)
+                        .map(|(row, a)| a * row.iter().zip(gradient).map(|(c, b)| c * b).sum::<f64>())
+                        .sum();
+                    (variance * scale).sqrt()
+                })
+                .filter(|uncertainty| uncertainty.is_finite()),
+        })
+        .collect();
+
+    let mean = ys.iter().sum::<f64>() / ys.len() as f64;
+    let total: f64 = ys.iter().map(|y| (y - mean).powi(2)).sum();
+    let unexplained: f64 = residuals.iter().map(|r| r * r).sum();
+    CurveFit {
+        model,
+        formula: model.formula(),
+        parameters,
+        uncertainties: if sigmas.is_some() { UncertaintySource::Measurements } else { UncertaintySource::Scatter },
+        chi_squared: result.chi_squared,
+        degrees_of_freedom,
+        reduced_chi_squared,
+        p_value: sigmas
+            .and(reduced_chi_squared)
+            .map(|_| gamma_q(degrees_of_freedom as f64 / 2.0, result.chi_squared / 2.0)),
+        r_squared: (total > 0.0).then(|| 1.0 - unexplained / total),
+        converged: result.converged,
+        iterations: result.iterations,
+        residuals,
+    }
+}
+
+/// Linear least squares of ys on the given functions of xs: the
+/// coefficients and the sum of squared residuals
+fn linear_fit(basis: &[fn(f64) -> f64], xs: &[f64], ys: &[f64]) -> Option<(Vec<f64>, f64)> {
+    let columns: Vec<Vec<f64>> = basis.iter().map(|f| xs.iter().map(|&x| f(x)).collect()).collect();
+    let columns: Vec<&[f64]> = columns.iter().map(Vec::as_slice).collect();
+    linear_fit_columns(&columns, ys)
+}
+
+/// Linear least squares of ys on the given columns
+fn linear_fit_columns(columns: &[&[f64]], ys: &[f64]) -> Option<(Vec<f64>, f64)> {
+    let dot = |a: &[f64], b: &[f64]| a.iter().zip(b).map(|(a, b)| a * b).sum::<f64>();
+    let normal: Vec<Vec<f64>> = columns.iter().map(|a| columns.iter().map(|b| dot(a, b)).collect()).collect();
+    let inverse = invert(&normal)?;
+    let projections: Vec<f64> = columns.iter().map(|column| dot(column, ys)).collect();
+    let coefficients: Vec<f64> = inverse.iter().map(|row| dot(row, &projections)).collect();
+    let residual = ys
+        .iter()
+        .enumerate()
+        .map(|(i, y)| y - columns.iter().zip(&coefficients).map(|(column, c)| column[i] * c).sum::<f64>())
+        .map(|r| r * r)
+        .sum();
+    Some((coefficients, residual))
+}
@@ -0,0 +1,271 @@
+// Animated GIF encoding
+
+use std::collections::HashMap;
+
+/// Largest LZW code width allowed by the GIF format
+const MAX_CODE_BITS: u32 = 12;
+
+/// Encode indexed-colour frames as a looping GIF89a animation.
+///
+/// Every frame holds `width × height` palette indices. `delay` is the time
+/// each frame is shown, in hundredths of a second. The palette is padded to
+/// a power of two with black.
+pub fn encode_gif(width: u16, height: u16, palette: &[[u8; 3]], frames: &[Vec<u8>], delay: u16) -> Vec<u8> {
+    // Colour table size is 2^(bits) entries, at least 2^2 so the LZW minimum
+    // code size is valid
+    let bits = (palette.len().max(4) as u32).next_power_of_two().trailing_zeros();
+
+    let mut gif = b"GIF89a".to_vec();
+    gif.extend_from_slice(&width.to_le_bytes());
+    gif.extend_from_slice(&height.to_le_bytes());
+    gif.push(0x80 | 0x70 | (bits - 1) as u8); // global table, 8-bit colour resolution
+    gif.push(0); // background colour index
+    gif.push(0); // square pixels
+    for i in 0..1usize << bits {
+        gif.extend_from_slice(palette.get(i).unwrap_or(&[0, 0, 0]));
+    }
+
+    // Loop forever
+    gif.extend_from_slice(&[0x21, 0xFF, 0x0B]);
+    gif.extend_from_slice(b"NETSCAPE2.0");
+    gif.extend_from_slice(&[0x03, 0x01, 0x00, 0x00, 0x00]);
+
+    for frame in frames {
+        // Graphic control: leave the frame in place, no transparency
+        gif.extend_from_slice(&[0x21, 0xF9, 0x04, 0x04]);
+        gif.extend_from_slice(&delay.to_le_bytes());
+        gif.extend_from_slice(&[0x00, 0x00]);
+
+        gif.push(0x2C);
+        gif.extend_from_slice(&[0, 0, 0, 0]);
+        gif.extend_from_slice(&width.to_le_bytes());
+        gif.extend_from_slice(&height.to_le_bytes());
+        gif.push(0); // no local colour table, not interlaced
+
+        gif.push(bits as u8);
+        for block in lzw_compress(frame, bits).chunks(255) {
+            gif.push(block.len() as u8);
+            gif.extend_from_slice(block);
+        }
+        gif.push(0);
+    }
+
+    gif.push(0x3B);
+    gif
+}
+
+/// Variable-width LZW as used by GIF, with codes packed LSB first
+fn lzw_compress(pixels: &[u8], min_bits: u32) -> Vec<u8> {
+    let clear = 1u16 << min_bits;
+    let end = clear + 1;
+    let mut writer = BitWriter::default();
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next = end + 1;
+    let mut width = min_bits + 1;
+
+    writer.write(clear, width);
+    let Some((&first, rest)) = pixels.split_first() else {
+        writer.write(end, width);
+        return writer.finish();
+    };
+    let mut prefix = first as u16;
+    for &pixel in rest {
+        if let Some(&code) = table.get(&(prefix, pixel)) {
+            prefix = code;
+            continue;
+        }
+        writer.write(prefix, width);
+        if next < 1 << MAX_CODE_BITS {
+            table.insert((prefix, pixel), next);
+            next += 1;
+            // The decoder adds each entry one code later, so widen once the
+            // entry just added no longer fits the current width
+            if next > 1 << width && width < MAX_CODE_BITS {
+                width += 1;
+            }
+        } else {
+            writer.write(clear, width);
+            table.clear();
+            next = end + 1;
+            width = min_bits + 1;
+        }
+        prefix = pixel as u16;
+    }
+    writer.write(prefix, width);
+    writer.write(end, width);
+    writer.finish()
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    count: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, width: u32) {
+        self.buffer |= (code as u32) << self.count;
+        self.count += width;
+        while self.count >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reference GIF LZW decoder, written from the GIF89a specification
+    fn lzw_decompress(data: &[u8], min_bits: u32) -> Vec<u8> {
+        let clear = 1usize << min_bits;
+        let end = clear + 1;
+        let mut table: Vec<Vec<u8>> = Vec::new();
+        let mut width = min_bits + 1;
+        let mut previous: Option<usize> = None;
+        let mut pixels = Vec::new();
+        let (mut buffer, mut count, mut bytes) = (0u32, 0u32, data.iter());
+        loop {
+            while count < width {
+                let byte = *bytes.next().unwrap_or_else(|| panic!("ran out of data before the end code"));
+                buffer |= u32::from(byte) << count;
+                count += 8;
+            }
+            let code = (buffer & ((1 << width) - 1)) as usize;
+            buffer >>= width;
+            count -= width;
+
+            if code == clear {
+                table = (0..clear).map(|i| vec![i as u8]).chain([Vec::new(), Vec::new()]).collect();
+                width = min_bits + 1;
+                previous = None;
+                continue;
+            }
+            if code == end {
+                return pixels;
+            }
+            let entry = match (previous, table.get(code).cloned()) {
+                (None, Some(entry)) => entry,
+                (Some(previous), Some(entry)) => {
+                    let mut added = table[previous].clone();
+                    added.push(entry[0]);
+                    table.push(added);
+                    entry
+                }
+                (Some(previous), None) => {
+                    assert_eq!(code, table.len(), "code {code} is not yet defined");
+                    let mut added = table[previous].clone();
+                    added.push(added[0]);
+                    table.push(added.clone());
+                    added
+                }
+                (None, None) => panic!("code {code} is not defined after a clear"),
+            };
+            pixels.extend_from_slice(&entry);
+            if table.len() == 1 << width && width < MAX_CODE_BITS {
+                width += 1;
+            }
+            previous = Some(code);
+        }
+    }
+
+    /// Image data of every frame, after the minimum code size byte
+    fn frames(gif: &[u8], colours: usize) -> Vec<(u8, Vec<u8>)> {
+        let mut pos = 13 + 3 * colours + 19;
+        let mut frames = Vec::new();
+        while gif[pos] == 0x21 {
+            assert_eq!(&gif[pos..pos + 4], &[0x21, 0xF9, 0x04, 0x04]);
+            pos += 8 + 10;
+            let min_bits = gif[pos];
+            pos += 1;
+            let mut data = Vec::new();
+            while gif[pos] != 0 {
+                let len = usize::from(gif[pos]);
+                data.extend_from_slice(&gif[pos + 1..pos + 1 + len]);
+                pos += 1 + len;
+            }
+            frames.push((min_bits, data));
+            pos += 1;
+        }
+        assert_eq!(&gif[pos..], &[0x3B]);
+        frames
+    }
+
+    #[test]
+    fn compresses_the_specification_sample() {
+        // The 10×10 sample image commonly used to explain GIF's LZW, with
+        // its well-known compressed bytes
+        let rows = [
+            "1111122222", "1111122222", "1111122222", "1110000222", "1110000222",
+            "2220000111", "2220000111", "2222211111", "2222211111", "2222211111",
+        ];
+        let pixels: Vec<u8> = rows.concat().bytes().map(|b| b - b'0').collect();
+        let expected = [
+            0x8C, 0x2D, 0x99, 0x87, 0x2A, 0x1C, 0xDC, 0x33, 0xA0, 0x02, 0x75, 0xEC, 0x95, 0xFA, 0xA8, 0xDE, 0x60,
+            0x8C, 0x04, 0x91, 0x4C, 0x01,
+        ];
+        assert_eq!(lzw_compress(&pixels, 2), expected);
+        assert_eq!(lzw_decompress(&expected, 2), pixels);
+    }
+
+    #[test]
+    fn round_trips() {
+        // Repetitive and noisy data, long enough to fill the code table and
+        // force clear codes
+        let mut seed = 1u32;
+        let noise: Vec<u8> = (0..40_000)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                (seed >> 16) as u8
+            })
+            .collect();
+        let stripes: Vec<u8> = (0..30_000).map(|i| ((i / 7) % 16) as u8).collect();
+        let cases: [(&[u8], u32); 5] =
+            [(&[], 2), (&[3], 2), (&[0, 0, 0, 0, 0, 0, 0], 2), (&stripes, 4), (&noise, 8)];
+        for (pixels, min_bits) in cases {
+            assert_eq!(lzw_decompress(&lzw_compress(pixels, min_bits), min_bits), pixels);
+        }
+    }
+
+    #[test]
+    fn animation() {
+        let palette = [[255, 0, 0], [0, 255, 0], [0, 0, 255]];
+        let first = vec![0, 1, 2, 0, 1, 2];
+        let second = vec![2, 2, 2, 1, 1, 1];
+        let gif = encode_gif(3, 2, &palette, &[first.clone(), second.clone()], 5);
+
+        assert_eq!(&gif[..6], b"GIF89a");
+        assert_eq!(&gif[6..10], &[3, 0, 2, 0]);
+        // Global table of four colours, padded with black
+        assert_eq!(gif[10], 0xF1);
+        assert_eq!(&gif[13..25], &[255, 0, 0, 0, 255, 0, 0, 0, 255, 0, 0, 0]);
+        assert_eq!(&gif[25..28], &[0x21, 0xFF, 0x0B]);
+        assert_eq!(&gif[28..39], b"NETSCAPE2.0");
+
+        let decoded: Vec<Vec<u8>> =
+            frames(&gif, 4).into_iter().map(|(min_bits, data)| lzw_decompress(&data, u32::from(min_bits))).collect();
+        assert_eq!(decoded, [first, second]);
+    }
+
+    #[test]
+    fn large_frames_are_split_into_blocks() {
+        let pixels: Vec<u8> = (0..200 * 200).map(|i| ((i * 31 + i / 200) % 256) as u8).collect();
+        let palette: Vec<[u8; 3]> = (0..=255).map(|i| [i, i, i]).collect();
+        let gif = encode_gif(200, 200, &palette, std::slice::from_ref(&pixels), 10);
+        let frames = frames(&gif, 256);
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].0, 8);
+        assert!(frames[0].1.len() > 255);
+        assert_eq!(lzw_decompress(&frames[0].1, 8), pixels);
+    }
+}
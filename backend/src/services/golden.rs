@@ -0,0 +1,276 @@
+// Golden outputs for simulation self-tests
+//
+// Each simulation may have a golden file, `content/golden/<id>.json`, that
+// records the output of one run with canonical parameters: every scalar
+// exactly, and every numeric array as its length, extremes, mean and a few
+// evenly spaced samples, which keeps the files small while still catching a
+// changed formula or integrator. A self-test reruns the recorded parameters
+// with the recorded computation version and checks each output against the
+// golden value within the file's tolerance.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Evenly spaced samples kept of each array
+const SAMPLES: usize = 8;
+
+/// Recorded run of one simulation
+#[derive(Serialize, Deserialize)]
+pub struct Golden {
+    pub simulation_version: u32,
+    /// Validated parameters of the recorded run
+    pub parameters: Map<String, Value>,
+    #[serde(default)]
+    pub tolerance: Tolerance,
+    /// Expected values by dotted path into the run data
+    pub outputs: BTreeMap<String, Expected>,
+}
+
+/// How far an output may stray from its golden value:
+/// |actual − expected| ≤ absolute + relative · |expected|
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Tolerance {
+    pub relative: f64,
+    pub absolute: f64,
+}
+
+impl Default for Tolerance {
+    /// Loose enough for floating point differences between platforms, tight
+    /// enough for any change to the physics
+    fn default() -> Self {
+        Self {
+            relative: 1e-6,
+            absolute: 1e-9,
+        }
+    }
+}
+
+impl Tolerance {
+    fn allows(&self, actual: f64, expected: f64) -> bool {
+        actual == expected || (actual - expected).abs() <= self.absolute + self.relative * expected.abs()
+    }
+}
+
+/// Golden value of one output
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Expected {
+    Number(f64),
+    Array(Summary),
+    /// Strings, booleans and nulls, which must match exactly
+    Exact(Value),
+}
+
+/// What is kept of a numeric array, nested arrays flattened
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Summary {
+    pub length: usize,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    /// Values at evenly spaced indices, first and last included
+    pub samples: Vec<f64>,
+}
+
+impl Summary {
+    fn of(values: &[f64]) -> Self {
+        let length = values.len();
+        let samples = match length {
+            0 => Vec::new(),
+            1 => values.to_vec(),
+            _ => {
+                let count = SAMPLES.min(length);
+                (0..count).map(|i| values[i * (length - 1) / (count - 1)]).collect()
+            }
+        };
+        Self {
+            length,
+            min: values.iter().copied().fold(f64::INFINITY, f64::min),
+            max: values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            mean: values.iter().sum::<f64>() / length.max(1) as f64,
+            samples,
+        }
+    }
+
+    fn numbers(&self) -> impl Iterator<Item = f64> + '_ {
+        [self.min, self.max, self.mean].into_iter().chain(self.samples.iter().copied())
+    }
+}
+
+/// Outcome of checking one output
+#[derive(Serialize)]
+pub struct Check {
+    /// Dotted path into the run data
+    pub output: String,
+    pub passed: bool,
+    pub expected: Expected,
+    /// Absent when the run no longer has this output
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub actual: Option<Expected>,
+    /// Largest |actual − expected| over the compared numbers
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deviation: Option<f64>,
+}
+
+impl Golden {
+    /// Golden record of a run
+    pub fn record(simulation_version: u32, parameters: Map<String, Value>, data: &Value) -> Self {
+        let mut outputs = BTreeMap::new();
+        collect(data, String::new(), &mut outputs);
+        Self {
+            simulation_version,
+            parameters,
+            tolerance: Tolerance::default(),
+            outputs,
+        }
+    }
+
+    /// Check every golden output against a rerun. Outputs the rerun has and
+    /// the golden file does not are not checked.
+    pub fn check(&self, data: &Value) -> Vec<Check> {
+        let mut actual = BTreeMap::new();
+        collect(data, String::new(), &mut actual);
+        self.outputs
+            .iter()
+            .map(|(output, expected)| {
+                let found = actual.remove(output);
+                let deviation = found.as_ref().and_then(|found| deviation(expected, found));
+                let passed = match (expected, &found) {
+                    (Expected::Number(e), Some(Expected::Number(a))) => self.tolerance.allows(*a, *e),
+                    (Expected::Array(e), Some(Expected::Array(a))) => {
+                        e.length == a.length
+                            && e.samples.len() == a.samples.len()
+                            && e.numbers().zip(a.numbers()).all(|(e, a)| self.tolerance.allows(a, e))
+                    }
+                    (expected, Some(found)) => expected == found,
+                    (_, None) => false,
+                };
+                Check {
+                    output: output.clone(),
+                    passed,
+                    expected: expected.clone(),
+                    actual: found,
+                    deviation,
+                }
+            })
+            .collect()
+    }
+}
+
+fn deviation(expected: &Expected, actual: &Expected) -> Option<f64> {
+    match (expected, actual) {
+        (Expected::Number(e), Expected::Number(a)) => Some((a - e).abs()),
+        (Expected::Array(e), Expected::Array(a)) if e.samples.len() == a.samples.len() => {
+            Some(e.numbers().zip(a.numbers()).map(|(e, a)| (a - e).abs()).fold(0.0, f64::max))
+        }
+        _ => None,
+    }
+}
+
+/// Expected value of every output, descending into objects and into
+/// arrays of objects by index
+fn collect(value: &Value, path: String, outputs: &mut BTreeMap<String, Expected>) {
+    let child_path = |key: &str| if path.is_empty() { key.to_string() } else { format!("{path}.{key}") };
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map {
+                collect(child, child_path(key), outputs);
+            }
+        }
+        Value::Array(items) => {
+            let mut numbers = Vec::new();
+            if flatten(value, &mut numbers) {
+                if !numbers.is_empty() {
+                    outputs.insert(path, Expected::Array(Summary::of(&numbers)));
+                }
+            } else {
+                for (index, item) in items.iter().enumerate() {
+                    collect(item, child_path(&index.to_string()), outputs);
+                }
+            }
+        }
+        Value::Number(number) => {
+            outputs.insert(path, Expected::Number(number.as_f64().unwrap_or(f64::NAN)));
+        }
+        other => {
+            outputs.insert(path, Expected::Exact(other.clone()));
+        }
+    }
+}
+
+/// Append the numbers of a possibly nested numeric array; false if it
+/// holds anything else
+fn flatten(value: &Value, numbers: &mut Vec<f64>) -> bool {
+    match value {
+        Value::Array(items) => items.iter().all(|item| flatten(item, numbers)),
+        Value::Number(number) => {
+            numbers.push(number.as_f64().unwrap_or(f64::NAN));
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Path of a simulation's golden file under the content directory
+pub fn path(content_dir: &Path, simulation_id: &str) -> PathBuf {
+    content_dir.join("golden").join(format!("{simulation_id}.json"))
+}
+
+/// The simulation's golden file, `None` if it has none
+pub fn load(content_dir: &Path, simulation_id: &str) -> Result<Option<Golden>, String> {
+    let path = path(content_dir, simulation_id);
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(format!("{}: {}", path.display(), err)),
+    };
+    serde_json::from_str(&text).map(Some).map_err(|err| format!("{}: {}", path.display(), err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulations::params::Params;
+    use crate::simulations::{kernel_version, Progress, SimulationRegistry};
+
+    /// Every golden file still matches a run of its simulation, checked as
+    /// the self-test endpoint checks it
+    #[test]
+    fn golden_files_match_their_simulations() {
+        let content_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("content");
+        let registry = SimulationRegistry::with_builtins();
+        let mut ids: Vec<String> = std::fs::read_dir(content_dir.join("golden"))
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|extension| extension == "json"))
+            .map(|path| path.file_stem().unwrap().to_string_lossy().into_owned())
+            .collect();
+        ids.sort();
+        assert!(!ids.is_empty());
+
+        let mut failures = Vec::new();
+        for id in &ids {
+            let simulation = registry.get_any(id).unwrap_or_else(|| panic!("{id}: no such simulation"));
+            let golden = load(&content_dir, id).unwrap().unwrap();
+            let version = if kernel_version(golden.simulation_version) == simulation.version() {
+                simulation.version()
+            } else {
+                golden.simulation_version
+            };
+            let params = Params::from_stored(golden.parameters.clone());
+            let data = simulation
+                .run_version(version, &params, &Progress::default())
+                .unwrap_or_else(|| panic!("{id}: version {version} can no longer be run"))
+                .unwrap_or_else(|err| panic!("{id}: {err}"));
+            let checks = golden.check(&data);
+            failures.extend(checks.iter().filter(|check| !check.passed).map(|check| format!("{id}: {}", check.output)));
+        }
+        assert!(failures.is_empty(), "outputs off their golden values:\n{}", failures.join("\n"));
+    }
+}
+
@@ -0,0 +1,159 @@
+// Localization of simulation text
+//
+// Clients ask for languages with `?lang=` or `Accept-Language`. Each
+// requested tag expands to a fallback chain (`pt-br`, then `pt`) ending with
+// English, the language simulations are written in. Every field is taken
+// from the first locale in the chain that translates it.
+
+use std::collections::HashMap;
+
+use crate::models::simulation::{SimulationDetails, SimulationInfo};
+use crate::models::translation::Translation;
+
+/// Language simulations are written in
+pub const DEFAULT_LOCALE: &str = "en";
+/// Most requested locales considered, to bound the chain
+const MAX_REQUESTED: usize = 8;
+
+/// Lowercase a BCP 47 language tag (`pt_BR` → `pt-br`), or `None` if it is
+/// not one
+pub fn normalize_locale(tag: &str) -> Option<String> {
+    let tag = tag.trim().replace('_', "-").to_ascii_lowercase();
+    let mut subtags = tag.split('-');
+    let language = subtags.next()?;
+    if !(2..=3).contains(&language.len()) || !language.bytes().all(|b| b.is_ascii_lowercase()) {
+        return None;
+    }
+    if !subtags.all(|s| (1..=8).contains(&s.len()) && s.bytes().all(|b| b.is_ascii_alphanumeric())) {
+        return None;
+    }
+    Some(tag)
+}
+
+/// Locales of an `Accept-Language` header, most preferred first. Wildcards,
+/// malformed tags and tags with `q=0` are skipped.
+pub fn parse_accept_language(header: &str) -> Vec<String> {
+    let mut weighted: Vec<(f32, usize, String)> = header
+        .split(',')
+        .enumerate()
+        .filter_map(|(i, item)| {
+            let mut parts = item.split(';');
+            let tag = normalize_locale(parts.next()?)?;
+            let q = match parts.find_map(|p| p.trim().strip_prefix("q=")) {
+                Some(q) => q.trim().parse::<f32>().ok()?,
+                None => 1.0,
+            };
+            (q > 0.0).then_some((q, i, tag))
+        })
+        .collect();
+    // Stable for equal weights, so header order breaks ties
+    weighted.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)));
+    weighted.into_iter().map(|(_, _, tag)| tag).collect()
+}
+
+/// Each requested locale followed by its shorter prefixes, without repeats,
+/// up to and including English
+pub fn fallback_chain(requested: &[String]) -> Vec<String> {
+    let mut chain: Vec<String> = Vec::new();
+    for tag in requested.iter().take(MAX_REQUESTED) {
+        let mut prefix = tag.as_str();
+        loop {
+            if !chain.iter().any(|t| t == prefix) {
+                chain.push(prefix.to_string());
+            }
+            // English is complete, so nothing after it is ever used
+            if prefix == DEFAULT_LOCALE {
+                return chain;
+            }
+            match prefix.rsplit_once('-') {
+                Some((shorter, _)) => prefix = shorter,
+                None => break,
+            }
+        }
+    }
+    chain.push(DEFAULT_LOCALE.to_string());
+    chain
+}
+
+/// Applies the translations of a fallback chain to simulation text
+pub struct Localizer {
+    chain: Vec<String>,
+    /// Translations per simulation, in chain order
+    translations: HashMap<String, Vec<Translation>>,
+}
+
+impl Localizer {
+    pub fn new(chain: Vec<String>, translations: Vec<Translation>) -> Self {
+        let mut by_simulation: HashMap<String, Vec<Translation>> = HashMap::new();
+        for translation in translations {
+            by_simulation
+                .entry(translation.simulation_id.clone())
+                .or_default()
+                .push(translation);
+        }
+        let rank = |locale: &str| chain.iter().position(|l| l == locale).unwrap_or(usize::MAX);
+        for translations in by_simulation.values_mut() {
+            translations.sort_by_key(|t| rank(&t.locale));
+        }
+        Self {
+            chain,
+            translations: by_simulation,
+        }
+    }
+
+    /// The most preferred locale that has any translation, for
+    /// `Content-Language` on listings
+    pub fn content_language(&self) -> &str {
+        self.chain
+            .iter()
+            .find(|locale| {
+                *locale == DEFAULT_LOCALE
+                    || self.translations.values().flatten().any(|t| &t.locale == *locale)
+            })
+            .map_or(DEFAULT_LOCALE, String::as_str)
+    }
+
+    /// First translation of a field along the chain, with its locale
+    fn pick<'a>(
+        &'a self,
+        simulation_id: &str,
+        field: impl Fn(&'a Translation) -> Option<&'a str>,
+    ) -> Option<(&'a str, &'a str)> {
+        self.translations
+            .get(simulation_id)?
+            .iter()
+            .find_map(|t| field(t).filter(|text| !text.is_empty()).map(|text| (text, t.locale.as_str())))
+    }
+
+    pub fn info(&self, info: &mut SimulationInfo) {
+        if let Some((name, _)) = self.pick(&info.id, |t| t.name.as_deref()) {
+            info.name = name.to_string();
+        }
+        if let Some((description, _)) = self.pick(&info.id, |t| t.description.as_deref()) {
+            info.description = description.to_string();
+        }
+    }
+
+    /// Translate details in place and return the locale of the theory text
+    pub fn details(&self, details: &mut SimulationDetails) -> &str {
+        let id = details.id.clone();
+        if let Some((name, _)) = self.pick(&id, |t| t.name.as_deref()) {
+            details.name = name.to_string();
+        }
+        if let Some((description, _)) = self.pick(&id, |t| t.description.as_deref()) {
+            details.description = description.to_string();
+        }
+        for parameter in &mut details.parameters {
+            if let Some((label, _)) = self.pick(&id, |t| t.parameter_labels.get(&parameter.name).map(String::as_str)) {
+                parameter.label = label.to_string();
+            }
+        }
+        match self.pick(&id, |t| t.theory.as_deref()) {
+            Some((theory, locale)) => {
+                details.theory = theory.to_string();
+                locale
+            }
+            None => DEFAULT_LOCALE,
+        }
+    }
+}
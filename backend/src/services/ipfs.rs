@@ -0,0 +1,143 @@
+// IPFS pinning of reproducibility bundles
+//
+// With `IPFS_API_URL` pointing at the RPC API of a Kubo node (or a pinning
+// service speaking the same API), result bundles are added and pinned there
+// and can be shared by their content identifier (CID). Anyone can fetch a
+// CID from any IPFS gateway; this server also serves it back after checking
+// it against the SHA-256 recorded when it was pinned.
+
+use std::fmt;
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::config::IpfsConfig;
+
+/// Longest wait for the IPFS node, which may have to find content on the
+/// network
+const NODE_TIMEOUT: Duration = Duration::from_secs(60);
+/// Separates the parts of the upload body
+const BOUNDARY: &str = "physics-tutorial-bundle";
+
+#[derive(Debug)]
+pub enum IpfsError {
+    /// `IPFS_API_URL` is not set
+    NotConfigured,
+    /// The node failed or did not answer
+    Node(String),
+}
+
+impl fmt::Display for IpfsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotConfigured => write!(f, "IPFS is not configured on this server"),
+            Self::Node(reason) => write!(f, "IPFS node request failed: {reason}"),
+        }
+    }
+}
+
+fn node_error(err: reqwest::Error) -> IpfsError {
+    IpfsError::Node(err.to_string())
+}
+
+/// Client of the IPFS node
+pub struct Ipfs {
+    api_url: Option<String>,
+    /// `Authorization` header for pinning services that require one
+    authorization: Option<String>,
+    /// Public gateway that shared links point to
+    pub gateway_url: String,
+    http: reqwest::Client,
+}
+
+impl Ipfs {
+    /// Node at the configured `api_url` (e.g. `http://127.0.0.1:5001`), if
+    /// any, sent `api_authorization` as its `Authorization` header
+    pub fn from_config(config: &IpfsConfig) -> Self {
+        let trim = |url: &String| url.trim_end_matches('/').to_string();
+        Self {
+            api_url: config.api_url.as_ref().map(trim),
+            authorization: config.api_authorization.clone(),
+            gateway_url: trim(&config.gateway_url),
+            http: reqwest::Client::builder().timeout(NODE_TIMEOUT).build().unwrap_or_default(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.api_url.is_some()
+    }
+
+    /// Public link to a CID
+    pub fn gateway_link(&self, cid: &str) -> String {
+        format!("{}/ipfs/{}", self.gateway_url, cid)
+    }
+
+    /// Add a file to the node, pin it and return its CID (version 1)
+    pub async fn add(&self, filename: &str, contents: Vec<u8>) -> Result<String, IpfsError> {
+        let mut body = format!(
+            "--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{filename}\"\r\n\
+             Content-Type: application/octet-stream\r\n\r\n"
+        )
+        .into_bytes();
+        body.extend_from_slice(&contents);
+        body.extend_from_slice(format!("\r\n--{BOUNDARY}--\r\n").as_bytes());
+
+        let added: Added = self
+            .request("add?pin=true&cid-version=1")?
+            .header(reqwest::header::CONTENT_TYPE, format!("multipart/form-data; boundary={BOUNDARY}"))
+            .body(body)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(node_error)?
+            .json()
+            .await
+            .map_err(node_error)?;
+        Ok(added.hash)
+    }
+
+    /// Contents of a file by CID
+    pub async fn cat(&self, cid: &str) -> Result<Vec<u8>, IpfsError> {
+        let bytes = self
+            .request(&format!("cat?arg={cid}"))?
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(node_error)?
+            .bytes()
+            .await
+            .map_err(node_error)?;
+        Ok(bytes.to_vec())
+    }
+
+    /// RPC call; the Kubo API takes every command as a POST
+    fn request(&self, command: &str) -> Result<reqwest::RequestBuilder, IpfsError> {
+        let api_url = self.api_url.as_ref().ok_or(IpfsError::NotConfigured)?;
+        let request = self.http.post(format!("{api_url}/api/v0/{command}"));
+        Ok(match &self.authorization {
+            Some(authorization) => request.header(reqwest::header::AUTHORIZATION, authorization),
+            None => request,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct Added {
+    #[serde(rename = "Hash")]
+    hash: String,
+}
+
+/// Whether `cid` looks like a CID: version 0 (base58, `Qm…`) or version 1
+/// in the default base32 (`b…`)
+pub fn is_cid(cid: &str) -> bool {
+    const BASE58: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+    if let Some(rest) = cid.strip_prefix("Qm") {
+        return cid.len() == 46 && rest.chars().all(|c| BASE58.contains(c));
+    }
+    match cid.strip_prefix('b') {
+        Some(rest) => {
+            (10..=100).contains(&rest.len()) && rest.chars().all(|c| c.is_ascii_lowercase() || ('2'..='7').contains(&c))
+        }
+        None => false,
+    }
+}
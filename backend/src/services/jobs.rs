@@ -0,0 +1,389 @@
+// Background simulation jobs
+//
+// At shutdown the queue stops taking submissions and gives running jobs a
+// deadline to finish. Jobs that never started, or were still running at the
+// deadline, are handed back to be saved and run again after the restart
+// under the same ID.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+use sqlx::SqlitePool;
+use tokio::sync::Semaphore;
+use tracing::Instrument;
+use uuid::Uuid;
+
+use crate::models::job::PendingJob;
+use crate::models::simulation::SimulationResult;
+use crate::services::attestation::{Attestation, Attester};
+use crate::services::budget::{check_output_size, JOB_TIME_LIMIT};
+use crate::services::trace;
+use crate::simulations::params::{recorded_seed, Params};
+use crate::simulations::{Progress, Simulation, SimulationError};
+
+/// Jobs computed at the same time; the rest wait in the queue
+const CONCURRENT_JOBS: usize = 2;
+/// Jobs accepted but not yet finished before new submissions are refused
+const MAX_PENDING_JOBS: usize = 32;
+/// How long a finished job's status stays available
+const RETENTION: Duration = Duration::from_secs(60 * 60);
+/// How often draining looks for running jobs that have finished
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Done,
+    Failed,
+    Cancelled,
+}
+
+impl JobStatus {
+    pub fn name(self) -> &'static str {
+        match self {
+            JobStatus::Queued => "queued",
+            JobStatus::Running => "running",
+            JobStatus::Done => "done",
+            JobStatus::Failed => "failed",
+            JobStatus::Cancelled => "cancelled",
+        }
+    }
+
+    /// Whether the job has stopped for good
+    pub fn is_final(self) -> bool {
+        matches!(self, JobStatus::Done | JobStatus::Failed | JobStatus::Cancelled)
+    }
+}
+
+/// Status report of one job
+#[derive(Clone, Serialize)]
+pub struct JobView {
+    pub id: String,
+    pub simulation_id: String,
+    pub status: JobStatus,
+    /// Completed fraction, 0 to 1
+    pub progress: f64,
+    /// Stored result to fetch once the job is done
+    pub result_id: Option<String>,
+    /// Parameter errors or a failure message when the job failed
+    pub error: Option<Value>,
+    pub created_at: String,
+    pub started_at: Option<String>,
+    pub finished_at: Option<String>,
+}
+
+impl JobView {
+    fn queued(id: String, simulation_id: &str, created_at: String) -> Self {
+        Self {
+            id,
+            simulation_id: simulation_id.to_string(),
+            status: JobStatus::Queued,
+            progress: 0.0,
+            result_id: None,
+            error: None,
+            created_at,
+            started_at: None,
+            finished_at: None,
+        }
+    }
+}
+
+struct Job {
+    view: JobView,
+    /// Validated values, kept to save the job if it has not run by shutdown
+    parameters: Map<String, Value>,
+    progress: Progress,
+    finished: Option<Instant>,
+}
+
+/// Reasons a job is refused
+pub enum SubmitError {
+    /// Too many jobs are already waiting
+    QueueFull,
+    /// The server is shutting down
+    ShuttingDown,
+}
+
+/// Reasons a job cannot be cancelled
+pub enum CancelError {
+    NotFound,
+    /// The job already finished, failed or was cancelled
+    Finished,
+}
+
+/// In-memory registry of submitted jobs. Results of finished jobs are stored
+/// with the other simulation results, so they outlive the job itself.
+pub struct JobQueue {
+    jobs: Mutex<HashMap<String, Job>>,
+    slots: Arc<Semaphore>,
+    /// Cleared when shutdown begins; queued jobs then stay queued
+    accepting: AtomicBool,
+}
+
+impl Default for JobQueue {
+    fn default() -> Self {
+        Self {
+            jobs: Mutex::new(HashMap::new()),
+            slots: Arc::new(Semaphore::new(CONCURRENT_JOBS)),
+            accepting: AtomicBool::new(true),
+        }
+    }
+}
+
+impl JobQueue {
+    /// Queue a run of `simulation` with validated parameters and return its
+    /// initial status immediately
+    pub fn submit(
+        self: &Arc<Self>,
+        simulation: Arc<dyn Simulation>,
+        params: Params,
+        db: SqlitePool,
+        attester: Arc<Attester>,
+    ) -> Result<JobView, SubmitError> {
+        if !self.is_accepting() {
+            return Err(SubmitError::ShuttingDown);
+        }
+        let view = JobView::queued(Uuid::new_v4().to_string(), simulation.id(), chrono::Utc::now().to_rfc3339());
+        let progress = self.admit(&view, &params, MAX_PENDING_JOBS)?;
+        self.start(view.clone(), progress, simulation, params, db, attester);
+        Ok(view)
+    }
+
+    /// Queue a job saved at the last shutdown again, under its old ID.
+    /// Saved jobs were accepted once, so the queue limit does not apply.
+    pub fn resume(
+        self: &Arc<Self>,
+        pending: PendingJob,
+        simulation: Arc<dyn Simulation>,
+        db: SqlitePool,
+        attester: Arc<Attester>,
+    ) -> Result<JobView, SubmitError> {
+        let view = JobView::queued(pending.id, simulation.id(), pending.created_at);
+        let params = Params::from_stored(pending.parameters);
+        let progress = self.admit(&view, &params, usize::MAX)?;
+        self.start(view.clone(), progress, simulation, params, db, attester);
+        Ok(view)
+    }
+
+    /// Record a new job, unless `limit` jobs are already unfinished
+    fn admit(&self, view: &JobView, params: &Params, limit: usize) -> Result<Progress, SubmitError> {
+        let progress = Progress::default();
+        let mut jobs = self.jobs.lock().unwrap();
+        jobs.retain(|_, job| job.finished.is_none_or(|at| at.elapsed() < RETENTION));
+        if jobs.values().filter(|job| job.finished.is_none()).count() >= limit {
+            return Err(SubmitError::QueueFull);
+        }
+        jobs.insert(
+            view.id.clone(),
+            Job {
+                view: view.clone(),
+                parameters: params.values().clone(),
+                progress: progress.clone(),
+                finished: None,
+            },
+        );
+        Ok(progress)
+    }
+
+    /// Run an admitted job once a worker slot is free
+    fn start(
+        self: &Arc<Self>,
+        view: JobView,
+        progress: Progress,
+        simulation: Arc<dyn Simulation>,
+        params: Params,
+        db: SqlitePool,
+        attester: Arc<Attester>,
+    ) {
+        let queue = self.clone();
+        let id = view.id.clone();
+        // Child of the submitting request's span, so the job's log lines
+        // carry its request ID
+        let span = tracing::info_span!("job", job_id = %id, simulation = %view.simulation_id);
+        tokio::spawn(async move {
+            let Ok(_permit) = queue.slots.clone().acquire_owned().await else {
+                return;
+            };
+            // Cancelled while waiting for a slot, or left for after the
+            // restart
+            if progress.is_cancelled() || !queue.is_accepting() {
+                return;
+            }
+            queue.update(&id, |job| {
+                job.view.status = JobStatus::Running;
+                job.view.started_at = Some(chrono::Utc::now().to_rfc3339());
+            });
+
+            let parameters = params.values().clone();
+            let signed = parameters.clone();
+            let worker = simulation.clone();
+            let budget = progress.with_time_limit(JOB_TIME_LIMIT);
+            let outcome = trace::spawn_blocking(move || {
+                let data = worker.run_with_progress(&params, &budget)?;
+                // Simulations that never check still finish; drop their output
+                budget.check()?;
+                check_output_size([&data])?;
+                let attestation = attester.attest_run(worker.id(), worker.version(), &signed, &data);
+                Ok((data, attestation))
+            })
+            .await;
+            let outcome = match outcome {
+                Ok(Ok((data, attestation))) => {
+                    store(&db, simulation.as_ref(), parameters, data, attestation).await.map_err(Some)
+                }
+                Ok(Err(SimulationError::Cancelled)) => Err(None),
+                Ok(Err(SimulationError::InvalidParameters(errors))) => Err(Some(serde_json::json!(errors))),
+                Ok(Err(err)) => Err(Some(Value::from(err.to_string()))),
+                Err(e) => {
+                    tracing::error!("Job {} failed: {}", id, e);
+                    Err(Some(Value::from("simulation failed")))
+                }
+            };
+
+            queue.update(&id, |job| {
+                match outcome {
+                    Ok(result_id) => {
+                        job.view.status = JobStatus::Done;
+                        job.view.result_id = Some(result_id);
+                        job.progress.set(1.0);
+                    }
+                    Err(Some(error)) => {
+                        job.view.status = JobStatus::Failed;
+                        job.view.error = Some(error);
+                    }
+                    Err(None) => job.view.status = JobStatus::Cancelled,
+                }
+                job.view.finished_at = Some(chrono::Utc::now().to_rfc3339());
+                job.finished = Some(Instant::now());
+            });
+        }
+        .instrument(span));
+    }
+
+    /// Current status of a job, if it exists and has not expired
+    pub fn status(&self, id: &str) -> Option<JobView> {
+        let jobs = self.jobs.lock().unwrap();
+        let job = jobs.get(id)?;
+        let mut view = job.view.clone();
+        view.progress = job.progress.get();
+        Some(view)
+    }
+
+    /// Whether new jobs are taken; false once shutdown has begun
+    pub fn is_accepting(&self) -> bool {
+        self.accepting.load(Ordering::SeqCst)
+    }
+
+    /// Stop taking jobs and wait up to `deadline` for the running ones to
+    /// finish. Returns the jobs that did not, cancelling those still
+    /// running, so they can be saved and run after the restart.
+    pub async fn drain(&self, deadline: Duration) -> Vec<PendingJob> {
+        self.accepting.store(false, Ordering::SeqCst);
+        let started = Instant::now();
+        loop {
+            let (_, running) = self.depth();
+            if running == 0 {
+                break;
+            }
+            if started.elapsed() >= deadline {
+                tracing::warn!("{} running jobs did not finish before shutdown; they will run again", running);
+                break;
+            }
+            tokio::time::sleep(DRAIN_POLL_INTERVAL).await;
+        }
+
+        let jobs = self.jobs.lock().unwrap();
+        let mut pending: Vec<PendingJob> = jobs
+            .values()
+            .filter(|job| job.finished.is_none() && !job.progress.is_cancelled())
+            .map(|job| {
+                job.progress.cancel();
+                PendingJob {
+                    id: job.view.id.clone(),
+                    simulation_id: job.view.simulation_id.clone(),
+                    parameters: job.parameters.clone(),
+                    created_at: job.view.created_at.clone(),
+                }
+            })
+            .collect();
+        pending.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+        pending
+    }
+
+    /// Jobs accepted but not yet finished before new submissions are refused
+    pub fn capacity(&self) -> usize {
+        MAX_PENDING_JOBS
+    }
+
+    /// Jobs waiting for a worker slot and jobs running
+    pub fn depth(&self) -> (usize, usize) {
+        let jobs = self.jobs.lock().unwrap();
+        let count = |status| jobs.values().filter(|job| job.view.status == status).count();
+        (count(JobStatus::Queued), count(JobStatus::Running))
+    }
+
+    /// Latest partial result published by a running job, with its sequence
+    /// number
+    pub fn partial(&self, id: &str) -> Option<(u64, Value)> {
+        self.jobs.lock().unwrap().get(id)?.progress.partial()
+    }
+
+    /// Stop a job. A queued job is cancelled straight away; a running one
+    /// stops at the simulation's next check and is reported as running
+    /// until then.
+    pub fn cancel(&self, id: &str) -> Result<JobView, CancelError> {
+        let mut jobs = self.jobs.lock().unwrap();
+        let job = jobs.get_mut(id).ok_or(CancelError::NotFound)?;
+        if job.finished.is_some() {
+            return Err(CancelError::Finished);
+        }
+        job.progress.cancel();
+        if job.view.status == JobStatus::Queued {
+            job.view.status = JobStatus::Cancelled;
+            job.view.finished_at = Some(chrono::Utc::now().to_rfc3339());
+            job.finished = Some(Instant::now());
+        }
+        let mut view = job.view.clone();
+        view.progress = job.progress.get();
+        Ok(view)
+    }
+
+    fn update(&self, id: &str, change: impl FnOnce(&mut Job)) {
+        if let Some(job) = self.jobs.lock().unwrap().get_mut(id) {
+            change(job);
+        }
+    }
+}
+
+/// Save a finished run as a simulation result and return its ID
+async fn store(
+    db: &SqlitePool,
+    simulation: &dyn Simulation,
+    parameters: serde_json::Map<String, Value>,
+    data: Value,
+    attestation: Attestation,
+) -> Result<String, Value> {
+    let parameters = Value::Object(parameters);
+    let result = SimulationResult {
+        id: Uuid::new_v4().to_string(),
+        simulation_id: simulation.id().to_string(),
+        simulation_version: simulation.version(),
+        seed: recorded_seed(&parameters),
+        parameters,
+        data,
+        computed_at: chrono::Utc::now().to_rfc3339(),
+        attestation: Some(attestation),
+        compute_backend: simulation.backend(),
+    };
+    result.insert(db).await.map_err(|e| {
+        tracing::error!("Failed to store job result: {}", e);
+        Value::from("result could not be stored")
+    })?;
+    Ok(result.id)
+}
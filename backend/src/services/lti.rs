@@ -0,0 +1,859 @@
+// LTI 1.3 tool provider
+//
+// Learning management systems such as Canvas and Moodle embed the tutorial
+// with LTI 1.3: the platform starts an OpenID Connect login, then posts an
+// id_token it signed with its own key. Trusted platforms are registered in
+// `LTI_PLATFORMS`. Our messages to them (deep linking responses and the
+// client assertions used for Assignment and Grade Services) are signed with
+// the tool key from `LTI_PRIVATE_KEY`, published as a JWKS.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::Utc;
+use rsa::pkcs8::DecodePrivateKey;
+use rsa::traits::PublicKeyParts;
+use rsa::{BigUint, Pkcs1v15Sign, RsaPrivateKey, RsaPublicKey};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::config::{LtiConfig, ServerConfig};
+use crate::models::lti::GradeLink;
+use crate::services::auth::generate_refresh_token;
+
+pub const LTI_VERSION: &str = "1.3.0";
+const CLAIM_PREFIX: &str = "https://purl.imsglobal.org/spec/lti/claim/";
+const DEEP_LINKING_PREFIX: &str = "https://purl.imsglobal.org/spec/lti-dl/claim/";
+const AGS_ENDPOINT_CLAIM: &str = "https://purl.imsglobal.org/spec/lti-ags/claim/endpoint";
+const SCORE_SCOPE: &str = "https://purl.imsglobal.org/spec/lti-ags/scope/score";
+const SCORE_CONTENT_TYPE: &str = "application/vnd.ims.lis.v1.score+json";
+
+/// How long a platform may take to answer our login redirect
+const LOGIN_TTL: Duration = Duration::from_secs(10 * 60);
+/// Prefix of the cookie a login leaves in the browser it started in,
+/// followed by its state
+const STATE_COOKIE_PREFIX: &str = "lti_state_";
+/// Logins awaiting their launch kept at once; anyone can start one, so the
+/// oldest give way beyond this
+const MAX_PENDING_LOGINS: usize = 10_000;
+/// How long an instructor may take to pick a simulation to link
+const DEEP_LINK_TTL: Duration = Duration::from_secs(30 * 60);
+/// How long fetched platform keys are trusted before fetching again
+const JWKS_TTL: Duration = Duration::from_secs(60 * 60);
+/// Timeout for requests to platforms
+const PLATFORM_TIMEOUT: Duration = Duration::from_secs(10);
+/// Lifetime of the messages we sign
+const MESSAGE_TTL_SECS: i64 = 5 * 60;
+/// Clock difference tolerated between us and a platform
+const CLOCK_SKEW_SECS: i64 = 60;
+/// Size of a generated tool key
+const KEY_BITS: usize = 2048;
+
+/// A platform we accept launches from, as registered with it
+#[derive(Deserialize, Clone)]
+pub struct Platform {
+    pub issuer: String,
+    /// Our client ID on the platform
+    pub client_id: String,
+    /// Accepted deployments; empty accepts any
+    #[serde(default)]
+    pub deployment_ids: Vec<String>,
+    /// OIDC authorization endpoint
+    pub auth_login_url: String,
+    /// OAuth 2 token endpoint, for grade passback
+    pub auth_token_url: String,
+    /// The platform's public keys
+    pub jwks_url: String,
+}
+
+/// Third-party login initiation, sent by the platform as a query or form
+#[derive(Deserialize)]
+pub struct LoginInitiation {
+    pub iss: String,
+    pub login_hint: String,
+    pub lti_message_hint: Option<String>,
+    pub client_id: Option<String>,
+    pub lti_deployment_id: Option<String>,
+}
+
+/// A login we redirected to the platform and expect a launch for
+pub struct PendingLogin {
+    pub issuer: String,
+    pub client_id: String,
+    nonce: String,
+    created: Instant,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum MessageType {
+    ResourceLink,
+    DeepLinking,
+}
+
+/// The claims of a verified launch that the tutorial uses
+pub struct Launch {
+    pub message_type: MessageType,
+    pub issuer: String,
+    pub client_id: String,
+    pub deployment_id: String,
+    /// The user's ID on the platform
+    pub subject: String,
+    pub email: Option<String>,
+    pub name: Option<String>,
+    pub roles: Vec<String>,
+    /// Platform course ID and title
+    pub context: Option<(String, String)>,
+    pub target_link_uri: String,
+    pub custom: Map<String, Value>,
+    /// AGS line item scores may be posted to
+    pub lineitem: Option<String>,
+    /// Where to send the deep linking response
+    pub deep_link_return_url: Option<String>,
+    /// Opaque value to hand back with the deep linking response
+    pub deep_link_data: Option<String>,
+}
+
+/// An instructor choosing content to place in their course
+pub struct DeepLinkSession {
+    pub issuer: String,
+    pub client_id: String,
+    pub deployment_id: String,
+    pub return_url: String,
+    pub data: Option<String>,
+    created: Instant,
+}
+
+#[derive(Debug)]
+pub enum LtiError {
+    /// No platforms are registered
+    NotConfigured,
+    UnknownPlatform(String),
+    /// The launch or its login state failed validation
+    InvalidLaunch(String),
+    /// A request to the platform failed
+    Platform(String),
+}
+
+impl fmt::Display for LtiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotConfigured => write!(f, "LTI is not configured on this server"),
+            Self::UnknownPlatform(issuer) => write!(f, "platform {issuer} is not registered"),
+            Self::InvalidLaunch(reason) => write!(f, "invalid launch: {reason}"),
+            Self::Platform(reason) => write!(f, "platform request failed: {reason}"),
+        }
+    }
+}
+
+/// Check that a launch's `Cookie` headers carry the cookie its login left
+pub fn check_state_cookie<'a>(cookies: impl IntoIterator<Item = &'a str>, state: &str) -> Result<(), LtiError> {
+    let name = format!("{STATE_COOKIE_PREFIX}{state}");
+    cookies
+        .into_iter()
+        .flat_map(|header| header.split(';'))
+        .any(|cookie| cookie.split_once('=').is_some_and(|(key, _)| key.trim() == name))
+        .then_some(())
+        .ok_or_else(|| invalid("the launch did not come from the browser the login started in"))
+}
+
+fn invalid(reason: impl Into<String>) -> LtiError {
+    LtiError::InvalidLaunch(reason.into())
+}
+
+fn platform_error(err: reqwest::Error) -> LtiError {
+    LtiError::Platform(err.to_string())
+}
+
+impl Launch {
+    /// Whether the user teaches the platform course
+    pub fn is_instructor(&self) -> bool {
+        self.roles.iter().any(|role| {
+            role.ends_with("membership#Instructor") || role.ends_with("#Administrator") || role == "Instructor"
+        })
+    }
+
+    /// The simulation the link points at: the `simulation_id` custom
+    /// parameter, or the last segment of the target link
+    pub fn simulation_id(&self) -> Option<&str> {
+        self.custom
+            .get("simulation_id")
+            .and_then(Value::as_str)
+            .or_else(|| self.target_link_uri.split(['?', '#']).next()?.rsplit('/').find(|s| !s.is_empty()))
+    }
+
+    fn from_claims(claims: &Map<String, Value>, login: &PendingLogin) -> Result<Self, LtiError> {
+        let text = |name: &str| claims.get(name).and_then(Value::as_str).map(str::to_string);
+        let lti = |name: &str| claims.get(&format!("{CLAIM_PREFIX}{name}"));
+
+        let message_type = match lti("message_type").and_then(Value::as_str) {
+            Some("LtiResourceLinkRequest") => MessageType::ResourceLink,
+            Some("LtiDeepLinkingRequest") => MessageType::DeepLinking,
+            other => return Err(invalid(format!("unsupported message type {other:?}"))),
+        };
+        if lti("version").and_then(Value::as_str) != Some(LTI_VERSION) {
+            return Err(invalid("LTI version must be 1.3.0"));
+        }
+        let deployment_id = lti("deployment_id")
+            .and_then(Value::as_str)
+            .ok_or_else(|| invalid("deployment_id is missing"))?;
+        let subject = text("sub").filter(|sub| !sub.is_empty()).ok_or_else(|| invalid("sub is missing"))?;
+        let context = lti("context").and_then(|context| {
+            let id = context.get("id")?.as_str()?.to_string();
+            let title = ["title", "label"]
+                .iter()
+                .find_map(|key| context.get(*key)?.as_str())
+                .unwrap_or("LMS course")
+                .to_string();
+            Some((id, title))
+        });
+        let ags = claims.get(AGS_ENDPOINT_CLAIM);
+        let can_score = ags
+            .and_then(|ags| ags.get("scope")?.as_array())
+            .is_some_and(|scopes| scopes.iter().any(|s| s.as_str() == Some(SCORE_SCOPE)));
+        let settings = claims.get(&format!("{DEEP_LINKING_PREFIX}deep_linking_settings"));
+
+        let name = text("name").or_else(|| {
+            let parts: Vec<String> = ["given_name", "family_name"].iter().filter_map(|key| text(key)).collect();
+            (!parts.is_empty()).then(|| parts.join(" "))
+        });
+
+        Ok(Self {
+            message_type,
+            issuer: login.issuer.clone(),
+            client_id: login.client_id.clone(),
+            deployment_id: deployment_id.to_string(),
+            subject,
+            email: text("email").filter(|email| !email.is_empty()),
+            name,
+            roles: lti("roles")
+                .and_then(Value::as_array)
+                .map(|roles| roles.iter().filter_map(|r| r.as_str().map(str::to_string)).collect())
+                .unwrap_or_default(),
+            context,
+            target_link_uri: lti("target_link_uri").and_then(Value::as_str).unwrap_or_default().to_string(),
+            custom: lti("custom").and_then(Value::as_object).cloned().unwrap_or_default(),
+            lineitem: ags
+                .filter(|_| can_score)
+                .and_then(|ags| ags.get("lineitem")?.as_str())
+                .map(str::to_string),
+            deep_link_return_url: settings
+                .and_then(|settings| settings.get("deep_link_return_url")?.as_str())
+                .map(str::to_string),
+            deep_link_data: settings.and_then(|settings| settings.get("data")?.as_str()).map(str::to_string),
+        })
+    }
+}
+
+/// The RSA key we sign messages with
+struct ToolKey {
+    private: RsaPrivateKey,
+    /// Key ID: the start of the SHA-256 of the modulus
+    kid: String,
+}
+
+impl ToolKey {
+    /// The configured key, or a generated one when none is configured. A
+    /// generated key changes whenever the server restarts, so platforms must
+    /// fetch our JWKS again. A configured key that does not parse is fatal;
+    /// `Config::load` reports it before the server gets here.
+    fn from_config(config: &LtiConfig) -> Self {
+        let private = match config.private_key.as_deref() {
+            Some(pem) => decode_private_key(pem)
+                .unwrap_or_else(|err| panic!("lti.private_key is not a PKCS#8 PEM RSA key ({err})")),
+            None => {
+                tracing::warn!("No LTI private key is configured; using a random key for this process");
+                RsaPrivateKey::new(&mut rand::thread_rng(), KEY_BITS).expect("RSA key generation failed")
+            }
+        };
+        let digest = Sha256::digest(private.n().to_bytes_be());
+        let kid = digest.iter().take(8).map(|b| format!("{b:02x}")).collect();
+        Self { private, kid }
+    }
+
+    fn sign(&self, message: &[u8]) -> Vec<u8> {
+        self.private
+            .sign(Pkcs1v15Sign::new::<Sha256>(), &Sha256::digest(message))
+            .expect("RS256 signing with a valid key cannot fail")
+    }
+}
+
+/// RSA key from PKCS#8 PEM
+pub fn decode_private_key(pem: &str) -> Result<RsaPrivateKey, String> {
+    RsaPrivateKey::from_pkcs8_pem(pem.trim()).map_err(|err| err.to_string())
+}
+
+/// A platform's public keys, as last fetched
+type KeySet = Arc<Vec<PlatformKey>>;
+
+/// A platform's public key
+struct PlatformKey {
+    kid: Option<String>,
+    key: RsaPublicKey,
+}
+
+#[derive(Deserialize)]
+struct Jwks {
+    keys: Vec<Jwk>,
+}
+
+#[derive(Deserialize)]
+struct Jwk {
+    kty: String,
+    kid: Option<String>,
+    n: Option<String>,
+    e: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: Option<u64>,
+}
+
+/// LTI state shared by the handlers
+pub struct Lti {
+    platforms: Vec<Platform>,
+    key: Option<ToolKey>,
+    /// Public address of this API, where platforms send launches
+    pub api_url: String,
+    /// Address of the web app launches continue to
+    pub frontend_url: String,
+    logins: Mutex<HashMap<String, PendingLogin>>,
+    deep_links: Mutex<HashMap<String, DeepLinkSession>>,
+    /// Public keys per platform issuer, with when they were fetched
+    platform_keys: Mutex<HashMap<String, (Instant, KeySet)>>,
+    /// AGS access tokens per platform issuer and client ID, with when they
+    /// expire
+    access_tokens: Mutex<HashMap<(String, String), (Instant, String)>>,
+    http: reqwest::Client,
+}
+
+impl Lti {
+    /// The configured platforms, with the public addresses of the server
+    pub fn from_config(config: &LtiConfig, server: &ServerConfig) -> Self {
+        let key = (!config.platforms.is_empty()).then(|| ToolKey::from_config(config));
+        let url = |url: &String| url.trim_end_matches('/').to_string();
+        Self {
+            platforms: config.platforms.clone(),
+            key,
+            api_url: url(&server.public_url),
+            frontend_url: url(&server.frontend_url),
+            logins: Mutex::new(HashMap::new()),
+            deep_links: Mutex::new(HashMap::new()),
+            platform_keys: Mutex::new(HashMap::new()),
+            access_tokens: Mutex::new(HashMap::new()),
+            http: reqwest::Client::builder().timeout(PLATFORM_TIMEOUT).build().unwrap_or_default(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.key.is_some()
+    }
+
+    /// Where platforms post launches
+    pub fn launch_url(&self) -> String {
+        format!("{}/api/v1/lti/launch", self.api_url)
+    }
+
+    fn platform(&self, issuer: &str, client_id: Option<&str>) -> Result<&Platform, LtiError> {
+        if !self.is_enabled() {
+            return Err(LtiError::NotConfigured);
+        }
+        self.platforms
+            .iter()
+            .find(|p| p.issuer == issuer && client_id.is_none_or(|id| p.client_id == id))
+            .ok_or_else(|| LtiError::UnknownPlatform(issuer.to_string()))
+    }
+
+    /// Our public key as a JWKS, for platforms to verify our messages
+    pub fn jwks(&self) -> Value {
+        let keys: Vec<Value> = self
+            .key
+            .iter()
+            .map(|key| {
+                let public = key.private.to_public_key();
+                json!({
+                    "kty": "RSA",
+                    "alg": "RS256",
+                    "use": "sig",
+                    "kid": key.kid,
+                    "n": URL_SAFE_NO_PAD.encode(public.n().to_bytes_be()),
+                    "e": URL_SAFE_NO_PAD.encode(public.e().to_bytes_be()),
+                })
+            })
+            .collect();
+        json!({ "keys": keys })
+    }
+
+    /// Answer a login initiation with the platform's authorization URL and
+    /// the login's state, remembering the state and nonce the launch must
+    /// carry
+    pub fn login_redirect(&self, request: &LoginInitiation) -> Result<(String, String), LtiError> {
+        let platform = self.platform(&request.iss, request.client_id.as_deref())?;
+        if let Some(deployment_id) = &request.lti_deployment_id {
+            check_deployment(platform, deployment_id)?;
+        }
+        let state = generate_refresh_token();
+        let nonce = generate_refresh_token();
+        let launch_url = self.launch_url();
+        let mut params = vec![
+            ("scope", "openid"),
+            ("response_type", "id_token"),
+            ("response_mode", "form_post"),
+            ("prompt", "none"),
+            ("client_id", platform.client_id.as_str()),
+            ("redirect_uri", launch_url.as_str()),
+            ("login_hint", request.login_hint.as_str()),
+            ("state", state.as_str()),
+            ("nonce", nonce.as_str()),
+        ];
+        if let Some(hint) = &request.lti_message_hint {
+            params.push(("lti_message_hint", hint));
+        }
+        let url = reqwest::Url::parse_with_params(&platform.auth_login_url, &params)
+            .map_err(|e| LtiError::Platform(format!("bad auth_login_url: {e}")))?;
+
+        let mut logins = self.logins.lock().unwrap();
+        logins.retain(|_, login| login.created.elapsed() < LOGIN_TTL);
+        while logins.len() >= MAX_PENDING_LOGINS {
+            let Some(oldest) = logins.iter().min_by_key(|(_, login)| login.created).map(|(state, _)| state.clone())
+            else {
+                break;
+            };
+            logins.remove(&oldest);
+        }
+        logins.insert(
+            state.clone(),
+            PendingLogin {
+                issuer: platform.issuer.clone(),
+                client_id: platform.client_id.clone(),
+                nonce,
+                created: Instant::now(),
+            },
+        );
+        Ok((url.into(), state))
+    }
+
+    /// `Set-Cookie` value binding a login to the browser it started in. A
+    /// launch must come with the cookie of its state: anyone can start a
+    /// login and post its launch from another browser, signing that
+    /// browser in to their account. The platform posts the launch from its
+    /// own site, so the cookie has to be `SameSite=None`.
+    pub fn state_cookie(&self, state: &str) -> String {
+        self.cookie(state, LOGIN_TTL.as_secs())
+    }
+
+    /// `Set-Cookie` value removing a login's state cookie once its launch
+    /// has arrived
+    pub fn expired_state_cookie(&self, state: &str) -> String {
+        self.cookie(state, 0)
+    }
+
+    fn cookie(&self, state: &str, max_age: u64) -> String {
+        let launch_url = self.launch_url();
+        let path = reqwest::Url::parse(&launch_url).map_or_else(|_| "/".to_string(), |url| url.path().to_string());
+        format!("{STATE_COOKIE_PREFIX}{state}=1; Path={path}; Max-Age={max_age}; HttpOnly; Secure; SameSite=None")
+    }
+
+    /// Claim the login a launch answers; each state is good for one launch
+    pub fn take_login(&self, state: &str) -> Result<PendingLogin, LtiError> {
+        self.logins
+            .lock()
+            .unwrap()
+            .remove(state)
+            .filter(|login| login.created.elapsed() < LOGIN_TTL)
+            .ok_or_else(|| invalid("unknown or expired state"))
+    }
+
+    /// Verify a launch's id_token: signed by the platform, addressed to us,
+    /// current, and carrying the nonce of its login
+    pub async fn verify_launch(&self, id_token: &str, login: &PendingLogin) -> Result<Launch, LtiError> {
+        let platform = self.platform(&login.issuer, Some(&login.client_id))?;
+        let (signing_input, signature) = id_token.rsplit_once('.').ok_or_else(|| invalid("id_token is not a JWT"))?;
+        let (header, payload) = signing_input.split_once('.').ok_or_else(|| invalid("id_token is not a JWT"))?;
+        let header: Map<String, Value> = decode_segment(header)?;
+        if header.get("alg").and_then(Value::as_str) != Some("RS256") {
+            return Err(invalid("id_token must be signed with RS256"));
+        }
+        let kid = header.get("kid").and_then(Value::as_str);
+        let signature = URL_SAFE_NO_PAD.decode(signature).map_err(|_| invalid("id_token signature is not base64url"))?;
+        let digest = Sha256::digest(signing_input.as_bytes());
+        let key = self.platform_key(platform, kid).await?;
+        if key.verify(Pkcs1v15Sign::new::<Sha256>(), &digest, &signature).is_err() {
+            return Err(invalid("id_token signature does not match the platform's key"));
+        }
+
+        let claims: Map<String, Value> = decode_segment(payload)?;
+        if claims.get("iss").and_then(Value::as_str) != Some(&platform.issuer) {
+            return Err(invalid("iss does not match the login"));
+        }
+        let audiences: Vec<&str> = match claims.get("aud") {
+            Some(Value::String(aud)) => vec![aud],
+            Some(Value::Array(auds)) => auds.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !audiences.contains(&platform.client_id.as_str()) {
+            return Err(invalid("id_token is not addressed to this tool"));
+        }
+        if audiences.len() > 1 && claims.get("azp").and_then(Value::as_str) != Some(&platform.client_id) {
+            return Err(invalid("azp must name this tool"));
+        }
+        let now = Utc::now().timestamp();
+        let time = |name: &str| claims.get(name).and_then(Value::as_i64);
+        if time("exp").is_none_or(|exp| exp + CLOCK_SKEW_SECS <= now) {
+            return Err(invalid("id_token has expired"));
+        }
+        if time("iat").is_some_and(|iat| iat - CLOCK_SKEW_SECS > now) {
+            return Err(invalid("id_token was issued in the future"));
+        }
+        if claims.get("nonce").and_then(Value::as_str) != Some(&login.nonce) {
+            return Err(invalid("nonce does not match the login"));
+        }
+
+        let launch = Launch::from_claims(&claims, login)?;
+        check_deployment(platform, &launch.deployment_id)?;
+        Ok(launch)
+    }
+
+    /// The platform key with the given ID, fetching the platform's JWKS when
+    /// the cached copy is stale or lacks it (keys are rotated)
+    async fn platform_key(&self, platform: &Platform, kid: Option<&str>) -> Result<RsaPublicKey, LtiError> {
+        let find = |keys: &[PlatformKey]| {
+            keys.iter()
+                .find(|key| kid.is_none() || key.kid.as_deref() == kid)
+                .map(|key| key.key.clone())
+        };
+        let cached = self.platform_keys.lock().unwrap().get(&platform.issuer).cloned();
+        if let Some((fetched, keys)) = cached {
+            if fetched.elapsed() < JWKS_TTL {
+                if let Some(key) = find(&keys) {
+                    return Ok(key);
+                }
+            }
+        }
+
+        let jwks: Jwks = self
+            .http
+            .get(&platform.jwks_url)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(platform_error)?
+            .json()
+            .await
+            .map_err(platform_error)?;
+        let keys: Vec<PlatformKey> = jwks
+            .keys
+            .into_iter()
+            .filter(|jwk| jwk.kty == "RSA")
+            .filter_map(|jwk| {
+                let n = URL_SAFE_NO_PAD.decode(jwk.n?).ok()?;
+                let e = URL_SAFE_NO_PAD.decode(jwk.e?).ok()?;
+                let key = RsaPublicKey::new(BigUint::from_bytes_be(&n), BigUint::from_bytes_be(&e)).ok()?;
+                Some(PlatformKey { kid: jwk.kid, key })
+            })
+            .collect();
+        let key = find(&keys);
+        self.platform_keys
+            .lock()
+            .unwrap()
+            .insert(platform.issuer.clone(), (Instant::now(), Arc::new(keys)));
+        key.ok_or_else(|| invalid(format!("platform has no key {}", kid.unwrap_or("for RS256"))))
+    }
+
+    /// Remember a deep linking request while the instructor picks content
+    pub fn begin_deep_link(&self, launch: &Launch) -> Result<String, LtiError> {
+        let return_url = launch
+            .deep_link_return_url
+            .clone()
+            .ok_or_else(|| invalid("deep_link_return_url is missing"))?;
+        let id = generate_refresh_token();
+        let mut sessions = self.deep_links.lock().unwrap();
+        sessions.retain(|_, session| session.created.elapsed() < DEEP_LINK_TTL);
+        sessions.insert(
+            id.clone(),
+            DeepLinkSession {
+                issuer: launch.issuer.clone(),
+                client_id: launch.client_id.clone(),
+                deployment_id: launch.deployment_id.clone(),
+                return_url,
+                data: launch.deep_link_data.clone(),
+                created: Instant::now(),
+            },
+        );
+        Ok(id)
+    }
+
+    pub fn take_deep_link(&self, id: &str) -> Result<DeepLinkSession, LtiError> {
+        self.deep_links
+            .lock()
+            .unwrap()
+            .remove(id)
+            .filter(|session| session.created.elapsed() < DEEP_LINK_TTL)
+            .ok_or_else(|| invalid("unknown or expired deep linking session"))
+    }
+
+    /// Signed LtiDeepLinkingResponse carrying the chosen content items
+    pub fn deep_linking_response(&self, session: &DeepLinkSession, items: Vec<Value>) -> Result<String, LtiError> {
+        let now = Utc::now().timestamp();
+        let mut claims = json!({
+            "iss": session.client_id,
+            "aud": session.issuer,
+            "iat": now,
+            "exp": now + MESSAGE_TTL_SECS,
+            "nonce": Uuid::new_v4().to_string(),
+            format!("{CLAIM_PREFIX}message_type"): "LtiDeepLinkingResponse",
+            format!("{CLAIM_PREFIX}version"): LTI_VERSION,
+            format!("{CLAIM_PREFIX}deployment_id"): session.deployment_id,
+            format!("{DEEP_LINKING_PREFIX}content_items"): items,
+        });
+        if let Some(data) = &session.data {
+            claims[format!("{DEEP_LINKING_PREFIX}data")] = data.clone().into();
+        }
+        self.sign(&claims)
+    }
+
+    /// Send a learner's quiz score to every gradebook column linked to the
+    /// simulation that has no score yet. A quiz shows which answers were
+    /// right and can be taken again, so only the first attempt after a
+    /// launch counts on the platform. Failures are logged, and leave the
+    /// column to the next attempt; the score stays recorded here.
+    pub async fn pass_back(&self, db: &SqlitePool, user_id: &str, simulation_id: &str, score: f64) {
+        let links = match GradeLink::claim_unscored(db, user_id, simulation_id).await {
+            Ok(links) => links,
+            Err(e) => {
+                tracing::error!("Failed to load LTI grade links: {}", e);
+                return;
+            }
+        };
+        for link in links {
+            if let Err(e) = self.post_score(&link, score).await {
+                tracing::warn!("LTI grade passback to {} failed: {}", link.lineitem, e);
+                if let Err(e) = link.release(db).await {
+                    tracing::error!("Failed to release LTI grade link: {}", e);
+                }
+            }
+        }
+    }
+
+    /// POST an AGS score, as a percentage, to a line item
+    async fn post_score(&self, link: &GradeLink, score: f64) -> Result<(), LtiError> {
+        let platform = self.platform(&link.issuer, link.client_id.as_deref())?;
+        let token = self.access_token(platform).await?;
+        let url = match link.lineitem.split_once('?') {
+            Some((path, query)) => format!("{}/scores?{}", path.trim_end_matches('/'), query),
+            None => format!("{}/scores", link.lineitem.trim_end_matches('/')),
+        };
+        let body = json!({
+            "userId": link.subject,
+            "scoreGiven": score,
+            "scoreMaximum": 100.0,
+            "activityProgress": "Completed",
+            "gradingProgress": "FullyGraded",
+            "timestamp": Utc::now().to_rfc3339(),
+        });
+        self.http
+            .post(url)
+            .bearer_auth(token)
+            .header(reqwest::header::CONTENT_TYPE, SCORE_CONTENT_TYPE)
+            .body(body.to_string())
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(platform_error)?;
+        Ok(())
+    }
+
+    /// An AGS access token for the platform, from the OAuth 2 client
+    /// credentials grant with a signed JWT assertion
+    async fn access_token(&self, platform: &Platform) -> Result<String, LtiError> {
+        let registration = (platform.issuer.clone(), platform.client_id.clone());
+        if let Some((expires, token)) = self.access_tokens.lock().unwrap().get(&registration) {
+            if Instant::now() < *expires {
+                return Ok(token.clone());
+            }
+        }
+        let now = Utc::now().timestamp();
+        let assertion = self.sign(&json!({
+            "iss": platform.client_id,
+            "sub": platform.client_id,
+            "aud": platform.auth_token_url,
+            "iat": now,
+            "exp": now + MESSAGE_TTL_SECS,
+            "jti": Uuid::new_v4().to_string(),
+        }))?;
+        let response: TokenResponse = self
+            .http
+            .post(&platform.auth_token_url)
+            .form(&[
+                ("grant_type", "client_credentials"),
+                ("client_assertion_type", "urn:ietf:params:oauth:client-assertion-type:jwt-bearer"),
+                ("client_assertion", assertion.as_str()),
+                ("scope", SCORE_SCOPE),
+            ])
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(platform_error)?
+            .json()
+            .await
+            .map_err(platform_error)?;
+        // Renew a minute early so a token never expires in flight
+        let lifetime = Duration::from_secs(response.expires_in.unwrap_or(3600).saturating_sub(60));
+        self.access_tokens
+            .lock()
+            .unwrap()
+            .insert(registration, (Instant::now() + lifetime, response.access_token.clone()));
+        Ok(response.access_token)
+    }
+
+    /// An RS256 JWT signed with the tool key
+    fn sign(&self, claims: &Value) -> Result<String, LtiError> {
+        let key = self.key.as_ref().ok_or(LtiError::NotConfigured)?;
+        let header = json!({ "alg": "RS256", "typ": "JWT", "kid": key.kid });
+        let signing_input = format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(header.to_string()),
+            URL_SAFE_NO_PAD.encode(claims.to_string())
+        );
+        let signature = URL_SAFE_NO_PAD.encode(key.sign(signing_input.as_bytes()));
+        Ok(format!("{signing_input}.{signature}"))
+    }
+}
+
+fn check_deployment(platform: &Platform, deployment_id: &str) -> Result<(), LtiError> {
+    if platform.deployment_ids.is_empty() || platform.deployment_ids.iter().any(|id| id == deployment_id) {
+        Ok(())
+    } else {
+        Err(invalid(format!("deployment {deployment_id} is not registered")))
+    }
+}
+
+fn decode_segment<T: DeserializeOwned>(segment: &str) -> Result<T, LtiError> {
+    let bytes = URL_SAFE_NO_PAD
+        .decode(segment.trim_end_matches('='))
+        .map_err(|_| invalid("id_token is not base64url"))?;
+    serde_json::from_slice(&bytes).map_err(|_| invalid("id_token is not JSON"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rsa::pkcs8::{EncodePrivateKey, LineEnding};
+
+    const ISSUER: &str = "https://lms.example.edu";
+    const CLIENT_ID: &str = "physics-tutorial";
+    const NONCE: &str = "nonce-from-the-login";
+
+    /// A tool registered with one platform, which signs with the tool's own
+    /// key and publishes it at a local JWKS endpoint
+    async fn tool() -> Lti {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let platform = Platform {
+            issuer: ISSUER.to_string(),
+            client_id: CLIENT_ID.to_string(),
+            deployment_ids: vec!["deployment-1".to_string()],
+            auth_login_url: format!("{ISSUER}/auth"),
+            auth_token_url: format!("{ISSUER}/token"),
+            jwks_url: format!("http://{}/jwks", listener.local_addr().unwrap()),
+        };
+        let private = RsaPrivateKey::new(&mut rand::thread_rng(), 1024).unwrap();
+        let config = LtiConfig {
+            platforms: vec![platform],
+            private_key: Some(private.to_pkcs8_pem(LineEnding::LF).unwrap().to_string()),
+        };
+        let lti = Lti::from_config(&config, &ServerConfig::default());
+        let jwks = lti.jwks();
+        let app = axum::Router::new().route("/jwks", axum::routing::get(move || async move { axum::Json(jwks) }));
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        lti
+    }
+
+    fn login() -> PendingLogin {
+        PendingLogin {
+            issuer: ISSUER.to_string(),
+            client_id: CLIENT_ID.to_string(),
+            nonce: NONCE.to_string(),
+            created: Instant::now(),
+        }
+    }
+
+    fn claims() -> Value {
+        let now = Utc::now().timestamp();
+        json!({
+            "iss": ISSUER,
+            "aud": CLIENT_ID,
+            "sub": "learner-1",
+            "iat": now,
+            "exp": now + 300,
+            "nonce": NONCE,
+            format!("{CLAIM_PREFIX}message_type"): "LtiResourceLinkRequest",
+            format!("{CLAIM_PREFIX}version"): LTI_VERSION,
+            format!("{CLAIM_PREFIX}deployment_id"): "deployment-1",
+        })
+    }
+
+    /// The claims signed as the platform would, under key ID `kid`
+    fn id_token(lti: &Lti, claims: &Value, kid: &str) -> String {
+        let header = json!({ "alg": "RS256", "typ": "JWT", "kid": kid });
+        let signing_input = format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(header.to_string()),
+            URL_SAFE_NO_PAD.encode(claims.to_string())
+        );
+        let signature = lti.key.as_ref().unwrap().sign(signing_input.as_bytes());
+        format!("{signing_input}.{}", URL_SAFE_NO_PAD.encode(signature))
+    }
+
+    #[tokio::test]
+    async fn id_tokens_are_verified() {
+        let lti = tool().await;
+        let kid = lti.key.as_ref().unwrap().kid.clone();
+        let launch = lti.verify_launch(&id_token(&lti, &claims(), &kid), &login()).await.unwrap();
+        assert_eq!(launch.subject, "learner-1");
+        assert_eq!(launch.deployment_id, "deployment-1");
+
+        let mut several = claims();
+        several["aud"] = json!([CLIENT_ID, "another-tool"]);
+        several["azp"] = json!(CLIENT_ID);
+        assert!(lti.verify_launch(&id_token(&lti, &several, &kid), &login()).await.is_ok());
+
+        let with = |name: &str, value: Value| {
+            let mut claims = claims();
+            claims[name] = value;
+            claims
+        };
+        let expired = Utc::now().timestamp() - CLOCK_SKEW_SECS - 1;
+        let cases = [
+            (with("aud", json!("another-tool")), kid.as_str(), "id_token is not addressed to this tool"),
+            (with("aud", json!([CLIENT_ID, "another-tool"])), &kid, "azp must name this tool"),
+            (with("exp", json!(expired)), &kid, "id_token has expired"),
+            (with("nonce", json!("another-nonce")), &kid, "nonce does not match the login"),
+            (claims(), "rotated-away", "platform has no key rotated-away"),
+        ];
+        for (claims, kid, reason) in cases {
+            match lti.verify_launch(&id_token(&lti, &claims, kid), &login()).await {
+                Err(LtiError::InvalidLaunch(message)) => assert_eq!(message, reason),
+                Err(err) => panic!("expected {reason:?}, got {err}"),
+                Ok(_) => panic!("expected {reason:?}, the launch was accepted"),
+            }
+        }
+    }
+
+    #[test]
+    fn launches_need_their_login_state_cookie() {
+        let state = "FWg7d5sZaV01k7kHeQukFisYXcHAHYeBt8t-x9xH1FE";
+        assert!(check_state_cookie([format!("theme=dark; lti_state_{state}=1").as_str()], state).is_ok());
+        assert!(check_state_cookie(["theme=dark", &format!("lti_state_{state}=1")], state).is_ok());
+        assert!(check_state_cookie([], state).is_err());
+        assert!(check_state_cookie(["lti_state_other=1"], state).is_err());
+        assert!(check_state_cookie([format!("x=lti_state_{state}").as_str()], state).is_err());
+    }
+}
@@ -0,0 +1,603 @@
+// Minimal Markdown renderer for theory sections
+//
+// Covers the CommonMark that theory sections are written in: ATX headings,
+// paragraphs, bullet and ordered lists nested by indentation, block quotes,
+// fenced code, thematic breaks, emphasis, strong emphasis, code spans,
+// links, images and autolinks. TeX math passes through for KaTeX in the
+// markup of pulldown-cmark's math extension: `$…$` becomes
+// `<span class="math math-inline">` and `$$…$$` `<span class="math
+// math-display">`, holding the TeX as text. Tables, setext headings,
+// reference links and entity references are not recognized and come out
+// as written.
+//
+// The output needs no further sanitizing: every character of the source
+// reaches it HTML-escaped, so raw HTML is shown as text rather than
+// interpreted, and links and images keep only http, https, mailto and
+// relative URLs.
+
+/// Schemes a link or image may use; URLs without one are relative
+const SAFE_SCHEMES: [&str; 3] = ["http", "https", "mailto"];
+/// Deepest nesting of block quotes and lists, and of emphasis and links,
+/// rendered as such; deeper markup comes out as written
+const MAX_DEPTH: usize = 32;
+
+/// HTML fragment for a Markdown document
+pub fn to_html(markdown: &str) -> String {
+    let lines: Vec<String> = markdown.lines().map(|line| line.replace('\t', "    ")).collect();
+    let mut html = String::new();
+    blocks(&lines, false, 0, &mut html);
+    html
+}
+
+/// Text of a Markdown document without its markup, for indexing and
+/// snippets; math stays as TeX
+pub fn to_text(markdown: &str) -> String {
+    let mut text = String::new();
+    let mut in_tag = false;
+    // Every `<` and `>` of the source is escaped, so these are the markup
+    for c in to_html(markdown).chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Escape text for element content and quoted attribute values
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    text.chars().for_each(|c| push_escaped(&mut escaped, c));
+    escaped
+}
+
+fn push_escaped(html: &mut String, c: char) {
+    match c {
+        '&' => html.push_str("&amp;"),
+        '<' => html.push_str("&lt;"),
+        '>' => html.push_str("&gt;"),
+        '"' => html.push_str("&quot;"),
+        '\'' => html.push_str("&#39;"),
+        _ => html.push(c),
+    }
+}
+
+/// Render block-level structure `depth` quotes and lists deep. Paragraphs
+/// of tight list items are not wrapped in `<p>`.
+fn blocks(lines: &[String], tight: bool, depth: usize, html: &mut String) {
+    let nests = depth < MAX_DEPTH;
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i].trim_start();
+        if line.is_empty() {
+            i += 1;
+        } else if let Some((marker, info)) = fence(line) {
+            i += 1;
+            let start = i;
+            while i < lines.len() && !lines[i].trim_start().starts_with(marker) {
+                i += 1;
+            }
+            let code = lines[start..i].join("\n");
+            i += 1;
+            match info.split_whitespace().next() {
+                Some(language) => html.push_str(&format!("<pre><code class=\"language-{}\">", escape_html(language))),
+                None => html.push_str("<pre><code>"),
+            }
+            html.push_str(&escape_html(&code));
+            if !code.is_empty() {
+                html.push('\n');
+            }
+            html.push_str("</code></pre>\n");
+        } else if let Some(rest) = line.strip_prefix("$$") {
+            let mut tex = rest.to_string();
+            i += 1;
+            if rest.trim_end().len() < 2 || !rest.trim_end().ends_with("$$") {
+                while i < lines.len() && !tex.trim_end().ends_with("$$") {
+                    tex.push('\n');
+                    tex.push_str(&lines[i]);
+                    i += 1;
+                }
+            }
+            let tex = tex.trim_end().strip_suffix("$$").unwrap_or(&tex).trim();
+            html.push_str(&format!("<p><span class=\"math math-display\">{}</span></p>\n", escape_html(tex)));
+        } else if let Some((level, text)) = heading(line) {
+            html.push_str(&format!("<h{level}>{}</h{level}>\n", inline(text)));
+            i += 1;
+        } else if is_thematic_break(line) {
+            html.push_str("<hr>\n");
+            i += 1;
+        } else if nests && line.starts_with('>') {
+            let mut quoted = Vec::new();
+            while i < lines.len() {
+                let Some(rest) = lines[i].trim_start().strip_prefix('>') else {
+                    break;
+                };
+                quoted.push(rest.strip_prefix(' ').unwrap_or(rest).to_string());
+                i += 1;
+            }
+            html.push_str("<blockquote>\n");
+            blocks(&quoted, false, depth + 1, html);
+            html.push_str("</blockquote>\n");
+        } else if nests && list_marker(&lines[i]).is_some() {
+            list(lines, &mut i, depth, html);
+        } else {
+            let mut text = vec![line.trim_end()];
+            i += 1;
+            while i < lines.len() && !lines[i].trim().is_empty() && (!nests || !starts_block(&lines[i])) {
+                text.push(lines[i].trim());
+                i += 1;
+            }
+            let content = inline(&text.join("\n"));
+            if tight {
+                html.push_str(&content);
+                html.push('\n');
+            } else {
+                html.push_str(&format!("<p>{content}</p>\n"));
+            }
+        }
+    }
+}
+
+/// Whether a line interrupts a paragraph
+fn starts_block(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    fence(trimmed).is_some()
+        || trimmed.starts_with("$$")
+        || heading(trimmed).is_some()
+        || is_thematic_break(trimmed)
+        || trimmed.starts_with('>')
+        || list_marker(line).is_some()
+}
+
+/// Opening code fence: the marker that closes it and the info string
+fn fence(line: &str) -> Option<(&'static str, &str)> {
+    ["```", "~~~"].into_iter().find_map(|marker| line.strip_prefix(marker).map(|info| (marker, info.trim())))
+}
+
+/// ATX heading: level and text without the closing `#`s
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    let rest = &line[level..];
+    if !(1..=6).contains(&level) || !(rest.is_empty() || rest.starts_with(' ')) {
+        return None;
+    }
+    let text = rest.trim();
+    let without_closing = text.trim_end_matches('#');
+    if without_closing.is_empty() || without_closing.ends_with(' ') {
+        Some((level, without_closing.trim_end()))
+    } else {
+        Some((level, text))
+    }
+}
+
+/// Three or more `-`, `*` or `_`, optionally spaced
+fn is_thematic_break(line: &str) -> bool {
+    let marks: Vec<char> = line.chars().filter(|c| !c.is_whitespace()).collect();
+    marks.len() >= 3 && ['-', '*', '_'].iter().any(|&mark| marks.iter().all(|&c| c == mark))
+}
+
+/// A list item's marker
+struct Marker {
+    /// The bullet character, or `.` or `)` after the number of an ordered item
+    delimiter: char,
+    /// Number of an ordered item
+    number: Option<u64>,
+    /// Column the item's content starts at
+    content: usize,
+}
+
+fn list_marker(line: &str) -> Option<Marker> {
+    let indent = indentation(line);
+    let rest = &line[indent..];
+    if indent > 3 || is_thematic_break(rest) {
+        return None;
+    }
+    let digits = rest.chars().take_while(char::is_ascii_digit).count();
+    let (delimiter, number, width) = match rest.chars().next()? {
+        c @ ('-' | '*' | '+') => (c, None, 1),
+        _ if (1..=9).contains(&digits) => {
+            let delimiter = rest[digits..].chars().next().filter(|c| matches!(c, '.' | ')'))?;
+            (delimiter, rest[..digits].parse().ok(), digits + 1)
+        }
+        _ => return None,
+    };
+    let after = &rest[width..];
+    if !(after.is_empty() || after.starts_with(' ')) {
+        return None;
+    }
+    // Content starts after one to four spaces; more make an indented line
+    let spaces = indentation(after);
+    let gap = if (1..=4).contains(&spaces) { spaces } else { 1 };
+    Some(Marker {
+        delimiter,
+        number,
+        content: indent + width + gap,
+    })
+}
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+/// Render the list starting at line `i` and move `i` past it. Items are
+/// tight unless blank lines separate them or their blocks.
+fn list(lines: &[String], i: &mut usize, depth: usize, html: &mut String) {
+    let first = list_marker(&lines[*i]).expect("a list starts with a marker");
+    let mut items: Vec<Vec<String>> = Vec::new();
+    let mut loose = false;
+    while *i < lines.len() {
+        let Some(marker) = list_marker(&lines[*i]).filter(|m| m.delimiter == first.delimiter) else {
+            break;
+        };
+        let mut body = vec![lines[*i].get(marker.content..).unwrap_or("").to_string()];
+        *i += 1;
+        while *i < lines.len() {
+            let line = &lines[*i];
+            if line.trim().is_empty() {
+                let Some(next) = lines[*i..].iter().position(|l| !l.trim().is_empty()) else {
+                    break;
+                };
+                if indentation(&lines[*i + next]) < marker.content {
+                    break;
+                }
+                body.extend(std::iter::repeat_n(String::new(), next));
+                *i += next;
+                loose = true;
+            } else if indentation(line) >= marker.content {
+                body.push(line[marker.content..].to_string());
+                *i += 1;
+            } else if !starts_block(line) && body.last().is_some_and(|last| !last.trim().is_empty()) {
+                // Lazy continuation of the item's paragraph
+                body.push(line.trim().to_string());
+                *i += 1;
+            } else {
+                break;
+            }
+        }
+        items.push(body);
+
+        // Blank lines before another item of this list make it loose
+        if let Some(next) = lines[*i..].iter().position(|l| !l.trim().is_empty()).filter(|&next| next > 0) {
+            let continues =
+                list_marker(&lines[*i + next]).is_some_and(|m| m.delimiter == first.delimiter);
+            if !continues {
+                break;
+            }
+            *i += next;
+            loose = true;
+        }
+    }
+
+    match first.number {
+        None => html.push_str("<ul>\n"),
+        Some(1) => html.push_str("<ol>\n"),
+        Some(start) => html.push_str(&format!("<ol start=\"{start}\">\n")),
+    }
+    for body in items {
+        let mut item = String::new();
+        blocks(&body, !loose, depth + 1, &mut item);
+        html.push_str("<li>");
+        html.push_str(item.trim_end());
+        html.push_str("</li>\n");
+    }
+    html.push_str(if first.number.is_some() { "</ol>\n" } else { "</ul>\n" });
+}
+
+/// Render inline content: emphasis, code, math, links and escapes
+fn inline(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut html = String::new();
+    inline_into(&chars, 0, &mut html);
+    html
+}
+
+/// Render inline content inside `depth` emphasis and links
+fn inline_into(chars: &[char], depth: usize, html: &mut String) {
+    if depth >= MAX_DEPTH {
+        chars.iter().for_each(|&c| push_escaped(html, c));
+        return;
+    }
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        match c {
+            '\\' if next.is_some_and(|n| n.is_ascii_punctuation()) => {
+                push_escaped(html, chars[i + 1]);
+                i += 2;
+            }
+            '\\' if next == Some('\n') => {
+                html.push_str("<br>\n");
+                i += 2;
+            }
+            '`' => {
+                let run = run_length(chars, i, '`');
+                match find_run(chars, i + run, '`', run) {
+                    Some(end) => {
+                        let code: String =
+                            chars[i + run..end].iter().map(|&c| if c == '\n' { ' ' } else { c }).collect();
+                        let code = match code.strip_prefix(' ').and_then(|c| c.strip_suffix(' ')) {
+                            Some(inner) if !inner.trim().is_empty() => inner.to_string(),
+                            _ => code,
+                        };
+                        html.push_str(&format!("<code>{}</code>", escape_html(&code)));
+                        i = end + run;
+                    }
+                    None => {
+                        (0..run).for_each(|_| html.push('`'));
+                        i += run;
+                    }
+                }
+            }
+            '$' => match math_end(chars, i) {
+                Some((end, display)) => {
+                    let width = if display { 2 } else { 1 };
+                    let tex: String = chars[i + width..end].iter().collect();
+                    let class = if display { "math math-display" } else { "math math-inline" };
+                    html.push_str(&format!("<span class=\"{class}\">{}</span>", escape_html(tex.trim())));
+                    i = end + width;
+                }
+                None => {
+                    html.push('$');
+                    i += 1;
+                }
+            },
+            '*' | '_' => {
+                let run = run_length(chars, i, c);
+                let opens = next.is_some_and(|n| !n.is_whitespace())
+                    && (c == '*' || i == 0 || !chars[i - 1].is_alphanumeric());
+                let strong = (opens && run >= 2).then(|| closing(chars, i + 2, c, 2)).flatten();
+                let emphasis = (opens && strong.is_none()).then(|| closing(chars, i + 1, c, 1)).flatten();
+                if let Some(end) = strong {
+                    html.push_str("<strong>");
+                    inline_into(&chars[i + 2..end], depth + 1, html);
+                    html.push_str("</strong>");
+                    i = end + 2;
+                } else if let Some(end) = emphasis {
+                    html.push_str("<em>");
+                    inline_into(&chars[i + 1..end], depth + 1, html);
+                    html.push_str("</em>");
+                    i = end + 1;
+                } else {
+                    (0..run).for_each(|_| html.push(c));
+                    i += run;
+                }
+            }
+            '!' if next == Some('[') => match link(chars, i + 1) {
+                Some((alt, url, end)) => {
+                    if safe_url(&url) {
+                        let alt: String = alt.iter().collect();
+                        html.push_str(&format!("<img src=\"{}\" alt=\"{}\">", escape_html(&url), escape_html(&alt)));
+                    } else {
+                        inline_into(alt, depth + 1, html);
+                    }
+                    i = end;
+                }
+                None => {
+                    html.push('!');
+                    i += 1;
+                }
+            },
+            '[' => match link(chars, i) {
+                Some((label, url, end)) => {
+                    if safe_url(&url) {
+                        html.push_str(&format!("<a href=\"{}\">", escape_html(&url)));
+                        inline_into(label, depth + 1, html);
+                        html.push_str("</a>");
+                    } else {
+                        inline_into(label, depth + 1, html);
+                    }
+                    i = end;
+                }
+                None => {
+                    html.push('[');
+                    i += 1;
+                }
+            },
+            '<' => match autolink(chars, i) {
+                Some((url, end)) => {
+                    let url = escape_html(&url);
+                    html.push_str(&format!("<a href=\"{url}\">{url}</a>"));
+                    i = end;
+                }
+                None => {
+                    html.push_str("&lt;");
+                    i += 1;
+                }
+            },
+            _ => {
+                push_escaped(html, c);
+                i += 1;
+            }
+        }
+    }
+}
+
+/// How many times `c` repeats from `start`
+fn run_length(chars: &[char], start: usize, c: char) -> usize {
+    chars[start..].iter().take_while(|&&x| x == c).count()
+}
+
+/// Start of the next run of exactly `length` copies of `c` from `start`
+fn find_run(chars: &[char], start: usize, c: char, length: usize) -> Option<usize> {
+    let mut i = start;
+    while i < chars.len() {
+        if chars[i] == c {
+            let run = run_length(chars, i, c);
+            if run == length {
+                return Some(i);
+            }
+            i += run;
+        } else {
+            i += 1;
+        }
+    }
+    None
+}
+
+/// Where the math starting with the `$` at `start` ends, and whether it is
+/// display math. Inline math follows Pandoc's rule so prices stay text:
+/// no space just inside either `$`, and no digit right after the closing one.
+fn math_end(chars: &[char], start: usize) -> Option<(usize, bool)> {
+    if chars.get(start + 1) == Some(&'$') {
+        let end = (start + 2..chars.len().saturating_sub(1)).find(|&j| chars[j] == '$' && chars[j + 1] == '$')?;
+        return (end > start + 2).then_some((end, true));
+    }
+    if chars.get(start + 1).is_none_or(|c| c.is_whitespace()) {
+        return None;
+    }
+    let mut j = start + 1;
+    while j < chars.len() {
+        match chars[j] {
+            '\\' => j += 2,
+            '$' if !chars[j - 1].is_whitespace() && !chars.get(j + 1).is_some_and(char::is_ascii_digit) => {
+                return Some((j, false));
+            }
+            _ => j += 1,
+        }
+    }
+    None
+}
+
+/// Position of the delimiter run of `length` copies of `c` closing
+/// emphasis opened before `start`, stepping over code spans and math so
+/// that their asterisks and underscores are not taken as delimiters
+fn closing(chars: &[char], start: usize, c: char, length: usize) -> Option<usize> {
+    let mut i = start;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 2,
+            '`' => {
+                let run = run_length(chars, i, '`');
+                i = find_run(chars, i + run, '`', run).map_or(i + run, |end| end + run);
+            }
+            '$' => i = math_end(chars, i).map_or(i + 1, |(end, display)| end + if display { 2 } else { 1 }),
+            x if x == c => {
+                let run = run_length(chars, i, c);
+                let closes = i > start
+                    && !chars[i - 1].is_whitespace()
+                    && (c == '*' || !chars.get(i + run).is_some_and(|n| n.is_alphanumeric()));
+                // A run of two closes strong emphasis, not emphasis
+                if closes && run >= length && !(length == 1 && run == 2) {
+                    return Some(i);
+                }
+                i += run;
+            }
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// `[label](url)` or `[label](url "title")` starting at the `[` at
+/// `start`: the label, the URL and the position after the `)`
+fn link(chars: &[char], start: usize) -> Option<(&[char], String, usize)> {
+    let mut depth = 0;
+    let mut i = start;
+    let close = loop {
+        match chars.get(i)? {
+            '\\' => i += 1,
+            '[' => depth += 1,
+            ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    break i;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    };
+    if chars.get(close + 1) != Some(&'(') {
+        return None;
+    }
+    // URLs may hold balanced parentheses
+    let mut depth = 0;
+    let end = (close + 2..chars.len()).find(|&j| {
+        match chars[j] {
+            '(' => depth += 1,
+            ')' if depth == 0 => return true,
+            ')' => depth -= 1,
+            _ => {}
+        }
+        false
+    })?;
+    let target: String = chars[close + 2..end].iter().collect();
+    let url = target.split_whitespace().next().unwrap_or("");
+    let url = url.strip_prefix('<').and_then(|u| u.strip_suffix('>')).unwrap_or(url);
+    Some((&chars[start + 1..close], url.to_string(), end + 1))
+}
+
+/// `<https://…>` or `<mailto:…>` starting at `start`: the URL and the
+/// position after the `>`
+fn autolink(chars: &[char], start: usize) -> Option<(String, usize)> {
+    let end = (start + 1..chars.len()).find(|&j| chars[j] == '>' || chars[j] == '<' || chars[j].is_whitespace())?;
+    if chars[end] != '>' {
+        return None;
+    }
+    let url: String = chars[start + 1..end].iter().collect();
+    let scheme = url.split_once(':')?.0.to_ascii_lowercase();
+    SAFE_SCHEMES.contains(&scheme.as_str()).then_some((url, end + 1))
+}
+
+/// Whether a link may point at `url`: one with a scheme in `SAFE_SCHEMES`,
+/// or a relative one. Anything else, `javascript:` and `data:` included, is
+/// dropped and its text kept.
+fn safe_url(url: &str) -> bool {
+    match url.find([':', '/', '?', '#']) {
+        Some(i) if url[i..].starts_with(':') => SAFE_SCHEMES.contains(&url[..i].to_ascii_lowercase().as_str()),
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_nested_quotes_and_lists() {
+        assert_eq!(
+            to_html("> quoted\n> > deeper"),
+            "<blockquote>\n<p>quoted</p>\n<blockquote>\n<p>deeper</p>\n</blockquote>\n</blockquote>\n"
+        );
+        assert_eq!(to_html("- a\n  - b"), "<ul>\n<li>a\n<ul>\n<li>b</li>\n</ul></li>\n</ul>\n");
+    }
+
+    #[test]
+    fn deep_nesting_comes_out_as_text() {
+        let html = to_html(&">".repeat(20_000));
+        assert_eq!(html.matches("<blockquote>").count(), MAX_DEPTH);
+        assert!(html.contains(&"&gt;".repeat(20_000 - MAX_DEPTH)));
+
+        let html = to_html(&format!("{}x", "1. ".repeat(20_000)));
+        assert_eq!(html.matches("<ol>").count(), MAX_DEPTH);
+
+        let html = to_html(&format!("{}x{}", "[".repeat(20_000), "](a)".repeat(20_000)));
+        assert_eq!(html.matches("<a href").count(), MAX_DEPTH);
+    }
+
+    #[test]
+    fn unsafe_urls_are_dropped() {
+        for url in ["javascript:alert(1)", "JavaScript:alert(1)", "data:text/html,alert(1)", "vbscript:msgbox(1)"] {
+            assert_eq!(to_html(&format!("[text]({url})")), "<p>text</p>\n", "link to {url}");
+            assert_eq!(to_html(&format!("![text]({url})")), "<p>text</p>\n", "image from {url}");
+            let escaped = escape_html(&format!("<{url}>"));
+            assert_eq!(to_html(&format!("<{url}>")), format!("<p>{escaped}</p>\n"), "autolink to {url}");
+        }
+        assert_eq!(to_html("[text](/simulations)"), "<p><a href=\"/simulations\">text</a></p>\n");
+        assert_eq!(to_html("![a](https://x.org/a.png)"), "<p><img src=\"https://x.org/a.png\" alt=\"a\"></p>\n");
+        assert_eq!(to_html("<mailto:a@x.org>"), "<p><a href=\"mailto:a@x.org\">mailto:a@x.org</a></p>\n");
+    }
+
+    #[test]
+    fn raw_html_is_escaped() {
+        assert_eq!(to_html("<script>alert(1)</script>"), "<p>&lt;script&gt;alert(1)&lt;/script&gt;</p>\n");
+        assert_eq!(to_html("<img src=x onerror=alert(1)>"), "<p>&lt;img src=x onerror=alert(1)&gt;</p>\n");
+    }
+}
@@ -0,0 +1,175 @@
+// Prometheus metrics
+//
+// Request latencies, simulation runs and compute times are recorded in
+// process as they happen and written in the Prometheus text exposition
+// format when `/metrics` is scraped. Gauges such as the job queue depth are
+// read from their services at scrape time. Counters start from zero with
+// every process; Prometheus `rate()` handles the resets.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Upper bounds of the request latency buckets (seconds)
+const REQUEST_BUCKETS: [f64; 11] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+/// Upper bounds of the compute time buckets (seconds), up to the job limit
+const COMPUTE_BUCKETS: [f64; 11] = [0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 10.0, 60.0, 300.0];
+
+/// Cumulative histogram with fixed buckets
+struct Histogram {
+    bounds: &'static [f64],
+    /// Observations at or below each bound, then above the last
+    counts: Vec<u64>,
+    sum: f64,
+}
+
+impl Histogram {
+    fn new(bounds: &'static [f64]) -> Self {
+        Self {
+            bounds,
+            counts: vec![0; bounds.len() + 1],
+            sum: 0.0,
+        }
+    }
+
+    fn observe(&mut self, value: f64) {
+        let bucket = self.bounds.iter().position(|&bound| value <= bound).unwrap_or(self.bounds.len());
+        self.counts[bucket] += 1;
+        self.sum += value;
+    }
+
+    fn write(&self, out: &mut String, name: &str, labels: &str) {
+        let separator = if labels.is_empty() { "" } else { "," };
+        let mut cumulative = 0;
+        for (bound, count) in self.bounds.iter().zip(&self.counts) {
+            cumulative += count;
+            let _ = writeln!(out, "{name}_bucket{{{labels}{separator}le=\"{bound}\"}} {cumulative}");
+        }
+        cumulative += self.counts[self.bounds.len()];
+        let _ = writeln!(out, "{name}_bucket{{{labels}{separator}le=\"+Inf\"}} {cumulative}");
+        let _ = writeln!(out, "{name}_sum{{{labels}}} {}", self.sum);
+        let _ = writeln!(out, "{name}_count{{{labels}}} {cumulative}");
+    }
+}
+
+/// How a simulation run ended
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RunOutcome {
+    Ok,
+    /// Rejected parameters, exceeded time or size limits
+    Error,
+    Cancelled,
+}
+
+impl RunOutcome {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::Error => "error",
+            Self::Cancelled => "cancelled",
+        }
+    }
+}
+
+/// Values read from other services when metrics are scraped
+pub struct Gauges {
+    /// Result cache lookups answered from memory, from Redis, and missed
+    pub cache_memory_hits: u64,
+    pub cache_redis_hits: u64,
+    pub cache_misses: u64,
+    pub jobs_queued: usize,
+    pub jobs_running: usize,
+}
+
+#[derive(Default)]
+pub struct Metrics {
+    /// Latency by method, route template and status
+    requests: Mutex<BTreeMap<(String, String, u16), Histogram>>,
+    /// Run counts by simulation and outcome
+    runs: Mutex<BTreeMap<(String, RunOutcome), u64>>,
+    /// Compute time of finished runs by simulation
+    compute: Mutex<BTreeMap<String, Histogram>>,
+}
+
+impl Metrics {
+    /// Record a handled request. `route` is the route template, such as
+    /// `/api/v1/simulations/:id/run`, so IDs do not multiply the series.
+    pub fn observe_request(&self, method: &str, route: &str, status: u16, elapsed: Duration) {
+        self.requests
+            .lock()
+            .unwrap()
+            .entry((method.to_string(), route.to_string(), status))
+            .or_insert_with(|| Histogram::new(&REQUEST_BUCKETS))
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// Record a simulation run and how long it computed
+    pub fn observe_run(&self, simulation_id: &str, outcome: RunOutcome, elapsed: Duration) {
+        *self.runs.lock().unwrap().entry((simulation_id.to_string(), outcome)).or_default() += 1;
+        if outcome == RunOutcome::Ok {
+            self.compute
+                .lock()
+                .unwrap()
+                .entry(simulation_id.to_string())
+                .or_insert_with(|| Histogram::new(&COMPUTE_BUCKETS))
+                .observe(elapsed.as_secs_f64());
+        }
+    }
+
+    /// Everything in the Prometheus text format
+    pub fn render(&self, gauges: &Gauges) -> String {
+        let mut out = String::new();
+
+        header(&mut out, "http_request_duration_seconds", "histogram", "Time to respond to HTTP requests, by route");
+        for ((method, route, status), histogram) in self.requests.lock().unwrap().iter() {
+            let labels = format!("method=\"{method}\",route=\"{}\",status=\"{status}\"", escape(route));
+            histogram.write(&mut out, "http_request_duration_seconds", &labels);
+        }
+
+        header(&mut out, "simulation_runs_total", "counter", "Simulation runs, by simulation and outcome");
+        for ((simulation, outcome), count) in self.runs.lock().unwrap().iter() {
+            let _ = writeln!(
+                out,
+                "simulation_runs_total{{simulation=\"{}\",outcome=\"{}\"}} {count}",
+                escape(simulation),
+                outcome.name()
+            );
+        }
+
+        header(&mut out, "simulation_compute_seconds", "histogram", "Compute time of completed simulation runs");
+        for (simulation, histogram) in self.compute.lock().unwrap().iter() {
+            let labels = format!("simulation=\"{}\"", escape(simulation));
+            histogram.write(&mut out, "simulation_compute_seconds", &labels);
+        }
+
+        header(
+            &mut out,
+            "result_cache_lookups_total",
+            "counter",
+            "Result cache lookups, by where the result was found",
+        );
+        for (outcome, count) in [
+            ("memory", gauges.cache_memory_hits),
+            ("redis", gauges.cache_redis_hits),
+            ("miss", gauges.cache_misses),
+        ] {
+            let _ = writeln!(out, "result_cache_lookups_total{{outcome=\"{outcome}\"}} {count}");
+        }
+
+        header(&mut out, "job_queue_depth", "gauge", "Background jobs waiting for or holding a worker slot");
+        let _ = writeln!(out, "job_queue_depth{{status=\"queued\"}} {}", gauges.jobs_queued);
+        let _ = writeln!(out, "job_queue_depth{{status=\"running\"}} {}", gauges.jobs_running);
+        out
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+/// Label value with backslashes, quotes and newlines escaped
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
@@ -3,3 +3,41 @@
 
 pub mod physics;
 pub mod ai;
+pub mod auth;
+pub mod audio;
+pub mod export;
+pub mod parquet;
+pub mod gif;
+pub mod animation;
+pub mod compare;
+pub mod datasets;
+pub mod achievements;
+pub mod expression;
+pub mod script;
+pub mod fitting;
+pub mod golden;
+pub mod noise;
+pub mod spectrum;
+pub mod statistics;
+pub mod jobs;
+pub mod budget;
+pub mod cache;
+pub mod curriculum;
+pub mod recommendations;
+pub mod lti;
+pub mod i18n;
+pub mod toml;
+pub mod markdown;
+pub mod search;
+pub mod embeddings;
+pub mod tutor;
+pub mod zip;
+pub mod bundle;
+pub mod ipfs;
+pub mod ed25519;
+pub mod attestation;
+pub mod citation;
+pub mod metrics;
+pub mod trace;
+pub mod rate_limit;
+pub mod wasm;
@@ -0,0 +1,229 @@
+// Simulated detector imperfections applied to a run's output
+//
+// Simulations compute exact curves; a real detector records them through
+// pixels of finite size, counts discrete quanta, picks up dark counts and
+// adds electronic readout noise. A noise model turns one intensity-like
+// output into such a recording: the curve is averaged over each pixel,
+// scaled so the brightest pixel expects `peak_counts` quanta, offset by the
+// mean dark count, drawn from a Poisson distribution and blurred by
+// Gaussian readout noise. It is applied after the run, to cached results
+// too, so the stored output stays exact; the same seed gives the same
+// recording.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::numerics::monte_carlo::MonteCarlo;
+use crate::services::compare::pointer;
+use crate::simulations::params::ParameterError;
+use crate::simulations::{series, Signal};
+
+/// Most pixels a recording may have
+const MAX_PIXELS: usize = 10_000;
+/// Largest expected count in the brightest pixel
+const MAX_PEAK_COUNTS: f64 = 1e12;
+
+fn default_peak_counts() -> f64 {
+    1_000.0
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Detector to record a run's output with, as given in a run request
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NoiseModel {
+    /// Dotted path of the recorded output; the simulation's detector
+    /// signal by default
+    #[serde(default)]
+    pub output: Option<String>,
+    /// Dotted path of the positions the output is sampled at; defaults
+    /// with `output`
+    #[serde(default)]
+    pub position: Option<String>,
+    /// Expected count in the brightest pixel, setting the exposure
+    #[serde(default = "default_peak_counts")]
+    pub peak_counts: f64,
+    /// Draw each pixel's count from a Poisson distribution
+    #[serde(default = "default_true")]
+    pub shot_noise: bool,
+    /// Standard deviation of the Gaussian readout noise, in counts
+    #[serde(default)]
+    pub readout_noise: f64,
+    /// Mean dark count per pixel
+    #[serde(default)]
+    pub dark_counts: f64,
+    /// Pixel width in the units of `position`; the output's own sampling by
+    /// default
+    #[serde(default)]
+    pub pixel_size: Option<f64>,
+    /// Seed of the noise; a random one, reported back, by default
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+/// A run's output as the modelled detector records it
+#[derive(Serialize)]
+pub struct Detector {
+    pub output: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<String>,
+    pub seed: u64,
+    /// Expected counts per unit of the output
+    pub counts_per_unit: f64,
+    /// Pixel centres, in the units of `position`; absent when the output is
+    /// recorded sample by sample without positions
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub x: Option<Vec<f64>>,
+    /// Mean count of each pixel, dark counts included
+    pub expected: Vec<f64>,
+    /// Recorded count of each pixel; not whole numbers with readout noise
+    pub counts: Vec<f64>,
+}
+
+impl NoiseModel {
+    /// The output and positions to record, or what is wrong with the model.
+    /// Checked before the run, against the simulation's default signal.
+    pub fn check(&self, signal: Option<Signal>) -> Result<(String, Option<String>), Vec<ParameterError>> {
+        let mut errors = Vec::new();
+        if !(self.peak_counts > 0.0 && self.peak_counts <= MAX_PEAK_COUNTS) {
+            errors.push(ParameterError::new(
+                "noise.peak_counts",
+                format!("must be positive and at most {MAX_PEAK_COUNTS:e}"),
+                Some(&self.peak_counts.into()),
+            ));
+        }
+        for (name, value) in [("noise.readout_noise", self.readout_noise), ("noise.dark_counts", self.dark_counts)] {
+            if value < 0.0 {
+                errors.push(ParameterError::new(name, "must not be negative", Some(&value.into())));
+            }
+        }
+        if let Some(size) = self.pixel_size.filter(|&size| size <= 0.0) {
+            errors.push(ParameterError::new("noise.pixel_size", "must be positive", Some(&size.into())));
+        }
+        let target = match (&self.output, signal) {
+            (Some(output), _) => Some((output.clone(), self.position.clone())),
+            (None, Some(signal)) => {
+                Some((signal.output.to_string(), Some(self.position.clone().unwrap_or(signal.position.to_string()))))
+            }
+            (None, None) => {
+                errors.push(ParameterError::new(
+                    "noise.output",
+                    "must name the output to record; this simulation has no default detector signal",
+                    None,
+                ));
+                None
+            }
+        };
+        if let Some((_, None)) = &target {
+            if self.pixel_size.is_some() {
+                errors.push(ParameterError::new("noise.pixel_size", "needs noise.position, the output's positions", None));
+            }
+        }
+        match target {
+            Some(target) if errors.is_empty() => Ok(target),
+            _ => Err(errors),
+        }
+    }
+
+    /// Record `output`, sampled at `position`, from a run's data
+    pub fn record(&self, data: &Value, output: &str, position: Option<&str>) -> Result<Detector, ParameterError> {
+        let values = series(data, &pointer(output));
+        if values.is_empty() || values.iter().any(|value| !value.is_finite()) {
+            return Err(ParameterError::new(
+                "noise.output",
+                format!("`{output}` is not an array of numbers in this run's output"),
+                None,
+            ));
+        }
+        let positions = match position {
+            Some(position) => {
+                let positions = series(data, &pointer(position));
+                let increasing = positions.iter().all(|x| x.is_finite()) && positions.windows(2).all(|pair| pair[1] > pair[0]);
+                if positions.len() != values.len() || !increasing {
+                    return Err(ParameterError::new(
+                        "noise.position",
+                        format!("`{position}` is not an increasing array the length of `{output}`"),
+                        None,
+                    ));
+                }
+                Some(positions)
+            }
+            None => None,
+        };
+
+        let (x, signal) = match (&positions, self.pixel_size) {
+            (Some(positions), Some(size)) => {
+                let (low, high) = (positions[0], positions[positions.len() - 1]);
+                let pixels = ((high - low) / size).floor().max(1.0);
+                if pixels > MAX_PIXELS as f64 {
+                    return Err(ParameterError::new(
+                        "noise.pixel_size",
+                        format!("gives {pixels} pixels; the limit is {MAX_PIXELS}"),
+                        Some(&size.into()),
+                    ));
+                }
+                // Pixels centred on the sampled range
+                let start = (low + high) / 2.0 - pixels * size / 2.0;
+                let centres: Vec<f64> = (0..pixels as usize).map(|i| start + (i as f64 + 0.5) * size).collect();
+                let averages = centres
+                    .iter()
+                    .map(|centre| average(positions, &values, centre - size / 2.0, centre + size / 2.0))
+                    .collect();
+                (Some(centres), averages)
+            }
+            _ => (positions, values),
+        };
+
+        // Negative values of the output have no counts to give
+        let brightest = signal.iter().copied().fold(0.0, f64::max);
+        let counts_per_unit = if brightest > 0.0 { self.peak_counts / brightest } else { 0.0 };
+        let seed = self.seed.unwrap_or_else(rand::random);
+        let mut mc = MonteCarlo::new(seed);
+        let expected: Vec<f64> =
+            signal.iter().map(|value| value.max(0.0) * counts_per_unit + self.dark_counts).collect();
+        let counts = expected
+            .iter()
+            .map(|&mean| {
+                let count = if self.shot_noise { mc.poisson(mean) } else { mean };
+                count + self.readout_noise * mc.gaussian()
+            })
+            .collect();
+        Ok(Detector {
+            output: output.to_string(),
+            position: position.map(str::to_string),
+            seed,
+            counts_per_unit,
+            x,
+            expected,
+            counts,
+        })
+    }
+}
+
+/// Mean of the piecewise linear curve through the samples over [a, b],
+/// clipped to the sampled range
+fn average(xs: &[f64], ys: &[f64], a: f64, b: f64) -> f64 {
+    let (a, b) = (a.max(xs[0]), b.min(xs[xs.len() - 1]));
+    if b <= a {
+        return interpolate(xs, ys, a);
+    }
+    // Trapezoids between a, the samples inside (a, b), and b
+    let inside = xs.iter().zip(ys).filter(|(&x, _)| x > a && x < b).map(|(&x, &y)| (x, y));
+    let points: Vec<(f64, f64)> =
+        std::iter::once((a, interpolate(xs, ys, a))).chain(inside).chain([(b, interpolate(xs, ys, b))]).collect();
+    let area: f64 = points.windows(2).map(|pair| (pair[1].0 - pair[0].0) * (pair[0].1 + pair[1].1) / 2.0).sum();
+    area / (b - a)
+}
+
+/// Linear interpolation between the samples, at x within their range
+fn interpolate(xs: &[f64], ys: &[f64], x: f64) -> f64 {
+    if xs.len() < 2 {
+        return ys[0];
+    }
+    let i = xs.partition_point(|&xi| xi <= x).clamp(1, xs.len() - 1);
+    let t = (x - xs[i - 1]) / (xs[i] - xs[i - 1]);
+    ys[i - 1] + t * (ys[i] - ys[i - 1])
+}
@@ -0,0 +1,608 @@
+// Apache Parquet encoding of exported tables
+//
+// Writes the smallest useful subset of the format: one row group, one
+// uncompressed PLAIN-encoded data page per column, and OPTIONAL columns so
+// nulls survive. The footer is Thrift compact protocol, written by hand.
+
+use serde_json::Value;
+
+use crate::services::export::Table;
+
+const MAGIC: &[u8] = b"PAR1";
+
+/// Encode a table as a Parquet file.
+///
+/// Columns of booleans, integers and other numbers become BOOLEAN, INT64 and
+/// DOUBLE; anything else is written as UTF-8 text. Each column's unit is kept
+/// in its own metadata and `metadata` is stored in the file footer.
+pub fn encode_parquet(table: &Table, metadata: &[(&str, String)]) -> Vec<u8> {
+    let rows = table.rows();
+    let mut file = MAGIC.to_vec();
+    let mut chunks = Vec::with_capacity(table.columns.len());
+
+    for column in &table.columns {
+        let kind = Kind::of(&column.values);
+        let page = encode_page(kind, &column.values);
+
+        let mut header = Compact::default();
+        header.i32(1, 0); // DATA_PAGE
+        header.i32(2, page.len() as i32);
+        header.i32(3, page.len() as i32);
+        header.begin_struct(5);
+        header.i32(1, rows as i32);
+        header.i32(2, PLAIN);
+        header.i32(3, RLE);
+        header.i32(4, RLE);
+        header.end_struct();
+        header.stop();
+
+        let offset = file.len() as i64;
+        file.extend_from_slice(&header.bytes);
+        file.extend_from_slice(&page);
+        chunks.push(Chunk {
+            name: column.path.clone(),
+            unit: column.unit,
+            kind,
+            offset,
+            size: file.len() as i64 - offset,
+        });
+    }
+
+    let footer = file_metadata(rows, &chunks, metadata);
+    file.extend_from_slice(&footer);
+    file.extend_from_slice(&(footer.len() as u32).to_le_bytes());
+    file.extend_from_slice(MAGIC);
+    file
+}
+
+/// Parquet encoding ids
+const PLAIN: i32 = 0;
+const RLE: i32 = 3;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    Boolean,
+    Int64,
+    Double,
+    Text,
+}
+
+impl Kind {
+    /// Narrowest type that holds every non-null value
+    fn of(values: &[Value]) -> Self {
+        let present = || values.iter().filter(|v| !v.is_null());
+        if present().all(Value::is_boolean) && present().next().is_some() {
+            Kind::Boolean
+        } else if present().all(Value::is_number) {
+            if present().next().is_some() && present().all(|v| v.as_i64().is_some()) {
+                Kind::Int64
+            } else {
+                Kind::Double
+            }
+        } else {
+            Kind::Text
+        }
+    }
+
+    fn physical_type(self) -> i32 {
+        match self {
+            Kind::Boolean => 0,
+            Kind::Int64 => 2,
+            Kind::Double => 5,
+            Kind::Text => 6,
+        }
+    }
+}
+
+struct Chunk {
+    name: String,
+    unit: Option<&'static str>,
+    kind: Kind,
+    offset: i64,
+    size: i64,
+}
+
+/// Definition levels followed by the PLAIN-encoded non-null values
+fn encode_page(kind: Kind, values: &[Value]) -> Vec<u8> {
+    // Definition levels (1 = present) as RLE runs of a 1-bit value, prefixed
+    // with their byte length
+    let mut levels = Vec::new();
+    let mut i = 0;
+    while i < values.len() {
+        let defined = !values[i].is_null();
+        let run = values[i..].iter().take_while(|v| v.is_null() != defined).count();
+        write_varint(&mut levels, (run as u64) << 1);
+        levels.push(defined as u8);
+        i += run;
+    }
+    let mut page = (levels.len() as u32).to_le_bytes().to_vec();
+    page.extend_from_slice(&levels);
+
+    let present = values.iter().filter(|v| !v.is_null());
+    match kind {
+        Kind::Boolean => {
+            let bits: Vec<bool> = present.map(|v| v.as_bool().unwrap_or(false)).collect();
+            for byte in bits.chunks(8) {
+                page.push(byte.iter().enumerate().fold(0, |acc, (b, &set)| acc | ((set as u8) << b)));
+            }
+        }
+        Kind::Int64 => {
+            for v in present {
+                page.extend_from_slice(&v.as_i64().unwrap_or(0).to_le_bytes());
+            }
+        }
+        Kind::Double => {
+            for v in present {
+                page.extend_from_slice(&v.as_f64().unwrap_or(f64::NAN).to_le_bytes());
+            }
+        }
+        Kind::Text => {
+            for v in present {
+                let text = match v {
+                    Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                };
+                page.extend_from_slice(&(text.len() as u32).to_le_bytes());
+                page.extend_from_slice(text.as_bytes());
+            }
+        }
+    }
+    page
+}
+
+fn file_metadata(rows: usize, chunks: &[Chunk], metadata: &[(&str, String)]) -> Vec<u8> {
+    let mut meta = Compact::default();
+    meta.i32(1, 1); // format version
+
+    // Flat schema: a root group with one optional field per column
+    meta.list(2, STRUCT, chunks.len() + 1);
+    meta.begin_element();
+    meta.string(4, "schema");
+    meta.i32(5, chunks.len() as i32);
+    meta.end_struct();
+    for chunk in chunks {
+        meta.begin_element();
+        meta.i32(1, chunk.kind.physical_type());
+        meta.i32(3, 1); // OPTIONAL
+        meta.string(4, &chunk.name);
+        if chunk.kind == Kind::Text {
+            meta.i32(6, 0); // UTF8
+        }
+        meta.end_struct();
+    }
+
+    meta.i64(3, rows as i64);
+
+    meta.list(4, STRUCT, 1);
+    meta.begin_element();
+    meta.list(1, STRUCT, chunks.len());
+    for chunk in chunks {
+        meta.begin_element();
+        meta.i64(2, chunk.offset);
+        meta.begin_struct(3);
+        meta.i32(1, chunk.kind.physical_type());
+        meta.list(2, I32, 2);
+        meta.varint_i32(PLAIN);
+        meta.varint_i32(RLE);
+        meta.list(3, BINARY, 1);
+        meta.raw_string(&chunk.name);
+        meta.i32(4, 0); // UNCOMPRESSED
+        meta.i64(5, rows as i64);
+        meta.i64(6, chunk.size);
+        meta.i64(7, chunk.size);
+        if let Some(unit) = chunk.unit {
+            meta.list(8, STRUCT, 1);
+            meta.key_value("unit", unit);
+        }
+        meta.i64(9, chunk.offset);
+        meta.end_struct();
+        meta.end_struct();
+    }
+    meta.i64(2, chunks.iter().map(|c| c.size).sum());
+    meta.i64(3, rows as i64);
+    meta.end_struct();
+
+    meta.list(5, STRUCT, metadata.len());
+    for (key, value) in metadata {
+        meta.key_value(key, value);
+    }
+    meta.string(6, concat!("physics-tutorial-api ", env!("CARGO_PKG_VERSION")));
+    meta.stop();
+    meta.bytes
+}
+
+/// Thrift compact protocol type ids
+const I32: u8 = 5;
+const I64: u8 = 6;
+const BINARY: u8 = 8;
+const LIST: u8 = 9;
+const STRUCT: u8 = 12;
+
+/// Minimal Thrift compact protocol writer
+#[derive(Default)]
+struct Compact {
+    bytes: Vec<u8>,
+    /// Last field id written in each open struct
+    last_field: Vec<i16>,
+    current: i16,
+}
+
+impl Compact {
+    fn field(&mut self, id: i16, kind: u8) {
+        let delta = id - self.current;
+        if (1..=15).contains(&delta) {
+            self.bytes.push(((delta as u8) << 4) | kind);
+        } else {
+            self.bytes.push(kind);
+            write_varint(&mut self.bytes, zigzag(id as i64));
+        }
+        self.current = id;
+    }
+
+    fn i32(&mut self, id: i16, value: i32) {
+        self.field(id, I32);
+        self.varint_i32(value);
+    }
+
+    fn i64(&mut self, id: i16, value: i64) {
+        self.field(id, I64);
+        write_varint(&mut self.bytes, zigzag(value));
+    }
+
+    fn string(&mut self, id: i16, value: &str) {
+        self.field(id, BINARY);
+        self.raw_string(value);
+    }
+
+    fn varint_i32(&mut self, value: i32) {
+        write_varint(&mut self.bytes, zigzag(value as i64));
+    }
+
+    fn raw_string(&mut self, value: &str) {
+        write_varint(&mut self.bytes, value.len() as u64);
+        self.bytes.extend_from_slice(value.as_bytes());
+    }
+
+    fn list(&mut self, id: i16, element: u8, len: usize) {
+        self.field(id, LIST);
+        if len < 15 {
+            self.bytes.push(((len as u8) << 4) | element);
+        } else {
+            self.bytes.push(0xF0 | element);
+            write_varint(&mut self.bytes, len as u64);
+        }
+    }
+
+    fn begin_struct(&mut self, id: i16) {
+        self.field(id, STRUCT);
+        self.begin_element();
+    }
+
+    /// Start a struct that is a list element rather than a field
+    fn begin_element(&mut self) {
+        self.last_field.push(self.current);
+        self.current = 0;
+    }
+
+    fn end_struct(&mut self) {
+        self.stop();
+        self.current = self.last_field.pop().unwrap_or(0);
+    }
+
+    fn stop(&mut self) {
+        self.bytes.push(0);
+    }
+
+    fn key_value(&mut self, key: &str, value: &str) {
+        self.begin_element();
+        self.string(1, key);
+        self.string(2, value);
+        self.end_struct();
+    }
+}
+
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::services::export::Column;
+
+    /// A value read back with the Thrift compact protocol
+    #[derive(Debug)]
+    enum Thrift {
+        Int(i64),
+        Binary(Vec<u8>),
+        List(Vec<Thrift>),
+        Struct(Vec<(i16, Thrift)>),
+    }
+
+    impl Thrift {
+        fn field(&self, id: i16) -> &Thrift {
+            match self {
+                Thrift::Struct(fields) => {
+                    &fields.iter().find(|(field, _)| *field == id).unwrap_or_else(|| panic!("no field {id}")).1
+                }
+                other => panic!("{other:?} is not a struct"),
+            }
+        }
+
+        fn has(&self, id: i16) -> bool {
+            matches!(self, Thrift::Struct(fields) if fields.iter().any(|(field, _)| *field == id))
+        }
+
+        fn int(&self) -> i64 {
+            match self {
+                Thrift::Int(value) => *value,
+                other => panic!("{other:?} is not an integer"),
+            }
+        }
+
+        fn text(&self) -> String {
+            match self {
+                Thrift::Binary(bytes) => String::from_utf8_lossy(bytes).to_string(),
+                other => panic!("{other:?} is not binary"),
+            }
+        }
+
+        fn items(&self) -> &[Thrift] {
+            match self {
+                Thrift::List(items) => items,
+                other => panic!("{other:?} is not a list"),
+            }
+        }
+    }
+
+    struct Reader<'a> {
+        bytes: &'a [u8],
+        at: usize,
+    }
+
+    impl Reader<'_> {
+        fn byte(&mut self) -> u8 {
+            self.at += 1;
+            self.bytes[self.at - 1]
+        }
+
+        fn varint(&mut self) -> u64 {
+            let (mut value, mut shift) = (0, 0);
+            loop {
+                let byte = self.byte();
+                value |= u64::from(byte & 0x7f) << shift;
+                if byte < 0x80 {
+                    return value;
+                }
+                shift += 7;
+            }
+        }
+
+        fn int(&mut self) -> i64 {
+            let value = self.varint();
+            (value >> 1) as i64 ^ -((value & 1) as i64)
+        }
+
+        fn value(&mut self, kind: u8) -> Thrift {
+            match kind {
+                I32 | I64 => Thrift::Int(self.int()),
+                BINARY => {
+                    let len = self.varint() as usize;
+                    self.at += len;
+                    Thrift::Binary(self.bytes[self.at - len..self.at].to_vec())
+                }
+                LIST => {
+                    let header = self.byte();
+                    let len = match header >> 4 {
+                        15 => self.varint() as usize,
+                        len => len as usize,
+                    };
+                    Thrift::List((0..len).map(|_| self.value(header & 0x0f)).collect())
+                }
+                STRUCT => self.structure(),
+                other => panic!("unexpected type {other}"),
+            }
+        }
+
+        fn structure(&mut self) -> Thrift {
+            let mut fields = Vec::new();
+            let mut last = 0;
+            loop {
+                let header = self.byte();
+                if header == 0 {
+                    return Thrift::Struct(fields);
+                }
+                let id = match header >> 4 {
+                    0 => self.int() as i16,
+                    delta => last + i16::from(delta),
+                };
+                last = id;
+                fields.push((id, self.value(header & 0x0f)));
+            }
+        }
+    }
+
+    fn column(path: &str, unit: Option<&'static str>, values: Vec<Value>) -> Column {
+        Column {
+            path: path.to_string(),
+            unit,
+            values,
+        }
+    }
+
+    /// Definition levels and values of a data page, decoded as `kind`
+    fn decode_page(kind: Kind, page: &[u8], rows: usize) -> Vec<Value> {
+        let levels_len = u32::from_le_bytes([page[0], page[1], page[2], page[3]]) as usize;
+        let mut levels = Reader {
+            bytes: &page[4..4 + levels_len],
+            at: 0,
+        };
+        let mut defined = Vec::new();
+        while levels.at < levels_len {
+            let header = levels.varint();
+            assert_eq!(header & 1, 0, "only RLE runs are written");
+            let level = levels.byte();
+            defined.extend(std::iter::repeat_n(level == 1, (header >> 1) as usize));
+        }
+        assert_eq!(defined.len(), rows);
+
+        let mut data = &page[4 + levels_len..];
+        let present = defined.iter().filter(|&&d| d).count();
+        let mut values: Vec<Value> = Vec::new();
+        for i in 0..present {
+            values.push(match kind {
+                Kind::Boolean => json!(data[i / 8] >> (i % 8) & 1 == 1),
+                Kind::Int64 | Kind::Double => {
+                    let mut word = [0u8; 8];
+                    word.copy_from_slice(&data[..8]);
+                    data = &data[8..];
+                    match kind {
+                        Kind::Int64 => json!(i64::from_le_bytes(word)),
+                        _ => json!(f64::from_le_bytes(word)),
+                    }
+                }
+                Kind::Text => {
+                    let len = u32::from_le_bytes([data[0], data[1], data[2], data[3]]) as usize;
+                    let text = String::from_utf8_lossy(&data[4..4 + len]).to_string();
+                    data = &data[4 + len..];
+                    json!(text)
+                }
+            });
+        }
+        let mut values = values.into_iter();
+        defined.iter().map(|&d| if d { values.next().unwrap_or(Value::Null) } else { Value::Null }).collect()
+    }
+
+    #[test]
+    fn compact_protocol_bytes() {
+        let mut varint = Vec::new();
+        write_varint(&mut varint, 300);
+        assert_eq!(varint, [0xac, 0x02]);
+        assert_eq!((zigzag(0), zigzag(-1), zigzag(1), zigzag(i64::MIN)), (0, 1, 2, u64::MAX));
+
+        let mut compact = Compact::default();
+        compact.i32(1, 0);
+        compact.i64(3, -2);
+        compact.string(20, "ab");
+        compact.list(21, I32, 20);
+        compact.stop();
+        assert_eq!(
+            compact.bytes,
+            [0x15, 0x00, 0x26, 0x03, 0x08, 0x28, 0x02, b'a', b'b', 0x19, 0xf5, 20, 0x00]
+        );
+    }
+
+    #[test]
+    fn page_bytes() {
+        let page = encode_page(Kind::Int64, &[json!(1), Value::Null, json!(2)]);
+        let mut expected = vec![6, 0, 0, 0, 2, 1, 2, 0, 2, 1];
+        expected.extend_from_slice(&1i64.to_le_bytes());
+        expected.extend_from_slice(&2i64.to_le_bytes());
+        assert_eq!(page, expected);
+
+        let page = encode_page(Kind::Boolean, &[json!(true), json!(false), json!(true)]);
+        assert_eq!(page, [2, 0, 0, 0, 6, 1, 0b101]);
+    }
+
+    #[test]
+    fn column_types() {
+        assert!(Kind::of(&[json!(true), Value::Null]) == Kind::Boolean);
+        assert!(Kind::of(&[json!(1), json!(-2)]) == Kind::Int64);
+        assert!(Kind::of(&[json!(1), json!(2.5)]) == Kind::Double);
+        assert!(Kind::of(&[Value::Null]) == Kind::Double);
+        assert!(Kind::of(&[json!(1), json!("a")]) == Kind::Text);
+    }
+
+    #[test]
+    fn round_trip() {
+        let table = Table {
+            columns: vec![
+                column("t", Some("s"), vec![json!(0.0), json!(0.5), json!(1.0), json!(1.5)]),
+                column("n", None, vec![json!(3), Value::Null, Value::Null, json!(-7)]),
+                column("detected", None, vec![json!(true), json!(false), Value::Null, json!(true)]),
+                column("label", None, vec![json!("a"), json!("βeta"), json!([1, 2]), Value::Null]),
+            ],
+        };
+        let file = encode_parquet(&table, &[("simulation_id", "double-slit".to_string())]);
+        assert_eq!(&file[..4], MAGIC);
+        assert_eq!(&file[file.len() - 4..], MAGIC);
+        let footer_len =
+            u32::from_le_bytes(file[file.len() - 8..file.len() - 4].try_into().unwrap_or_default()) as usize;
+        let footer_start = file.len() - 8 - footer_len;
+        let mut reader = Reader {
+            bytes: &file[footer_start..file.len() - 8],
+            at: 0,
+        };
+        let meta = reader.structure();
+        assert_eq!(reader.at, footer_len);
+
+        assert_eq!(meta.field(1).int(), 1);
+        assert_eq!(meta.field(3).int(), 4);
+        let schema = meta.field(2).items();
+        assert_eq!(schema[0].field(4).text(), "schema");
+        assert_eq!(schema[0].field(5).int(), 4);
+        let names: Vec<String> = schema[1..].iter().map(|element| element.field(4).text()).collect();
+        assert_eq!(names, ["t", "n", "detected", "label"]);
+        let types: Vec<i64> = schema[1..].iter().map(|element| element.field(1).int()).collect();
+        assert_eq!(types, [5, 2, 0, 6]);
+        assert!(schema[1..].iter().all(|element| element.field(3).int() == 1));
+        assert_eq!(schema[4].field(6).int(), 0);
+
+        let pairs = meta.field(5).items();
+        assert_eq!(pairs[0].field(1).text(), "simulation_id");
+        assert_eq!(pairs[0].field(2).text(), "double-slit");
+
+        let row_groups = meta.field(4).items();
+        assert_eq!(row_groups.len(), 1);
+        assert_eq!(row_groups[0].field(3).int(), 4);
+        let chunks = row_groups[0].field(1).items();
+        let mut end = MAGIC.len();
+        for (chunk, column) in chunks.iter().zip(&table.columns) {
+            let offset = chunk.field(2).int() as usize;
+            assert_eq!(offset, end, "chunks are contiguous");
+            let info = chunk.field(3);
+            assert_eq!(info.field(3).items()[0].text(), column.path);
+            assert_eq!(info.field(9).int() as usize, offset);
+            assert_eq!(info.field(5).int(), 4);
+            assert_eq!(info.has(8), column.unit.is_some());
+            if column.unit.is_some() {
+                assert_eq!(info.field(8).items()[0].field(2).text(), "s");
+            }
+            let size = info.field(6).int() as usize;
+            end = offset + size;
+
+            let mut header = Reader {
+                bytes: &file[offset..end],
+                at: 0,
+            };
+            let page_header = header.structure();
+            assert_eq!(page_header.field(1).int(), 0);
+            let page_len = page_header.field(3).int() as usize;
+            assert_eq!(header.at + page_len, size);
+            assert_eq!(page_header.field(5).field(1).int(), 4);
+
+            let kind = Kind::of(&column.values);
+            let expected: Vec<Value> = column
+                .values
+                .iter()
+                .map(|v| match (kind, v) {
+                    (Kind::Double, Value::Number(n)) => json!(n.as_f64()),
+                    (Kind::Text, v) if !v.is_null() && !v.is_string() => json!(v.to_string()),
+                    (_, v) => v.clone(),
+                })
+                .collect();
+            assert_eq!(decode_page(kind, &file[offset + header.at..end], 4), expected);
+        }
+        assert_eq!(end, footer_start);
+    }
+}
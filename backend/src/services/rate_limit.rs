@@ -0,0 +1,130 @@
+// Request rate limits
+//
+// Token buckets per client: signed-in users are limited by account,
+// everyone else by IP address. Requests that start a computation draw
+// from a separate, smaller budget than metadata reads, so browsing stays
+// responsive for a client that has used up its compute allowance. Buckets
+// live in process memory; with several instances each enforces its own.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::config::RateLimitConfig;
+
+/// Buckets kept before idle ones are dropped
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+/// Which budget a request draws from
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Budget {
+    /// Catalog, content, results and other reads
+    Read,
+    /// Runs, sweeps, comparisons, self-tests, jobs, replays, rendering, curve
+    /// fits, statistics, Fourier transforms and AI questions
+    Compute,
+}
+
+/// Who a bucket belongs to
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub enum Client {
+    User(String),
+    Ip(IpAddr),
+}
+
+/// Bucket size and refill rate of one budget
+#[derive(Clone, Copy)]
+pub struct Rate {
+    /// Requests allowed at once
+    pub burst: u32,
+    /// Requests regained per minute
+    pub per_minute: u32,
+}
+
+impl Rate {
+    /// A configured rate, with the burst defaulting to `default_burst` or
+    /// the rate a minute, whichever is smaller; `0` per minute turns the
+    /// limit off
+    fn configured(per_minute: u32, burst: Option<u32>, default_burst: u32) -> Option<Self> {
+        (per_minute > 0).then_some(Self {
+            burst: burst.unwrap_or(default_burst.min(per_minute)).max(1),
+            per_minute,
+        })
+    }
+
+    fn refill(&self) -> f64 {
+        self.per_minute as f64 / 60.0
+    }
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+pub struct RateLimiter {
+    read: Option<Rate>,
+    compute: Option<Rate>,
+    /// Whether the client address is the last hop of `X-Forwarded-For`
+    pub trust_forwarded: bool,
+    buckets: Mutex<HashMap<(Client, Budget), Bucket>>,
+}
+
+impl RateLimiter {
+    /// Configured budgets, with reads bursting to 60 and computations to
+    /// 10 unless set otherwise
+    pub fn from_config(config: &RateLimitConfig) -> Self {
+        let read = Rate::configured(config.read_per_minute, config.read_burst, 60);
+        let compute = Rate::configured(config.compute_per_minute, config.compute_burst, 10);
+        Self::new(read, compute, config.trust_forwarded)
+    }
+
+    pub fn new(read: Option<Rate>, compute: Option<Rate>, trust_forwarded: bool) -> Self {
+        Self {
+            read,
+            compute,
+            trust_forwarded,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Take one request from the client's budget, or say how long to wait
+    /// until one is available
+    pub fn check(&self, client: Client, budget: Budget) -> Result<(), Duration> {
+        let Some(rate) = (match budget {
+            Budget::Read => self.read,
+            Budget::Compute => self.compute,
+        }) else {
+            return Ok(());
+        };
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() >= MAX_TRACKED_CLIENTS {
+            // A bucket that has refilled holds nothing worth remembering
+            buckets.retain(|(_, budget), bucket| {
+                let rate = match budget {
+                    Budget::Read => self.read,
+                    Budget::Compute => self.compute,
+                };
+                rate.is_some_and(|rate| {
+                    bucket.tokens + now.duration_since(bucket.updated).as_secs_f64() * rate.refill() < rate.burst as f64
+                })
+            });
+        }
+
+        let bucket = buckets.entry((client, budget)).or_insert(Bucket {
+            tokens: rate.burst as f64,
+            updated: now,
+        });
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate.refill()).min(rate.burst as f64);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / rate.refill()))
+        }
+    }
+}
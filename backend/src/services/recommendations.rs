@@ -0,0 +1,269 @@
+// What a learner should study next
+//
+// A rules-based engine over a learner's stored progress and quiz scores. A
+// simulation is mastered once its best quiz score reaches `MASTERY_SCORE`,
+// or once it is completed if it has no quiz, and a simulation is ready once
+// all of its prerequisites are mastered. The learner's level is the first
+// difficulty at which they have not yet mastered `LEVEL_UP` simulations (or
+// all of them, where there are fewer). Strategies turn that picture into a
+// ranked list of suggestions; each is a `Strategy` in `STRATEGIES`, picked
+// per request, so another way of choosing is one more implementation.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde::Serialize;
+
+use crate::models::progress::{QuizSummary, SimulationProgress};
+use crate::models::simulation::DIFFICULTIES;
+use crate::services::curriculum::learning_order;
+use crate::simulations::{Simulation, SimulationRegistry};
+
+/// Best quiz score (percent) at which a simulation counts as mastered
+pub const MASTERY_SCORE: f64 = 80.0;
+/// Simulations to master at a difficulty before moving up a level
+const LEVEL_UP: usize = 3;
+
+/// Every registered strategy; the first is the default
+pub static STRATEGIES: [&dyn Strategy; 3] = [&Mastery, &Review, &Challenge];
+
+/// The strategy called `name`
+pub fn strategy(name: &str) -> Option<&'static dyn Strategy> {
+    STRATEGIES.iter().copied().find(|strategy| strategy.name() == name)
+}
+
+/// A way of choosing what to suggest
+pub trait Strategy: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Suggestions for the learner, best first
+    fn recommend(&self, learner: &Learner) -> Vec<Recommendation>;
+}
+
+#[derive(Serialize)]
+pub struct Recommendation {
+    pub simulation_id: &'static str,
+    pub name: String,
+    pub difficulty: String,
+    /// Why it is suggested, for showing to the learner
+    pub reason: String,
+    /// Best quiz score so far
+    pub quiz_score: Option<f64>,
+}
+
+/// What one learner has done on one simulation they started
+#[derive(Default)]
+struct Record {
+    completed: bool,
+    quiz_score: Option<f64>,
+}
+
+/// A learner's standing across the catalog
+pub struct Learner<'a> {
+    registry: &'a SimulationRegistry,
+    records: HashMap<String, Record>,
+    /// Every simulation in learning order, prerequisites first
+    order: Vec<&'static str>,
+    /// Index into `DIFFICULTIES`
+    level: usize,
+}
+
+impl<'a> Learner<'a> {
+    pub fn new(registry: &'a SimulationRegistry, progress: &[SimulationProgress], quizzes: &[QuizSummary]) -> Self {
+        let mut records: HashMap<String, Record> = HashMap::new();
+        for entry in progress {
+            records.entry(entry.simulation_id.clone()).or_default().completed = entry.completed;
+        }
+        for quiz in quizzes {
+            records.entry(quiz.simulation_id.clone()).or_default().quiz_score = Some(quiz.best_score);
+        }
+        let mut learner = Self {
+            registry,
+            records,
+            order: learning_order(registry, None),
+            level: 0,
+        };
+        learner.level = learner.find_level();
+        learner
+    }
+
+    /// The difficulty the learner is working at
+    pub fn level(&self) -> &'static str {
+        DIFFICULTIES[self.level]
+    }
+
+    /// IDs of the simulations the learner has mastered, in learning order
+    pub fn mastered(&self) -> Vec<&'static str> {
+        self.simulations().filter(|simulation| self.is_mastered(simulation.as_ref())).map(|s| s.id()).collect()
+    }
+
+    fn simulations(&self) -> impl Iterator<Item = Arc<dyn Simulation>> + '_ {
+        self.order.iter().filter_map(|id| self.registry.get(id))
+    }
+
+    fn record(&self, id: &str) -> Option<&Record> {
+        self.records.get(id)
+    }
+
+    fn is_mastered(&self, simulation: &dyn Simulation) -> bool {
+        let Some(record) = self.record(simulation.id()) else {
+            return false;
+        };
+        match simulation.quiz() {
+            Some(_) => record.quiz_score.is_some_and(|score| score >= MASTERY_SCORE),
+            None => record.completed,
+        }
+    }
+
+    /// Whether every registered prerequisite of a simulation is mastered
+    fn is_ready(&self, simulation: &dyn Simulation) -> bool {
+        simulation
+            .prerequisites()
+            .iter()
+            .filter_map(|id| self.registry.get(id))
+            .all(|prerequisite| self.is_mastered(prerequisite.as_ref()))
+    }
+
+    fn difficulty(simulation: &dyn Simulation) -> usize {
+        let difficulty = simulation.info().difficulty;
+        DIFFICULTIES.iter().position(|d| *d == difficulty).unwrap_or(0)
+    }
+
+    fn find_level(&self) -> usize {
+        for level in 0..DIFFICULTIES.len() {
+            let at_level: Vec<_> = self.simulations().filter(|s| Self::difficulty(s.as_ref()) == level).collect();
+            let mastered = at_level.iter().filter(|s| self.is_mastered(s.as_ref())).count();
+            if mastered < LEVEL_UP.min(at_level.len()) {
+                return level;
+            }
+        }
+        DIFFICULTIES.len() - 1
+    }
+
+    fn recommendation(&self, simulation: &dyn Simulation, reason: String) -> Recommendation {
+        let info = simulation.info();
+        Recommendation {
+            simulation_id: simulation.id(),
+            name: info.name,
+            difficulty: info.difficulty,
+            reason,
+            quiz_score: self.record(simulation.id()).and_then(|record| record.quiz_score),
+        }
+    }
+}
+
+/// The next step up: simulations whose prerequisites are mastered, at or
+/// below the learner's level. What they started comes first, then the
+/// prerequisites of what they started but are not ready for, then the rest
+/// at their level, each in learning order.
+pub struct Mastery;
+
+impl Strategy for Mastery {
+    fn name(&self) -> &'static str {
+        "mastery"
+    }
+
+    fn recommend(&self, learner: &Learner) -> Vec<Recommendation> {
+        // Unmastered prerequisites of started simulations, with the name of
+        // one that needs them
+        let mut needed: HashMap<&'static str, String> = HashMap::new();
+        for simulation in learner.simulations() {
+            if learner.record(simulation.id()).is_some() && !learner.is_ready(simulation.as_ref()) {
+                for prerequisite in simulation.prerequisites().iter().filter_map(|id| learner.registry.get(id)) {
+                    if !learner.is_mastered(prerequisite.as_ref()) {
+                        needed.entry(prerequisite.id()).or_insert_with(|| simulation.info().name);
+                    }
+                }
+            }
+        }
+
+        let mut candidates: Vec<(u8, usize, usize, Recommendation)> = learner
+            .simulations()
+            .enumerate()
+            .filter(|(_, s)| {
+                let s = s.as_ref();
+                !learner.is_mastered(s) && learner.is_ready(s) && Learner::difficulty(s) <= learner.level
+            })
+            .map(|(position, simulation)| {
+                let simulation = simulation.as_ref();
+                let (rank, reason) = match (learner.record(simulation.id()), needed.get(simulation.id())) {
+                    (Some(Record { quiz_score: Some(score), .. }), _) => {
+                        (0, format!("Your best quiz score is {score:.0}%; reach {MASTERY_SCORE:.0}% to master it"))
+                    }
+                    (Some(_), _) => (0, "You started this simulation".to_string()),
+                    (None, Some(name)) => (1, format!("Master it first to get the most out of {name}, which you started")),
+                    (None, None) if simulation.prerequisites().is_empty() => {
+                        (2, "A starting point at your level".to_string())
+                    }
+                    (None, None) => (2, "You have mastered its prerequisites".to_string()),
+                };
+                let distance = learner.level - Learner::difficulty(simulation);
+                (rank, distance, position, learner.recommendation(simulation, reason))
+            })
+            .collect();
+        candidates.sort_by_key(|(rank, distance, position, _)| (*rank, *distance, *position));
+        candidates.into_iter().map(|(.., recommendation)| recommendation).collect()
+    }
+}
+
+/// Revision: quizzes taken but not yet passed, lowest score first, then
+/// completed simulations whose quiz was never taken
+pub struct Review;
+
+impl Strategy for Review {
+    fn name(&self) -> &'static str {
+        "review"
+    }
+
+    fn recommend(&self, learner: &Learner) -> Vec<Recommendation> {
+        let mut weak: Vec<(f64, Recommendation)> = Vec::new();
+        let mut untested = Vec::new();
+        for simulation in learner.simulations() {
+            let simulation = simulation.as_ref();
+            let (Some(record), Some(_)) = (learner.record(simulation.id()), simulation.quiz()) else {
+                continue;
+            };
+            match record.quiz_score {
+                Some(score) if score < MASTERY_SCORE => {
+                    let reason = format!("Your best quiz score is {score:.0}%, below the {MASTERY_SCORE:.0}% mastery mark");
+                    weak.push((score, learner.recommendation(simulation, reason)));
+                }
+                None if record.completed => {
+                    let reason = "You completed it but have not taken its quiz".to_string();
+                    untested.push(learner.recommendation(simulation, reason));
+                }
+                _ => {}
+            }
+        }
+        weak.sort_by(|a, b| a.0.total_cmp(&b.0));
+        weak.into_iter().map(|(_, recommendation)| recommendation).chain(untested).collect()
+    }
+}
+
+/// A stretch: simulations above the learner's level whose prerequisites
+/// are already mastered, the nearest level first
+pub struct Challenge;
+
+impl Strategy for Challenge {
+    fn name(&self) -> &'static str {
+        "challenge"
+    }
+
+    fn recommend(&self, learner: &Learner) -> Vec<Recommendation> {
+        let mut candidates: Vec<(usize, usize, Recommendation)> = learner
+            .simulations()
+            .enumerate()
+            .filter(|(_, s)| {
+                let s = s.as_ref();
+                !learner.is_mastered(s) && learner.is_ready(s) && Learner::difficulty(s) > learner.level
+            })
+            .map(|(position, simulation)| {
+                let simulation = simulation.as_ref();
+                let reason = format!("A step above your {} level, and you have its prerequisites", learner.level());
+                (Learner::difficulty(simulation), position, learner.recommendation(simulation, reason))
+            })
+            .collect();
+        candidates.sort_by_key(|(difficulty, position, _)| (*difficulty, *position));
+        candidates.into_iter().map(|(.., recommendation)| recommendation).collect()
+    }
+}
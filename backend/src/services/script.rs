@@ -0,0 +1,106 @@
+// Scripts of user-defined simulations
+//
+// A script computes f(parameters, x) in a few lines of the expression
+// language: assignments `name = expression` that name intermediate
+// quantities, then one last expression whose value is f. Lines may also be
+// separated by `;`, and `#` starts a comment. There are no loops, no
+// recursion and nothing outside the script's own variables to reach, so
+// the interpreter is its own sandbox: one evaluation costs at most a fixed
+// number of operations set by the length limits, and what a run holds in
+// memory is its points. The run itself is held to a wall-time deadline.
+
+use std::collections::BTreeMap;
+
+use super::expression::{is_constant, Expression};
+
+/// Longest script accepted, in bytes
+pub const MAX_SCRIPT_LENGTH: usize = 4_000;
+/// Most lines with an expression, the last one included
+pub const MAX_STATEMENTS: usize = 40;
+/// The variable f is sampled along
+pub const VARIABLE: &str = "x";
+
+/// A parsed script
+#[derive(Clone, Debug)]
+pub struct Script {
+    /// Intermediate quantities in the order they are computed
+    assignments: Vec<(String, Expression)>,
+    result: Expression,
+}
+
+impl Script {
+    /// Parse a script that may read `x` and the variables `parameters`, or
+    /// say what is wrong with it and on which line
+    pub fn parse(text: &str, parameters: &[&str]) -> Result<Self, String> {
+        if text.len() > MAX_SCRIPT_LENGTH {
+            return Err(format!("is longer than {MAX_SCRIPT_LENGTH} characters"));
+        }
+        let statements: Vec<(usize, &str)> = text
+            .lines()
+            .enumerate()
+            .flat_map(|(number, line)| {
+                let code = line.split_once('#').map_or(line, |(code, _)| code);
+                code.split(';').map(move |statement| (number + 1, statement.trim()))
+            })
+            .filter(|(_, statement)| !statement.is_empty())
+            .collect();
+        if statements.len() > MAX_STATEMENTS {
+            return Err(format!("has more than {MAX_STATEMENTS} statements"));
+        }
+        let Some((&(last_line, last), assignments)) = statements.split_last() else {
+            return Err("is empty; it needs an expression giving f".to_string());
+        };
+
+        let mut known: Vec<&str> = parameters.to_vec();
+        known.push(VARIABLE);
+        let mut parsed = Vec::with_capacity(assignments.len());
+        for &(line, statement) in assignments {
+            let Some((name, formula)) = statement.split_once('=') else {
+                return Err(format!("line {line}: only the last statement may be a bare expression"));
+            };
+            let name = name.trim();
+            if !is_identifier(name) {
+                return Err(format!("line {line}: `{name}` is not a variable name"));
+            }
+            if known.contains(&name) || is_constant(name) {
+                return Err(format!("line {line}: `{name}` is already defined"));
+            }
+            let expression = checked(formula, &known).map_err(|message| format!("line {line}: {message}"))?;
+            parsed.push((name.to_string(), expression));
+            known.push(name);
+        }
+        if last.contains('=') {
+            return Err(format!("line {last_line}: the last statement must be the expression giving f"));
+        }
+        let result = checked(last, &known).map_err(|message| format!("line {last_line}: {message}"))?;
+        Ok(Self {
+            assignments: parsed,
+            result,
+        })
+    }
+
+    /// Value of f for the parameters and `x` in `values`, which also
+    /// receives the intermediate quantities
+    pub fn eval(&self, values: &mut BTreeMap<String, f64>) -> f64 {
+        for (name, expression) in &self.assignments {
+            let value = expression.eval(values);
+            values.insert(name.clone(), value);
+        }
+        self.result.eval(values)
+    }
+}
+
+/// `formula` parsed, using no variables but `known` and the constants
+fn checked(formula: &str, known: &[&str]) -> Result<Expression, String> {
+    let expression = Expression::parse(formula.trim())?;
+    if let Some(name) = expression.variables().into_iter().find(|name| !known.contains(name) && !is_constant(name)) {
+        return Err(format!("`{name}` is neither a parameter, `{VARIABLE}` nor defined on an earlier line"));
+    }
+    Ok(expression)
+}
+
+/// A name the expression language reads as a single variable
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_') && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
@@ -0,0 +1,140 @@
+// Full-text search over the catalog
+//
+// Simulation names, topics, descriptions and theory, and the glossary of key
+// concepts the theory sections define, are indexed in an SQLite FTS5 table
+// (`models::search`) with the Porter stemmer, so "oscillating" also finds
+// "oscillation" and "oscillators". Matches are ranked by BM25 with the title
+// weighted most and the theory text least. The index is derived data: it is
+// rebuilt from the catalog on the first search after anything served changes
+// (a reload, published content, a retired simulation), so nothing has to
+// keep it in step. Only the English text is indexed.
+
+use tokio::sync::Mutex;
+
+use crate::models::search::{self, SearchDocument};
+use crate::services::markdown;
+use crate::simulations::SimulationRegistry;
+
+pub const SIMULATION: &str = "simulation";
+pub const GLOSSARY: &str = "glossary";
+pub const KINDS: [&str; 2] = [SIMULATION, GLOSSARY];
+/// Most words of a query that are searched for
+const MAX_WORDS: usize = 20;
+
+/// Tracks which version of the catalog the index holds
+#[derive(Default)]
+pub struct SearchIndex {
+    /// Catalog generation last indexed, `None` before the first build
+    indexed: Mutex<Option<u64>>,
+}
+
+impl SearchIndex {
+    /// Rebuild the index if the catalog changed since it was built
+    pub async fn refresh(&self, pool: &sqlx::SqlitePool, simulations: &SimulationRegistry) -> Result<(), sqlx::Error> {
+        let mut indexed = self.indexed.lock().await;
+        let generation = simulations.catalog().generation();
+        if *indexed == Some(generation) {
+            return Ok(());
+        }
+        let documents = documents(simulations);
+        search::replace_all(pool, &documents).await?;
+        *indexed = Some(generation);
+        tracing::info!("Search index rebuilt with {} entries", documents.len());
+        Ok(())
+    }
+}
+
+/// A term and what it means, from a theory section's key concepts
+pub struct GlossaryEntry {
+    pub term: String,
+    pub definition: String,
+}
+
+/// Key concepts a theory section defines: list items that open with a
+/// term in bold, as in `1. **Planck's law**: Planck explained …`. The
+/// definition is the text after the colon, or the whole item when the term
+/// starts a sentence.
+pub fn glossary(theory: &str) -> Vec<GlossaryEntry> {
+    theory
+        .lines()
+        .filter_map(|line| {
+            let item = list_item(line.trim_start())?;
+            let (term, rest) = item.strip_prefix("**")?.split_once("**")?;
+            let definition = match rest.trim_start().strip_prefix(':') {
+                Some(definition) => definition,
+                None => item,
+            };
+            let (term, definition) = (markdown::to_text(term), markdown::to_text(definition));
+            let (term, definition) = (term.trim(), definition.trim());
+            (!term.is_empty() && !definition.is_empty()).then(|| GlossaryEntry {
+                term: term.to_string(),
+                definition: definition.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Text of a bullet or numbered list item
+fn list_item(line: &str) -> Option<&str> {
+    let rest = match line.strip_prefix(['-', '*', '+']) {
+        Some(rest) => rest,
+        None => {
+            let digits = line.chars().take_while(char::is_ascii_digit).count();
+            line[digits..].strip_prefix(['.', ')']).filter(|_| digits > 0)?
+        }
+    };
+    rest.strip_prefix(' ').map(str::trim_start)
+}
+
+/// FTS5 expression for the words of `query`: all of them, or any of them
+/// when `any` is set. The last word also matches as a prefix, for search as
+/// the user types. Words are quoted, so FTS5 syntax in the query is taken
+/// as text; `None` when the query has no words.
+pub fn match_expression(query: &str, any: bool) -> Option<String> {
+    let mut words: Vec<String> = Vec::new();
+    for word in query.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()).map(str::to_lowercase) {
+        if !words.contains(&word) && words.len() < MAX_WORDS {
+            words.push(word);
+        }
+    }
+    let last = words.pop()?;
+    let mut terms: Vec<String> = words.iter().map(|word| format!("\"{word}\"")).collect();
+    terms.push(format!("\"{last}\"*"));
+    Some(terms.join(if any { " OR " } else { " " }))
+}
+
+/// Snippet from the index as HTML on one line: escaped, with the matched
+/// words in `<mark>`
+pub fn highlight(snippet: &str) -> String {
+    let line = snippet.split_whitespace().collect::<Vec<_>>().join(" ");
+    markdown::escape_html(&line).replace('\u{2}', "<mark>").replace('\u{3}', "</mark>")
+}
+
+/// Index entries for every simulation being served and its glossary
+fn documents(simulations: &SimulationRegistry) -> Vec<SearchDocument> {
+    let mut documents = Vec::new();
+    for simulation in simulations.iter() {
+        let Some(content) = simulations.catalog().get(simulation.id()) else {
+            continue;
+        };
+        documents.push(SearchDocument {
+            kind: SIMULATION,
+            simulation_id: simulation.id().to_string(),
+            title: content.name.clone(),
+            topics: content.topics.join(", "),
+            description: format!("{}\n{}", content.summary, content.description),
+            body: markdown::to_text(&content.theory),
+        });
+        for entry in glossary(&content.theory) {
+            documents.push(SearchDocument {
+                kind: GLOSSARY,
+                simulation_id: simulation.id().to_string(),
+                title: entry.term,
+                topics: String::new(),
+                description: entry.definition,
+                body: String::new(),
+            });
+        }
+    }
+    documents
+}
@@ -0,0 +1,108 @@
+// Amplitude and power spectra of sampled signals
+//
+// A real signal of n samples taken at rate fₛ is windowed and transformed,
+// and the coefficients from zero to the Nyquist frequency fₛ/2 are scaled
+// into a one-sided spectrum. Amplitudes are divided by the window's
+// coherent gain Σw/n, so a sinusoid of amplitude A centred on a bin reads
+// A whichever window is used; the power spectral density is divided by
+// fₛ Σw², so that integrating it over frequency gives the signal's mean
+// square.
+
+use serde::Serialize;
+
+use crate::numerics::fft::{real_forward, real_frequencies, Window};
+
+#[derive(Serialize)]
+pub struct Spectrum {
+    pub samples: usize,
+    /// Samples per unit time
+    pub sample_rate: f64,
+    pub window: &'static str,
+    /// Spacing of the frequencies, fₛ/n
+    pub resolution: f64,
+    /// Highest frequency the samples can represent, fₛ/2
+    pub nyquist: f64,
+    pub frequencies: Vec<f64>,
+    /// One-sided amplitude of each frequency, in the signal's units
+    pub amplitude: Vec<f64>,
+    /// Phase of each frequency relative to a cosine starting at the first
+    /// sample (rad)
+    pub phase: Vec<f64>,
+    /// One-sided power spectral density, signal units² per unit frequency
+    pub power_density: Vec<f64>,
+    /// Strongest frequency above zero; absent for a constant signal
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub peak: Option<Peak>,
+}
+
+#[derive(Serialize)]
+pub struct Peak {
+    /// Frequency interpolated between the strongest bin and its neighbours
+    pub frequency: f64,
+    pub amplitude: f64,
+}
+
+/// Spectrum of `signal`, sampled `sample_rate` times per unit time, after
+/// applying `window`
+pub fn analyze(signal: &[f64], sample_rate: f64, window: Window) -> Spectrum {
+    let n = signal.len();
+    let weights = window.weights(n);
+    let gain = weights.iter().sum::<f64>() / n as f64;
+    let energy: f64 = weights.iter().map(|w| w * w).sum();
+    let coefficients = real_forward(&window.apply(signal));
+
+    // Bins other than zero and (for even n) Nyquist stand for a pair of
+    // frequencies ±f and are doubled
+    let doubled = |k: usize| k != 0 && 2 * k != n;
+    let mut amplitude = Vec::with_capacity(coefficients.len());
+    let mut phase = Vec::with_capacity(coefficients.len());
+    let mut power_density = Vec::with_capacity(coefficients.len());
+    for (k, c) in coefficients.iter().enumerate() {
+        let factor = if doubled(k) { 2.0 } else { 1.0 };
+        amplitude.push(factor * c.abs() / (n as f64 * gain));
+        phase.push(c.im.atan2(c.re));
+        power_density.push(factor * c.norm_sqr() / (sample_rate * energy));
+    }
+
+    let resolution = sample_rate / n as f64;
+    let peak = strongest(&amplitude).map(|k| Peak {
+        frequency: (k as f64 + interpolate(&amplitude, k)) * resolution,
+        amplitude: amplitude[k],
+    });
+    Spectrum {
+        samples: n,
+        sample_rate,
+        window: window.name(),
+        resolution,
+        nyquist: sample_rate / 2.0,
+        frequencies: real_frequencies(n, sample_rate),
+        amplitude,
+        phase,
+        power_density,
+        peak,
+    }
+}
+
+/// Index of the largest amplitude above zero frequency, if any is non-zero
+fn strongest(amplitude: &[f64]) -> Option<usize> {
+    let (k, &largest) = amplitude
+        .iter()
+        .enumerate()
+        .skip(1)
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))?;
+    (largest > 0.0).then_some(k)
+}
+
+/// Offset of a peak from bin `k`, in bins, by the vertex of the parabola
+/// through it and its neighbours
+fn interpolate(amplitude: &[f64], k: usize) -> f64 {
+    let (Some(&left), Some(&right)) = (amplitude.get(k - 1), amplitude.get(k + 1)) else {
+        return 0.0;
+    };
+    let centre = amplitude[k];
+    let curvature = left - 2.0 * centre + right;
+    if curvature >= 0.0 {
+        return 0.0;
+    }
+    (0.5 * (left - right) / curvature).clamp(-0.5, 0.5)
+}
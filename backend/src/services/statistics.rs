@@ -0,0 +1,186 @@
+// Descriptive statistics and error propagation for lab measurements
+//
+// Each array of repeated measurements is summarized by its mean, sample
+// standard deviation, standard error of the mean and a histogram. A formula
+// over the measured quantities, and over others given directly as a value
+// with an uncertainty, is propagated to first order: the uncertainty of
+// f(x₁, …, xₙ) is √Σ(∂f/∂xᵢ · σᵢ)² for independent xᵢ, each measured
+// quantity entering as its mean with the standard error as σ.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::services::expression::Expression;
+
+/// Fraction of a quantity's uncertainty used as the step of the numerical
+/// partial derivatives
+const DERIVATIVE_STEP: f64 = 1e-3;
+
+/// A value known to within an uncertainty, e.g. a constant or a reading
+/// taken once
+#[derive(Clone, Copy, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Quantity {
+    pub value: f64,
+    /// One standard deviation
+    #[serde(default)]
+    pub uncertainty: f64,
+}
+
+/// Summary of one array of measurements
+#[derive(Serialize)]
+pub struct Summary {
+    pub count: usize,
+    pub mean: f64,
+    pub median: f64,
+    /// Sample standard deviation, with n − 1 in the denominator; absent for
+    /// a single measurement
+    pub standard_deviation: Option<f64>,
+    /// Standard deviation of the mean, s/√n
+    pub standard_error: Option<f64>,
+    pub min: f64,
+    pub max: f64,
+    pub histogram: Histogram,
+}
+
+/// Counts in equal-width bins between the smallest and largest value
+#[derive(Serialize)]
+pub struct Histogram {
+    /// Bin boundaries, one more than the counts; the last bin includes its
+    /// upper edge
+    pub edges: Vec<f64>,
+    pub counts: Vec<usize>,
+}
+
+/// A formula's value with its propagated uncertainty
+#[derive(Serialize)]
+pub struct Propagation {
+    pub expression: String,
+    pub value: f64,
+    pub uncertainty: f64,
+    /// Uncertainty over |value|; absent when the value is zero
+    pub relative_uncertainty: Option<f64>,
+    /// What each quantity adds to the uncertainty, largest first
+    pub contributions: Vec<Contribution>,
+}
+
+/// One quantity's share of a propagated uncertainty
+#[derive(Serialize)]
+pub struct Contribution {
+    pub name: String,
+    pub value: f64,
+    pub uncertainty: f64,
+    /// ∂f/∂x at the values used
+    pub partial_derivative: f64,
+    /// |∂f/∂x| σ, this quantity's part of the uncertainty on its own
+    pub contribution: f64,
+    /// Fraction of the variance of f it accounts for
+    pub variance_fraction: f64,
+}
+
+impl Summary {
+    /// Summary of a non-empty array, its histogram in `bins` bins
+    pub fn of(values: &[f64], bins: usize) -> Self {
+        let count = values.len();
+        let mean = values.iter().sum::<f64>() / count as f64;
+        let mut sorted = values.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        let median = match count % 2 {
+            1 => sorted[count / 2],
+            _ => (sorted[count / 2 - 1] + sorted[count / 2]) / 2.0,
+        };
+        let standard_deviation = (count > 1)
+            .then(|| (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (count - 1) as f64).sqrt());
+        Self {
+            count,
+            mean,
+            median,
+            standard_deviation,
+            standard_error: standard_deviation.map(|s| s / (count as f64).sqrt()),
+            min: sorted[0],
+            max: sorted[count - 1],
+            histogram: Histogram::of(&sorted, bins),
+        }
+    }
+
+    /// The mean as a quantity, with the standard error as its uncertainty
+    pub fn quantity(&self) -> Quantity {
+        Quantity {
+            value: self.mean,
+            uncertainty: self.standard_error.unwrap_or(0.0),
+        }
+    }
+}
+
+impl Histogram {
+    fn of(sorted: &[f64], bins: usize) -> Self {
+        let (low, high) = (sorted[0], sorted[sorted.len() - 1]);
+        // All values equal: one unit-wide range centred on them
+        let (low, high) = if high > low { (low, high) } else { (low - 0.5, high + 0.5) };
+        let width = (high - low) / bins as f64;
+        let edges = (0..=bins).map(|i| low + width * i as f64).collect();
+        let mut counts = vec![0; bins];
+        for value in sorted {
+            let bin = (((value - low) / width) as usize).min(bins - 1);
+            counts[bin] += 1;
+        }
+        Self { edges, counts }
+    }
+}
+
+/// Sturges' rule, ⌈log₂ n⌉ + 1 bins for n values
+pub fn default_bins(count: usize) -> usize {
+    (count.max(1) as f64).log2().ceil() as usize + 1
+}
+
+/// Propagate the uncertainties of `quantities` through `expression`, which
+/// must use no other variables
+pub fn propagate(text: &str, expression: &Expression, quantities: &BTreeMap<String, Quantity>) -> Propagation {
+    let mut values: BTreeMap<String, f64> =
+        quantities.iter().map(|(name, quantity)| (name.clone(), quantity.value)).collect();
+    let value = expression.eval(&values);
+
+    let mut contributions: Vec<Contribution> = expression
+        .variables()
+        .into_iter()
+        .filter_map(|name| quantities.get(name).map(|quantity| (name, quantity)))
+        .map(|(name, quantity)| {
+            let partial_derivative = if quantity.uncertainty > 0.0 {
+                let h = DERIVATIVE_STEP * quantity.uncertainty;
+                values.insert(name.to_string(), quantity.value + h);
+                let above = expression.eval(&values);
+                values.insert(name.to_string(), quantity.value - h);
+                let below = expression.eval(&values);
+                values.insert(name.to_string(), quantity.value);
+                (above - below) / (2.0 * h)
+            } else {
+                0.0
+            };
+            Contribution {
+                name: name.to_string(),
+                value: quantity.value,
+                uncertainty: quantity.uncertainty,
+                partial_derivative,
+                contribution: (partial_derivative * quantity.uncertainty).abs(),
+                variance_fraction: 0.0,
+            }
+        })
+        .collect();
+
+    let variance: f64 = contributions.iter().map(|c| c.contribution * c.contribution).sum();
+    for contribution in &mut contributions {
+        if variance > 0.0 {
+            contribution.variance_fraction = contribution.contribution * contribution.contribution / variance;
+        }
+    }
+    contributions.sort_by(|a, b| b.contribution.total_cmp(&a.contribution));
+    let uncertainty = variance.sqrt();
+    Propagation {
+        expression: text.to_string(),
+        value,
+        uncertainty,
+        relative_uncertainty: (value != 0.0).then(|| uncertainty / value.abs()),
+        contributions,
+    }
+}
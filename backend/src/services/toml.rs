@@ -0,0 +1,635 @@
+// Minimal TOML reader for content files
+//
+// Covers the parts of TOML 1.0 that content authors use: comments, tables,
+// arrays of tables, dotted keys, all four kinds of string, integers, floats,
+// booleans, arrays and inline tables. Dates and times are rejected. The
+// document is read into a JSON value so it deserializes with serde like a
+// request body.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use serde_json::{Map, Number, Value};
+
+/// Syntax error with the line it was found on (1-based)
+#[derive(Debug)]
+pub struct TomlError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for TomlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// Parse a TOML document into a JSON object
+pub fn parse(input: &str) -> Result<Value, TomlError> {
+    let mut parser = Parser {
+        chars: input.chars().collect(),
+        pos: 0,
+    };
+    let mut root = Map::new();
+    // Path of the table that key/value pairs go into
+    let mut current: Vec<String> = Vec::new();
+    let mut defined: HashSet<Vec<String>> = HashSet::new();
+
+    loop {
+        parser.skip_blank_lines();
+        let Some(c) = parser.peek() else { break };
+        if c == '[' {
+            parser.pos += 1;
+            let array = parser.eat('[');
+            parser.skip_space();
+            let path = parser.key()?;
+            parser.skip_space();
+            parser.expect(']')?;
+            if array {
+                parser.expect(']')?;
+                push_table(&mut root, &path).map_err(|message| parser.error(message))?;
+            } else {
+                if !defined.insert(path.clone()) {
+                    return Err(parser.error(format!("table [{}] is defined twice", path.join("."))));
+                }
+                table_at(&mut root, &path).map_err(|message| parser.error(message))?;
+            }
+            current = path;
+        } else {
+            let key = parser.key()?;
+            parser.skip_space();
+            parser.expect('=')?;
+            parser.skip_space();
+            let value = parser.value()?;
+            let table = table_at(&mut root, &current).map_err(|message| parser.error(message))?;
+            insert(table, &key, value).map_err(|message| parser.error(message))?;
+        }
+        parser.end_of_line()?;
+    }
+    Ok(Value::Object(root))
+}
+
+/// The table at `path`, created if needed. A path through an array of
+/// tables continues in its last element.
+fn table_at<'a>(root: &'a mut Map<String, Value>, path: &[String]) -> Result<&'a mut Map<String, Value>, String> {
+    let mut table = root;
+    for key in path {
+        let value = table.entry(key.clone()).or_insert_with(|| Value::Object(Map::new()));
+        let value = match value {
+            Value::Array(items) => items.last_mut().ok_or_else(|| format!("`{key}` is not a table"))?,
+            value => value,
+        };
+        table = value.as_object_mut().ok_or_else(|| format!("`{key}` is not a table"))?;
+    }
+    Ok(table)
+}
+
+/// Start a new element of the array of tables at `path`
+fn push_table(root: &mut Map<String, Value>, path: &[String]) -> Result<(), String> {
+    let (last, parent) = path.split_last().ok_or("empty table name")?;
+    let parent = table_at(root, parent)?;
+    match parent.entry(last.clone()).or_insert_with(|| Value::Array(Vec::new())) {
+        Value::Array(items) => {
+            items.push(Value::Object(Map::new()));
+            Ok(())
+        }
+        _ => Err(format!("`{last}` is not an array of tables")),
+    }
+}
+
+/// Set a dotted key in `table`, refusing to overwrite
+fn insert(table: &mut Map<String, Value>, key: &[String], value: Value) -> Result<(), String> {
+    let (last, parents) = key.split_last().ok_or("empty key")?;
+    let table = table_at(table, parents)?;
+    if table.contains_key(last) {
+        return Err(format!("`{}` is defined twice", key.join(".")));
+    }
+    table.insert(last.clone(), value);
+    Ok(())
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn error(&self, message: impl Into<String>) -> TomlError {
+        let line = 1 + self.chars[..self.pos.min(self.chars.len())].iter().filter(|c| **c == '\n').count();
+        TomlError {
+            line,
+            message: message.into(),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn starts_with(&self, text: &str) -> bool {
+        text.chars().enumerate().all(|(i, c)| self.chars.get(self.pos + i) == Some(&c))
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), TomlError> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected `{c}`")))
+        }
+    }
+
+    fn skip_space(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t')) {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        if self.peek() == Some('#') {
+            while !matches!(self.peek(), None | Some('\n')) {
+                self.pos += 1;
+            }
+        }
+    }
+
+    /// Skip whitespace, newlines and comments
+    fn skip_blank_lines(&mut self) {
+        loop {
+            self.skip_space();
+            self.skip_comment();
+            match self.peek() {
+                Some('\n') => self.pos += 1,
+                Some('\r') if self.chars.get(self.pos + 1) == Some(&'\n') => self.pos += 2,
+                _ => break,
+            }
+        }
+    }
+
+    /// Only a comment may follow a key/value pair or table header
+    fn end_of_line(&mut self) -> Result<(), TomlError> {
+        self.skip_space();
+        self.skip_comment();
+        match self.peek() {
+            None | Some('\n') => Ok(()),
+            Some('\r') if self.chars.get(self.pos + 1) == Some(&'\n') => Ok(()),
+            Some(c) => Err(self.error(format!("unexpected `{c}` after value"))),
+        }
+    }
+
+    /// Bare, quoted or dotted key
+    fn key(&mut self) -> Result<Vec<String>, TomlError> {
+        let mut parts = Vec::new();
+        loop {
+            self.skip_space();
+            let part = match self.peek() {
+                Some('"') => {
+                    self.pos += 1;
+                    self.basic_string()?
+                }
+                Some('\'') => {
+                    self.pos += 1;
+                    self.literal_string()?
+                }
+                _ => {
+                    let start = self.pos;
+                    while matches!(self.peek(), Some(c) if c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+                        self.pos += 1;
+                    }
+                    if start == self.pos {
+                        return Err(self.error("expected a key"));
+                    }
+                    self.chars[start..self.pos].iter().collect()
+                }
+            };
+            parts.push(part);
+            self.skip_space();
+            if !self.eat('.') {
+                return Ok(parts);
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, TomlError> {
+        match self.peek() {
+            Some('"') if self.starts_with("\"\"\"") => {
+                self.pos += 3;
+                self.multiline_basic_string().map(Value::String)
+            }
+            Some('"') => {
+                self.pos += 1;
+                self.basic_string().map(Value::String)
+            }
+            Some('\'') if self.starts_with("'''") => {
+                self.pos += 3;
+                self.multiline_literal_string().map(Value::String)
+            }
+            Some('\'') => {
+                self.pos += 1;
+                self.literal_string().map(Value::String)
+            }
+            Some('[') => {
+                self.pos += 1;
+                self.array()
+            }
+            Some('{') => {
+                self.pos += 1;
+                self.inline_table()
+            }
+            Some(_) if self.starts_with("true") => {
+                self.pos += 4;
+                Ok(Value::Bool(true))
+            }
+            Some(_) if self.starts_with("false") => {
+                self.pos += 5;
+                Ok(Value::Bool(false))
+            }
+            Some(_) => self.number(),
+            None => Err(self.error("expected a value")),
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, TomlError> {
+        let mut items = Vec::new();
+        loop {
+            self.skip_blank_lines();
+            if self.eat(']') {
+                return Ok(Value::Array(items));
+            }
+            items.push(self.value()?);
+            self.skip_blank_lines();
+            if !self.eat(',') {
+                self.skip_blank_lines();
+                self.expect(']')?;
+                return Ok(Value::Array(items));
+            }
+        }
+    }
+
+    fn inline_table(&mut self) -> Result<Value, TomlError> {
+        let mut table = Map::new();
+        self.skip_space();
+        if self.eat('}') {
+            return Ok(Value::Object(table));
+        }
+        loop {
+            let key = self.key()?;
+            self.skip_space();
+            self.expect('=')?;
+            self.skip_space();
+            let value = self.value()?;
+            insert(&mut table, &key, value).map_err(|message| self.error(message))?;
+            self.skip_space();
+            if self.eat('}') {
+                return Ok(Value::Object(table));
+            }
+            self.expect(',')?;
+        }
+    }
+
+    /// Integer or float, including `inf` and `nan`
+    fn number(&mut self) -> Result<Value, TomlError> {
+        let start = self.pos;
+        while matches!(self.peek(), Some(c) if c.is_ascii_alphanumeric() || matches!(c, '_' | '+' | '-' | '.' | ':'))
+        {
+            self.pos += 1;
+        }
+        let token: String = self.chars[start..self.pos].iter().collect();
+        if token.is_empty() {
+            return Err(self.error(format!("unexpected `{}`", self.peek().unwrap_or(' '))));
+        }
+        let date = token.len() > 4 && token[..4].bytes().all(|b| b.is_ascii_digit()) && token[4..].starts_with('-');
+        if token.contains(':') || date {
+            return Err(self.error("dates and times are not supported"));
+        }
+        let invalid = || self.error(format!("`{token}` is not a valid value"));
+        if token.contains("__") || token.starts_with('_') || token.ends_with('_') {
+            return Err(invalid());
+        }
+        let digits = token.replace('_', "");
+        let (sign, unsigned) = match digits.strip_prefix('-') {
+            Some(rest) => (-1, rest),
+            None => (1, digits.strip_prefix('+').unwrap_or(&digits)),
+        };
+        let radix = match unsigned.get(..2) {
+            Some("0x") => Some(16),
+            Some("0o") => Some(8),
+            Some("0b") => Some(2),
+            _ => None,
+        };
+        if let Some(radix) = radix {
+            let value = i64::from_str_radix(&unsigned[2..], radix).map_err(|_| invalid())?;
+            return Ok(Value::from(sign * value));
+        }
+        let float = match unsigned {
+            "inf" => Some(f64::INFINITY),
+            "nan" => Some(f64::NAN),
+            _ if unsigned.contains(['.', 'e', 'E']) => {
+                let valid = unsigned.starts_with(|c: char| c.is_ascii_digit())
+                    && !unsigned.contains(".e")
+                    && !unsigned.contains(".E")
+                    && !unsigned.ends_with('.');
+                Some(unsigned.parse::<f64>().ok().filter(|_| valid).ok_or_else(invalid)?)
+            }
+            _ => None,
+        };
+        match float {
+            // JSON has no infinities or NaN; content files have no use for them
+            Some(value) => Number::from_f64(sign as f64 * value)
+                .map(Value::Number)
+                .ok_or_else(|| self.error(format!("`{token}` cannot be represented"))),
+            None => {
+                if (unsigned.len() > 1 && unsigned.starts_with('0')) || !unsigned.bytes().all(|b| b.is_ascii_digit()) {
+                    return Err(invalid());
+                }
+                digits.parse::<i64>().map(Value::from).map_err(|_| invalid())
+            }
+        }
+    }
+
+    /// Body of a `"..."` string, after the opening quote
+    fn basic_string(&mut self) -> Result<String, TomlError> {
+        let mut text = String::new();
+        loop {
+            match self.peek() {
+                None | Some('\n') => return Err(self.error("unterminated string")),
+                Some('"') => {
+                    self.pos += 1;
+                    return Ok(text);
+                }
+                Some('\\') => {
+                    self.pos += 1;
+                    text.push(self.escape()?);
+                }
+                Some(c) => {
+                    self.pos += 1;
+                    text.push(c);
+                }
+            }
+        }
+    }
+
+    /// Body of a `"""..."""` string, after the opening quotes
+    fn multiline_basic_string(&mut self) -> Result<String, TomlError> {
+        self.skip_first_newline();
+        let mut text = String::new();
+        loop {
+            if self.starts_with("\"\"\"") {
+                // Up to two quotes may directly precede the closing delimiter
+                while self.starts_with("\"\"\"\"") {
+                    text.push('"');
+                    self.pos += 1;
+                }
+                self.pos += 3;
+                return Ok(text);
+            }
+            match self.peek() {
+                None => return Err(self.error("unterminated string")),
+                Some('\\') => {
+                    self.pos += 1;
+                    // A backslash at the end of a line trims the following whitespace
+                    let rest = self.pos;
+                    self.skip_space();
+                    if matches!(self.peek(), Some('\n' | '\r')) {
+                        while matches!(self.peek(), Some(' ' | '\t' | '\n' | '\r')) {
+                            self.pos += 1;
+                        }
+                    } else {
+                        self.pos = rest;
+                        text.push(self.escape()?);
+                    }
+                }
+                Some(c) => {
+                    self.pos += 1;
+                    text.push(c);
+                }
+            }
+        }
+    }
+
+    /// Body of a `'...'` string, after the opening quote
+    fn literal_string(&mut self) -> Result<String, TomlError> {
+        let start = self.pos;
+        loop {
+            match self.peek() {
+                None | Some('\n') => return Err(self.error("unterminated string")),
+                Some('\'') => {
+                    let text = self.chars[start..self.pos].iter().collect();
+                    self.pos += 1;
+                    return Ok(text);
+                }
+                Some(_) => self.pos += 1,
+            }
+        }
+    }
+
+    /// Body of a `'''...'''` string, after the opening quotes
+    fn multiline_literal_string(&mut self) -> Result<String, TomlError> {
+        self.skip_first_newline();
+        let start = self.pos;
+        loop {
+            if self.starts_with("'''") {
+                while self.starts_with("''''") {
+                    self.pos += 1;
+                }
+                let text = self.chars[start..self.pos].iter().collect();
+                self.pos += 3;
+                return Ok(text);
+            }
+            if self.peek().is_none() {
+                return Err(self.error("unterminated string"));
+            }
+            self.pos += 1;
+        }
+    }
+
+    /// A newline right after the opening delimiter is not part of the string
+    fn skip_first_newline(&mut self) {
+        if self.starts_with("\r\n") {
+            self.pos += 2;
+        } else if self.starts_with("\n") {
+            self.pos += 1;
+        }
+    }
+
+    /// Character of an escape sequence, after the backslash
+    fn escape(&mut self) -> Result<char, TomlError> {
+        let c = self.peek().ok_or_else(|| self.error("unterminated string"))?;
+        self.pos += 1;
+        let hex_digits = match c {
+            'b' => return Ok('\u{8}'),
+            't' => return Ok('\t'),
+            'n' => return Ok('\n'),
+            'f' => return Ok('\u{c}'),
+            'r' => return Ok('\r'),
+            '"' => return Ok('"'),
+            '\\' => return Ok('\\'),
+            'u' => 4,
+            'U' => 8,
+            _ => return Err(self.error(format!("unknown escape `\\{c}`"))),
+        };
+        let end = (self.pos + hex_digits).min(self.chars.len());
+        let code: String = self.chars[self.pos..end].iter().collect();
+        self.pos = end;
+        u32::from_str_radix(&code, 16)
+            .ok()
+            .filter(|_| code.len() == hex_digits)
+            .and_then(char::from_u32)
+            .ok_or_else(|| self.error(format!("`\\{c}{code}` is not a valid escape")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn error(input: &str) -> (usize, String) {
+        match parse(input) {
+            Ok(value) => panic!("parsed {input:?} as {value}"),
+            Err(err) => (err.line, err.message),
+        }
+    }
+
+    #[test]
+    fn document() {
+        let input = r#"
+# A lesson
+title = "Projectile motion" # trailing comment
+site.name = 'Physics Tutorial'
+
+[simulation]
+id = "projectile"
+steps = 1_000
+mask = 0xff
+mode = 0o17
+flags = 0b101
+gravity = -9.81
+tolerance = 1e-6
+scale = +2.5E3
+enabled = true
+hidden = false
+
+[simulation.parameters]
+angles = [15, 30, [45, 60]]
+range = { min = 0.0, max = 90, unit.name = "degree" }
+empty = []
+multi = [
+  1,
+  2, # comment inside an array
+]
+
+[[questions]]
+text = "How far?"
+
+[[questions]]
+text = "How high?"
+choices = [{ label = "a" }, { label = "b" }]
+"#;
+        let expected = json!({
+            "title": "Projectile motion",
+            "site": { "name": "Physics Tutorial" },
+            "simulation": {
+                "id": "projectile",
+                "steps": 1000,
+                "mask": 255,
+                "mode": 15,
+                "flags": 5,
+                "gravity": -9.81,
+                "tolerance": 1e-6,
+                "scale": 2500.0,
+                "enabled": true,
+                "hidden": false,
+                "parameters": {
+                    "angles": [15, 30, [45, 60]],
+                    "range": { "min": 0.0, "max": 90, "unit": { "name": "degree" } },
+                    "empty": [],
+                    "multi": [1, 2],
+                },
+            },
+            "questions": [
+                { "text": "How far?" },
+                { "text": "How high?", "choices": [{ "label": "a" }, { "label": "b" }] },
+            ],
+        });
+        assert_eq!(parse(input).ok(), Some(expected));
+    }
+
+    #[test]
+    fn strings() {
+        let input = concat!(
+            "basic = \"tab\\tquote\\\" slash\\\\ \\u00e9\\U0001F680\"\n",
+            "literal = 'C:\\path\\n'\n",
+            "multi = \"\"\"\nline one\nline \\\n    two\"\"\"\n",
+            "quotes = \"\"\"say \"hi\"\"\"\"\n",
+            "raw = '''\nkeep \\n as is\n'''\n",
+            "\"quoted key\" = 1\n",
+        );
+        let expected = json!({
+            "basic": "tab\tquote\" slash\\ \u{e9}\u{1F680}",
+            "literal": "C:\\path\\n",
+            "multi": "line one\nline two",
+            "quotes": "say \"hi\"",
+            "raw": "keep \\n as is\n",
+            "quoted key": 1,
+        });
+        assert_eq!(parse(input).ok(), Some(expected));
+    }
+
+    #[test]
+    fn sub_tables_of_array_elements() {
+        let input = "[[steps]]\nname = \"a\"\n[steps.check]\nmin = 1\n[[steps]]\nname = \"b\"\n";
+        let expected = json!({ "steps": [{ "name": "a", "check": { "min": 1 } }, { "name": "b" }] });
+        assert_eq!(parse(input).ok(), Some(expected));
+    }
+
+    #[test]
+    fn invalid_documents() {
+        let cases: [(&str, usize, &str); 17] = [
+            ("a = 1\na = 2", 2, "`a` is defined twice"),
+            ("a.b = 1\na.b = 2", 2, "`a.b` is defined twice"),
+            ("[x]\n[y]\n[x]", 3, "table [x] is defined twice"),
+            ("x = 1\n[[x]]", 2, "`x` is not an array of tables"),
+            ("x = 1\n[x.y]", 2, "`x` is not a table"),
+            ("when = 2024-01-01", 1, "dates and times are not supported"),
+            ("when = 07:30:00", 1, "dates and times are not supported"),
+            ("s = \"open\nt = 1", 1, "unterminated string"),
+            ("s = '''never closed", 1, "unterminated string"),
+            ("s = \"\\q\"", 1, "unknown escape `\\q`"),
+            ("s = \"\\u12\"", 1, "`\\u12\"` is not a valid escape"),
+            ("n =", 1, "expected a value"),
+            ("n = 1_000_", 1, "`1_000_` is not a valid value"),
+            ("n = 012", 1, "`012` is not a valid value"),
+            ("n = 1.", 1, "`1.` is not a valid value"),
+            ("n = inf", 1, "`inf` cannot be represented"),
+            ("\n\nn = 99999999999999999999", 3, "`99999999999999999999` is not a valid value"),
+        ];
+        for (input, line, message) in cases {
+            assert_eq!(error(input), (line, message.to_string()), "{input:?}");
+        }
+    }
+
+    #[test]
+    fn trailing_garbage() {
+        let (line, message) = error("a = 1 2");
+        assert_eq!(line, 1);
+        assert!(message.starts_with("unexpected"), "{message}");
+        assert_eq!(error("a = [1, 2").0, 1);
+        assert_eq!(error("a = { b = 1").0, 1);
+    }
+
+    #[test]
+    fn error_display() {
+        let err = parse("ok = 1\nbad = ?").err().unwrap_or_else(|| panic!("parsed"));
+        assert_eq!(err.line, 2);
+        assert!(err.to_string().starts_with("line 2: "), "{err}");
+    }
+}
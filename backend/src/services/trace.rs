@@ -0,0 +1,48 @@
+// Request correlation for logs
+//
+// Every request gets an ID, taken from its `X-Request-Id` header or made up,
+// which is echoed in the response, recorded on the request's tracing span
+// and put in error bodies. Work moved off the request's task must carry the
+// span along for its log lines to show the ID; `spawn_blocking` here does.
+
+use std::future::Future;
+
+use tokio::task::JoinHandle;
+use tracing::Span;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+/// Longest request ID accepted from a client
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// ID of the request being handled on this task, if any
+pub fn request_id() -> Option<String> {
+    REQUEST_ID.try_with(String::clone).ok()
+}
+
+/// Run `future` as part of the request with this ID
+pub async fn with_request_id<F: Future>(id: String, future: F) -> F::Output {
+    REQUEST_ID.scope(id, future).await
+}
+
+/// A client's request ID if it is safe to log and echo: short, and only
+/// letters, digits and `-_.:`
+pub fn accept_request_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_REQUEST_ID_LENGTH
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'))
+}
+
+/// `tokio::task::spawn_blocking`, keeping the caller's span so the
+/// computation's log lines name the request that started it
+pub fn spawn_blocking<F, R>(f: F) -> JoinHandle<R>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    let span = Span::current();
+    tokio::task::spawn_blocking(move || span.in_scope(f))
+}
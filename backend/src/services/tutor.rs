@@ -0,0 +1,295 @@
+// AI tutor
+//
+// Conversations with a language model about the simulation a student has
+// open. Each request carries the conversation so far, and the server puts a
+// system prompt in front of it with the simulation's theory, the student's
+// parameter values and a summary of their latest result, so answers can
+// refer to what the student is looking at. The reply is streamed back as
+// the model writes it; nothing is stored.
+//
+// Two kinds of API are spoken: `openai`, the chat completions API of
+// OpenAI and of the servers that copy it (vLLM, llama.cpp, LM Studio,
+// LocalAI), and `ollama`, Ollama's own chat API, so self-hosted models work
+// as well as hosted ones.
+
+use std::fmt;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use crate::config::TutorConfig;
+use crate::models::simulation::SimulationDetails;
+use crate::simulations::Derived;
+
+pub const OPENAI: &str = "openai";
+pub const OLLAMA: &str = "ollama";
+pub const PROVIDERS: [&str; 2] = [OPENAI, OLLAMA];
+/// Longest a reply may take to arrive in full
+const REPLY_TIMEOUT: Duration = Duration::from_secs(180);
+/// Longest wait to connect to the API
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Longest theory text put in the prompt (characters); longer sections are
+/// cut
+const MAX_THEORY_LENGTH: usize = 16_000;
+/// Most fields of a result described in the prompt
+const MAX_RESULT_FIELDS: usize = 30;
+
+#[derive(Debug)]
+pub enum TutorError {
+    /// `TUTOR_API_URL` is not set
+    NotConfigured,
+    /// The model's API failed or did not answer
+    Provider(String),
+}
+
+impl fmt::Display for TutorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotConfigured => write!(f, "the AI tutor is not configured on this server"),
+            Self::Provider(reason) => write!(f, "tutor model request failed: {reason}"),
+        }
+    }
+}
+
+fn provider_error(err: reqwest::Error) -> TutorError {
+    TutorError::Provider(err.to_string())
+}
+
+/// One turn of a conversation
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ChatMessage {
+    /// `system`, `user` or `assistant`
+    pub role: String,
+    pub content: String,
+}
+
+/// Client of the configured model
+pub struct Tutor {
+    api: Option<Api>,
+}
+
+struct Api {
+    /// One of `PROVIDERS`
+    provider: &'static str,
+    url: String,
+    key: Option<String>,
+    model: String,
+    http: reqwest::Client,
+}
+
+impl Tutor {
+    pub fn from_config(config: &TutorConfig) -> Self {
+        let api = config.api_url.as_ref().zip(config.model.as_ref()).map(|(url, model)| {
+            let provider = if config.provider == OLLAMA { OLLAMA } else { OPENAI };
+            let path = if provider == OLLAMA { "api/chat" } else { "chat/completions" };
+            Api {
+                provider,
+                url: format!("{}/{path}", url.trim_end_matches('/')),
+                key: config.api_key.clone(),
+                model: model.clone(),
+                http: reqwest::Client::builder()
+                    .connect_timeout(CONNECT_TIMEOUT)
+                    .timeout(REPLY_TIMEOUT)
+                    .build()
+                    .unwrap_or_default(),
+            }
+        });
+        Self { api }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.api.is_some()
+    }
+
+    /// Model the tutor chats with
+    pub fn model(&self) -> Option<&str> {
+        self.api.as_ref().map(|api| api.model.as_str())
+    }
+
+    /// Start the model's reply to a conversation. Fails if the API cannot
+    /// be reached or refuses the request; failures later on come from
+    /// `Reply::next`.
+    pub async fn chat(&self, messages: &[ChatMessage]) -> Result<Reply, TutorError> {
+        let api = self.api.as_ref().ok_or(TutorError::NotConfigured)?;
+        let request = api.http.post(&api.url).json(&json!({
+            "model": api.model,
+            "messages": messages,
+            "stream": true,
+        }));
+        let request = match &api.key {
+            Some(key) => request.bearer_auth(key),
+            None => request,
+        };
+        let response = request.send().await.map_err(provider_error)?;
+        if let Err(err) = response.error_for_status_ref() {
+            let body = response.text().await.unwrap_or_default();
+            let detail: String = body.chars().take(500).collect();
+            return Err(TutorError::Provider(format!("{err}: {detail}")));
+        }
+        Ok(Reply {
+            provider: api.provider,
+            response,
+            buffer: Vec::new(),
+            finished: false,
+        })
+    }
+}
+
+/// A reply as the model writes it: Server-Sent Events from OpenAI-style
+/// APIs, one JSON object a line from Ollama
+pub struct Reply {
+    provider: &'static str,
+    response: reqwest::Response,
+    /// Received text not yet split into lines
+    buffer: Vec<u8>,
+    finished: bool,
+}
+
+impl Reply {
+    /// Next piece of the reply, or `None` once it is complete
+    pub async fn next(&mut self) -> Result<Option<String>, TutorError> {
+        while !self.finished {
+            let line = match self.buffer.iter().position(|&b| b == b'\n') {
+                Some(end) => {
+                    let line: Vec<u8> = self.buffer.drain(..=end).collect();
+                    String::from_utf8_lossy(&line).into_owned()
+                }
+                None => match self.response.chunk().await.map_err(provider_error)? {
+                    Some(bytes) => {
+                        self.buffer.extend_from_slice(&bytes);
+                        continue;
+                    }
+                    None => {
+                        self.finished = true;
+                        String::from_utf8_lossy(&std::mem::take(&mut self.buffer)).into_owned()
+                    }
+                },
+            };
+            if let Some(piece) = self.parse(line.trim())? {
+                return Ok(Some(piece));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Text in one line of the stream, if it has any
+    fn parse(&mut self, line: &str) -> Result<Option<String>, TutorError> {
+        let payload = match self.provider {
+            OLLAMA => line,
+            _ => match line.strip_prefix("data:").map(str::trim) {
+                Some("[DONE]") => {
+                    self.finished = true;
+                    return Ok(None);
+                }
+                Some(payload) => payload,
+                None => return Ok(None),
+            },
+        };
+        if payload.is_empty() {
+            return Ok(None);
+        }
+        let chunk: Value = serde_json::from_str(payload)
+            .map_err(|err| TutorError::Provider(format!("unreadable reply from the model: {err}")))?;
+        if let Some(error) = chunk.get("error") {
+            let message = error.get("message").unwrap_or(error);
+            return Err(TutorError::Provider(message.as_str().map_or_else(|| message.to_string(), str::to_string)));
+        }
+        let content = match self.provider {
+            OLLAMA => {
+                self.finished = chunk["done"].as_bool().unwrap_or(false);
+                &chunk["message"]["content"]
+            }
+            _ => &chunk["choices"][0]["delta"]["content"],
+        };
+        Ok(content.as_str().filter(|text| !text.is_empty()).map(str::to_string))
+    }
+}
+
+/// Instructions and context the conversation is prefixed with: the
+/// simulation's description and theory, the parameter values the student
+/// has set and, if given, a summary of their latest result
+pub fn system_prompt(details: &SimulationDetails, parameters: &Map<String, Value>, result: Option<&str>) -> String {
+    let mut prompt = format!(
+        "You are a patient physics tutor inside the DIU Physics Tutorial, an interactive simulation app. The \
+         student has the \"{}\" simulation open. Help them understand the physics behind what they see: explain \
+         step by step, relate answers to the simulation's parameters and results, suggest changes to the \
+         parameters they could try, and prefer guiding questions to handing over answers to exercises. Keep \
+         answers short unless asked for detail, write math as LaTeX between $ signs, and answer in the language \
+         the student writes in. If a question is not about physics or this app, say so briefly.\n\n\
+         ## Simulation\n{}\n",
+        details.name, details.description
+    );
+
+    prompt.push_str("\n## Parameters set by the student\n");
+    for parameter in &details.parameters {
+        let value = parameters.get(&parameter.name).unwrap_or(&parameter.default);
+        let unit = parameter.unit.as_deref().map(|unit| format!(" {unit}")).unwrap_or_default();
+        let range = match (parameter.min, parameter.max) {
+            (Some(min), Some(max)) => format!(" (range {min} to {max}{unit})"),
+            _ => String::new(),
+        };
+        prompt.push_str(&format!("- {} (`{}`): {}{unit}{range}\n", parameter.label, parameter.name, value));
+    }
+
+    if let Some(result) = result {
+        prompt.push_str("\n## The student's latest result\n");
+        prompt.push_str(result);
+    }
+
+    let theory: String = details.theory.chars().take(MAX_THEORY_LENGTH).collect();
+    prompt.push_str("\n## Theory section the student can read\n");
+    prompt.push_str(&theory);
+    prompt
+}
+
+/// What a result holds, in a few lines: the quantities the simulation
+/// derives from it and the shape of its output
+pub fn result_summary(data: &Value, derived: &[Derived]) -> String {
+    let mut summary = String::new();
+    for quantity in derived {
+        let unit = if quantity.unit.is_empty() { String::new() } else { format!(" {}", quantity.unit) };
+        summary.push_str(&format!("- {}: {}{unit}\n", quantity.label, number(quantity.value)));
+    }
+    if let Value::Object(fields) = data {
+        for (name, value) in fields.iter().take(MAX_RESULT_FIELDS) {
+            summary.push_str(&format!("- `{name}`: {}\n", describe(value)));
+        }
+    }
+    summary
+}
+
+/// A short description of an output value
+fn describe(value: &Value) -> String {
+    match value {
+        Value::Array(items) => {
+            let numbers: Vec<f64> = items.iter().filter_map(Value::as_f64).collect();
+            if !numbers.is_empty() && numbers.len() == items.len() {
+                let min = numbers.iter().copied().fold(f64::INFINITY, f64::min);
+                let max = numbers.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+                format!("{} numbers from {} to {}", numbers.len(), number(min), number(max))
+            } else {
+                format!("list of {} items", items.len())
+            }
+        }
+        Value::Object(fields) => {
+            let names: Vec<&str> = fields.keys().map(String::as_str).take(10).collect();
+            format!("object with {}", names.join(", "))
+        }
+        Value::String(text) => text.chars().take(200).collect(),
+        other => other.to_string(),
+    }
+}
+
+/// A number to six significant digits, in scientific notation when very
+/// large or small
+fn number(value: f64) -> String {
+    let magnitude = value.abs();
+    if magnitude == 0.0 || (1e-3..1e6).contains(&magnitude) {
+        let decimals = (5 - magnitude.log10().floor() as i32).max(0) as usize;
+        let text = format!("{value:.decimals$}");
+        if text.contains('.') { text.trim_end_matches('0').trim_end_matches('.').to_string() } else { text }
+    } else {
+        format!("{value:.5e}")
+    }
+}
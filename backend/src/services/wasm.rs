@@ -0,0 +1,1823 @@
+// WebAssembly interpreter for simulation plugins
+//
+// Runs modules in the WebAssembly 1.0 binary format together with the
+// additions compilers now emit by default: sign extension, saturating float
+// to integer conversion, multi-value blocks and the bulk memory copy and
+// fill. Modules get no imports, so they compute on their own memory and
+// globals and can reach nothing else. Every instruction executed costs one
+// unit of fuel, which stops a run after the same amount of work on any
+// machine, and memory grows only up to a set number of pages.
+//
+// Decoding checks what execution relies on (indices of functions, types,
+// locals, globals and branch targets, and that a memory exists before it is
+// used) but not the types of operands. Values live on one untyped stack of
+// 64-bit words, so a module that mixes them up computes garbage or traps;
+// it cannot reach outside its own memory, which every access checks.
+
+use std::collections::HashMap;
+use std::fmt;
+
+/// Size of a memory page (bytes)
+pub const PAGE_SIZE: usize = 65_536;
+/// Instructions executed between two checks of `Fuel::interrupt`
+const INTERRUPT_INTERVAL: u64 = 1 << 16;
+/// Nested calls allowed before a run traps
+const MAX_CALL_DEPTH: usize = 10_000;
+/// Locals one function may declare
+const MAX_LOCALS: u32 = 50_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ValType {
+    I32,
+    I64,
+    F32,
+    F64,
+}
+
+/// A value passed to or returned from an exported function
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Val {
+    I32(i32),
+    I64(i64),
+    F32(f32),
+    F64(f64),
+}
+
+impl Val {
+    fn to_raw(self) -> u64 {
+        match self {
+            Val::I32(v) => v as u32 as u64,
+            Val::I64(v) => v as u64,
+            Val::F32(v) => v.to_bits() as u64,
+            Val::F64(v) => v.to_bits(),
+        }
+    }
+
+    fn from_raw(raw: u64, ty: ValType) -> Self {
+        match ty {
+            ValType::I32 => Val::I32(raw as u32 as i32),
+            ValType::I64 => Val::I64(raw as i64),
+            ValType::F32 => Val::F32(f32::from_bits(raw as u32)),
+            ValType::F64 => Val::F64(f64::from_bits(raw)),
+        }
+    }
+
+    fn ty(self) -> ValType {
+        match self {
+            Val::I32(_) => ValType::I32,
+            Val::I64(_) => ValType::I64,
+            Val::F32(_) => ValType::F32,
+            Val::F64(_) => ValType::F64,
+        }
+    }
+}
+
+/// Why a call stopped before returning
+#[derive(Debug, PartialEq)]
+pub enum Trap {
+    Unreachable,
+    MemoryOutOfBounds,
+    DivideByZero,
+    IntegerOverflow,
+    /// A float that is NaN or out of range was converted to an integer
+    InvalidConversion,
+    /// An indirect call hit an empty table slot or a function of another type
+    BadIndirectCall,
+    CallStackExhausted,
+    /// The operand stack was empty where a value was expected
+    StackUnderflow,
+    OutOfFuel,
+    /// `Fuel::interrupt` asked the call to stop
+    Interrupted,
+    /// No function of that name is exported, or not with these arguments
+    BadExport(String),
+}
+
+impl fmt::Display for Trap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Trap::Unreachable => write!(f, "unreachable executed"),
+            Trap::MemoryOutOfBounds => write!(f, "out of bounds memory access"),
+            Trap::DivideByZero => write!(f, "integer divide by zero"),
+            Trap::IntegerOverflow => write!(f, "integer overflow"),
+            Trap::InvalidConversion => write!(f, "invalid conversion to integer"),
+            Trap::BadIndirectCall => write!(f, "indirect call to a missing function or one of another type"),
+            Trap::CallStackExhausted => write!(f, "call stack exhausted"),
+            Trap::StackUnderflow => write!(f, "operand stack underflow"),
+            Trap::OutOfFuel => write!(f, "out of fuel"),
+            Trap::Interrupted => write!(f, "interrupted"),
+            Trap::BadExport(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+/// Work a call may still do, and whether to stop early
+pub struct Fuel<'a> {
+    /// Instructions left to execute
+    pub remaining: u64,
+    /// Asked every few thousand instructions; `true` stops the call
+    pub interrupt: &'a dyn Fn() -> bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct FuncType {
+    params: Vec<ValType>,
+    results: Vec<ValType>,
+}
+
+struct Function {
+    type_index: u32,
+    /// Declared locals after the parameters
+    locals: usize,
+    code: Vec<Instr>,
+}
+
+#[derive(Clone, Copy)]
+struct Limits {
+    min: u32,
+    max: Option<u32>,
+}
+
+struct Global {
+    mutable: bool,
+    init: u64,
+}
+
+/// Something a module exports
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Export {
+    Function(u32),
+    Table,
+    Memory,
+    Global(u32),
+}
+
+/// Decoded instruction. Branch targets of blocks are resolved while
+/// decoding, to the index of their `End` (and `Else`).
+#[derive(Clone, Debug)]
+enum Instr {
+    Unreachable,
+    Nop,
+    Block { params: u32, results: u32, end: u32 },
+    Loop { params: u32, results: u32 },
+    If { params: u32, results: u32, other: Option<u32>, end: u32 },
+    Else { end: u32 },
+    End,
+    Br(u32),
+    BrIf(u32),
+    BrTable(Box<[u32]>, u32),
+    Return,
+    Call(u32),
+    CallIndirect(u32),
+    Drop,
+    Select,
+    LocalGet(u32),
+    LocalSet(u32),
+    LocalTee(u32),
+    GlobalGet(u32),
+    GlobalSet(u32),
+    Load(u8, u32),
+    Store(u8, u32),
+    MemorySize,
+    MemoryGrow,
+    MemoryCopy,
+    MemoryFill,
+    Const(u64),
+    /// Single-byte numeric instruction, by opcode
+    Numeric(u8),
+    /// Saturating conversion, by its 0xFC sub-opcode
+    TruncSat(u8),
+}
+
+/// A decoded module, ready to be instantiated any number of times
+pub struct Module {
+    types: Vec<FuncType>,
+    functions: Vec<Function>,
+    table: Option<Limits>,
+    memory: Option<Limits>,
+    globals: Vec<Global>,
+    exports: HashMap<String, Export>,
+    start: Option<u32>,
+    /// Table offset and function indices of each element segment
+    elements: Vec<(u32, Vec<u32>)>,
+    /// Memory offset and bytes of each data segment
+    data: Vec<(u32, Vec<u8>)>,
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, pos: 0 }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos >= self.bytes.len()
+    }
+
+    fn byte(&mut self) -> Result<u8, String> {
+        let byte = *self.bytes.get(self.pos).ok_or("unexpected end of module")?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self.pos.checked_add(len).filter(|&end| end <= self.bytes.len()).ok_or("unexpected end of module")?;
+        let bytes = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    /// LEB128 integer of at most `bits` bits, sign-extended when `signed`
+    fn leb(&mut self, bits: u32, signed: bool) -> Result<u64, String> {
+        let mut result = 0u64;
+        let mut shift = 0;
+        loop {
+            let byte = self.byte()?;
+            if shift >= bits {
+                return Err("integer too long".to_string());
+            }
+            result |= u64::from(byte & 0x7F) << shift;
+            shift += 7;
+            if byte & 0x80 == 0 {
+                if signed && shift < 64 && byte & 0x40 != 0 {
+                    result |= !0 << shift;
+                }
+                return Ok(result);
+            }
+        }
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let value = self.leb(32, false)?;
+        u32::try_from(value).map_err(|_| "integer too large".to_string())
+    }
+
+    fn len(&mut self) -> Result<usize, String> {
+        let len = self.u32()? as usize;
+        // Every item takes at least a byte, which bounds what is allocated
+        if len > self.bytes.len() - self.pos {
+            return Err("length exceeds the module".to_string());
+        }
+        Ok(len)
+    }
+
+    fn name(&mut self) -> Result<String, String> {
+        let len = self.len()?;
+        String::from_utf8(self.take(len)?.to_vec()).map_err(|_| "name is not UTF-8".to_string())
+    }
+
+    fn val_type(&mut self) -> Result<ValType, String> {
+        match self.byte()? {
+            0x7F => Ok(ValType::I32),
+            0x7E => Ok(ValType::I64),
+            0x7D => Ok(ValType::F32),
+            0x7C => Ok(ValType::F64),
+            other => Err(format!("unsupported value type 0x{other:02x}")),
+        }
+    }
+
+    fn limits(&mut self) -> Result<Limits, String> {
+        match self.byte()? {
+            0x00 => Ok(Limits { min: self.u32()?, max: None }),
+            0x01 => {
+                let min = self.u32()?;
+                let max = self.u32()?;
+                if max < min {
+                    return Err("limits have max below min".to_string());
+                }
+                Ok(Limits { min, max: Some(max) })
+            }
+            _ => Err("unsupported limits".to_string()),
+        }
+    }
+
+    /// Constant expression: one constant, or a global read, then `end`
+    fn const_expr(&mut self, globals: &[Global]) -> Result<u64, String> {
+        let value = match self.byte()? {
+            0x41 => self.leb(32, true)? as u32 as u64,
+            0x42 => self.leb(64, true)?,
+            0x43 => u64::from(u32::from_le_bytes(self.take(4)?.try_into().unwrap_or_default())),
+            0x44 => u64::from_le_bytes(self.take(8)?.try_into().unwrap_or_default()),
+            0x23 => globals.get(self.u32()? as usize).ok_or("unknown global")?.init,
+            other => return Err(format!("unsupported constant instruction 0x{other:02x}")),
+        };
+        match self.byte()? {
+            0x0B => Ok(value),
+            _ => Err("constant expression is not a single constant".to_string()),
+        }
+    }
+}
+
+impl Module {
+    /// Decode a module in the binary format
+    pub fn decode(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = Reader::new(bytes);
+        if reader.take(4)? != b"\0asm" {
+            return Err("not a WebAssembly module".to_string());
+        }
+        if reader.take(4)? != [1, 0, 0, 0] {
+            return Err("unsupported WebAssembly version".to_string());
+        }
+
+        let mut module = Module {
+            types: Vec::new(),
+            functions: Vec::new(),
+            table: None,
+            memory: None,
+            globals: Vec::new(),
+            exports: HashMap::new(),
+            start: None,
+            elements: Vec::new(),
+            data: Vec::new(),
+        };
+        let mut function_types: Vec<u32> = Vec::new();
+        let mut last_section = 0;
+        while !reader.is_empty() {
+            let id = reader.byte()?;
+            let len = reader.len()?;
+            let mut section = Reader::new(reader.take(len)?);
+            // Sections other than custom ones come in a fixed order, the
+            // data count (12) between elements (9) and code (10)
+            let order = match id {
+                12 => 10,
+                10 | 11 => id + 1,
+                _ => id,
+            };
+            if id != 0 {
+                if order <= last_section {
+                    return Err(format!("section {id} is out of order"));
+                }
+                last_section = order;
+            }
+            match id {
+                0 => continue,
+                1 => {
+                    for _ in 0..section.len()? {
+                        if section.byte()? != 0x60 {
+                            return Err("malformed function type".to_string());
+                        }
+                        let params = (0..section.len()?).map(|_| section.val_type()).collect::<Result<_, _>>()?;
+                        let results = (0..section.len()?).map(|_| section.val_type()).collect::<Result<_, _>>()?;
+                        module.types.push(FuncType { params, results });
+                    }
+                }
+                2 => {
+                    if section.len()? > 0 {
+                        return Err("modules may not import anything".to_string());
+                    }
+                }
+                3 => {
+                    for _ in 0..section.len()? {
+                        let index = section.u32()?;
+                        if index as usize >= module.types.len() {
+                            return Err("function has an unknown type".to_string());
+                        }
+                        function_types.push(index);
+                    }
+                }
+                4 => {
+                    for _ in 0..section.len()? {
+                        if section.byte()? != 0x70 {
+                            return Err("only tables of functions are supported".to_string());
+                        }
+                        if module.table.replace(section.limits()?).is_some() {
+                            return Err("at most one table is supported".to_string());
+                        }
+                    }
+                }
+                5 => {
+                    for _ in 0..section.len()? {
+                        if module.memory.replace(section.limits()?).is_some() {
+                            return Err("at most one memory is supported".to_string());
+                        }
+                    }
+                }
+                6 => {
+                    for _ in 0..section.len()? {
+                        section.val_type()?;
+                        let mutable = match section.byte()? {
+                            0 => false,
+                            1 => true,
+                            _ => return Err("malformed global".to_string()),
+                        };
+                        let init = section.const_expr(&module.globals)?;
+                        module.globals.push(Global { mutable, init });
+                    }
+                }
+                7 => {
+                    for _ in 0..section.len()? {
+                        let name = section.name()?;
+                        let kind = section.byte()?;
+                        let index = section.u32()?;
+                        let export = match kind {
+                            0 => Export::Function(index),
+                            1 => Export::Table,
+                            2 => Export::Memory,
+                            3 => Export::Global(index),
+                            _ => return Err("malformed export".to_string()),
+                        };
+                        if module.exports.insert(name.clone(), export).is_some() {
+                            return Err(format!("`{name}` is exported twice"));
+                        }
+                    }
+                }
+                8 => module.start = Some(section.u32()?),
+                9 => {
+                    for _ in 0..section.len()? {
+                        let flags = section.u32()?;
+                        match flags {
+                            0 | 2 => {
+                                if flags == 2 && section.u32()? != 0 {
+                                    return Err("unknown table".to_string());
+                                }
+                                let offset = section.const_expr(&module.globals)? as u32;
+                                if flags == 2 && section.byte()? != 0x00 {
+                                    return Err("only function elements are supported".to_string());
+                                }
+                                let functions = (0..section.len()?).map(|_| section.u32()).collect::<Result<_, _>>()?;
+                                module.elements.push((offset, functions));
+                            }
+                            // Declarations for `ref.func`, which is not
+                            // supported anyway
+                            3 => {
+                                section.byte()?;
+                                for _ in 0..section.len()? {
+                                    section.u32()?;
+                                }
+                            }
+                            _ => return Err("only active element segments are supported".to_string()),
+                        }
+                    }
+                }
+                10 => {
+                    let count = section.len()?;
+                    if count != function_types.len() {
+                        return Err("function and code sections differ in length".to_string());
+                    }
+                    for &type_index in &function_types {
+                        let size = section.len()?;
+                        let body = Reader::new(section.take(size)?);
+                        module.functions.push(module.decode_function(type_index, body, &function_types)?);
+                    }
+                }
+                11 => {
+                    for _ in 0..section.len()? {
+                        let flags = section.u32()?;
+                        if flags == 2 && section.u32()? != 0 {
+                            return Err("unknown memory".to_string());
+                        }
+                        let offset = match flags {
+                            0 | 2 => Some(section.const_expr(&module.globals)? as u32),
+                            1 => None,
+                            _ => return Err("malformed data segment".to_string()),
+                        };
+                        let len = section.len()?;
+                        let bytes = section.take(len)?.to_vec();
+                        // Passive segments are only read by `memory.init`,
+                        // which is not supported
+                        if let Some(offset) = offset {
+                            if module.memory.is_none() {
+                                return Err("data segment without a memory".to_string());
+                            }
+                            module.data.push((offset, bytes));
+                        }
+                    }
+                }
+                12 => {
+                    section.u32()?;
+                }
+                _ => return Err(format!("unknown section {id}")),
+            }
+            if !section.is_empty() {
+                return Err(format!("section {id} is longer than its contents"));
+            }
+        }
+        if !function_types.is_empty() && module.functions.is_empty() {
+            return Err("functions have no code".to_string());
+        }
+
+        let functions = module.functions.len() as u32;
+        let mut referenced = module.elements.iter().flat_map(|(_, indices)| indices.iter().copied());
+        if module.start.is_some_and(|start| start >= functions) || referenced.any(|index| index >= functions) {
+            return Err("unknown function".to_string());
+        }
+        if !module.elements.is_empty() && module.table.is_none() {
+            return Err("element segment without a table".to_string());
+        }
+        for export in module.exports.values() {
+            let known = match *export {
+                Export::Function(index) => index < functions,
+                Export::Table => module.table.is_some(),
+                Export::Memory => module.memory.is_some(),
+                Export::Global(index) => (index as usize) < module.globals.len(),
+            };
+            if !known {
+                return Err("export of something that does not exist".to_string());
+            }
+        }
+        Ok(module)
+    }
+
+    /// Decode one function body, resolving block ends
+    fn decode_function(&self, type_index: u32, mut body: Reader, function_types: &[u32]) -> Result<Function, String> {
+        let ty = &self.types[type_index as usize];
+        let mut locals = 0u32;
+        for _ in 0..body.len()? {
+            let count = body.u32()?;
+            body.val_type()?;
+            locals = locals.checked_add(count).filter(|&n| n <= MAX_LOCALS).ok_or("too many locals")?;
+        }
+        let all_locals = ty.params.len() as u32 + locals;
+
+        let mut code: Vec<Instr> = Vec::new();
+        // Index of the instruction opening each enclosing block
+        let mut open: Vec<usize> = Vec::new();
+        loop {
+            let opcode = body.byte()?;
+            let depth = open.len() as u32;
+            let label = |body: &mut Reader| -> Result<u32, String> {
+                let label = body.u32()?;
+                if label > depth {
+                    return Err("branch to an unknown label".to_string());
+                }
+                Ok(label)
+            };
+            let instr = match opcode {
+                0x00 => Instr::Unreachable,
+                0x01 => Instr::Nop,
+                0x02..=0x04 => {
+                    let (params, results) = self.block_type(&mut body)?;
+                    open.push(code.len());
+                    match opcode {
+                        0x02 => Instr::Block { params, results, end: 0 },
+                        0x03 => Instr::Loop { params, results },
+                        _ => Instr::If { params, results, other: None, end: 0 },
+                    }
+                }
+                0x05 => {
+                    let opener = *open.last().ok_or("else outside an if")?;
+                    let here = code.len() as u32;
+                    match &mut code[opener] {
+                        Instr::If { other: other @ None, .. } => *other = Some(here),
+                        _ => return Err("else outside an if".to_string()),
+                    }
+                    Instr::Else { end: 0 }
+                }
+                0x0B => {
+                    let end = code.len() as u32;
+                    let Some(opener) = open.pop() else {
+                        code.push(Instr::End);
+                        break;
+                    };
+                    match &mut code[opener] {
+                        Instr::Block { end: target, .. } => *target = end,
+                        Instr::If { end: target, other, .. } => {
+                            *target = end;
+                            if let Some(other) = *other {
+                                code[other as usize] = Instr::Else { end };
+                            }
+                        }
+                        _ => {}
+                    }
+                    Instr::End
+                }
+                0x0C => Instr::Br(label(&mut body)?),
+                0x0D => Instr::BrIf(label(&mut body)?),
+                0x0E => {
+                    let targets = (0..body.len()?).map(|_| label(&mut body)).collect::<Result<Vec<_>, _>>()?;
+                    Instr::BrTable(targets.into_boxed_slice(), label(&mut body)?)
+                }
+                0x0F => Instr::Return,
+                0x10 => {
+                    let index = body.u32()?;
+                    if index as usize >= function_types.len() {
+                        return Err("call to an unknown function".to_string());
+                    }
+                    Instr::Call(index)
+                }
+                0x11 => {
+                    let type_index = body.u32()?;
+                    if type_index as usize >= self.types.len() || body.u32()? != 0 || self.table.is_none() {
+                        return Err("indirect call with an unknown type or table".to_string());
+                    }
+                    Instr::CallIndirect(type_index)
+                }
+                0x1A => Instr::Drop,
+                0x1B => Instr::Select,
+                0x1C => {
+                    for _ in 0..body.len()? {
+                        body.val_type()?;
+                    }
+                    Instr::Select
+                }
+                0x20..=0x22 => {
+                    let index = body.u32()?;
+                    if index >= all_locals {
+                        return Err("unknown local".to_string());
+                    }
+                    match opcode {
+                        0x20 => Instr::LocalGet(index),
+                        0x21 => Instr::LocalSet(index),
+                        _ => Instr::LocalTee(index),
+                    }
+                }
+                0x23 | 0x24 => {
+                    let index = body.u32()?;
+                    let global = self.globals.get(index as usize).ok_or("unknown global")?;
+                    if opcode == 0x23 {
+                        Instr::GlobalGet(index)
+                    } else if global.mutable {
+                        Instr::GlobalSet(index)
+                    } else {
+                        return Err("global.set of an immutable global".to_string());
+                    }
+                }
+                0x28..=0x3E => {
+                    self.needs_memory()?;
+                    let _align = body.u32()?;
+                    let offset = body.u32()?;
+                    if opcode <= 0x35 {
+                        Instr::Load(opcode, offset)
+                    } else {
+                        Instr::Store(opcode, offset)
+                    }
+                }
+                0x3F | 0x40 => {
+                    self.needs_memory()?;
+                    if body.byte()? != 0 {
+                        return Err("unknown memory".to_string());
+                    }
+                    if opcode == 0x3F {
+                        Instr::MemorySize
+                    } else {
+                        Instr::MemoryGrow
+                    }
+                }
+                0x41 => Instr::Const(body.leb(32, true)? as u32 as u64),
+                0x42 => Instr::Const(body.leb(64, true)?),
+                0x43 => Instr::Const(u64::from(u32::from_le_bytes(body.take(4)?.try_into().unwrap_or_default()))),
+                0x44 => Instr::Const(u64::from_le_bytes(body.take(8)?.try_into().unwrap_or_default())),
+                0x45..=0xC4 => Instr::Numeric(opcode),
+                0xFC => match body.u32()? {
+                    sub @ 0..=7 => Instr::TruncSat(sub as u8),
+                    10 => {
+                        self.needs_memory()?;
+                        if body.byte()? != 0 || body.byte()? != 0 {
+                            return Err("unknown memory".to_string());
+                        }
+                        Instr::MemoryCopy
+                    }
+                    11 => {
+                        self.needs_memory()?;
+                        if body.byte()? != 0 {
+                            return Err("unknown memory".to_string());
+                        }
+                        Instr::MemoryFill
+                    }
+                    sub => return Err(format!("unsupported instruction 0xfc {sub}")),
+                },
+                other => return Err(format!("unsupported instruction 0x{other:02x}")),
+            };
+            code.push(instr);
+        }
+        if !body.is_empty() {
+            return Err("function body continues after its end".to_string());
+        }
+        self.check_operands(ty, &code, function_types)?;
+        Ok(Function {
+            type_index,
+            locals: locals as usize,
+            code,
+        })
+    }
+
+    /// Check that every instruction of a decoded body finds the operands it
+    /// takes within its own block, and that blocks, branches and the body
+    /// leave as many values as their types say. Execution relies on this to
+    /// never reach below a frame's locals; the types of the values are not
+    /// checked.
+    fn check_operands(&self, ty: &FuncType, code: &[Instr], function_types: &[u32]) -> Result<(), String> {
+        let results = ty.results.len();
+        let mut operands = Operands {
+            height: 0,
+            blocks: vec![OperandBlock::new(0, 0, results, results, false)],
+        };
+        let signature = |type_index: u32| {
+            let ty = &self.types[type_index as usize];
+            (ty.params.len(), ty.results.len())
+        };
+        for instr in code {
+            match instr {
+                Instr::Unreachable => operands.unreachable(),
+                Instr::Nop => {}
+                &Instr::Block { params, results, .. } => operands.enter(params as usize, results as usize, false)?,
+                &Instr::Loop { params, results } => operands.enter(params as usize, results as usize, true)?,
+                &Instr::If { params, results, .. } => {
+                    operands.pop(1)?;
+                    operands.enter(params as usize, results as usize, false)?;
+                    operands.top().without_else = true;
+                }
+                Instr::Else { .. } => {
+                    let block = operands.leave()?;
+                    operands.height = block.base + block.params;
+                    operands.blocks.push(OperandBlock { without_else: false, ..block });
+                }
+                Instr::End => {
+                    let block = operands.leave()?;
+                    if block.without_else && block.params != block.results {
+                        return Err("if without else must leave its parameters".to_string());
+                    }
+                    operands.height = block.base + block.results;
+                }
+                &Instr::Br(depth) => {
+                    operands.pop(operands.label(depth))?;
+                    operands.unreachable();
+                }
+                &Instr::BrIf(depth) => {
+                    operands.pop(1)?;
+                    let arity = operands.label(depth);
+                    operands.pop(arity)?;
+                    operands.push(arity);
+                }
+                Instr::BrTable(targets, default) => {
+                    operands.pop(1)?;
+                    let arity = operands.label(*default);
+                    if targets.iter().any(|&depth| operands.label(depth) != arity) {
+                        return Err("branch table targets take different numbers of values".to_string());
+                    }
+                    operands.pop(arity)?;
+                    operands.unreachable();
+                }
+                Instr::Return => {
+                    operands.pop(results)?;
+                    operands.unreachable();
+                }
+                &Instr::Call(function) => {
+                    let (params, results) = signature(function_types[function as usize]);
+                    operands.pop(params)?;
+                    operands.push(results);
+                }
+                &Instr::CallIndirect(type_index) => {
+                    let (params, results) = signature(type_index);
+                    operands.pop(params + 1)?;
+                    operands.push(results);
+                }
+                Instr::Drop | Instr::LocalSet(_) | Instr::GlobalSet(_) => operands.pop(1)?,
+                Instr::Select => {
+                    operands.pop(3)?;
+                    operands.push(1);
+                }
+                Instr::LocalGet(_) | Instr::GlobalGet(_) | Instr::MemorySize | Instr::Const(_) => operands.push(1),
+                Instr::LocalTee(_) | Instr::Load(..) | Instr::MemoryGrow | Instr::TruncSat(_) => {
+                    operands.pop(1)?;
+                    operands.push(1);
+                }
+                Instr::Store(..) => operands.pop(2)?,
+                Instr::MemoryCopy | Instr::MemoryFill => operands.pop(3)?,
+                &Instr::Numeric(opcode) => {
+                    operands.pop(if takes_one(opcode) { 1 } else { 2 })?;
+                    operands.push(1);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Parameter and result counts of a block
+    fn block_type(&self, body: &mut Reader) -> Result<(u32, u32), String> {
+        match body.bytes.get(body.pos) {
+            Some(0x40) => {
+                body.pos += 1;
+                Ok((0, 0))
+            }
+            Some(0x7C..=0x7F) => {
+                body.val_type()?;
+                Ok((0, 1))
+            }
+            _ => {
+                let index = body.leb(33, true)?;
+                let ty = usize::try_from(index).ok().and_then(|index| self.types.get(index)).ok_or("unknown block type")?;
+                Ok((ty.params.len() as u32, ty.results.len() as u32))
+            }
+        }
+    }
+
+    fn needs_memory(&self) -> Result<(), String> {
+        self.memory.map(|_| ()).ok_or_else(|| "memory instruction without a memory".to_string())
+    }
+
+    /// Whether the module exports a function `name` taking and returning
+    /// values of these types
+    pub fn exports_function(&self, name: &str, params: &[ValType], results: &[ValType]) -> bool {
+        match self.exports.get(name) {
+            Some(&Export::Function(index)) => {
+                let ty = &self.types[self.functions[index as usize].type_index as usize];
+                ty.params == params && ty.results == results
+            }
+            _ => false,
+        }
+    }
+
+    pub fn exports_memory(&self) -> bool {
+        self.exports.values().any(|export| *export == Export::Memory)
+    }
+}
+
+/// Operand stack heights while checking a body
+struct Operands {
+    height: usize,
+    /// Enclosing blocks, the body itself first
+    blocks: Vec<OperandBlock>,
+}
+
+#[derive(Clone, Copy)]
+struct OperandBlock {
+    /// Height below the block's parameters
+    base: usize,
+    params: usize,
+    results: usize,
+    /// Values a branch to the block carries
+    label: usize,
+    /// Past an unconditional branch, where any operands may be taken
+    unreachable: bool,
+    /// An `if` that has had no `else` so far
+    without_else: bool,
+}
+
+impl OperandBlock {
+    fn new(base: usize, params: usize, results: usize, label: usize, without_else: bool) -> Self {
+        Self {
+            base,
+            params,
+            results,
+            label,
+            unreachable: false,
+            without_else,
+        }
+    }
+}
+
+impl Operands {
+    fn top(&mut self) -> &mut OperandBlock {
+        self.blocks.last_mut().expect("the body is a block")
+    }
+
+    fn pop(&mut self, count: usize) -> Result<(), String> {
+        let block = *self.top();
+        if self.height >= block.base + count {
+            self.height -= count;
+        } else if block.unreachable {
+            self.height = block.base;
+        } else {
+            return Err("an instruction takes more operands than its block has".to_string());
+        }
+        Ok(())
+    }
+
+    fn push(&mut self, count: usize) {
+        self.height += count;
+    }
+
+    fn unreachable(&mut self) {
+        self.height = self.top().base;
+        self.top().unreachable = true;
+    }
+
+    /// Start a block taking its parameters from the stack
+    fn enter(&mut self, params: usize, results: usize, is_loop: bool) -> Result<(), String> {
+        self.pop(params)?;
+        let label = if is_loop { params } else { results };
+        self.blocks.push(OperandBlock::new(self.height, params, results, label, false));
+        self.push(params);
+        Ok(())
+    }
+
+    /// End the innermost block, which must leave exactly its results
+    fn leave(&mut self) -> Result<OperandBlock, String> {
+        let block = *self.top();
+        self.pop(block.results)?;
+        if self.height != block.base {
+            return Err("a block leaves more values than its results".to_string());
+        }
+        self.blocks.pop();
+        Ok(block)
+    }
+
+    /// Values a branch `depth` blocks out carries
+    fn label(&self, depth: u32) -> usize {
+        self.blocks[self.blocks.len() - 1 - depth as usize].label
+    }
+}
+
+/// Where a branch to a label continues
+struct Label {
+    target: usize,
+    /// Operand stack height below the block's parameters
+    height: usize,
+    /// Values the branch carries: a block's results, a loop's parameters
+    arity: usize,
+    is_loop: bool,
+}
+
+struct Frame {
+    function: u32,
+    pc: usize,
+    /// Start of the locals on the operand stack; the operands follow them
+    locals: usize,
+    /// Labels of enclosing calls below this
+    labels: usize,
+}
+
+/// A module with its own memory, table and globals
+pub struct Instance<'m> {
+    module: &'m Module,
+    memory: Vec<u8>,
+    max_pages: usize,
+    table: Vec<Option<u32>>,
+    globals: Vec<u64>,
+}
+
+impl<'m> Instance<'m> {
+    /// Set up memory, table and globals, then run the start function.
+    /// Memory may grow to `max_pages` or the module's own maximum.
+    pub fn new(module: &'m Module, max_pages: u32, fuel: &mut Fuel) -> Result<Self, Trap> {
+        let limits = module.memory.unwrap_or(Limits { min: 0, max: Some(0) });
+        let max_pages = limits.max.unwrap_or(u32::MAX).min(max_pages) as usize;
+        if limits.min as usize > max_pages {
+            return Err(Trap::MemoryOutOfBounds);
+        }
+        let table_size = module.table.map_or(0, |table| table.min as usize);
+        let mut instance = Self {
+            module,
+            memory: vec![0; limits.min as usize * PAGE_SIZE],
+            max_pages,
+            table: vec![None; table_size],
+            globals: module.globals.iter().map(|global| global.init).collect(),
+        };
+        for (offset, functions) in &module.elements {
+            let start = *offset as usize;
+            let slots = instance.table.get_mut(start..start + functions.len()).ok_or(Trap::BadIndirectCall)?;
+            slots.iter_mut().zip(functions).for_each(|(slot, &function)| *slot = Some(function));
+        }
+        for (offset, bytes) in &module.data {
+            let start = *offset as usize;
+            instance.memory.get_mut(start..start + bytes.len()).ok_or(Trap::MemoryOutOfBounds)?.copy_from_slice(bytes);
+        }
+        if let Some(start) = module.start {
+            instance.execute(start, Vec::new(), fuel)?;
+        }
+        Ok(instance)
+    }
+
+    /// Call the exported function `name`
+    pub fn call(&mut self, name: &str, args: &[Val], fuel: &mut Fuel) -> Result<Vec<Val>, Trap> {
+        let Some(&Export::Function(index)) = self.module.exports.get(name) else {
+            return Err(Trap::BadExport(format!("no function `{name}` is exported")));
+        };
+        let ty = &self.module.types[self.module.functions[index as usize].type_index as usize];
+        if args.len() != ty.params.len() || args.iter().zip(&ty.params).any(|(arg, ty)| arg.ty() != *ty) {
+            return Err(Trap::BadExport(format!("`{name}` takes other arguments")));
+        }
+        let results = self.execute(index, args.iter().map(|arg| arg.to_raw()).collect(), fuel)?;
+        Ok(results.into_iter().zip(&ty.results).map(|(raw, &ty)| Val::from_raw(raw, ty)).collect())
+    }
+
+    pub fn memory(&self) -> &[u8] {
+        &self.memory
+    }
+
+    pub fn memory_mut(&mut self) -> &mut [u8] {
+        &mut self.memory
+    }
+
+    /// Run function `index` with `stack` holding its arguments, returning
+    /// its results
+    fn execute(&mut self, index: u32, mut stack: Vec<u64>, fuel: &mut Fuel) -> Result<Vec<u64>, Trap> {
+        let module = self.module;
+        let mut frames: Vec<Frame> = Vec::new();
+        let mut labels: Vec<Label> = Vec::new();
+        self.enter(index, &mut stack, &mut frames, &labels)?;
+        let mut until_interrupt = INTERRUPT_INTERVAL;
+
+        loop {
+            let Some(frame) = frames.last_mut() else {
+                return Ok(stack);
+            };
+            let instr = &module.functions[frame.function as usize].code[frame.pc];
+            frame.pc += 1;
+            let (pc, locals) = (frame.pc, frame.locals);
+
+            fuel.remaining = fuel.remaining.checked_sub(1).ok_or(Trap::OutOfFuel)?;
+            until_interrupt -= 1;
+            if until_interrupt == 0 {
+                until_interrupt = INTERRUPT_INTERVAL;
+                if (fuel.interrupt)() {
+                    return Err(Trap::Interrupted);
+                }
+            }
+
+            match instr {
+                Instr::Unreachable => return Err(Trap::Unreachable),
+                Instr::Nop => {}
+                &Instr::Block { params, results, end } => labels.push(Label {
+                    target: end as usize + 1,
+                    height: height(&stack, params)?,
+                    arity: results as usize,
+                    is_loop: false,
+                }),
+                &Instr::Loop { params, .. } => labels.push(Label {
+                    target: pc,
+                    height: height(&stack, params)?,
+                    arity: params as usize,
+                    is_loop: true,
+                }),
+                &Instr::If { params, results, other, end } => {
+                    let condition = pop(&mut stack)? as u32;
+                    let label = Label {
+                        target: end as usize + 1,
+                        height: height(&stack, params)?,
+                        arity: results as usize,
+                        is_loop: false,
+                    };
+                    if condition != 0 {
+                        labels.push(label);
+                    } else if let Some(other) = other {
+                        labels.push(label);
+                        jump(&mut frames, other as usize + 1);
+                    } else {
+                        jump(&mut frames, end as usize + 1);
+                    }
+                }
+                &Instr::Else { end } => {
+                    labels.pop();
+                    jump(&mut frames, end as usize + 1);
+                }
+                Instr::End => {
+                    if labels.len() > frame.labels {
+                        labels.pop();
+                    } else {
+                        self.leave(&mut stack, &mut frames, &mut labels)?;
+                    }
+                }
+                &Instr::Br(depth) => self.branch(depth, &mut stack, &mut frames, &mut labels)?,
+                &Instr::BrIf(depth) => {
+                    if pop(&mut stack)? as u32 != 0 {
+                        self.branch(depth, &mut stack, &mut frames, &mut labels)?;
+                    }
+                }
+                Instr::BrTable(targets, default) => {
+                    let index = pop(&mut stack)? as u32 as usize;
+                    let depth = targets.get(index).copied().unwrap_or(*default);
+                    self.branch(depth, &mut stack, &mut frames, &mut labels)?;
+                }
+                Instr::Return => self.leave(&mut stack, &mut frames, &mut labels)?,
+                &Instr::Call(function) => self.enter(function, &mut stack, &mut frames, &labels)?,
+                &Instr::CallIndirect(type_index) => {
+                    let slot = pop(&mut stack)? as u32 as usize;
+                    let function = self.table.get(slot).copied().flatten().ok_or(Trap::BadIndirectCall)?;
+                    let actual = &module.types[module.functions[function as usize].type_index as usize];
+                    if *actual != module.types[type_index as usize] {
+                        return Err(Trap::BadIndirectCall);
+                    }
+                    self.enter(function, &mut stack, &mut frames, &labels)?;
+                }
+                Instr::Drop => {
+                    pop(&mut stack)?;
+                }
+                Instr::Select => {
+                    let condition = pop(&mut stack)? as u32;
+                    let second = pop(&mut stack)?;
+                    let first = pop(&mut stack)?;
+                    stack.push(if condition != 0 { first } else { second });
+                }
+                &Instr::LocalGet(index) => {
+                    let value = stack[locals + index as usize];
+                    stack.push(value);
+                }
+                &Instr::LocalSet(index) => {
+                    let value = pop(&mut stack)?;
+                    stack[locals + index as usize] = value;
+                }
+                &Instr::LocalTee(index) => {
+                    let value = *stack.last().ok_or(Trap::StackUnderflow)?;
+                    stack[locals + index as usize] = value;
+                }
+                &Instr::GlobalGet(index) => stack.push(self.globals[index as usize]),
+                &Instr::GlobalSet(index) => self.globals[index as usize] = pop(&mut stack)?,
+                &Instr::Load(opcode, offset) => {
+                    let address = pop(&mut stack)? as u32;
+                    stack.push(self.load(opcode, address, offset)?);
+                }
+                &Instr::Store(opcode, offset) => {
+                    let value = pop(&mut stack)?;
+                    let address = pop(&mut stack)? as u32;
+                    self.store(opcode, address, offset, value)?;
+                }
+                Instr::MemorySize => stack.push((self.memory.len() / PAGE_SIZE) as u64),
+                Instr::MemoryGrow => {
+                    let delta = pop(&mut stack)? as u32 as usize;
+                    let pages = self.memory.len() / PAGE_SIZE;
+                    if pages + delta <= self.max_pages {
+                        self.memory.resize((pages + delta) * PAGE_SIZE, 0);
+                        stack.push(pages as u64);
+                    } else {
+                        stack.push(u64::from(u32::MAX));
+                    }
+                }
+                Instr::MemoryCopy => {
+                    let len = pop(&mut stack)? as u32 as usize;
+                    let source = pop(&mut stack)? as u32 as usize;
+                    let destination = pop(&mut stack)? as u32 as usize;
+                    if source + len > self.memory.len() || destination + len > self.memory.len() {
+                        return Err(Trap::MemoryOutOfBounds);
+                    }
+                    self.memory.copy_within(source..source + len, destination);
+                }
+                Instr::MemoryFill => {
+                    let len = pop(&mut stack)? as u32 as usize;
+                    let value = pop(&mut stack)? as u8;
+                    let destination = pop(&mut stack)? as u32 as usize;
+                    self.memory.get_mut(destination..destination + len).ok_or(Trap::MemoryOutOfBounds)?.fill(value);
+                }
+                &Instr::Const(value) => stack.push(value),
+                &Instr::Numeric(opcode) => numeric(opcode, &mut stack)?,
+                &Instr::TruncSat(sub) => {
+                    let value = pop(&mut stack)?;
+                    let (float, signed, wide) = match sub {
+                        0 | 1 | 4 | 5 => (f64::from(f32::from_bits(value as u32)), sub % 2 == 0, sub >= 4),
+                        _ => (f64::from_bits(value), sub % 2 == 0, sub >= 4),
+                    };
+                    // `as` saturates and sends NaN to zero, as these do
+                    stack.push(match (signed, wide) {
+                        (true, false) => float as i32 as u32 as u64,
+                        (false, false) => float as u32 as u64,
+                        (true, true) => float as i64 as u64,
+                        (false, true) => float as u64,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Push a frame for a call of `index`, whose arguments are on the stack
+    fn enter(&self, index: u32, stack: &mut Vec<u64>, frames: &mut Vec<Frame>, labels: &[Label]) -> Result<(), Trap> {
+        if frames.len() >= MAX_CALL_DEPTH {
+            return Err(Trap::CallStackExhausted);
+        }
+        let function = &self.module.functions[index as usize];
+        let ty = &self.module.types[function.type_index as usize];
+        let locals = height(stack, ty.params.len() as u32)?;
+        stack.resize(stack.len() + function.locals, 0);
+        frames.push(Frame {
+            function: index,
+            pc: 0,
+            locals,
+            labels: labels.len(),
+        });
+        Ok(())
+    }
+
+    /// Return from the current function, leaving its results in place of
+    /// its locals and operands
+    fn leave(&self, stack: &mut Vec<u64>, frames: &mut Vec<Frame>, labels: &mut Vec<Label>) -> Result<(), Trap> {
+        let frame = frames.pop().ok_or(Trap::StackUnderflow)?;
+        let function = &self.module.functions[frame.function as usize];
+        let results = self.module.types[function.type_index as usize].results.len();
+        keep_top(stack, frame.locals, results)?;
+        labels.truncate(frame.labels);
+        Ok(())
+    }
+
+    fn branch(
+        &self,
+        depth: u32,
+        stack: &mut Vec<u64>,
+        frames: &mut Vec<Frame>,
+        labels: &mut Vec<Label>,
+    ) -> Result<(), Trap> {
+        let frame = frames.last_mut().ok_or(Trap::StackUnderflow)?;
+        let depth = depth as usize;
+        // The outermost label is the function body's
+        if depth == labels.len() - frame.labels {
+            return self.leave(stack, frames, labels);
+        }
+        let index = labels.len() - 1 - depth;
+        let label = &labels[index];
+        keep_top(stack, label.height, label.arity)?;
+        frame.pc = label.target;
+        labels.truncate(if label.is_loop { index + 1 } else { index });
+        Ok(())
+    }
+
+    fn address(&self, address: u32, offset: u32, size: usize) -> Result<usize, Trap> {
+        let start = address as usize + offset as usize;
+        if start + size > self.memory.len() {
+            return Err(Trap::MemoryOutOfBounds);
+        }
+        Ok(start)
+    }
+
+    fn load(&self, opcode: u8, address: u32, offset: u32) -> Result<u64, Trap> {
+        let size = match opcode {
+            0x29 | 0x2B => 8,
+            0x28 | 0x2A | 0x34 | 0x35 => 4,
+            0x2E | 0x2F | 0x32 | 0x33 => 2,
+            _ => 1,
+        };
+        let start = self.address(address, offset, size)?;
+        let mut bytes = [0u8; 8];
+        bytes[..size].copy_from_slice(&self.memory[start..start + size]);
+        let raw = u64::from_le_bytes(bytes);
+        Ok(match opcode {
+            0x2C => raw as i8 as i32 as u32 as u64,
+            0x2E => raw as i16 as i32 as u32 as u64,
+            0x30 => raw as i8 as i64 as u64,
+            0x32 => raw as i16 as i64 as u64,
+            0x34 => raw as i32 as i64 as u64,
+            _ => raw,
+        })
+    }
+
+    fn store(&mut self, opcode: u8, address: u32, offset: u32, value: u64) -> Result<(), Trap> {
+        let size = match opcode {
+            0x37 | 0x39 => 8,
+            0x36 | 0x38 | 0x3E => 4,
+            0x3B | 0x3D => 2,
+            _ => 1,
+        };
+        let start = self.address(address, offset, size)?;
+        self.memory[start..start + size].copy_from_slice(&value.to_le_bytes()[..size]);
+        Ok(())
+    }
+}
+
+/// Continue the current function at instruction `pc`
+fn jump(frames: &mut [Frame], pc: usize) {
+    if let Some(frame) = frames.last_mut() {
+        frame.pc = pc;
+    }
+}
+
+fn pop(stack: &mut Vec<u64>) -> Result<u64, Trap> {
+    stack.pop().ok_or(Trap::StackUnderflow)
+}
+
+/// Stack height below the top `count` values
+fn height(stack: &[u64], count: u32) -> Result<usize, Trap> {
+    stack.len().checked_sub(count as usize).ok_or(Trap::StackUnderflow)
+}
+
+/// Move the top `count` values down to `base`, dropping what was between
+fn keep_top(stack: &mut Vec<u64>, base: usize, count: usize) -> Result<(), Trap> {
+    let top = height(stack, count as u32)?;
+    if top < base {
+        return Err(Trap::StackUnderflow);
+    }
+    stack.copy_within(top.., base);
+    stack.truncate(base + count);
+    Ok(())
+}
+
+fn f32_of(raw: u64) -> f32 {
+    f32::from_bits(raw as u32)
+}
+
+fn f32_raw(value: f32) -> u64 {
+    value.to_bits() as u64
+}
+
+/// WebAssembly's `min`: NaN if either is, and −0 below +0
+fn wasm_min(a: f64, b: f64) -> f64 {
+    if a.is_nan() || b.is_nan() {
+        f64::NAN
+    } else if a == b {
+        if a.is_sign_negative() {
+            a
+        } else {
+            b
+        }
+    } else {
+        a.min(b)
+    }
+}
+
+fn wasm_max(a: f64, b: f64) -> f64 {
+    if a.is_nan() || b.is_nan() {
+        f64::NAN
+    } else if a == b {
+        if a.is_sign_positive() {
+            a
+        } else {
+            b
+        }
+    } else {
+        a.max(b)
+    }
+}
+
+/// Truncate toward zero into the integer range (lower, upper), exclusive,
+/// trapping on NaN and values outside it
+fn truncate(value: f64, lower: f64, upper: f64) -> Result<f64, Trap> {
+    if value.is_nan() {
+        return Err(Trap::InvalidConversion);
+    }
+    let truncated = value.trunc();
+    if truncated <= lower || truncated >= upper {
+        return Err(Trap::IntegerOverflow);
+    }
+    Ok(truncated)
+}
+
+/// Whether a numeric instruction takes one operand: tests for zero, unary
+/// operations and conversions do, the rest two
+fn takes_one(opcode: u8) -> bool {
+    matches!(opcode, 0x45 | 0x50 | 0x67..=0x69 | 0x79..=0x7B | 0x8B..=0x91 | 0x99..=0x9F | 0xA7..=0xC4)
+}
+
+/// Execute a single-byte numeric instruction
+fn numeric(opcode: u8, stack: &mut Vec<u64>) -> Result<(), Trap> {
+    let b = pop(stack)?;
+    let a = if takes_one(opcode) { 0 } else { pop(stack)? };
+    let (a32, b32) = (a as u32, b as u32);
+    let (f32a, f32b) = (f64::from(f32_of(a)), f64::from(f32_of(b)));
+    let (f64a, f64b) = (f64::from_bits(a), f64::from_bits(b));
+    let flag = |condition: bool| u64::from(condition);
+
+    let result = match opcode {
+        0x45 => flag(b32 == 0),
+        0x46 => flag(a32 == b32),
+        0x47 => flag(a32 != b32),
+        0x48 => flag((a32 as i32) < b32 as i32),
+        0x49 => flag(a32 < b32),
+        0x4A => flag(a32 as i32 > b32 as i32),
+        0x4B => flag(a32 > b32),
+        0x4C => flag(a32 as i32 <= b32 as i32),
+        0x4D => flag(a32 <= b32),
+        0x4E => flag(a32 as i32 >= b32 as i32),
+        0x4F => flag(a32 >= b32),
+        0x50 => flag(b == 0),
+        0x51 => flag(a == b),
+        0x52 => flag(a != b),
+        0x53 => flag((a as i64) < b as i64),
+        0x54 => flag(a < b),
+        0x55 => flag(a as i64 > b as i64),
+        0x56 => flag(a > b),
+        0x57 => flag(a as i64 <= b as i64),
+        0x58 => flag(a <= b),
+        0x59 => flag(a as i64 >= b as i64),
+        0x5A => flag(a >= b),
+        // f32 values widen to f64 exactly, so comparisons agree
+        0x5B => flag(f32a == f32b),
+        0x5C => flag(f32a != f32b),
+        0x5D => flag(f32a < f32b),
+        0x5E => flag(f32a > f32b),
+        0x5F => flag(f32a <= f32b),
+        0x60 => flag(f32a >= f32b),
+        0x61 => flag(f64a == f64b),
+        0x62 => flag(f64a != f64b),
+        0x63 => flag(f64a < f64b),
+        0x64 => flag(f64a > f64b),
+        0x65 => flag(f64a <= f64b),
+        0x66 => flag(f64a >= f64b),
+
+        0x67 => u64::from(b32.leading_zeros()),
+        0x68 => u64::from(b32.trailing_zeros()),
+        0x69 => u64::from(b32.count_ones()),
+        0x6A => u64::from(a32.wrapping_add(b32)),
+        0x6B => u64::from(a32.wrapping_sub(b32)),
+        0x6C => u64::from(a32.wrapping_mul(b32)),
+        0x6D => {
+            let (a, b) = (a32 as i32, b32 as i32);
+            if b == 0 {
+                return Err(Trap::DivideByZero);
+            }
+            u64::from(a.checked_div(b).ok_or(Trap::IntegerOverflow)? as u32)
+        }
+        0x6E => u64::from(a32.checked_div(b32).ok_or(Trap::DivideByZero)?),
+        0x6F => {
+            if b32 == 0 {
+                return Err(Trap::DivideByZero);
+            }
+            u64::from((a32 as i32).wrapping_rem(b32 as i32) as u32)
+        }
+        0x70 => u64::from(a32.checked_rem(b32).ok_or(Trap::DivideByZero)?),
+        0x71 => u64::from(a32 & b32),
+        0x72 => u64::from(a32 | b32),
+        0x73 => u64::from(a32 ^ b32),
+        0x74 => u64::from(a32.wrapping_shl(b32)),
+        0x75 => u64::from((a32 as i32).wrapping_shr(b32) as u32),
+        0x76 => u64::from(a32.wrapping_shr(b32)),
+        0x77 => u64::from(a32.rotate_left(b32 % 32)),
+        0x78 => u64::from(a32.rotate_right(b32 % 32)),
+
+        0x79 => u64::from(b.leading_zeros()),
+        0x7A => u64::from(b.trailing_zeros()),
+        0x7B => u64::from(b.count_ones()),
+        0x7C => a.wrapping_add(b),
+        0x7D => a.wrapping_sub(b),
+        0x7E => a.wrapping_mul(b),
+        0x7F => {
+            if b == 0 {
+                return Err(Trap::DivideByZero);
+            }
+            (a as i64).checked_div(b as i64).ok_or(Trap::IntegerOverflow)? as u64
+        }
+        0x80 => a.checked_div(b).ok_or(Trap::DivideByZero)?,
+        0x81 => {
+            if b == 0 {
+                return Err(Trap::DivideByZero);
+            }
+            (a as i64).wrapping_rem(b as i64) as u64
+        }
+        0x82 => a.checked_rem(b).ok_or(Trap::DivideByZero)?,
+        0x83 => a & b,
+        0x84 => a | b,
+        0x85 => a ^ b,
+        0x86 => a.wrapping_shl(b as u32),
+        0x87 => (a as i64).wrapping_shr(b as u32) as u64,
+        0x88 => a.wrapping_shr(b as u32),
+        0x89 => a.rotate_left((b % 64) as u32),
+        0x8A => a.rotate_right((b % 64) as u32),
+
+        0x8B => b & 0x7FFF_FFFF,
+        0x8C => (b ^ 0x8000_0000) & 0xFFFF_FFFF,
+        0x8D => f32_raw(f32_of(b).ceil()),
+        0x8E => f32_raw(f32_of(b).floor()),
+        0x8F => f32_raw(f32_of(b).trunc()),
+        0x90 => f32_raw(f32_of(b).round_ties_even()),
+        0x91 => f32_raw(f32_of(b).sqrt()),
+        0x92 => f32_raw(f32_of(a) + f32_of(b)),
+        0x93 => f32_raw(f32_of(a) - f32_of(b)),
+        0x94 => f32_raw(f32_of(a) * f32_of(b)),
+        0x95 => f32_raw(f32_of(a) / f32_of(b)),
+        0x96 => f32_raw(wasm_min(f32a, f32b) as f32),
+        0x97 => f32_raw(wasm_max(f32a, f32b) as f32),
+        0x98 => (a & 0x7FFF_FFFF) | (b & 0x8000_0000),
+
+        0x99 => b & !(1 << 63),
+        0x9A => b ^ (1 << 63),
+        0x9B => f64b.ceil().to_bits(),
+        0x9C => f64b.floor().to_bits(),
+        0x9D => f64b.trunc().to_bits(),
+        0x9E => f64b.round_ties_even().to_bits(),
+        0x9F => f64b.sqrt().to_bits(),
+        0xA0 => (f64a + f64b).to_bits(),
+        0xA1 => (f64a - f64b).to_bits(),
+        0xA2 => (f64a * f64b).to_bits(),
+        0xA3 => (f64a / f64b).to_bits(),
+        0xA4 => wasm_min(f64a, f64b).to_bits(),
+        0xA5 => wasm_max(f64a, f64b).to_bits(),
+        0xA6 => (a & !(1 << 63)) | (b & (1 << 63)),
+
+        0xA7 => u64::from(b32),
+        0xA8 => truncate(f32b, -2_147_483_649.0, 2_147_483_648.0)? as i32 as u32 as u64,
+        0xA9 => truncate(f32b, -1.0, 4_294_967_296.0)? as u32 as u64,
+        0xAA => truncate(f64b, -2_147_483_649.0, 2_147_483_648.0)? as i32 as u32 as u64,
+        0xAB => truncate(f64b, -1.0, 4_294_967_296.0)? as u32 as u64,
+        0xAC => b32 as i32 as i64 as u64,
+        0xAD => u64::from(b32),
+        0xAE => truncate_i64(f32b)? as u64,
+        0xAF => truncate(f32b, -1.0, 18_446_744_073_709_551_616.0)? as u64,
+        0xB0 => truncate_i64(f64b)? as u64,
+        0xB1 => truncate(f64b, -1.0, 18_446_744_073_709_551_616.0)? as u64,
+        0xB2 => f32_raw(b32 as i32 as f32),
+        0xB3 => f32_raw(b32 as f32),
+        0xB4 => f32_raw(b as i64 as f32),
+        0xB5 => f32_raw(b as f32),
+        0xB6 => f32_raw(f64b as f32),
+        0xB7 => f64::from(b32 as i32).to_bits(),
+        0xB8 => f64::from(b32).to_bits(),
+        0xB9 => (b as i64 as f64).to_bits(),
+        0xBA => (b as f64).to_bits(),
+        0xBB => f32b.to_bits(),
+        // Reinterpretations: the stack holds the bits already
+        0xBC..=0xBF => b,
+        0xC0 => b32 as i8 as i32 as u32 as u64,
+        0xC1 => b32 as i16 as i32 as u32 as u64,
+        0xC2 => b as i8 as i64 as u64,
+        0xC3 => b as i16 as i64 as u64,
+        0xC4 => b as i32 as i64 as u64,
+        _ => return Err(Trap::Unreachable),
+    };
+    stack.push(result);
+    Ok(())
+}
+
+/// Truncate to i64, trapping outside [-2⁶³, 2⁶³)
+fn truncate_i64(value: f64) -> Result<i64, Trap> {
+    if value.is_nan() {
+        return Err(Trap::InvalidConversion);
+    }
+    let truncated = value.trunc();
+    if !(-9_223_372_036_854_775_808.0..9_223_372_036_854_775_808.0).contains(&truncated) {
+        return Err(Trap::IntegerOverflow);
+    }
+    Ok(truncated as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const I32: u8 = 0x7F;
+    const I64: u8 = 0x7E;
+    const F64: u8 = 0x7C;
+
+    fn leb(mut value: u32) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        loop {
+            let byte = (value & 0x7F) as u8;
+            value >>= 7;
+            if value == 0 {
+                bytes.push(byte);
+                return bytes;
+            }
+            bytes.push(byte | 0x80);
+        }
+    }
+
+    fn vector(items: &[Vec<u8>]) -> Vec<u8> {
+        let mut bytes = leb(items.len() as u32);
+        items.iter().for_each(|item| bytes.extend(item));
+        bytes
+    }
+
+    fn wasm(sections: &[(u8, Vec<u8>)]) -> Vec<u8> {
+        let mut bytes = b"\0asm\x01\0\0\0".to_vec();
+        for (id, contents) in sections {
+            bytes.push(*id);
+            bytes.extend(leb(contents.len() as u32));
+            bytes.extend(contents);
+        }
+        bytes
+    }
+
+    fn func_type(params: &[u8], results: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![0x60];
+        bytes.extend(vector(&params.iter().map(|&ty| vec![ty]).collect::<Vec<_>>()));
+        bytes.extend(vector(&results.iter().map(|&ty| vec![ty]).collect::<Vec<_>>()));
+        bytes
+    }
+
+    fn export(name: &str, kind: u8, index: u32) -> Vec<u8> {
+        let mut bytes = leb(name.len() as u32);
+        bytes.extend(name.as_bytes());
+        bytes.push(kind);
+        bytes.extend(leb(index));
+        bytes
+    }
+
+    /// Function body declaring `locals` extra i32 locals
+    fn body(locals: u32, code: &[u8]) -> Vec<u8> {
+        let mut bytes = if locals == 0 { vec![0] } else { [vec![1], leb(locals), vec![I32]].concat() };
+        bytes.extend(code);
+        [leb(bytes.len() as u32), bytes].concat()
+    }
+
+    /// Module of functions of one type each, all exported as `f0`, `f1`…
+    fn functions(functions: &[(Vec<u8>, Vec<u8>)]) -> Vec<u8> {
+        let types: Vec<_> = functions.iter().map(|(ty, _)| ty.clone()).collect();
+        let indices: Vec<_> = (0..functions.len() as u32).map(leb).collect();
+        let exports: Vec<_> = (0..functions.len() as u32).map(|index| export(&format!("f{index}"), 0, index)).collect();
+        let bodies: Vec<_> = functions.iter().map(|(_, body)| body.clone()).collect();
+        wasm(&[(1, vector(&types)), (3, vector(&indices)), (7, vector(&exports)), (10, vector(&bodies))])
+    }
+
+    fn never() -> bool {
+        false
+    }
+
+    fn call(module: &Module, name: &str, args: &[Val]) -> Result<Vec<Val>, Trap> {
+        let mut fuel = Fuel { remaining: 10_000_000, interrupt: &never };
+        Instance::new(module, 16, &mut fuel)?.call(name, args, &mut fuel)
+    }
+
+    #[test]
+    fn computes_integer_arithmetic() {
+        let module = Module::decode(&functions(&[
+            (func_type(&[I32, I32], &[I32]), body(0, &[0x20, 0, 0x20, 1, 0x6A, 0x0B])),
+            (func_type(&[I32, I32], &[I32]), body(0, &[0x20, 0, 0x20, 1, 0x6D, 0x0B])),
+            (func_type(&[I64, I64], &[I64]), body(0, &[0x20, 0, 0x20, 1, 0x7E, 0x0B])),
+            (func_type(&[I32], &[I64]), body(0, &[0x20, 0, 0xAC, 0x0B])),
+        ]))
+        .unwrap();
+
+        assert_eq!(call(&module, "f0", &[Val::I32(i32::MAX), Val::I32(1)]), Ok(vec![Val::I32(i32::MIN)]));
+        assert_eq!(call(&module, "f1", &[Val::I32(-7), Val::I32(2)]), Ok(vec![Val::I32(-3)]));
+        assert_eq!(call(&module, "f1", &[Val::I32(1), Val::I32(0)]), Err(Trap::DivideByZero));
+        assert_eq!(call(&module, "f1", &[Val::I32(i32::MIN), Val::I32(-1)]), Err(Trap::IntegerOverflow));
+        assert_eq!(call(&module, "f2", &[Val::I64(1 << 40), Val::I64(3)]), Ok(vec![Val::I64(3 << 40)]));
+        assert_eq!(call(&module, "f3", &[Val::I32(-5)]), Ok(vec![Val::I64(-5)]));
+    }
+
+    #[test]
+    fn follows_float_semantics() {
+        let module = Module::decode(&functions(&[
+            (func_type(&[F64], &[F64]), body(0, &[0x20, 0, 0x9E, 0x0B])),
+            (func_type(&[F64, F64], &[F64]), body(0, &[0x20, 0, 0x20, 1, 0xA4, 0x0B])),
+            (func_type(&[F64], &[I32]), body(0, &[0x20, 0, 0xAA, 0x0B])),
+            (func_type(&[F64], &[I32]), body(0, &[0x20, 0, 0xFC, 0x02, 0x0B])),
+        ]))
+        .unwrap();
+
+        assert_eq!(call(&module, "f0", &[Val::F64(2.5)]), Ok(vec![Val::F64(2.0)]));
+        assert_eq!(call(&module, "f0", &[Val::F64(-3.5)]), Ok(vec![Val::F64(-4.0)]));
+        let Ok(result) = call(&module, "f1", &[Val::F64(1.0), Val::F64(f64::NAN)]) else { panic!() };
+        assert!(matches!(result[..], [Val::F64(value)] if value.is_nan()));
+        let Ok(result) = call(&module, "f1", &[Val::F64(0.0), Val::F64(-0.0)]) else { panic!() };
+        assert!(matches!(result[..], [Val::F64(value)] if value == 0.0 && value.is_sign_negative()));
+        assert_eq!(call(&module, "f2", &[Val::F64(-2.9)]), Ok(vec![Val::I32(-2)]));
+        assert_eq!(call(&module, "f2", &[Val::F64(f64::NAN)]), Err(Trap::InvalidConversion));
+        assert_eq!(call(&module, "f2", &[Val::F64(3e9)]), Err(Trap::IntegerOverflow));
+        assert_eq!(call(&module, "f3", &[Val::F64(3e9)]), Ok(vec![Val::I32(i32::MAX)]));
+        assert_eq!(call(&module, "f3", &[Val::F64(f64::NAN)]), Ok(vec![Val::I32(0)]));
+    }
+
+    #[test]
+    fn recurses_through_if_else() {
+        // fac(n) = n == 0 ? 1 : n * fac(n - 1)
+        let code = [
+            0x20, 0, 0x50, 0x04, I64, 0x42, 1, 0x05, 0x20, 0, 0x20, 0, 0x42, 1, 0x7D, 0x10, 0, 0x7E, 0x0B, 0x0B,
+        ];
+        let module = Module::decode(&functions(&[(func_type(&[I64], &[I64]), body(0, &code))])).unwrap();
+
+        assert_eq!(call(&module, "f0", &[Val::I64(0)]), Ok(vec![Val::I64(1)]));
+        assert_eq!(call(&module, "f0", &[Val::I64(20)]), Ok(vec![Val::I64(2_432_902_008_176_640_000)]));
+    }
+
+    #[test]
+    fn stops_unbounded_recursion() {
+        let module = Module::decode(&functions(&[(func_type(&[], &[]), body(0, &[0x10, 0, 0x0B]))])).unwrap();
+
+        assert_eq!(call(&module, "f0", &[]), Err(Trap::CallStackExhausted));
+    }
+
+    /// sum(n) = n + (n - 1) + … + 1, counting down in a loop
+    fn sum() -> Module {
+        let code = [
+            0x02, 0x40, 0x03, 0x40, 0x20, 0, 0x45, 0x0D, 1, 0x20, 1, 0x20, 0, 0x6A, 0x21, 1, 0x20, 0, 0x41, 1, 0x6B,
+            0x21, 0, 0x0C, 0, 0x0B, 0x0B, 0x20, 1, 0x0B,
+        ];
+        Module::decode(&functions(&[(func_type(&[I32], &[I32]), body(1, &code))])).unwrap()
+    }
+
+    #[test]
+    fn loops_and_breaks() {
+        assert_eq!(call(&sum(), "f0", &[Val::I32(100)]), Ok(vec![Val::I32(5050)]));
+        assert_eq!(call(&sum(), "f0", &[Val::I32(0)]), Ok(vec![Val::I32(0)]));
+    }
+
+    #[test]
+    fn charges_fuel_per_instruction() {
+        let module = sum();
+        let mut fuel = Fuel { remaining: 1_000, interrupt: &never };
+        let mut instance = Instance::new(&module, 0, &mut fuel).unwrap();
+        assert_eq!(instance.call("f0", &[Val::I32(1_000)], &mut fuel), Err(Trap::OutOfFuel));
+        assert_eq!(fuel.remaining, 0);
+
+        // 2 instructions to enter the loop, 12 per iteration, 3 to leave it
+        // and 2 to return
+        let mut fuel = Fuel { remaining: 1_000, interrupt: &never };
+        instance.call("f0", &[Val::I32(10)], &mut fuel).unwrap();
+        assert_eq!(1_000 - fuel.remaining, 2 + 12 * 10 + 3 + 2);
+    }
+
+    #[test]
+    fn stops_when_interrupted() {
+        let module = sum();
+        let mut fuel = Fuel { remaining: u64::MAX, interrupt: &|| true };
+        let mut instance = Instance::new(&module, 0, &mut fuel).unwrap();
+
+        assert_eq!(instance.call("f0", &[Val::I32(i32::MAX)], &mut fuel), Err(Trap::Interrupted));
+        assert_eq!(u64::MAX - fuel.remaining, INTERRUPT_INTERVAL);
+    }
+
+    #[test]
+    fn branches_through_tables_and_block_results() {
+        // switch (n) { case 0: 10; case 1: 20; default: 30 }
+        let switch = [
+            0x02, 0x40, 0x02, 0x40, 0x02, 0x40, 0x20, 0, 0x0E, 2, 0, 1, 2, 0x0B, 0x41, 10, 0x0F, 0x0B, 0x41, 20,
+            0x0F, 0x0B, 0x41, 30, 0x0B,
+        ];
+        // A branch out of a block keeps only the block's result
+        let carry = [0x02, I32, 0x41, 1, 0x41, 2, 0x0C, 0, 0x0B, 0x0B];
+        let module = Module::decode(&functions(&[
+            (func_type(&[I32], &[I32]), body(0, &switch)),
+            (func_type(&[], &[I32]), body(0, &carry)),
+        ]))
+        .unwrap();
+
+        assert_eq!(call(&module, "f0", &[Val::I32(0)]), Ok(vec![Val::I32(10)]));
+        assert_eq!(call(&module, "f0", &[Val::I32(1)]), Ok(vec![Val::I32(20)]));
+        assert_eq!(call(&module, "f0", &[Val::I32(7)]), Ok(vec![Val::I32(30)]));
+        assert_eq!(call(&module, "f0", &[Val::I32(-1)]), Ok(vec![Val::I32(30)]));
+        assert_eq!(call(&module, "f1", &[]), Ok(vec![Val::I32(2)]));
+    }
+
+    #[test]
+    fn calls_through_the_table() {
+        let types = vector(&[func_type(&[], &[I32]), func_type(&[I32], &[I32])]);
+        let table = vector(&[[vec![0x70], vec![0, 3]].concat()]);
+        let elements = vector(&[[vec![0, 0x41, 0, 0x0B], vector(&[vec![0], vec![1], vec![2]])].concat()]);
+        let bodies = vector(&[
+            body(0, &[0x41, 1, 0x0B]),
+            body(0, &[0x41, 2, 0x0B]),
+            body(0, &[0x20, 0, 0x11, 0, 0, 0x0B]),
+        ]);
+        let module = Module::decode(&wasm(&[
+            (1, types),
+            (3, vector(&[vec![0], vec![0], vec![1]])),
+            (4, table),
+            (7, vector(&[export("dispatch", 0, 2)])),
+            (9, elements),
+            (10, bodies),
+        ]))
+        .unwrap();
+
+        assert_eq!(call(&module, "dispatch", &[Val::I32(0)]), Ok(vec![Val::I32(1)]));
+        assert_eq!(call(&module, "dispatch", &[Val::I32(1)]), Ok(vec![Val::I32(2)]));
+        // Slot 2 holds a function of the other type, slot 3 is past the end
+        assert_eq!(call(&module, "dispatch", &[Val::I32(2)]), Err(Trap::BadIndirectCall));
+        assert_eq!(call(&module, "dispatch", &[Val::I32(3)]), Err(Trap::BadIndirectCall));
+    }
+
+    #[test]
+    fn reads_writes_and_grows_memory() {
+        let types = vector(&[func_type(&[I32], &[I32]), func_type(&[I32, I32], &[]), func_type(&[I32], &[I32])]);
+        let bodies = vector(&[
+            body(0, &[0x20, 0, 0x2D, 0, 16, 0x0B]),
+            body(0, &[0x20, 0, 0x20, 1, 0x36, 2, 0, 0x0B]),
+            body(0, &[0x20, 0, 0x40, 0, 0x0B]),
+        ]);
+        let data = vector(&[[vec![0, 0x41, 16, 0x0B], vector(&b"hello".map(|byte| vec![byte])).split_off(0)].concat()]);
+        let module = Module::decode(&wasm(&[
+            (1, types),
+            (3, vector(&[vec![0], vec![1], vec![2]])),
+            (5, vector(&[vec![1, 1, 3]])),
+            (7, vector(&[export("load", 0, 0), export("store", 0, 1), export("grow", 0, 2), export("memory", 2, 0)])),
+            (10, bodies),
+            (11, data),
+        ]))
+        .unwrap();
+        assert!(module.exports_memory());
+        assert!(module.exports_function("store", &[ValType::I32, ValType::I32], &[]));
+        assert!(!module.exports_function("store", &[ValType::I32], &[]));
+
+        let mut fuel = Fuel { remaining: 1_000, interrupt: &never };
+        let mut instance = Instance::new(&module, 2, &mut fuel).unwrap();
+        assert_eq!(&instance.memory()[16..21], b"hello");
+        assert_eq!(instance.call("load", &[Val::I32(1)], &mut fuel), Ok(vec![Val::I32(i32::from(b'e'))]));
+
+        instance.call("store", &[Val::I32(100), Val::I32(0x0403_0201)], &mut fuel).unwrap();
+        assert_eq!(&instance.memory()[100..104], &[1, 2, 3, 4]);
+        instance.memory_mut()[200] = 0xFF;
+        assert_eq!(instance.call("load", &[Val::I32(184)], &mut fuel), Ok(vec![Val::I32(0xFF)]));
+
+        let end = PAGE_SIZE as i32;
+        assert_eq!(instance.call("store", &[Val::I32(end - 2), Val::I32(0)], &mut fuel), Err(Trap::MemoryOutOfBounds));
+        // The module allows 3 pages but the instance only 2
+        assert_eq!(instance.call("grow", &[Val::I32(2)], &mut fuel), Ok(vec![Val::I32(-1)]));
+        assert_eq!(instance.call("grow", &[Val::I32(1)], &mut fuel), Ok(vec![Val::I32(1)]));
+        assert_eq!(instance.memory().len(), 2 * PAGE_SIZE);
+        instance.call("store", &[Val::I32(end - 2), Val::I32(0)], &mut fuel).unwrap();
+    }
+
+    #[test]
+    fn checks_exports_and_arguments() {
+        let module = sum();
+
+        assert_eq!(call(&module, "f1", &[]), Err(Trap::BadExport("no function `f1` is exported".to_string())));
+        assert_eq!(call(&module, "f0", &[Val::I64(1)]), Err(Trap::BadExport("`f0` takes other arguments".to_string())));
+        assert_eq!(call(&module, "f0", &[]), Err(Trap::BadExport("`f0` takes other arguments".to_string())));
+    }
+
+    #[test]
+    fn rejects_malformed_modules() {
+        let cases: &[(Vec<u8>, &str)] = &[
+            (b"\0wasm\x01\0\0\0".to_vec(), "not a WebAssembly module"),
+            (b"\0asm\x02\0\0\0".to_vec(), "unsupported WebAssembly version"),
+            (wasm(&[(2, vector(&[[export("f", 0, 0)].concat()]))]), "modules may not import anything"),
+            (wasm(&[(3, vector(&[])), (1, vector(&[]))]), "section 1 is out of order"),
+            (functions(&[(func_type(&[], &[]), body(0, &[0xFE, 0x0B]))]), "unsupported instruction 0xfe"),
+            (functions(&[(func_type(&[], &[]), body(0, &[0x0C, 1, 0x0B]))]), "branch to an unknown label"),
+            (functions(&[(func_type(&[], &[]), body(0, &[0x20, 0, 0x0B]))]), "unknown local"),
+            (functions(&[(func_type(&[], &[]), body(0, &[0x10, 5, 0x0B]))]), "call to an unknown function"),
+            (functions(&[(func_type(&[I32], &[I32]), body(0, &[0x20, 0, 0x28, 2, 0, 0x0B]))]), "memory instruction without a memory"),
+            (functions(&[(func_type(&[], &[]), body(0, &[0x01]))]), "unexpected end of module"),
+            (wasm(&[(1, vector(&[func_type(&[0x7B], &[])]))]), "unsupported value type 0x7b"),
+            (functions(&[(func_type(&[], &[]), body(1, &[0x1A, 0x20, 0, 0x1A, 0x0B]))]), "an instruction takes more operands than its block has"),
+            (functions(&[(func_type(&[], &[I32]), body(0, &[0x02, 0x7F, 0x0C, 0, 0x0B, 0x0B]))]), "an instruction takes more operands than its block has"),
+            (functions(&[(func_type(&[], &[I32]), body(0, &[0x02, 0x40, 0x41, 1, 0x0B, 0x41, 1, 0x0B]))]), "a block leaves more values than its results"),
+            (functions(&[(func_type(&[], &[I32]), body(0, &[0x0B]))]), "an instruction takes more operands than its block has"),
+            (functions(&[(func_type(&[], &[I32]), body(0, &[0x41, 1, 0x04, 0x7F, 0x41, 2, 0x0B, 0x0B]))]), "if without else must leave its parameters"),
+            (wasm(&[(1, vector(&[])), (3, vector(&[vec![0]]))]), "function has an unknown type"),
+        ];
+        for (bytes, message) in cases {
+            assert_eq!(Module::decode(bytes).err().as_deref(), Some(*message));
+        }
+    }
+}
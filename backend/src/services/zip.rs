@@ -0,0 +1,81 @@
+// ZIP archive encoding
+
+use chrono::{DateTime, Datelike, Timelike, Utc};
+
+/// Package files into a ZIP archive.
+///
+/// Entries are stored without compression, which every unzip tool reads and
+/// keeps the archive byte-for-byte predictable. All entries get the same
+/// modification time.
+pub fn encode_zip(files: &[(String, Vec<u8>)], modified: DateTime<Utc>) -> Vec<u8> {
+    let (time, date) = dos_time(modified);
+    let mut zip = Vec::new();
+    let mut directory = Vec::new();
+
+    for (name, contents) in files {
+        let offset = zip.len() as u32;
+        let crc = crc32(contents);
+        let size = contents.len() as u32;
+
+        // Version 2.0, UTF-8 names, stored
+        let mut header = Vec::new();
+        header.extend_from_slice(&20u16.to_le_bytes());
+        header.extend_from_slice(&0x0800u16.to_le_bytes());
+        header.extend_from_slice(&0u16.to_le_bytes());
+        header.extend_from_slice(&time.to_le_bytes());
+        header.extend_from_slice(&date.to_le_bytes());
+        header.extend_from_slice(&crc.to_le_bytes());
+        header.extend_from_slice(&size.to_le_bytes()); // compressed
+        header.extend_from_slice(&size.to_le_bytes());
+        header.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        header.extend_from_slice(&0u16.to_le_bytes()); // extra field
+
+        zip.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+        zip.extend_from_slice(&header);
+        zip.extend_from_slice(name.as_bytes());
+        zip.extend_from_slice(contents);
+
+        directory.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+        directory.extend_from_slice(&20u16.to_le_bytes()); // made by
+        directory.extend_from_slice(&header);
+        directory.extend_from_slice(&0u16.to_le_bytes()); // comment
+        directory.extend_from_slice(&0u16.to_le_bytes()); // disk
+        directory.extend_from_slice(&0u16.to_le_bytes()); // internal attributes
+        directory.extend_from_slice(&0u32.to_le_bytes()); // external attributes
+        directory.extend_from_slice(&offset.to_le_bytes());
+        directory.extend_from_slice(name.as_bytes());
+    }
+
+    let directory_offset = zip.len() as u32;
+    let entries = files.len() as u16;
+    zip.extend_from_slice(&directory);
+    zip.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+    zip.extend_from_slice(&0u16.to_le_bytes()); // this disk
+    zip.extend_from_slice(&0u16.to_le_bytes()); // directory disk
+    zip.extend_from_slice(&entries.to_le_bytes());
+    zip.extend_from_slice(&entries.to_le_bytes());
+    zip.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+    zip.extend_from_slice(&directory_offset.to_le_bytes());
+    zip.extend_from_slice(&0u16.to_le_bytes()); // comment
+    zip
+}
+
+/// MS-DOS time and date, which count years from 1980 and seconds in twos
+fn dos_time(at: DateTime<Utc>) -> (u16, u16) {
+    let year = at.year().clamp(1980, 2107) as u16;
+    let time = ((at.hour() as u16) << 11) | ((at.minute() as u16) << 5) | (at.second() as u16 / 2);
+    let date = ((year - 1980) << 9) | ((at.month() as u16) << 5) | at.day() as u16;
+    (time, date)
+}
+
+/// CRC-32 as used by ZIP (reflected, polynomial 0xEDB88320)
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
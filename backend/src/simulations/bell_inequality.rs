@@ -0,0 +1,217 @@
+//! Bell's inequality: entangled spin correlations against local hidden variables
+
+use serde_json::Value;
+
+use crate::models::quiz::{Question, Quiz};
+use crate::numerics::monte_carlo::MonteCarlo;
+use crate::simulations::params::Params;
+use crate::simulations::{Derived, Simulation, SimulationError, SimulationRegistry, output};
+
+/// Points in the correlation vs. relative angle curves over 0–360°
+const CURVE_POINTS: usize = 361;
+/// Number of individual events listed in the output
+const EVENT_LOG_SIZE: usize = 50;
+
+pub fn register(registry: &mut SimulationRegistry) {
+    registry.register(BellInequality);
+}
+
+pub struct BellInequality;
+
+impl Simulation for BellInequality {
+    fn id(&self) -> &'static str {
+        "bell-inequality"
+    }
+
+    fn quiz(&self) -> Option<Quiz> {
+        Some(Quiz {
+            simulation_id: "bell-inequality".to_string(),
+            title: "Bell's Inequality Quiz".to_string(),
+            questions: vec![
+                Question::numeric(
+                    "local_bound",
+                    "What is the largest value of |S| allowed by any local hidden-variable theory?",
+                    "",
+                    2.0,
+                    0.01,
+                    "The CHSH inequality states |S| ≤ 2 for all local realistic theories.",
+                )
+                .with_hints(&[
+                    "The CHSH combination S adds four correlations, each between −1 and +1.",
+                    "In a local theory every outcome is fixed in advance as ±1. Try writing S for one pair of predetermined outcomes and factor it.",
+                    "A(B + B′) + A′(B − B′): one of the brackets is always zero and the other is ±2.",
+                ]),
+                Question::numeric(
+                    "tsirelson",
+                    "What is the largest |S| that quantum mechanics allows?",
+                    "",
+                    2.828,
+                    0.01,
+                    "Tsirelson's bound is 2√2 ≈ 2.828.",
+                )
+                .with_hints(&[
+                    "Quantum mechanics beats the local bound. By how much depends on how well the four angles are chosen.",
+                    "For the singlet E(a, b) = −cos(a − b). Try detector angles 45° apart from each other.",
+                    "With the optimal angles each of the four terms contributes cos 45° = 1/√2 in magnitude.",
+                ]),
+                Question::multiple_choice(
+                    "same_angle",
+                    "Alice and Bob measure the singlet state with identical detector angles. What do they find?",
+                    &["Always the same result", "Always opposite results", "Uncorrelated results", "Opposite results half the time"],
+                    1,
+                    "E(a, a) = −cos 0 = −1: the spins are perfectly anticorrelated along any shared axis.",
+                )
+                .with_hints(&[
+                    "What is the total spin of the singlet state?",
+                    "Look up the correlation E(a, b) = −cos(a − b) and set a = b.",
+                ]),
+            ],
+        })
+    }
+
+    fn units(&self) -> &'static [(&'static str, &'static str)] {
+        &[("relative_angle", "°")]
+    }
+
+    fn prerequisites(&self) -> &'static [&'static str] {
+        &["stern-gerlach", "polarization"]
+    }
+
+    fn derived(&self, _params: &Params, data: &Value) -> Vec<Derived> {
+        vec![
+            Derived::new(
+                "chsh_quantum",
+                "CHSH Value, Quantum",
+                output(data, "/chsh_quantum"),
+                "",
+                r"S = E(a,b) - E(a,b') + E(a',b) + E(a',b'),\quad E = -\cos(a - b)",
+            ),
+            Derived::new(
+                "chsh_lhv",
+                "CHSH Value, Local Hidden Variables",
+                output(data, "/chsh_lhv"),
+                "",
+                r"S = E(a,b) - E(a,b') + E(a',b) + E(a',b'),\quad |S| \le 2",
+            ),
+        ]
+    }
+
+    fn run(&self, params: &Params) -> Result<Value, SimulationError> {
+        let a = params.f64("alice_a").to_radians();
+        let a_prime = params.f64("alice_a_prime").to_radians();
+        let b = params.f64("bob_b").to_radians();
+        let b_prime = params.f64("bob_b_prime").to_radians();
+        // Setting pairs in CHSH order, with their sign in S
+        let settings = [(a, b, 1.0), (a, b_prime, -1.0), (a_prime, b, 1.0), (a_prime, b_prime, 1.0)];
+        let labels = ["ab", "ab'", "a'b", "a'b'"];
+
+        let chsh = |correlation: fn(f64, f64) -> f64| -> f64 {
+            settings.iter().map(|&(x, y, sign)| sign * correlation(x, y)).sum()
+        };
+        let pairs: Vec<Value> = settings
+            .iter()
+            .zip(labels)
+            .map(|(&(x, y, _), label)| {
+                let e = quantum_correlation(x, y);
+                serde_json::json!({
+                    "settings": label,
+                    "alice_angle": x.to_degrees(),
+                    "bob_angle": y.to_degrees(),
+                    "quantum_correlation": e,
+                    "lhv_correlation": lhv_correlation(x, y),
+                    "coincidence": coincidences(e),
+                })
+            })
+            .collect();
+
+        let angles: Vec<f64> = (0..CURVE_POINTS).map(|i| i as f64 * 360.0 / (CURVE_POINTS - 1) as f64).collect();
+        let mut data = serde_json::json!({
+            "pairs": pairs,
+            "chsh_quantum": chsh(quantum_correlation),
+            "chsh_lhv": chsh(lhv_correlation),
+            "local_bound": 2.0,
+            "tsirelson_bound": 2.0 * std::f64::consts::SQRT_2,
+            "relative_angle": angles,
+            "quantum_curve": angles.iter().map(|d| quantum_correlation(0.0, d.to_radians())).collect::<Vec<_>>(),
+            "lhv_curve": angles.iter().map(|d| lhv_correlation(0.0, d.to_radians())).collect::<Vec<_>>(),
+        });
+
+        if params.bool("sample_events") {
+            let mut mc = params.monte_carlo();
+            data["events"] = sample_events(&settings, params.i64("num_events") as usize, &mut mc);
+            data["seed"] = mc.seed().into();
+        }
+        Ok(data)
+    }
+}
+
+/// Singlet-state correlation E(a, b) = −cos(a − b)
+fn quantum_correlation(a: f64, b: f64) -> f64 {
+    -(a - b).cos()
+}
+
+/// Correlation of the deterministic sign model with a uniform hidden angle
+fn lhv_correlation(a: f64, b: f64) -> f64 {
+    let delta = (a - b).rem_euclid(std::f64::consts::TAU);
+    let delta = delta.min(std::f64::consts::TAU - delta);
+    -1.0 + 2.0 * delta / std::f64::consts::PI
+}
+
+/// Joint outcome probabilities for a correlation E, symmetric under A ↔ −A
+fn coincidences(correlation: f64) -> Value {
+    let same = (1.0 + correlation) / 4.0;
+    let opposite = (1.0 - correlation) / 4.0;
+    serde_json::json!({ "++": same, "--": same, "+-": opposite, "-+": opposite })
+}
+
+/// Draw individual events with a random setting pair each, as in an
+/// experiment, and estimate the correlations and S from the counts
+fn sample_events(settings: &[(f64, f64, f64); 4], count: usize, mc: &mut MonteCarlo) -> Value {
+    // [quantum, lhv] × setting pair: (sum of AB, events)
+    let mut tallies = [[(0.0, 0usize); 4]; 2];
+    let mut log = Vec::with_capacity(EVENT_LOG_SIZE);
+    for event in 0..count {
+        let pair = ((mc.uniform() * 4.0) as usize).min(3);
+        let (a, b, _) = settings[pair];
+
+        let alice = if mc.uniform() < 0.5 { 1.0 } else { -1.0 };
+        // Given Alice's result, Bob agrees with probability (1 + E)/2
+        let agree = mc.uniform() < (1.0 + quantum_correlation(a, b)) / 2.0;
+        let quantum = (alice, if agree { alice } else { -alice });
+
+        let lambda = mc.uniform_range(0.0, std::f64::consts::TAU);
+        let lhv = ((a - lambda).cos().signum(), -(b - lambda).cos().signum());
+
+        for (model, (x, y)) in [quantum, lhv].into_iter().enumerate() {
+            tallies[model][pair].0 += x * y;
+            tallies[model][pair].1 += 1;
+        }
+        if event < EVENT_LOG_SIZE {
+            log.push(serde_json::json!({
+                "settings": pair,
+                "quantum": [quantum.0, quantum.1],
+                "lhv": [lhv.0, lhv.1],
+            }));
+        }
+    }
+
+    let estimate = |model: usize| {
+        let correlations: Vec<f64> = tallies[model].iter().map(|&(sum, n)| sum / n.max(1) as f64).collect();
+        let s: f64 = correlations.iter().zip(settings).map(|(e, s)| s.2 * e).sum();
+        // Each correlation estimate has variance (1 − E²)/n
+        let error = tallies[model]
+            .iter()
+            .zip(&correlations)
+            .map(|(&(_, n), e)| (1.0 - e * e) / n.max(1) as f64)
+            .sum::<f64>()
+            .sqrt();
+        serde_json::json!({ "correlations": correlations, "chsh": s, "chsh_error": error })
+    };
+
+    serde_json::json!({
+        "count": count,
+        "quantum": estimate(0),
+        "lhv": estimate(1),
+        "log": log,
+    })
+}
@@ -0,0 +1,173 @@
+//! Blackbody radiation spectra
+
+use serde_json::Value;
+
+use crate::models::quiz::{Question, Quiz};
+use crate::physics::constants::{
+    BOLTZMANN, GIGA, NANO, PLANCK, SPEED_OF_LIGHT, STEFAN_BOLTZMANN, WIEN_DISPLACEMENT,
+};
+use crate::simulations::params::Params;
+use crate::simulations::{Derived, Signal, Simulation, SimulationError, SimulationRegistry, output};
+
+/// Number of wavelength samples in each spectrum
+const SPECTRUM_POINTS: usize = 500;
+/// Comparison temperatures are switched off at this value
+const TEMPERATURE_OFF: f64 = 0.0;
+
+pub fn register(registry: &mut SimulationRegistry) {
+    registry.register(Blackbody);
+}
+
+pub struct Blackbody;
+
+impl Simulation for Blackbody {
+    fn id(&self) -> &'static str {
+        "blackbody"
+    }
+
+    fn quiz(&self) -> Option<Quiz> {
+        Some(Quiz {
+            simulation_id: "blackbody".to_string(),
+            title: "Blackbody Radiation Quiz".to_string(),
+            questions: vec![
+                Question::numeric(
+                    "sun_peak",
+                    "The Sun's surface is about 5800 K. At what wavelength does its spectrum peak?",
+                    "nm",
+                    500.0,
+                    0.03,
+                    "λ_peak = b/T = 2.898 × 10⁻³ / 5800 ≈ 5.0 × 10⁻⁷ m = 500 nm, in the middle of the visible range.",
+                )
+                .with_hints(&[
+                    "Wien's displacement law links the peak wavelength to the temperature.",
+                    "λ_peak = b/T with b ≈ 2.898 × 10⁻³ m·K. Remember to convert metres to nanometres.",
+                ]),
+                Question::numeric(
+                    "power_ratio",
+                    "By what factor does the total radiated power grow when the temperature doubles?",
+                    "×",
+                    16.0,
+                    0.01,
+                    "P = σT⁴, so doubling T multiplies the power by 2⁴ = 16.",
+                )
+                .with_hints(&[
+                    "The Stefan–Boltzmann law tells you how the total power depends on temperature.",
+                    "P ∝ T⁴. What is 2 raised to that power?",
+                ]),
+                Question::multiple_choice(
+                    "catastrophe",
+                    "Where does the classical Rayleigh–Jeans law fail?",
+                    &["At long wavelengths", "At short wavelengths, where it predicts unlimited emission", "Only at very low temperatures", "It agrees with Planck's law everywhere"],
+                    1,
+                    "The Rayleigh–Jeans radiance grows as 1/λ⁴ without bound; quantization of light suppresses the short-wavelength emission.",
+                )
+                .with_hints(&[
+                    "Compare the Rayleigh–Jeans curve with Planck's curve in the simulation. Where do they part ways?",
+                    "Integrate the Rayleigh–Jeans radiance, proportional to 1/λ⁴, down to λ → 0. Does the total stay finite?",
+                ]),
+            ],
+        })
+    }
+
+    fn units(&self) -> &'static [(&'static str, &'static str)] {
+        &[("wavelength", "nm"), ("spectra.*.radiance", "W/(m²·sr·nm)"), ("spectra.*.rayleigh_jeans", "W/(m²·sr·nm)")]
+    }
+
+    fn signal(&self) -> Option<Signal> {
+        Some(Signal { output: "spectra.0.radiance", position: "wavelength" })
+    }
+
+    fn prerequisites(&self) -> &'static [&'static str] {
+        &["ideal-gas", "standing-waves"]
+    }
+
+    fn derived(&self, _params: &Params, data: &Value) -> Vec<Derived> {
+        let spectra = data["spectra"].as_array().map_or(&[][..], Vec::as_slice);
+        spectra
+            .iter()
+            .enumerate()
+            .flat_map(|(i, spectrum)| {
+                let suffix = if i == 0 { String::new() } else { format!("_{}", i + 1) };
+                let at = format!(" at {} K", output(spectrum, "/temperature"));
+                [
+                    Derived::new(
+                        format!("peak_wavelength{suffix}"),
+                        format!("Peak Wavelength{at}"),
+                        output(spectrum, "/peak_wavelength"),
+                        "nm",
+                        r"\lambda_{\max} = b / T",
+                    ),
+                    Derived::new(
+                        format!("total_power{suffix}"),
+                        format!("Radiated Power per Area{at}"),
+                        output(spectrum, "/total_power"),
+                        "W/m²",
+                        r"j = \sigma T^4",
+                    ),
+                ]
+            })
+            .collect()
+    }
+
+    fn run(&self, params: &Params) -> Result<Value, SimulationError> {
+        let max_wavelength = params.f64("max_wavelength");
+        let rayleigh_jeans = params.bool("rayleigh_jeans");
+        let temperatures: Vec<f64> = ["temperature", "temperature_2", "temperature_3"]
+            .into_iter()
+            .map(|name| params.f64(name))
+            .filter(|&t| t > TEMPERATURE_OFF)
+            .collect();
+
+        // Start one step above zero, where the radiance vanishes anyway
+        let step = max_wavelength / SPECTRUM_POINTS as f64;
+        let wavelengths: Vec<f64> = (1..=SPECTRUM_POINTS).map(|i| i as f64 * step).collect();
+
+        let spectra: Vec<Value> = temperatures
+            .iter()
+            .map(|&temperature| {
+                let radiance: Vec<f64> = wavelengths.iter().map(|&l| planck_radiance(l * NANO, temperature) * NANO).collect();
+                // Trapezoidal rule; the radiance is zero at λ = 0
+                let power_in_range = std::f64::consts::PI
+                    * step
+                    * (radiance.iter().sum::<f64>() - radiance[SPECTRUM_POINTS - 1] / 2.0);
+                let total_power = STEFAN_BOLTZMANN * temperature.powi(4);
+                let peak_wavelength = WIEN_DISPLACEMENT / temperature;
+
+                let mut spectrum = serde_json::json!({
+                    "temperature": temperature,
+                    "radiance": radiance,
+                    "peak_wavelength": peak_wavelength * GIGA,
+                    "peak_radiance": planck_radiance(peak_wavelength, temperature) * NANO,
+                    "total_power": total_power,
+                    "fraction_in_range": power_in_range / total_power,
+                });
+                if rayleigh_jeans {
+                    spectrum["rayleigh_jeans"] = wavelengths
+                        .iter()
+                        .map(|&l| rayleigh_jeans_radiance(l * NANO, temperature) * NANO)
+                        .collect::<Vec<_>>()
+                        .into();
+                }
+                spectrum
+            })
+            .collect();
+
+        Ok(serde_json::json!({
+            "wavelength": wavelengths,
+            "spectra": spectra,
+            "max_wavelength": max_wavelength,
+            "rayleigh_jeans": rayleigh_jeans,
+        }))
+    }
+}
+
+/// Planck spectral radiance B_λ (W·sr⁻¹·m⁻³) at wavelength `lambda` (m)
+fn planck_radiance(lambda: f64, temperature: f64) -> f64 {
+    let x = PLANCK * SPEED_OF_LIGHT / (lambda * BOLTZMANN * temperature);
+    2.0 * PLANCK * SPEED_OF_LIGHT * SPEED_OF_LIGHT / lambda.powi(5) / x.exp_m1()
+}
+
+/// Classical Rayleigh–Jeans spectral radiance (W·sr⁻¹·m⁻³)
+fn rayleigh_jeans_radiance(lambda: f64, temperature: f64) -> f64 {
+    2.0 * SPEED_OF_LIGHT * BOLTZMANN * temperature / lambda.powi(4)
+}
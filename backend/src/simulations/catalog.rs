@@ -0,0 +1,641 @@
+//! Simulation text and parameter definitions, read from content files so
+//! authors can edit them without rebuilding the server.
+//!
+//! Every simulation has `<id>.toml` with its listing, description and
+//! parameters, and `<id>.md` with its theory section, in the `simulations`
+//! directory under the configured content directory (`content` by default). The catalog is
+//! loaded at startup and can be reloaded while the server runs; a reload
+//! that fails validation leaves the previous catalog in place.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::models::quiz::Quiz;
+use crate::models::simulation::{SimulationDetails, SimulationInfo, SimulationParameter, DIFFICULTIES};
+use crate::physics::units;
+use crate::services::toml;
+use crate::simulations::params::{check_formula, Params, CURVE_POINTS};
+use crate::simulations::{
+    Backend, Derived, FrameStream, Progress, Signal, Simulation, SimulationError, SimulationRegistry, Validation,
+};
+
+/// Parameter kinds the run request validation understands
+const PARAMETER_TYPES: [&str; 5] = ["slider", "toggle", "select", "curve", "formula"];
+/// Kinds of works a simulation can cite
+const REFERENCE_TYPES: [&str; 2] = ["article", "book"];
+
+/// Problems found while loading the catalog, each with the file it is in
+#[derive(Debug, Default)]
+pub struct CatalogError {
+    pub problems: Vec<(String, String)>,
+}
+
+impl CatalogError {
+    pub fn push(&mut self, file: impl Into<String>, message: impl Into<String>) {
+        self.problems.push((file.into(), message.into()));
+    }
+}
+
+impl fmt::Display for CatalogError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let problems: Vec<String> = self.problems.iter().map(|(file, message)| format!("{file}: {message}")).collect();
+        write!(f, "{}", problems.join("; "))
+    }
+}
+
+/// A simulation's editable content: `<id>.toml` with the theory text of
+/// `<id>.md`, or the same fields as JSON through the admin API
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SimulationContent {
+    /// Taken from the file name or URL when left out
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    /// One line shown in listings
+    pub summary: String,
+    /// Introduction shown above the controls
+    pub description: String,
+    pub difficulty: String,
+    pub estimated_time_minutes: u32,
+    #[serde(default)]
+    pub topics: Vec<String>,
+    #[serde(default)]
+    pub parameters: Vec<ParameterDefinition>,
+    /// Markdown theory section. Content files keep it in `<id>.md`.
+    #[serde(default)]
+    pub theory: String,
+    /// Works describing the physics the simulation models, for citations
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub references: Vec<Reference>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ParameterDefinition {
+    pub name: String,
+    pub label: String,
+    #[serde(rename = "type")]
+    pub param_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+    pub default: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub step: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<Vec<String>>,
+    /// Symbol of the unit the simulation code expects a slider's value in,
+    /// one of `/api/v1/units`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unit: Option<String>,
+    /// Variables a formula may use, which the simulation code sets when it
+    /// evaluates it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variables: Option<Vec<String>>,
+}
+
+/// A paper or book a simulation's physics comes from
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Reference {
+    /// `article` or `book`
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// "Family, Given", in the order printed on the work
+    pub authors: Vec<String>,
+    pub title: String,
+    pub year: i32,
+    /// Journal of an article
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub journal: Option<String>,
+    /// Publisher of a book
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub publisher: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub edition: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub volume: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub issue: Option<String>,
+    /// Page range, e.g. "1-16"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pages: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub doi: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+impl Reference {
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let title = &self.title;
+        if title.trim().is_empty() {
+            problems.push("references need a title".to_string());
+        }
+        if !REFERENCE_TYPES.contains(&self.kind.as_str()) {
+            problems.push(format!("reference \"{title}\" must have type {}", REFERENCE_TYPES.join(", ")));
+        }
+        if self.authors.is_empty() || self.authors.iter().any(|a| a.trim().is_empty()) {
+            problems.push(format!("reference \"{title}\" needs its authors"));
+        }
+        match self.kind.as_str() {
+            "article" if self.journal.is_none() => problems.push(format!("article \"{title}\" needs its journal")),
+            "book" if self.publisher.is_none() => problems.push(format!("book \"{title}\" needs its publisher")),
+            _ => {}
+        }
+        if self.doi.as_ref().is_some_and(|doi| !doi.starts_with("10.")) {
+            problems.push(format!("reference \"{title}\" needs a DOI of the form 10.prefix/suffix"));
+        }
+        problems
+    }
+}
+
+/// Name, type, options, unit and variables of a parameter definition
+type Contract<'a> = (&'a str, &'a str, Option<&'a [String]>, Option<&'a str>, Option<&'a [String]>);
+
+impl ParameterDefinition {
+    /// Problems with the definition, which would make run requests
+    /// impossible to validate
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let name = &self.name;
+        if !PARAMETER_TYPES.contains(&self.param_type.as_str()) {
+            problems.push(format!("parameter `{name}` must have type {}", PARAMETER_TYPES.join(", ")));
+        }
+        match self.param_type.as_str() {
+            "slider" => match (self.min, self.max, self.default.as_f64()) {
+                (Some(min), Some(max), Some(default)) if min < max && (min..=max).contains(&default) => {}
+                (Some(_), Some(_), Some(_)) => {
+                    problems.push(format!("slider `{name}` needs min < max and a default between them"))
+                }
+                _ => problems.push(format!("slider `{name}` needs numeric min, max and default")),
+            },
+            "toggle" if !self.default.is_boolean() => {
+                problems.push(format!("toggle `{name}` needs a true or false default"))
+            }
+            "select" => match (&self.options, self.default.as_str()) {
+                (Some(options), Some(default)) if options.iter().any(|o| o == default) => {}
+                _ => problems.push(format!("select `{name}` needs options and a default among them")),
+            },
+            "curve" => {
+                let default: Option<Vec<f64>> =
+                    self.default.as_array().and_then(|points| points.iter().map(Value::as_f64).collect());
+                match (self.min, self.max, default) {
+                    (Some(min), Some(max), Some(default))
+                        if min < max
+                            && CURVE_POINTS.contains(&default.len())
+                            && default.iter().all(|point| (min..=max).contains(point)) => {}
+                    _ => problems.push(format!(
+                        "curve `{name}` needs min < max and a default of {} to {} numbers between them",
+                        CURVE_POINTS.start(),
+                        CURVE_POINTS.end()
+                    )),
+                }
+            }
+            "formula" => {
+                let variables = self.variables.as_deref().unwrap_or_default();
+                match self.default.as_str().map(|text| check_formula(text, variables)) {
+                    Some(Ok(_)) => {}
+                    Some(Err(message)) => problems.push(format!("formula `{name}` default {message}")),
+                    None => problems.push(format!("formula `{name}` needs the text of a formula as its default")),
+                }
+            }
+            _ => {}
+        }
+        if self.step.is_some() && matches!(self.param_type.as_str(), "curve" | "formula") {
+            problems.push(format!("{} `{name}` takes no step", self.param_type));
+        }
+        if self.variables.is_some() && self.param_type != "formula" {
+            problems.push(format!("only formulas can have variables, not `{name}`"));
+        }
+        if self.step.is_some_and(|step| step <= 0.0) {
+            problems.push(format!("parameter `{name}` needs a positive step"));
+        }
+        match &self.unit {
+            Some(_) if !matches!(self.param_type.as_str(), "slider" | "curve" | "formula") => {
+                problems.push(format!("only sliders, curves and formulas can have a unit, not `{name}`"))
+            }
+            Some(unit) if units::find(unit).is_none_or(|known| known.symbol != unit) => {
+                problems.push(format!("parameter `{name}` has unknown unit `{unit}`; use a symbol from /api/v1/units"))
+            }
+            _ => {}
+        }
+        problems
+    }
+
+    /// What the simulation code relies on: the name, the kind of value, the
+    /// choices it matches on, the unit it computes in and the variables it sets
+    fn contract(&self) -> Contract<'_> {
+        (&self.name, &self.param_type, self.options.as_deref(), self.unit.as_deref(), self.variables.as_deref())
+    }
+
+    pub fn to_parameter(&self) -> SimulationParameter {
+        // Sliders and curves always report float defaults, as `20.0` rather than `20`
+        let default = match self.param_type.as_str() {
+            "slider" => self.default.as_f64().map_or(Value::Null, Value::from),
+            "curve" => self.default.as_array().map_or(Value::Null, |points| {
+                points.iter().filter_map(Value::as_f64).collect::<Vec<f64>>().into()
+            }),
+            _ => self.default.clone(),
+        };
+        SimulationParameter {
+            name: self.name.clone(),
+            label: self.label.clone(),
+            param_type: self.param_type.clone(),
+            min: self.min,
+            max: self.max,
+            default,
+            step: self.step,
+            options: self.options.clone(),
+            unit: self.unit.clone(),
+            dimension: self.unit.as_deref().and_then(units::find).map(|unit| unit.dimension),
+            variables: self.variables.clone(),
+        }
+    }
+}
+
+impl SimulationContent {
+    /// Read and check `<id>.toml` and `<id>.md`
+    fn load(dir: &Path, id: &str, errors: &mut CatalogError) -> Option<Self> {
+        let toml_name = format!("{id}.toml");
+        let theory_name = format!("{id}.md");
+
+        let content = fs::read_to_string(dir.join(&toml_name))
+            .map_err(|err| err.to_string())
+            .and_then(|text| toml::parse(&text).map_err(|err| err.to_string()))
+            .and_then(|value| serde_json::from_value::<Self>(value).map_err(|err| err.to_string()))
+            .map_err(|message| errors.push(&toml_name, message))
+            .ok();
+        let theory = fs::read_to_string(dir.join(&theory_name))
+            .map_err(|err| errors.push(&theory_name, err.to_string()))
+            .ok();
+        let (mut content, theory) = (content?, theory?);
+
+        if content.id != id {
+            errors.push(&toml_name, format!("id must be \"{id}\", the file name"));
+            return None;
+        }
+        if !content.theory.is_empty() {
+            errors.push(&toml_name, format!("theory belongs in {theory_name}"));
+            return None;
+        }
+        content.theory = theory;
+        let problems = content.problems();
+        for (field, message) in &problems {
+            let file = if field == "theory" { &theory_name } else { &toml_name };
+            errors.push(file, message.as_str());
+        }
+        problems.is_empty().then_some(content)
+    }
+
+    /// Problems that keep the content from being served, as (field, message)
+    pub fn problems(&self) -> Vec<(String, String)> {
+        let mut problems = Vec::new();
+        if !DIFFICULTIES.contains(&self.difficulty.as_str()) {
+            problems.push((
+                "difficulty".to_string(),
+                format!("difficulty must be one of: {}", DIFFICULTIES.join(", ")),
+            ));
+        }
+        for (field, text) in [
+            ("name", &self.name),
+            ("summary", &self.summary),
+            ("description", &self.description),
+            ("theory", &self.theory),
+        ] {
+            if text.trim().is_empty() {
+                problems.push((field.to_string(), format!("{field} must not be empty")));
+            }
+        }
+        for (i, parameter) in self.parameters.iter().enumerate() {
+            let field = format!("parameters.{}", parameter.name);
+            if self.parameters[..i].iter().any(|p| p.name == parameter.name) {
+                problems.push((field.clone(), format!("parameter `{}` is defined twice", parameter.name)));
+            }
+            for problem in parameter.problems() {
+                problems.push((field.clone(), problem));
+            }
+        }
+        for reference in &self.references {
+            for problem in reference.problems() {
+                problems.push(("references".to_string(), problem));
+            }
+        }
+        problems
+    }
+
+    /// Whether both declare the parameters the simulation code reads: the
+    /// same names, types, select options, units and formula variables
+    pub fn same_parameters(&self, other: &Self) -> bool {
+        let mut mine: Vec<_> = self.parameters.iter().map(ParameterDefinition::contract).collect();
+        let mut theirs: Vec<_> = other.parameters.iter().map(ParameterDefinition::contract).collect();
+        mine.sort();
+        theirs.sort();
+        mine == theirs
+    }
+
+    /// The declared parameters, e.g. `particle (select: photon, electron), wavelength (slider, nm)`
+    /// or `potential (formula of x, eV)`
+    pub fn parameter_summary(&self) -> String {
+        let parameters: Vec<String> = self
+            .parameters
+            .iter()
+            .map(|p| {
+                let kind = match &p.variables {
+                    Some(variables) => format!("{} of {}", p.param_type, variables.join(", ")),
+                    None => p.param_type.clone(),
+                };
+                match (&p.options, &p.unit) {
+                    (Some(options), _) => format!("{} ({kind}: {})", p.name, options.join(", ")),
+                    (None, Some(unit)) => format!("{} ({kind}, {unit})", p.name),
+                    (None, None) => format!("{} ({kind})", p.name),
+                }
+            })
+            .collect();
+        parameters.join(", ")
+    }
+
+    pub fn info(&self) -> SimulationInfo {
+        SimulationInfo {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            description: self.summary.clone(),
+            difficulty: self.difficulty.clone(),
+            estimated_time_minutes: self.estimated_time_minutes,
+            topics: self.topics.clone(),
+        }
+    }
+
+    pub fn details(&self) -> SimulationDetails {
+        SimulationDetails {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            description: self.description.clone(),
+            parameters: self.parameters.iter().map(ParameterDefinition::to_parameter).collect(),
+            theory: self.theory.clone(),
+        }
+    }
+}
+
+/// Content of every registered simulation. The content files are swapped as
+/// a whole on reload; content published through the admin API is layered on
+/// top of them.
+#[derive(Default)]
+pub struct Catalog {
+    dir: Mutex<Option<PathBuf>>,
+    files: RwLock<HashMap<String, Arc<SimulationContent>>>,
+    /// Content of simulations without content files, loaded in place of them
+    embedded: RwLock<HashMap<String, Arc<SimulationContent>>>,
+    /// Content shown instead of the file's
+    published: RwLock<HashMap<String, Arc<SimulationContent>>>,
+    /// Simulations withdrawn from listings and lookups
+    retired: RwLock<HashSet<String>>,
+    /// Counts changes to what is served, so derived data such as the
+    /// search index knows when to rebuild
+    generation: AtomicU64,
+}
+
+impl Catalog {
+    /// The content being served: published content, or else the file's
+    pub fn get(&self, id: &str) -> Option<Arc<SimulationContent>> {
+        let published = self.published.read().unwrap().get(id).cloned();
+        published.or_else(|| self.file(id))
+    }
+
+    /// Content as read from the files
+    pub fn file(&self, id: &str) -> Option<Arc<SimulationContent>> {
+        self.files.read().unwrap().get(id).cloned()
+    }
+
+    pub fn is_published(&self, id: &str) -> bool {
+        self.published.read().unwrap().contains_key(id)
+    }
+
+    /// Serve `content` instead of the file. It must declare the same
+    /// parameters as the file, which ships with the code that reads them.
+    pub fn publish(&self, content: SimulationContent) -> Result<(), String> {
+        let file = self.file(&content.id).ok_or("the simulation has no content file")?;
+        if !content.same_parameters(&file) {
+            return Err(format!("parameters must match the content file: {}", file.parameter_summary()));
+        }
+        self.published.write().unwrap().insert(content.id.clone(), Arc::new(content));
+        self.changed();
+        Ok(())
+    }
+
+    /// Serve the file's content again
+    pub fn unpublish(&self, id: &str) -> bool {
+        let removed = self.published.write().unwrap().remove(id).is_some();
+        self.changed();
+        removed
+    }
+
+    pub fn is_retired(&self, id: &str) -> bool {
+        self.retired.read().unwrap().contains(id)
+    }
+
+    pub fn set_retired(&self, id: &str, retired: bool) {
+        let mut ids = self.retired.write().unwrap();
+        if retired {
+            ids.insert(id.to_string());
+        } else {
+            ids.remove(id);
+        }
+        self.changed();
+    }
+
+    /// Use `content` for its simulation from the next load on, instead of
+    /// reading content files
+    pub fn embed(&self, content: SimulationContent) {
+        self.embedded.write().unwrap().insert(content.id.clone(), Arc::new(content));
+    }
+
+    /// Read the content of each of `ids` from `dir`, or take it from what
+    /// was embedded. On the first load any valid content is accepted. On later loads parameter names, types,
+    /// options, units and formula variables must stay as they are, since the simulation code reads them;
+    /// changing those needs a restart with matching code.
+    pub fn load(&self, dir: &Path, ids: &[&str]) -> Result<usize, CatalogError> {
+        let mut errors = CatalogError::default();
+        let current = self.files.read().unwrap().clone();
+        let embedded = self.embedded.read().unwrap().clone();
+        let mut files = HashMap::new();
+
+        for id in ids {
+            if let Some(content) = embedded.get(*id) {
+                files.insert(id.to_string(), content.clone());
+                continue;
+            }
+            let Some(content) = SimulationContent::load(dir, id, &mut errors) else {
+                continue;
+            };
+            if current.get(*id).is_some_and(|previous| !previous.same_parameters(&content)) {
+                errors.push(
+                    format!("{id}.toml"),
+                    "parameter names, types, options, units and variables changed; restart the server to apply this",
+                );
+                continue;
+            }
+            files.insert(id.to_string(), Arc::new(content));
+        }
+        if let Ok(listing) = fs::read_dir(dir) {
+            for file in listing.flatten() {
+                let path = file.path();
+                let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+                if path.extension().is_some_and(|e| e == "toml") && !ids.contains(&stem) {
+                    tracing::warn!("Ignoring {}: no simulation has this ID", path.display());
+                }
+            }
+        }
+
+        if !errors.problems.is_empty() {
+            return Err(errors);
+        }
+        let count = files.len();
+        *self.files.write().unwrap() = files;
+        *self.dir.lock().unwrap() = Some(dir.to_path_buf());
+        self.changed();
+        Ok(count)
+    }
+
+    /// Directory of the last successful load
+    pub fn dir(&self) -> Option<PathBuf> {
+        self.dir.lock().unwrap().clone()
+    }
+
+    /// Changes so far to the content served or the simulations retired
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    fn changed(&self) {
+        self.generation.fetch_add(1, Ordering::AcqRel);
+    }
+}
+
+/// Modification times and sizes of the content files, to notice edits
+fn fingerprint(dir: &Path) -> Vec<(PathBuf, Option<SystemTime>, u64)> {
+    let mut files: Vec<_> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|file| {
+            let metadata = file.metadata().ok()?;
+            Some((file.path(), metadata.modified().ok(), metadata.len()))
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// Reload the catalog whenever the content files change, checking every
+/// `interval`. Polling keeps this working on bind mounts and network
+/// drives, where change notifications are unreliable.
+pub fn spawn_watcher(registry: Arc<SimulationRegistry>, interval: Duration) {
+    tokio::spawn(async move {
+        let Some(dir) = registry.catalog_dir() else { return };
+        let mut seen = fingerprint(&dir);
+        let mut ticks = tokio::time::interval(interval);
+        loop {
+            ticks.tick().await;
+            let current = fingerprint(&dir);
+            if current == seen {
+                continue;
+            }
+            seen = current;
+            match registry.reload_catalog() {
+                Ok(count) => tracing::info!("Reloaded {} simulations from {}", count, dir.display()),
+                Err(err) => tracing::warn!("Keeping the previous simulation catalog: {}", err),
+            }
+        }
+    });
+}
+
+/// A simulation whose text and parameters come from the catalog
+pub(super) struct Cataloged {
+    pub simulation: Box<dyn Simulation>,
+    pub catalog: Arc<Catalog>,
+}
+
+impl Simulation for Cataloged {
+    fn id(&self) -> &'static str {
+        self.simulation.id()
+    }
+
+    fn info(&self) -> SimulationInfo {
+        match self.catalog.get(self.id()) {
+            Some(content) => content.info(),
+            None => self.simulation.info(),
+        }
+    }
+
+    fn details(&self) -> SimulationDetails {
+        match self.catalog.get(self.id()) {
+            Some(content) => content.details(),
+            None => self.simulation.details(),
+        }
+    }
+
+    fn quiz(&self) -> Option<Quiz> {
+        self.simulation.quiz()
+    }
+
+    fn prerequisites(&self) -> &'static [&'static str] {
+        self.simulation.prerequisites()
+    }
+
+    fn units(&self) -> &'static [(&'static str, &'static str)] {
+        self.simulation.units()
+    }
+
+    fn signal(&self) -> Option<Signal> {
+        self.simulation.signal()
+    }
+
+    fn derived(&self, params: &Params, data: &Value) -> Vec<Derived> {
+        self.simulation.derived(params, data)
+    }
+
+    fn validate(&self, params: &Params, data: &Value) -> Vec<Validation> {
+        self.simulation.validate(params, data)
+    }
+
+    fn version(&self) -> u32 {
+        self.simulation.version()
+    }
+
+    fn backend(&self) -> Backend {
+        self.simulation.backend()
+    }
+
+    fn run_version(
+        &self,
+        version: u32,
+        params: &Params,
+        progress: &Progress,
+    ) -> Option<Result<Value, SimulationError>> {
+        self.simulation.run_version(version, params, progress)
+    }
+
+    fn run(&self, params: &Params) -> Result<Value, SimulationError> {
+        self.simulation.run(params)
+    }
+
+    fn run_with_progress(&self, params: &Params, progress: &Progress) -> Result<Value, SimulationError> {
+        self.simulation.run_with_progress(params, progress)
+    }
+
+    fn stream(&self, params: &Params) -> Result<Option<Box<dyn FrameStream>>, SimulationError> {
+        self.simulation.stream(params)
+    }
+}
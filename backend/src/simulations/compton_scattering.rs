@@ -0,0 +1,188 @@
+//! Compton scattering of photons off free electrons
+
+use serde_json::Value;
+
+use crate::models::quiz::{Question, Quiz};
+use crate::physics::constants::{
+    BARN, CLASSICAL_ELECTRON_RADIUS, ELECTRON_MASS, ELECTRON_VOLT, KILO, PLANCK, SPEED_OF_LIGHT, TERA,
+};
+use crate::simulations::params::Params;
+use crate::simulations::{Derived, Simulation, SimulationError, SimulationRegistry, output};
+
+/// Scattering angles sampled from 0° to 180°
+const ANGLE_POINTS: usize = 181;
+/// Intervals in the midpoint rule for the total cross-section
+const INTEGRATION_STEPS: usize = 2000;
+
+pub fn register(registry: &mut SimulationRegistry) {
+    registry.register(ComptonScattering);
+}
+
+pub struct ComptonScattering;
+
+impl Simulation for ComptonScattering {
+    fn id(&self) -> &'static str {
+        "compton-scattering"
+    }
+
+    fn quiz(&self) -> Option<Quiz> {
+        Some(Quiz {
+            simulation_id: "compton-scattering".to_string(),
+            title: "Compton Scattering Quiz".to_string(),
+            questions: vec![
+                Question::numeric(
+                    "shift_90",
+                    "By how much does a photon's wavelength increase when it scatters through 90°?",
+                    "pm",
+                    2.426,
+                    0.01,
+                    "Δλ = (h/m_ec)(1 − cos 90°) = h/m_ec ≈ 2.426 pm.",
+                )
+                .with_hints(&[
+                    "The Compton formula is Δλ = (h/m_ec)(1 − cos θ).",
+                    "cos 90° = 0, so the shift equals the Compton wavelength of the electron. Work out h/(m_ec) in picometres.",
+                ]),
+                Question::multiple_choice(
+                    "max_recoil",
+                    "At which photon scattering angle does the electron receive the most energy?",
+                    &["0°", "45°", "90°", "180°"],
+                    3,
+                    "Backscattering reverses the photon's momentum, transferring the most energy: this is the Compton edge.",
+                )
+                .with_hints(&[
+                    "The electron's energy comes from the photon's loss. When does the photon lose the most?",
+                    "The shift Δλ grows with 1 − cos θ. At which angle is that largest?",
+                ]),
+                Question::multiple_choice(
+                    "visible",
+                    "Why is the Compton shift hard to notice with visible light?",
+                    &[
+                        "Visible photons have no momentum",
+                        "The shift of a few pm is tiny compared with a wavelength of hundreds of nm",
+                        "Electrons do not scatter visible light",
+                        "The shift is negative for visible light",
+                    ],
+                    1,
+                    "Δλ is at most about 5 pm, a negligible fraction of a visible wavelength.",
+                )
+                .with_hints(&[
+                    "How big is the largest possible Compton shift, in picometres?",
+                    "Compare a few picometres with the wavelength of green light, about 500 nm.",
+                ]),
+            ],
+        })
+    }
+
+    fn units(&self) -> &'static [(&'static str, &'static str)] {
+        &[
+            ("angle", "°"),
+            ("electron_angle", "°"),
+            ("scattered_energy", "keV"),
+            ("electron_energy", "keV"),
+            ("scattered_wavelength", "pm"),
+            ("wavelength_shift", "pm"),
+        ]
+    }
+
+    fn prerequisites(&self) -> &'static [&'static str] {
+        &["photoelectric", "special-relativity"]
+    }
+
+    fn derived(&self, _params: &Params, data: &Value) -> Vec<Derived> {
+        vec![
+            Derived::new(
+                "incident_wavelength",
+                "Incident Wavelength",
+                output(data, "/incident_wavelength"),
+                "pm",
+                r"\lambda = hc / E",
+            ),
+            Derived::new(
+                "compton_wavelength",
+                "Compton Wavelength",
+                output(data, "/compton_wavelength"),
+                "pm",
+                r"\lambda_C = h / m_e c",
+            ),
+            Derived::new(
+                "max_wavelength_shift",
+                "Largest Wavelength Shift",
+                2.0 * output(data, "/compton_wavelength"),
+                "pm",
+                r"\Delta\lambda_{\max} = \lambda_C (1 - \cos 180°) = 2\lambda_C",
+            ),
+            Derived::new(
+                "compton_edge",
+                "Compton Edge",
+                output(data, "/compton_edge"),
+                "keV",
+                r"T_{\max} = \frac{2E^2}{m_e c^2 + 2E}",
+            ),
+        ]
+    }
+
+    fn run(&self, params: &Params) -> Result<Value, SimulationError> {
+        let energy = params.f64("photon_energy");
+        let rest_energy = ELECTRON_MASS * SPEED_OF_LIGHT * SPEED_OF_LIGHT / ELECTRON_VOLT / KILO;
+        let compton_wavelength = PLANCK / (ELECTRON_MASS * SPEED_OF_LIGHT);
+        // Photon wavelength (pm) of an energy in keV
+        let wavelength = |e_kev: f64| PLANCK * SPEED_OF_LIGHT / (e_kev * KILO * ELECTRON_VOLT) * TERA;
+        let ratio = energy / rest_energy;
+        let scattered = |theta: f64| energy / (1.0 + ratio * (1.0 - theta.cos()));
+
+        let angles: Vec<f64> = (0..ANGLE_POINTS).map(|i| i as f64 * 180.0 / (ANGLE_POINTS - 1) as f64).collect();
+        let scattered_energy: Vec<f64> = angles.iter().map(|a| scattered(a.to_radians())).collect();
+        let electron_angle: Vec<f64> = angles
+            .iter()
+            .map(|a| {
+                let half = a.to_radians() / 2.0;
+                if half == 0.0 {
+                    90.0
+                } else {
+                    (1.0 / ((1.0 + ratio) * half.tan())).atan().to_degrees()
+                }
+            })
+            .collect();
+
+        let mut data = serde_json::json!({
+            "angle": angles,
+            "scattered_wavelength": scattered_energy.iter().map(|&e| wavelength(e)).collect::<Vec<_>>(),
+            "wavelength_shift": angles
+                .iter()
+                .map(|a| compton_wavelength * (1.0 - a.to_radians().cos()) * TERA)
+                .collect::<Vec<_>>(),
+            "scattered_energy": scattered_energy,
+            "electron_energy": scattered_energy.iter().map(|e| energy - e).collect::<Vec<_>>(),
+            "electron_angle": electron_angle,
+            "incident_wavelength": wavelength(energy),
+            "compton_wavelength": compton_wavelength * TERA,
+            "compton_edge": energy - scattered(std::f64::consts::PI),
+            "electron_rest_energy": rest_energy,
+            "photon_energy": energy,
+        });
+
+        if params.bool("advanced") {
+            let r2 = CLASSICAL_ELECTRON_RADIUS * CLASSICAL_ELECTRON_RADIUS / BARN;
+            let klein_nishina = |theta: f64| {
+                let p = scattered(theta) / energy;
+                r2 / 2.0 * p * p * (p + 1.0 / p - theta.sin().powi(2))
+            };
+            let thomson = |theta: f64| r2 / 2.0 * (1.0 + theta.cos().powi(2));
+            // σ = ∫ dσ/dΩ 2π sin θ dθ, by the midpoint rule
+            let total = |f: &dyn Fn(f64) -> f64| {
+                let h = std::f64::consts::PI / INTEGRATION_STEPS as f64;
+                (0..INTEGRATION_STEPS)
+                    .map(|i| {
+                        let theta = (i as f64 + 0.5) * h;
+                        f(theta) * std::f64::consts::TAU * theta.sin() * h
+                    })
+                    .sum::<f64>()
+            };
+            data["klein_nishina"] = angles.iter().map(|a| klein_nishina(a.to_radians())).collect::<Vec<_>>().into();
+            data["thomson"] = angles.iter().map(|a| thomson(a.to_radians())).collect::<Vec<_>>().into();
+            data["total_cross_section"] = total(&klein_nishina).into();
+            data["thomson_cross_section"] = total(&thomson).into();
+        }
+        Ok(data)
+    }
+}
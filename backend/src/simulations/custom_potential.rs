@@ -0,0 +1,338 @@
+//! Bound states and transmission of an electron meeting a potential the user
+//! writes as a formula or gives as samples, between two flat leads
+
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+
+use serde_json::Value;
+
+use crate::models::quiz::{Question, Quiz};
+use crate::numerics::complex::Complex;
+use crate::numerics::eigen::{lowest, SymmetricTridiagonal};
+use crate::physics::constants::ELECTRON_KINETIC;
+use crate::simulations::params::{ParameterError, Params};
+use crate::simulations::potential_well::{nodes, sample};
+use crate::simulations::{output, series, Derived, Simulation, SimulationError, SimulationRegistry, Validation};
+
+/// Cells the region between the leads is divided into, each holding the
+/// potential at its centre
+const CELLS: usize = 400;
+/// Least length of lead (nm) kept on either side of the region when
+/// solving for bound states, with hard walls beyond it
+const MIN_PADDING: f64 = 5.0;
+/// Energies at which the transmission is computed
+const TRANSMISSION_POINTS: usize = 400;
+/// Values the potential may take (eV), as for drawn curves
+const POTENTIAL_LIMITS: RangeInclusive<f64> = -20.0..=50.0;
+/// Size of (ψ, ψ') at which the transfer-matrix propagation is rescaled,
+/// well short of overflow
+const RESCALE_ABOVE: f64 = 1e100;
+
+pub fn register(registry: &mut SimulationRegistry) {
+    registry.register(CustomPotential);
+}
+
+pub struct CustomPotential;
+
+impl Simulation for CustomPotential {
+    fn id(&self) -> &'static str {
+        "custom-potential"
+    }
+
+    fn quiz(&self) -> Option<Quiz> {
+        Some(Quiz {
+            simulation_id: "custom-potential".to_string(),
+            title: "User-Defined Potential Quiz".to_string(),
+            questions: vec![
+                Question::numeric(
+                    "flux",
+                    "What do the transmission and reflection probabilities add up to at any energy?",
+                    "",
+                    1.0,
+                    0.01,
+                    "Nothing is absorbed, so every electron that is not transmitted is reflected: T + R = 1.",
+                )
+                .with_hints(&[
+                    "An electron hitting the potential can only end up on one of two sides.",
+                    "Nothing in the simulation absorbs electrons, so probability is conserved.",
+                ]),
+                Question::multiple_choice(
+                    "shallow_well",
+                    "How many bound states does a very shallow, narrow well between equal leads have?",
+                    &["None", "At least one", "Exactly two", "Infinitely many"],
+                    1,
+                    "In one dimension any attractive well between equal leads binds at least one state, however weak it is.",
+                )
+                .with_hints(&[
+                    "Try it: draw a very shallow well and look at the bound states the simulation finds.",
+                    "In one dimension, does an attractive dip need a minimum depth to trap a particle, as it does in three dimensions?",
+                ]),
+                Question::multiple_choice(
+                    "double_barrier",
+                    "Why does the transmission of two barriers reach nearly 1 at some energies, although each barrier alone reflects most electrons?",
+                    &[
+                        "The barriers lower each other",
+                        "The electron matches a quasi-bound level between them",
+                        "The electron gains energy",
+                        "It is a numerical artefact",
+                    ],
+                    1,
+                    "At a quasi-bound level of the well between the barriers the waves reflected back and forth interfere constructively on the far side: resonant tunneling.",
+                )
+                .with_hints(&[
+                    "The region between two barriers behaves like a small well. Does that well have levels of its own?",
+                    "Think of a Fabry–Pérot interferometer: waves bouncing between two mirrors.",
+                    "When the electron's energy matches a level between the barriers, the waves leaking through the far barrier add up in phase.",
+                ]),
+            ],
+        })
+    }
+
+    fn units(&self) -> &'static [(&'static str, &'static str)] {
+        &[
+            ("x", "nm"),
+            ("potential", "eV"),
+            ("left_lead", "eV"),
+            ("right_lead", "eV"),
+            ("threshold", "eV"),
+            ("energies", "eV"),
+            ("states.*.energy", "eV"),
+            ("states.*.binding_energy", "eV"),
+            ("transmission_energy", "eV"),
+            ("resonances.*.energy", "eV"),
+        ]
+    }
+
+    fn prerequisites(&self) -> &'static [&'static str] {
+        &["potential-well", "quantum-tunneling"]
+    }
+
+    fn derived(&self, _params: &Params, data: &Value) -> Vec<Derived> {
+        let energies = series(data, "/energies");
+        let mut derived = vec![
+            Derived::new("bound_states", "Bound States", energies.len() as f64, "", r"E_n < \min(V_L, V_R)"),
+            Derived::new(
+                "threshold",
+                "Transmission Threshold",
+                output(data, "/threshold"),
+                "eV",
+                r"\max(V_L, V_R)",
+            ),
+        ];
+        if let Some(&ground) = energies.first() {
+            derived.push(Derived::new("ground_state_energy", "Ground-State Energy", ground, "eV", r"E_1"));
+        }
+        let resonance = output(data, "/resonances/0/energy");
+        if resonance.is_finite() {
+            derived.push(Derived::new(
+                "first_resonance",
+                "Lowest Transmission Resonance",
+                resonance,
+                "eV",
+                r"E:\ T(E)\ \text{locally largest}",
+            ));
+        }
+        derived
+    }
+
+    fn validate(&self, params: &Params, data: &Value) -> Vec<Validation> {
+        let transmission = series(data, "/transmission");
+        let reflection = series(data, "/reflection");
+        let total: Vec<f64> = transmission.iter().zip(&reflection).map(|(t, r)| t + r).collect();
+        let mut validations: Vec<Validation> =
+            Validation::compare("transmission + reflection", r"T + R = 1", "", &total, &vec![1.0; total.len()])
+                .into_iter()
+                .collect();
+
+        // A single rectangular step between equal leads has a closed form
+        let width = params.f64("width");
+        let dx = width / CELLS as f64;
+        let pad = padding(width, dx);
+        let potential = series(data, "/potential");
+        if let Some((lead, height, thickness)) = potential.get(pad..pad + CELLS).and_then(rectangle) {
+            let exact: Vec<f64> = series(data, "/transmission_energy")
+                .iter()
+                .map(|e| rectangle_transmission(e - lead, height - lead, thickness as f64 * dx))
+                .collect();
+            validations.extend(Validation::compare(
+                "transmission",
+                r"T = \left[1 + \frac{V_0^2 \sin^2(q a)}{4E(E - V_0)}\right]^{-1}",
+                "",
+                &transmission,
+                &exact,
+            ));
+        }
+        validations
+    }
+
+    fn run(&self, params: &Params) -> Result<Value, SimulationError> {
+        let width = params.f64("width");
+        let range = params.f64("energy_range");
+        let count = params.i64("max_states") as usize;
+
+        let dx = width / CELLS as f64;
+        let centres: Vec<f64> = (0..CELLS).map(|j| (j as f64 + 0.5) * dx).collect();
+        let (name, cells) = if params.str("definition") == "formula" {
+            let formula = params.formula("formula");
+            let mut variables = BTreeMap::new();
+            let cells = centres
+                .iter()
+                .map(|&x| {
+                    variables.insert("x".to_string(), x);
+                    formula.eval(&variables)
+                })
+                .collect::<Vec<f64>>();
+            ("formula", cells)
+        } else {
+            let curve = params.curve("samples");
+            ("samples", centres.iter().map(|&x| sample(&curve, x / width, false)).collect())
+        };
+        if let Some((x, v)) = centres.iter().zip(&cells).find(|(_, v)| !POTENTIAL_LIMITS.contains(v)) {
+            let message = if v.is_finite() {
+                format!(
+                    "gives {v:.3} eV at x = {x:.3} nm; the potential must stay between {} and {} eV",
+                    POTENTIAL_LIMITS.start(),
+                    POTENTIAL_LIMITS.end()
+                )
+            } else {
+                format!("is not a finite number at x = {x:.3} nm")
+            };
+            return Err(SimulationError::InvalidParameters(vec![ParameterError::new(
+                name,
+                message,
+                params.values().get(name),
+            )]));
+        }
+
+        let (left, right) = (cells[0], cells[CELLS - 1]);
+        let threshold = left.max(right);
+
+        // Bound states: the region with a stretch of each lead either side,
+        // closed by hard walls far enough out not to disturb them
+        let pad = padding(width, dx);
+        let mut potential = vec![left; pad];
+        potential.extend(&cells);
+        potential.extend(vec![right; pad]);
+        let x: Vec<f64> = (0..potential.len()).map(|j| (j as f64 + 0.5 - pad as f64) * dx).collect();
+        let hamiltonian = SymmetricTridiagonal::hamiltonian(&potential, dx, ELECTRON_KINETIC);
+        // Levels above the lower lead belong to the continuum between the
+        // walls, so only those below it are solved for
+        let bound = hamiltonian.count_below(left.min(right));
+        let pairs = lowest(&hamiltonian, count.min(bound));
+        let states: Vec<Value> = pairs
+            .iter()
+            .enumerate()
+            .map(|(i, pair)| {
+                let psi: Vec<f64> = pair.vector.iter().map(|v| v / dx.sqrt()).collect();
+                serde_json::json!({
+                    "n": i + 1,
+                    "energy": pair.value,
+                    "binding_energy": left.min(right) - pair.value,
+                    "nodes": nodes(&psi),
+                    "psi": psi,
+                })
+            })
+            .collect();
+        let energies: Vec<f64> = pairs.iter().map(|pair| pair.value).collect();
+
+        // Scattering from the left lead into the right one, above the threshold
+        let transmission_energy: Vec<f64> = (1..=TRANSMISSION_POINTS)
+            .map(|i| threshold + range * i as f64 / TRANSMISSION_POINTS as f64)
+            .collect();
+        let (transmission, reflection): (Vec<f64>, Vec<f64>) =
+            transmission_energy.iter().map(|&e| scatter(&cells, dx, left, right, e)).unzip();
+        let resonances: Vec<Value> = (1..TRANSMISSION_POINTS - 1)
+            .filter(|&i| transmission[i] > transmission[i - 1] && transmission[i] >= transmission[i + 1])
+            .map(|i| serde_json::json!({ "energy": transmission_energy[i], "transmission": transmission[i] }))
+            .collect();
+
+        Ok(serde_json::json!({
+            "x": x,
+            "potential": potential,
+            "width": width,
+            "left_lead": left,
+            "right_lead": right,
+            "threshold": threshold,
+            "states": states,
+            "energies": energies,
+            "transmission_energy": transmission_energy,
+            "transmission": transmission,
+            "reflection": reflection,
+            "resonances": resonances,
+        }))
+    }
+}
+
+/// Cells of lead on each side of the region for the bound-state grid
+fn padding(width: f64, dx: f64) -> usize {
+    (width.max(MIN_PADDING) / dx).ceil() as usize
+}
+
+/// Transmission and reflection probabilities at `energy` of the cells of
+/// potential `cells`, each `dx` wide, between leads at `left` and `right`,
+/// for an electron coming from the left.
+///
+/// Inside a cell of constant V the solution is a combination of cos and
+/// sin(qx)/q, q² = (E − V)/c, exact for any sign of q². Starting from the
+/// transmitted wave e^{ik_R x} at the right edge, (ψ, ψ') is carried back
+/// cell by cell to the left edge and split there into incident and
+/// reflected waves A e^{ik_L x} + B e^{−ik_L x}.
+fn scatter(cells: &[f64], dx: f64, left: f64, right: f64, energy: f64) -> (f64, f64) {
+    let k_left = ((energy - left) / ELECTRON_KINETIC).sqrt();
+    let k_right = ((energy - right) / ELECTRON_KINETIC).sqrt();
+    let mut psi = Complex::real(1.0);
+    let mut slope = Complex::new(0.0, k_right);
+    // ln of the factor (ψ, ψ') has been divided by, so deep barriers do not overflow
+    let mut log_scale = 0.0;
+    for &v in cells.iter().rev() {
+        let q2 = (energy - v) / ELECTRON_KINETIC;
+        // ψ(x − d) = ψ C − ψ' S, ψ'(x − d) = q² S ψ + C ψ'
+        let (c, s) = if q2 > 0.0 {
+            let q = q2.sqrt();
+            ((q * dx).cos(), (q * dx).sin() / q)
+        } else if q2 < 0.0 {
+            let kappa = (-q2).sqrt();
+            ((kappa * dx).cosh(), (kappa * dx).sinh() / kappa)
+        } else {
+            (1.0, dx)
+        };
+        (psi, slope) = (psi * c - slope * s, psi * (q2 * s) + slope * c);
+        let size = psi.abs().max(slope.abs());
+        if size > RESCALE_ABOVE {
+            psi = psi * (1.0 / size);
+            slope = slope * (1.0 / size);
+            log_scale += size.ln();
+        }
+    }
+    // ψ'/(ik_L), so that ψ = A + B and ψ'/(ik_L) = A − B at the left edge
+    let turned = Complex::new(slope.im, -slope.re) * (1.0 / k_left);
+    let incident = (psi + turned) * 0.5;
+    let reflected = (psi - turned) * 0.5;
+    let transmission = k_right / k_left * (-2.0 * log_scale).exp() / incident.norm_sqr();
+    (transmission, reflected.norm_sqr() / incident.norm_sqr())
+}
+
+/// Lead level, height and width in cells of a potential that is one level
+/// with a single contiguous block of another level inside it
+fn rectangle(cells: &[f64]) -> Option<(f64, f64, usize)> {
+    let lead = cells[0];
+    let start = cells.iter().position(|&v| v != lead)?;
+    let height = cells[start];
+    let thickness = cells[start..].iter().take_while(|&&v| v == height).count();
+    cells[start + thickness..].iter().all(|&v| v == lead).then_some((lead, height, thickness))
+}
+
+/// Transmission of a rectangular step of height `v0` and width `a` at an
+/// energy `e` above the leads, both in eV; a negative `v0` is a well
+fn rectangle_transmission(e: f64, v0: f64, a: f64) -> f64 {
+    let q2 = (e - v0) / ELECTRON_KINETIC;
+    let stretch = if q2 > 0.0 {
+        (q2.sqrt() * a).sin().powi(2) / q2
+    } else if q2 < 0.0 {
+        ((-q2).sqrt() * a).sinh().powi(2) / -q2
+    } else {
+        a * a
+    };
+    // V₀² sin²(qa) / 4E(E − V₀), with E − V₀ = c q²
+    1.0 / (1.0 + v0 * v0 * stretch / (4.0 * e * ELECTRON_KINETIC))
+}
@@ -0,0 +1,202 @@
+//! Doppler effect for a sound source passing an observer
+
+use serde_json::Value;
+
+use crate::models::quiz::{Question, Quiz};
+use crate::numerics::roots::bisect;
+use crate::simulations::params::Params;
+use crate::simulations::{Derived, Simulation, SimulationError, SimulationRegistry, output};
+
+/// Speed of sound in dry air at 20 °C (m/s)
+const SPEED_OF_SOUND: f64 = 343.0;
+/// Emission times sampled for the frequency curve
+const CURVE_POINTS: usize = 500;
+/// Audio sample rate of the synthesized clip (Hz)
+pub const SAMPLE_RATE: u32 = 22_050;
+/// Fade applied at both ends of the clip to avoid clicks (s)
+const FADE_TIME: f64 = 0.02;
+/// Peak amplitude of the clip, leaving headroom below full scale
+const PEAK_AMPLITUDE: f64 = 0.8;
+
+pub fn register(registry: &mut SimulationRegistry) {
+    registry.register(Doppler);
+}
+
+pub struct Doppler;
+
+impl Simulation for Doppler {
+    fn id(&self) -> &'static str {
+        "doppler"
+    }
+
+    fn quiz(&self) -> Option<Quiz> {
+        Some(Quiz {
+            simulation_id: "doppler".to_string(),
+            title: "Doppler Effect Quiz".to_string(),
+            questions: vec![
+                Question::numeric(
+                    "approaching",
+                    "A 500 Hz siren drives straight toward you at 34.3 m/s. What frequency do you hear? (c = 343 m/s)",
+                    "Hz",
+                    555.6,
+                    0.01,
+                    "f' = 500 / (1 − 34.3/343) = 500 / 0.9 ≈ 555.6 Hz.",
+                )
+                .with_hints(&[
+                    "For a moving source and a listener at rest, f′ = f / (1 − v/c).",
+                    "Here v/c = 34.3/343 = 0.1.",
+                ]),
+                Question::multiple_choice(
+                    "closest",
+                    "At the instant the source is closest to you, what frequency was it emitting toward you?",
+                    &["Higher than the true pitch", "Exactly the true pitch", "Lower than the true pitch", "No sound at all"],
+                    1,
+                    "At closest approach the source moves perpendicular to the line of sight, so there is no radial velocity and no shift.",
+                )
+                .with_hints(&[
+                    "Only the part of the velocity along the line to the listener shifts the pitch.",
+                    "At closest approach, which way does the source move relative to the line between you?",
+                ]),
+                Question::multiple_choice(
+                    "delay",
+                    "Why do you hear the unshifted pitch slightly after the source has passed its closest point?",
+                    &[
+                        "Sound slows down near the listener",
+                        "The sound emitted at closest approach still has to travel to you",
+                        "Your ears respond slowly",
+                        "The source changes its frequency",
+                    ],
+                    1,
+                    "Every sound reaches you r/c after it was emitted.",
+                )
+                .with_hints(&[
+                    "Sound travels at a finite speed, 343 m/s.",
+                    "What you hear at any moment was emitted when the source was somewhere else.",
+                ]),
+            ],
+        })
+    }
+
+    fn units(&self) -> &'static [(&'static str, &'static str)] {
+        &[
+            ("emission_time", "s"),
+            ("reception_time", "s"),
+            ("observed_frequency", "Hz"),
+            ("source_position", "m"),
+            ("distance", "m"),
+        ]
+    }
+
+    fn prerequisites(&self) -> &'static [&'static str] {
+        &["standing-waves"]
+    }
+
+    fn derived(&self, _params: &Params, data: &Value) -> Vec<Derived> {
+        vec![
+            Derived::new(
+                "mach_number",
+                "Mach Number",
+                output(data, "/mach_number"),
+                "",
+                r"M = v_s / c",
+            ),
+            Derived::new(
+                "approach_frequency",
+                "Frequency Heard on Approach",
+                output(data, "/approach_limit"),
+                "Hz",
+                r"f' = f \frac{c}{c - v_s}",
+            ),
+            Derived::new(
+                "recession_frequency",
+                "Frequency Heard Receding",
+                output(data, "/recession_limit"),
+                "Hz",
+                r"f' = f \frac{c}{c + v_s}",
+            ),
+        ]
+    }
+
+    fn run(&self, params: &Params) -> Result<Value, SimulationError> {
+        let pass = Pass::new(params);
+        let emitted: Vec<f64> = (0..CURVE_POINTS)
+            .map(|i| -pass.half_duration + 2.0 * pass.half_duration * i as f64 / (CURVE_POINTS - 1) as f64)
+            .collect();
+
+        let v_over_c = pass.speed / SPEED_OF_SOUND;
+        Ok(serde_json::json!({
+            "emission_time": emitted,
+            "reception_time": emitted.iter().map(|&t| pass.reception_time(t)).collect::<Vec<_>>(),
+            "observed_frequency": emitted.iter().map(|&t| pass.observed_frequency(t)).collect::<Vec<_>>(),
+            "source_position": emitted.iter().map(|&t| pass.speed * t).collect::<Vec<_>>(),
+            "distance": emitted.iter().map(|&t| pass.distance(t)).collect::<Vec<_>>(),
+            "approach_limit": pass.frequency / (1.0 - v_over_c),
+            "recession_limit": pass.frequency / (1.0 + v_over_c),
+            "mach_number": v_over_c,
+            "speed_of_sound": SPEED_OF_SOUND,
+            "sample_rate": SAMPLE_RATE,
+        }))
+    }
+}
+
+/// Mono audio heard by the listener, sampled at `SAMPLE_RATE`
+pub fn synthesize(params: &Params) -> Vec<f64> {
+    let pass = Pass::new(params);
+    let start = pass.reception_time(-pass.half_duration);
+    let end = pass.reception_time(pass.half_duration);
+    let samples = ((end - start) * SAMPLE_RATE as f64) as usize;
+    let max_delay = pass.distance(-pass.half_duration).max(pass.distance(pass.half_duration)) / SPEED_OF_SOUND;
+
+    (0..samples)
+        .map(|i| {
+            let heard = start + i as f64 / SAMPLE_RATE as f64;
+            // Reception time grows monotonically with emission time for a
+            // subsonic source, so the emission time is bracketed
+            let emitted = bisect(
+                |t| pass.reception_time(t) - heard,
+                heard - max_delay,
+                heard,
+                1e-9,
+            );
+            let fade = ((heard - start).min(end - heard) / FADE_TIME).min(1.0);
+            let loudness = pass.closest / pass.distance(emitted);
+            PEAK_AMPLITUDE * fade * loudness * (std::f64::consts::TAU * pass.frequency * emitted).sin()
+        })
+        .collect()
+}
+
+/// Straight-line pass of the source along the road, through x = 0 at t = 0
+struct Pass {
+    speed: f64,
+    frequency: f64,
+    /// Perpendicular distance from the road (m)
+    closest: f64,
+    /// Listener position along the road (m)
+    offset: f64,
+    half_duration: f64,
+}
+
+impl Pass {
+    fn new(params: &Params) -> Self {
+        Self {
+            speed: params.f64("source_speed"),
+            frequency: params.f64("frequency"),
+            closest: params.f64("observer_distance"),
+            offset: params.f64("observer_position"),
+            half_duration: params.f64("duration") / 2.0,
+        }
+    }
+
+    fn distance(&self, emitted: f64) -> f64 {
+        (self.speed * emitted - self.offset).hypot(self.closest)
+    }
+
+    fn reception_time(&self, emitted: f64) -> f64 {
+        emitted + self.distance(emitted) / SPEED_OF_SOUND
+    }
+
+    fn observed_frequency(&self, emitted: f64) -> f64 {
+        let toward = -self.speed * (self.speed * emitted - self.offset) / self.distance(emitted);
+        self.frequency / (1.0 - toward / SPEED_OF_SOUND)
+    }
+}
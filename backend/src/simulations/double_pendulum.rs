@@ -0,0 +1,251 @@
+//! Chaotic double pendulum
+
+use serde_json::Value;
+
+use crate::models::quiz::{Question, Quiz};
+use crate::numerics::ode::rk4_step;
+use crate::simulations::params::Params;
+use crate::simulations::{Derived, Simulation, SimulationError, SimulationRegistry, output};
+
+const GRAVITY: f64 = 9.81;
+/// Integration time step (s)
+const TIME_STEP: f64 = 0.001;
+/// Number of samples returned per time series
+const OUTPUT_SAMPLES: usize = 1000;
+/// Angular separation (rad) at which the two runs count as diverged
+const DIVERGENCE_THRESHOLD: f64 = 1.0;
+
+pub fn register(registry: &mut SimulationRegistry) {
+    registry.register(DoublePendulum);
+}
+
+pub struct DoublePendulum;
+
+impl Simulation for DoublePendulum {
+    fn id(&self) -> &'static str {
+        "double-pendulum"
+    }
+
+    fn quiz(&self) -> Option<Quiz> {
+        Some(Quiz {
+            simulation_id: "double-pendulum".to_string(),
+            title: "Double Pendulum Quiz".to_string(),
+            questions: vec![
+                Question::multiple_choice(
+                    "deterministic",
+                    "If two double pendulums start from exactly the same state, their motions are...",
+                    &["identical", "different after a few seconds", "random", "mirror images"],
+                    0,
+                    "The equations are deterministic: identical initial conditions give identical motion. Chaos is about nearly identical conditions.",
+                )
+                .with_hints(&[
+                    "Does the simulation use random numbers anywhere in the equations of motion?",
+                    "Chaos means sensitivity to initial conditions. What if there is no difference at all?",
+                ]),
+                Question::multiple_choice(
+                    "divergence",
+                    "How does a small difference between two chaotic trajectories typically grow?",
+                    &["It stays constant", "Linearly in time", "Exponentially in time", "It shrinks to zero"],
+                    2,
+                    "Sensitive dependence means |δθ(t)| ≈ |δθ₀| e^{λt} with a positive Lyapunov exponent λ.",
+                )
+                .with_hints(&[
+                    "Look at how the separation of the two runs grows before it saturates, on a logarithmic scale.",
+                    "A straight line on a logarithmic plot means what kind of growth?",
+                ]),
+                Question::numeric(
+                    "horizon",
+                    "With a Lyapunov exponent of 1 s⁻¹, how much longer can you predict the motion if you measure the initial angle 1000 times more precisely?",
+                    "s",
+                    6.91,
+                    0.03,
+                    "The horizon grows as ln(1/δθ₀)/λ, so the extra time is ln(1000)/1 ≈ 6.9 s.",
+                )
+                .with_hints(&[
+                    "The separation grows as δθ₀ e^{λt}. Prediction fails when it reaches some fixed tolerance.",
+                    "Solve δθ₀ e^{λt} = tolerance for t, then compare the times for δθ₀ and δθ₀/1000.",
+                    "The tolerance cancels. The extra time is ln(1000)/λ.",
+                ]),
+            ],
+        })
+    }
+
+    fn units(&self) -> &'static [(&'static str, &'static str)] {
+        &[
+            ("t", "s"),
+            ("separation", "rad"),
+            ("pendulum_a.theta1", "rad"),
+            ("pendulum_a.theta2", "rad"),
+            ("pendulum_a.x1", "m"),
+            ("pendulum_a.y1", "m"),
+            ("pendulum_a.x2", "m"),
+            ("pendulum_a.y2", "m"),
+            ("pendulum_b.theta1", "rad"),
+            ("pendulum_b.theta2", "rad"),
+            ("pendulum_b.x1", "m"),
+            ("pendulum_b.y1", "m"),
+            ("pendulum_b.x2", "m"),
+            ("pendulum_b.y2", "m"),
+        ]
+    }
+
+    fn prerequisites(&self) -> &'static [&'static str] {
+        &["projectile-motion"]
+    }
+
+    fn derived(&self, params: &Params, data: &Value) -> Vec<Derived> {
+        let small_angle_period = |length: f64| std::f64::consts::TAU * (length / GRAVITY).sqrt();
+        vec![
+            Derived::new(
+                "upper_period",
+                "Small-Swing Period of the Upper Pendulum Alone",
+                small_angle_period(params.f64("length1")),
+                "s",
+                r"T = 2\pi \sqrt{L_1 / g}",
+            ),
+            Derived::new(
+                "divergence_time",
+                "Time for the Two Runs to Diverge",
+                output(data, "/divergence_time"),
+                "s",
+                r"\min\{t : \sqrt{\Delta\theta_1^2 + \Delta\theta_2^2} > 1\ \mathrm{rad}\}",
+            ),
+        ]
+    }
+
+    fn run(&self, params: &Params) -> Result<Value, SimulationError> {
+        let pendulum = Pendulum {
+            length1: params.f64("length1"),
+            length2: params.f64("length2"),
+            mass1: params.f64("mass1"),
+            mass2: params.f64("mass2"),
+        };
+        let theta1 = params.f64("theta1").to_radians();
+        let theta2 = params.f64("theta2").to_radians();
+        let perturbation = params.f64("perturbation").to_radians();
+        let duration = params.f64("duration");
+
+        let steps = (duration / TIME_STEP).round() as usize;
+        let stride = steps.div_ceil(OUTPUT_SAMPLES).max(1);
+        let mut a = [theta1, theta2, 0.0, 0.0];
+        let mut b = [theta1, theta2 + perturbation, 0.0, 0.0];
+        let initial_energy = pendulum.energy(&a);
+
+        let mut t_out = Vec::new();
+        let mut series_a = Series::default();
+        let mut series_b = Series::default();
+        let mut separation = Vec::new();
+        let mut divergence_time = None;
+        let mut max_energy_error: f64 = 0.0;
+
+        let derivative = |_t: f64, s: &[f64; 4]| pendulum.derivative(s);
+        for step in 0..=steps {
+            let t = step as f64 * TIME_STEP;
+            let distance = angular_distance(&a, &b);
+            if divergence_time.is_none() && distance > DIVERGENCE_THRESHOLD {
+                divergence_time = Some(t);
+            }
+            if step % stride == 0 {
+                t_out.push(t);
+                series_a.push(&a, &pendulum);
+                series_b.push(&b, &pendulum);
+                separation.push(distance);
+                max_energy_error = max_energy_error.max((pendulum.energy(&a) - initial_energy).abs());
+            }
+            a = rk4_step(derivative, t, &a, TIME_STEP);
+            b = rk4_step(derivative, t, &b, TIME_STEP);
+        }
+
+        Ok(serde_json::json!({
+            "t": t_out,
+            "pendulum_a": series_a,
+            "pendulum_b": series_b,
+            "separation": separation,
+            "divergence_time": divergence_time,
+            "relative_energy_error": max_energy_error / pendulum.energy_scale(),
+            "theta1": params.f64("theta1"),
+            "theta2": params.f64("theta2"),
+            "perturbation": params.f64("perturbation"),
+            "duration": duration,
+        }))
+    }
+}
+
+struct Pendulum {
+    length1: f64,
+    length2: f64,
+    mass1: f64,
+    mass2: f64,
+}
+
+impl Pendulum {
+    /// Time derivative of the state [θ₁, θ₂, ω₁, ω₂]
+    fn derivative(&self, s: &[f64; 4]) -> [f64; 4] {
+        let (l1, l2, m1, m2) = (self.length1, self.length2, self.mass1, self.mass2);
+        let [th1, th2, w1, w2] = *s;
+        let delta = th1 - th2;
+        let den = 2.0 * m1 + m2 - m2 * (2.0 * delta).cos();
+
+        let a1 = (-GRAVITY * (2.0 * m1 + m2) * th1.sin()
+            - m2 * GRAVITY * (th1 - 2.0 * th2).sin()
+            - 2.0 * delta.sin() * m2 * (w2 * w2 * l2 + w1 * w1 * l1 * delta.cos()))
+            / (l1 * den);
+        let a2 = (2.0
+            * delta.sin()
+            * (w1 * w1 * l1 * (m1 + m2) + GRAVITY * (m1 + m2) * th1.cos() + w2 * w2 * l2 * m2 * delta.cos()))
+            / (l2 * den);
+        [w1, w2, a1, a2]
+    }
+
+    /// Total mechanical energy, with the pivot as the zero of potential
+    fn energy(&self, s: &[f64; 4]) -> f64 {
+        let (l1, l2, m1, m2) = (self.length1, self.length2, self.mass1, self.mass2);
+        let [th1, th2, w1, w2] = *s;
+        let kinetic = 0.5 * (m1 + m2) * (l1 * w1).powi(2)
+            + 0.5 * m2 * (l2 * w2).powi(2)
+            + m2 * l1 * l2 * w1 * w2 * (th1 - th2).cos();
+        let potential = -(m1 + m2) * GRAVITY * l1 * th1.cos() - m2 * GRAVITY * l2 * th2.cos();
+        kinetic + potential
+    }
+
+    /// Potential energy difference between hanging straight up and straight
+    /// down, used to express energy errors as a fraction
+    fn energy_scale(&self) -> f64 {
+        2.0 * GRAVITY * ((self.mass1 + self.mass2) * self.length1 + self.mass2 * self.length2)
+    }
+
+    /// Cartesian bob positions with y pointing up from the pivot
+    fn positions(&self, s: &[f64; 4]) -> [f64; 4] {
+        let x1 = self.length1 * s[0].sin();
+        let y1 = -self.length1 * s[0].cos();
+        [x1, y1, x1 + self.length2 * s[1].sin(), y1 - self.length2 * s[1].cos()]
+    }
+}
+
+/// Sampled angles and bob positions of one pendulum
+#[derive(Default, serde::Serialize)]
+struct Series {
+    theta1: Vec<f64>,
+    theta2: Vec<f64>,
+    x1: Vec<f64>,
+    y1: Vec<f64>,
+    x2: Vec<f64>,
+    y2: Vec<f64>,
+}
+
+impl Series {
+    fn push(&mut self, s: &[f64; 4], pendulum: &Pendulum) {
+        let [x1, y1, x2, y2] = pendulum.positions(s);
+        self.theta1.push(s[0]);
+        self.theta2.push(s[1]);
+        self.x1.push(x1);
+        self.y1.push(y1);
+        self.x2.push(x2);
+        self.y2.push(y2);
+    }
+}
+
+/// Distance between two configurations in (θ₁, θ₂) space
+fn angular_distance(a: &[f64; 4], b: &[f64; 4]) -> f64 {
+    (a[0] - b[0]).hypot(a[1] - b[1])
+}
@@ -0,0 +1,442 @@
+//! Double-slit interference
+
+use serde_json::Value;
+
+use crate::models::quiz::{Question, Quiz};
+use crate::numerics::monte_carlo::MonteCarlo;
+use crate::numerics::parallel::par_map;
+#[cfg(feature = "simd")]
+use crate::numerics::simd::{self, F64x4};
+use crate::physics::constants::{self, GIGA, KILO, MILLI, NANO};
+use crate::simulations::params::{ParameterError, Params};
+use crate::simulations::{Derived, Signal, Simulation, SimulationError, SimulationRegistry, kernel_version, output};
+
+pub fn register(registry: &mut SimulationRegistry) {
+    registry.register(DoubleSlit);
+}
+
+pub struct DoubleSlit;
+
+impl Simulation for DoubleSlit {
+    fn id(&self) -> &'static str {
+        "double-slit"
+    }
+
+    fn version(&self) -> u32 {
+        kernel_version(1)
+    }
+
+    fn quiz(&self) -> Option<Quiz> {
+        Some(Quiz {
+            simulation_id: "double-slit".to_string(),
+            title: "Double-Slit Experiment Quiz".to_string(),
+            questions: vec![
+                Question::multiple_choice(
+                    "observer",
+                    "What happens to the pattern on the screen when the observer detects which slit each particle passes through?",
+                    &["The fringes become brighter", "The interference fringes disappear and two bands remain", "The fringes move closer together", "Nothing changes"],
+                    1,
+                    "Which-path information destroys the superposition of the two paths, so the interference term vanishes.",
+                )
+                .with_hints(&[
+                    "Interference needs two indistinguishable paths to the same point on the screen.",
+                    "Once the path is known, do you add the amplitudes or the probabilities?",
+                ]),
+                Question::multiple_choice(
+                    "wavelength",
+                    "If the wavelength is increased while the slit separation stays fixed, the bright fringes on the screen...",
+                    &["move farther apart", "move closer together", "stay in the same place", "disappear"],
+                    0,
+                    "Bright fringes satisfy d sin θ = mλ, so a longer wavelength gives larger angles between fringes.",
+                )
+                .with_hints(&[
+                    "Bright fringes satisfy d sin θ = mλ.",
+                    "Keep d fixed and make λ bigger. What must happen to θ?",
+                ]),
+                Question::numeric(
+                    "fringe_spacing",
+                    "Light of wavelength 500 nm passes through slits 0.1 mm apart onto a screen 1 m away. What is the spacing between neighbouring bright fringes?",
+                    "mm",
+                    5.0,
+                    0.02,
+                    "For small angles the fringe spacing is Δy = λL/d = (500 × 10⁻⁹ m)(1 m)/(10⁻⁴ m) = 5 mm.",
+                )
+                .with_hints(&[
+                    "For small angles the spacing between bright fringes is Δy = λL/d.",
+                    "Put everything in metres: λ = 5 × 10⁻⁷ m, L = 1 m, d = 10⁻⁴ m.",
+                ]),
+            ],
+        })
+    }
+
+    fn units(&self) -> &'static [(&'static str, &'static str)] {
+        &[("x", "mm"), ("y", "mm")]
+    }
+
+    fn signal(&self) -> Option<Signal> {
+        Some(Signal { output: "pattern", position: "x" })
+    }
+
+    fn prerequisites(&self) -> &'static [&'static str] {
+        &["standing-waves"]
+    }
+
+    fn derived(&self, params: &Params, data: &Value) -> Vec<Derived> {
+        let wavelength = output(data, "/wavelength");
+        let screen_distance = params.f64("screen_distance");
+        let de_broglie = params.str("particle_type") != "photon";
+        vec![
+            Derived::new(
+                "wavelength",
+                if de_broglie { "de Broglie Wavelength" } else { "Wavelength" },
+                wavelength,
+                "nm",
+                if de_broglie { r"\lambda = h / p,\quad pc = \sqrt{K^2 + 2Kmc^2}" } else { r"\lambda" },
+            ),
+            Derived::new(
+                "fringe_spacing",
+                "Fringe Spacing",
+                output(data, "/fringe_spacing"),
+                "mm",
+                r"\Delta y = \lambda L / d",
+            ),
+            Derived::new(
+                "envelope_half_width",
+                "Distance to the First Single-Slit Minimum",
+                wavelength * NANO * screen_distance / (params.f64("slit_width") * MILLI) * KILO,
+                "mm",
+                r"y_1 = \lambda L / a",
+            ),
+            Derived::new(
+                "visibility",
+                "Fringe Visibility",
+                output(data, "/visibility"),
+                "",
+                r"V = \sqrt{1 - \eta^2}",
+            ),
+        ]
+    }
+
+    fn run(&self, params: &Params) -> Result<Value, SimulationError> {
+        let particle_type = params.str("particle_type");
+        let kinetic_energy = params.f64("kinetic_energy");
+        let wavelength = match particle_type {
+            "electron" => constants::de_broglie_wavelength(constants::ELECTRON_MASS, kinetic_energy) * GIGA,
+            "neutron" => constants::de_broglie_wavelength(constants::NEUTRON_MASS, kinetic_energy) * GIGA,
+            _ => params.f64("wavelength"),
+        };
+        let slit_separation = params.f64("slit_separation");
+        let slit_width = params.f64("slit_width");
+        let num_slits = params.i64("num_slits") as u32;
+        let observer_mode = params.bool("observer_mode");
+        let detector_efficiency = if observer_mode { 1.0 } else { params.f64("detector_efficiency") };
+        let num_detections = params.i64("num_detections") as usize;
+        let image = params.str("output_dims") == "2d";
+        let seed = params.seed();
+
+        if slit_width >= slit_separation {
+            return Err(SimulationError::InvalidParameters(vec![ParameterError::new(
+                "slit_width",
+                format!("must be less than the slit separation ({slit_separation} mm)"),
+                params.values().get("slit_width"),
+            )]));
+        }
+
+        let resolution = params.i64("resolution") as usize;
+        if image && resolution > MAX_RESOLUTION_2D {
+            return Err(SimulationError::InvalidParameters(vec![ParameterError::new(
+                "resolution",
+                format!("must be at most {MAX_RESOLUTION_2D} for 2D output"),
+                params.values().get("resolution"),
+            )]));
+        }
+
+        let setup = SlitSetup {
+            wavelength: wavelength * NANO,
+            slit_separation: slit_separation * MILLI,
+            slit_width: slit_width * MILLI,
+            num_slits,
+            slit_height: params.f64("slit_height") * MILLI,
+            visibility: fringe_visibility(detector_efficiency),
+            screen_distance: params.f64("screen_distance"),
+            screen_width: params.f64("screen_width") * MILLI,
+            resolution,
+        };
+
+        let mut mc = params.monte_carlo();
+        let detections = setup.sample_detections(|xs| setup.intensities(xs), num_detections, &mut mc);
+
+        let mut data = serde_json::json!({
+            "pattern": setup.pattern(),
+            "x": setup.positions_mm(),
+            "detections": detections,
+            "seed": seed,
+            "particle_type": particle_type,
+            "wavelength": wavelength,
+            "slit_separation": slit_separation,
+            "slit_width": slit_width,
+            "num_slits": num_slits,
+            "observer_mode": observer_mode,
+            "detector_efficiency": detector_efficiency,
+            "visibility": setup.visibility,
+            // Small-angle spacing of bright fringes, λL/d (mm)
+            "fringe_spacing": setup.wavelength * setup.screen_distance / setup.slit_separation * KILO,
+            "screen_distance": setup.screen_distance,
+            "screen_width": params.f64("screen_width"),
+            "resolution": setup.resolution,
+            "output_dims": params.str("output_dims"),
+        });
+        if image {
+            // Vertical coordinates are drawn after the horizontal ones so a
+            // seed yields the same x positions in both output modes
+            data["image"] = setup.image().into();
+            data["y"] = setup.positions_mm().into();
+            data["detections_y"] = setup
+                .sample_detections(|ys| setup.vertical_intensities(ys), num_detections, &mut mc)
+                .into();
+        }
+        Ok(data)
+    }
+}
+
+/// Detections are drawn from the intensity on a grid this many times finer
+/// than the returned pattern, so narrow grating peaks are not missed
+const SAMPLING_REFINEMENT: usize = 20;
+/// Largest resolution allowed for 2D output, which has resolution² points
+const MAX_RESOLUTION_2D: usize = 400;
+/// Detection bins whose density one worker evaluates at a time
+const DENSITY_BLOCK: usize = 1_024;
+
+/// Slit arrangement, light source and screen, with lengths in metres
+struct SlitSetup {
+    wavelength: f64,
+    slit_separation: f64,
+    slit_width: f64,
+    num_slits: u32,
+    slit_height: f64,
+    /// Weight of the interference pattern; the rest goes to the which-path bands
+    visibility: f64,
+    screen_distance: f64,
+    screen_width: f64,
+    /// Number of pattern points across the screen
+    resolution: usize,
+}
+
+impl SlitSetup {
+    /// Relative intensity at horizontal screen position `x` (m), across the slits
+    #[cfg(any(test, not(feature = "simd")))]
+    fn intensity(&self, x: f64) -> f64 {
+        let theta = (x / self.screen_distance).atan();
+
+        // Wave behavior: N-slit fringes under the single-slit envelope
+        let phase = std::f64::consts::PI * self.slit_separation * theta.sin() / self.wavelength;
+        let beta = std::f64::consts::PI * self.slit_width * theta.sin() / self.wavelength;
+        let wave = grating_factor(phase, self.num_slits) * sinc(beta).powi(2);
+
+        // Particle behavior: two distinct bands (Gaussian distributions)
+        let band1 = (-((theta + 0.05_f64).powi(2)) / 0.001).exp();
+        let band2 = (-((theta - 0.05_f64).powi(2)) / 0.001).exp();
+        let particle = (band1 + band2) * 0.5;
+
+        self.visibility * wave + (1.0 - self.visibility) * particle
+    }
+
+    /// `intensity` at four positions at once
+    #[cfg(feature = "simd")]
+    fn intensity_lanes(&self, x: F64x4) -> F64x4 {
+        let theta = (x / self.screen_distance).atan();
+
+        let phase = std::f64::consts::PI * self.slit_separation * theta.sin() / self.wavelength;
+        let beta = std::f64::consts::PI * self.slit_width * theta.sin() / self.wavelength;
+        let wave = grating_factor_lanes(phase, self.num_slits) * sinc_lanes(beta).powi(2);
+
+        let band1 = (-((theta + 0.05).powi(2)) / 0.001).exp();
+        let band2 = (-((theta - 0.05).powi(2)) / 0.001).exp();
+        let particle = (band1 + band2) * 0.5;
+
+        self.visibility * wave + (1.0 - self.visibility) * particle
+    }
+
+    /// Relative intensity at vertical screen position `y` (m): single-slit
+    /// diffraction along the slit height. The aperture is separable, so the
+    /// full image is intensity(x) × vertical_intensity(y).
+    #[cfg(any(test, not(feature = "simd")))]
+    fn vertical_intensity(&self, y: f64) -> f64 {
+        let theta = (y / self.screen_distance).atan();
+        let beta = std::f64::consts::PI * self.slit_height * theta.sin() / self.wavelength;
+        sinc(beta).powi(2)
+    }
+
+    /// `vertical_intensity` at four positions at once
+    #[cfg(feature = "simd")]
+    fn vertical_intensity_lanes(&self, y: F64x4) -> F64x4 {
+        let theta = (y / self.screen_distance).atan();
+        let beta = std::f64::consts::PI * self.slit_height * theta.sin() / self.wavelength;
+        sinc_lanes(beta).powi(2)
+    }
+
+    /// Intensity at each horizontal position (m)
+    fn intensities(&self, xs: &[f64]) -> Vec<f64> {
+        #[cfg(feature = "simd")]
+        return simd::map(xs, |x| self.intensity_lanes(x));
+        #[cfg(not(feature = "simd"))]
+        xs.iter().map(|&x| self.intensity(x)).collect()
+    }
+
+    /// Vertical intensity at each vertical position (m)
+    fn vertical_intensities(&self, ys: &[f64]) -> Vec<f64> {
+        #[cfg(feature = "simd")]
+        return simd::map(ys, |y| self.vertical_intensity_lanes(y));
+        #[cfg(not(feature = "simd"))]
+        ys.iter().map(|&y| self.vertical_intensity(y)).collect()
+    }
+
+    /// Intensity at each pattern point, across the full screen width
+    fn pattern(&self) -> Vec<f64> {
+        self.intensities(&self.screen_positions())
+    }
+
+    /// Square detector image, one row per vertical position from bottom to
+    /// top, the rows computed in parallel
+    fn image(&self) -> Vec<Vec<f64>> {
+        let horizontal = self.pattern();
+        par_map(self.vertical_intensities(&self.screen_positions()), |v| horizontal.iter().map(|h| h * v).collect())
+    }
+
+    /// Pattern point positions (m), shared by both screen axes
+    fn screen_positions(&self) -> Vec<f64> {
+        (0..self.resolution).map(|i| self.screen_position(i as f64)).collect()
+    }
+
+    /// Pattern point positions (mm)
+    fn positions_mm(&self) -> Vec<f64> {
+        self.screen_positions().into_iter().map(|x| x * KILO).collect()
+    }
+
+    /// Screen position (m) of fractional pattern index `i`
+    fn screen_position(&self, i: f64) -> f64 {
+        (i - self.resolution as f64 / 2.0) * self.screen_width / self.resolution as f64
+    }
+
+    /// Draw `count` independent detection positions (mm) from a density,
+    /// evaluated at many positions (m) at once, along one screen axis, in
+    /// order of arrival
+    fn sample_detections(
+        &self,
+        density: impl Fn(&[f64]) -> Vec<f64> + Sync,
+        count: usize,
+        mc: &mut MonteCarlo,
+    ) -> Vec<f64> {
+        if count == 0 {
+            return Vec::new();
+        }
+
+        // Cumulative distribution over fine bins covering the pattern range,
+        // the densities evaluated in parallel blocks and summed in order
+        let bins = self.resolution * SAMPLING_REFINEMENT;
+        let bin_width = 1.0 / SAMPLING_REFINEMENT as f64;
+        let centers: Vec<f64> =
+            (0..bins).map(|b| self.screen_position((b as f64 + 0.5) * bin_width - 0.5)).collect();
+        let blocks = centers.chunks(DENSITY_BLOCK).map(<[f64]>::to_vec).collect();
+        let cdf: Vec<f64> = par_map(blocks, |block| density(&block))
+            .into_iter()
+            .flatten()
+            .scan(0.0, |total, density| {
+                *total += density;
+                Some(*total)
+            })
+            .collect();
+
+        (0..count)
+            .map(|_| {
+                let b = mc.sample_cumulative(&cdf);
+                // Uniform position within the chosen bin
+                let offset = (b as f64 + mc.uniform()) * bin_width - 0.5;
+                self.screen_position(offset) * KILO
+            })
+            .collect()
+    }
+}
+
+/// Fringe visibility left by a which-path detector of the given efficiency,
+/// from the duality relation D² + V² ≤ 1 with distinguishability D = efficiency
+fn fringe_visibility(detector_efficiency: f64) -> f64 {
+    (1.0 - detector_efficiency.powi(2)).max(0.0).sqrt()
+}
+
+/// Normalized N-slit interference term (sin Nφ / N sin φ)², equal to 1 at
+/// the principal maxima φ = mπ
+#[cfg(any(test, not(feature = "simd")))]
+fn grating_factor(phase: f64, num_slits: u32) -> f64 {
+    let n = num_slits as f64;
+    let denominator = n * phase.sin();
+    if denominator.abs() < 1e-9 {
+        1.0
+    } else {
+        ((n * phase).sin() / denominator).powi(2)
+    }
+}
+
+/// sin(x)/x, continuous at x = 0
+#[cfg(any(test, not(feature = "simd")))]
+fn sinc(x: f64) -> f64 {
+    if x.abs() < 1e-8 {
+        1.0
+    } else {
+        x.sin() / x
+    }
+}
+
+/// `grating_factor` in four lanes
+#[cfg(feature = "simd")]
+fn grating_factor_lanes(phase: F64x4, num_slits: u32) -> F64x4 {
+    let n = num_slits as f64;
+    let denominator = n * phase.sin();
+    let ratio = (n * phase).sin() / denominator;
+    denominator.abs().lt(F64x4::splat(1e-9)).select(F64x4::splat(1.0), ratio * ratio)
+}
+
+/// `sinc` in four lanes
+#[cfg(feature = "simd")]
+fn sinc_lanes(x: F64x4) -> F64x4 {
+    x.abs().lt(F64x4::splat(1e-8)).select(F64x4::splat(1.0), x.sin() / x)
+}
+
+#[cfg(all(test, feature = "simd"))]
+mod tests {
+    use super::*;
+
+    /// The five-slit setup `benches/kernels.rs` times, on a 40 000-point
+    /// detection grid 10 cm wide
+    fn five_slits() -> SlitSetup {
+        SlitSetup {
+            wavelength: 550e-9,
+            slit_separation: 1e-4,
+            slit_width: 2e-5,
+            num_slits: 5,
+            slit_height: 5e-3,
+            visibility: 0.9,
+            screen_distance: 1.0,
+            screen_width: 0.1,
+            resolution: 40_000,
+        }
+    }
+
+    /// Largest |simd − scalar| / max(1, |scalar|)
+    fn deviation(scalar: impl Iterator<Item = f64>, simd: &[f64]) -> f64 {
+        scalar.zip(simd).map(|(scalar, simd)| (scalar - simd).abs() / scalar.abs().max(1.0)).fold(0.0, f64::max)
+    }
+
+    #[test]
+    fn simd_intensities_match_the_scalar_ones() {
+        let setup = five_slits();
+        let xs = setup.screen_positions();
+        let horizontal = deviation(xs.iter().map(|&x| setup.intensity(x)), &setup.intensities(&xs));
+        let vertical = deviation(xs.iter().map(|&y| setup.vertical_intensity(y)), &setup.vertical_intensities(&xs));
+        // The documented bounds: 2e-14 for the intensity, one unit in the
+        // last place for the single sinc² of the vertical one
+        assert!(horizontal <= 2e-14, "intensity deviates by {horizontal:e}");
+        assert!(vertical <= f64::EPSILON, "vertical intensity deviates by {vertical:e}");
+    }
+}
+
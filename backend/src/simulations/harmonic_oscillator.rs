@@ -0,0 +1,264 @@
+//! Quantum harmonic oscillator eigenstates and coherent states
+
+use serde_json::Value;
+
+use crate::models::quiz::{Question, Quiz};
+use crate::numerics::complex::Complex;
+use crate::numerics::special::{factorial, hermite_function};
+use crate::simulations::params::Params;
+use crate::simulations::{Derived, FrameStream, Simulation, SimulationError, SimulationRegistry, output};
+
+/// Grid spans [-X_MAX, X_MAX] in units of the oscillator length √(ħ/mω)
+const X_MAX: f64 = 8.0;
+const GRID_POINTS: usize = 401;
+/// Eigenstates kept in the coherent-state expansion; the largest
+/// displacement has a mean quantum number of 8, far below this cut-off
+const EXPANSION_TERMS: u32 = 60;
+/// Number of occupation probabilities reported for the coherent state
+const OCCUPATION_TERMS: u32 = 25;
+
+pub fn register(registry: &mut SimulationRegistry) {
+    registry.register(HarmonicOscillator);
+}
+
+pub struct HarmonicOscillator;
+
+impl Simulation for HarmonicOscillator {
+    fn id(&self) -> &'static str {
+        "harmonic-oscillator"
+    }
+
+    fn quiz(&self) -> Option<Quiz> {
+        Some(Quiz {
+            simulation_id: "harmonic-oscillator".to_string(),
+            title: "Harmonic Oscillator Quiz".to_string(),
+            questions: vec![
+                Question::numeric(
+                    "ground_energy",
+                    "What is the ground-state energy of a quantum harmonic oscillator, in units of ħω?",
+                    "ħω",
+                    0.5,
+                    0.01,
+                    "E₀ = ħω(0 + ½) = ½ħω, the zero-point energy.",
+                )
+                .with_hints(&[
+                    "The levels of the oscillator are Eₙ = ħω(n + ½).",
+                    "The ground state has n = 0.",
+                ]),
+                Question::numeric(
+                    "nodes",
+                    "How many nodes does the n = 4 eigenfunction have?",
+                    "",
+                    4.0,
+                    0.01,
+                    "The n-th eigenstate has exactly n nodes.",
+                )
+                .with_hints(&[
+                    "Look at the eigenfunctions in the simulation: count the zeros of n = 0, 1 and 2.",
+                    "Each step up the ladder adds one node.",
+                ]),
+                Question::multiple_choice(
+                    "coherent",
+                    "How does the probability density of a coherent state change over one period?",
+                    &["It spreads out steadily", "It keeps its shape and swings back and forth", "It splits in two", "It stays at rest"],
+                    1,
+                    "A coherent state is a Gaussian that follows the classical trajectory without spreading.",
+                )
+                .with_hints(&[
+                    "A coherent state is built to behave as classically as quantum mechanics allows.",
+                    "Watch the packet in the simulation: does its width change over a period?",
+                ]),
+            ],
+        })
+    }
+
+    fn units(&self) -> &'static [(&'static str, &'static str)] {
+        &[
+            ("x", "√(ħ/mω)"),
+            ("potential", "ħω"),
+            ("times", "periods"),
+            ("mean_position", "√(ħ/mω)"),
+        ]
+    }
+
+    fn prerequisites(&self) -> &'static [&'static str] {
+        &["square-well"]
+    }
+
+    fn derived(&self, _params: &Params, data: &Value) -> Vec<Derived> {
+        vec![
+            Derived::new(
+                "mean_quantum_number",
+                "Mean Quantum Number",
+                output(data, "/mean_quantum_number"),
+                "",
+                r"\langle n \rangle = |\alpha|^2",
+            ),
+            Derived::new(
+                "mean_energy",
+                "Mean Energy",
+                output(data, "/mean_energy"),
+                "ħω",
+                r"\langle E \rangle = \hbar\omega (|\alpha|^2 + \tfrac{1}{2})",
+            ),
+            Derived::new(
+                "classical_amplitude",
+                "Classical Amplitude",
+                output(data, "/classical_amplitude"),
+                "√(ħ/mω)",
+                r"x_0 = \sqrt{2}\,\alpha \sqrt{\hbar / m\omega}",
+            ),
+        ]
+    }
+
+    fn run(&self, params: &Params) -> Result<Value, SimulationError> {
+        let mut stream = CoherentStream::new(params);
+        let mut times = vec![stream.time()];
+        let mut frames = vec![stream.density()];
+        let mut mean_position = vec![stream.mean_position()];
+        while stream.advance() {
+            times.push(stream.time());
+            frames.push(stream.density());
+            mean_position.push(stream.mean_position());
+        }
+
+        let mut data = stream.header();
+        data["times"] = times.into();
+        data["frames"] = frames.into();
+        data["mean_position"] = mean_position.into();
+        Ok(data)
+    }
+
+    fn stream(&self, params: &Params) -> Result<Option<Box<dyn FrameStream>>, SimulationError> {
+        Ok(Some(Box::new(CoherentStream::new(params))))
+    }
+}
+
+/// Coherent state evolved exactly in the eigenstate basis
+struct CoherentStream {
+    x: Vec<f64>,
+    /// ψₙ(x) on the grid for every term of the expansion
+    basis: Vec<Vec<f64>>,
+    /// Expansion coefficients at t = 0
+    coefficients: Vec<f64>,
+    displacement: f64,
+    num_states: usize,
+    /// Time between frames, in periods
+    dt: f64,
+    num_frames: usize,
+    frame: usize,
+}
+
+impl CoherentStream {
+    fn new(params: &Params) -> Self {
+        let displacement = params.f64("displacement");
+        let num_frames = params.i64("num_frames") as usize;
+        let dx = 2.0 * X_MAX / (GRID_POINTS - 1) as f64;
+        let x: Vec<f64> = (0..GRID_POINTS).map(|i| -X_MAX + i as f64 * dx).collect();
+        let basis = (0..EXPANSION_TERMS)
+            .map(|n| x.iter().map(|&xi| hermite_function(n, xi)).collect())
+            .collect();
+
+        // Released at rest, so α = x₀/√2 is real
+        let alpha = displacement / std::f64::consts::SQRT_2;
+        let coefficients = (0..EXPANSION_TERMS)
+            .map(|n| (-alpha * alpha / 2.0).exp() * alpha.powi(n as i32) / factorial(n).sqrt())
+            .collect();
+
+        Self {
+            x,
+            basis,
+            coefficients,
+            displacement,
+            num_states: params.i64("num_states") as usize,
+            dt: params.f64("duration") / num_frames as f64,
+            num_frames,
+            frame: 0,
+        }
+    }
+
+    /// Step forward to the next output frame; false once the run is over
+    fn advance(&mut self) -> bool {
+        if self.frame >= self.num_frames {
+            return false;
+        }
+        self.frame += 1;
+        true
+    }
+
+    fn time(&self) -> f64 {
+        self.frame as f64 * self.dt
+    }
+
+    fn psi(&self) -> Vec<Complex> {
+        let phase = std::f64::consts::TAU * self.time();
+        let mut psi = vec![Complex::default(); GRID_POINTS];
+        for (n, (c, row)) in self.coefficients.iter().zip(&self.basis).enumerate() {
+            let weight = Complex::expi(-(n as f64 + 0.5) * phase) * *c;
+            for (value, &phi) in psi.iter_mut().zip(row) {
+                *value = *value + weight * phi;
+            }
+        }
+        psi
+    }
+
+    fn density(&self) -> Vec<f64> {
+        self.psi().iter().map(|p| p.norm_sqr()).collect()
+    }
+
+    /// ⟨x⟩ integrated numerically from the current density
+    fn mean_position(&self) -> f64 {
+        let dx = self.x[1] - self.x[0];
+        self.density().iter().zip(&self.x).map(|(p, x)| p * x * dx).sum()
+    }
+}
+
+impl FrameStream for CoherentStream {
+    fn header(&self) -> Value {
+        let eigenstates: Vec<Value> = self
+            .basis
+            .iter()
+            .take(self.num_states)
+            .enumerate()
+            .map(|(n, psi)| {
+                serde_json::json!({
+                    "n": n,
+                    "energy": n as f64 + 0.5,
+                    "psi": psi,
+                })
+            })
+            .collect();
+        let alpha_sq = self.displacement * self.displacement / 2.0;
+
+        serde_json::json!({
+            "x": self.x,
+            "potential": self.x.iter().map(|x| x * x / 2.0).collect::<Vec<_>>(),
+            "eigenstates": eigenstates,
+            "alpha": self.displacement / std::f64::consts::SQRT_2,
+            "mean_quantum_number": alpha_sq,
+            "mean_energy": alpha_sq + 0.5,
+            "occupation": self
+                .coefficients
+                .iter()
+                .take(OCCUPATION_TERMS as usize)
+                .map(|c| c * c)
+                .collect::<Vec<_>>(),
+            "classical_amplitude": self.displacement,
+            "num_frames": self.num_frames,
+            "frame_interval": self.dt,
+        })
+    }
+
+    fn next_frame(&mut self) -> Option<Value> {
+        if !self.advance() {
+            return None;
+        }
+        Some(serde_json::json!({
+            "index": self.frame,
+            "time": self.time(),
+            "density": self.density(),
+            "mean_position": self.mean_position(),
+            "classical_position": self.displacement * (std::f64::consts::TAU * self.time()).cos(),
+        }))
+    }
+}
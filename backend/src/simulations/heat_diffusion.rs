@@ -0,0 +1,273 @@
+//! Heat diffusion along a thin rod
+
+use serde_json::Value;
+
+use crate::models::quiz::{Question, Quiz};
+use crate::numerics::diffusion::{Boundary, Diffusion};
+use crate::physics::constants::{CENTI, HECTO, MEGA, MICRO};
+use crate::simulations::params::Params;
+use crate::simulations::{Derived, FrameStream, Simulation, SimulationError, SimulationRegistry, output};
+
+const GRID_POINTS: usize = 201;
+/// Largest mesh ratio DΔt/Δx² used for the Crank–Nicolson steps
+const MAX_MESH_RATIO: f64 = 5.0;
+/// Backward Euler steps taken first to damp the sharp edges of the
+/// initial profile before switching to Crank–Nicolson (Rannacher start-up)
+const STARTUP_STEPS: usize = 4;
+/// Upper bound on time steps per run
+const MAX_STEPS: usize = 100_000;
+
+pub fn register(registry: &mut SimulationRegistry) {
+    registry.register(HeatDiffusion);
+}
+
+pub struct HeatDiffusion;
+
+impl Simulation for HeatDiffusion {
+    fn id(&self) -> &'static str {
+        "heat-diffusion"
+    }
+
+    fn quiz(&self) -> Option<Quiz> {
+        Some(Quiz {
+            simulation_id: "heat-diffusion".to_string(),
+            title: "Heat Diffusion Quiz".to_string(),
+            questions: vec![
+                Question::multiple_choice(
+                    "steady_fixed",
+                    "Both ends of a rod are held at different fixed temperatures. What is the final temperature profile?",
+                    &["Uniform", "A straight line between the end temperatures", "A parabola", "It never settles"],
+                    1,
+                    "In steady state ∂²T/∂x² = 0, so T varies linearly between the ends.",
+                )
+                .with_hints(&[
+                    "In steady state the temperature no longer changes, so ∂T/∂t = 0.",
+                    "Then the heat equation says ∂²T/∂x² = 0. Which functions have zero second derivative?",
+                ]),
+                Question::numeric(
+                    "scaling",
+                    "Heat takes 10 s to spread across 1 cm of a rod. Roughly how long does it take to spread across 3 cm?",
+                    "s",
+                    90.0,
+                    0.05,
+                    "Diffusion time scales with length squared: 10 s × 3² = 90 s.",
+                )
+                .with_hints(&[
+                    "The heat equation gives a diffusion time that scales as L²/α.",
+                    "Tripling the distance multiplies the time by 3².",
+                ]),
+                Question::multiple_choice(
+                    "insulated",
+                    "Both ends of a rod with a hot spot are insulated. What happens to the average temperature?",
+                    &["It rises", "It falls to zero", "It stays the same", "It oscillates"],
+                    2,
+                    "No heat can leave, so the total heat and the average temperature are conserved.",
+                )
+                .with_hints(&[
+                    "Insulated ends mean no heat flows in or out of the rod.",
+                    "If the total heat is fixed, what happens to the average temperature?",
+                ]),
+            ],
+        })
+    }
+
+    fn units(&self) -> &'static [(&'static str, &'static str)] {
+        &[("x", "cm"), ("times", "s")]
+    }
+
+    fn prerequisites(&self) -> &'static [&'static str] {
+        &["ideal-gas"]
+    }
+
+    fn derived(&self, params: &Params, data: &Value) -> Vec<Derived> {
+        let mixed = params.str("left_boundary") != params.str("right_boundary");
+        vec![Derived::new(
+            "relaxation_time",
+            "Relaxation Time of the Slowest Mode",
+            output(data, "/relaxation_time"),
+            "s",
+            if mixed { r"\tau = \frac{4L^2}{\pi^2 \alpha}" } else { r"\tau = \frac{L^2}{\pi^2 \alpha}" },
+        )]
+    }
+
+    fn run(&self, params: &Params) -> Result<Value, SimulationError> {
+        let mut stream = RodStream::new(params);
+        let mut times = vec![stream.time()];
+        let mut frames = vec![stream.temperature.clone()];
+        let mut mean_temperature = vec![stream.mean_temperature()];
+        while stream.advance() {
+            times.push(stream.time());
+            frames.push(stream.temperature.clone());
+            mean_temperature.push(stream.mean_temperature());
+        }
+
+        let mut data = stream.header();
+        data["times"] = times.into();
+        data["frames"] = frames.into();
+        data["mean_temperature"] = mean_temperature.into();
+        Ok(data)
+    }
+
+    fn stream(&self, params: &Params) -> Result<Option<Box<dyn FrameStream>>, SimulationError> {
+        Ok(Some(Box::new(RodStream::new(params))))
+    }
+}
+
+struct RodStream {
+    temperature: Vec<f64>,
+    initial: Vec<f64>,
+    startup: Diffusion,
+    solver: Diffusion,
+    left: Boundary,
+    right: Boundary,
+    /// Rod length (m)
+    length: f64,
+    /// Diffusivity (m²/s)
+    diffusivity: f64,
+    dt: f64,
+    step: usize,
+    steps_per_frame: usize,
+    num_frames: usize,
+    frame: usize,
+}
+
+impl RodStream {
+    fn new(params: &Params) -> Self {
+        let length = params.f64("length") * CENTI;
+        let diffusivity = params.f64("diffusivity") * MICRO;
+        let num_frames = params.i64("num_frames") as usize;
+        let duration = params.f64("duration");
+        let boundary = |side: &str| match params.str(&format!("{side}_boundary")) {
+            "insulated" => Boundary::Insulated,
+            _ => Boundary::Fixed(params.f64(&format!("{side}_temperature"))),
+        };
+        let (left, right) = (boundary("left"), boundary("right"));
+
+        let dx = length / (GRID_POINTS - 1) as f64;
+        let frame_time = duration / num_frames as f64;
+        let steps_per_frame = ((frame_time * diffusivity / (MAX_MESH_RATIO * dx * dx)).ceil() as usize)
+            .clamp(1, MAX_STEPS / num_frames);
+        let dt = frame_time / steps_per_frame as f64;
+
+        let base = params.f64("base_temperature");
+        let peak = params.f64("peak_temperature");
+        let mut temperature: Vec<f64> = (0..GRID_POINTS)
+            .map(|i| {
+                let s = i as f64 / (GRID_POINTS - 1) as f64;
+                match params.str("initial_profile") {
+                    "step" if s < 0.5 => peak,
+                    "spike" => base + (peak - base) * (-((s - 0.5) / 0.05).powi(2)).exp(),
+                    "sine" => base + (peak - base) * (std::f64::consts::PI * s).sin(),
+                    _ => base,
+                }
+            })
+            .collect();
+        // Fixed ends take their reservoir temperature from the start
+        if let Boundary::Fixed(value) = left {
+            temperature[0] = value;
+        }
+        if let Boundary::Fixed(value) = right {
+            temperature[GRID_POINTS - 1] = value;
+        }
+
+        Self {
+            initial: temperature.clone(),
+            temperature,
+            startup: Diffusion::new(GRID_POINTS, dx, dt, diffusivity, 1.0, left, right),
+            solver: Diffusion::new(GRID_POINTS, dx, dt, diffusivity, 0.5, left, right),
+            left,
+            right,
+            length,
+            diffusivity,
+            dt,
+            step: 0,
+            steps_per_frame,
+            num_frames,
+            frame: 0,
+        }
+    }
+
+    /// Step forward to the next output frame; false once the run is over
+    fn advance(&mut self) -> bool {
+        if self.frame >= self.num_frames {
+            return false;
+        }
+        for _ in 0..self.steps_per_frame {
+            let solver = if self.step < STARTUP_STEPS { &self.startup } else { &self.solver };
+            solver.step(&mut self.temperature);
+            self.step += 1;
+        }
+        self.frame += 1;
+        true
+    }
+
+    fn time(&self) -> f64 {
+        self.step as f64 * self.dt
+    }
+
+    fn mean_temperature(&self) -> f64 {
+        rod_average(&self.temperature)
+    }
+
+    /// τ = 1/(αk²) for the slowest decaying mode: k = π/L with both ends
+    /// fixed or both insulated, k = π/2L with one of each
+    fn relaxation_time(&self) -> f64 {
+        let mixed = matches!(
+            (self.left, self.right),
+            (Boundary::Fixed(_), Boundary::Insulated) | (Boundary::Insulated, Boundary::Fixed(_))
+        );
+        let k = std::f64::consts::PI / self.length / if mixed { 2.0 } else { 1.0 };
+        1.0 / (self.diffusivity * k * k)
+    }
+
+    /// Temperature profile the rod relaxes to
+    fn steady_state(&self, initial_mean: f64) -> Vec<f64> {
+        (0..GRID_POINTS)
+            .map(|i| {
+                let s = i as f64 / (GRID_POINTS - 1) as f64;
+                match (self.left, self.right) {
+                    (Boundary::Fixed(a), Boundary::Fixed(b)) => a + (b - a) * s,
+                    (Boundary::Fixed(a), Boundary::Insulated) | (Boundary::Insulated, Boundary::Fixed(a)) => a,
+                    (Boundary::Insulated, Boundary::Insulated) => initial_mean,
+                }
+            })
+            .collect()
+    }
+}
+
+impl FrameStream for RodStream {
+    fn header(&self) -> Value {
+        serde_json::json!({
+            "x": (0..GRID_POINTS)
+                .map(|i| self.length * HECTO * i as f64 / (GRID_POINTS - 1) as f64)
+                .collect::<Vec<_>>(),
+            "initial": self.initial,
+            "steady_state": self.steady_state(rod_average(&self.initial)),
+            // Decay time of the slowest mode, set by the end conditions
+            "relaxation_time": self.relaxation_time(),
+            "time_step": self.dt,
+            "num_frames": self.num_frames,
+            "diffusivity": self.diffusivity * MEGA,
+            "length": self.length * HECTO,
+        })
+    }
+
+    fn next_frame(&mut self) -> Option<Value> {
+        if !self.advance() {
+            return None;
+        }
+        Some(serde_json::json!({
+            "index": self.frame,
+            "time": self.time(),
+            "temperature": self.temperature,
+            "mean_temperature": self.mean_temperature(),
+        }))
+    }
+}
+
+/// Average over the rod by the trapezoidal rule
+fn rod_average(values: &[f64]) -> f64 {
+    let n = values.len();
+    let interior: f64 = values[1..n - 1].iter().sum();
+    (interior + (values[0] + values[n - 1]) / 2.0) / (n - 1) as f64
+}
@@ -0,0 +1,424 @@
+//! Kinetic theory of a two-dimensional hard-sphere gas
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+
+use serde_json::Value;
+
+use crate::models::quiz::{Question, Quiz};
+use crate::numerics::monte_carlo::MonteCarlo;
+use crate::physics::constants::{ATOMIC_MASS_UNIT, BOLTZMANN, GIGA, NANO, TERA};
+use crate::simulations::params::Params;
+use crate::simulations::{Derived, Simulation, SimulationError, SimulationRegistry, output};
+
+/// Argon atom mass (u)
+const PARTICLE_MASS_U: f64 = 39.948;
+/// Argon hard-sphere diameter (m)
+const PARTICLE_DIAMETER: f64 = 0.34e-9;
+/// Particle–particle collisions per particle, first to relax to
+/// equilibrium and then again while measuring
+const COLLISIONS_PER_PARTICLE: usize = 20;
+/// Speed snapshots taken at evenly spaced times while measuring
+const SNAPSHOTS: usize = 200;
+const HISTOGRAM_BINS: usize = 40;
+/// Histogram range in units of the most probable speed
+const HISTOGRAM_RANGE: f64 = 4.0;
+/// Safety limit on processed events per phase
+const MAX_EVENTS: usize = 2_000_000;
+
+pub fn register(registry: &mut SimulationRegistry) {
+    registry.register(IdealGas);
+}
+
+pub struct IdealGas;
+
+impl Simulation for IdealGas {
+    fn id(&self) -> &'static str {
+        "ideal-gas"
+    }
+
+    fn quiz(&self) -> Option<Quiz> {
+        Some(Quiz {
+            simulation_id: "ideal-gas".to_string(),
+            title: "Kinetic Theory Quiz".to_string(),
+            questions: vec![
+                Question::numeric(
+                    "rms_scaling",
+                    "By what factor does the rms speed of the atoms grow when the temperature is quadrupled?",
+                    "×",
+                    2.0,
+                    0.01,
+                    "v_rms = √(2k_BT/m) grows with the square root of temperature, so 4× the temperature gives 2× the speed.",
+                )
+                .with_hints(&[
+                    "The mean kinetic energy of an atom is proportional to the temperature.",
+                    "Kinetic energy goes as v², so v_rms goes as the square root of T.",
+                ]),
+                Question::multiple_choice(
+                    "thermalization",
+                    "All atoms start with exactly the same speed. What happens to the speed distribution?",
+                    &["It stays a single sharp spike", "Collisions spread it into the Maxwell–Boltzmann distribution", "All atoms slow down to zero", "It becomes uniform between zero and twice the speed"],
+                    1,
+                    "Elastic collisions conserve total energy but share it out randomly, which relaxes the speeds to the Maxwell–Boltzmann distribution.",
+                )
+                .with_hints(&[
+                    "Collisions conserve total energy, but do they conserve each atom's speed?",
+                    "Run the simulation from equal speeds and watch the histogram settle. Which distribution does a gas in equilibrium have?",
+                ]),
+                Question::multiple_choice(
+                    "dense_pressure",
+                    "Why does a dense hard-sphere gas exert more pressure than the ideal gas law predicts?",
+                    &["The atoms attract each other", "The atoms take up space, so they hit the walls more often", "The temperature rises with density", "Collisions lose energy"],
+                    1,
+                    "The atoms' own size reduces the free area, so they cross the box and strike the walls more often than point particles would.",
+                )
+                .with_hints(&[
+                    "The ideal gas law treats atoms as points. What changes when they have a size?",
+                    "Bigger atoms leave less free space, so each one needs less time to cross the box.",
+                ]),
+            ],
+        })
+    }
+
+    fn units(&self) -> &'static [(&'static str, &'static str)] {
+        &[("speed_bins", "m/s"), ("x", "nm"), ("y", "nm")]
+    }
+
+    fn prerequisites(&self) -> &'static [&'static str] {
+        &["projectile-motion"]
+    }
+
+    fn derived(&self, _params: &Params, data: &Value) -> Vec<Derived> {
+        vec![
+            Derived::new(
+                "most_probable_speed",
+                "Most Probable Speed",
+                output(data, "/theory_speeds/most_probable"),
+                "m/s",
+                r"v_p = \sqrt{k_B T / m}",
+            ),
+            Derived::new(
+                "rms_speed",
+                "Root-Mean-Square Speed",
+                output(data, "/theory_speeds/rms"),
+                "m/s",
+                r"v_{\mathrm{rms}} = \sqrt{2 k_B T / m}",
+            ),
+            Derived::new(
+                "ideal_pressure",
+                "Ideal-Gas Pressure",
+                output(data, "/ideal_pressure"),
+                "N/m",
+                r"P = N k_B T / A",
+            ),
+            Derived::new(
+                "compressibility_factor",
+                "Compressibility Factor",
+                output(data, "/compressibility_factor"),
+                "",
+                r"Z = P A / N k_B T",
+            ),
+        ]
+    }
+
+    fn run(&self, params: &Params) -> Result<Value, SimulationError> {
+        let num_particles = params.i64("num_particles") as usize;
+        let temperature = params.f64("temperature");
+        let area = params.f64("area");
+
+        let mass = PARTICLE_MASS_U * ATOMIC_MASS_UNIT;
+        let side = area.sqrt() * NANO;
+        let kt = BOLTZMANN * temperature;
+        let most_probable_speed = (kt / mass).sqrt();
+
+        // Equal speeds in random directions, with the same total energy as
+        // an equilibrium gas at this temperature
+        let mut mc = params.monte_carlo();
+        let mut gas = Gas::new(num_particles, side, mass, (2.0 * kt / mass).sqrt(), &mut mc);
+
+        // Relax to equilibrium, then measure over the same span of time
+        let target = COLLISIONS_PER_PARTICLE * num_particles / 2;
+        let mut events = 0;
+        while gas.collisions < target && events < MAX_EVENTS && gas.step(f64::INFINITY) {
+            events += 1;
+        }
+        let duration = gas.time;
+        let start = gas.time;
+        gas.collisions = 0;
+        gas.wall_impulse = 0.0;
+
+        let mut speeds = Vec::with_capacity(SNAPSHOTS * num_particles);
+        let mut events = 0;
+        for k in 1..=SNAPSHOTS {
+            let until = start + duration * k as f64 / SNAPSHOTS as f64;
+            while events < MAX_EVENTS && gas.step(until) {
+                events += 1;
+            }
+            gas.drift_to(until);
+            speeds.extend(gas.velocities.iter().map(|v| v[0].hypot(v[1])));
+        }
+
+        let bin_width = HISTOGRAM_RANGE * most_probable_speed / HISTOGRAM_BINS as f64;
+        let mut counts = vec![0usize; HISTOGRAM_BINS];
+        for &speed in &speeds {
+            let bin = (speed / bin_width) as usize;
+            if bin < HISTOGRAM_BINS {
+                counts[bin] += 1;
+            }
+        }
+        let centers: Vec<f64> = (0..HISTOGRAM_BINS).map(|b| (b as f64 + 0.5) * bin_width).collect();
+        let histogram: Vec<f64> = counts
+            .iter()
+            .map(|&c| c as f64 / (speeds.len() as f64 * bin_width))
+            .collect();
+        let maxwell_boltzmann: Vec<f64> = centers
+            .iter()
+            .map(|&v| mass * v / kt * (-mass * v * v / (2.0 * kt)).exp())
+            .collect();
+
+        let n = speeds.len() as f64;
+        let mean_speed = speeds.iter().sum::<f64>() / n;
+        let rms_speed = (speeds.iter().map(|v| v * v).sum::<f64>() / n).sqrt();
+
+        let area_m2 = side * side;
+        let pressure = gas.wall_impulse / (duration * 4.0 * side);
+        let ideal_pressure = num_particles as f64 * kt / area_m2;
+        let packing_fraction = num_particles as f64 * std::f64::consts::PI * (PARTICLE_DIAMETER / 2.0).powi(2) / area_m2;
+        let henderson = (1.0 + packing_fraction.powi(2) / 8.0) / (1.0 - packing_fraction).powi(2);
+
+        Ok(serde_json::json!({
+            "speed_bins": centers,
+            "speed_histogram": histogram,
+            "maxwell_boltzmann": maxwell_boltzmann,
+            "mean_speed": mean_speed,
+            "rms_speed": rms_speed,
+            "theory_speeds": {
+                "most_probable": most_probable_speed,
+                "mean": (std::f64::consts::PI * kt / (2.0 * mass)).sqrt(),
+                "rms": (2.0 * kt / mass).sqrt(),
+            },
+            "pressure": pressure,
+            "ideal_pressure": ideal_pressure,
+            "hard_disk_pressure": ideal_pressure * henderson,
+            "compressibility_factor": pressure / ideal_pressure,
+            "packing_fraction": packing_fraction,
+            "duration_ps": duration * TERA,
+            "collisions": gas.collisions,
+            "x": gas.positions.iter().map(|p| p[0] * GIGA).collect::<Vec<_>>(),
+            "y": gas.positions.iter().map(|p| p[1] * GIGA).collect::<Vec<_>>(),
+            "box_size_nm": side * GIGA,
+            "seed": mc.seed(),
+            "num_particles": num_particles,
+            "temperature": temperature,
+            "area": area,
+        }))
+    }
+}
+
+/// Event-driven hard-disc gas in a square box, in SI units
+struct Gas {
+    positions: Vec<[f64; 2]>,
+    velocities: Vec<[f64; 2]>,
+    /// Bumped whenever a particle's velocity changes, so stale events can
+    /// be recognised
+    versions: Vec<u64>,
+    side: f64,
+    mass: f64,
+    time: f64,
+    events: BinaryHeap<Reverse<Event>>,
+    /// Particle–particle collisions processed
+    collisions: usize,
+    /// Momentum delivered to the walls (N·s)
+    wall_impulse: f64,
+}
+
+struct Event {
+    time: f64,
+    kind: EventKind,
+    /// Versions of the particles involved when the event was predicted
+    versions: (u64, u64),
+}
+
+#[derive(Clone, Copy)]
+enum EventKind {
+    Pair(usize, usize),
+    /// Bounce off a wall perpendicular to the given axis
+    Wall(usize, usize),
+}
+
+impl PartialEq for Event {
+    fn eq(&self, other: &Self) -> bool {
+        self.time.total_cmp(&other.time) == Ordering::Equal
+    }
+}
+
+impl Eq for Event {}
+
+impl PartialOrd for Event {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Event {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.time.total_cmp(&other.time)
+    }
+}
+
+impl Gas {
+    /// Place `count` particles on a jittered square lattice, moving at
+    /// `speed` in random directions
+    fn new(count: usize, side: f64, mass: f64, speed: f64, mc: &mut MonteCarlo) -> Self {
+        let per_row = (count as f64).sqrt().ceil() as usize;
+        let spacing = side / per_row as f64;
+        // Keeps neighbours apart and clear of the walls while spacing > diameter
+        let jitter = 0.45 * (spacing - PARTICLE_DIAMETER);
+
+        let mut positions = Vec::with_capacity(count);
+        let mut velocities = Vec::with_capacity(count);
+        for i in 0..count {
+            let cell = [(i % per_row) as f64, (i / per_row) as f64];
+            positions.push(cell.map(|c| (c + 0.5) * spacing + mc.uniform_range(-jitter, jitter)));
+            velocities.push(mc.direction_2d().map(|d| d * speed));
+        }
+
+        let mut gas = Self {
+            positions,
+            velocities,
+            versions: vec![0; count],
+            side,
+            mass,
+            time: 0.0,
+            events: BinaryHeap::new(),
+            collisions: 0,
+            wall_impulse: 0.0,
+        };
+        for i in 0..count {
+            gas.predict(i);
+        }
+        gas
+    }
+
+    /// Process the next valid event if it happens no later than `until`.
+    /// Returns false when there is none.
+    fn step(&mut self, until: f64) -> bool {
+        while let Some(Reverse(event)) = self.events.peek() {
+            if event.time > until {
+                return false;
+            }
+            let Some(Reverse(event)) = self.events.pop() else {
+                return false;
+            };
+            match event.kind {
+                EventKind::Pair(a, b) => {
+                    let a_current = self.versions[a] == event.versions.0;
+                    let b_current = self.versions[b] == event.versions.1;
+                    if a_current && b_current {
+                        self.drift_to(event.time);
+                        self.collide(a, b);
+                        self.predict(a);
+                        self.predict(b);
+                        return true;
+                    }
+                    // Only the earliest partner of each particle is queued, so a
+                    // particle whose partner changed course needs a new prediction
+                    if a_current {
+                        self.predict(a);
+                    }
+                    if b_current {
+                        self.predict(b);
+                    }
+                }
+                EventKind::Wall(i, axis) => {
+                    if self.versions[i] == event.versions.0 {
+                        self.drift_to(event.time);
+                        self.wall_impulse += 2.0 * self.mass * self.velocities[i][axis].abs();
+                        self.velocities[i][axis] = -self.velocities[i][axis];
+                        self.versions[i] += 1;
+                        self.predict(i);
+                        return true;
+                    }
+                }
+            }
+        }
+        false
+    }
+
+    /// Move every particle in a straight line up to time `t`
+    fn drift_to(&mut self, t: f64) {
+        let dt = t - self.time;
+        for (p, v) in self.positions.iter_mut().zip(&self.velocities) {
+            p[0] += v[0] * dt;
+            p[1] += v[1] * dt;
+        }
+        self.time = t;
+    }
+
+    /// Elastic collision between equal-mass discs in contact
+    fn collide(&mut self, a: usize, b: usize) {
+        let dr = [self.positions[b][0] - self.positions[a][0], self.positions[b][1] - self.positions[a][1]];
+        let dv = [self.velocities[b][0] - self.velocities[a][0], self.velocities[b][1] - self.velocities[a][1]];
+        let distance = dr[0].hypot(dr[1]);
+        // Velocity change along the line of centres
+        let exchange = (dv[0] * dr[0] + dv[1] * dr[1]) / (distance * distance);
+        for (axis, d) in dr.iter().enumerate() {
+            self.velocities[a][axis] += exchange * d;
+            self.velocities[b][axis] -= exchange * d;
+        }
+        self.versions[a] += 1;
+        self.versions[b] += 1;
+        self.collisions += 1;
+    }
+
+    /// Queue the next wall bounce of particle `i` and its earliest collision
+    /// with another particle on current trajectories
+    fn predict(&mut self, i: usize) {
+        let radius = PARTICLE_DIAMETER / 2.0;
+        let [x, v] = [self.positions[i], self.velocities[i]];
+
+        let wall = (0..2)
+            .filter(|&axis| v[axis] != 0.0)
+            .map(|axis| {
+                let target = if v[axis] > 0.0 { self.side - radius } else { radius };
+                (((target - x[axis]) / v[axis]).max(0.0), axis)
+            })
+            .min_by(|a, b| a.0.total_cmp(&b.0));
+        if let Some((dt, axis)) = wall {
+            self.push(dt, EventKind::Wall(i, axis), (self.versions[i], 0));
+        }
+
+        let pair = (0..self.positions.len())
+            .filter(|&j| j != i)
+            .filter_map(|j| self.time_to_contact(i, j).map(|dt| (dt, j)))
+            .min_by(|a, b| a.0.total_cmp(&b.0));
+        if let Some((dt, j)) = pair {
+            self.push(dt, EventKind::Pair(i, j), (self.versions[i], self.versions[j]));
+        }
+    }
+
+    /// Time until discs `i` and `j` touch, if they are approaching
+    fn time_to_contact(&self, i: usize, j: usize) -> Option<f64> {
+        let dr = [self.positions[j][0] - self.positions[i][0], self.positions[j][1] - self.positions[i][1]];
+        let dv = [self.velocities[j][0] - self.velocities[i][0], self.velocities[j][1] - self.velocities[i][1]];
+        let dvdr = dv[0] * dr[0] + dv[1] * dr[1];
+        if dvdr >= 0.0 {
+            return None;
+        }
+        let dvdv = dv[0] * dv[0] + dv[1] * dv[1];
+        let drdr = dr[0] * dr[0] + dr[1] * dr[1];
+        let discriminant = dvdr * dvdr - dvdv * (drdr - PARTICLE_DIAMETER * PARTICLE_DIAMETER);
+        if discriminant < 0.0 {
+            return None;
+        }
+        Some((-(dvdr + discriminant.sqrt()) / dvdv).max(0.0))
+    }
+
+    fn push(&mut self, dt: f64, kind: EventKind, versions: (u64, u64)) {
+        self.events.push(Reverse(Event {
+            time: self.time + dt,
+            kind,
+            versions,
+        }));
+    }
+}
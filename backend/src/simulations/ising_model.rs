@@ -0,0 +1,311 @@
+//! 2D Ising model with Metropolis Monte Carlo
+
+use serde_json::Value;
+
+use crate::models::quiz::{Question, Quiz};
+use crate::numerics::monte_carlo::MonteCarlo;
+use crate::simulations::params::{ParameterError, Params};
+use crate::simulations::{Derived, Progress, Simulation, SimulationError, SimulationRegistry, output};
+
+/// Upper bound on attempted spin flips per run (L² × sweeps × temperatures)
+const MAX_SPIN_UPDATES: usize = 50_000_000;
+/// Fraction of the sweeps at each temperature discarded as equilibration
+const EQUILIBRATION_FRACTION: f64 = 0.2;
+/// Number of lattice snapshots returned across the temperature scan
+const SNAPSHOTS: usize = 5;
+/// Running averages published per temperature step for job observers
+const PARTIAL_REPORTS: usize = 10;
+/// Onsager's exact critical temperature, in units of J/k_B
+const CRITICAL_TEMPERATURE: f64 = 2.269_185_314_213_022;
+
+pub fn register(registry: &mut SimulationRegistry) {
+    registry.register(IsingModel);
+}
+
+pub struct IsingModel;
+
+impl Simulation for IsingModel {
+    fn id(&self) -> &'static str {
+        "ising-model"
+    }
+
+    fn quiz(&self) -> Option<Quiz> {
+        Some(Quiz {
+            simulation_id: "ising-model".to_string(),
+            title: "Ising Model Quiz".to_string(),
+            questions: vec![
+                Question::multiple_choice(
+                    "high_temperature",
+                    "What happens to the magnetization well above the critical temperature with no external field?",
+                    &["It saturates at 1", "It averages to zero", "It oscillates", "It becomes negative"],
+                    1,
+                    "Thermal fluctuations dominate and the spins point up and down at random.",
+                )
+                .with_hints(&[
+                    "At high temperature thermal energy k_BT is much larger than the coupling J.",
+                    "If every spin flips almost at random, what is the average of many ±1 values?",
+                ]),
+                Question::numeric(
+                    "critical_temperature",
+                    "What is Onsager's critical temperature of the 2D square-lattice Ising model, in units of J/k_B?",
+                    "J/k_B",
+                    2.269,
+                    0.01,
+                    "T_c = 2 / ln(1 + √2) ≈ 2.269 J/k_B.",
+                )
+                .with_hints(&[
+                    "Onsager solved the 2D model exactly; T_c is where sinh(2J/k_BT) = 1.",
+                    "Solving gives T_c = 2 / ln(1 + √2) in units of J/k_B.",
+                ]),
+                Question::multiple_choice(
+                    "susceptibility",
+                    "Why does the susceptibility peak near the critical temperature?",
+                    &[
+                        "The spins stop moving",
+                        "The magnetization fluctuates strongly as large domains form and dissolve",
+                        "The external field is largest there",
+                        "The lattice expands",
+                    ],
+                    1,
+                    "χ measures magnetization fluctuations, which are largest where domains of every size coexist.",
+                )
+                .with_hints(&[
+                    "The susceptibility is related to the fluctuations of the magnetization: χ ∝ ⟨M²⟩ − ⟨M⟩².",
+                    "Watch the lattice near T_c. How big are the domains, and how fast does M change?",
+                ]),
+            ],
+        })
+    }
+
+    fn units(&self) -> &'static [(&'static str, &'static str)] {
+        &[("temperature", "J/k_B")]
+    }
+
+    fn prerequisites(&self) -> &'static [&'static str] {
+        &["ideal-gas"]
+    }
+
+    fn derived(&self, _params: &Params, data: &Value) -> Vec<Derived> {
+        let numbers = |name: &str| -> Vec<f64> {
+            data[name].as_array().map_or(Vec::new(), |values| values.iter().filter_map(Value::as_f64).collect())
+        };
+        let heat_capacity = numbers("heat_capacity");
+        let peak = numbers("temperature")
+            .into_iter()
+            .zip(heat_capacity)
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map_or(f64::NAN, |(temperature, _)| temperature);
+        vec![
+            Derived::new(
+                "critical_temperature",
+                "Critical Temperature (Onsager)",
+                output(data, "/critical_temperature"),
+                "J/k_B",
+                r"T_c = \frac{2J}{k_B \ln(1 + \sqrt{2})}",
+            ),
+            Derived::new(
+                "heat_capacity_peak",
+                "Temperature of the Heat Capacity Peak",
+                peak,
+                "J/k_B",
+                r"\arg\max_T C(T),\quad C = \frac{\langle E^2 \rangle - \langle E \rangle^2}{k_B T^2}",
+            ),
+        ]
+    }
+
+    fn run(&self, params: &Params) -> Result<Value, SimulationError> {
+        self.run_with_progress(params, &Progress::default())
+    }
+
+    fn run_with_progress(&self, params: &Params, progress: &Progress) -> Result<Value, SimulationError> {
+        let size = params.i64("lattice_size") as usize;
+        let temperature_min = params.f64("temperature_min");
+        let temperature_max = params.f64("temperature_max");
+        let points = params.i64("temperature_points") as usize;
+        let field = params.f64("external_field");
+        let sweeps = params.i64("sweeps") as usize;
+
+        if temperature_max <= temperature_min {
+            return Err(SimulationError::InvalidParameters(vec![ParameterError::new(
+                "temperature_max",
+                format!("must be greater than the lowest temperature ({temperature_min})"),
+                params.values().get("temperature_max"),
+            )]));
+        }
+        let updates = size * size * sweeps * points;
+        if updates > MAX_SPIN_UPDATES {
+            return Err(SimulationError::InvalidParameters(vec![ParameterError::new(
+                "sweeps",
+                format!("L² × sweeps × temperature steps is {updates}; the limit is {MAX_SPIN_UPDATES}"),
+                params.values().get("sweeps"),
+            )]));
+        }
+
+        // Anneal from hot to cold, carrying the lattice over so each
+        // temperature starts close to equilibrium
+        let mut mc = params.monte_carlo();
+        let mut lattice = Lattice::random(size, &mut mc);
+        let equilibration = (sweeps as f64 * EQUILIBRATION_FRACTION) as usize;
+        let snapshot_every = (points - 1).div_ceil(SNAPSHOTS - 1).max(1);
+        let n = (size * size) as f64;
+
+        let mut temperatures = Vec::with_capacity(points);
+        let mut magnetization = Vec::with_capacity(points);
+        let mut abs_magnetization = Vec::with_capacity(points);
+        let mut energy = Vec::with_capacity(points);
+        let mut susceptibility = Vec::with_capacity(points);
+        let mut heat_capacity = Vec::with_capacity(points);
+        let mut exact = Vec::with_capacity(points);
+        let mut snapshots = Vec::new();
+        for i in 0..points {
+            let temperature = temperature_max - (temperature_max - temperature_min) * i as f64 / (points - 1) as f64;
+            let acceptance = Acceptance::new(temperature, field);
+            for _ in 0..equilibration {
+                progress.check()?;
+                lattice.sweep(&acceptance, &mut mc);
+            }
+            let mut stats = [0.0; 5];
+            let measured = sweeps - equilibration;
+            let report_every = (measured / PARTIAL_REPORTS).max(1);
+            for done in 1..=measured {
+                progress.check()?;
+                lattice.sweep(&acceptance, &mut mc);
+                let m = lattice.magnetization();
+                let e = lattice.energy(field);
+                for (acc, value) in stats.iter_mut().zip([m, m.abs(), m * m, e, e * e]) {
+                    *acc += value;
+                }
+                if done % report_every == 0 {
+                    progress.set((i as f64 + done as f64 / measured as f64) / points as f64);
+                    progress.publish(serde_json::json!({
+                        "temperature": temperature,
+                        "sweeps": done,
+                        "abs_magnetization": stats[1] / done as f64,
+                        "energy": stats[3] / done as f64,
+                        "completed": {
+                            "temperature": temperatures,
+                            "abs_magnetization": abs_magnetization,
+                            "energy": energy,
+                        },
+                    }));
+                }
+            }
+            let [m, m_abs, m2, e, e2] = stats.map(|s| s / measured as f64);
+
+            temperatures.push(temperature);
+            magnetization.push(m);
+            abs_magnetization.push(m_abs);
+            energy.push(e);
+            susceptibility.push(n * (m2 - m_abs * m_abs) / temperature);
+            heat_capacity.push(n * (e2 - e * e) / (temperature * temperature));
+            exact.push(onsager_magnetization(temperature));
+            if i % snapshot_every == 0 || i == points - 1 {
+                snapshots.push(serde_json::json!({
+                    "temperature": temperature,
+                    "spins": lattice.rows(),
+                }));
+            }
+            progress.set((i + 1) as f64 / points as f64);
+        }
+
+        Ok(serde_json::json!({
+            "temperature": temperatures,
+            "magnetization": magnetization,
+            "abs_magnetization": abs_magnetization,
+            "energy": energy,
+            "susceptibility": susceptibility,
+            "heat_capacity": heat_capacity,
+            "onsager_magnetization": if field == 0.0 { Some(exact) } else { None },
+            "critical_temperature": CRITICAL_TEMPERATURE,
+            "snapshots": snapshots,
+            "lattice_size": size,
+            "sweeps": sweeps,
+            "equilibration_sweeps": equilibration,
+            "seed": mc.seed(),
+        }))
+    }
+}
+
+/// Metropolis acceptance probabilities, indexed by spin and neighbour sum
+struct Acceptance([f64; 10]);
+
+impl Acceptance {
+    fn new(temperature: f64, field: f64) -> Self {
+        let mut table = [0.0; 10];
+        for (index, p) in table.iter_mut().enumerate() {
+            let spin = if index < 5 { -1.0 } else { 1.0 };
+            let neighbours = (index % 5) as f64 * 2.0 - 4.0;
+            let delta_e = 2.0 * spin * (neighbours + field);
+            *p = (-delta_e / temperature).exp().min(1.0);
+        }
+        Self(table)
+    }
+
+    fn get(&self, spin: i8, neighbours: i8) -> f64 {
+        let index = if spin > 0 { 5 } else { 0 } + ((neighbours + 4) / 2) as usize;
+        self.0[index]
+    }
+}
+
+/// Square lattice of ±1 spins with periodic boundaries
+struct Lattice {
+    size: usize,
+    spins: Vec<i8>,
+}
+
+impl Lattice {
+    fn random(size: usize, mc: &mut MonteCarlo) -> Self {
+        let spins = (0..size * size).map(|_| if mc.uniform() < 0.5 { -1 } else { 1 }).collect();
+        Self { size, spins }
+    }
+
+    fn neighbour_sum(&self, row: usize, col: usize) -> i8 {
+        let l = self.size;
+        self.spins[(row + l - 1) % l * l + col]
+            + self.spins[(row + 1) % l * l + col]
+            + self.spins[row * l + (col + l - 1) % l]
+            + self.spins[row * l + (col + 1) % l]
+    }
+
+    /// One Monte Carlo sweep: L² single-spin Metropolis updates in sequence
+    fn sweep(&mut self, acceptance: &Acceptance, mc: &mut MonteCarlo) {
+        for row in 0..self.size {
+            for col in 0..self.size {
+                let index = row * self.size + col;
+                let p = acceptance.get(self.spins[index], self.neighbour_sum(row, col));
+                if p >= 1.0 || mc.uniform() < p {
+                    self.spins[index] = -self.spins[index];
+                }
+            }
+        }
+    }
+
+    fn magnetization(&self) -> f64 {
+        self.spins.iter().map(|&s| s as f64).sum::<f64>() / self.spins.len() as f64
+    }
+
+    /// Energy per spin in units of J, counting each bond once
+    fn energy(&self, field: f64) -> f64 {
+        let l = self.size;
+        let mut bonds = 0.0;
+        for row in 0..l {
+            for col in 0..l {
+                let s = self.spins[row * l + col];
+                bonds += (s * (self.spins[(row + 1) % l * l + col] + self.spins[row * l + (col + 1) % l])) as f64;
+            }
+        }
+        -(bonds + field * self.spins.iter().map(|&s| s as f64).sum::<f64>()) / self.spins.len() as f64
+    }
+
+    fn rows(&self) -> Vec<&[i8]> {
+        self.spins.chunks(self.size).collect()
+    }
+}
+
+/// Exact spontaneous magnetization of the infinite lattice at zero field
+fn onsager_magnetization(temperature: f64) -> f64 {
+    if temperature >= CRITICAL_TEMPERATURE {
+        return 0.0;
+    }
+    (1.0 - (2.0 / temperature).sinh().powi(-4)).powf(0.125)
+}
@@ -0,0 +1,283 @@
+//! Two-body gravitational orbits
+
+use serde_json::Value;
+
+use crate::models::quiz::{Question, Quiz};
+use crate::numerics::ode::verlet_step;
+use crate::physics::constants::{ASTRONOMICAL_UNIT, DAY, KILO, SOLAR_MASS_PARAMETER};
+use crate::simulations::params::Params;
+use crate::simulations::{Derived, Simulation, SimulationError, SimulationRegistry, Validation, output, series};
+
+/// Integration steps per run
+const STEPS: usize = 100_000;
+/// Number of path samples returned
+const OUTPUT_SAMPLES: usize = 500;
+/// Unbound orbits are followed for this many multiples of r₀/v₀
+const ESCAPE_TIME_SCALE: f64 = 50.0;
+/// Newton iterations allowed when solving Kepler's equation
+const KEPLER_ITERATIONS: usize = 50;
+
+pub fn register(registry: &mut SimulationRegistry) {
+    registry.register(KeplerOrbit);
+}
+
+pub struct KeplerOrbit;
+
+impl Simulation for KeplerOrbit {
+    fn id(&self) -> &'static str {
+        "kepler-orbit"
+    }
+
+    fn quiz(&self) -> Option<Quiz> {
+        Some(Quiz {
+            simulation_id: "kepler-orbit".to_string(),
+            title: "Kepler Orbits Quiz".to_string(),
+            questions: vec![
+                Question::multiple_choice(
+                    "fastest",
+                    "Where on an elliptical orbit does the planet move fastest?",
+                    &["At apoapsis, farthest from the star", "At periapsis, closest to the star", "Halfway between", "Its speed is constant"],
+                    1,
+                    "Equal areas in equal times: near the star the planet must cover more arc per second.",
+                )
+                .with_hints(&[
+                    "Kepler's second law: the line to the star sweeps equal areas in equal times.",
+                    "Close to the star the swept triangle is short. How must its arc compensate?",
+                ]),
+                Question::numeric(
+                    "period",
+                    "A planet orbits a 1 solar-mass star on an orbit with semi-major axis 4 AU. What is its period?",
+                    "years",
+                    8.0,
+                    0.02,
+                    "Kepler's third law in solar units gives T² = a³, so T = 4^{3/2} = 8 years.",
+                )
+                .with_hints(&[
+                    "Kepler's third law in years, AU and solar masses reads T² = a³/M.",
+                    "With M = 1, T = a^{3/2}.",
+                ]),
+                Question::multiple_choice(
+                    "escape",
+                    "By what factor must the circular orbit speed be increased to escape?",
+                    &["2", "√2", "1.5", "4"],
+                    1,
+                    "v_esc = √(2GM/r) = √2 × √(GM/r).",
+                )
+                .with_hints(&[
+                    "Circular speed is √(GM/r); escape speed follows from setting the total energy to zero.",
+                    "½v² = GM/r gives v_esc = √(2GM/r). Divide by the circular speed.",
+                ]),
+            ],
+        })
+    }
+
+    fn units(&self) -> &'static [(&'static str, &'static str)] {
+        &[("t", "d"), ("x", "AU"), ("y", "AU")]
+    }
+
+    fn prerequisites(&self) -> &'static [&'static str] {
+        &["projectile-motion"]
+    }
+
+    fn derived(&self, _params: &Params, data: &Value) -> Vec<Derived> {
+        vec![
+            Derived::new(
+                "period",
+                "Orbital Period",
+                output(data, "/period"),
+                "d",
+                r"T = 2\pi \sqrt{a^3 / GM}",
+            ),
+            Derived::new(
+                "semi_major_axis",
+                "Semi-Major Axis",
+                output(data, "/semi_major_axis"),
+                "AU",
+                r"a = -\frac{GM}{2\varepsilon},\quad \varepsilon = \frac{v^2}{2} - \frac{GM}{r}",
+            ),
+            Derived::new(
+                "eccentricity",
+                "Eccentricity",
+                output(data, "/eccentricity"),
+                "",
+                r"e = \sqrt{1 + \frac{2\varepsilon h^2}{(GM)^2}}",
+            ),
+            Derived::new(
+                "escape_velocity",
+                "Escape Velocity at the Start",
+                output(data, "/escape_velocity"),
+                "km/s",
+                r"v_{\mathrm{esc}} = \sqrt{2GM / r}",
+            ),
+        ]
+    }
+
+    /// Bound orbits follow Kepler's equation; unbound ones are not compared
+    fn validate(&self, params: &Params, data: &Value) -> Vec<Validation> {
+        let mu = SOLAR_MASS_PARAMETER * params.f64("central_mass");
+        let r0 = params.f64("initial_distance") * ASTRONOMICAL_UNIT;
+        let v0 = params.f64("initial_velocity") * KILO;
+        let elements = OrbitalElements::from_perpendicular_launch(mu, r0, v0);
+        if elements.period.is_none() {
+            return Vec::new();
+        }
+        let (x, y): (Vec<f64>, Vec<f64>) = series(data, "/t")
+            .iter()
+            .map(|t| {
+                let [x, y] = elements.kepler_position(t * DAY);
+                (x / ASTRONOMICAL_UNIT, y / ASTRONOMICAL_UNIT)
+            })
+            .unzip();
+        let formula = r"E - e \sin E = \frac{2\pi t}{T},\quad (x, y) = \left(a(\cos E - e),\ a\sqrt{1 - e^2}\,\sin E\right)";
+        [
+            Validation::compare("x", formula, "AU", &series(data, "/x"), &x),
+            Validation::compare("y", formula, "AU", &series(data, "/y"), &y),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    fn run(&self, params: &Params) -> Result<Value, SimulationError> {
+        let central_mass = params.f64("central_mass");
+        let initial_distance = params.f64("initial_distance");
+        let initial_velocity = params.f64("initial_velocity");
+
+        let mu = SOLAR_MASS_PARAMETER * central_mass;
+        let r0 = initial_distance * ASTRONOMICAL_UNIT;
+        let v0 = initial_velocity * KILO;
+        let elements = OrbitalElements::from_perpendicular_launch(mu, r0, v0);
+
+        let duration = elements
+            .period
+            .unwrap_or(ESCAPE_TIME_SCALE * r0 / v0);
+        let orbit = integrate_orbit(mu, r0, v0, duration);
+
+        Ok(serde_json::json!({
+            "t": orbit.t.iter().map(|t| t / DAY).collect::<Vec<_>>(),
+            "x": orbit.x.iter().map(|x| x / ASTRONOMICAL_UNIT).collect::<Vec<_>>(),
+            "y": orbit.y.iter().map(|y| y / ASTRONOMICAL_UNIT).collect::<Vec<_>>(),
+            "bound": elements.period.is_some(),
+            "eccentricity": elements.eccentricity,
+            "semi_major_axis": elements.semi_major_axis.map(|a| a / ASTRONOMICAL_UNIT),
+            "period": elements.period.map(|t| t / DAY),
+            "periapsis": elements.periapsis / ASTRONOMICAL_UNIT,
+            "apoapsis": elements.apoapsis.map(|r| r / ASTRONOMICAL_UNIT),
+            "circular_velocity": (mu / r0).sqrt() / KILO,
+            "escape_velocity": (2.0 * mu / r0).sqrt() / KILO,
+            "relative_energy_error": orbit.max_energy_error / elements.energy_scale,
+            "central_mass": central_mass,
+            "initial_distance": initial_distance,
+            "initial_velocity": initial_velocity,
+        }))
+    }
+}
+
+/// Shape of the orbit from its initial state, in SI units
+struct OrbitalElements {
+    eccentricity: f64,
+    semi_major_axis: Option<f64>,
+    period: Option<f64>,
+    periapsis: f64,
+    apoapsis: Option<f64>,
+    /// Whether the launch point is the periapsis rather than the apoapsis
+    starts_at_periapsis: bool,
+    /// |GM/r₀|, the size of the energies involved
+    energy_scale: f64,
+}
+
+impl OrbitalElements {
+    /// Elements of an orbit launched at distance `r0` with speed `v0`
+    /// perpendicular to the radius vector
+    fn from_perpendicular_launch(mu: f64, r0: f64, v0: f64) -> Self {
+        let energy = 0.5 * v0 * v0 - mu / r0;
+        let h = r0 * v0;
+        let eccentricity = (1.0 + 2.0 * energy * h * h / (mu * mu)).max(0.0).sqrt();
+        let periapsis = h * h / mu / (1.0 + eccentricity);
+
+        let (semi_major_axis, period, apoapsis) = if energy < 0.0 {
+            let a = -mu / (2.0 * energy);
+            let period = 2.0 * std::f64::consts::PI * (a.powi(3) / mu).sqrt();
+            (Some(a), Some(period), Some(a * (1.0 + eccentricity)))
+        } else {
+            (None, None, None)
+        };
+
+        Self {
+            eccentricity,
+            semi_major_axis,
+            period,
+            periapsis,
+            apoapsis,
+            starts_at_periapsis: v0 * v0 >= mu / r0,
+            energy_scale: mu / r0,
+        }
+    }
+
+    /// Position `t` seconds after launch on a bound orbit, from Kepler's
+    /// equation solved by Newton's method
+    fn kepler_position(&self, t: f64) -> [f64; 2] {
+        use std::f64::consts::{PI, TAU};
+
+        let (Some(a), Some(period)) = (self.semi_major_axis, self.period) else {
+            return [f64::NAN; 2];
+        };
+        let e = self.eccentricity;
+        let start = if self.starts_at_periapsis { 0.0 } else { PI };
+        let mean_anomaly = (start + TAU * t / period).rem_euclid(TAU);
+        let mut anomaly = if e > 0.8 { PI } else { mean_anomaly };
+        for _ in 0..KEPLER_ITERATIONS {
+            let step = (anomaly - e * anomaly.sin() - mean_anomaly) / (1.0 - e * anomaly.cos());
+            anomaly -= step;
+            if step.abs() < 1e-14 {
+                break;
+            }
+        }
+        // Launched from apoapsis, the periapsis lies on the negative x axis
+        let sign = if self.starts_at_periapsis { 1.0 } else { -1.0 };
+        [
+            sign * a * (anomaly.cos() - e),
+            sign * a * (1.0 - e * e).sqrt() * anomaly.sin(),
+        ]
+    }
+}
+
+struct Orbit {
+    t: Vec<f64>,
+    x: Vec<f64>,
+    y: Vec<f64>,
+    max_energy_error: f64,
+}
+
+/// Integrate from (r₀, 0) with velocity (0, v₀) for `duration` seconds
+fn integrate_orbit(mu: f64, r0: f64, v0: f64, duration: f64) -> Orbit {
+    let accel = |p: &[f64; 2]| -> [f64; 2] {
+        let r3 = p[0].hypot(p[1]).powi(3);
+        [-mu * p[0] / r3, -mu * p[1] / r3]
+    };
+    let energy = |p: &[f64; 2], v: &[f64; 2]| 0.5 * (v[0] * v[0] + v[1] * v[1]) - mu / p[0].hypot(p[1]);
+
+    let dt = duration / STEPS as f64;
+    let stride = STEPS / OUTPUT_SAMPLES;
+    let mut position = [r0, 0.0];
+    let mut velocity = [0.0, v0];
+    let initial_energy = energy(&position, &velocity);
+
+    let mut orbit = Orbit {
+        t: Vec::with_capacity(OUTPUT_SAMPLES + 1),
+        x: Vec::with_capacity(OUTPUT_SAMPLES + 1),
+        y: Vec::with_capacity(OUTPUT_SAMPLES + 1),
+        max_energy_error: 0.0,
+    };
+    for step in 0..=STEPS {
+        if step % stride == 0 {
+            orbit.t.push(step as f64 * dt);
+            orbit.x.push(position[0]);
+            orbit.y.push(position[1]);
+            let error = (energy(&position, &velocity) - initial_energy).abs();
+            orbit.max_energy_error = orbit.max_energy_error.max(error);
+        }
+        (position, velocity) = verlet_step(accel, &position, &velocity, dt);
+    }
+    orbit
+}
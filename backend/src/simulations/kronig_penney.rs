@@ -0,0 +1,205 @@
+//! Kronig–Penney model of electron bands in a periodic lattice
+
+use serde_json::Value;
+
+use crate::models::quiz::{Question, Quiz};
+use crate::numerics::roots::find_roots;
+use crate::physics::constants::ELECTRON_KINETIC;
+use crate::simulations::params::Params;
+use crate::simulations::{Derived, Simulation, SimulationError, SimulationRegistry, output};
+
+/// Points in the returned Kronig–Penney function
+const ENERGY_POINTS: usize = 1000;
+/// Intervals scanned for band edges; fine enough for the narrowest gaps
+const EDGE_SCAN_INTERVALS: usize = 20_000;
+/// Band edge tolerance (eV)
+const EDGE_TOLERANCE: f64 = 1e-10;
+/// Points per band in the dispersion relation
+const BAND_POINTS: usize = 100;
+/// Scan starts just above zero energy, where α = 0
+const MIN_ENERGY: f64 = 1e-9;
+
+pub fn register(registry: &mut SimulationRegistry) {
+    registry.register(KronigPenney);
+}
+
+pub struct KronigPenney;
+
+impl Simulation for KronigPenney {
+    fn id(&self) -> &'static str {
+        "kronig-penney"
+    }
+
+    fn quiz(&self) -> Option<Quiz> {
+        Some(Quiz {
+            simulation_id: "kronig-penney".to_string(),
+            title: "Band Structure Quiz".to_string(),
+            questions: vec![
+                Question::multiple_choice(
+                    "gap_location",
+                    "At which crystal momenta do the band gaps open?",
+                    &["k = 0 only", "k = nπ/a, at the Brillouin zone boundaries", "Randomly", "Where E is largest"],
+                    1,
+                    "At k = nπ/a, reflections from neighbouring barriers add in phase and form standing waves with split energies.",
+                )
+                .with_hints(&[
+                    "Gaps open where waves reflected from successive barriers interfere constructively.",
+                    "That is the Bragg condition: 2a = nλ. Write it in terms of k = 2π/λ.",
+                ]),
+                Question::multiple_choice(
+                    "strong_barriers",
+                    "What happens to the bands as the barrier strength P grows very large?",
+                    &["They widen into a continuum", "They narrow toward discrete levels", "They disappear entirely", "Nothing changes"],
+                    1,
+                    "Strong barriers isolate the wells, so the bands shrink toward the particle-in-a-box levels.",
+                )
+                .with_hints(&[
+                    "Very strong barriers hardly let the electron tunnel to the next well.",
+                    "What are the energy levels of a single isolated well?",
+                ]),
+                Question::numeric(
+                    "zone_edge",
+                    "For a lattice spacing of 0.4 nm, at what crystal momentum is the first Brillouin zone boundary?",
+                    "nm⁻¹",
+                    7.854,
+                    0.01,
+                    "k = π/a = π / 0.4 nm ≈ 7.85 nm⁻¹.",
+                )
+                .with_hints(&[
+                    "The first Brillouin zone ends at k = π/a.",
+                    "π divided by 0.4 nm.",
+                ]),
+            ],
+        })
+    }
+
+    fn units(&self) -> &'static [(&'static str, &'static str)] {
+        &[
+            ("energy", "eV"),
+            ("free_electron_k", "1/nm"),
+            ("free_electron_energy", "eV"),
+            ("bands.*.energy", "eV"),
+            ("bands.*.k_reduced", "1/nm"),
+            ("bands.*.k_extended", "1/nm"),
+        ]
+    }
+
+    fn prerequisites(&self) -> &'static [&'static str] {
+        &["square-well", "quantum-tunneling"]
+    }
+
+    fn derived(&self, _params: &Params, data: &Value) -> Vec<Derived> {
+        vec![
+            Derived::new(
+                "zone_boundary",
+                "Brillouin Zone Boundary",
+                output(data, "/zone_boundary"),
+                "1/nm",
+                r"k = \pi / a",
+            ),
+            Derived::new(
+                "first_band_width",
+                "Width of the Lowest Band",
+                output(data, "/bands/0/width"),
+                "eV",
+                r"E_{\mathrm{top}} - E_{\mathrm{bottom}},\quad |f(E)| \le 1",
+            ),
+            Derived::new(
+                "first_gap",
+                "First Band Gap",
+                output(data, "/gaps/0/width"),
+                "eV",
+                r"E_{\mathrm{bottom},2} - E_{\mathrm{top},1}",
+            ),
+        ]
+    }
+
+    fn run(&self, params: &Params) -> Result<Value, SimulationError> {
+        let spacing = params.f64("lattice_spacing");
+        let strength = params.f64("barrier_strength");
+        let max_energy = params.f64("max_energy");
+        let f = |e: f64| kronig_penney(e, spacing, strength);
+
+        // Band edges are where |f| crosses 1; the allowed state alternates
+        // at each edge
+        let edges = find_roots(|e| f(e).abs() - 1.0, MIN_ENERGY, max_energy, EDGE_SCAN_INTERVALS, EDGE_TOLERANCE);
+        let mut allowed = f(MIN_ENERGY).abs() <= 1.0;
+        let mut bounds = vec![0.0];
+        bounds.extend(&edges);
+        bounds.push(max_energy);
+        let mut bands = Vec::new();
+        let mut gaps = Vec::new();
+        for (i, window) in bounds.windows(2).enumerate() {
+            let (lower, upper) = (window[0], window[1]);
+            if upper > lower {
+                let complete = i + 2 < bounds.len();
+                if allowed {
+                    bands.push((lower, upper, complete));
+                } else if i > 0 {
+                    gaps.push((lower, upper, complete));
+                }
+            }
+            allowed = !allowed;
+        }
+
+        let energies: Vec<f64> = (0..ENERGY_POINTS)
+            .map(|i| MIN_ENERGY + (max_energy - MIN_ENERGY) * i as f64 / (ENERGY_POINTS - 1) as f64)
+            .collect();
+
+        let band_values: Vec<Value> = bands
+            .iter()
+            .enumerate()
+            .map(|(index, &(lower, upper, complete))| {
+                let n = index + 1;
+                let energy: Vec<f64> = (0..BAND_POINTS)
+                    .map(|i| lower + (upper - lower) * i as f64 / (BAND_POINTS - 1) as f64)
+                    .collect();
+                let reduced: Vec<f64> = energy.iter().map(|&e| f(e).clamp(-1.0, 1.0).acos() / spacing).collect();
+                // Unfold into the n-th Brillouin zone: odd bands rise from
+                // (n−1)π/a, even bands fall back from nπ/a
+                let zone_start = (n - n % 2) as f64 * std::f64::consts::PI / spacing;
+                let extended: Vec<f64> = reduced
+                    .iter()
+                    .map(|&k| if n % 2 == 1 { zone_start + k } else { zone_start - k })
+                    .collect();
+                serde_json::json!({
+                    "index": n,
+                    "lower": lower,
+                    "upper": upper,
+                    "width": upper - lower,
+                    "complete": complete,
+                    "energy": energy,
+                    "k_reduced": reduced,
+                    "k_extended": extended,
+                })
+            })
+            .collect();
+        let gap_values: Vec<Value> = gaps
+            .iter()
+            .map(|&(lower, upper, complete)| {
+                serde_json::json!({ "lower": lower, "upper": upper, "width": upper - lower, "complete": complete })
+            })
+            .collect();
+
+        let k_max = (max_energy / ELECTRON_KINETIC).sqrt();
+        let free_k: Vec<f64> = (0..ENERGY_POINTS).map(|i| k_max * i as f64 / (ENERGY_POINTS - 1) as f64).collect();
+
+        Ok(serde_json::json!({
+            "energy": energies,
+            "kp_function": energies.iter().map(|&e| f(e)).collect::<Vec<_>>(),
+            "bands": band_values,
+            "gaps": gap_values,
+            "free_electron_k": free_k,
+            "free_electron_energy": free_k.iter().map(|k| ELECTRON_KINETIC * k * k).collect::<Vec<_>>(),
+            "zone_boundary": std::f64::consts::PI / spacing,
+            "lattice_spacing": spacing,
+            "barrier_strength": strength,
+        }))
+    }
+}
+
+/// Right-hand side of the Kronig–Penney condition, f(E) = cos(ka)
+fn kronig_penney(energy: f64, spacing: f64, strength: f64) -> f64 {
+    let alpha_a = (energy / ELECTRON_KINETIC).sqrt() * spacing;
+    alpha_a.cos() + strength * alpha_a.sin() / alpha_a
+}
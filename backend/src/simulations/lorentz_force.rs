@@ -0,0 +1,269 @@
+//! Charged particle motion in uniform electric and magnetic fields
+
+use serde_json::Value;
+
+use crate::models::quiz::{Question, Quiz};
+use crate::physics::constants::{ELECTRON_MASS, ELECTRON_VOLT, GIGA, KILO, PROTON_MASS};
+use crate::simulations::params::Params;
+use crate::simulations::{Derived, Simulation, SimulationError, SimulationRegistry, Validation, output, series};
+
+/// Boris steps per cyclotron period
+const STEPS_PER_PERIOD: usize = 200;
+/// Reference time (s) used in place of the cyclotron period when B = 0
+const FIELD_FREE_TIME: f64 = 1e-6;
+/// Upper bound on the number of trajectory samples returned
+const OUTPUT_SAMPLES: usize = 2000;
+
+pub fn register(registry: &mut SimulationRegistry) {
+    registry.register(LorentzForce);
+}
+
+pub struct LorentzForce;
+
+impl Simulation for LorentzForce {
+    fn id(&self) -> &'static str {
+        "lorentz-force"
+    }
+
+    fn quiz(&self) -> Option<Quiz> {
+        Some(Quiz {
+            simulation_id: "lorentz-force".to_string(),
+            title: "Lorentz Force Quiz".to_string(),
+            questions: vec![
+                Question::multiple_choice(
+                    "work",
+                    "How much work does a magnetic field do on a moving charge?",
+                    &["Positive work", "Negative work", "None, the force is perpendicular to the velocity", "It depends on the charge"],
+                    2,
+                    "F = qv × B is always perpendicular to v, so it changes direction but never speed.",
+                )
+                .with_hints(&[
+                    "Power is F · v. What is the angle between qv × B and v?",
+                    "A cross product is perpendicular to both of its factors.",
+                ]),
+                Question::numeric(
+                    "drift",
+                    "E = 100 V/m and B = 1 mT are perpendicular. What is the E×B drift speed?",
+                    "km/s",
+                    100.0,
+                    0.01,
+                    "v_D = E/B = 100 / 10⁻³ = 10⁵ m/s = 100 km/s.",
+                )
+                .with_hints(&[
+                    "In crossed fields the drift speed is v_D = E/B.",
+                    "Use SI units: B = 1 mT = 10⁻³ T. Then convert m/s to km/s.",
+                ]),
+                Question::multiple_choice(
+                    "drift_charge",
+                    "How does the E×B drift of a proton compare with that of an electron in the same fields?",
+                    &["Same direction and speed", "Opposite direction", "Same direction, much slower", "Protons do not drift"],
+                    0,
+                    "v_D = E × B / B² contains neither charge nor mass, so all particles drift together.",
+                )
+                .with_hints(&[
+                    "Write out the drift velocity v_D = E × B / B². Does it contain q or m?",
+                    "Mass and charge set how tightly the particle gyrates, but do they set where its orbit drifts?",
+                ]),
+            ],
+        })
+    }
+
+    fn units(&self) -> &'static [(&'static str, &'static str)] {
+        &[
+            ("t", "ns"),
+            ("x", "mm"),
+            ("y", "mm"),
+            ("z", "mm"),
+            ("vx", "km/s"),
+            ("vy", "km/s"),
+            ("vz", "km/s"),
+            ("kinetic_energy_ev", "eV"),
+        ]
+    }
+
+    fn prerequisites(&self) -> &'static [&'static str] {
+        &["projectile-motion"]
+    }
+
+    fn derived(&self, _params: &Params, data: &Value) -> Vec<Derived> {
+        let drift = data["drift_velocity"]
+            .as_array()
+            .map_or(f64::NAN, |v| v.iter().filter_map(Value::as_f64).map(|c| c * c).sum::<f64>().sqrt());
+        vec![
+            Derived::new(
+                "cyclotron_frequency",
+                "Cyclotron Frequency",
+                output(data, "/cyclotron_frequency"),
+                "Hz",
+                r"f_c = \frac{|q| B}{2\pi m}",
+            ),
+            Derived::new(
+                "larmor_radius",
+                "Larmor Radius",
+                output(data, "/larmor_radius"),
+                "mm",
+                r"r_L = \frac{m v_\perp}{|q| B}",
+            ),
+            Derived::new(
+                "drift_speed",
+                "E×B Drift Speed",
+                drift,
+                "km/s",
+                r"v_d = \frac{|\mathbf{E} \times \mathbf{B}|}{B^2}",
+            ),
+        ]
+    }
+
+    /// Uniform fields have a closed-form solution: E×B drift plus gyration
+    /// about B and uniform acceleration along it
+    fn validate(&self, params: &Params, data: &Value) -> Vec<Validation> {
+        let setup = Setup::new(params);
+        let positions: Vec<[f64; 3]> = series(data, "/t")
+            .iter()
+            .map(|t| setup.analytic_position(t / GIGA).map(|x| x * KILO))
+            .collect();
+        let formula = r"\mathbf{r} = \mathbf{v}_d t + \hat{\mathbf{b}}\left(v_\parallel t + \tfrac{q E_\parallel}{2m} t^2\right) + \frac{\mathbf{u}_0 \sin\omega t + (\mathbf{u}_0 \times \hat{\mathbf{b}})(1 - \cos\omega t)}{\omega}";
+        ["x", "y", "z"]
+            .iter()
+            .enumerate()
+            .filter_map(|(axis, name)| {
+                let analytic: Vec<f64> = positions.iter().map(|r| r[axis]).collect();
+                Validation::compare(*name, formula, "mm", &series(data, &format!("/{name}")), &analytic)
+            })
+            .collect()
+    }
+
+    fn run(&self, params: &Params) -> Result<Value, SimulationError> {
+        let particle = params.str("particle");
+        let Setup { charge, mass, electric, magnetic, velocity } = Setup::new(params);
+        let periods = params.f64("periods");
+
+        let b = norm(&magnetic);
+        let cyclotron_frequency = charge.abs() * b / mass;
+        let reference_time = if b > 0.0 { std::f64::consts::TAU / cyclotron_frequency } else { FIELD_FREE_TIME };
+        let steps = (periods * STEPS_PER_PERIOD as f64).round() as usize;
+        let dt = periods * reference_time / steps as f64;
+
+        let mut data = serde_json::json!({
+            "particle": particle,
+            "charge": charge,
+            "mass": mass,
+        });
+        let trajectory = push_particle(charge / mass, &electric, &magnetic, velocity, dt, steps);
+        data["t"] = trajectory.iter().map(|s| s.0 * GIGA).collect::<Vec<_>>().into();
+        for (axis, name) in ["x", "y", "z"].iter().enumerate() {
+            data[*name] = trajectory.iter().map(|s| s.1[axis] * KILO).collect::<Vec<_>>().into();
+            data[format!("v{name}")] = trajectory.iter().map(|s| s.2[axis] / KILO).collect::<Vec<_>>().into();
+        }
+        let speeds: Vec<f64> = trajectory.iter().map(|s| norm(&s.2)).collect();
+        let kinetic = |v: f64| 0.5 * mass * v * v / ELECTRON_VOLT;
+        data["kinetic_energy_ev"] = speeds.iter().map(|&v| kinetic(v)).collect::<Vec<_>>().into();
+
+        if b > 0.0 {
+            let drift = cross(&electric, &magnetic).map(|c| c / (b * b));
+            // Perpendicular speed seen from the drifting frame
+            let relative: [f64; 3] = std::array::from_fn(|i| velocity[i] - drift[i]);
+            let along = dot(&relative, &magnetic) / b;
+            let perpendicular = (dot(&relative, &relative) - along * along).max(0.0).sqrt();
+            data["cyclotron_frequency"] = (cyclotron_frequency / std::f64::consts::TAU).into();
+            data["cyclotron_period"] = (reference_time * GIGA).into();
+            data["larmor_radius"] = (perpendicular / cyclotron_frequency * KILO).into();
+            data["drift_velocity"] = drift.map(|d| d / KILO).to_vec().into();
+        }
+        Ok(data)
+    }
+}
+
+/// Particle and fields of a run, in SI units
+struct Setup {
+    charge: f64,
+    mass: f64,
+    electric: [f64; 3],
+    magnetic: [f64; 3],
+    velocity: [f64; 3],
+}
+
+impl Setup {
+    fn new(params: &Params) -> Self {
+        let vector = |prefix: &str, scale: f64| -> [f64; 3] {
+            ["x", "y", "z"].map(|axis| params.f64(&format!("{prefix}_{axis}")) * scale)
+        };
+        let (charge, mass) = match params.str("particle") {
+            "proton" => (ELECTRON_VOLT, PROTON_MASS),
+            "positron" => (ELECTRON_VOLT, ELECTRON_MASS),
+            _ => (-ELECTRON_VOLT, ELECTRON_MASS),
+        };
+        Self {
+            charge,
+            mass,
+            electric: vector("electric", 1.0),
+            magnetic: vector("magnetic", 1e-3),
+            velocity: vector("velocity", 1e3),
+        }
+    }
+
+    /// Exact position `t` seconds after leaving the origin
+    fn analytic_position(&self, t: f64) -> [f64; 3] {
+        let k = self.charge / self.mass;
+        let b = norm(&self.magnetic);
+        if b == 0.0 {
+            return std::array::from_fn(|i| self.velocity[i] * t + 0.5 * k * self.electric[i] * t * t);
+        }
+        let unit = self.magnetic.map(|c| c / b);
+        let drift = cross(&self.electric, &self.magnetic).map(|c| c / (b * b));
+        let along = dot(&self.velocity, &unit);
+        let gyration: [f64; 3] = std::array::from_fn(|i| self.velocity[i] - along * unit[i] - drift[i]);
+        let turned = cross(&gyration, &unit);
+        // Signed, so that the sense of rotation follows the charge
+        let omega = k * b;
+        let (sin, cos) = (omega * t).sin_cos();
+        let parallel = along * t + 0.5 * k * dot(&self.electric, &unit) * t * t;
+        std::array::from_fn(|i| {
+            drift[i] * t + unit[i] * parallel + (gyration[i] * sin + turned[i] * (1.0 - cos)) / omega
+        })
+    }
+}
+
+/// Integrate with the Boris scheme, returning (t, x, v) samples in SI units
+fn push_particle(
+    charge_to_mass: f64,
+    electric: &[f64; 3],
+    magnetic: &[f64; 3],
+    mut velocity: [f64; 3],
+    dt: f64,
+    steps: usize,
+) -> Vec<(f64, [f64; 3], [f64; 3])> {
+    let half_kick = electric.map(|e| charge_to_mass * e * dt / 2.0);
+    let t = magnetic.map(|b| charge_to_mass * b * dt / 2.0);
+    let s_factor = 2.0 / (1.0 + dot(&t, &t));
+    let s = t.map(|ti| ti * s_factor);
+
+    let stride = steps.div_ceil(OUTPUT_SAMPLES).max(1);
+    let mut position = [0.0; 3];
+    let mut samples = vec![(0.0, position, velocity)];
+    for step in 1..=steps {
+        // Half electric kick, magnetic rotation, half electric kick, drift
+        let minus: [f64; 3] = std::array::from_fn(|i| velocity[i] + half_kick[i]);
+        let prime_cross = cross(&minus, &t);
+        let prime: [f64; 3] = std::array::from_fn(|i| minus[i] + prime_cross[i]);
+        let plus_cross = cross(&prime, &s);
+        velocity = std::array::from_fn(|i| minus[i] + plus_cross[i] + half_kick[i]);
+        position = std::array::from_fn(|i| position[i] + velocity[i] * dt);
+        if step % stride == 0 || step == steps {
+            samples.push((step as f64 * dt, position, velocity));
+        }
+    }
+    samples
+}
+
+fn dot(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: &[f64; 3], b: &[f64; 3]) -> [f64; 3] {
+    [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]]
+}
+
+fn norm(a: &[f64; 3]) -> f64 {
+    dot(a, a).sqrt()
+}
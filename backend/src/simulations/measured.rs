@@ -0,0 +1,222 @@
+// Run metrics, logs and thread limits for every registered simulation
+//
+// The registry wraps each simulation so that every run, whichever route or
+// job started it, is counted, timed, logged and held to its simulation's
+// thread limit in one place. Runs log under the caller's span, so their
+// lines carry the request or job ID.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+
+use crate::models::quiz::Quiz;
+use crate::numerics::parallel::with_workers;
+use crate::models::simulation::{SimulationDetails, SimulationInfo, SimulationParameter};
+use crate::services::metrics::{Metrics, RunOutcome};
+use crate::simulations::params::Params;
+use crate::simulations::{Backend, Derived, FrameStream, Progress, Signal, Simulation, SimulationError, Validation};
+
+pub(super) struct Measured {
+    pub simulation: Box<dyn Simulation>,
+    pub metrics: Arc<Metrics>,
+    pub threads: Arc<ThreadLimits>,
+}
+
+/// Most threads the parallel loops of each simulation's runs may use;
+/// every core unless the configuration sets a limit
+#[derive(Default)]
+pub(super) struct ThreadLimits {
+    /// Limit for every simulation, and limits of particular ones by ID
+    limits: RwLock<(Option<usize>, HashMap<String, usize>)>,
+}
+
+impl ThreadLimits {
+    pub fn set(&self, threads: Option<usize>, per_simulation: HashMap<String, usize>) {
+        *self.limits.write().unwrap() = (threads, per_simulation);
+    }
+
+    fn get(&self, id: &str) -> usize {
+        let (threads, per_simulation) = &*self.limits.read().unwrap();
+        per_simulation.get(id).copied().or(*threads).unwrap_or(usize::MAX)
+    }
+}
+
+/// Runs taking longer than this are logged as warnings
+const SLOW_RUN: Duration = Duration::from_secs(5);
+
+impl Measured {
+    fn measure(
+        &self,
+        params: &Params,
+        run: impl FnOnce() -> Result<Value, SimulationError>,
+    ) -> Result<Value, SimulationError> {
+        let span = tracing::debug_span!("run", simulation = self.id(), version = self.version());
+        let _entered = span.enter();
+        let started = Instant::now();
+        let result = with_workers(self.threads.get(self.id()), run);
+        self.finish(&result, params, started.elapsed());
+        result
+    }
+
+    fn finish(&self, result: &Result<Value, SimulationError>, params: &Params, elapsed: Duration) {
+        self.metrics.observe_run(self.id(), outcome(result), elapsed);
+        log(result, params, elapsed);
+    }
+}
+
+fn log(result: &Result<Value, SimulationError>, params: &Params, elapsed: Duration) {
+    let parameters = || Value::Object(params.values().clone());
+    match result {
+        Ok(_) if elapsed > SLOW_RUN => {
+            tracing::warn!(?elapsed, parameters = %parameters(), "Slow simulation run");
+        }
+        Ok(_) => tracing::debug!(?elapsed, "Simulation run finished"),
+        Err(SimulationError::Cancelled) => tracing::info!(?elapsed, "Simulation run cancelled"),
+        Err(SimulationError::InvalidParameters(_)) => {
+            tracing::debug!(parameters = %parameters(), "Simulation rejected its parameters");
+        }
+        Err(err) => tracing::warn!(?elapsed, parameters = %parameters(), "Simulation run failed: {}", err),
+    }
+}
+
+fn outcome(result: &Result<Value, SimulationError>) -> RunOutcome {
+    match result {
+        Ok(_) => RunOutcome::Ok,
+        Err(SimulationError::Cancelled) => RunOutcome::Cancelled,
+        Err(_) => RunOutcome::Error,
+    }
+}
+
+impl Simulation for Measured {
+    fn id(&self) -> &'static str {
+        self.simulation.id()
+    }
+
+    fn info(&self) -> SimulationInfo {
+        self.simulation.info()
+    }
+
+    fn details(&self) -> SimulationDetails {
+        self.simulation.details()
+    }
+
+    fn parameters(&self) -> Vec<SimulationParameter> {
+        self.simulation.parameters()
+    }
+
+    fn quiz(&self) -> Option<Quiz> {
+        self.simulation.quiz()
+    }
+
+    fn prerequisites(&self) -> &'static [&'static str] {
+        self.simulation.prerequisites()
+    }
+
+    fn units(&self) -> &'static [(&'static str, &'static str)] {
+        self.simulation.units()
+    }
+
+    fn signal(&self) -> Option<Signal> {
+        self.simulation.signal()
+    }
+
+    fn derived(&self, params: &Params, data: &Value) -> Vec<Derived> {
+        self.simulation.derived(params, data)
+    }
+
+    fn validate(&self, params: &Params, data: &Value) -> Vec<Validation> {
+        self.simulation.validate(params, data)
+    }
+
+    fn version(&self) -> u32 {
+        self.simulation.version()
+    }
+
+    fn backend(&self) -> Backend {
+        self.simulation.backend()
+    }
+
+    fn run_version(
+        &self,
+        version: u32,
+        params: &Params,
+        progress: &Progress,
+    ) -> Option<Result<Value, SimulationError>> {
+        let span = tracing::debug_span!("run", simulation = self.id(), version);
+        let _entered = span.enter();
+        let started = Instant::now();
+        let workers = self.threads.get(self.id());
+        let result = with_workers(workers, || self.simulation.run_version(version, params, progress))?;
+        self.finish(&result, params, started.elapsed());
+        Some(result)
+    }
+
+    fn run(&self, params: &Params) -> Result<Value, SimulationError> {
+        self.measure(params, || self.simulation.run(params))
+    }
+
+    fn run_with_progress(&self, params: &Params, progress: &Progress) -> Result<Value, SimulationError> {
+        self.measure(params, || self.simulation.run_with_progress(params, progress))
+    }
+
+    fn stream(&self, params: &Params) -> Result<Option<Box<dyn FrameStream>>, SimulationError> {
+        let started = Instant::now();
+        let workers = self.threads.get(self.id());
+        let stream = with_workers(workers, || self.simulation.stream(params));
+        match stream {
+            Ok(Some(frames)) => Ok(Some(Box::new(MeasuredStream {
+                frames,
+                workers,
+                simulation_id: self.id(),
+                metrics: self.metrics.clone(),
+                computing: started.elapsed(),
+                finished: false,
+            }))),
+            Ok(None) => Ok(None),
+            Err(err) => {
+                self.metrics.observe_run(self.id(), RunOutcome::Error, started.elapsed());
+                Err(err)
+            }
+        }
+    }
+}
+
+/// A streamed run, counted when its last frame is computed or, as
+/// cancelled, when the client leaves before then. Only the time spent
+/// computing frames counts, not the time spent waiting to send them.
+struct MeasuredStream {
+    frames: Box<dyn FrameStream>,
+    /// Thread limit of the simulation when the stream started
+    workers: usize,
+    simulation_id: &'static str,
+    metrics: Arc<Metrics>,
+    computing: Duration,
+    finished: bool,
+}
+
+impl FrameStream for MeasuredStream {
+    fn header(&self) -> Value {
+        self.frames.header()
+    }
+
+    fn next_frame(&mut self) -> Option<Value> {
+        let started = Instant::now();
+        let frame = with_workers(self.workers, || self.frames.next_frame());
+        self.computing += started.elapsed();
+        if frame.is_none() && !self.finished {
+            self.finished = true;
+            self.metrics.observe_run(self.simulation_id, RunOutcome::Ok, self.computing);
+        }
+        frame
+    }
+}
+
+impl Drop for MeasuredStream {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.metrics.observe_run(self.simulation_id, RunOutcome::Cancelled, self.computing);
+        }
+    }
+}
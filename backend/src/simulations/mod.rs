@@ -0,0 +1,586 @@
+// Simulation implementations
+// Each simulation lives in its own module and registers itself at startup
+
+pub mod bell_inequality;
+pub mod blackbody;
+pub mod catalog;
+pub mod compton_scattering;
+pub mod custom_potential;
+pub mod double_pendulum;
+pub mod double_slit;
+pub mod doppler;
+pub mod harmonic_oscillator;
+pub mod heat_diffusion;
+pub mod hydrogen_atom;
+pub mod ideal_gas;
+pub mod ising_model;
+pub mod kepler_orbit;
+pub mod kronig_penney;
+pub mod lorentz_force;
+mod measured;
+pub mod params;
+pub mod photoelectric;
+pub mod plugin;
+pub mod polarization;
+pub mod potential_well;
+pub mod projectile_motion;
+pub mod quantum_tunneling;
+pub mod radioactive_decay;
+pub mod ray_optics;
+pub mod rlc_circuit;
+pub mod special_relativity;
+pub mod square_well;
+pub mod standing_waves;
+pub mod stern_gerlach;
+pub mod wave_packet;
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::config::{ComputeConfig, ContentConfig};
+use crate::models::quiz::Quiz;
+use crate::models::simulation::{SimulationDetails, SimulationInfo, SimulationParameter, DIFFICULTIES};
+use crate::services::metrics::Metrics;
+use catalog::{Catalog, CatalogError, Cataloged, SimulationContent};
+use measured::{Measured, ThreadLimits};
+use params::{ParameterError, Params};
+
+/// Reasons a simulation run can fail
+#[derive(Debug)]
+pub enum SimulationError {
+    /// Parameters passed the schema but are inconsistent with each other
+    InvalidParameters(Vec<ParameterError>),
+    /// The run was stopped on request before it finished
+    Cancelled,
+    /// The run used up its computation time
+    TimeLimitExceeded(Duration),
+    /// The output would exceed the size allowed for one request (bytes)
+    OutputTooLarge { size: usize, limit: usize },
+    /// A plugin executed as many instructions as one run may
+    FuelExhausted(u64),
+    /// A plugin trapped or broke the plugin interface
+    Failed(String),
+}
+
+impl fmt::Display for SimulationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidParameters(_) => write!(f, "one or more simulation parameters are invalid"),
+            Self::Cancelled => write!(f, "the run was cancelled"),
+            Self::TimeLimitExceeded(limit) => {
+                write!(f, "the computation did not finish within its {} s time limit", limit.as_secs())
+            }
+            Self::OutputTooLarge { size, limit } => write!(
+                f,
+                "the output is {:.1} MB; the limit is {:.1} MB",
+                *size as f64 / 1e6,
+                *limit as f64 / 1e6
+            ),
+            Self::FuelExhausted(fuel) => write!(f, "the computation did not finish within its {fuel} instructions"),
+            Self::Failed(message) => write!(f, "the simulation failed: {message}"),
+        }
+    }
+}
+
+/// A quantity worked out from a run, such as a fringe spacing or an orbital
+/// period, with the formula that gives it
+#[derive(Serialize)]
+pub struct Derived {
+    /// Key, unique within a run, e.g. `fringe_spacing`
+    pub name: String,
+    pub label: String,
+    pub value: f64,
+    /// Empty for dimensionless quantities
+    pub unit: &'static str,
+    /// LaTeX, as in the theory sections
+    pub formula: &'static str,
+}
+
+impl Derived {
+    pub fn new(
+        name: impl Into<String>,
+        label: impl Into<String>,
+        value: f64,
+        unit: &'static str,
+        formula: &'static str,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            label: label.into(),
+            value,
+            unit,
+            formula,
+        }
+    }
+}
+
+/// Offset of the computation versions a `simd` build reports for
+/// simulations with SIMD kernels
+const SIMD_VERSION_OFFSET: u32 = 1000;
+
+/// Computation version of a simulation with SIMD kernels. A `simd` build's
+/// output differs from the default build's in the last bits, so it reports
+/// `version` + 1000: the two builds' results then never share a cache
+/// entry or an attestation, and neither build claims to replay the other's.
+pub const fn kernel_version(version: u32) -> u32 {
+    if cfg!(feature = "simd") {
+        version + SIMD_VERSION_OFFSET
+    } else {
+        version
+    }
+}
+
+/// Hardware a run computed on
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    #[default]
+    Cpu,
+    /// wgpu compute shaders, for the large grids of the orbital and Ising
+    /// simulations
+    Gpu,
+}
+
+/// Values `compute.backend` may take
+pub const BACKENDS: [&str; 2] = ["cpu", "gpu"];
+
+impl Backend {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Cpu => "cpu",
+            Self::Gpu => "gpu",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        [Self::Cpu, Self::Gpu].into_iter().find(|backend| backend.as_str() == name)
+    }
+
+    /// The backend runs use for the configured `compute.backend`. Asking
+    /// for the GPU falls back to the CPU, with a warning, when the build
+    /// lacks the `gpu` feature or no adapter is found; no build finds one
+    /// until the wgpu kernels exist.
+    pub fn select(configured: &str) -> Self {
+        if Self::parse(configured) == Some(Self::Gpu) {
+            if cfg!(feature = "gpu") {
+                tracing::warn!("No GPU adapter is available; simulations run on the CPU");
+            } else {
+                tracing::warn!("This build has no GPU backend (the `gpu` feature); simulations run on the CPU");
+            }
+        }
+        Self::Cpu
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Cpu => "CPU",
+            Self::Gpu => "GPU",
+        })
+    }
+}
+
+/// Number in run output at a JSON pointer such as `/image/distance`, or NaN
+/// where the output has none
+pub fn output(data: &Value, pointer: &str) -> f64 {
+    data.pointer(pointer).and_then(Value::as_f64).unwrap_or(f64::NAN)
+}
+
+/// Numbers in run output at a JSON pointer such as `/x`, empty where the
+/// output has no array there and NaN for entries that are not numbers
+pub fn series(data: &Value, pointer: &str) -> Vec<f64> {
+    data.pointer(pointer)
+        .and_then(Value::as_array)
+        .map(|values| values.iter().map(|value| value.as_f64().unwrap_or(f64::NAN)).collect())
+        .unwrap_or_default()
+}
+
+/// The output a detector in a real experiment would record, which
+/// simulated measurement noise is applied to
+#[derive(Clone, Copy)]
+pub struct Signal {
+    /// Dotted path of an intensity-like array, e.g. `pattern`
+    pub output: &'static str,
+    /// Dotted path of the positions it is sampled at, e.g. `x`
+    pub position: &'static str,
+}
+
+/// How far one numerical output strays from the closed-form solution to
+/// the same problem
+#[derive(Serialize)]
+pub struct Validation {
+    /// Dotted path of the compared output, as in `units`, e.g. `y`
+    pub output: String,
+    /// LaTeX, as in the theory sections
+    pub analytic: &'static str,
+    /// Largest |numerical − analytic| over the compared points
+    pub max_deviation: f64,
+    /// `max_deviation` over the largest |analytic| value
+    pub max_relative_deviation: f64,
+    /// Empty for dimensionless outputs
+    pub unit: &'static str,
+    pub points: usize,
+}
+
+impl Validation {
+    /// Compare `numerical` with `analytic` point by point; `None` when
+    /// their lengths differ or there is nothing to compare
+    pub fn compare(
+        output: impl Into<String>,
+        formula: &'static str,
+        unit: &'static str,
+        numerical: &[f64],
+        analytic: &[f64],
+    ) -> Option<Self> {
+        if numerical.is_empty() || numerical.len() != analytic.len() {
+            return None;
+        }
+        let max_deviation = numerical
+            .iter()
+            .zip(analytic)
+            .map(|(numerical, analytic)| (numerical - analytic).abs())
+            .fold(0.0, f64::max);
+        let scale = analytic.iter().map(|value| value.abs()).fold(0.0, f64::max);
+        Some(Self {
+            output: output.into(),
+            analytic: formula,
+            max_deviation,
+            max_relative_deviation: if scale > 0.0 { max_deviation / scale } else { max_deviation },
+            unit,
+            points: numerical.len(),
+        })
+    }
+}
+
+/// A runnable physics simulation exposed through the API
+pub trait Simulation: Send + Sync {
+    /// Stable identifier used in URLs, e.g. "double-slit"
+    fn id(&self) -> &'static str;
+
+    /// Short catalog entry shown in simulation listings. The registry
+    /// serves it from the simulation's content file; this placeholder only
+    /// shows for simulations without one.
+    fn info(&self) -> SimulationInfo {
+        SimulationInfo {
+            id: self.id().to_string(),
+            name: self.id().to_string(),
+            description: String::new(),
+            difficulty: DIFFICULTIES[0].to_string(),
+            estimated_time_minutes: 0,
+            topics: Vec::new(),
+        }
+    }
+
+    /// Full description including parameters and theory, served from the
+    /// content file like `info`
+    fn details(&self) -> SimulationDetails {
+        SimulationDetails {
+            id: self.id().to_string(),
+            name: self.id().to_string(),
+            description: String::new(),
+            parameters: Vec::new(),
+            theory: String::new(),
+        }
+    }
+
+    /// Declared parameter schema used to validate run requests
+    fn parameters(&self) -> Vec<SimulationParameter> {
+        self.details().parameters
+    }
+
+    /// Short quiz on the theory section, if the simulation has one
+    fn quiz(&self) -> Option<Quiz> {
+        None
+    }
+
+    /// IDs of simulations a student should work through first
+    fn prerequisites(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Units of numeric outputs, keyed by dotted path into the run data
+    /// with `*` standing for any array index. Used to label exported columns.
+    fn units(&self) -> &'static [(&'static str, &'static str)] {
+        &[]
+    }
+
+    /// The output a detector would record, if the simulation has one that
+    /// noise can be applied to by default
+    fn signal(&self) -> Option<Signal> {
+        None
+    }
+
+    /// Quantities worth pointing out in a run's output, worked out from the
+    /// parameters and the output itself. Values that are not finite for a
+    /// run, such as the period of an unbound orbit, are left out of
+    /// responses.
+    fn derived(&self, _params: &Params, _data: &Value) -> Vec<Derived> {
+        Vec::new()
+    }
+
+    /// Deviations of a run's output from the analytic solution, for the
+    /// parameters where one exists (no drag, a bound orbit, a step source).
+    /// Empty when the simulation has none for these parameters.
+    fn validate(&self, _params: &Params, _data: &Value) -> Vec<Validation> {
+        Vec::new()
+    }
+
+    /// Version of the computation, stored with every result. Bump it when a
+    /// change (a formula fix, a different integrator) alters the output for
+    /// the same parameters, and keep the previous code reachable through
+    /// `run_version` so stored results can still be replayed.
+    fn version(&self) -> u32 {
+        1
+    }
+
+    /// Hardware the runs compute on, stored with every result. No
+    /// simulation has GPU kernels yet, so every run is on the CPU.
+    fn backend(&self) -> Backend {
+        Backend::Cpu
+    }
+
+    /// Run as computation `version` did, or `None` if that version's code
+    /// is no longer available. Only the current version by default.
+    fn run_version(
+        &self,
+        version: u32,
+        params: &Params,
+        progress: &Progress,
+    ) -> Option<Result<Value, SimulationError>> {
+        (version == self.version()).then(|| self.run_with_progress(params, progress))
+    }
+
+    /// Compute the simulation output for already validated parameters
+    fn run(&self, params: &Params) -> Result<Value, SimulationError>;
+
+    /// `run`, reporting the completed fraction as it goes and stopping early
+    /// when `progress.check()` fails. Simulations that are always quick just
+    /// run.
+    fn run_with_progress(&self, params: &Params, progress: &Progress) -> Result<Value, SimulationError> {
+        let _ = progress;
+        self.run(params)
+    }
+
+    /// Frame-by-frame source for time-evolving simulations, used for
+    /// streaming. Returns `Ok(None)` for simulations without a time axis.
+    fn stream(&self, _params: &Params) -> Result<Option<Box<dyn FrameStream>>, SimulationError> {
+        Ok(None)
+    }
+}
+
+/// Completed fraction of a run and the means to stop it, shared between the
+/// worker and observers
+#[derive(Clone, Default)]
+pub struct Progress {
+    shared: Arc<SharedProgress>,
+    /// Time by which the run must finish, and the limit it came from
+    deadline: Option<(Instant, Duration)>,
+}
+
+#[derive(Default)]
+struct SharedProgress {
+    fraction: AtomicU64,
+    cancelled: AtomicBool,
+    /// Latest partial result and how many have been published
+    partial: Mutex<(u64, Option<Value>)>,
+}
+
+impl Progress {
+    /// Copy sharing the same progress and cancellation, which must finish
+    /// within `limit` from now
+    pub fn with_time_limit(&self, limit: Duration) -> Self {
+        Self {
+            shared: self.shared.clone(),
+            deadline: Some((Instant::now() + limit, limit)),
+        }
+    }
+
+    /// Record the completed fraction, clamped to [0, 1]
+    pub fn set(&self, fraction: f64) {
+        self.shared.fraction.store(fraction.clamp(0.0, 1.0).to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> f64 {
+        f64::from_bits(self.shared.fraction.load(Ordering::Relaxed))
+    }
+
+    /// Publish an intermediate result (running averages, curves so far),
+    /// replacing the previous one
+    pub fn publish(&self, partial: Value) {
+        let mut latest = self.shared.partial.lock().unwrap();
+        *latest = (latest.0 + 1, Some(partial));
+    }
+
+    /// Latest partial result with its sequence number, if any was published
+    pub fn partial(&self) -> Option<(u64, Value)> {
+        let latest = self.shared.partial.lock().unwrap();
+        latest.1.clone().map(|partial| (latest.0, partial))
+    }
+
+    /// Ask the run to stop at its next check
+    pub fn cancel(&self) {
+        self.shared.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.shared.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Fails once the run has been cancelled or is out of time. Long loops
+    /// call this regularly and return the error.
+    pub fn check(&self) -> Result<(), SimulationError> {
+        if self.is_cancelled() {
+            return Err(SimulationError::Cancelled);
+        }
+        match self.deadline {
+            Some((deadline, limit)) if Instant::now() > deadline => Err(SimulationError::TimeLimitExceeded(limit)),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Incrementally computed animation frames
+pub trait FrameStream: Send {
+    /// Data shared by every frame (grid coordinates, potential, ...)
+    fn header(&self) -> Value;
+
+    /// Compute the next frame, or `None` once the run is complete
+    fn next_frame(&mut self) -> Option<Value>;
+}
+
+/// Catalog of available simulations, kept in registration order
+#[derive(Default)]
+pub struct SimulationRegistry {
+    simulations: Vec<Arc<dyn Simulation>>,
+    /// Text and parameter definitions of every simulation
+    catalog: Arc<Catalog>,
+    /// Counts and times the runs of every simulation
+    metrics: Arc<Metrics>,
+    /// Threads each simulation's runs may use
+    threads: Arc<ThreadLimits>,
+}
+
+impl SimulationRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registry populated with every built-in simulation
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::new();
+        double_slit::register(&mut registry);
+        quantum_tunneling::register(&mut registry);
+        hydrogen_atom::register(&mut registry);
+        wave_packet::register(&mut registry);
+        projectile_motion::register(&mut registry);
+        double_pendulum::register(&mut registry);
+        kepler_orbit::register(&mut registry);
+        ideal_gas::register(&mut registry);
+        blackbody::register(&mut registry);
+        photoelectric::register(&mut registry);
+        special_relativity::register(&mut registry);
+        radioactive_decay::register(&mut registry);
+        standing_waves::register(&mut registry);
+        rlc_circuit::register(&mut registry);
+        lorentz_force::register(&mut registry);
+        ising_model::register(&mut registry);
+        harmonic_oscillator::register(&mut registry);
+        square_well::register(&mut registry);
+        stern_gerlach::register(&mut registry);
+        bell_inequality::register(&mut registry);
+        compton_scattering::register(&mut registry);
+        kronig_penney::register(&mut registry);
+        heat_diffusion::register(&mut registry);
+        doppler::register(&mut registry);
+        ray_optics::register(&mut registry);
+        polarization::register(&mut registry);
+        potential_well::register(&mut registry);
+        custom_potential::register(&mut registry);
+        registry
+    }
+
+    /// Add a simulation, replacing any existing one with the same ID
+    pub fn register(&mut self, simulation: impl Simulation + 'static) {
+        let simulation: Arc<dyn Simulation> = Arc::new(Measured {
+            simulation: Box::new(Cataloged {
+                simulation: Box::new(simulation),
+                catalog: self.catalog.clone(),
+            }),
+            metrics: self.metrics.clone(),
+            threads: self.threads.clone(),
+        });
+        match self.simulations.iter().position(|s| s.id() == simulation.id()) {
+            Some(index) => self.simulations[index] = simulation,
+            None => self.simulations.push(simulation),
+        }
+    }
+
+    /// Add a simulation that brings its own content instead of reading it
+    /// from the content files, as plugins do
+    pub fn register_embedded(&mut self, simulation: impl Simulation + 'static, content: SimulationContent) {
+        self.catalog.embed(content);
+        self.register(simulation);
+    }
+
+    /// The simulation with this ID, unless it has been retired
+    pub fn get(&self, id: &str) -> Option<Arc<dyn Simulation>> {
+        self.get_any(id).filter(|_| !self.catalog.is_retired(id))
+    }
+
+    /// The simulation with this ID, retired or not
+    pub fn get_any(&self, id: &str) -> Option<Arc<dyn Simulation>> {
+        self.simulations.iter().find(|s| s.id() == id).cloned()
+    }
+
+    /// Simulations that have not been retired, in registration order
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<dyn Simulation>> {
+        self.simulations.iter().filter(|s| !self.catalog.is_retired(s.id()))
+    }
+
+    /// Every simulation, retired ones included
+    pub fn iter_all(&self) -> impl Iterator<Item = &Arc<dyn Simulation>> {
+        self.simulations.iter()
+    }
+
+    pub fn catalog(&self) -> &Catalog {
+        &self.catalog
+    }
+
+    pub fn metrics(&self) -> Arc<Metrics> {
+        self.metrics.clone()
+    }
+
+    /// Apply the configured thread limits to every run from now on. Fails
+    /// naming a limited simulation that is not registered.
+    pub fn limit_threads(&self, config: &ComputeConfig) -> Result<(), String> {
+        if let Some(id) = config.simulation_threads.keys().find(|id| self.get_any(id).is_none()) {
+            return Err(format!("compute.simulation_threads: unknown simulation `{id}`"));
+        }
+        let per_simulation = config.simulation_threads.iter().map(|(id, &threads)| (id.clone(), threads)).collect();
+        self.threads.set(config.threads, per_simulation);
+        Ok(())
+    }
+
+    /// Load every simulation's content from `dir`; see [`catalog`]
+    pub fn load_catalog(&self, dir: &Path) -> Result<usize, CatalogError> {
+        let ids: Vec<&str> = self.simulations.iter().map(|s| s.id()).collect();
+        self.catalog.load(dir, &ids)
+    }
+
+    /// Load the content again from the directory it was last loaded from
+    pub fn reload_catalog(&self) -> Result<usize, CatalogError> {
+        let dir = self.catalog_dir().unwrap_or_else(|| ContentConfig::default().simulations_dir());
+        self.load_catalog(&dir)
+    }
+
+    pub fn catalog_dir(&self) -> Option<PathBuf> {
+        self.catalog.dir()
+    }
+}
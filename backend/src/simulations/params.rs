@@ -0,0 +1,311 @@
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::models::simulation::SimulationParameter;
+use crate::numerics::monte_carlo::MonteCarlo;
+use crate::physics::units;
+use crate::services::expression::{is_constant, Expression};
+
+/// Relative tolerance when checking that a value lies on the step grid
+const STEP_TOLERANCE: f64 = 1e-6;
+
+/// Parameter a stochastic simulation draws its random numbers from
+pub const SEED: &str = "seed";
+/// Fewest and most points of a curve parameter
+pub const CURVE_POINTS: std::ops::RangeInclusive<usize> = 2..=200;
+
+/// A single parameter that failed validation
+#[derive(Debug, Clone, Serialize)]
+pub struct ParameterError {
+    pub name: String,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+}
+
+impl ParameterError {
+    pub fn new(name: &str, message: impl Into<String>, value: Option<&Value>) -> Self {
+        Self {
+            name: name.to_string(),
+            message: message.into(),
+            value: value.cloned(),
+        }
+    }
+}
+
+/// Request parameters checked against a simulation's declared schema.
+///
+/// Every declared parameter is present: values missing from the request take
+/// the declared default. Sliders are stored as numbers, toggles as booleans,
+/// selects as strings, curves as arrays of numbers and formulas as their
+/// text. A slider with a unit may be given in another unit of
+/// the same dimension, as `{"value": 0.55, "unit": "μm"}` or `"0.55 μm"`, and
+/// is stored converted to its own unit.
+#[derive(Debug, Clone)]
+pub struct Params {
+    values: Map<String, Value>,
+}
+
+impl Params {
+    /// Validate raw request parameters, collecting every invalid field
+    pub fn validate(
+        schema: &[SimulationParameter],
+        raw: &Map<String, Value>,
+    ) -> Result<Self, Vec<ParameterError>> {
+        let mut errors = Vec::new();
+        let mut values = Map::new();
+
+        for name in raw.keys() {
+            if !schema.iter().any(|p| &p.name == name) {
+                errors.push(ParameterError::new(name, "unknown parameter", raw.get(name)));
+            }
+        }
+
+        for param in schema {
+            match raw.get(&param.name) {
+                None | Some(Value::Null) => {
+                    values.insert(param.name.clone(), param.default.clone());
+                }
+                Some(value) => match check_value(param, value) {
+                    Ok(value) => {
+                        values.insert(param.name.clone(), value);
+                    }
+                    Err(message) => errors.push(ParameterError::new(&param.name, message, Some(value))),
+                },
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(Self { values })
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Parameters stored with an earlier result. They were validated when
+    /// the result was computed; ranges may have been edited since, so they
+    /// are not checked again.
+    pub fn from_stored(values: Map<String, Value>) -> Self {
+        Self { values }
+    }
+
+    /// Resolved values, including defaults for omitted parameters
+    pub fn values(&self) -> &Map<String, Value> {
+        &self.values
+    }
+
+    /// Numeric parameter value.
+    ///
+    /// Panics if `name` is not a declared numeric parameter, which is a bug in
+    /// the simulation rather than bad user input.
+    pub fn f64(&self, name: &str) -> f64 {
+        self.values
+            .get(name)
+            .and_then(Value::as_f64)
+            .unwrap_or_else(|| panic!("undeclared numeric parameter `{name}`"))
+    }
+
+    /// Integer-valued slider, e.g. a quantum number
+    pub fn i64(&self, name: &str) -> i64 {
+        self.f64(name).round() as i64
+    }
+
+    /// Seed of a stochastic simulation's random numbers
+    pub fn seed(&self) -> u64 {
+        self.i64(SEED) as u64
+    }
+
+    /// Random source of a stochastic simulation, seeded from its `seed`
+    /// parameter so the same parameters always give the same run
+    pub fn monte_carlo(&self) -> MonteCarlo {
+        MonteCarlo::new(self.seed())
+    }
+
+    /// Curve parameter values, evenly spaced along whatever the curve spans
+    pub fn curve(&self, name: &str) -> Vec<f64> {
+        self.values
+            .get(name)
+            .and_then(Value::as_array)
+            .and_then(|points| points.iter().map(Value::as_f64).collect())
+            .unwrap_or_else(|| panic!("undeclared curve parameter `{name}`"))
+    }
+
+    /// Formula parameter, parsed; it was checked to parse and to use only
+    /// its declared variables when the parameters were validated
+    pub fn formula(&self, name: &str) -> Expression {
+        let text = self
+            .values
+            .get(name)
+            .and_then(Value::as_str)
+            .unwrap_or_else(|| panic!("undeclared formula parameter `{name}`"));
+        Expression::parse(text).unwrap_or_else(|message| panic!("formula parameter `{name}` {message}"))
+    }
+
+    /// Toggle parameter value
+    pub fn bool(&self, name: &str) -> bool {
+        self.values
+            .get(name)
+            .and_then(Value::as_bool)
+            .unwrap_or_else(|| panic!("undeclared toggle parameter `{name}`"))
+    }
+
+    /// Select parameter value, one of the declared options
+    pub fn str(&self, name: &str) -> &str {
+        self.values
+            .get(name)
+            .and_then(Value::as_str)
+            .unwrap_or_else(|| panic!("undeclared select parameter `{name}`"))
+    }
+}
+
+/// A fresh seed for runs that do not give one, within the range the
+/// simulations declare for `seed`
+pub fn random_seed() -> u64 {
+    rand::random::<u32>().into()
+}
+
+/// Set `seed` in raw request parameters that leave it out, if the schema
+/// takes one, so a stochastic run records the seed that reproduces it
+/// rather than falling back to the same default every time
+pub fn fill_seed(schema: &[SimulationParameter], raw: &mut Map<String, Value>, seed: u64) {
+    let omitted = matches!(raw.get(SEED), None | Some(Value::Null));
+    if omitted && schema.iter().any(|p| p.name == SEED) {
+        raw.insert(SEED.to_string(), seed.into());
+    }
+}
+
+/// Seed recorded in a run's parameters, if its simulation is stochastic
+pub fn recorded_seed(parameters: &Value) -> Option<u64> {
+    parameters.get(SEED)?.as_f64().map(|seed| seed.round() as u64)
+}
+
+fn check_value(param: &SimulationParameter, value: &Value) -> Result<Value, String> {
+    match param.param_type.as_str() {
+        "toggle" => match value {
+            Value::Bool(b) => Ok(Value::Bool(*b)),
+            Value::Number(n) if n.as_f64() == Some(0.0) => Ok(Value::Bool(false)),
+            Value::Number(n) if n.as_f64() == Some(1.0) => Ok(Value::Bool(true)),
+            _ => Err("expected a boolean".to_string()),
+        },
+        "select" => {
+            let options = param.options.as_deref().unwrap_or_default();
+            match value.as_str() {
+                Some(choice) if options.iter().any(|o| o == choice) => Ok(value.clone()),
+                _ => Err(format!("must be one of: {}", options.join(", "))),
+            }
+        }
+        "curve" => {
+            let points = curve_points(value)?;
+            if !CURVE_POINTS.contains(&points.len()) {
+                return Err(format!(
+                    "must have between {} and {} points, not {}",
+                    CURVE_POINTS.start(),
+                    CURVE_POINTS.end(),
+                    points.len()
+                ));
+            }
+            for (i, &number) in points.iter().enumerate() {
+                check_range(param, number).map_err(|message| format!("point {i} {message}"))?;
+            }
+            Ok(points.into())
+        }
+        "formula" => {
+            let text = value.as_str().ok_or_else(|| "expected the text of a formula".to_string())?;
+            check_formula(text, param.variables.as_deref().unwrap_or_default())?;
+            Ok(value.clone())
+        }
+        _ => {
+            let number = slider_value(param, value)?;
+            check_range(param, number)?;
+            let unit = param.unit.as_deref().map_or(String::new(), |unit| format!(" {unit}"));
+            if let Some(step) = param.step {
+                let steps = (number - param.min.unwrap_or(0.0)) / step;
+                if (steps - steps.round()).abs() > STEP_TOLERANCE {
+                    return Err(format!("must be a multiple of {step}{unit}"));
+                }
+            }
+            Ok(Value::from(number))
+        }
+    }
+}
+
+/// `text` parsed as a formula that uses no variables besides `variables`
+/// and the built-in constants
+pub fn check_formula(text: &str, variables: &[String]) -> Result<Expression, String> {
+    let expression = Expression::parse(text)?;
+    let unknown = expression
+        .variables()
+        .into_iter()
+        .find(|name| !variables.iter().any(|v| v == name) && !is_constant(name));
+    match unknown {
+        Some(name) if variables.is_empty() => Err(format!("uses `{name}`, but takes no variables")),
+        Some(name) => Err(format!("uses `{name}`, which is not one of its variables {}", variables.join(", "))),
+        None => Ok(expression),
+    }
+}
+
+/// A finite number within the parameter's range
+fn check_range(param: &SimulationParameter, number: f64) -> Result<(), String> {
+    if !number.is_finite() {
+        return Err("expected a finite number".to_string());
+    }
+    let unit = param.unit.as_deref().map_or(String::new(), |unit| format!(" {unit}"));
+    if let Some(min) = param.min {
+        if number < min {
+            return Err(format!("must be at least {min}{unit}"));
+        }
+    }
+    if let Some(max) = param.max {
+        if number > max {
+            return Err(format!("must be at most {max}{unit}"));
+        }
+    }
+    Ok(())
+}
+
+/// Curve values, always in the curve's own unit: an array of numbers, or
+/// the numbers separated by commas as in a query string
+fn curve_points(value: &Value) -> Result<Vec<f64>, String> {
+    let expected = || "expected an array of numbers".to_string();
+    match value {
+        Value::Array(items) => items.iter().map(|item| item.as_f64().ok_or_else(expected)).collect(),
+        Value::String(text) => text.split(',').map(|item| item.trim().parse().map_err(|_| expected())).collect(),
+        _ => Err(expected()),
+    }
+}
+
+/// A slider value in the slider's own unit: a plain number, or a number
+/// with a unit to convert from
+fn slider_value(param: &SimulationParameter, value: &Value) -> Result<f64, String> {
+    let (number, unit) = match value {
+        Value::Object(fields) => {
+            let number = fields.get("value").and_then(Value::as_f64);
+            match (number, fields.get("unit").and_then(Value::as_str)) {
+                (Some(number), Some(unit)) if fields.len() == 2 => (number, unit),
+                _ => return Err("expected a number, or an object with a numeric `value` and a `unit`".to_string()),
+            }
+        }
+        Value::String(text) => {
+            split_quantity(text).ok_or_else(|| "expected a number, or a number followed by a unit".to_string())?
+        }
+        _ => return value.as_f64().ok_or_else(|| "expected a number".to_string()),
+    };
+    let declared = param
+        .unit
+        .as_deref()
+        .and_then(units::find)
+        .ok_or_else(|| "takes a plain number, without a unit".to_string())?;
+    units::convert(number, unit, declared)
+}
+
+/// `"0.55 μm"` or `"0.55μm"` as the number and the unit symbol
+fn split_quantity(text: &str) -> Option<(f64, &str)> {
+    let text = text.trim();
+    // The longest prefix that is a number, so exponents such as `1e-3` stay with it
+    text.char_indices()
+        .map(|(at, _)| at)
+        .chain([text.len()])
+        .rev()
+        .find_map(|at| Some((text[..at].parse().ok()?, text[at..].trim())))
+        .filter(|(_, unit)| !unit.is_empty())
+}
@@ -0,0 +1,208 @@
+//! Photoelectric effect
+
+use serde_json::Value;
+
+use crate::models::quiz::{Question, Quiz};
+use crate::physics::constants::{ELECTRON_VOLT, GIGA, MEGA, PLANCK, SPEED_OF_LIGHT};
+use crate::physics::materials::find_metal;
+use crate::simulations::params::Params;
+use crate::simulations::{Derived, Signal, Simulation, SimulationError, SimulationRegistry, output};
+
+/// Illuminated cathode area (m²)
+const CATHODE_AREA: f64 = 1e-4;
+/// Photoelectrons emitted per absorbed photon above threshold
+const QUANTUM_EFFICIENCY: f64 = 1e-3;
+/// Anode voltage range (V) of the current–voltage curve
+const VOLTAGE_RANGE: (f64, f64) = (-10.0, 5.0);
+const VOLTAGE_POINTS: usize = 301;
+/// Frequency range (10¹⁴ Hz) of the stopping voltage curve, matching the slider
+const FREQUENCY_RANGE: (f64, f64) = (1.0, 30.0);
+const FREQUENCY_POINTS: usize = 300;
+
+pub fn register(registry: &mut SimulationRegistry) {
+    registry.register(Photoelectric);
+}
+
+pub struct Photoelectric;
+
+impl Simulation for Photoelectric {
+    fn id(&self) -> &'static str {
+        "photoelectric"
+    }
+
+    fn quiz(&self) -> Option<Quiz> {
+        Some(Quiz {
+            simulation_id: "photoelectric".to_string(),
+            title: "Photoelectric Effect Quiz".to_string(),
+            questions: vec![
+                Question::multiple_choice(
+                    "intensity",
+                    "Light just below the threshold frequency falls on a metal. What happens if its intensity is doubled?",
+                    &["Electrons are emitted with twice the energy", "Electrons are emitted at half the energy", "Still no electrons are emitted", "Electrons are emitted after a delay"],
+                    2,
+                    "Each electron absorbs one photon, and a photon below threshold never has enough energy, no matter how many arrive.",
+                )
+                .with_hints(&[
+                    "In Einstein's picture each electron is freed by a single photon.",
+                    "Intensity changes the number of photons. Does it change the energy of each photon?",
+                ]),
+                Question::numeric(
+                    "stopping_voltage",
+                    "Photons of 4.0 eV hit sodium (φ = 2.36 eV). What is the stopping voltage?",
+                    "V",
+                    1.64,
+                    0.02,
+                    "eV_s = hf − φ = 4.0 − 2.36 = 1.64 eV, so V_s = 1.64 V.",
+                )
+                .with_hints(&[
+                    "The maximum kinetic energy of an electron is hf − φ.",
+                    "The stopping voltage in volts equals that kinetic energy in electronvolts.",
+                ]),
+                Question::multiple_choice(
+                    "slope",
+                    "What is the slope of a graph of stopping voltage against frequency?",
+                    &["φ/e, different for every metal", "h/e, the same for every metal", "It depends on the intensity", "e/h"],
+                    1,
+                    "eV_s = hf − φ, so V_s = (h/e)f − φ/e. The slope h/e is universal; only the intercept depends on the metal.",
+                )
+                .with_hints(&[
+                    "Start from eV_s = hf − φ and divide by e.",
+                    "That is a straight line in f. Which part depends on the metal: the slope or the intercept?",
+                ]),
+            ],
+        })
+    }
+
+    fn units(&self) -> &'static [(&'static str, &'static str)] {
+        &[
+            ("voltage", "V"),
+            ("current", "μA"),
+            ("frequency_sweep", "10¹⁴ Hz"),
+            ("stopping_voltage_sweep", "V"),
+        ]
+    }
+
+    fn signal(&self) -> Option<Signal> {
+        Some(Signal { output: "current", position: "voltage" })
+    }
+
+    fn prerequisites(&self) -> &'static [&'static str] {
+        &["blackbody"]
+    }
+
+    fn derived(&self, _params: &Params, data: &Value) -> Vec<Derived> {
+        vec![
+            Derived::new(
+                "photon_energy",
+                "Photon Energy",
+                output(data, "/photon_energy"),
+                "eV",
+                r"E = hf",
+            ),
+            Derived::new(
+                "threshold_frequency",
+                "Threshold Frequency",
+                output(data, "/threshold_frequency"),
+                "10¹⁴ Hz",
+                r"f_0 = \phi / h",
+            ),
+            Derived::new(
+                "threshold_wavelength",
+                "Threshold Wavelength",
+                output(data, "/threshold_wavelength"),
+                "nm",
+                r"\lambda_0 = hc / \phi",
+            ),
+            Derived::new(
+                "max_kinetic_energy",
+                "Maximum Kinetic Energy of Electrons",
+                output(data, "/max_kinetic_energy"),
+                "eV",
+                r"K_{\max} = \max(hf - \phi, 0)",
+            ),
+            Derived::new(
+                "stopping_voltage",
+                "Stopping Voltage",
+                output(data, "/stopping_voltage"),
+                "V",
+                r"V_s = K_{\max} / e",
+            ),
+        ]
+    }
+
+    fn run(&self, params: &Params) -> Result<Value, SimulationError> {
+        let frequency = params.f64("frequency");
+        let intensity = params.f64("intensity");
+        let metal = params.str("metal");
+        let work_function = match find_metal(metal) {
+            Some(m) => m.work_function,
+            None => params.f64("work_function"),
+        };
+
+        let photon_energy = photon_energy_ev(frequency);
+        let threshold_frequency = work_function * ELECTRON_VOLT / PLANCK / 1e14;
+        let max_kinetic_energy = (photon_energy - work_function).max(0.0);
+        let emits = photon_energy > work_function;
+
+        // Every electron above threshold reaches the anode once the voltage
+        // no longer opposes it
+        let photon_rate = intensity * CATHODE_AREA / (photon_energy * ELECTRON_VOLT);
+        let saturation_current = if emits { QUANTUM_EFFICIENCY * photon_rate * ELECTRON_VOLT } else { 0.0 };
+
+        let voltages: Vec<f64> = linspace(VOLTAGE_RANGE, VOLTAGE_POINTS);
+        let current: Vec<f64> = voltages
+            .iter()
+            .map(|&v| saturation_current * collected_fraction(v, max_kinetic_energy) * MEGA)
+            .collect();
+
+        let frequencies: Vec<f64> = linspace(FREQUENCY_RANGE, FREQUENCY_POINTS);
+        let stopping_voltages: Vec<f64> = frequencies
+            .iter()
+            .map(|&f| (photon_energy_ev(f) - work_function).max(0.0))
+            .collect();
+
+        Ok(serde_json::json!({
+            "photon_energy": photon_energy,
+            "work_function": work_function,
+            "threshold_frequency": threshold_frequency,
+            "threshold_wavelength": GIGA * PLANCK * SPEED_OF_LIGHT / (work_function * ELECTRON_VOLT),
+            "emits_electrons": emits,
+            "max_kinetic_energy": max_kinetic_energy,
+            "stopping_voltage": max_kinetic_energy,
+            "saturation_current": saturation_current * MEGA,
+            "voltage": voltages,
+            "current": current,
+            "frequency_sweep": frequencies,
+            "stopping_voltage_sweep": stopping_voltages,
+            "frequency": frequency,
+            "intensity": intensity,
+            "metal": metal,
+        }))
+    }
+}
+
+/// Photon energy (eV) at a frequency given in units of 10¹⁴ Hz
+fn photon_energy_ev(frequency: f64) -> f64 {
+    PLANCK * frequency * 1e14 / ELECTRON_VOLT
+}
+
+/// Fraction of emitted electrons that reach the anode at voltage `v`.
+///
+/// Kinetic energies are taken as spread evenly between 0 and `max_kinetic`
+/// (eV), so a retarding voltage cuts the current off linearly, reaching
+/// zero at the stopping voltage.
+fn collected_fraction(v: f64, max_kinetic: f64) -> f64 {
+    if v >= 0.0 {
+        1.0
+    } else if max_kinetic <= 0.0 {
+        0.0
+    } else {
+        (1.0 + v / max_kinetic).max(0.0)
+    }
+}
+
+fn linspace((start, stop): (f64, f64), points: usize) -> Vec<f64> {
+    (0..points)
+        .map(|i| start + (stop - start) * i as f64 / (points - 1) as f64)
+        .collect()
+}
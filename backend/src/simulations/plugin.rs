@@ -0,0 +1,356 @@
+//! Simulations compiled to WebAssembly and loaded at startup, so a course can
+//! add its own without rebuilding the server.
+//!
+//! Every `<id>.wasm` in the configured plugins directory becomes the
+//! simulation `<id>`. Modules run on the interpreter in
+//! [`crate::services::wasm`], with no imports, a bounded memory and a fuel
+//! budget per run, and each run starts from a fresh instance. A module
+//! exports:
+//!
+//! - `memory`
+//! - `alloc(len: i32) -> i32`, room for `len` bytes of input
+//! - `content() -> i64`, the simulation's content as JSON, with the fields
+//!   of a content file and the theory section under `theory`
+//! - `run(ptr: i32, len: i32) -> i64`, the output for the parameters at
+//!   `ptr`, a JSON object of the validated values
+//!
+//! Both return the location of their JSON in memory as `ptr << 32 | len`.
+//! A run whose output is `{"errors": [{"parameter": …, "message": …}]}`
+//! rejects its parameters, like a built-in simulation finding them
+//! inconsistent.
+
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::config::PluginsConfig;
+use crate::services::wasm::{Fuel, Instance, Module, Val, ValType, PAGE_SIZE};
+use crate::simulations::catalog::SimulationContent;
+use crate::simulations::params::{ParameterError, Params};
+use crate::simulations::{Progress, Simulation, SimulationError, SimulationRegistry};
+
+/// Functions every plugin exports, with their parameter and result types
+const EXPORTS: [(&str, &[ValType], &[ValType]); 3] = [
+    ("alloc", &[ValType::I32], &[ValType::I32]),
+    ("content", &[], &[ValType::I64]),
+    ("run", &[ValType::I32, ValType::I32], &[ValType::I64]),
+];
+
+/// Register every plugin in `dir`. Fails on the first module that cannot
+/// be used, naming it.
+pub fn load(registry: &mut SimulationRegistry, dir: &Path, config: &PluginsConfig) -> Result<usize, String> {
+    let mut paths: Vec<_> = fs::read_dir(dir)
+        .map_err(|err| err.to_string())?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "wasm"))
+        .collect();
+    paths.sort();
+
+    for path in &paths {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let id = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or_default();
+        if registry.get_any(id).is_some() {
+            return Err(format!("{name}: a simulation with ID `{id}` already exists"));
+        }
+        let bytes = fs::read(path).map_err(|err| format!("{name}: {err}"))?;
+        let (plugin, content) = Plugin::new(id, &bytes, config).map_err(|err| format!("{name}: {err}"))?;
+        registry.register_embedded(plugin, content);
+    }
+    Ok(paths.len())
+}
+
+/// A simulation run by a WebAssembly module
+pub struct Plugin {
+    id: &'static str,
+    module: Module,
+    /// First four bytes of the module's SHA-256, so results of another
+    /// build of the plugin are never taken for this one's
+    version: u32,
+    fuel: u64,
+    max_pages: u32,
+}
+
+/// A parameter a run rejected
+#[derive(Deserialize)]
+struct RejectedParameter {
+    parameter: String,
+    message: String,
+}
+
+impl Plugin {
+    /// Decode and check the module `bytes` of the simulation `id`, and read
+    /// its content
+    pub fn new(id: &str, bytes: &[u8], config: &PluginsConfig) -> Result<(Self, SimulationContent), String> {
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-') {
+            return Err("the ID, the file name, must be lowercase letters, digits and hyphens".to_string());
+        }
+        let module = Module::decode(bytes)?;
+        if !module.exports_memory() {
+            return Err("the module does not export its memory".to_string());
+        }
+        for (name, params, results) in EXPORTS {
+            if !module.exports_function(name, params, results) {
+                return Err(format!("the module does not export `{name}` of the plugin interface"));
+            }
+        }
+
+        let digest = Sha256::digest(bytes);
+        let plugin = Self {
+            // Plugins are loaded once, at startup
+            id: Box::leak(id.to_string().into_boxed_str()),
+            module,
+            version: u32::from_be_bytes([digest[0], digest[1], digest[2], digest[3]]),
+            fuel: config.fuel,
+            max_pages: (config.max_memory_mb as usize * (1 << 20) / PAGE_SIZE) as u32,
+        };
+
+        let mut fuel = Fuel {
+            remaining: plugin.fuel,
+            interrupt: &|| false,
+        };
+        let mut instance = Instance::new(&plugin.module, plugin.max_pages, &mut fuel).map_err(|trap| trap.to_string())?;
+        let location = instance.call("content", &[], &mut fuel).map_err(|trap| format!("content: {trap}"))?;
+        let json = returned(&instance, &location).ok_or("content: returned a location outside its memory")?;
+        let mut content: SimulationContent = serde_json::from_slice(json).map_err(|err| format!("content: {err}"))?;
+        if content.id.is_empty() {
+            content.id = id.to_string();
+        }
+        if content.id != id {
+            return Err(format!("content: id must be \"{id}\", the file name"));
+        }
+        let problems: Vec<_> = content.problems().into_iter().map(|(field, message)| format!("{field}: {message}")).collect();
+        if !problems.is_empty() {
+            return Err(format!("content: {}", problems.join("; ")));
+        }
+        Ok((plugin, content))
+    }
+
+    /// Run the module on `input`, returning its output
+    fn call(&self, input: &[u8], fuel: &mut Fuel) -> Result<Vec<u8>, String> {
+        let mut instance = Instance::new(&self.module, self.max_pages, fuel).map_err(|trap| trap.to_string())?;
+        let len = i32::try_from(input.len()).map_err(|_| "the parameters are too large".to_string())?;
+        let ptr = match instance.call("alloc", &[Val::I32(len)], fuel).map_err(|trap| format!("alloc: {trap}"))?[..] {
+            [Val::I32(ptr)] => ptr as u32 as usize,
+            _ => return Err("alloc: returned no pointer".to_string()),
+        };
+        instance
+            .memory_mut()
+            .get_mut(ptr..ptr + input.len())
+            .ok_or("alloc: returned a pointer outside its memory")?
+            .copy_from_slice(input);
+        let location =
+            instance.call("run", &[Val::I32(ptr as i32), Val::I32(len)], fuel).map_err(|trap| format!("run: {trap}"))?;
+        let output = returned(&instance, &location).ok_or("run: returned a location outside its memory")?;
+        Ok(output.to_vec())
+    }
+}
+
+/// The bytes at the `ptr << 32 | len` an export returned
+fn returned<'a>(instance: &'a Instance, results: &[Val]) -> Option<&'a [u8]> {
+    let [Val::I64(location)] = results[..] else {
+        return None;
+    };
+    let ptr = (location as u64 >> 32) as usize;
+    let len = location as u32 as usize;
+    instance.memory().get(ptr..ptr.checked_add(len)?)
+}
+
+impl Simulation for Plugin {
+    fn id(&self) -> &'static str {
+        self.id
+    }
+
+    fn version(&self) -> u32 {
+        self.version
+    }
+
+    fn run(&self, params: &Params) -> Result<Value, SimulationError> {
+        self.run_with_progress(params, &Progress::default())
+    }
+
+    fn run_with_progress(&self, params: &Params, progress: &Progress) -> Result<Value, SimulationError> {
+        let input = serde_json::to_vec(params.values()).expect("parameters serialize");
+        let interrupt = || progress.check().is_err();
+        let mut fuel = Fuel {
+            remaining: self.fuel,
+            interrupt: &interrupt,
+        };
+        // Traps out of fuel leave none, and interrupted ones a failed check
+        let output = self.call(&input, &mut fuel).map_err(|message| {
+            if fuel.remaining == 0 {
+                SimulationError::FuelExhausted(self.fuel)
+            } else if let Err(err) = progress.check() {
+                err
+            } else {
+                SimulationError::Failed(message)
+            }
+        })?;
+
+        let output: Value = serde_json::from_slice(&output)
+            .map_err(|err| SimulationError::Failed(format!("run: the output is not JSON ({err})")))?;
+        if !output.is_object() {
+            return Err(SimulationError::Failed("run: the output is not a JSON object".to_string()));
+        }
+        if let Some(errors) = output.get("errors") {
+            let rejected = Vec::<RejectedParameter>::deserialize(errors)
+                .map_err(|err| SimulationError::Failed(format!("run: malformed errors ({err})")))?;
+            return Err(SimulationError::InvalidParameters(
+                rejected
+                    .iter()
+                    .map(|rejected| {
+                        let value = params.values().get(&rejected.parameter);
+                        ParameterError::new(&rejected.parameter, rejected.message.as_str(), value)
+                    })
+                    .collect(),
+            ));
+        }
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const CONTENT: &str = r#"{"name": "Echo", "summary": "Returns its parameters", "description": "Echoes.",
+        "difficulty": "beginner", "estimated_time_minutes": 1, "theory": "Nothing to it.",
+        "parameters": [{"name": "x", "label": "X", "type": "slider", "min": 0, "max": 10, "default": 1}]}"#;
+
+    fn leb(mut value: i64) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        loop {
+            let byte = (value & 0x7F) as u8;
+            value >>= 7;
+            if (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0) {
+                bytes.push(byte);
+                return bytes;
+            }
+            bytes.push(byte | 0x80);
+        }
+    }
+
+    fn vector(items: &[Vec<u8>]) -> Vec<u8> {
+        [leb(items.len() as i64), items.concat()].concat()
+    }
+
+    fn name(name: &str) -> Vec<u8> {
+        [leb(name.len() as i64), name.as_bytes().to_vec()].concat()
+    }
+
+    /// Plugin with `content` as its content whose runs return their input,
+    /// exporting the functions named in `exports`
+    fn echo(content: &str, exports: &[&str]) -> Vec<u8> {
+        let sections = [
+            (1, vector(&[vec![0x60, 1, 0x7F, 1, 0x7F], vec![0x60, 0, 1, 0x7E], vec![0x60, 2, 0x7F, 0x7F, 1, 0x7E]])),
+            (3, vector(&[vec![0], vec![1], vec![2]])),
+            (5, vector(&[vec![0, 1]])),
+            (
+                7,
+                vector(
+                    &[("memory", 2, 0), ("alloc", 0, 0), ("content", 0, 1), ("run", 0, 2)]
+                        .iter()
+                        .filter(|(export, ..)| exports.contains(export))
+                        .map(|&(export, kind, index)| [name(export), vec![kind, index]].concat())
+                        .collect::<Vec<_>>(),
+                ),
+            ),
+            (
+                10,
+                vector(
+                    &[
+                        [vec![0, 0x41], leb(4096), vec![0x0B]].concat(),
+                        [vec![0, 0x42], leb(content.len() as i64), vec![0x0B]].concat(),
+                        vec![0, 0x20, 0, 0xAD, 0x42, 32, 0x86, 0x20, 1, 0xAD, 0x84, 0x0B],
+                    ]
+                    .map(|body| [leb(body.len() as i64), body].concat()),
+                ),
+            ),
+            (11, vector(&[[vec![0, 0x41, 0, 0x0B], name(content)].concat()])),
+        ];
+        let mut bytes = b"\0asm\x01\0\0\0".to_vec();
+        for (id, contents) in sections {
+            bytes.push(id);
+            bytes.extend(leb(contents.len() as i64));
+            bytes.extend(contents);
+        }
+        bytes
+    }
+
+    const ALL: [&str; 4] = ["memory", "alloc", "content", "run"];
+
+    fn params(values: Value) -> Params {
+        let Value::Object(values) = values else { unreachable!() };
+        Params::from_stored(values)
+    }
+
+    #[test]
+    fn runs_a_plugin() {
+        let bytes = echo(CONTENT, &ALL);
+        let (plugin, content) = Plugin::new("echo", &bytes, &PluginsConfig::default()).unwrap();
+        assert_eq!(content.id, "echo");
+        assert_eq!(content.name, "Echo");
+        assert_eq!(content.theory, "Nothing to it.");
+        assert_eq!(plugin.version(), u32::from_be_bytes(Sha256::digest(&bytes)[..4].try_into().unwrap()));
+
+        let output = plugin.run(&params(json!({"x": 2.5, "label": "ü"}))).unwrap();
+        assert_eq!(output, json!({"x": 2.5, "label": "ü"}));
+    }
+
+    #[test]
+    fn serves_embedded_content() {
+        let (plugin, content) = Plugin::new("echo", &echo(CONTENT, &ALL), &PluginsConfig::default()).unwrap();
+        let mut registry = SimulationRegistry::new();
+        registry.register_embedded(plugin, content);
+
+        assert_eq!(registry.load_catalog(Path::new("missing")).unwrap(), 1);
+        let simulation = registry.get("echo").unwrap();
+        assert_eq!(simulation.info().name, "Echo");
+        assert_eq!(simulation.parameters()[0].name, "x");
+    }
+
+    #[test]
+    fn reports_rejected_parameters() {
+        let (plugin, _) = Plugin::new("echo", &echo(CONTENT, &ALL), &PluginsConfig::default()).unwrap();
+
+        let rejected = plugin.run(&params(json!({"x": 9, "errors": [{"parameter": "x", "message": "too large"}]})));
+        let Err(SimulationError::InvalidParameters(errors)) = rejected else { panic!() };
+        assert_eq!(errors.len(), 1);
+        assert_eq!((errors[0].name.as_str(), errors[0].message.as_str()), ("x", "too large"));
+        assert_eq!(errors[0].value, Some(json!(9)));
+
+        let malformed = plugin.run(&params(json!({"errors": "x"})));
+        assert!(matches!(malformed, Err(SimulationError::Failed(message)) if message.starts_with("run: malformed errors")));
+    }
+
+    #[test]
+    fn stops_runs_out_of_fuel() {
+        // Reading the content takes 2 instructions, a run 10
+        let config = PluginsConfig { fuel: 5, ..PluginsConfig::default() };
+        let (plugin, _) = Plugin::new("echo", &echo(CONTENT, &ALL), &config).unwrap();
+
+        assert!(matches!(plugin.run(&params(json!({}))), Err(SimulationError::FuelExhausted(5))));
+    }
+
+    #[test]
+    fn rejects_unusable_modules() {
+        let config = PluginsConfig::default();
+        let other_id = CONTENT.replacen('{', r#"{"id": "other", "#, 1);
+        let no_theory = CONTENT.replace("Nothing to it.", " ");
+        let cases = [
+            ("Echo", echo(CONTENT, &ALL), "the ID, the file name, must be lowercase letters, digits and hyphens"),
+            ("echo", b"\0asm".to_vec(), "unexpected end of module"),
+            ("echo", echo(CONTENT, &["alloc", "content", "run"]), "the module does not export its memory"),
+            ("echo", echo(CONTENT, &["memory", "alloc", "content"]), "the module does not export `run` of the plugin interface"),
+            ("echo", echo(&other_id, &ALL), "content: id must be \"echo\", the file name"),
+            ("echo", echo(&no_theory, &ALL), "content: theory: theory must not be empty"),
+        ];
+        for (id, bytes, message) in cases {
+            assert_eq!(Plugin::new(id, &bytes, &config).err().as_deref(), Some(message));
+        }
+    }
+}
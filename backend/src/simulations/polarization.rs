@@ -0,0 +1,181 @@
+//! Light through a stack of linear polarizers (Malus's law)
+
+use serde_json::Value;
+
+use crate::models::quiz::{Question, Quiz};
+use crate::simulations::params::Params;
+use crate::simulations::{Derived, Simulation, SimulationError, SimulationRegistry, output};
+
+/// Number of polarizer slots the content file defines angles for; a larger
+/// count in the catalog is capped here
+const MAX_POLARIZERS: usize = 6;
+/// Angles sampled when sweeping a single inserted polarizer
+const SWEEP_POINTS: usize = 181;
+/// Largest number of evenly stepped polarizers in the graded-stack curve
+const MAX_GRADED: usize = 30;
+
+pub fn register(registry: &mut SimulationRegistry) {
+    registry.register(Polarization);
+}
+
+pub struct Polarization;
+
+impl Simulation for Polarization {
+    fn id(&self) -> &'static str {
+        "polarization"
+    }
+
+    fn quiz(&self) -> Option<Quiz> {
+        Some(Quiz {
+            simulation_id: "polarization".to_string(),
+            title: "Polarizers and Malus's Law Quiz".to_string(),
+            questions: vec![
+                Question::numeric(
+                    "single",
+                    "Vertically polarized light meets a polarizer whose axis is 60° from vertical. What fraction of the intensity is transmitted?",
+                    "",
+                    0.25,
+                    0.01,
+                    "cos²60° = 0.25.",
+                )
+                .with_hints(&[
+                    "Malus's law: I = I₀ cos²θ.",
+                    "cos 60° = ½.",
+                ]),
+                Question::numeric(
+                    "middle",
+                    "Unpolarized light passes through polarizers at 0°, 45° and 90°. What fraction of the original intensity emerges?",
+                    "",
+                    0.125,
+                    0.01,
+                    "½ × cos²45° × cos²45° = ½ × ½ × ½ = 1/8.",
+                )
+                .with_hints(&[
+                    "The first polarizer passes half of unpolarized light.",
+                    "Each further polarizer multiplies by cos² of the angle between it and the one before.",
+                    "Both steps are 45°, and cos²45° = ½.",
+                ]),
+                Question::multiple_choice(
+                    "remove_middle",
+                    "In that stack, what happens when the 45° polarizer is removed?",
+                    &["More light gets through", "No light gets through", "The intensity is unchanged", "The light becomes unpolarized"],
+                    1,
+                    "The 0° and 90° polarizers are crossed, so cos²90° = 0 blocks everything.",
+                )
+                .with_hints(&[
+                    "Without the middle polarizer, what angle separates the remaining two?",
+                    "Apply Malus's law with θ = 90°.",
+                ]),
+            ],
+        })
+    }
+
+    fn units(&self) -> &'static [(&'static str, &'static str)] {
+        &[("middle_sweep.angle", "°")]
+    }
+
+    fn derived(&self, _params: &Params, data: &Value) -> Vec<Derived> {
+        vec![
+            Derived::new(
+                "transmitted",
+                "Transmitted Fraction",
+                output(data, "/transmitted"),
+                "",
+                r"I / I_0 = \prod_i \cos^2(\theta_i - \theta_{i-1})",
+            ),
+            Derived::new(
+                "output_polarization",
+                "Outgoing Polarization Angle",
+                output(data, "/output_polarization"),
+                "°",
+                r"\theta_{\mathrm{out}} = \theta_{\mathrm{last\ polarizer}}",
+            ),
+        ]
+    }
+
+    fn run(&self, params: &Params) -> Result<Value, SimulationError> {
+        let count = (params.i64("polarizers") as usize).min(MAX_POLARIZERS);
+        let angles: Vec<f64> = (1..=count).map(|slot| params.f64(&format!("polarizer_{slot}_angle"))).collect();
+        let source = match params.str("input") {
+            "polarized" => Some(params.f64("input_angle")),
+            _ => None,
+        };
+
+        let mut intensity = 1.0;
+        let mut polarization = source;
+        let stages: Vec<Value> = angles
+            .iter()
+            .enumerate()
+            .map(|(i, &angle)| {
+                let incoming = intensity;
+                let relative = polarization.map(|p| relative_angle(p, angle));
+                intensity *= relative.map_or(0.5, |r| r.to_radians().cos().powi(2));
+                polarization = Some(angle);
+                serde_json::json!({
+                    "index": i + 1,
+                    "angle": angle,
+                    "relative_angle": relative,
+                    "incoming": incoming,
+                    "transmitted": intensity,
+                    "fraction": if incoming > 0.0 { intensity / incoming } else { 0.0 },
+                })
+            })
+            .collect();
+
+        let first = angles[0];
+        let last = angles[count - 1];
+
+        // The same stack with every polarizer between the first and last taken out
+        let without_intermediate = (count > 2).then(|| transmission(source, &[first, last]));
+
+        // A single polarizer inserted between the first and last, at every angle
+        let middle_sweep = (count > 1).then(|| {
+            let sweep_angles: Vec<f64> = (0..SWEEP_POINTS)
+                .map(|i| 180.0 * i as f64 / (SWEEP_POINTS - 1) as f64)
+                .collect();
+            let transmitted: Vec<f64> = sweep_angles.iter().map(|&a| transmission(source, &[first, a, last])).collect();
+            serde_json::json!({ "angle": sweep_angles, "transmitted": transmitted })
+        });
+
+        // The rotation from the first axis to the last split into n equal steps
+        let graded_stack = (count > 1).then(|| {
+            let turn = relative_signed(first, last);
+            let transmitted: Vec<f64> = (1..=MAX_GRADED)
+                .map(|n| {
+                    let stack: Vec<f64> = (0..=n).map(|k| first + turn * k as f64 / n as f64).collect();
+                    transmission(source, &stack)
+                })
+                .collect();
+            serde_json::json!({ "steps": (1..=MAX_GRADED).collect::<Vec<_>>(), "transmitted": transmitted })
+        });
+
+        Ok(serde_json::json!({
+            "stages": stages,
+            "transmitted": intensity,
+            "output_polarization": polarization,
+            "without_intermediate": without_intermediate,
+            "middle_sweep": middle_sweep,
+            "graded_stack": graded_stack,
+        }))
+    }
+}
+
+/// Fraction of the incoming intensity that leaves a stack of polarizers
+fn transmission(source: Option<f64>, angles: &[f64]) -> f64 {
+    let mut polarization = source;
+    angles.iter().fold(1.0, |intensity, &angle| {
+        let factor = polarization.map_or(0.5, |p| relative_angle(p, angle).to_radians().cos().powi(2));
+        polarization = Some(angle);
+        intensity * factor
+    })
+}
+
+/// Angle between two polarization axes, folded into [0°, 90°]
+fn relative_angle(a: f64, b: f64) -> f64 {
+    relative_signed(a, b).abs()
+}
+
+/// Smallest rotation (°) taking axis `a` onto axis `b`, in [−90°, 90°]
+fn relative_signed(a: f64, b: f64) -> f64 {
+    (b - a + 90.0).rem_euclid(180.0) - 90.0
+}
@@ -0,0 +1,201 @@
+//! Bound states of an electron in a potential drawn by the user, found by
+//! diagonalizing the discretized Hamiltonian
+
+use serde_json::Value;
+
+use crate::models::quiz::{Question, Quiz};
+use crate::numerics::eigen::{lowest, SymmetricTridiagonal};
+use crate::physics::constants::{ELECTRON_KINETIC, ELECTRON_VOLT, GIGA, PLANCK, SPEED_OF_LIGHT};
+use crate::simulations::params::Params;
+use crate::simulations::{output, series, Derived, Simulation, SimulationError, SimulationRegistry, Validation};
+
+/// Interior grid points; the walls are one spacing beyond each end
+const GRID_POINTS: usize = 600;
+
+pub fn register(registry: &mut SimulationRegistry) {
+    registry.register(PotentialWell);
+}
+
+pub struct PotentialWell;
+
+impl Simulation for PotentialWell {
+    fn id(&self) -> &'static str {
+        "potential-well"
+    }
+
+    fn quiz(&self) -> Option<Quiz> {
+        Some(Quiz {
+            simulation_id: "potential-well".to_string(),
+            title: "Arbitrary Potential Well Quiz".to_string(),
+            questions: vec![
+                Question::numeric(
+                    "nodes",
+                    "How many nodes inside the well does the wave function of the fourth state have?",
+                    "",
+                    3.0,
+                    0.01,
+                    "In one dimension the n-th bound state has n − 1 nodes, whatever the shape of the potential.",
+                )
+                .with_hints(&[
+                    "Plot the first few states and count their nodes, not counting the walls.",
+                    "The ground state has no nodes, and each level up adds one.",
+                ]),
+                Question::multiple_choice(
+                    "double_well",
+                    "A high barrier splits the well into two identical halves. What happens to the two lowest levels?",
+                    &["They move far apart", "They become nearly equal", "They vanish", "They cross"],
+                    1,
+                    "Tunneling through the barrier couples the states of the two halves only weakly, so the symmetric and antisymmetric combinations differ very little in energy.",
+                )
+                .with_hints(&[
+                    "With a high barrier the particle hardly tunnels between the halves. What do the two halves look like on their own?",
+                    "The two lowest states are the symmetric and antisymmetric combinations of the two halves' ground states. How different are their energies if they barely overlap?",
+                ]),
+                Question::multiple_choice(
+                    "raise_floor",
+                    "Adding the same constant to the potential everywhere changes the wave functions how?",
+                    &["Not at all", "They shrink", "They gain a node", "They spread out"],
+                    0,
+                    "A constant shifts every energy by that constant and leaves the Schrödinger equation for ψ unchanged.",
+                )
+                .with_hints(&[
+                    "Write the Schrödinger equation with V + C in place of V.",
+                    "Move C to the energy side: it is the same equation with E − C in place of E.",
+                ]),
+            ],
+        })
+    }
+
+    fn units(&self) -> &'static [(&'static str, &'static str)] {
+        &[
+            ("x", "nm"),
+            ("potential", "eV"),
+            ("curve_x", "nm"),
+            ("energies", "eV"),
+            ("box_levels", "eV"),
+            ("states.*.energy", "eV"),
+            ("states.*.mean_position", "nm"),
+            ("states.*.spread", "nm"),
+        ]
+    }
+
+    fn prerequisites(&self) -> &'static [&'static str] {
+        &["square-well", "quantum-tunneling"]
+    }
+
+    fn derived(&self, _params: &Params, data: &Value) -> Vec<Derived> {
+        let ground = output(data, "/energies/0");
+        let excitation = output(data, "/energies/1") - ground;
+        vec![
+            Derived::new("ground_state_energy", "Ground-State Energy", ground, "eV", r"E_1"),
+            Derived::new("excitation_energy", "First Excitation Energy", excitation, "eV", r"\Delta E = E_2 - E_1"),
+            Derived::new(
+                "transition_wavelength",
+                "Wavelength of the 2 → 1 Transition",
+                GIGA * PLANCK * SPEED_OF_LIGHT / (excitation * ELECTRON_VOLT),
+                "nm",
+                r"\lambda = \frac{hc}{E_2 - E_1}",
+            ),
+        ]
+    }
+
+    fn validate(&self, params: &Params, data: &Value) -> Vec<Validation> {
+        // A flat floor is a particle in a box, raised by the floor's height
+        let curve = params.curve("potential");
+        if curve.iter().any(|&v| v != curve[0]) {
+            return Vec::new();
+        }
+        let energies = series(data, "/energies");
+        let exact: Vec<f64> = series(data, "/box_levels").iter().map(|e| e + curve[0]).collect();
+        Validation::compare(
+            "energies",
+            r"E_n = V_0 + \frac{n^2 \pi^2 \hbar^2}{2 m L^2}",
+            "eV",
+            &energies,
+            &exact[..energies.len().min(exact.len())],
+        )
+        .into_iter()
+        .collect()
+    }
+
+    fn run(&self, params: &Params) -> Result<Value, SimulationError> {
+        let curve = params.curve("potential");
+        let width = params.f64("width");
+        let steps = params.str("interpolation") == "steps";
+        let count = params.i64("states") as usize;
+
+        let dx = width / (GRID_POINTS + 1) as f64;
+        let x: Vec<f64> = (1..=GRID_POINTS).map(|i| i as f64 * dx).collect();
+        let potential: Vec<f64> = x.iter().map(|&xi| sample(&curve, xi / width, steps)).collect();
+
+        let hamiltonian = SymmetricTridiagonal::hamiltonian(&potential, dx, ELECTRON_KINETIC);
+        let pairs = lowest(&hamiltonian, count);
+
+        let states: Vec<Value> = pairs
+            .iter()
+            .enumerate()
+            .map(|(i, pair)| {
+                // Unit vectors to wave functions with ∫|ψ|² dx = 1
+                let psi: Vec<f64> = pair.vector.iter().map(|v| v / dx.sqrt()).collect();
+                let density: Vec<f64> = pair.vector.iter().map(|v| v * v).collect();
+                let mean: f64 = density.iter().zip(&x).map(|(p, x)| p * x).sum();
+                let spread = density.iter().zip(&x).map(|(p, x)| p * (x - mean).powi(2)).sum::<f64>().sqrt();
+                let forbidden: f64 = density
+                    .iter()
+                    .zip(&potential)
+                    .filter(|(_, &v)| v > pair.value)
+                    .map(|(p, _)| p)
+                    .sum();
+                serde_json::json!({
+                    "n": i + 1,
+                    "energy": pair.value,
+                    "nodes": nodes(&psi),
+                    "mean_position": mean,
+                    "spread": spread,
+                    "forbidden_probability": forbidden,
+                    "psi": psi,
+                })
+            })
+            .collect();
+        let energies: Vec<f64> = pairs.iter().map(|pair| pair.value).collect();
+        let box_levels: Vec<f64> = (1..=count)
+            .map(|n| ELECTRON_KINETIC * (n as f64 * std::f64::consts::PI / width).powi(2))
+            .collect();
+        let curve_x: Vec<f64> = if steps {
+            (0..curve.len()).map(|j| (j as f64 + 0.5) * width / curve.len() as f64).collect()
+        } else {
+            (0..curve.len()).map(|j| j as f64 * width / (curve.len() - 1) as f64).collect()
+        };
+
+        Ok(serde_json::json!({
+            "x": x,
+            "potential": potential,
+            "curve_x": curve_x,
+            "states": states,
+            "energies": energies,
+            "box_levels": box_levels,
+            "width": width,
+        }))
+    }
+}
+
+/// The drawn potential at fraction `t` of the way across the box: the
+/// points spread evenly from wall to wall and joined by straight lines, or
+/// each held across an equal share of the width
+pub(super) fn sample(curve: &[f64], t: f64, steps: bool) -> f64 {
+    let last = curve.len() - 1;
+    if steps {
+        return curve[((t * curve.len() as f64) as usize).min(last)];
+    }
+    let position = t * last as f64;
+    let i = (position as usize).min(last - 1);
+    let fraction = position - i as f64;
+    curve[i] + fraction * (curve[i + 1] - curve[i])
+}
+
+/// Sign changes of ψ, ignoring the round-off where it is negligible
+pub(super) fn nodes(psi: &[f64]) -> usize {
+    let largest = psi.iter().fold(0.0, |m: f64, p| m.max(p.abs()));
+    let significant: Vec<f64> = psi.iter().copied().filter(|p| p.abs() > 1e-6 * largest).collect();
+    significant.windows(2).filter(|pair| pair[0] * pair[1] < 0.0).count()
+}
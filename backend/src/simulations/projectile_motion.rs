@@ -0,0 +1,239 @@
+//! Projectile motion with optional quadratic air drag
+
+use serde_json::Value;
+
+use crate::models::quiz::{Question, Quiz};
+use crate::numerics::ode::rk4_step;
+use crate::simulations::params::Params;
+use crate::simulations::{Derived, Simulation, SimulationError, SimulationRegistry, Validation, output, series};
+
+/// Integration steps per drag-free flight time
+const STEPS_PER_FLIGHT: f64 = 4000.0;
+/// Upper bound on the number of trajectory samples returned
+const OUTPUT_SAMPLES: usize = 200;
+
+pub fn register(registry: &mut SimulationRegistry) {
+    registry.register(ProjectileMotion);
+}
+
+pub struct ProjectileMotion;
+
+impl Simulation for ProjectileMotion {
+    fn id(&self) -> &'static str {
+        "projectile-motion"
+    }
+
+    fn quiz(&self) -> Option<Quiz> {
+        Some(Quiz {
+            simulation_id: "projectile-motion".to_string(),
+            title: "Projectile Motion Quiz".to_string(),
+            questions: vec![
+                Question::multiple_choice(
+                    "best_angle",
+                    "Without air drag, which launch angle gives the longest range?",
+                    &["30°", "45°", "60°", "90°"],
+                    1,
+                    "R = v₀² sin(2θ)/g is largest when sin(2θ) = 1, that is θ = 45°.",
+                )
+                .with_hints(&[
+                    "Without drag, R = v₀² sin(2θ)/g.",
+                    "The sine is largest when its argument is 90°.",
+                ]),
+                Question::multiple_choice(
+                    "drag_angle",
+                    "How does air drag change the launch angle that gives the longest range?",
+                    &["It rises above 45°", "It stays at 45°", "It drops below 45°", "Every angle gives the same range"],
+                    2,
+                    "Drag hurts long, high flights the most, so flatter launches that spend less time in the air do better.",
+                )
+                .with_hints(&[
+                    "Drag acts for as long as the ball is in the air.",
+                    "Higher launches stay up longer and lose more speed. Try a few angles with drag switched on.",
+                ]),
+                Question::numeric(
+                    "range",
+                    "A ball is launched at 20 m/s and 45° with g = 9.81 m/s² and no drag. How far does it travel?",
+                    "m",
+                    40.77,
+                    0.02,
+                    "R = v₀² sin(2θ)/g = 400 × 1 / 9.81 ≈ 40.8 m.",
+                )
+                .with_hints(&[
+                    "Use R = v₀² sin(2θ)/g.",
+                    "At 45°, sin(2θ) = 1, so R = v₀²/g.",
+                ]),
+            ],
+        })
+    }
+
+    fn units(&self) -> &'static [(&'static str, &'static str)] {
+        &[
+            ("t", "s"),
+            ("x", "m"),
+            ("y", "m"),
+            ("vx", "m/s"),
+            ("vy", "m/s"),
+        ]
+    }
+
+    fn derived(&self, _params: &Params, data: &Value) -> Vec<Derived> {
+        vec![
+            Derived::new(
+                "range",
+                "Range",
+                output(data, "/range"),
+                "m",
+                r"R = \frac{v_0^2 \sin 2\theta}{g}\ \text{without drag}",
+            ),
+            Derived::new(
+                "max_height",
+                "Maximum Height",
+                output(data, "/max_height"),
+                "m",
+                r"H = \frac{v_0^2 \sin^2\theta}{2g}\ \text{without drag}",
+            ),
+            Derived::new(
+                "flight_time",
+                "Flight Time",
+                output(data, "/flight_time"),
+                "s",
+                r"t_f = \frac{2 v_0 \sin\theta}{g}\ \text{without drag}",
+            ),
+        ]
+    }
+
+    /// Without drag the trajectory is the textbook parabola
+    fn validate(&self, params: &Params, data: &Value) -> Vec<Validation> {
+        if params.f64("drag_coefficient") != 0.0 {
+            return Vec::new();
+        }
+        let speed = params.f64("initial_speed");
+        let gravity = params.f64("gravity");
+        let (sin, cos) = params.f64("launch_angle").to_radians().sin_cos();
+        let t = series(data, "/t");
+        let x: Vec<f64> = t.iter().map(|t| speed * cos * t).collect();
+        let y: Vec<f64> = t.iter().map(|t| speed * sin * t - gravity * t * t / 2.0).collect();
+        [
+            Validation::compare("x", r"x = v_0 \cos\theta\, t", "m", &series(data, "/x"), &x),
+            Validation::compare("y", r"y = v_0 \sin\theta\, t - \tfrac{1}{2} g t^2", "m", &series(data, "/y"), &y),
+            Validation::compare(
+                "range",
+                r"R = \frac{v_0^2 \sin 2\theta}{g}",
+                "m",
+                &[output(data, "/range")],
+                &[2.0 * speed * speed * sin * cos / gravity],
+            ),
+            Validation::compare(
+                "flight_time",
+                r"t_f = \frac{2 v_0 \sin\theta}{g}",
+                "s",
+                &[output(data, "/flight_time")],
+                &[2.0 * speed * sin / gravity],
+            ),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    fn run(&self, params: &Params) -> Result<Value, SimulationError> {
+        let initial_speed = params.f64("initial_speed");
+        let launch_angle = params.f64("launch_angle");
+        let drag_coefficient = params.f64("drag_coefficient");
+        let gravity = params.f64("gravity");
+
+        let flight = simulate_flight(initial_speed, launch_angle.to_radians(), drag_coefficient, gravity);
+
+        // Closed-form results without drag, for comparison
+        let (sin, cos) = launch_angle.to_radians().sin_cos();
+        let ideal_time = 2.0 * initial_speed * sin / gravity;
+
+        Ok(serde_json::json!({
+            "t": flight.t,
+            "x": flight.x,
+            "y": flight.y,
+            "vx": flight.vx,
+            "vy": flight.vy,
+            "range": flight.range,
+            "max_height": flight.max_height,
+            "flight_time": flight.flight_time,
+            "impact_speed": flight.impact_speed,
+            "no_drag": {
+                "range": initial_speed * cos * ideal_time,
+                "max_height": (initial_speed * sin).powi(2) / (2.0 * gravity),
+                "flight_time": ideal_time,
+            },
+            "initial_speed": initial_speed,
+            "launch_angle": launch_angle,
+            "drag_coefficient": drag_coefficient,
+            "gravity": gravity,
+        }))
+    }
+}
+
+struct Flight {
+    t: Vec<f64>,
+    x: Vec<f64>,
+    y: Vec<f64>,
+    vx: Vec<f64>,
+    vy: Vec<f64>,
+    range: f64,
+    max_height: f64,
+    flight_time: f64,
+    impact_speed: f64,
+}
+
+/// Integrate the flight from launch at the origin until the projectile
+/// returns to the ground. State is [x, y, vx, vy] in SI units.
+fn simulate_flight(speed: f64, angle: f64, drag: f64, gravity: f64) -> Flight {
+    let derivative = |_t: f64, s: &[f64; 4]| -> [f64; 4] {
+        let v = s[2].hypot(s[3]);
+        [s[2], s[3], -drag * v * s[2], -gravity - drag * v * s[3]]
+    };
+
+    // Drag only shortens the flight, so the drag-free time bounds the step count
+    let dt = 2.0 * speed * angle.sin() / gravity / STEPS_PER_FLIGHT;
+    let mut t = 0.0;
+    let mut state = [0.0, 0.0, speed * angle.cos(), speed * angle.sin()];
+    let mut history = vec![(t, state)];
+    let mut max_height: f64 = 0.0;
+
+    loop {
+        let next = rk4_step(derivative, t, &state, dt);
+        if next[1] < 0.0 {
+            // Interpolate the ground crossing within the final step
+            let fraction = state[1] / (state[1] - next[1]);
+            let landing: [f64; 4] = std::array::from_fn(|i| state[i] + fraction * (next[i] - state[i]));
+            t += fraction * dt;
+            state = [landing[0], 0.0, landing[2], landing[3]];
+            history.push((t, state));
+            break;
+        }
+        t += dt;
+        state = next;
+        max_height = max_height.max(state[1]);
+        history.push((t, state));
+    }
+
+    // Keep the final landing sample when thinning the output
+    let stride = history.len().div_ceil(OUTPUT_SAMPLES).max(1);
+    let last = history.len() - 1;
+    let samples: Vec<&(f64, [f64; 4])> = history
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| i % stride == 0 || *i == last)
+        .map(|(_, sample)| sample)
+        .collect();
+
+    Flight {
+        t: samples.iter().map(|(t, _)| *t).collect(),
+        x: samples.iter().map(|(_, s)| s[0]).collect(),
+        y: samples.iter().map(|(_, s)| s[1]).collect(),
+        vx: samples.iter().map(|(_, s)| s[2]).collect(),
+        vy: samples.iter().map(|(_, s)| s[3]).collect(),
+        range: state[0],
+        max_height,
+        flight_time: t,
+        impact_speed: state[2].hypot(state[3]),
+    }
+}
@@ -0,0 +1,201 @@
+//! Quantum tunneling through a rectangular barrier
+
+use serde_json::Value;
+
+use crate::models::quiz::{Question, Quiz};
+use crate::numerics::complex::Complex;
+use crate::physics::constants::{ELECTRON_MASS, ELECTRON_VOLT, NANO, REDUCED_PLANCK};
+use crate::simulations::params::Params;
+use crate::simulations::{Derived, Signal, Simulation, SimulationError, SimulationRegistry, output};
+
+pub fn register(registry: &mut SimulationRegistry) {
+    registry.register(QuantumTunneling);
+}
+
+pub struct QuantumTunneling;
+
+impl Simulation for QuantumTunneling {
+    fn id(&self) -> &'static str {
+        "quantum-tunneling"
+    }
+
+    fn quiz(&self) -> Option<Quiz> {
+        Some(Quiz {
+            simulation_id: "quantum-tunneling".to_string(),
+            title: "Quantum Tunneling Quiz".to_string(),
+            questions: vec![
+                Question::multiple_choice(
+                    "classical",
+                    "A classical particle with energy E below the barrier height V₀...",
+                    &["always passes through", "is always reflected", "passes through half of the time", "gains energy from the barrier"],
+                    1,
+                    "Classically the particle would need negative kinetic energy inside the barrier, so it is always reflected.",
+                )
+                .with_hints(&[
+                    "Total energy is kinetic plus potential. What would the kinetic energy be inside the barrier?",
+                    "Kinetic energy ½mv² can never be negative.",
+                ]),
+                Question::multiple_choice(
+                    "width_dependence",
+                    "How does the transmission probability change as a thick barrier is made wider?",
+                    &["It grows linearly", "It stays the same", "It decreases exponentially", "It oscillates between 0 and 1"],
+                    2,
+                    "Inside the barrier the wave function decays as e^{−κx}, so for thick barriers T ≈ e^{−2κa}.",
+                )
+                .with_hints(&[
+                    "Inside the barrier the wave function is not a wave: it decays.",
+                    "ψ ∝ e^{−κx}, and the transmission goes with |ψ|² at the far side.",
+                ]),
+                Question::numeric(
+                    "decay_constant",
+                    "An electron meets a barrier 1 eV above its energy. What is the decay constant κ = √(2m(V₀ − E))/ħ inside the barrier?",
+                    "nm⁻¹",
+                    5.123,
+                    0.03,
+                    "With ħ²/2m = 0.0381 eV·nm² for an electron, κ = √(1 eV / 0.0381 eV·nm²) ≈ 5.12 nm⁻¹.",
+                )
+                .with_hints(&[
+                    "For an electron, ħ²/2m ≈ 0.0381 eV·nm².",
+                    "κ = √((V₀ − E) / (ħ²/2m)), with V₀ − E = 1 eV.",
+                ]),
+            ],
+        })
+    }
+
+    fn units(&self) -> &'static [(&'static str, &'static str)] {
+        &[("x", "nm"), ("potential", "eV")]
+    }
+
+    fn signal(&self) -> Option<Signal> {
+        Some(Signal { output: "probability_density", position: "x" })
+    }
+
+    fn prerequisites(&self) -> &'static [&'static str] {
+        &["double-slit"]
+    }
+
+    fn derived(&self, params: &Params, data: &Value) -> Vec<Derived> {
+        let deficit = params.f64("barrier_height") - params.f64("particle_energy");
+        let kappa = (2.0 * ELECTRON_MASS * deficit * ELECTRON_VOLT).sqrt() / REDUCED_PLANCK * NANO;
+        vec![
+            Derived::new(
+                "transmission",
+                "Tunneling Probability",
+                output(data, "/transmission"),
+                "",
+                r"T = \left[1 + \frac{V_0^2 \sinh^2(\kappa a)}{4E(V_0 - E)}\right]^{-1}",
+            ),
+            Derived::new(
+                "reflection",
+                "Reflection Probability",
+                output(data, "/reflection"),
+                "",
+                r"R = 1 - T",
+            ),
+            Derived::new(
+                "decay_length",
+                "Decay Length inside the Barrier",
+                1.0 / kappa,
+                "nm",
+                r"1/\kappa = \frac{\hbar}{\sqrt{2m(V_0 - E)}}",
+            ),
+        ]
+    }
+
+    fn run(&self, params: &Params) -> Result<Value, SimulationError> {
+        let particle_energy = params.f64("particle_energy");
+        let barrier_height = params.f64("barrier_height");
+        let barrier_width = params.f64("barrier_width");
+
+        let tunneling = calculate_tunneling(particle_energy, barrier_height, barrier_width);
+
+        Ok(serde_json::json!({
+            "x": tunneling.x,
+            "probability_density": tunneling.probability_density,
+            "potential": tunneling.potential,
+            "transmission": tunneling.transmission,
+            "reflection": tunneling.reflection,
+            "particle_energy": particle_energy,
+            "barrier_height": barrier_height,
+            "barrier_width": barrier_width,
+        }))
+    }
+}
+
+struct TunnelingResult {
+    x: Vec<f64>,
+    probability_density: Vec<f64>,
+    potential: Vec<f64>,
+    transmission: f64,
+    reflection: f64,
+}
+
+/// Solve scattering of an electron off a rectangular barrier occupying 0 ≤ x ≤ a.
+///
+/// The wave function is matched at both edges of the barrier with an incident
+/// plane wave of unit amplitude coming from the left. Lengths are in nm and
+/// energies in eV. The sampled window extends one barrier width plus two
+/// incident wavelengths on either side.
+fn calculate_tunneling(energy_ev: f64, barrier_ev: f64, width_nm: f64) -> TunnelingResult {
+    let num_points = 400;
+    // Wave number in nm⁻¹ for a given kinetic energy in eV
+    let wave_number = |e_ev: f64| (2.0 * ELECTRON_MASS * e_ev * ELECTRON_VOLT).sqrt() / REDUCED_PLANCK * NANO;
+
+    let k = Complex::real(wave_number(energy_ev));
+    // Inside the barrier the wave number is imaginary when E < V₀. Nudge the
+    // degenerate E = V₀ case so the matching equations stay well-defined.
+    let mut delta = energy_ev - barrier_ev;
+    if delta.abs() < 1e-9 {
+        delta = 1e-9;
+    }
+    let q = if delta > 0.0 {
+        Complex::real(wave_number(delta))
+    } else {
+        Complex::new(0.0, wave_number(-delta))
+    };
+
+    // Inside: ψ = C e^{iqx} + D e^{-iqx}; outside right: ψ = t e^{ikx}
+    // Matching at x = a gives C and D in units of t; matching at x = 0 fixes t.
+    let a = width_nm;
+    let eika = Complex::expi(k.re * a);
+    let k_over_q = k / q;
+    let c_per_t = eika * (Complex::real(1.0) + k_over_q) * (Complex::i() * q * -a).exp() * 0.5;
+    let d_per_t = eika * (Complex::real(1.0) - k_over_q) * (Complex::i() * q * a).exp() * 0.5;
+    let q_over_k = q / k;
+    let denom = c_per_t * (Complex::real(1.0) + q_over_k) + d_per_t * (Complex::real(1.0) - q_over_k);
+    let t = Complex::real(2.0) / denom;
+    let c = c_per_t * t;
+    let d = d_per_t * t;
+    let r = c + d - Complex::real(1.0);
+
+    let wavelength = 2.0 * std::f64::consts::PI / k.re;
+    let margin = a + 2.0 * wavelength;
+    let x_min = -margin;
+    let x_max = a + margin;
+
+    let mut x = Vec::with_capacity(num_points);
+    let mut probability_density = Vec::with_capacity(num_points);
+    let mut potential = Vec::with_capacity(num_points);
+    for i in 0..num_points {
+        let xi = x_min + (x_max - x_min) * i as f64 / (num_points - 1) as f64;
+        let psi = if xi < 0.0 {
+            Complex::expi(k.re * xi) + r * Complex::expi(-k.re * xi)
+        } else if xi <= a {
+            c * (Complex::i() * q * xi).exp() + d * (Complex::i() * q * -xi).exp()
+        } else {
+            t * Complex::expi(k.re * xi)
+        };
+        x.push(xi);
+        probability_density.push(psi.norm_sqr());
+        potential.push(if (0.0..=a).contains(&xi) { barrier_ev } else { 0.0 });
+    }
+
+    let transmission = t.norm_sqr();
+    TunnelingResult {
+        x,
+        probability_density,
+        potential,
+        transmission,
+        reflection: r.norm_sqr(),
+    }
+}
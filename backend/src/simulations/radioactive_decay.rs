@@ -0,0 +1,196 @@
+//! Radioactive decay chains
+
+use serde_json::Value;
+
+use crate::models::quiz::{Question, Quiz};
+use crate::simulations::params::Params;
+use crate::simulations::{Derived, Simulation, SimulationError, SimulationRegistry, output};
+
+/// Number of time bins across the run
+const TIME_BINS: usize = 200;
+
+pub fn register(registry: &mut SimulationRegistry) {
+    registry.register(RadioactiveDecay);
+}
+
+pub struct RadioactiveDecay;
+
+impl Simulation for RadioactiveDecay {
+    fn id(&self) -> &'static str {
+        "radioactive-decay"
+    }
+
+    fn quiz(&self) -> Option<Quiz> {
+        Some(Quiz {
+            simulation_id: "radioactive-decay".to_string(),
+            title: "Radioactive Decay Quiz".to_string(),
+            questions: vec![
+                Question::numeric(
+                    "remaining",
+                    "What fraction of a large sample remains after three half-lives?",
+                    "",
+                    0.125,
+                    0.01,
+                    "Each half-life halves the sample: (1/2)³ = 1/8 = 0.125.",
+                )
+                .with_hints(&[
+                    "Each half-life leaves half of what was there.",
+                    "Multiply ½ by itself three times.",
+                ]),
+                Question::numeric(
+                    "counting_error",
+                    "A detector counts 400 decays in a minute. What is the relative statistical uncertainty of that count?",
+                    "%",
+                    5.0,
+                    0.02,
+                    "The uncertainty is √400 = 20 counts, which is 20/400 = 5%.",
+                )
+                .with_hints(&[
+                    "Decay counts follow Poisson statistics: the standard deviation is √N.",
+                    "Divide √400 by 400 and express it as a percentage.",
+                ]),
+                Question::multiple_choice(
+                    "single_nucleus",
+                    "A nucleus with a 10-minute half-life has survived for an hour. What is the chance it decays in the next 10 minutes?",
+                    &["Almost certain, it is overdue", "50%", "Less than 50%, it has proved stable", "Zero"],
+                    1,
+                    "Nuclei do not age: the chance of decaying in the next half-life is always 50%.",
+                )
+                .with_hints(&[
+                    "Does a nucleus carry a memory of how long it has existed?",
+                    "Decay is memoryless: the probability per unit time is always the same constant λ.",
+                ]),
+            ],
+        })
+    }
+
+    fn units(&self) -> &'static [(&'static str, &'static str)] {
+        &[("t", "min"), ("bin_centers", "min")]
+    }
+
+    fn derived(&self, _params: &Params, data: &Value) -> Vec<Derived> {
+        vec![
+            Derived::new(
+                "decay_constant",
+                "Decay Constant",
+                output(data, "/decay_constant"),
+                "1/min",
+                r"\lambda = \ln 2 / t_{1/2}",
+            ),
+            Derived::new(
+                "mean_lifetime",
+                "Mean Lifetime",
+                output(data, "/mean_lifetime"),
+                "min",
+                r"\tau = 1/\lambda = t_{1/2} / \ln 2",
+            ),
+        ]
+    }
+
+    fn run(&self, params: &Params) -> Result<Value, SimulationError> {
+        let initial_nuclei = params.i64("initial_nuclei") as usize;
+        let half_life = params.f64("half_life");
+        let daughter_half_life = params.f64("daughter_half_life");
+        let duration = params.f64("duration") * half_life;
+
+        let parent_rate = std::f64::consts::LN_2 / half_life;
+        let daughter_rate = (daughter_half_life > 0.0).then(|| std::f64::consts::LN_2 / daughter_half_life);
+
+        // One realization: each nucleus's parent decay time, then the
+        // daughter's own lifetime on top if it is radioactive
+        let mut mc = params.monte_carlo();
+        let mut parent_decays = Vec::with_capacity(initial_nuclei);
+        let mut daughter_decays = Vec::with_capacity(initial_nuclei);
+        for _ in 0..initial_nuclei {
+            let t = mc.exponential(parent_rate);
+            parent_decays.push(t);
+            if let Some(rate) = daughter_rate {
+                daughter_decays.push(t + mc.exponential(rate));
+            }
+        }
+        parent_decays.sort_by(f64::total_cmp);
+        daughter_decays.sort_by(f64::total_cmp);
+        let decayed_by = |times: &[f64], t: f64| times.partition_point(|&d| d <= t) as f64;
+
+        let dt = duration / TIME_BINS as f64;
+        let times: Vec<f64> = (0..=TIME_BINS).map(|i| i as f64 * dt).collect();
+        let n0 = initial_nuclei as f64;
+
+        let mut parent = Vec::with_capacity(times.len());
+        let mut daughter = Vec::with_capacity(times.len());
+        let mut parent_mc = Vec::with_capacity(times.len());
+        let mut daughter_mc = Vec::with_capacity(times.len());
+        for &t in &times {
+            parent.push(n0 * (-parent_rate * t).exp());
+            daughter.push(n0 * bateman_daughter(parent_rate, daughter_rate, t));
+            let parent_gone = decayed_by(&parent_decays, t);
+            parent_mc.push(n0 - parent_gone);
+            daughter_mc.push(match daughter_rate {
+                Some(_) => parent_gone - decayed_by(&daughter_decays, t),
+                None => parent_gone,
+            });
+        }
+
+        // Parent decays counted in each bin, against the expected count
+        let counts: Vec<f64> = times
+            .windows(2)
+            .map(|w| decayed_by(&parent_decays, w[1]) - decayed_by(&parent_decays, w[0]))
+            .collect();
+        let expected: Vec<f64> = times
+            .windows(2)
+            .map(|w| n0 * ((-parent_rate * w[0]).exp() - (-parent_rate * w[1]).exp()))
+            .collect();
+        let within_one_sigma = counts
+            .iter()
+            .zip(&expected)
+            .filter(|(c, e)| (*c - *e).abs() <= e.sqrt())
+            .count() as f64
+            / TIME_BINS as f64;
+
+        let mut data = serde_json::json!({
+            "t": times,
+            "parent": parent,
+            "daughter": daughter,
+            "parent_mc": parent_mc,
+            "daughter_mc": daughter_mc,
+            "bin_centers": times.windows(2).map(|w| (w[0] + w[1]) / 2.0).collect::<Vec<_>>(),
+            "decays_per_bin": counts,
+            "decays_per_bin_error": counts.iter().map(|c| c.sqrt()).collect::<Vec<_>>(),
+            "expected_decays_per_bin": expected,
+            "within_one_sigma": within_one_sigma,
+            "decay_constant": parent_rate,
+            "mean_lifetime": 1.0 / parent_rate,
+            "chain": daughter_rate.is_some(),
+            "seed": mc.seed(),
+            "initial_nuclei": initial_nuclei,
+            "half_life": half_life,
+            "daughter_half_life": daughter_half_life,
+        });
+        if daughter_rate.is_some() {
+            // Stable end product of the chain
+            data["granddaughter"] = parent
+                .iter()
+                .zip(&daughter)
+                .map(|(a, b)| n0 - a - b)
+                .collect::<Vec<_>>()
+                .into();
+            data["granddaughter_mc"] = times
+                .iter()
+                .map(|&t| decayed_by(&daughter_decays, t))
+                .collect::<Vec<_>>()
+                .into();
+        }
+        Ok(data)
+    }
+}
+
+/// Daughter population per initial parent nucleus at time `t`. A stable
+/// daughter simply accumulates the parent's decays.
+fn bateman_daughter(parent_rate: f64, daughter_rate: Option<f64>, t: f64) -> f64 {
+    let parent_left = (-parent_rate * t).exp();
+    match daughter_rate {
+        None => 1.0 - parent_left,
+        Some(rate) if (rate - parent_rate).abs() < 1e-12 * parent_rate => parent_rate * t * parent_left,
+        Some(rate) => parent_rate / (rate - parent_rate) * (parent_left - (-rate * t).exp()),
+    }
+}
@@ -0,0 +1,254 @@
+//! Geometric optics: rays through refracting interfaces and lenses
+
+use serde_json::Value;
+
+use crate::models::quiz::{Question, Quiz};
+use crate::numerics::roots::bisect;
+use crate::physics::optics::{OpticalSystem, RayStatus, Surface};
+use crate::simulations::params::Params;
+use crate::simulations::{Derived, Simulation, SimulationError, SimulationRegistry, output};
+
+/// Largest clear aperture half-height (mm)
+const APERTURE: f64 = 25.0;
+/// Apertures stay inside this fraction of a surface's radius
+const APERTURE_FRACTION: f64 = 0.9;
+/// Thinnest lens rim kept when the faces would otherwise meet, as a fraction
+/// of the centre thickness
+const MIN_EDGE_FRACTION: f64 = 0.1;
+/// Rays are fanned across this fraction of the first aperture
+const FILL_FRACTION: f64 = 0.9;
+/// Distance traced past the last surface when there is no real image (mm)
+const DEFAULT_TRAIL: f64 = 300.0;
+/// Longest distance traced past the last surface (mm)
+const MAX_TRAIL: f64 = 2000.0;
+
+pub fn register(registry: &mut SimulationRegistry) {
+    registry.register(RayOptics);
+}
+
+pub struct RayOptics;
+
+impl Simulation for RayOptics {
+    fn id(&self) -> &'static str {
+        "ray-optics"
+    }
+
+    fn quiz(&self) -> Option<Quiz> {
+        Some(Quiz {
+            simulation_id: "ray-optics".to_string(),
+            title: "Refraction and Lenses Quiz".to_string(),
+            questions: vec![
+                Question::numeric(
+                    "image_distance",
+                    "An object stands 300 mm in front of a thin lens with a focal length of 100 mm. How far behind the lens is the image?",
+                    "mm",
+                    150.0,
+                    0.02,
+                    "1/s_i = 1/100 − 1/300 = 2/300, so s_i = 150 mm.",
+                )
+                .with_hints(&[
+                    "The thin lens equation is 1/f = 1/s_o + 1/s_i.",
+                    "Solve for 1/s_i = 1/100 − 1/300 in mm⁻¹.",
+                ]),
+                Question::numeric(
+                    "lensmaker",
+                    "A thin symmetric biconvex lens with n = 1.5 has R₁ = 100 mm and R₂ = −100 mm. What is its focal length?",
+                    "mm",
+                    100.0,
+                    0.02,
+                    "1/f = 0.5 × (1/100 + 1/100) = 1/100, so f = 100 mm.",
+                )
+                .with_hints(&[
+                    "The lensmaker's equation is 1/f = (n − 1)(1/R₁ − 1/R₂).",
+                    "With R₂ = −100 mm, the bracket is 1/100 + 1/100.",
+                ]),
+                Question::multiple_choice(
+                    "inside_focus",
+                    "An object is placed closer to a converging lens than its focal length. What image forms?",
+                    &["A real, inverted image", "A virtual, upright, magnified image", "No image at all", "A real image at the focal point"],
+                    1,
+                    "The rays diverge after the lens, so they only appear to come from an upright, enlarged virtual image: a magnifying glass.",
+                )
+                .with_hints(&[
+                    "Trace the ray through the focal point: can it be drawn for an object inside f?",
+                    "After the lens the rays diverge. Where do they seem to come from?",
+                ]),
+            ],
+        })
+    }
+
+    fn derived(&self, _params: &Params, data: &Value) -> Vec<Derived> {
+        vec![
+            Derived::new(
+                "focal_length",
+                "Effective Focal Length",
+                output(data, "/focal_length"),
+                "mm",
+                r"f = -n_{\mathrm{out}} / C,\quad \begin{pmatrix} A & B \\ C & D \end{pmatrix} = \prod_i M_i",
+            ),
+            Derived::new(
+                "image_distance",
+                "Image Distance from the Last Surface",
+                output(data, "/image/distance"),
+                "mm",
+                r"s' = -n_{\mathrm{out}} \frac{A u + B}{C u + D},\quad u = s / n_{\mathrm{in}}",
+            ),
+            Derived::new(
+                "magnification",
+                "Lateral Magnification",
+                output(data, "/image/magnification"),
+                "",
+                r"m = A + s' C / n_{\mathrm{out}}",
+            ),
+        ]
+    }
+
+    fn run(&self, params: &Params) -> Result<Value, SimulationError> {
+        let system = build_system(params);
+        let object_distance = params.f64("object_distance");
+        let object_height = params.f64("object_height");
+        let num_rays = params.i64("num_rays") as usize;
+        let first = system.surfaces[0];
+        let last_vertex = system.surfaces.last().map_or(0.0, |s| s.vertex);
+        let n_in = system.initial_index;
+        let n_out = system.final_index();
+
+        // Paraxial imaging with s and s' measured from the first and last
+        // vertices: the object-to-image matrix has B = 0
+        let [[a, b], [c, d]] = system.system_matrix();
+        let u = object_distance / n_in;
+        let denominator = c * u + d;
+        let image_distance = (denominator.abs() > 1e-12).then(|| -n_out * (a * u + b) / denominator);
+        let magnification = image_distance.map(|s| a + s * c / n_out);
+        let focal_length = (c != 0.0).then(|| -n_out / c);
+        let back_focal_distance = (c != 0.0).then(|| -n_out * a / c);
+
+        let trail = match image_distance {
+            Some(s) if s > 0.0 => (1.3 * s).min(MAX_TRAIL),
+            _ => match back_focal_distance {
+                Some(f) if f > 0.0 => (1.3 * f).clamp(DEFAULT_TRAIL, MAX_TRAIL),
+                _ => DEFAULT_TRAIL,
+            },
+        };
+        let end = last_vertex + trail;
+
+        // Fan of rays from the object tip aimed across the first aperture
+        let object_x = first.vertex - object_distance;
+        let fan = |i: usize| -> f64 {
+            if num_rays == 1 {
+                return 0.0;
+            }
+            FILL_FRACTION * first.aperture * (2.0 * i as f64 / (num_rays - 1) as f64 - 1.0)
+        };
+        let object_rays: Vec<_> = (0..num_rays)
+            .map(|i| system.trace([object_x, object_height], [object_distance, fan(i) - object_height], end))
+            .collect();
+
+        // Parallel bundle from the left to locate the focus of each zone
+        let start = first.vertex - 0.2 * object_distance.min(DEFAULT_TRAIL);
+        let parallel_rays: Vec<_> = (0..num_rays)
+            .map(|i| system.trace([start, fan(i)], [1.0, 0.0], end))
+            .collect();
+        let marginal_focus = parallel_rays
+            .iter()
+            .filter(|r| r.status == RayStatus::Transmitted)
+            .max_by(|p, q| p.points[0][1].abs().total_cmp(&q.points[0][1].abs()))
+            .and_then(|r| r.axis_crossing());
+
+        // Where the traced rays actually converge: the plane at the paraxial
+        // image and the spread of heights there
+        let traced_heights: Vec<f64> = image_distance
+            .filter(|&s| s > 0.0)
+            .map(|s| {
+                object_rays
+                    .iter()
+                    .filter(|r| r.status == RayStatus::Transmitted)
+                    .filter_map(|r| r.height_at(last_vertex + s))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let blur = traced_heights.iter().copied().fold(f64::NEG_INFINITY, f64::max)
+            - traced_heights.iter().copied().fold(f64::INFINITY, f64::min);
+
+        Ok(serde_json::json!({
+            "surfaces": system
+                .surfaces
+                .iter()
+                .map(|s| serde_json::json!({
+                    "vertex": s.vertex,
+                    "radius": s.radius,
+                    "aperture": s.aperture,
+                    "index_after": s.index_after,
+                }))
+                .collect::<Vec<_>>(),
+            "initial_index": n_in,
+            "object": { "x": object_x, "height": object_height },
+            "rays": object_rays,
+            "parallel_rays": parallel_rays,
+            "system_matrix": [[a, b], [c, d]],
+            "focal_length": focal_length,
+            "back_focal_point": back_focal_distance.map(|f| last_vertex + f),
+            "marginal_focus": marginal_focus,
+            "image": image_distance.map(|s| serde_json::json!({
+                "x": last_vertex + s,
+                "distance": s,
+                "height": magnification.map(|m| m * object_height),
+                "magnification": magnification,
+                "real": s > 0.0,
+            })),
+            "image_blur": if traced_heights.len() > 1 { Some(blur) } else { None },
+        }))
+    }
+}
+
+/// Surfaces for the chosen setup, with the first vertex at x = 0
+fn build_system(params: &Params) -> OpticalSystem {
+    let medium = params.f64("medium_index");
+    let aperture = |radius: f64| {
+        if radius == 0.0 {
+            APERTURE
+        } else {
+            APERTURE.min(APERTURE_FRACTION * radius.abs())
+        }
+    };
+
+    let surfaces = match params.str("setup") {
+        "interface" => {
+            let radius = params.f64("interface_radius");
+            vec![Surface { vertex: 0.0, radius, aperture: aperture(radius), index_after: params.f64("interface_index") }]
+        }
+        setup => {
+            let (r1, r2) = (params.f64("lens_radius_1"), params.f64("lens_radius_2"));
+            let thickness = params.f64("lens_thickness");
+            let lens_index = params.f64("lens_index");
+            // Both faces share the smaller aperture, shrunk further if a thin
+            // convex lens would otherwise have its faces cross before the rim
+            let edge_thickness = |h: f64| thickness + sag(r2, h) - sag(r1, h) - MIN_EDGE_FRACTION * thickness;
+            let mut edge = aperture(r1).min(aperture(r2));
+            if edge_thickness(edge) < 0.0 {
+                edge = bisect(edge_thickness, 0.0, edge, 1e-9);
+            }
+            let lens = |front: f64| {
+                [
+                    Surface { vertex: front, radius: r1, aperture: edge, index_after: lens_index },
+                    Surface { vertex: front + thickness, radius: r2, aperture: edge, index_after: medium },
+                ]
+            };
+            let mut surfaces = lens(0.0).to_vec();
+            if setup == "two_lenses" {
+                surfaces.extend(lens(thickness + params.f64("lens_separation")));
+            }
+            surfaces
+        }
+    };
+    OpticalSystem { initial_index: medium, surfaces }
+}
+
+/// Axial distance from the vertex to a surface of radius `radius` at height `h`
+fn sag(radius: f64, h: f64) -> f64 {
+    if radius == 0.0 {
+        0.0
+    } else {
+        radius - radius.signum() * (radius * radius - h * h).sqrt()
+    }
+}
@@ -0,0 +1,373 @@
+//! Series RC and RLC circuit transients and frequency response
+
+use serde_json::Value;
+
+use crate::models::quiz::{Question, Quiz};
+use crate::numerics::complex::Complex;
+use crate::numerics::ode::rk4_step;
+use crate::physics::constants::{KILO, MICRO, MILLI};
+use crate::simulations::params::Params;
+use crate::simulations::{Derived, Simulation, SimulationError, SimulationRegistry, Validation, output, series};
+
+/// Integration steps per shortest time scale of the circuit or source
+const STEPS_PER_TIME_SCALE: f64 = 50.0;
+/// Upper bound on integration steps per run
+const MAX_STEPS: usize = 500_000;
+/// Number of samples returned per time series
+const OUTPUT_SAMPLES: usize = 1000;
+/// Bode plot range (Hz), log-spaced
+const BODE_RANGE: (f64, f64) = (1.0, 1e6);
+const BODE_POINTS: usize = 300;
+/// Root separation, relative to the decay rate R/2L, below which the step
+/// response is taken as critically damped
+const CRITICAL_SPLIT: f64 = 1e-6;
+
+pub fn register(registry: &mut SimulationRegistry) {
+    registry.register(RlcCircuit);
+}
+
+pub struct RlcCircuit;
+
+impl Simulation for RlcCircuit {
+    fn id(&self) -> &'static str {
+        "rlc-circuit"
+    }
+
+    fn quiz(&self) -> Option<Quiz> {
+        Some(Quiz {
+            simulation_id: "rlc-circuit".to_string(),
+            title: "RC and RLC Circuits Quiz".to_string(),
+            questions: vec![
+                Question::numeric(
+                    "time_constant",
+                    "What is the time constant of a 1 kΩ resistor in series with a 10 μF capacitor?",
+                    "ms",
+                    10.0,
+                    0.01,
+                    "τ = RC = 1000 × 10 × 10⁻⁶ = 0.01 s = 10 ms.",
+                )
+                .with_hints(&[
+                    "For an RC circuit τ = RC.",
+                    "10 μF is 10 × 10⁻⁶ F. Give the answer in milliseconds.",
+                ]),
+                Question::numeric(
+                    "resonance",
+                    "What is the resonant frequency of a 10 mH inductor with a 10 μF capacitor?",
+                    "Hz",
+                    503.3,
+                    0.02,
+                    "f₀ = 1/(2π√(LC)) = 1/(2π√(10⁻⁷)) ≈ 503 Hz.",
+                )
+                .with_hints(&[
+                    "Resonance is where the inductive and capacitive reactances are equal: ωL = 1/ωC.",
+                    "f₀ = 1/(2π√(LC)) with L = 10⁻² H and C = 10⁻⁵ F.",
+                ]),
+                Question::multiple_choice(
+                    "resonance_current",
+                    "At resonance, what limits the current in a series RLC circuit?",
+                    &["The inductance", "The capacitance", "Only the resistance", "Nothing, it is infinite"],
+                    2,
+                    "At resonance ωL = 1/ωC, so the reactances cancel and Z = R.",
+                )
+                .with_hints(&[
+                    "The impedance is Z = √(R² + (ωL − 1/ωC)²).",
+                    "What is left of Z when ωL = 1/ωC?",
+                ]),
+            ],
+        })
+    }
+
+    fn units(&self) -> &'static [(&'static str, &'static str)] {
+        &[
+            ("t", "ms"),
+            ("source_voltage", "V"),
+            ("current", "mA"),
+            ("capacitor_voltage", "V"),
+            ("resistor_voltage", "V"),
+            ("inductor_voltage", "V"),
+            ("bode.frequency", "Hz"),
+            ("bode.capacitor_gain_db", "dB"),
+            ("bode.capacitor_phase_degrees", "°"),
+        ]
+    }
+
+    fn derived(&self, _params: &Params, data: &Value) -> Vec<Derived> {
+        vec![
+            Derived::new(
+                "natural_frequency",
+                "Natural Frequency",
+                output(data, "/natural_frequency"),
+                "Hz",
+                r"f_0 = \frac{1}{2\pi\sqrt{LC}}",
+            ),
+            Derived::new(
+                "damping_ratio",
+                "Damping Ratio",
+                output(data, "/damping_ratio"),
+                "",
+                r"\zeta = \frac{R}{2} \sqrt{C / L}",
+            ),
+            Derived::new(
+                "quality_factor",
+                "Quality Factor",
+                output(data, "/quality_factor"),
+                "",
+                r"Q = \frac{1}{R} \sqrt{L / C}",
+            ),
+            Derived::new(
+                "time_constant",
+                "Time Constant",
+                output(data, "/time_constant"),
+                "ms",
+                r"\tau = 2L / R\ \text{(RLC)},\quad \tau = RC\ \text{(RC)}",
+            ),
+        ]
+    }
+
+    /// A step source switched onto the uncharged circuit has a closed-form
+    /// response; sine sources are not compared
+    fn validate(&self, params: &Params, data: &Value) -> Vec<Validation> {
+        if params.str("source_type") == "sine" {
+            return Vec::new();
+        }
+        let circuit = Circuit::new(params);
+        let amplitude = params.f64("source_voltage");
+        let (voltage, current): (Vec<f64>, Vec<f64>) = series(data, "/t")
+            .iter()
+            .map(|t| {
+                let (voltage, current) = circuit.step_response(amplitude, t / KILO);
+                (voltage, current * KILO)
+            })
+            .unzip();
+        let (voltage_formula, current_formula) = if circuit.inductance > 0.0 {
+            (
+                r"V_C = V\left(1 - \frac{s_2 e^{s_1 t} - s_1 e^{s_2 t}}{s_2 - s_1}\right),\quad s_{1,2} = -\frac{R}{2L} \pm \sqrt{\frac{R^2}{4L^2} - \frac{1}{LC}}",
+                r"I = \frac{V}{L} \frac{e^{s_2 t} - e^{s_1 t}}{s_2 - s_1}",
+            )
+        } else {
+            (r"V_C = V\left(1 - e^{-t/RC}\right)", r"I = \frac{V}{R} e^{-t/RC}")
+        };
+        [
+            Validation::compare("capacitor_voltage", voltage_formula, "V", &series(data, "/capacitor_voltage"), &voltage),
+            Validation::compare("current", current_formula, "mA", &series(data, "/current"), &current),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    fn run(&self, params: &Params) -> Result<Value, SimulationError> {
+        let circuit = Circuit::new(params);
+        let sine = params.str("source_type") == "sine";
+        let amplitude = params.f64("source_voltage");
+        let frequency = params.f64("source_frequency");
+        let duration = params.f64("duration") * MILLI;
+        let omega = std::f64::consts::TAU * frequency;
+        let source = |t: f64| if sine { amplitude * (omega * t).sin() } else { amplitude };
+
+        let response = circuit.simulate(&source, sine.then_some(frequency), duration);
+
+        let mut data = serde_json::json!({
+            "t": response.t.iter().map(|t| t * KILO).collect::<Vec<_>>(),
+            "source_voltage": response.t.iter().map(|&t| source(t)).collect::<Vec<_>>(),
+            "current": response.current.iter().map(|i| i * KILO).collect::<Vec<_>>(),
+            "capacitor_voltage": response.charge.iter().map(|q| q / circuit.capacitance).collect::<Vec<_>>(),
+            "resistor_voltage": response.current.iter().map(|i| i * circuit.resistance).collect::<Vec<_>>(),
+            "inductor_voltage": response.inductor_voltage,
+            "time_constant": circuit.time_constant() * KILO,
+            "bode": circuit.bode(),
+            "resistance": circuit.resistance,
+            "inductance": params.f64("inductance"),
+            "capacitance": params.f64("capacitance"),
+            "source_type": params.str("source_type"),
+            "source_frequency": frequency,
+        });
+        if circuit.inductance > 0.0 {
+            data["natural_frequency"] = circuit.natural_frequency().into();
+            data["quality_factor"] = circuit.quality_factor().into();
+            data["damping_ratio"] = circuit.damping_ratio().into();
+            data["damping_regime"] = circuit.damping_regime().into();
+        } else {
+            data["cutoff_frequency"] = (1.0 / (std::f64::consts::TAU * circuit.time_constant())).into();
+        }
+        if sine {
+            let z = circuit.impedance(omega);
+            let current_amplitude = amplitude / z.abs();
+            // Current leads the source by −arg Z
+            let phase = -z.im.atan2(z.re);
+            data["steady_state"] = serde_json::json!({
+                "impedance": z.abs(),
+                "phase_degrees": phase.to_degrees(),
+                "current_amplitude": current_amplitude * KILO,
+                "capacitor_voltage_amplitude": current_amplitude / (omega * circuit.capacitance),
+                "current": response
+                    .t
+                    .iter()
+                    .map(|&t| current_amplitude * (omega * t + phase).sin() * KILO)
+                    .collect::<Vec<_>>(),
+            });
+        }
+        Ok(data)
+    }
+}
+
+/// Series R, L, C in SI units; L = 0 gives an RC circuit
+struct Circuit {
+    resistance: f64,
+    inductance: f64,
+    capacitance: f64,
+}
+
+struct Response {
+    t: Vec<f64>,
+    charge: Vec<f64>,
+    current: Vec<f64>,
+    inductor_voltage: Vec<f64>,
+}
+
+impl Circuit {
+    fn new(params: &Params) -> Self {
+        Self {
+            resistance: params.f64("resistance"),
+            inductance: params.f64("inductance") * MILLI,
+            capacitance: params.f64("capacitance") * MICRO,
+        }
+    }
+
+    /// Exact capacitor voltage and current `t` seconds after a constant
+    /// `amplitude` is switched on, from an uncharged, currentless start
+    fn step_response(&self, amplitude: f64, t: f64) -> (f64, f64) {
+        let (r, l, c) = (self.resistance, self.inductance, self.capacitance);
+        if l == 0.0 {
+            let decay = (-t / (r * c)).exp();
+            return (amplitude * (1.0 - decay), amplitude / r * decay);
+        }
+        let alpha = r / (2.0 * l);
+        let split = Complex::real(alpha * alpha - 1.0 / (l * c)).sqrt();
+        if split.abs() < CRITICAL_SPLIT * alpha {
+            // Repeated root: the distinct-root forms below lose all precision
+            let decay = (-alpha * t).exp();
+            return (amplitude * (1.0 - decay * (1.0 + alpha * t)), amplitude / l * t * decay);
+        }
+        // s₁,₂ = −α ± √(α² − ω₀²), complex for an underdamped circuit
+        let s1 = Complex::real(-alpha) + split;
+        let s2 = Complex::real(-alpha) - split;
+        let (e1, e2) = ((s1 * t).exp(), (s2 * t).exp());
+        let gap = s2 - s1;
+        let voltage = ((s2 * e1 - s1 * e2) / gap).re;
+        let current = ((e2 - e1) / gap).re;
+        (amplitude * (1.0 - voltage), amplitude / l * current)
+    }
+
+    fn time_constant(&self) -> f64 {
+        if self.inductance > 0.0 {
+            // Decay time of the transient envelope
+            2.0 * self.inductance / self.resistance
+        } else {
+            self.resistance * self.capacitance
+        }
+    }
+
+    fn natural_frequency(&self) -> f64 {
+        1.0 / (std::f64::consts::TAU * (self.inductance * self.capacitance).sqrt())
+    }
+
+    fn quality_factor(&self) -> f64 {
+        (self.inductance / self.capacitance).sqrt() / self.resistance
+    }
+
+    fn damping_ratio(&self) -> f64 {
+        self.resistance / 2.0 * (self.capacitance / self.inductance).sqrt()
+    }
+
+    fn damping_regime(&self) -> &'static str {
+        let zeta = self.damping_ratio();
+        if (zeta - 1.0).abs() < 1e-3 {
+            "critically_damped"
+        } else if zeta < 1.0 {
+            "underdamped"
+        } else {
+            "overdamped"
+        }
+    }
+
+    fn impedance(&self, omega: f64) -> Complex {
+        Complex::new(self.resistance, omega * self.inductance - 1.0 / (omega * self.capacitance))
+    }
+
+    /// Integrate from an uncharged, currentless start. State is [q, i].
+    fn simulate(&self, source: &impl Fn(f64) -> f64, frequency: Option<f64>, duration: f64) -> Response {
+        let (r, l, c) = (self.resistance, self.inductance, self.capacitance);
+        let derivative = |t: f64, s: &[f64; 2]| -> [f64; 2] {
+            if l > 0.0 {
+                [s[1], (source(t) - r * s[1] - s[0] / c) / l]
+            } else {
+                [(source(t) - s[0] / c) / r, 0.0]
+            }
+        };
+        // In an RC circuit the current follows from the charge
+        let current = |t: f64, s: &[f64; 2]| if l > 0.0 { s[1] } else { (source(t) - s[0] / c) / r };
+
+        // Resolve the fastest of the decay time, the natural period and the
+        // source period
+        let mut shortest = self.time_constant().min(duration);
+        if l > 0.0 {
+            shortest = shortest.min(1.0 / self.natural_frequency());
+            // Overdamped circuits also have a fast mode of rate ≈ R/L
+            shortest = shortest.min(l / r);
+        }
+        if let Some(f) = frequency {
+            shortest = shortest.min(1.0 / f);
+        }
+        let steps = ((duration / shortest * STEPS_PER_TIME_SCALE).ceil() as usize).clamp(OUTPUT_SAMPLES, MAX_STEPS);
+        let dt = duration / steps as f64;
+        let stride = steps.div_ceil(OUTPUT_SAMPLES).max(1);
+
+        let mut response = Response {
+            t: Vec::with_capacity(OUTPUT_SAMPLES + 1),
+            charge: Vec::with_capacity(OUTPUT_SAMPLES + 1),
+            current: Vec::with_capacity(OUTPUT_SAMPLES + 1),
+            inductor_voltage: Vec::with_capacity(OUTPUT_SAMPLES + 1),
+        };
+        let mut state = [0.0, 0.0];
+        for step in 0..=steps {
+            let t = step as f64 * dt;
+            if step % stride == 0 || step == steps {
+                let i = current(t, &state);
+                response.t.push(t);
+                response.charge.push(state[0]);
+                response.current.push(i);
+                // Whatever the source drives beyond R and C appears across L
+                response.inductor_voltage.push(source(t) - r * i - state[0] / c);
+            }
+            state = rk4_step(derivative, t, &state, dt);
+        }
+        response
+    }
+
+    /// Frequency response of the capacitor voltage and of the current, for
+    /// a unit source amplitude
+    fn bode(&self) -> Value {
+        let (start, stop) = BODE_RANGE;
+        let frequencies: Vec<f64> = (0..BODE_POINTS)
+            .map(|i| start * (stop / start).powf(i as f64 / (BODE_POINTS - 1) as f64))
+            .collect();
+        let mut gain_db = Vec::with_capacity(BODE_POINTS);
+        let mut phase_degrees = Vec::with_capacity(BODE_POINTS);
+        let mut current = Vec::with_capacity(BODE_POINTS);
+        for &f in &frequencies {
+            let omega = std::f64::consts::TAU * f;
+            // V_C / V = 1 / (iωC Z)
+            let transfer = Complex::real(1.0) / (Complex::new(0.0, omega * self.capacitance) * self.impedance(omega));
+            gain_db.push(20.0 * transfer.abs().log10());
+            phase_degrees.push(transfer.im.atan2(transfer.re).to_degrees());
+            current.push(KILO / self.impedance(omega).abs());
+        }
+        serde_json::json!({
+            "frequency": frequencies,
+            "capacitor_gain_db": gain_db,
+            "capacitor_phase_degrees": phase_degrees,
+            "current_per_volt": current,
+        })
+    }
+}
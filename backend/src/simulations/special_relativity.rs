@@ -0,0 +1,207 @@
+//! Special relativity: time dilation, length contraction and Minkowski diagrams
+
+use serde_json::Value;
+
+use crate::models::quiz::{Question, Quiz};
+use crate::physics::constants::SPEED_OF_LIGHT;
+use crate::simulations::params::Params;
+use crate::simulations::{Derived, Simulation, SimulationError, SimulationRegistry, output};
+
+/// Points in the velocity sweep, from rest up to the fastest slider speed
+const SWEEP_POINTS: usize = 200;
+const MAX_BETA: f64 = 0.999;
+/// Minkowski diagram extent in light-years (x) and years (ct)
+const DIAGRAM_EXTENT: f64 = 10.0;
+
+pub fn register(registry: &mut SimulationRegistry) {
+    registry.register(SpecialRelativity);
+}
+
+pub struct SpecialRelativity;
+
+impl Simulation for SpecialRelativity {
+    fn id(&self) -> &'static str {
+        "special-relativity"
+    }
+
+    fn quiz(&self) -> Option<Quiz> {
+        Some(Quiz {
+            simulation_id: "special-relativity".to_string(),
+            title: "Special Relativity Quiz".to_string(),
+            questions: vec![
+                Question::numeric(
+                    "gamma",
+                    "What is the Lorentz factor γ at 0.6c?",
+                    "",
+                    1.25,
+                    0.01,
+                    "γ = 1/√(1 − 0.36) = 1/0.8 = 1.25.",
+                )
+                .with_hints(&[
+                    "γ = 1/√(1 − v²/c²).",
+                    "At 0.6c, v²/c² = 0.36.",
+                ]),
+                Question::numeric(
+                    "dilation",
+                    "A traveller at 0.8c ages 6 years on a round trip. How many years pass on Earth?",
+                    "years",
+                    10.0,
+                    0.01,
+                    "At 0.8c, γ = 1/0.6 ≈ 1.67, so Earth time is γΔτ = 6/0.6 = 10 years.",
+                )
+                .with_hints(&[
+                    "The traveller's own clock shows the proper time Δτ.",
+                    "Earth time is γΔτ. Work out γ at 0.8c first.",
+                ]),
+                Question::multiple_choice(
+                    "simultaneity",
+                    "Two events are simultaneous on Earth but far apart. For a passing spaceship they are:",
+                    &["Also simultaneous", "Generally not simultaneous", "The same event", "Impossible to order"],
+                    1,
+                    "Lines of simultaneity tilt in the moving frame, so spatially separated events that are simultaneous in one frame happen at different times in another.",
+                )
+                .with_hints(&[
+                    "Draw both events in a spacetime diagram with the Earth frame's lines of equal time.",
+                    "How do the spaceship's lines of equal time look in the same diagram?",
+                ]),
+            ],
+        })
+    }
+
+    fn units(&self) -> &'static [(&'static str, &'static str)] {
+        &[
+            ("sweep.beta", "c"),
+            ("sweep.time_dilation", "yr"),
+            ("sweep.length_contraction", "m"),
+            ("sweep.momentum", "mc"),
+            ("sweep.classical_momentum", "mc"),
+            ("sweep.kinetic_energy", "mc²"),
+            ("sweep.classical_kinetic_energy", "mc²"),
+        ]
+    }
+
+    fn prerequisites(&self) -> &'static [&'static str] {
+        &["projectile-motion"]
+    }
+
+    fn derived(&self, _params: &Params, data: &Value) -> Vec<Derived> {
+        vec![
+            Derived::new(
+                "gamma",
+                "Lorentz Factor",
+                output(data, "/gamma"),
+                "",
+                r"\gamma = \frac{1}{\sqrt{1 - v^2/c^2}}",
+            ),
+            Derived::new(
+                "earth_time",
+                "Trip Time on Earth",
+                output(data, "/earth_time"),
+                "yr",
+                r"\Delta t = \gamma \Delta\tau",
+            ),
+            Derived::new(
+                "contracted_length",
+                "Ship Length Seen from Earth",
+                output(data, "/contracted_length"),
+                "m",
+                r"L = L_0 / \gamma",
+            ),
+            Derived::new(
+                "kinetic_energy",
+                "Kinetic Energy",
+                output(data, "/kinetic_energy"),
+                "J",
+                r"K = (\gamma - 1) m c^2",
+            ),
+        ]
+    }
+
+    fn run(&self, params: &Params) -> Result<Value, SimulationError> {
+        let beta = params.f64("velocity");
+        let proper_time = params.f64("proper_time");
+        let proper_length = params.f64("proper_length");
+        let rest_mass = params.f64("rest_mass");
+
+        let gamma = lorentz_factor(beta);
+        let rest_energy = rest_mass * SPEED_OF_LIGHT * SPEED_OF_LIGHT;
+
+        let betas: Vec<f64> = (0..SWEEP_POINTS)
+            .map(|i| MAX_BETA * i as f64 / (SWEEP_POINTS - 1) as f64)
+            .collect();
+        let gammas: Vec<f64> = betas.iter().map(|&b| lorentz_factor(b)).collect();
+
+        Ok(serde_json::json!({
+            "gamma": gamma,
+            "earth_time": gamma * proper_time,
+            "distance_travelled": gamma * beta * proper_time,
+            "contracted_length": proper_length / gamma,
+            "momentum": gamma * rest_mass * beta * SPEED_OF_LIGHT,
+            "total_energy": gamma * rest_energy,
+            "kinetic_energy": (gamma - 1.0) * rest_energy,
+            "classical_kinetic_energy": 0.5 * beta * beta * rest_energy,
+            "sweep": {
+                "beta": betas,
+                "gamma": gammas,
+                "time_dilation": gammas.iter().map(|g| g * proper_time).collect::<Vec<_>>(),
+                "length_contraction": gammas.iter().map(|g| proper_length / g).collect::<Vec<_>>(),
+                // Momentum in units of mc and energy in units of mc²
+                "momentum": betas.iter().zip(&gammas).map(|(b, g)| g * b).collect::<Vec<_>>(),
+                "classical_momentum": betas.clone(),
+                "kinetic_energy": gammas.iter().map(|g| g - 1.0).collect::<Vec<_>>(),
+                "classical_kinetic_energy": betas.iter().map(|b| 0.5 * b * b).collect::<Vec<_>>(),
+            },
+            "minkowski": minkowski_diagram(beta, gamma),
+            "velocity": beta,
+            "proper_time": proper_time,
+            "proper_length": proper_length,
+            "rest_mass": rest_mass,
+        }))
+    }
+}
+
+fn lorentz_factor(beta: f64) -> f64 {
+    1.0 / (1.0 - beta * beta).sqrt()
+}
+
+/// Lines for a spacetime diagram drawn in Earth's frame, with x in
+/// light-years and ct in years. Each line is given by its two end points.
+fn minkowski_diagram(beta: f64, gamma: f64) -> Value {
+    let line = |name: &str, x: [f64; 2], ct: [f64; 2]| serde_json::json!({ "name": name, "x": x, "ct": ct });
+    let e = DIAGRAM_EXTENT;
+
+    let worldlines = vec![
+        line("earth", [0.0, 0.0], [0.0, e]),
+        line("ship", [0.0, beta * e], [0.0, e]),
+        line("light_forward", [0.0, e], [0.0, e]),
+        line("light_backward", [0.0, -e], [0.0, e]),
+    ];
+
+    // Whole years of ship time: ct = βx + ct'/γ. Earth's own lines of
+    // simultaneity are the horizontals ct = const.
+    let years = e as usize;
+    let ship_simultaneity: Vec<Value> = (0..=years)
+        .map(|k| {
+            let ct_prime = k as f64;
+            line(&format!("ship_t{k}"), [-e, e], [ct_prime / gamma - beta * e, ct_prime / gamma + beta * e])
+        })
+        .collect();
+    let earth_simultaneity: Vec<Value> = (0..=years)
+        .map(|k| line(&format!("earth_t{k}"), [-e, e], [k as f64, k as f64]))
+        .collect();
+
+    // Ticks of the ship's clock along its worldline, one per year of proper time
+    let ship_ticks: Vec<Value> = (0..=years)
+        .map(|k| k as f64)
+        .take_while(|tau| gamma * tau <= e)
+        .map(|tau| serde_json::json!({ "proper_time": tau, "x": gamma * beta * tau, "ct": gamma * tau }))
+        .collect();
+
+    serde_json::json!({
+        "extent": e,
+        "worldlines": worldlines,
+        "ship_simultaneity": ship_simultaneity,
+        "earth_simultaneity": earth_simultaneity,
+        "ship_ticks": ship_ticks,
+    })
+}
@@ -0,0 +1,241 @@
+//! Bound states of an electron in an infinite or finite square well
+
+use serde_json::Value;
+
+use crate::models::quiz::{Question, Quiz};
+use crate::numerics::roots::bisect;
+use crate::physics::constants::ELECTRON_KINETIC;
+use crate::simulations::params::Params;
+use crate::simulations::{Derived, Simulation, SimulationError, SimulationRegistry, output};
+
+const GRID_POINTS: usize = 500;
+/// Absolute tolerance on z = ka/2 when solving for a bound state
+const ROOT_TOLERANCE: f64 = 1e-12;
+/// Points in the bound-state count vs. depth curve, up to twice the depth
+const DEPTH_SCAN_POINTS: usize = 200;
+
+pub fn register(registry: &mut SimulationRegistry) {
+    registry.register(SquareWell);
+}
+
+pub struct SquareWell;
+
+impl Simulation for SquareWell {
+    fn id(&self) -> &'static str {
+        "square-well"
+    }
+
+    fn quiz(&self) -> Option<Quiz> {
+        Some(Quiz {
+            simulation_id: "square-well".to_string(),
+            title: "Particle in a Box Quiz".to_string(),
+            questions: vec![
+                Question::numeric(
+                    "level_ratio",
+                    "In an infinite well, how many times higher is the energy of the n = 3 level than the ground state?",
+                    "",
+                    9.0,
+                    0.01,
+                    "Energies scale as n², so E₃/E₁ = 9.",
+                )
+                .with_hints(&[
+                    "In an infinite well Eₙ ∝ n².",
+                    "Compare n = 3 with n = 1.",
+                ]),
+                Question::multiple_choice(
+                    "finite_lower",
+                    "How do the levels of a finite well compare with those of an infinite well of the same width?",
+                    &["Higher", "Lower", "Identical", "Randomly shifted"],
+                    1,
+                    "Leaking into the walls lets the wave function spread out, so its wavelength is longer and its energy lower.",
+                )
+                .with_hints(&[
+                    "In a finite well the wave function does not vanish at the walls: it leaks into them.",
+                    "A wave function spread over more room can have a longer wavelength. What does that do to the kinetic energy?",
+                ]),
+                Question::multiple_choice(
+                    "one_state",
+                    "A 1D finite square well is made extremely shallow. How many bound states remain?",
+                    &["None", "Always at least one", "Exactly two", "Infinitely many"],
+                    1,
+                    "In one dimension any attractive well has at least one even bound state.",
+                )
+                .with_hints(&[
+                    "Try it in the simulation: make the well shallower and shallower.",
+                    "In one dimension the even ground state can always fit, however shallow the well is. Is the same true in three dimensions?",
+                ]),
+            ],
+        })
+    }
+
+    fn units(&self) -> &'static [(&'static str, &'static str)] {
+        &[("x", "nm"), ("potential", "eV"), ("depth_scan", "eV")]
+    }
+
+    fn prerequisites(&self) -> &'static [&'static str] {
+        &["standing-waves", "double-slit"]
+    }
+
+    fn derived(&self, _params: &Params, data: &Value) -> Vec<Derived> {
+        vec![
+            Derived::new(
+                "ground_state_energy",
+                "Ground-State Energy",
+                output(data, "/states/0/energy"),
+                "eV",
+                r"E_1:\ \text{lowest root of the matching condition}",
+            ),
+            Derived::new(
+                "infinite_ground_state_energy",
+                "Ground-State Energy of an Infinite Well",
+                output(data, "/infinite_levels/0"),
+                "eV",
+                r"E_1 = \frac{\pi^2 \hbar^2}{2 m L^2}",
+            ),
+            Derived::new(
+                "well_strength",
+                "Well Strength",
+                output(data, "/well_strength"),
+                "",
+                r"z_0 = \frac{L}{2\hbar} \sqrt{2 m V_0}",
+            ),
+        ]
+    }
+
+    fn run(&self, params: &Params) -> Result<Value, SimulationError> {
+        let finite = params.str("well_type") == "finite";
+        let width = params.f64("width");
+        let depth = params.f64("depth");
+        let max_states = params.i64("max_states") as usize;
+
+        let infinite_levels: Vec<f64> = (1..=max_states)
+            .map(|n| ELECTRON_KINETIC * (n as f64 * std::f64::consts::PI / width).powi(2))
+            .collect();
+
+        if !finite {
+            let half_extent = 0.75 * width;
+            let x = grid(half_extent);
+            let states: Vec<Value> = infinite_levels
+                .iter()
+                .enumerate()
+                .map(|(i, &energy)| {
+                    let n = (i + 1) as f64;
+                    let psi: Vec<f64> = x
+                        .iter()
+                        .map(|&xi| {
+                            if xi.abs() < width / 2.0 {
+                                (2.0 / width).sqrt() * (n * std::f64::consts::PI * (xi + width / 2.0) / width).sin()
+                            } else {
+                                0.0
+                            }
+                        })
+                        .collect();
+                    serde_json::json!({ "n": i + 1, "energy": energy, "psi": psi })
+                })
+                .collect();
+            return Ok(serde_json::json!({
+                "well_type": "infinite",
+                "x": x,
+                "states": states,
+                "infinite_levels": infinite_levels,
+                "width": width,
+            }));
+        }
+
+        let z0 = well_strength(width, depth);
+        let bound_count = bound_state_count(z0);
+        let roots: Vec<f64> = (0..bound_count.min(max_states)).map(|n| solve_bound_state(n, z0)).collect();
+
+        // Show the walls out to three decay lengths of the least bound state
+        let kappa_min = roots.last().map_or(1.0, |&z| 2.0 * (z0 * z0 - z * z).sqrt() / width);
+        let half_extent = width / 2.0 + (3.0 / kappa_min).min(2.0 * width);
+        let x = grid(half_extent);
+        let dx = x[1] - x[0];
+
+        let states: Vec<Value> = roots
+            .iter()
+            .enumerate()
+            .map(|(n, &z)| {
+                let k = 2.0 * z / width;
+                let kappa = 2.0 * (z0 * z0 - z * z).sqrt() / width;
+                let even = n.is_multiple_of(2);
+                let inside = |xi: f64| if even { (k * xi).cos() } else { (k * xi).sin() };
+                let edge = inside(width / 2.0);
+                let mut psi: Vec<f64> = x
+                    .iter()
+                    .map(|&xi| {
+                        if xi.abs() <= width / 2.0 {
+                            inside(xi)
+                        } else {
+                            // Continue the interior value at the wall, odd states flipping sign on the left
+                            let sign = if !even && xi < 0.0 { -1.0 } else { 1.0 };
+                            sign * edge * (-kappa * (xi.abs() - width / 2.0)).exp()
+                        }
+                    })
+                    .collect();
+                let norm = (psi.iter().map(|p| p * p).sum::<f64>() * dx).sqrt();
+                psi.iter_mut().for_each(|p| *p /= norm);
+                let energy = ELECTRON_KINETIC * k * k;
+                serde_json::json!({
+                    "n": n + 1,
+                    "parity": if even { "even" } else { "odd" },
+                    "energy": energy,
+                    "binding_energy": depth - energy,
+                    "penetration_depth": 1.0 / kappa,
+                    "psi": psi,
+                })
+            })
+            .collect();
+
+        let depth_scan: Vec<f64> = (1..=DEPTH_SCAN_POINTS)
+            .map(|i| 2.0 * depth * i as f64 / DEPTH_SCAN_POINTS as f64)
+            .collect();
+        let counts: Vec<usize> = depth_scan.iter().map(|&v| bound_state_count(well_strength(width, v))).collect();
+
+        Ok(serde_json::json!({
+            "well_type": "finite",
+            "x": x,
+            "potential": x.iter().map(|xi| if xi.abs() <= width / 2.0 { 0.0 } else { depth }).collect::<Vec<_>>(),
+            "states": states,
+            "bound_state_count": bound_count,
+            "infinite_levels": infinite_levels,
+            "well_strength": z0,
+            "depth_scan": depth_scan,
+            "bound_states_vs_depth": counts,
+            "width": width,
+            "depth": depth,
+        }))
+    }
+}
+
+fn grid(half_extent: f64) -> Vec<f64> {
+    let dx = 2.0 * half_extent / (GRID_POINTS - 1) as f64;
+    (0..GRID_POINTS).map(|i| -half_extent + i as f64 * dx).collect()
+}
+
+/// Dimensionless well strength z₀ = (a/2)√(2mV₀)/ħ
+fn well_strength(width: f64, depth: f64) -> f64 {
+    width / 2.0 * (depth / ELECTRON_KINETIC).sqrt()
+}
+
+fn bound_state_count(z0: f64) -> usize {
+    (2.0 * z0 / std::f64::consts::PI).ceil().max(1.0) as usize
+}
+
+/// z = ka/2 for the n-th bound state (from 0), which lies in
+/// [nπ/2, (n+1)π/2] and below z₀. The matching conditions are multiplied
+/// through by cos z or sin z so they stay continuous on the bracket.
+fn solve_bound_state(n: usize, z0: f64) -> f64 {
+    let even = n.is_multiple_of(2);
+    let f = |z: f64| {
+        let outside = (z0 * z0 - z * z).max(0.0).sqrt();
+        if even {
+            z * z.sin() - outside * z.cos()
+        } else {
+            z * z.cos() + outside * z.sin()
+        }
+    };
+    let lo = n as f64 * std::f64::consts::FRAC_PI_2;
+    let hi = ((n + 1) as f64 * std::f64::consts::FRAC_PI_2).min(z0);
+    bisect(f, lo, hi, ROOT_TOLERANCE)
+}
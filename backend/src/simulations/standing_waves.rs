@@ -0,0 +1,289 @@
+//! Standing waves on a driven string
+
+use serde_json::Value;
+
+use crate::models::quiz::{Question, Quiz};
+use crate::numerics::complex::Complex;
+use crate::physics::constants::{KILO, MILLI};
+use crate::simulations::params::Params;
+use crate::simulations::{Derived, FrameStream, Simulation, SimulationError, SimulationRegistry, output};
+
+const GRID_POINTS: usize = 401;
+/// Number of points per frame sent to the client
+const OUTPUT_POINTS: usize = 101;
+/// Time step as a fraction of the CFL limit dx/c
+const COURANT: f64 = 0.9;
+/// Amplitude of the driver at the left end (m)
+const DRIVE_AMPLITUDE: f64 = 1e-3;
+/// Number of normal modes returned
+const NUM_MODES: usize = 5;
+/// The resonance curve spans 0 to this many fundamental frequencies
+const RESPONSE_RANGE: f64 = 5.5;
+const RESPONSE_POINTS: usize = 550;
+
+pub fn register(registry: &mut SimulationRegistry) {
+    registry.register(StandingWaves);
+}
+
+pub struct StandingWaves;
+
+impl Simulation for StandingWaves {
+    fn id(&self) -> &'static str {
+        "standing-waves"
+    }
+
+    fn quiz(&self) -> Option<Quiz> {
+        Some(Quiz {
+            simulation_id: "standing-waves".to_string(),
+            title: "Standing Waves Quiz".to_string(),
+            questions: vec![
+                Question::numeric(
+                    "fundamental",
+                    "A 1 m string carries waves at 200 m/s. What is its fundamental frequency?",
+                    "Hz",
+                    100.0,
+                    0.01,
+                    "f₁ = c/2L = 200/2 = 100 Hz.",
+                )
+                .with_hints(&[
+                    "In the fundamental mode, half a wavelength fits on the string.",
+                    "λ = 2L, and f = c/λ.",
+                ]),
+                Question::multiple_choice(
+                    "tension",
+                    "What happens to the natural frequencies if the tension is quadrupled?",
+                    &["They halve", "They double", "They quadruple", "They do not change"],
+                    1,
+                    "c = √(T/μ) doubles when T is quadrupled, and every fₙ = nc/2L doubles with it.",
+                )
+                .with_hints(&[
+                    "The wave speed on a string is c = √(T/μ).",
+                    "The frequencies are fₙ = nc/2L, so they scale with c.",
+                ]),
+                Question::numeric(
+                    "nodes",
+                    "How many nodes does the third mode have, counting both fixed ends?",
+                    "nodes",
+                    4.0,
+                    0.0,
+                    "Mode n fits n half-wavelengths and has n + 1 nodes including the ends, so the third mode has 4.",
+                )
+                .with_hints(&[
+                    "Mode n fits n half-wavelengths on the string.",
+                    "Draw the third mode and count the points that never move, including both ends.",
+                ]),
+            ],
+        })
+    }
+
+    fn units(&self) -> &'static [(&'static str, &'static str)] {
+        &[("x", "m"), ("times", "s"), ("response_frequencies", "Hz")]
+    }
+
+    fn derived(&self, _params: &Params, data: &Value) -> Vec<Derived> {
+        vec![
+            Derived::new(
+                "wave_speed",
+                "Wave Speed",
+                output(data, "/wave_speed"),
+                "m/s",
+                r"v = \sqrt{T / \mu}",
+            ),
+            Derived::new(
+                "fundamental_frequency",
+                "Fundamental Frequency",
+                output(data, "/fundamental_frequency"),
+                "Hz",
+                r"f_1 = \frac{v}{2L}",
+            ),
+            Derived::new(
+                "fundamental_wavelength",
+                "Fundamental Wavelength",
+                output(data, "/modes/0/wavelength"),
+                "m",
+                r"\lambda_1 = 2L",
+            ),
+        ]
+    }
+
+    fn run(&self, params: &Params) -> Result<Value, SimulationError> {
+        let mut stream = StringStream::new(params);
+        let mut times = vec![stream.time()];
+        let mut frames = vec![stream.displacement()];
+        while stream.advance() {
+            times.push(stream.time());
+            frames.push(stream.displacement());
+        }
+
+        let mut data = stream.header();
+        data["times"] = times.into();
+        data["frames"] = frames.into();
+        Ok(data)
+    }
+
+    fn stream(&self, params: &Params) -> Result<Option<Box<dyn FrameStream>>, SimulationError> {
+        Ok(Some(Box::new(StringStream::new(params))))
+    }
+}
+
+/// Finite-difference time stepping of a damped string driven at x = 0 and
+/// fixed at x = L, one output frame at a time. SI units throughout.
+struct StringStream {
+    /// Displacement now and one time step earlier
+    y: Vec<f64>,
+    y_prev: Vec<f64>,
+    length: f64,
+    wave_speed: f64,
+    driving_frequency: f64,
+    damping: f64,
+    dx: f64,
+    dt: f64,
+    step: usize,
+    steps_per_frame: usize,
+    num_frames: usize,
+    frame: usize,
+}
+
+impl StringStream {
+    fn new(params: &Params) -> Self {
+        let length = params.f64("length");
+        let tension = params.f64("tension");
+        let linear_density = params.f64("linear_density") * MILLI;
+        let num_frames = params.i64("num_frames") as usize;
+
+        let wave_speed = (tension / linear_density).sqrt();
+        let dx = length / (GRID_POINTS - 1) as f64;
+        let total_time = params.f64("duration") * 2.0 * length / wave_speed;
+        let steps_per_frame = (total_time / num_frames as f64 / (COURANT * dx / wave_speed)).ceil() as usize;
+        let dt = total_time / (num_frames * steps_per_frame) as f64;
+
+        Self {
+            y: vec![0.0; GRID_POINTS],
+            y_prev: vec![0.0; GRID_POINTS],
+            length,
+            wave_speed,
+            driving_frequency: params.f64("driving_frequency"),
+            damping: params.f64("damping"),
+            dx,
+            dt,
+            step: 0,
+            steps_per_frame,
+            num_frames,
+            frame: 0,
+        }
+    }
+
+    /// One leapfrog step of the damped wave equation
+    fn step(&mut self) {
+        let r2 = (self.wave_speed * self.dt / self.dx).powi(2);
+        let drag = self.damping * self.dt / 2.0;
+        let mut next = vec![0.0; GRID_POINTS];
+        for (i, value) in next.iter_mut().enumerate().take(GRID_POINTS - 1).skip(1) {
+            let laplacian = self.y[i + 1] - 2.0 * self.y[i] + self.y[i - 1];
+            *value = (2.0 * self.y[i] - (1.0 - drag) * self.y_prev[i] + r2 * laplacian) / (1.0 + drag);
+        }
+        self.step += 1;
+        next[0] = DRIVE_AMPLITUDE * (std::f64::consts::TAU * self.driving_frequency * self.time_at(self.step)).sin();
+        self.y_prev = std::mem::replace(&mut self.y, next);
+    }
+
+    /// Step forward to the next output frame; false once the run is over
+    fn advance(&mut self) -> bool {
+        if self.frame >= self.num_frames {
+            return false;
+        }
+        for _ in 0..self.steps_per_frame {
+            self.step();
+        }
+        self.frame += 1;
+        true
+    }
+
+    fn time_at(&self, step: usize) -> f64 {
+        step as f64 * self.dt
+    }
+
+    fn time(&self) -> f64 {
+        self.time_at(self.step)
+    }
+
+    fn stride(&self) -> usize {
+        (GRID_POINTS - 1) / (OUTPUT_POINTS - 1)
+    }
+
+    /// Displacement (mm) downsampled for output
+    fn displacement(&self) -> Vec<f64> {
+        self.y.iter().step_by(self.stride()).map(|y| y * KILO).collect()
+    }
+
+    fn fundamental(&self) -> f64 {
+        self.wave_speed / (2.0 * self.length)
+    }
+
+    /// Steady-state amplitude profile relative to the driver, |Y(x)|/A with
+    /// Y(x) = A sin(k(L − x))/sin(kL) and complex k² = (ω² − iγω)/c²
+    fn steady_state_profile(&self, frequency: f64, xs: &[f64]) -> Vec<f64> {
+        let omega = std::f64::consts::TAU * frequency;
+        let k = Complex::new(omega * omega, -self.damping * omega).sqrt() * (1.0 / self.wave_speed);
+        let denominator = (k * self.length).sin();
+        xs.iter()
+            .map(|&x| ((k * (self.length - x)).sin() / denominator).abs())
+            .collect()
+    }
+}
+
+impl FrameStream for StringStream {
+    fn header(&self) -> Value {
+        let xs: Vec<f64> = self.y.iter().enumerate().step_by(self.stride()).map(|(i, _)| i as f64 * self.dx).collect();
+        let f1 = self.fundamental();
+
+        let modes: Vec<Value> = (1..=NUM_MODES)
+            .map(|n| {
+                let k = n as f64 * std::f64::consts::PI / self.length;
+                serde_json::json!({
+                    "n": n,
+                    "frequency": n as f64 * f1,
+                    "wavelength": 2.0 * self.length / n as f64,
+                    "shape": xs.iter().map(|x| (k * x).sin()).collect::<Vec<_>>(),
+                })
+            })
+            .collect();
+
+        let response_frequencies: Vec<f64> = (1..=RESPONSE_POINTS)
+            .map(|i| RESPONSE_RANGE * f1 * i as f64 / RESPONSE_POINTS as f64)
+            .collect();
+        let response: Vec<f64> = response_frequencies
+            .iter()
+            .map(|&f| self.steady_state_profile(f, &xs).into_iter().fold(0.0, f64::max))
+            .collect();
+
+        serde_json::json!({
+            "x": xs,
+            "wave_speed": self.wave_speed,
+            "fundamental_frequency": f1,
+            "modes": modes,
+            "steady_state_amplitude": self
+                .steady_state_profile(self.driving_frequency, &xs)
+                .iter()
+                .map(|a| a * DRIVE_AMPLITUDE * KILO)
+                .collect::<Vec<_>>(),
+            "response_frequencies": response_frequencies,
+            "response": response,
+            "num_frames": self.num_frames,
+            "length": self.length,
+            "driving_frequency": self.driving_frequency,
+            "damping": self.damping,
+        })
+    }
+
+    fn next_frame(&mut self) -> Option<Value> {
+        if !self.advance() {
+            return None;
+        }
+        Some(serde_json::json!({
+            "index": self.frame,
+            "time": self.time(),
+            "displacement": self.displacement(),
+        }))
+    }
+}
@@ -0,0 +1,157 @@
+//! Sequential Stern–Gerlach measurements on spin-½ particles
+
+use serde_json::Value;
+
+use crate::models::quiz::{Question, Quiz};
+use crate::simulations::params::Params;
+use crate::simulations::{Derived, Simulation, SimulationError, SimulationRegistry, output};
+
+/// Number of apparatus slots the content file defines settings for; a larger
+/// count in the catalog is capped here
+const MAX_STAGES: usize = 5;
+/// Orientations closer than this (radians) count as the same axis
+const ALIGNMENT_TOLERANCE: f64 = 1e-9;
+
+pub fn register(registry: &mut SimulationRegistry) {
+    registry.register(SternGerlach);
+}
+
+pub struct SternGerlach;
+
+impl Simulation for SternGerlach {
+    fn id(&self) -> &'static str {
+        "stern-gerlach"
+    }
+
+    fn quiz(&self) -> Option<Quiz> {
+        Some(Quiz {
+            simulation_id: "stern-gerlach".to_string(),
+            title: "Stern–Gerlach Quiz".to_string(),
+            questions: vec![
+                Question::numeric(
+                    "z_x_z",
+                    "Atoms selected spin-up along z, then spin-up along x, enter a final z magnet. What fraction of them exits spin-down along z?",
+                    "",
+                    0.5,
+                    0.01,
+                    "The x measurement leaves the atoms in an x eigenstate, which is an equal mix of z-up and z-down.",
+                )
+                .with_hints(&[
+                    "After the x magnet, do the atoms still remember that they were z-up?",
+                    "An x eigenstate written in the z basis is (|↑⟩ ± |↓⟩)/√2.",
+                ]),
+                Question::numeric(
+                    "angle",
+                    "Spin-up atoms along z enter a magnet tilted 60° from z. What fraction exits spin-up along the new axis?",
+                    "",
+                    0.75,
+                    0.01,
+                    "P = cos²(60°/2) = cos²30° = 0.75.",
+                )
+                .with_hints(&[
+                    "For spin ½ the probability to stay up along an axis tilted by θ is cos²(θ/2).",
+                    "Here θ = 60°, so use cos²30°.",
+                ]),
+                Question::multiple_choice(
+                    "repeat",
+                    "Atoms selected spin-up along z pass through a second z magnet. What happens?",
+                    &["Half go up, half go down", "All go up", "All go down", "The beam does not split at all"],
+                    1,
+                    "Repeating the same measurement always reproduces the previous result.",
+                )
+                .with_hints(&[
+                    "A measurement leaves the atom in the state it found.",
+                    "What would a second measurement of the same quantity find?",
+                ]),
+            ],
+        })
+    }
+
+    fn prerequisites(&self) -> &'static [&'static str] {
+        &["lorentz-force", "double-slit"]
+    }
+
+    fn derived(&self, _params: &Params, data: &Value) -> Vec<Derived> {
+        vec![Derived::new(
+            "transmitted",
+            "Fraction of Atoms Reaching the End",
+            output(data, "/transmitted"),
+            "",
+            r"P = \prod_i \cos^2\!\left(\frac{\Theta_i}{2}\right),\quad \Theta_i = \angle(\hat n_i, \hat n_{i-1})",
+        )]
+    }
+
+    fn run(&self, params: &Params) -> Result<Value, SimulationError> {
+        let stages = (params.i64("stages") as usize).min(MAX_STAGES);
+
+        // Each beam carries its path label, intensity and the axis its spin
+        // was last found up along (None while still unpolarized)
+        let mut beams: Vec<(String, f64, Option<[f64; 3]>)> = vec![(String::new(), 1.0, None)];
+        let mut stage_reports = Vec::with_capacity(stages);
+        let mut previous_axis: Option<[f64; 3]> = None;
+
+        for stage in 1..=stages {
+            let theta = params.f64(&format!("stage_{stage}_theta")).to_radians();
+            let phi = params.f64(&format!("stage_{stage}_phi")).to_radians();
+            let pass = params.str(&format!("stage_{stage}_pass")).to_string();
+            let axis = [theta.sin() * phi.cos(), theta.sin() * phi.sin(), theta.cos()];
+            let down_axis = axis.map(|a| -a);
+
+            let mut splits = Vec::with_capacity(beams.len());
+            let mut next = Vec::new();
+            for (path, intensity, state) in &beams {
+                let p_up = state.map_or(0.5, |s| (1.0 + dot(&s, &axis)) / 2.0);
+                let up = intensity * p_up;
+                let down = intensity * (1.0 - p_up);
+                splits.push(serde_json::json!({
+                    "path": path,
+                    "incoming": intensity,
+                    "up": up,
+                    "down": down,
+                }));
+                if pass != "down" {
+                    next.push((format!("{path}+"), up, Some(axis)));
+                }
+                if pass != "up" {
+                    next.push((format!("{path}-"), down, Some(down_axis)));
+                }
+            }
+            beams = next;
+
+            // Parallel or antiparallel axes measure the same observable
+            let compatible = previous_axis.map(|p| 1.0 - dot(&p, &axis).abs() < ALIGNMENT_TOLERANCE);
+            previous_axis = Some(axis);
+            stage_reports.push(serde_json::json!({
+                "index": stage,
+                "theta": theta.to_degrees(),
+                "phi": phi.to_degrees(),
+                "axis": axis,
+                "pass": pass,
+                "beams": splits,
+                "transmitted": beams.iter().map(|b| b.1).sum::<f64>(),
+                "compatible_with_previous": compatible,
+            }));
+        }
+
+        let final_beams: Vec<Value> = beams
+            .iter()
+            .map(|(path, intensity, state)| {
+                serde_json::json!({
+                    "path": path,
+                    "intensity": intensity,
+                    "spin_axis": state,
+                })
+            })
+            .collect();
+
+        Ok(serde_json::json!({
+            "stages": stage_reports,
+            "final_beams": final_beams,
+            "transmitted": beams.iter().map(|b| b.1).sum::<f64>(),
+        }))
+    }
+}
+
+fn dot(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
@@ -0,0 +1,281 @@
+//! Gaussian wave packet scattering off a barrier
+
+use serde_json::Value;
+
+use crate::models::quiz::{Question, Quiz};
+use crate::numerics::complex::Complex;
+use crate::numerics::schrodinger::CrankNicolson;
+use crate::physics::constants::{ELECTRON_KINETIC, REDUCED_PLANCK_EV_FS};
+use crate::simulations::params::Params;
+use crate::simulations::{Derived, FrameStream, Simulation, SimulationError, SimulationRegistry, output};
+
+/// Simulation box is [-DOMAIN_HALF_WIDTH, DOMAIN_HALF_WIDTH] in nm
+const DOMAIN_HALF_WIDTH: f64 = 40.0;
+const GRID_POINTS: usize = 4000;
+/// Packet starts this far to the left of the barrier (nm)
+const START_OFFSET: f64 = 15.0;
+/// Number of points per frame sent to the client
+const OUTPUT_POINTS: usize = 400;
+/// Upper bound on the time step (fs)
+const MAX_TIME_STEP: f64 = 0.01;
+
+pub fn register(registry: &mut SimulationRegistry) {
+    registry.register(WavePacket);
+}
+
+pub struct WavePacket;
+
+impl Simulation for WavePacket {
+    fn id(&self) -> &'static str {
+        "wave-packet"
+    }
+
+    fn quiz(&self) -> Option<Quiz> {
+        Some(Quiz {
+            simulation_id: "wave-packet".to_string(),
+            title: "Wave Packet Quiz".to_string(),
+            questions: vec![
+                Question::multiple_choice(
+                    "spreading",
+                    "Why does a free wave packet spread out as it travels?",
+                    &["Energy leaks out of the packet", "Its momentum components travel at different speeds", "The numerical grid is too coarse", "It is attracted by the barrier"],
+                    1,
+                    "The packet is a superposition of momenta. Each component moves at its own speed, so the packet disperses.",
+                )
+                .with_hints(&[
+                    "A packet is a sum of plane waves with a range of momenta.",
+                    "For a free particle the speed of each component depends on its momentum: v = ħk/m.",
+                ]),
+                Question::multiple_choice(
+                    "norm",
+                    "What happens to the total probability ∫|ψ|²dx as the packet scatters off the barrier?",
+                    &["It decreases as the packet tunnels", "It increases at the barrier", "It stays equal to 1", "It is split equally between both sides"],
+                    2,
+                    "The Schrödinger equation conserves probability. Crank–Nicolson preserves the norm exactly, so the transmitted and reflected parts always add up to 1.",
+                )
+                .with_hints(&[
+                    "The Schrödinger equation is unitary. What does that preserve?",
+                    "If the transmitted part grows, what must happen to the reflected part?",
+                ]),
+                Question::numeric(
+                    "de_broglie",
+                    "What is the de Broglie wavelength of an electron with kinetic energy 2 eV?",
+                    "nm",
+                    0.867,
+                    0.03,
+                    "k = √(E / (ħ²/2m)) = √(2 / 0.0381) nm⁻¹ ≈ 7.25 nm⁻¹, so λ = 2π/k ≈ 0.87 nm.",
+                )
+                .with_hints(&[
+                    "First find k from E = ħ²k²/2m, using ħ²/2m ≈ 0.0381 eV·nm² for an electron.",
+                    "Then λ = 2π/k.",
+                ]),
+            ],
+        })
+    }
+
+    fn units(&self) -> &'static [(&'static str, &'static str)] {
+        &[("x", "nm"), ("potential", "eV"), ("times_fs", "fs")]
+    }
+
+    fn prerequisites(&self) -> &'static [&'static str] {
+        &["double-slit"]
+    }
+
+    fn derived(&self, params: &Params, data: &Value) -> Vec<Derived> {
+        let wavenumber = (params.f64("packet_energy") / ELECTRON_KINETIC).sqrt();
+        vec![
+            Derived::new(
+                "wavenumber",
+                "Mean Wavenumber",
+                wavenumber,
+                "1/nm",
+                r"k_0 = \sqrt{2 m E} / \hbar",
+            ),
+            Derived::new(
+                "group_velocity",
+                "Group Velocity",
+                2.0 * ELECTRON_KINETIC * wavenumber / REDUCED_PLANCK_EV_FS,
+                "nm/fs",
+                r"v_g = \frac{\hbar k_0}{m} = \sqrt{2E / m}",
+            ),
+            Derived::new(
+                "transmission",
+                "Transmitted Probability",
+                output(data, "/transmission"),
+                "",
+                r"T = \int_{x > a} |\psi(x, t_{\mathrm{end}})|^2\, dx",
+            ),
+            Derived::new(
+                "reflection",
+                "Reflected Probability",
+                output(data, "/reflection"),
+                "",
+                r"R = \int_{x < 0} |\psi(x, t_{\mathrm{end}})|^2\, dx",
+            ),
+        ]
+    }
+
+    fn run(&self, params: &Params) -> Result<Value, SimulationError> {
+        let mut stream = WavePacketStream::new(params);
+        let mut times = vec![stream.time()];
+        let mut frames = vec![stream.density()];
+        while stream.advance() {
+            times.push(stream.time());
+            frames.push(stream.density());
+        }
+        let (transmission, reflection) = stream.split_probabilities();
+
+        let mut data = stream.header();
+        data["times_fs"] = times.into();
+        data["frames"] = frames.into();
+        data["transmission"] = transmission.into();
+        data["reflection"] = reflection.into();
+        Ok(data)
+    }
+
+    fn stream(&self, params: &Params) -> Result<Option<Box<dyn FrameStream>>, SimulationError> {
+        Ok(Some(Box::new(WavePacketStream::new(params))))
+    }
+}
+
+/// Crank–Nicolson propagation of a Gaussian packet toward a barrier at
+/// 0 ≤ x ≤ width, one output frame at a time.
+///
+/// Lengths are in nm, energies in eV and times in fs. The run lasts long
+/// enough for the packet centre to travel twice the start offset, so the
+/// reflected and transmitted parts are well separated by the last frame.
+struct WavePacketStream {
+    grid: Vec<f64>,
+    potential: Vec<f64>,
+    psi: Vec<Complex>,
+    propagator: CrankNicolson,
+    dx: f64,
+    dt: f64,
+    steps_per_frame: usize,
+    num_frames: usize,
+    frame: usize,
+    packet_energy: f64,
+    packet_width: f64,
+    barrier_height: f64,
+    barrier_width: f64,
+}
+
+impl WavePacketStream {
+    fn new(params: &Params) -> Self {
+        let packet_energy = params.f64("packet_energy");
+        let packet_width = params.f64("packet_width");
+        let barrier_height = params.f64("barrier_height");
+        let barrier_width = params.f64("barrier_width");
+        let num_frames = params.i64("num_frames") as usize;
+
+        let dx = 2.0 * DOMAIN_HALF_WIDTH / (GRID_POINTS - 1) as f64;
+        let grid: Vec<f64> = (0..GRID_POINTS)
+            .map(|i| -DOMAIN_HALF_WIDTH + i as f64 * dx)
+            .collect();
+        let potential: Vec<f64> = grid
+            .iter()
+            .map(|&x| if (0.0..=barrier_width).contains(&x) { barrier_height } else { 0.0 })
+            .collect();
+
+        // ψ(x, 0) = exp(−(x − x₀)²/4σ²) e^{ik₀x}, normalized on the grid
+        let k0 = (packet_energy / ELECTRON_KINETIC).sqrt();
+        let x0 = -START_OFFSET;
+        let sigma = packet_width;
+        let mut psi: Vec<Complex> = grid
+            .iter()
+            .map(|&x| Complex::expi(k0 * x) * (-(x - x0).powi(2) / (4.0 * sigma * sigma)).exp())
+            .collect();
+        let norm = (psi.iter().map(|p| p.norm_sqr()).sum::<f64>() * dx).sqrt();
+        psi.iter_mut().for_each(|p| *p = *p * (1.0 / norm));
+
+        // Group velocity v = 2(ħ²/2m)k₀/ħ in nm/fs
+        let velocity = 2.0 * ELECTRON_KINETIC * k0 / REDUCED_PLANCK_EV_FS;
+        let total_time = 2.0 * START_OFFSET / velocity;
+        let steps_per_frame = ((total_time / num_frames as f64) / MAX_TIME_STEP).ceil().max(1.0) as usize;
+        let dt = total_time / (num_frames * steps_per_frame) as f64;
+        let propagator = CrankNicolson::new(&potential, dx, dt, ELECTRON_KINETIC, REDUCED_PLANCK_EV_FS);
+
+        Self {
+            grid,
+            potential,
+            psi,
+            propagator,
+            dx,
+            dt,
+            steps_per_frame,
+            num_frames,
+            frame: 0,
+            packet_energy,
+            packet_width,
+            barrier_height,
+            barrier_width,
+        }
+    }
+
+    /// Step forward to the next output frame; false once the run is over
+    fn advance(&mut self) -> bool {
+        if self.frame >= self.num_frames {
+            return false;
+        }
+        for _ in 0..self.steps_per_frame {
+            self.propagator.step(&mut self.psi);
+        }
+        self.frame += 1;
+        true
+    }
+
+    fn time(&self) -> f64 {
+        (self.frame * self.steps_per_frame) as f64 * self.dt
+    }
+
+    fn stride(&self) -> usize {
+        GRID_POINTS / OUTPUT_POINTS
+    }
+
+    /// |ψ|² downsampled for output
+    fn density(&self) -> Vec<f64> {
+        self.psi.iter().step_by(self.stride()).map(|p| p.norm_sqr()).collect()
+    }
+
+    /// Probability to the right of the barrier and to the left of it
+    fn split_probabilities(&self) -> (f64, f64) {
+        let mut transmitted = 0.0;
+        let mut reflected = 0.0;
+        for (&x, p) in self.grid.iter().zip(&self.psi) {
+            if x > self.barrier_width {
+                transmitted += p.norm_sqr() * self.dx;
+            } else if x < 0.0 {
+                reflected += p.norm_sqr() * self.dx;
+            }
+        }
+        (transmitted, reflected)
+    }
+}
+
+impl FrameStream for WavePacketStream {
+    fn header(&self) -> Value {
+        serde_json::json!({
+            "x": self.grid.iter().step_by(self.stride()).collect::<Vec<_>>(),
+            "potential": self.potential.iter().step_by(self.stride()).collect::<Vec<_>>(),
+            "num_frames": self.num_frames,
+            "packet_energy": self.packet_energy,
+            "packet_width": self.packet_width,
+            "barrier_height": self.barrier_height,
+            "barrier_width": self.barrier_width,
+        })
+    }
+
+    fn next_frame(&mut self) -> Option<Value> {
+        if !self.advance() {
+            return None;
+        }
+        let (transmission, reflection) = self.split_probabilities();
+        Some(serde_json::json!({
+            "index": self.frame,
+            "time_fs": self.time(),
+            "density": self.density(),
+            "transmission": transmission,
+            "reflection": reflection,
+        }))
+    }
+}
@@ -0,0 +1,86 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use sqlx::SqlitePool;
+
+use crate::config::Config;
+use crate::services::achievements::Achievements;
+use crate::services::attestation::Attester;
+use crate::services::auth::{admin_emails, TokenKeys};
+use crate::services::cache::ResultCache;
+use crate::services::citation::Software;
+use crate::services::datasets::Datasets;
+use crate::services::embeddings::Embeddings;
+use crate::services::ipfs::Ipfs;
+use crate::services::jobs::JobQueue;
+use crate::services::lti::Lti;
+use crate::services::metrics::Metrics;
+use crate::services::rate_limit::RateLimiter;
+use crate::services::search::SearchIndex;
+use crate::services::tutor::Tutor;
+use crate::simulations::SimulationRegistry;
+
+/// Shared application state handed to every handler
+#[derive(Clone)]
+pub struct AppState {
+    /// Settings the server was started with
+    pub config: Arc<Config>,
+    pub simulations: Arc<SimulationRegistry>,
+    /// Experimental data runs can be compared with
+    pub datasets: Arc<Datasets>,
+    /// Badge definitions
+    pub achievements: Arc<Achievements>,
+    pub db: SqlitePool,
+    pub keys: Arc<TokenKeys>,
+    pub jobs: Arc<JobQueue>,
+    pub cache: Arc<ResultCache>,
+    pub lti: Arc<Lti>,
+    pub ipfs: Arc<Ipfs>,
+    /// Signs computed results
+    pub attester: Arc<Attester>,
+    /// How the server is cited
+    pub citation: Arc<Software>,
+    /// Request and simulation metrics, shared with the simulation registry
+    pub metrics: Arc<Metrics>,
+    /// Per-client request budgets
+    pub rate_limiter: Arc<RateLimiter>,
+    /// Full-text index of the catalog
+    pub search: Arc<SearchIndex>,
+    /// Passage vectors for semantic search and related simulations
+    pub embeddings: Arc<Embeddings>,
+    /// Language model the AI tutor asks
+    pub tutor: Arc<Tutor>,
+    /// Emails of administrator accounts
+    pub admins: Arc<HashSet<String>>,
+}
+
+impl AppState {
+    pub fn new(
+        config: Config,
+        simulations: SimulationRegistry,
+        datasets: Datasets,
+        achievements: Achievements,
+        db: SqlitePool,
+    ) -> Self {
+        Self {
+            metrics: simulations.metrics(),
+            simulations: Arc::new(simulations),
+            datasets: Arc::new(datasets),
+            achievements: Arc::new(achievements),
+            db,
+            keys: Arc::new(TokenKeys::from_config(&config.auth)),
+            jobs: Arc::new(JobQueue::default()),
+            cache: Arc::new(ResultCache::from_config(&config.cache)),
+            lti: Arc::new(Lti::from_config(&config.lti, &config.server)),
+            ipfs: Arc::new(Ipfs::from_config(&config.ipfs)),
+            attester: Arc::new(Attester::from_config(&config.attestation)),
+            citation: Arc::new(Software::from_config(&config.citation)),
+            rate_limiter: Arc::new(RateLimiter::from_config(&config.rate_limit)),
+            search: Arc::new(SearchIndex::default()),
+            embeddings: Arc::new(Embeddings::from_config(&config.embeddings)),
+            tutor: Arc::new(Tutor::from_config(&config.tutor)),
+            admins: Arc::new(admin_emails(&config.auth)),
+            config: Arc::new(config),
+        }
+    }
+}
@@ -0,0 +1,423 @@
+// Minimal RFC 6455 WebSocket support on top of hyper's connection upgrades.
+// Handles text/binary messages, fragmentation, ping/pong and close; no extensions.
+// Clients that break the framing rules are closed with status 1002.
+
+use axum::{
+    async_trait,
+    body::Body,
+    extract::FromRequestParts,
+    http::{header, request::Parts, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use hyper_util::rt::TokioIo;
+use sha1::{Digest, Sha1};
+use std::future::Future;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadHalf, WriteHalf};
+
+/// GUID appended to the client key when computing Sec-WebSocket-Accept
+const ACCEPT_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+/// Largest message accepted from a client
+const MAX_MESSAGE_SIZE: usize = 64 * 1024;
+/// Largest payload of a ping, pong or close frame
+const MAX_CONTROL_PAYLOAD: usize = 125;
+
+/// Close status for a normal closure
+const CLOSE_NORMAL: u16 = 1000;
+/// Close status for a client that broke the protocol
+const CLOSE_PROTOCOL_ERROR: u16 = 1002;
+
+const OPCODE_CONTINUATION: u8 = 0x0;
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_BINARY: u8 = 0x2;
+const OPCODE_CLOSE: u8 = 0x8;
+const OPCODE_PING: u8 = 0x9;
+const OPCODE_PONG: u8 = 0xA;
+
+type Upgraded = TokioIo<hyper::upgrade::Upgraded>;
+
+/// Extractor for a WebSocket upgrade request
+pub struct WebSocketUpgrade {
+    accept_key: HeaderValue,
+    on_upgrade: hyper::upgrade::OnUpgrade,
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for WebSocketUpgrade {
+    type Rejection = (StatusCode, &'static str);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let headers = &parts.headers;
+        if !header_contains(headers, header::CONNECTION, "upgrade")
+            || !header_contains(headers, header::UPGRADE, "websocket")
+        {
+            return Err((StatusCode::BAD_REQUEST, "expected a WebSocket upgrade request"));
+        }
+        if headers.get(header::SEC_WEBSOCKET_VERSION).map(|v| v.as_bytes()) != Some(b"13") {
+            return Err((StatusCode::BAD_REQUEST, "unsupported WebSocket version"));
+        }
+        let key = headers
+            .get(header::SEC_WEBSOCKET_KEY)
+            .ok_or((StatusCode::BAD_REQUEST, "missing Sec-WebSocket-Key"))?;
+
+        let mut hasher = Sha1::new();
+        hasher.update(key.as_bytes());
+        hasher.update(ACCEPT_GUID.as_bytes());
+        let accept_key = HeaderValue::from_str(&STANDARD.encode(hasher.finalize()))
+            .map_err(|_| (StatusCode::BAD_REQUEST, "invalid Sec-WebSocket-Key"))?;
+
+        let on_upgrade = parts
+            .extensions
+            .remove::<hyper::upgrade::OnUpgrade>()
+            .ok_or((StatusCode::BAD_REQUEST, "connection cannot be upgraded"))?;
+
+        Ok(Self { accept_key, on_upgrade })
+    }
+}
+
+impl WebSocketUpgrade {
+    /// Complete the handshake and run `callback` with the established socket
+    pub fn on_upgrade<F, Fut>(self, callback: F) -> Response
+    where
+        F: FnOnce(WebSocket) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let on_upgrade = self.on_upgrade;
+        tokio::spawn(async move {
+            match on_upgrade.await {
+                Ok(upgraded) => callback(WebSocket::new(TokioIo::new(upgraded))).await,
+                Err(e) => tracing::warn!("WebSocket upgrade failed: {}", e),
+            }
+        });
+
+        Response::builder()
+            .status(StatusCode::SWITCHING_PROTOCOLS)
+            .header(header::CONNECTION, "upgrade")
+            .header(header::UPGRADE, "websocket")
+            .header(header::SEC_WEBSOCKET_ACCEPT, self.accept_key)
+            .body(Body::empty())
+            .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
+    }
+}
+
+fn header_contains(headers: &HeaderMap, name: header::HeaderName, token: &str) -> bool {
+    headers
+        .get_all(name)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .any(|v| v.trim().eq_ignore_ascii_case(token))
+}
+
+/// A complete message received from the client
+#[derive(Debug)]
+pub enum Message {
+    Text(String),
+    /// Binary payloads are not used by the API and are discarded
+    Binary,
+    Ping(Vec<u8>),
+    Pong,
+    Close,
+    /// The client broke the framing rules; the connection should be closed
+    /// with status 1002, and nothing more is read from it
+    ProtocolError(&'static str),
+}
+
+/// An established WebSocket connection
+pub struct WebSocket {
+    reader: WebSocketReader,
+    writer: WebSocketWriter,
+}
+
+impl WebSocket {
+    fn new(io: Upgraded) -> Self {
+        let (read, write) = tokio::io::split(io);
+        Self {
+            reader: WebSocketReader::new(read),
+            writer: WebSocketWriter { io: write },
+        }
+    }
+
+    /// Split into halves that can be driven from separate tasks
+    pub fn split(self) -> (WebSocketReader, WebSocketWriter) {
+        (self.reader, self.writer)
+    }
+}
+
+/// Receiving half of a WebSocket
+pub struct WebSocketReader<R = ReadHalf<Upgraded>> {
+    io: R,
+    /// Opcode and payload so far of a fragmented message. Kept here rather
+    /// than in `recv` because control frames may arrive between fragments
+    /// (RFC 6455 §5.4) and are returned before the message is complete.
+    fragments: Option<(u8, Vec<u8>)>,
+    failed: bool,
+}
+
+impl<R: AsyncRead + Unpin> WebSocketReader<R> {
+    fn new(io: R) -> Self {
+        Self {
+            io,
+            fragments: None,
+            failed: false,
+        }
+    }
+
+    /// Read the next message, reassembling fragments. Returns `None` once the
+    /// connection is gone or after a protocol error has been reported.
+    pub async fn recv(&mut self) -> Option<Message> {
+        if self.failed {
+            return None;
+        }
+        match self.next_message().await {
+            Ok(message) => Some(message),
+            Err(FrameError::Io(e)) => {
+                tracing::debug!("WebSocket read ended: {}", e);
+                None
+            }
+            Err(FrameError::Protocol(reason)) => {
+                self.failed = true;
+                Some(Message::ProtocolError(reason))
+            }
+        }
+    }
+
+    async fn next_message(&mut self) -> Result<Message, FrameError> {
+        loop {
+            let (fin, opcode, payload) = read_frame(&mut self.io).await?;
+            match opcode {
+                OPCODE_PING => return Ok(Message::Ping(payload)),
+                OPCODE_PONG => return Ok(Message::Pong),
+                OPCODE_CLOSE => return Ok(Message::Close),
+                OPCODE_TEXT | OPCODE_BINARY if self.fragments.is_some() => {
+                    return Err(FrameError::Protocol("new message before the previous one was finished"));
+                }
+                OPCODE_TEXT | OPCODE_BINARY if fin => return to_message(opcode, payload),
+                OPCODE_TEXT | OPCODE_BINARY => self.fragments = Some((opcode, payload)),
+                OPCODE_CONTINUATION => {
+                    let (first_opcode, mut buffer) = self
+                        .fragments
+                        .take()
+                        .ok_or(FrameError::Protocol("continuation frame outside a fragmented message"))?;
+                    if buffer.len() + payload.len() > MAX_MESSAGE_SIZE {
+                        return Err(FrameError::Io(too_large()));
+                    }
+                    buffer.extend_from_slice(&payload);
+                    if fin {
+                        return to_message(first_opcode, buffer);
+                    }
+                    self.fragments = Some((first_opcode, buffer));
+                }
+                _ => return Err(FrameError::Protocol("unknown opcode")),
+            }
+        }
+    }
+}
+
+/// Why no message could be read: the connection failed, or the client sent
+/// frames that RFC 6455 forbids
+enum FrameError {
+    Io(std::io::Error),
+    Protocol(&'static str),
+}
+
+impl From<std::io::Error> for FrameError {
+    fn from(err: std::io::Error) -> Self {
+        FrameError::Io(err)
+    }
+}
+
+fn too_large() -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, "message too large")
+}
+
+fn to_message(opcode: u8, payload: Vec<u8>) -> Result<Message, FrameError> {
+    match opcode {
+        OPCODE_TEXT => String::from_utf8(payload)
+            .map(Message::Text)
+            .map_err(|_| FrameError::Io(std::io::Error::new(std::io::ErrorKind::InvalidData, "text is not UTF-8"))),
+        _ => Ok(Message::Binary),
+    }
+}
+
+async fn read_frame<R: AsyncRead + Unpin>(io: &mut R) -> Result<(bool, u8, Vec<u8>), FrameError> {
+    let mut header = [0u8; 2];
+    io.read_exact(&mut header).await?;
+    let fin = header[0] & 0x80 != 0;
+    let opcode = header[0] & 0x0F;
+    let masked = header[1] & 0x80 != 0;
+
+    let len = match header[1] & 0x7F {
+        126 => io.read_u16().await? as usize,
+        127 => io.read_u64().await? as usize,
+        n => n as usize,
+    };
+    if !masked {
+        return Err(FrameError::Protocol("client frames must be masked"));
+    }
+    // Control frames have the high opcode bit set (RFC 6455 §5.5)
+    if opcode & 0x8 != 0 && (!fin || len > MAX_CONTROL_PAYLOAD) {
+        return Err(FrameError::Protocol("control frames must be unfragmented and at most 125 bytes"));
+    }
+    if len > MAX_MESSAGE_SIZE {
+        return Err(FrameError::Io(too_large()));
+    }
+
+    let mut mask = [0u8; 4];
+    io.read_exact(&mut mask).await?;
+    let mut payload = vec![0u8; len];
+    io.read_exact(&mut payload).await?;
+    payload.iter_mut().enumerate().for_each(|(i, b)| *b ^= mask[i % 4]);
+
+    Ok((fin, opcode, payload))
+}
+
+/// Sending half of a WebSocket
+pub struct WebSocketWriter<W = WriteHalf<Upgraded>> {
+    io: W,
+}
+
+impl<W: AsyncWrite + Unpin> WebSocketWriter<W> {
+    pub async fn send_text(&mut self, text: &str) -> std::io::Result<()> {
+        write_frame(&mut self.io, OPCODE_TEXT, text.as_bytes()).await
+    }
+
+    pub async fn send_pong(&mut self, payload: &[u8]) -> std::io::Result<()> {
+        write_frame(&mut self.io, OPCODE_PONG, payload).await
+    }
+
+    /// Send a normal-closure frame
+    pub async fn close(&mut self) -> std::io::Result<()> {
+        self.close_with(CLOSE_NORMAL).await
+    }
+
+    /// Send a protocol-error close frame, after the client broke the rules
+    pub async fn close_protocol_error(&mut self) -> std::io::Result<()> {
+        self.close_with(CLOSE_PROTOCOL_ERROR).await
+    }
+
+    async fn close_with(&mut self, status: u16) -> std::io::Result<()> {
+        write_frame(&mut self.io, OPCODE_CLOSE, &status.to_be_bytes()).await?;
+        self.io.shutdown().await
+    }
+}
+
+async fn write_frame<W: AsyncWrite + Unpin>(io: &mut W, opcode: u8, payload: &[u8]) -> std::io::Result<()> {
+    let mut frame = Vec::with_capacity(payload.len() + 10);
+    frame.push(0x80 | opcode);
+    match payload.len() {
+        len if len < 126 => frame.push(len as u8),
+        len if len <= u16::MAX as usize => {
+            frame.push(126);
+            frame.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(127);
+            frame.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    io.write_all(&frame).await?;
+    io.flush().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A masked client frame
+    fn frame(fin: bool, opcode: u8, payload: &[u8]) -> Vec<u8> {
+        let mask = [0x37, 0xFA, 0x21, 0x3D];
+        let mut frame = vec![if fin { 0x80 | opcode } else { opcode }];
+        match payload.len() {
+            len if len < 126 => frame.push(0x80 | len as u8),
+            len => {
+                frame.push(0x80 | 126);
+                frame.extend_from_slice(&(len as u16).to_be_bytes());
+            }
+        }
+        frame.extend_from_slice(&mask);
+        frame.extend(payload.iter().enumerate().map(|(i, b)| b ^ mask[i % 4]));
+        frame
+    }
+
+    async fn messages(frames: &[Vec<u8>]) -> Vec<Message> {
+        let bytes = frames.concat();
+        let mut reader = WebSocketReader::new(bytes.as_slice());
+        let mut messages = Vec::new();
+        while let Some(message) = reader.recv().await {
+            messages.push(message);
+        }
+        messages
+    }
+
+    #[tokio::test]
+    async fn reassembles_fragments() {
+        let received = messages(&[
+            frame(false, OPCODE_TEXT, b"Hel"),
+            frame(false, OPCODE_CONTINUATION, b"lo, "),
+            frame(true, OPCODE_CONTINUATION, b"world"),
+            frame(true, OPCODE_TEXT, b"again"),
+        ])
+        .await;
+        assert!(
+            matches!(received.as_slice(), [Message::Text(a), Message::Text(b)] if a == "Hello, world" && b == "again"),
+            "{received:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn control_frames_between_fragments_keep_the_message() {
+        let received = messages(&[
+            frame(false, OPCODE_TEXT, b"split "),
+            frame(true, OPCODE_PING, b"are you there"),
+            frame(false, OPCODE_CONTINUATION, b"across "),
+            frame(true, OPCODE_PONG, b""),
+            frame(true, OPCODE_CONTINUATION, b"pings"),
+        ])
+        .await;
+        assert!(
+            matches!(
+                received.as_slice(),
+                [Message::Ping(ping), Message::Pong, Message::Text(text)]
+                    if ping == b"are you there" && text == "split across pings"
+            ),
+            "{received:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn long_control_frames_are_protocol_errors() {
+        let received = messages(&[frame(true, OPCODE_PING, &[0; 125]), frame(true, OPCODE_PING, &[0; 126])]).await;
+        assert!(
+            matches!(received.as_slice(), [Message::Ping(ping), Message::ProtocolError(_)] if ping.len() == 125),
+            "{received:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn framing_violations_are_protocol_errors() {
+        let cases = [
+            vec![frame(false, OPCODE_PING, b"")],
+            vec![frame(true, OPCODE_CONTINUATION, b"orphan")],
+            vec![frame(false, OPCODE_TEXT, b"a"), frame(true, OPCODE_TEXT, b"b")],
+            vec![frame(true, 0x3, b"")],
+            vec![vec![0x81, 0x01, b'x']],
+        ];
+        for frames in cases {
+            let received = messages(&frames).await;
+            // Nothing is read after the error
+            assert!(matches!(received.as_slice(), [Message::ProtocolError(_)]), "{received:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn writes_close_statuses() {
+        let (mut client, server) = tokio::io::duplex(64);
+        let mut writer = WebSocketWriter { io: server };
+        writer.close_protocol_error().await.unwrap_or_else(|e| panic!("{e}"));
+        let mut sent = Vec::new();
+        client.read_to_end(&mut sent).await.unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(sent, [0x88, 0x02, 0x03, 0xEA]);
+    }
+}